@@ -1,4 +1,5 @@
 (require "steel/colors/colors.scm")
+(require (only-in "steel/testing" test-harness-running? register-check-result!))
 
 (provide test
          (for-syntax check-equal?)
@@ -16,12 +17,25 @@
 ;; Failed tests
 (define *failures* '())
 
-(define (mark-success)
-  (set! *SUCCESS-COUNT* (+ *SUCCESS-COUNT* 1)))
+;; Used by `test-module`, which can only refer to names defined in this module
+(define (harness-running?)
+  (test-harness-running?))
 
-(define (mark-failed name)
+(define (mark-success name)
+  (set! *SUCCESS-COUNT* (+ *SUCCESS-COUNT* 1))
+  ;; Under `steel test` the harness does the reporting
+  (if (test-harness-running?)
+      (register-check-result! name #f)
+      (print-success name)))
+
+(define (mark-failed name message)
   (set! *FAILURE-COUNT* (+ *FAILURE-COUNT* 1))
-  (set! *failures* (cons name *failures*)))
+  (set! *failures* (cons name *failures*))
+  (if (test-harness-running?)
+      (register-check-result! name (to-string message))
+      (begin
+        (print-failure name)
+        (displayln message))))
 
 (define (mark-skipped)
   (set! *FAILED-TO-COMPILE* (+ *FAILED-TO-COMPILE* 1)))
@@ -41,42 +55,33 @@
 (define-syntax check-equal?
   (syntax-rules ()
     [(check-equal? name input expected)
-     (with-handler (lambda (err)
-                     (mark-failed name)
-                     (print-failure name)
-                     (displayln err))
-                   (test name input expected))]))
+     (with-handler (lambda (err) (mark-failed name err)) (test name input expected))]))
 
 (define-syntax check-err?
   (syntax-rules ()
     [(check-err? name input expected)
-     (with-handler (lambda (err)
-                     (mark-success)
-                     (print-success name))
-                   (test name input expected))]))
+     (with-handler (lambda (err) (mark-success name)) (test name input expected))]))
 
 ;; Check the equality, and otherwise do some nice printing of the result
 (define (test name input expected)
   (if (equal? input expected)
-      (begin
-        (mark-success)
-        (print-success name))
-      (begin
-        (mark-failed name)
-        (print-failure name)
-        (displayln "    Expected: " expected ", Found " input))))
+      (mark-success name)
+      (mark-failed name (to-string "expected:" expected "found:" input))))
 
 (define-syntax test-module
   (syntax-rules ()
     [(test-module name expr ...)
      (when (get-test-mode)
-       (begin
-         (displayln "###### Running tests for module " name " ######")
-         (begin
-           expr ...)
-         (displayln "Test result: " *SUCCESS-COUNT* " passed; " *FAILURE-COUNT* " failed;")
-         (display "Failures: ")
-         (displayln *failures*)))]
+       (if (harness-running?)
+           (begin
+             expr ...)
+           (begin
+             (displayln "###### Running tests for module " name " ######")
+             (begin
+               expr ...)
+             (displayln "Test result: " *SUCCESS-COUNT* " passed; " *FAILURE-COUNT* " failed;")
+             (display "Failures: ")
+             (displayln *failures*))))]
     [(test-module expr ...)
      (begin
        expr ...)]))
@@ -0,0 +1,59 @@
+// Embeds Steel in a C program.
+//
+// Build the library with `cargo build -p steel-capi --release`, then compile this with e.g.
+//
+//     cc crates/steel-capi/examples/embed.c -Icrates/steel-capi/include \
+//         target/release/libsteel_capi.a -lm -ldl -lpthread -o embed
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "steel.h"
+
+// Multiplies its integer arguments together, counting how many times it was called
+static SteelValue *product(void *user_data, const SteelValue *const *args, size_t argc) {
+  int *calls = user_data;
+  *calls += 1;
+
+  int64_t result = 1;
+
+  for (size_t i = 0; i < argc; i++) {
+    int64_t value;
+
+    if (!steel_value_as_int(args[i], &value)) {
+      steel_raise_error("expected an integer");
+      return NULL;
+    }
+
+    result *= value;
+  }
+
+  return steel_value_int(result);
+}
+
+int main(void) {
+  SteelEngine *engine = steel_engine_new();
+
+  int calls = 0;
+  steel_register_function(engine, "product", -1, product, &calls, NULL);
+
+  SteelValue *result = steel_eval(engine, "(define (square x) (product x x)) (square 12)");
+
+  if (result == NULL) {
+    fprintf(stderr, "%s\n", steel_engine_last_error(engine));
+    return 1;
+  }
+
+  char *rendered = steel_value_to_string(result);
+  printf("(square 12) = %s, product was called %d time(s)\n", rendered, calls);
+  steel_string_free(rendered);
+  steel_value_free(result);
+
+  // Errors raised by host functions are reported like any other error
+  if (steel_eval(engine, "(product 1 \"two\")") == NULL) {
+    printf("error: %s\n", steel_engine_last_error(engine));
+  }
+
+  steel_engine_free(engine);
+  return 0;
+}
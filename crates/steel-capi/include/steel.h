@@ -0,0 +1,155 @@
+#ifndef STEEL_H
+#define STEEL_H
+
+/* Generated by cbindgen from crates/steel-capi. Do not edit by hand, run `UPDATE_HEADER=1 cargo test -p steel-capi` instead. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+// The type of a `SteelValue`
+typedef enum SteelValueKind {
+  STEEL_VALUE_KIND_VOID,
+  STEEL_VALUE_KIND_BOOL,
+  STEEL_VALUE_KIND_INT,
+  STEEL_VALUE_KIND_FLOAT,
+  STEEL_VALUE_KIND_CHAR,
+  STEEL_VALUE_KIND_STRING,
+  STEEL_VALUE_KIND_SYMBOL,
+  STEEL_VALUE_KIND_LIST,
+  STEEL_VALUE_KIND_VECTOR,
+  STEEL_VALUE_KIND_HASH_MAP,
+  STEEL_VALUE_KIND_FUNCTION,
+  STEEL_VALUE_KIND_OTHER,
+} SteelValueKind;
+
+// A Steel engine, with its own global environment
+typedef struct SteelEngine SteelEngine;
+
+// A Steel value
+typedef struct SteelValue SteelValue;
+
+// A function implemented by the host. It receives the `user_data` it was registered with, and the
+// arguments it was called with, which are only valid for the duration of the call.
+//
+// The function returns a new value, whose ownership is passed to the engine. To signal an error,
+// call `steel_raise_error` and return `NULL`.
+typedef struct SteelValue *(*SteelHostFunction)(void *user_data,
+                                                const struct SteelValue *const *args,
+                                                size_t argc);
+
+// Called with the `user_data` of a host function when the function is no longer in use
+typedef void (*SteelFreeUserData)(void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates a new engine, with the standard library loaded
+struct SteelEngine *steel_engine_new(void);
+
+// Frees an engine created by `steel_engine_new`
+void steel_engine_free(struct SteelEngine *engine);
+
+// The message of the last error raised by a call on this engine, or `NULL` if the last call
+// succeeded. The string is owned by the engine, and is valid until the next call on it.
+const char *steel_engine_last_error(const struct SteelEngine *engine);
+
+// Evaluates the UTF-8 encoded `source`, returning the value of the last expression, or void if
+// there aren't any. Returns `NULL` on error.
+struct SteelValue *steel_eval(struct SteelEngine *engine, const char *source);
+
+// Calls `function` with the given arguments, returning its result or `NULL` on error
+struct SteelValue *steel_call(struct SteelEngine *engine,
+                              const struct SteelValue *function,
+                              const struct SteelValue *const *args,
+                              size_t argc);
+
+// Defines `name` in the global environment of the engine, as a copy of `value`
+bool steel_register_value(struct SteelEngine *engine,
+                          const char *name,
+                          const struct SteelValue *value);
+
+// Looks up `name` in the global environment of the engine, returning `NULL` if it isn't defined
+struct SteelValue *steel_get_value(struct SteelEngine *engine, const char *name);
+
+// Defines `name` in the global environment of the engine as a function implemented by the host.
+// If `arity` is negative the function accepts any number of arguments, otherwise calls with a
+// different number of arguments raise an error.
+//
+// `free_user_data`, if not `NULL`, is called with `user_data` once the function is no longer
+// referenced by the engine.
+bool steel_register_function(struct SteelEngine *engine,
+                             const char *name,
+                             ptrdiff_t arity,
+                             SteelHostFunction function,
+                             void *user_data,
+                             SteelFreeUserData free_user_data);
+
+// Raises an error from within a host function, with the UTF-8 encoded `message`. The host
+// function should return `NULL` afterwards.
+void steel_raise_error(const char *message);
+
+// Creates a void value
+struct SteelValue *steel_value_void(void);
+
+// Creates a boolean
+struct SteelValue *steel_value_bool(bool value);
+
+// Creates an integer
+struct SteelValue *steel_value_int(int64_t value);
+
+// Creates a floating point number
+struct SteelValue *steel_value_float(double value);
+
+// Creates a string from a UTF-8 encoded string, returning `NULL` if it isn't valid UTF-8
+struct SteelValue *steel_value_string(const char *value);
+
+// Creates a symbol from a UTF-8 encoded string, returning `NULL` if it isn't valid UTF-8
+struct SteelValue *steel_value_symbol(const char *value);
+
+// Creates a list containing copies of the `len` values in `items`, returning `NULL` if `items` or
+// any of the values is `NULL`
+struct SteelValue *steel_value_list(const struct SteelValue *const *items, size_t len);
+
+// Creates a copy of `value`. Copies share the underlying data where possible, so this is cheap.
+struct SteelValue *steel_value_clone(const struct SteelValue *value);
+
+// Frees a value returned by the API
+void steel_value_free(struct SteelValue *value);
+
+// The type of `value`
+enum SteelValueKind steel_value_kind(const struct SteelValue *value);
+
+// Reads a boolean into `out`, returning false if `value` isn't a boolean
+bool steel_value_as_bool(const struct SteelValue *value, bool *out);
+
+// Reads an integer into `out`, returning false if `value` isn't an integer that fits in 64 bits
+bool steel_value_as_int(const struct SteelValue *value, int64_t *out);
+
+// Reads a number into `out`, returning false if `value` isn't a real number. Integers and
+// rationals are converted to the nearest floating point number.
+bool steel_value_as_float(const struct SteelValue *value, double *out);
+
+// Returns the contents of a string or symbol as a new UTF-8 encoded string, or `NULL` if `value`
+// is neither
+char *steel_value_as_string(const struct SteelValue *value);
+
+// Renders `value` the same way `display` does, as a new UTF-8 encoded string
+char *steel_value_to_string(const struct SteelValue *value);
+
+// The number of elements in a list or vector, or 0 if `value` is neither
+size_t steel_value_length(const struct SteelValue *value);
+
+// Returns a copy of the element at `index` of a list or vector, or `NULL` if `value` is neither
+// or the index is out of range
+struct SteelValue *steel_value_get(const struct SteelValue *value, size_t index);
+
+// Frees a string returned by the API
+void steel_string_free(char *value);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* STEEL_H */
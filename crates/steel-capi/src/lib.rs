@@ -0,0 +1,775 @@
+//! A C API for embedding Steel.
+//!
+//! The generated header lives in `include/steel.h`. Engines and values are handed out as opaque
+//! pointers, which are owned by the caller and must be released with `steel_engine_free` and
+//! `steel_value_free` respectively. Strings returned by the API are owned by the caller as well, and
+//! must be released with `steel_string_free`, unless stated otherwise.
+//!
+//! Functions that can fail return `NULL` or `false`. For the functions that take an engine, the
+//! error message can then be retrieved with `steel_engine_last_error`.
+//!
+//! None of the handles are thread safe, an engine and the values created by it should only be used
+//! from the thread that created them.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use num::ToPrimitive;
+
+use steel::rerrs::{ErrorKind, SteelErr};
+use steel::rvals::{IntoSteelVal, Result, SteelVal};
+use steel::steel_vm::engine::Engine;
+
+/// A Steel engine, with its own global environment
+pub struct SteelEngine {
+    engine: Engine,
+    last_error: Option<CString>,
+}
+
+impl SteelEngine {
+    fn set_error(&mut self, error: SteelErr) {
+        let message = self
+            .engine
+            .raise_error_to_string(error.clone())
+            .unwrap_or_else(|| error.to_string());
+
+        self.last_error = Some(to_c_string(message));
+    }
+
+    // Runs `f`, recording its error if it fails, or if it panics
+    fn record<T>(&mut self, f: impl FnOnce(&mut Engine) -> Result<T>) -> Option<T> {
+        self.last_error = None;
+
+        match catch_unwind(AssertUnwindSafe(|| f(&mut self.engine))) {
+            Ok(Ok(value)) => Some(value),
+            Ok(Err(e)) => {
+                self.set_error(e);
+                None
+            }
+            Err(_) => {
+                self.last_error = Some(to_c_string("the engine panicked"));
+                None
+            }
+        }
+    }
+}
+
+/// A Steel value
+pub struct SteelValue(SteelVal);
+
+/// The type of a `SteelValue`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SteelValueKind {
+    Void,
+    Bool,
+    Int,
+    Float,
+    Char,
+    String,
+    Symbol,
+    List,
+    Vector,
+    HashMap,
+    Function,
+    Other,
+}
+
+/// A function implemented by the host. It receives the `user_data` it was registered with, and the
+/// arguments it was called with, which are only valid for the duration of the call.
+///
+/// The function returns a new value, whose ownership is passed to the engine. To signal an error,
+/// call `steel_raise_error` and return `NULL`.
+pub type SteelHostFunction = Option<
+    unsafe extern "C" fn(
+        user_data: *mut c_void,
+        args: *const *const SteelValue,
+        argc: usize,
+    ) -> *mut SteelValue,
+>;
+
+/// Called with the `user_data` of a host function when the function is no longer in use
+pub type SteelFreeUserData = Option<unsafe extern "C" fn(user_data: *mut c_void)>;
+
+thread_local! {
+    // The error raised by the host function that is currently running, if any
+    static HOST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+struct HostFunction {
+    callback: unsafe extern "C" fn(*mut c_void, *const *const SteelValue, usize) -> *mut SteelValue,
+    user_data: *mut c_void,
+    free_user_data: SteelFreeUserData,
+}
+
+// Engines are confined to a single thread, so the host function never actually leaves it
+unsafe impl Send for HostFunction {}
+unsafe impl Sync for HostFunction {}
+
+impl HostFunction {
+    fn call(&self, name: &str, args: &[SteelVal]) -> Result<SteelVal> {
+        let values = args
+            .iter()
+            .map(|x| SteelValue(x.clone()))
+            .collect::<Vec<_>>();
+        let args = values
+            .iter()
+            .map(|x| x as *const SteelValue)
+            .collect::<Vec<_>>();
+
+        HOST_ERROR.with(|x| x.borrow_mut().take());
+
+        let result = unsafe { (self.callback)(self.user_data, args.as_ptr(), args.len()) };
+
+        let error = HOST_ERROR.with(|x| x.borrow_mut().take());
+
+        if result.is_null() {
+            let message = error.unwrap_or_else(|| "the host function returned NULL".to_string());
+            return Err(SteelErr::new(
+                ErrorKind::Generic,
+                format!("{name}: {message}"),
+            ));
+        }
+
+        Ok(unsafe { Box::from_raw(result) }.0)
+    }
+}
+
+impl Drop for HostFunction {
+    fn drop(&mut self) {
+        if let Some(free) = self.free_user_data {
+            unsafe { free(self.user_data) }
+        }
+    }
+}
+
+fn to_c_string(value: impl Into<String>) -> CString {
+    let value = value.into().replace('\0', "\\0");
+    CString::new(value).unwrap()
+}
+
+fn into_raw(value: SteelVal) -> *mut SteelValue {
+    Box::into_raw(Box::new(SteelValue(value)))
+}
+
+unsafe fn str_arg<'a>(value: *const c_char) -> Option<&'a str> {
+    if value.is_null() {
+        return None;
+    }
+
+    CStr::from_ptr(value).to_str().ok()
+}
+
+fn invalid_argument(engine: &mut SteelEngine, message: &str) {
+    engine.last_error = Some(to_c_string(message));
+}
+
+/// Creates a new engine, with the standard library loaded
+#[no_mangle]
+pub extern "C" fn steel_engine_new() -> *mut SteelEngine {
+    Box::into_raw(Box::new(SteelEngine {
+        engine: Engine::new(),
+        last_error: None,
+    }))
+}
+
+/// Frees an engine created by `steel_engine_new`
+#[no_mangle]
+pub unsafe extern "C" fn steel_engine_free(engine: *mut SteelEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
+
+/// The message of the last error raised by a call on this engine, or `NULL` if the last call
+/// succeeded. The string is owned by the engine, and is valid until the next call on it.
+#[no_mangle]
+pub unsafe extern "C" fn steel_engine_last_error(engine: *const SteelEngine) -> *const c_char {
+    match engine.as_ref().and_then(|x| x.last_error.as_ref()) {
+        Some(error) => error.as_ptr(),
+        None => std::ptr::null(),
+    }
+}
+
+/// Evaluates the UTF-8 encoded `source`, returning the value of the last expression, or void if
+/// there aren't any. Returns `NULL` on error.
+#[no_mangle]
+pub unsafe extern "C" fn steel_eval(
+    engine: *mut SteelEngine,
+    source: *const c_char,
+) -> *mut SteelValue {
+    let Some(engine) = engine.as_mut() else {
+        return std::ptr::null_mut();
+    };
+
+    let Some(source) = str_arg(source) else {
+        invalid_argument(engine, "steel_eval: the source must be valid UTF-8");
+        return std::ptr::null_mut();
+    };
+
+    match engine.record(|engine| engine.compile_and_run_raw_program(source.to_string())) {
+        Some(mut values) => into_raw(values.pop().unwrap_or(SteelVal::Void)),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Calls `function` with the given arguments, returning its result or `NULL` on error
+#[no_mangle]
+pub unsafe extern "C" fn steel_call(
+    engine: *mut SteelEngine,
+    function: *const SteelValue,
+    args: *const *const SteelValue,
+    argc: usize,
+) -> *mut SteelValue {
+    let Some(engine) = engine.as_mut() else {
+        return std::ptr::null_mut();
+    };
+
+    let Some(function) = function.as_ref() else {
+        invalid_argument(engine, "steel_call: the function must not be NULL");
+        return std::ptr::null_mut();
+    };
+
+    let args = if argc == 0 {
+        Vec::new()
+    } else {
+        if args.is_null() {
+            invalid_argument(engine, "steel_call: the arguments must not be NULL");
+            return std::ptr::null_mut();
+        }
+
+        let args = std::slice::from_raw_parts(args, argc);
+
+        if args.iter().any(|x| x.is_null()) {
+            invalid_argument(engine, "steel_call: the arguments must not be NULL");
+            return std::ptr::null_mut();
+        }
+
+        args.iter().map(|x| (**x).0.clone()).collect()
+    };
+
+    engine
+        .record(|engine| engine.call_function_with_args(function.0.clone(), args))
+        .map(into_raw)
+        .unwrap_or(std::ptr::null_mut())
+}
+
+/// Defines `name` in the global environment of the engine, as a copy of `value`
+#[no_mangle]
+pub unsafe extern "C" fn steel_register_value(
+    engine: *mut SteelEngine,
+    name: *const c_char,
+    value: *const SteelValue,
+) -> bool {
+    let Some(engine) = engine.as_mut() else {
+        return false;
+    };
+
+    match (str_arg(name), value.as_ref()) {
+        (Some(name), Some(value)) => {
+            engine.last_error = None;
+            engine.engine.register_value(name, value.0.clone());
+            true
+        }
+        _ => {
+            invalid_argument(
+                engine,
+                "steel_register_value: the name must be valid UTF-8 and the value must not be NULL",
+            );
+            false
+        }
+    }
+}
+
+/// Looks up `name` in the global environment of the engine, returning `NULL` if it isn't defined
+#[no_mangle]
+pub unsafe extern "C" fn steel_get_value(
+    engine: *mut SteelEngine,
+    name: *const c_char,
+) -> *mut SteelValue {
+    let Some(engine) = engine.as_mut() else {
+        return std::ptr::null_mut();
+    };
+
+    let Some(name) = str_arg(name) else {
+        invalid_argument(engine, "steel_get_value: the name must be valid UTF-8");
+        return std::ptr::null_mut();
+    };
+
+    engine
+        .record(|engine| engine.extract_value(name))
+        .map(into_raw)
+        .unwrap_or(std::ptr::null_mut())
+}
+
+/// Defines `name` in the global environment of the engine as a function implemented by the host.
+/// If `arity` is negative the function accepts any number of arguments, otherwise calls with a
+/// different number of arguments raise an error.
+///
+/// `free_user_data`, if not `NULL`, is called with `user_data` once the function is no longer
+/// referenced by the engine.
+#[no_mangle]
+pub unsafe extern "C" fn steel_register_function(
+    engine: *mut SteelEngine,
+    name: *const c_char,
+    arity: isize,
+    function: SteelHostFunction,
+    user_data: *mut c_void,
+    free_user_data: SteelFreeUserData,
+) -> bool {
+    let Some(engine) = engine.as_mut() else {
+        return false;
+    };
+
+    let (Some(name), Some(callback)) = (str_arg(name), function) else {
+        invalid_argument(
+            engine,
+            "steel_register_function: the name must be valid UTF-8 and the function must not be NULL",
+        );
+        return false;
+    };
+
+    let host_function = HostFunction {
+        callback,
+        user_data,
+        free_user_data,
+    };
+
+    let function_name = name.to_string();
+    let arity = usize::try_from(arity).ok();
+
+    engine.last_error = None;
+    engine.engine.register_dyn_fn(name, arity, move |args| {
+        host_function.call(&function_name, args)
+    });
+
+    true
+}
+
+/// Raises an error from within a host function, with the UTF-8 encoded `message`. The host
+/// function should return `NULL` afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn steel_raise_error(message: *const c_char) {
+    let message = if message.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(message).to_string_lossy().into_owned()
+    };
+
+    HOST_ERROR.with(|x| *x.borrow_mut() = Some(message));
+}
+
+/// Creates a void value
+#[no_mangle]
+pub extern "C" fn steel_value_void() -> *mut SteelValue {
+    into_raw(SteelVal::Void)
+}
+
+/// Creates a boolean
+#[no_mangle]
+pub extern "C" fn steel_value_bool(value: bool) -> *mut SteelValue {
+    into_raw(SteelVal::BoolV(value))
+}
+
+/// Creates an integer
+#[no_mangle]
+pub extern "C" fn steel_value_int(value: i64) -> *mut SteelValue {
+    // Integers that don't fit in a pointer sized integer become big integers
+    into_raw(value.into_steelval().unwrap())
+}
+
+/// Creates a floating point number
+#[no_mangle]
+pub extern "C" fn steel_value_float(value: f64) -> *mut SteelValue {
+    into_raw(SteelVal::NumV(value))
+}
+
+/// Creates a string from a UTF-8 encoded string, returning `NULL` if it isn't valid UTF-8
+#[no_mangle]
+pub unsafe extern "C" fn steel_value_string(value: *const c_char) -> *mut SteelValue {
+    match str_arg(value) {
+        Some(value) => into_raw(SteelVal::StringV(value.into())),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Creates a symbol from a UTF-8 encoded string, returning `NULL` if it isn't valid UTF-8
+#[no_mangle]
+pub unsafe extern "C" fn steel_value_symbol(value: *const c_char) -> *mut SteelValue {
+    match str_arg(value) {
+        Some(value) => into_raw(SteelVal::SymbolV(value.into())),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Creates a list containing copies of the `len` values in `items`, returning `NULL` if `items` or
+/// any of the values is `NULL`
+#[no_mangle]
+pub unsafe extern "C" fn steel_value_list(
+    items: *const *const SteelValue,
+    len: usize,
+) -> *mut SteelValue {
+    if len == 0 {
+        return into_raw(SteelVal::ListV(Default::default()));
+    }
+
+    if items.is_null() {
+        return std::ptr::null_mut();
+    }
+
+    let items = std::slice::from_raw_parts(items, len);
+
+    if items.iter().any(|x| x.is_null()) {
+        return std::ptr::null_mut();
+    }
+
+    into_raw(SteelVal::ListV(
+        items.iter().map(|x| (**x).0.clone()).collect(),
+    ))
+}
+
+/// Creates a copy of `value`. Copies share the underlying data where possible, so this is cheap.
+#[no_mangle]
+pub unsafe extern "C" fn steel_value_clone(value: *const SteelValue) -> *mut SteelValue {
+    match value.as_ref() {
+        Some(value) => into_raw(value.0.clone()),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Frees a value returned by the API
+#[no_mangle]
+pub unsafe extern "C" fn steel_value_free(value: *mut SteelValue) {
+    if !value.is_null() {
+        drop(Box::from_raw(value));
+    }
+}
+
+/// The type of `value`
+#[no_mangle]
+pub unsafe extern "C" fn steel_value_kind(value: *const SteelValue) -> SteelValueKind {
+    let Some(value) = value.as_ref() else {
+        return SteelValueKind::Other;
+    };
+
+    match &value.0 {
+        SteelVal::Void => SteelValueKind::Void,
+        SteelVal::BoolV(_) => SteelValueKind::Bool,
+        SteelVal::IntV(_) | SteelVal::BigNum(_) => SteelValueKind::Int,
+        SteelVal::NumV(_) => SteelValueKind::Float,
+        SteelVal::CharV(_) => SteelValueKind::Char,
+        SteelVal::StringV(_) => SteelValueKind::String,
+        SteelVal::SymbolV(_) => SteelValueKind::Symbol,
+        SteelVal::ListV(_) => SteelValueKind::List,
+        SteelVal::VectorV(_) | SteelVal::MutableVector(_) => SteelValueKind::Vector,
+        SteelVal::HashMapV(_) => SteelValueKind::HashMap,
+        SteelVal::Closure(_)
+        | SteelVal::FuncV(_)
+        | SteelVal::BoxedFunction(_)
+        | SteelVal::MutFunc(_)
+        | SteelVal::ContinuationFunction(_)
+        | SteelVal::BuiltIn(_) => SteelValueKind::Function,
+        _ => SteelValueKind::Other,
+    }
+}
+
+/// Reads a boolean into `out`, returning false if `value` isn't a boolean
+#[no_mangle]
+pub unsafe extern "C" fn steel_value_as_bool(value: *const SteelValue, out: *mut bool) -> bool {
+    match (value.as_ref(), out.as_mut()) {
+        (Some(SteelValue(SteelVal::BoolV(b))), Some(out)) => {
+            *out = *b;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Reads an integer into `out`, returning false if `value` isn't an integer that fits in 64 bits
+#[no_mangle]
+pub unsafe extern "C" fn steel_value_as_int(value: *const SteelValue, out: *mut i64) -> bool {
+    let result = match value.as_ref() {
+        Some(SteelValue(SteelVal::IntV(i))) => i64::try_from(*i).ok(),
+        Some(SteelValue(SteelVal::BigNum(b))) => i64::try_from(b.as_ref()).ok(),
+        _ => None,
+    };
+
+    match (result, out.as_mut()) {
+        (Some(result), Some(out)) => {
+            *out = result;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Reads a number into `out`, returning false if `value` isn't a real number. Integers and
+/// rationals are converted to the nearest floating point number.
+#[no_mangle]
+pub unsafe extern "C" fn steel_value_as_float(value: *const SteelValue, out: *mut f64) -> bool {
+    let result = match value.as_ref() {
+        Some(SteelValue(SteelVal::NumV(n))) => Some(*n),
+        Some(SteelValue(SteelVal::IntV(i))) => Some(*i as f64),
+        Some(SteelValue(SteelVal::BigNum(b))) => b.to_f64(),
+        Some(SteelValue(SteelVal::Rational(r))) => r.to_f64(),
+        Some(SteelValue(SteelVal::BigRational(r))) => r.to_f64(),
+        _ => None,
+    };
+
+    match (result, out.as_mut()) {
+        (Some(result), Some(out)) => {
+            *out = result;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Returns the contents of a string or symbol as a new UTF-8 encoded string, or `NULL` if `value`
+/// is neither
+#[no_mangle]
+pub unsafe extern "C" fn steel_value_as_string(value: *const SteelValue) -> *mut c_char {
+    match value.as_ref() {
+        Some(SteelValue(SteelVal::StringV(s) | SteelVal::SymbolV(s))) => {
+            to_c_string(s.as_str()).into_raw()
+        }
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Renders `value` the same way `display` does, as a new UTF-8 encoded string
+#[no_mangle]
+pub unsafe extern "C" fn steel_value_to_string(value: *const SteelValue) -> *mut c_char {
+    match value.as_ref() {
+        Some(value) => to_c_string(value.0.to_string()).into_raw(),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// The number of elements in a list or vector, or 0 if `value` is neither
+#[no_mangle]
+pub unsafe extern "C" fn steel_value_length(value: *const SteelValue) -> usize {
+    match value.as_ref() {
+        Some(SteelValue(SteelVal::ListV(l))) => l.len(),
+        Some(SteelValue(SteelVal::VectorV(v))) => v.len(),
+        Some(SteelValue(SteelVal::MutableVector(v))) => v.get().len(),
+        _ => 0,
+    }
+}
+
+/// Returns a copy of the element at `index` of a list or vector, or `NULL` if `value` is neither
+/// or the index is out of range
+#[no_mangle]
+pub unsafe extern "C" fn steel_value_get(
+    value: *const SteelValue,
+    index: usize,
+) -> *mut SteelValue {
+    let element = match value.as_ref() {
+        Some(SteelValue(SteelVal::ListV(l))) => l.get(index).cloned(),
+        Some(SteelValue(SteelVal::VectorV(v))) => v.get(index).cloned(),
+        Some(SteelValue(SteelVal::MutableVector(v))) => v.get().get(index).cloned(),
+        _ => None,
+    };
+
+    element.map(into_raw).unwrap_or(std::ptr::null_mut())
+}
+
+/// Frees a string returned by the API
+#[no_mangle]
+pub unsafe extern "C" fn steel_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn c(value: &str) -> CString {
+        CString::new(value).unwrap()
+    }
+
+    unsafe fn last_error(engine: *const SteelEngine) -> Option<String> {
+        let error = steel_engine_last_error(engine);
+        (!error.is_null()).then(|| CStr::from_ptr(error).to_string_lossy().into_owned())
+    }
+
+    #[test]
+    fn eval_and_read_values() {
+        unsafe {
+            let engine = steel_engine_new();
+
+            let value = steel_eval(engine, c("(define x 20) (+ x 22)").as_ptr());
+            assert_eq!(steel_value_kind(value), SteelValueKind::Int);
+
+            let mut out = 0;
+            assert!(steel_value_as_int(value, &mut out));
+            assert_eq!(out, 42);
+            assert!(!steel_value_as_bool(value, &mut false));
+            steel_value_free(value);
+
+            let value = steel_eval(engine, c(r#"(list "a" 'b 1.5)"#).as_ptr());
+            assert_eq!(steel_value_length(value), 3);
+
+            let first = steel_value_get(value, 0);
+            let string = steel_value_as_string(first);
+            assert_eq!(CStr::from_ptr(string).to_str().unwrap(), "a");
+            steel_string_free(string);
+            steel_value_free(first);
+
+            assert!(steel_value_get(value, 3).is_null());
+            steel_value_free(value);
+
+            let item = steel_value_int(1);
+            let list = steel_value_list(&(item as *const _), 1);
+            assert_eq!(steel_value_length(list), 1);
+            assert!(steel_value_list(std::ptr::null(), 1).is_null());
+            assert!(steel_value_list(&std::ptr::null(), 1).is_null());
+            steel_value_free(list);
+            steel_value_free(item);
+
+            let mut float = 0.0;
+            let value = steel_eval(engine, c("1/4").as_ptr());
+            assert!(steel_value_as_float(value, &mut float));
+            assert_eq!(float, 0.25);
+            steel_value_free(value);
+
+            let value = steel_eval(engine, c("(expt 2 100)").as_ptr());
+            assert!(steel_value_as_float(value, &mut float));
+            assert_eq!(float, 2f64.powi(100));
+            steel_value_free(value);
+
+            let value = steel_eval(engine, c("(/ (expt 2 100) 3)").as_ptr());
+            assert!(steel_value_as_float(value, &mut float));
+            assert_eq!(float, 2f64.powi(100) / 3.0);
+            steel_value_free(value);
+
+            steel_engine_free(engine);
+        }
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        unsafe {
+            let engine = steel_engine_new();
+
+            let value = steel_eval(engine, c("(error \"oh no\")").as_ptr());
+            assert!(value.is_null());
+            assert!(last_error(engine).unwrap().contains("oh no"));
+
+            let value = steel_eval(engine, c("10").as_ptr());
+            assert!(last_error(engine).is_none());
+            steel_value_free(value);
+
+            assert!(steel_get_value(engine, c("not-defined").as_ptr()).is_null());
+            assert!(last_error(engine).is_some());
+
+            steel_engine_free(engine);
+        }
+    }
+
+    unsafe extern "C" fn add(
+        user_data: *mut c_void,
+        args: *const *const SteelValue,
+        argc: usize,
+    ) -> *mut SteelValue {
+        *(user_data as *mut usize) += 1;
+
+        let args = std::slice::from_raw_parts(args, argc);
+        let mut total = 0;
+
+        for arg in args {
+            let mut value = 0;
+            if !steel_value_as_int(*arg, &mut value) {
+                steel_raise_error(c("expected an integer").as_ptr());
+                return std::ptr::null_mut();
+            }
+            total += value;
+        }
+
+        steel_value_int(total)
+    }
+
+    unsafe extern "C" fn free_counter(user_data: *mut c_void) {
+        drop(Box::from_raw(user_data as *mut usize));
+    }
+
+    #[test]
+    fn host_functions() {
+        unsafe {
+            let engine = steel_engine_new();
+            let calls = Box::into_raw(Box::new(0usize));
+
+            assert!(steel_register_function(
+                engine,
+                c("host-add").as_ptr(),
+                -1,
+                Some(add),
+                calls as *mut c_void,
+                Some(free_counter),
+            ));
+
+            let value = steel_eval(engine, c("(host-add 1 2 (host-add 3 4))").as_ptr());
+            let mut out = 0;
+            assert!(steel_value_as_int(value, &mut out));
+            assert_eq!(out, 10);
+            assert_eq!(*calls, 2);
+            steel_value_free(value);
+
+            let value = steel_eval(engine, c("(host-add 1 \"two\")").as_ptr());
+            assert!(value.is_null());
+            assert!(last_error(engine)
+                .unwrap()
+                .contains("host-add: expected an integer"));
+
+            // Steel functions can be called from the host
+            let function = steel_eval(engine, c("(lambda (x) (host-add x x))").as_ptr());
+            assert_eq!(steel_value_kind(function), SteelValueKind::Function);
+
+            let argument = steel_value_int(5);
+            let value = steel_call(engine, function, &(argument as *const _), 1);
+            assert!(steel_value_as_int(value, &mut out));
+            assert_eq!(out, 10);
+
+            steel_value_free(value);
+
+            // NULL arguments are rejected instead of being dereferenced
+            assert!(steel_call(engine, function, std::ptr::null(), 1).is_null());
+            assert!(last_error(engine).unwrap().contains("steel_call"));
+            assert!(steel_call(engine, function, &std::ptr::null(), 1).is_null());
+            assert!(steel_call(engine, std::ptr::null(), &(argument as *const _), 1).is_null());
+
+            steel_value_free(argument);
+            steel_value_free(function);
+            steel_engine_free(engine);
+        }
+    }
+
+    #[test]
+    fn header_is_up_to_date() {
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let header = crate_dir.join("include").join("steel.h");
+
+        let mut generated = Vec::new();
+        cbindgen::generate(crate_dir)
+            .expect("unable to generate the header")
+            .write(&mut generated);
+        let generated = String::from_utf8(generated).unwrap();
+
+        if std::env::var_os("UPDATE_HEADER").is_some() {
+            std::fs::write(&header, generated).unwrap();
+            return;
+        }
+
+        let existing = std::fs::read_to_string(&header).unwrap_or_default();
+        assert!(
+            existing == generated,
+            "include/steel.h is out of date, run `UPDATE_HEADER=1 cargo test -p steel-capi` to regenerate it"
+        );
+    }
+}
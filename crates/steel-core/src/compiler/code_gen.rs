@@ -15,7 +15,9 @@ use crate::{
         visitors::VisitorMut,
     },
     rvals::IntoSteelVal,
-    stop, SteelVal,
+    stop,
+    values::functions::signature_to_constant,
+    SteelVal,
 };
 use num::{BigInt, BigRational, Rational32};
 use smallvec::SmallVec;
@@ -67,7 +69,7 @@ fn eval_atom(t: &SyntaxObject) -> Result<SteelVal> {
             BigRational::new(BigInt::from(n.clone()), BigInt::from(d.clone())).into_steelval()
         }
         // TODO: Keywords shouldn't be misused as an expression - only in function calls are keywords allowed
-        TokenType::Keyword(k) => Ok(SteelVal::SymbolV(k.clone().into())),
+        TokenType::Keyword(k) => Ok(SteelVal::Keyword(k.resolve().into())),
         what => {
             stop!(UnexpectedToken => what; t.span)
         }
@@ -84,7 +86,7 @@ fn try_eval_atom(t: &SyntaxObject) -> Option<SteelVal> {
         TokenType::CharacterLiteral(c) => Some(SteelVal::CharV(*c)),
         TokenType::IntegerLiteral(MaybeBigInt::Small(n)) => Some(SteelVal::IntV(*n)),
         // TODO: Keywords shouldn't be misused as an expression - only in function calls are keywords allowed
-        TokenType::Keyword(k) => Some(SteelVal::SymbolV(k.clone().into())),
+        TokenType::Keyword(k) => Some(SteelVal::Keyword(k.resolve().into())),
         _what => {
             // println!("getting here in the eval_atom - code_gen");
             // stop!(UnexpectedToken => what; t.span)
@@ -460,8 +462,16 @@ impl<'a> VisitorMut for CodeGenerator<'a> {
 
         // Attach the debug symbols here
         self.push(LabeledInstruction::builder(op_code).contents(lambda_function.location.clone()));
+        // The low bit marks a rest argument, the rest points at the signature in the constant map
+        let signature = lambda_function
+            .signature
+            .as_ref()
+            .map(|x| self.constant_map.add_or_get(signature_to_constant(x)) + 1)
+            .unwrap_or(0);
+
         self.push(
-            LabeledInstruction::builder(OpCode::PASS).payload(usize::from(lambda_function.rest)),
+            LabeledInstruction::builder(OpCode::PASS)
+                .payload(signature << 1 | usize::from(lambda_function.rest)),
         );
 
         let arity = lambda_function.args.len();
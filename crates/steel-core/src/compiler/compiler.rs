@@ -24,7 +24,7 @@ use crate::{
 use std::{borrow::Cow, iter::Iterator};
 use std::{
     collections::{HashMap, HashSet},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 // TODO: Replace the usages of hashmap with this directly
@@ -270,7 +270,7 @@ pub enum OptLevel {
     Three,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub(crate) struct KernelDefMacroSpec {
     pub(crate) _env: String,
     pub(crate) _exported: Option<HashSet<InternedString>>,
@@ -300,6 +300,14 @@ pub struct Compiler {
     search_dirs: Vec<PathBuf>,
 }
 
+// The parts of the compiler state that belong to a single namespace
+#[derive(Clone)]
+pub(crate) struct NamespaceState {
+    symbol_map: SymbolMap,
+    macro_env: FxHashMap<InternedString, SteelMacro>,
+    module_manager: ModuleManager,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct SerializableCompiler {
     pub(crate) symbol_map: SymbolMap,
@@ -335,6 +343,57 @@ impl Compiler {
     }
 }
 
+/// The compiler state that gets written into an engine snapshot. The constant map is
+/// restored along with the rest of the heap, and the kernel is snapshotted on its own.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct CompilerSnapshot {
+    symbol_map: SymbolMap,
+    macro_env: FxHashMap<InternedString, SteelMacro>,
+    module_manager: ModuleManager,
+    opt_level: OptLevel,
+    mangled_identifiers: FxHashSet<InternedString>,
+    lifted_kernel_environments: HashMap<String, KernelDefMacroSpec>,
+    lifted_macro_environments: HashSet<PathBuf>,
+    search_dirs: Vec<PathBuf>,
+}
+
+impl Compiler {
+    pub(crate) fn snapshot(&self) -> CompilerSnapshot {
+        CompilerSnapshot {
+            symbol_map: self.symbol_map.clone(),
+            macro_env: self.macro_env.clone(),
+            module_manager: self.module_manager.clone(),
+            opt_level: self.opt_level,
+            mangled_identifiers: self.mangled_identifiers.clone(),
+            lifted_kernel_environments: self.lifted_kernel_environments.clone(),
+            lifted_macro_environments: self.lifted_macro_environments.clone(),
+            search_dirs: self.search_dirs.clone(),
+        }
+    }
+
+    pub(crate) fn from_snapshot(
+        snapshot: CompilerSnapshot,
+        constant_map: ConstantMap,
+        kernel: Option<Kernel>,
+    ) -> Compiler {
+        let mut compiler = Compiler::new(
+            snapshot.symbol_map,
+            constant_map,
+            snapshot.macro_env,
+            snapshot.module_manager,
+        );
+
+        compiler.opt_level = snapshot.opt_level;
+        compiler.kernel = kernel;
+        compiler.mangled_identifiers = snapshot.mangled_identifiers;
+        compiler.lifted_kernel_environments = snapshot.lifted_kernel_environments;
+        compiler.lifted_macro_environments = snapshot.lifted_macro_environments;
+        compiler.search_dirs = snapshot.search_dirs;
+
+        compiler
+    }
+}
+
 impl Default for Compiler {
     fn default() -> Self {
         Compiler::new(
@@ -542,6 +601,66 @@ impl Compiler {
         self.module_manager.modules()
     }
 
+    pub(crate) fn prelude_macros(&self) -> FxHashMap<InternedString, SteelMacro> {
+        self.module_manager.prelude_macros()
+    }
+
+    pub(crate) fn set_prelude_macros(&mut self, macros: FxHashMap<InternedString, SteelMacro>) {
+        self.module_manager.set_prelude_macros(macros)
+    }
+
+    pub(crate) fn namespace_state(&self) -> NamespaceState {
+        NamespaceState {
+            symbol_map: self.symbol_map.clone(),
+            macro_env: self.macro_env.clone(),
+            module_manager: self.module_manager.clone(),
+        }
+    }
+
+    pub(crate) fn swap_namespace_state(&mut self, state: &mut NamespaceState) {
+        std::mem::swap(&mut self.symbol_map, &mut state.symbol_map);
+        std::mem::swap(&mut self.macro_env, &mut state.macro_env);
+        std::mem::swap(&mut self.module_manager, &mut state.module_manager);
+    }
+
+    /// Compile the module at `path` again, producing a program that redefines it
+    pub fn reload_module(
+        &mut self,
+        path: PathBuf,
+        constants: ImmutableHashMap<InternedString, SteelVal, FxBuildHasher>,
+        builtin_modules: ModuleContainer,
+        sources: &mut Sources,
+    ) -> Result<RawProgramWithSymbols> {
+        let module_statements = self.module_manager.reload_module(
+            path,
+            &mut self.macro_env,
+            &mut self.kernel,
+            sources,
+            builtin_modules.clone(),
+            &self.search_dirs,
+        )?;
+
+        let expanded_statements =
+            self.lower_module_statements(module_statements, constants, builtin_modules, sources)?;
+
+        self.emit_raw_program(expanded_statements)
+    }
+
+    pub(crate) fn modified_modules(&self) -> Vec<PathBuf> {
+        self.module_manager.modified_modules()
+    }
+
+    pub(crate) fn module_dependents(&self, path: &Path) -> Vec<PathBuf> {
+        self.module_manager.dependents(path)
+    }
+
+    pub(crate) fn module_require_aliases(
+        &self,
+        path: &Path,
+    ) -> Result<Vec<(String, InternedString)>> {
+        self.module_manager.require_aliases(path)
+    }
+
     pub fn expand_expressions(
         &mut self,
         exprs: Vec<ExprKind>,
@@ -787,12 +906,23 @@ impl Compiler {
         #[cfg(feature = "profiling")]
         let now = Instant::now();
 
-        let mut expanded_statements =
+        let expanded_statements =
             self.expand_expressions(exprs, path, sources, builtin_modules.clone())?;
 
         #[cfg(feature = "profiling")]
         log::debug!(target: "pipeline_time", "Phase 1 module expansion time: {:?}", now.elapsed());
 
+        self.lower_module_statements(expanded_statements, constants, builtin_modules, sources)
+    }
+
+    // Everything after module expansion
+    fn lower_module_statements(
+        &mut self,
+        mut expanded_statements: Vec<ExprKind>,
+        constants: ImmutableHashMap<InternedString, SteelVal, FxBuildHasher>,
+        builtin_modules: ModuleContainer,
+        sources: &mut Sources,
+    ) -> Result<Vec<ExprKind>> {
         #[cfg(feature = "profiling")]
         let now = Instant::now();
 
@@ -992,6 +1122,13 @@ impl Compiler {
 
         // expanded_statements.pretty_print();
 
+        self.emit_raw_program(expanded_statements)
+    }
+
+    fn emit_raw_program(
+        &mut self,
+        expanded_statements: Vec<ExprKind>,
+    ) -> Result<RawProgramWithSymbols> {
         log::debug!(target: "expansion-phase", "Generating instructions");
 
         let instructions = self.generate_instructions_for_executable(expanded_statements)?;
@@ -1,9 +1,11 @@
 use crate::parser::tryfrom_visitor::TryFromExprKindForSteelVal;
+use crate::rerrs::{ErrorKind, SteelErr};
 use crate::rvals::{into_serializable_value, Result, SerializableSteelVal, SteelVal};
 
 use crate::parser::{
-    ast::ExprKind,
-    parser::{ParseError, Parser},
+    ast::{Atom, ExprKind},
+    parser::{ParseError, Parser, SyntaxObject},
+    tokens::TokenType,
 };
 
 use std::collections::HashMap;
@@ -21,8 +23,15 @@ pub struct ConstantMap {
     values: Rc<RefCell<Vec<SteelVal>>>,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct SerializableConstantMap(Vec<u8>);
+/// The constants, each written out as the source of an expression that evaluates to it
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SerializableConstantMap(Vec<String>);
+
+impl SerializableConstantMap {
+    pub fn constants(&self) -> &[String] {
+        &self.0
+    }
+}
 
 // #[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 // struct ConstantExprMap {
@@ -52,7 +61,11 @@ impl ConstantMap {
     }
 
     pub(crate) fn into_serializable_map(self) -> SerializableConstantMap {
-        SerializableConstantMap(self.to_bytes().unwrap())
+        self.to_serializable()
+    }
+
+    pub fn to_serializable(&self) -> SerializableConstantMap {
+        SerializableConstantMap(self.to_constant_expr_map())
     }
 
     pub fn to_serializable_vec(
@@ -109,21 +122,19 @@ impl ConstantMap {
     pub fn to_bytes(&self) -> Result<Vec<u8>> {
         let str_vector = self.to_constant_expr_map();
 
-        println!("{:?}", str_vector);
+        bincode::serialize(&str_vector)
+            .map_err(|e| SteelErr::new(ErrorKind::Generic, e.to_string()))
+    }
 
-        let result = bincode::serialize(&str_vector);
+    pub fn from_bytes(encoded: &[u8]) -> Result<ConstantMap> {
+        let str_vector: Vec<String> = bincode::deserialize(encoded)
+            .map_err(|e| SteelErr::new(ErrorKind::Generic, e.to_string()))?;
 
-        Ok(result.unwrap())
+        Self::from_serialized(SerializableConstantMap(str_vector))
     }
 
     pub fn from_serialized(map: SerializableConstantMap) -> Result<Self> {
-        Self::from_bytes(&map.0)
-    }
-
-    pub fn from_bytes(encoded: &[u8]) -> Result<ConstantMap> {
-        let str_vector: Vec<String> = bincode::deserialize(encoded).unwrap();
-
-        str_vector
+        map.0
             .into_iter()
             .map(|x| {
                 // Parse the input
@@ -135,6 +146,17 @@ impl ConstantMap {
 
                 // println!("{}", &parsed[0]);
 
+                if let ExprKind::Atom(Atom {
+                    syn:
+                        SyntaxObject {
+                            ty: TokenType::Keyword(k),
+                            ..
+                        },
+                }) = &parsed[0]
+                {
+                    return Ok(SteelVal::Keyword(k.resolve().into()));
+                }
+
                 TryFromExprKindForSteelVal::try_from_expr_kind(parsed[0].clone())
 
                 // Ok(SteelVal::try_from(parsed[0].clone()).unwrap())
@@ -234,6 +256,25 @@ pub mod constant_table_tests {
         test_get(&mut instance);
     }
 
+    #[test]
+    fn keywords_are_kept_apart_from_symbols() {
+        let mut instance = ConstantMap::new();
+
+        let symbol = instance.add_or_get(SteelVal::SymbolV("#:tag".into()));
+        let keyword = instance.add_or_get(SteelVal::Keyword("#:tag".into()));
+
+        assert_ne!(symbol, keyword);
+        assert_eq!(
+            instance.add_or_get(SteelVal::Keyword("#:tag".into())),
+            keyword
+        );
+
+        let decoded = ConstantMap::from_bytes(&instance.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(decoded.get(symbol), SteelVal::SymbolV("#:tag".into()));
+        assert_eq!(decoded.get(keyword), SteelVal::Keyword("#:tag".into()));
+    }
+
     fn test_add(instance: &mut ConstantMap) {
         assert_eq!(instance.len(), 0);
         let val1 = SteelVal::BoolV(true);
@@ -0,0 +1,684 @@
+//! The container format for serialized programs.
+//!
+//! Compiled programs are written out as an image, which wraps the serialized program in a header that
+//! identifies the version of steel that produced it, so that an image from an incompatible version is
+//! rejected up front rather than failing in some confusing way partway through deserialization.
+//!
+//! All integers are little endian. An image is laid out as:
+//!
+//! ```text
+//! magic           8 bytes     "\x7fSTEELIM"
+//! format version  u16         FORMAT_VERSION, bumped whenever this layout changes
+//! image kind      u8          see `ImageKind`
+//! reserved        u8          always 0
+//! opcode table    u64         FNV-1a hash of the op code names, in declaration order
+//! opcode count    u16         the number of op codes
+//! features        u32         bitset of the steel-core features the producer was built with, see `FEATURES`
+//! steel version   u16 + utf8  length prefixed version of steel-core that produced the image
+//! section count   u16
+//! sections        ...
+//! ```
+//!
+//! Followed by `section count` sections, each of which is:
+//!
+//! ```text
+//! kind            u16         see `SectionKind`
+//! length          u64         length of the payload in bytes
+//! checksum        u32         CRC-32 (IEEE) of the payload
+//! payload         length bytes
+//! ```
+//!
+//! Section payloads are encoded with bincode. Readers skip sections with a kind they don't recognize,
+//! so new optional sections can be added without bumping the format version.
+
+use std::fmt::{self, Write as _};
+use std::path::PathBuf;
+
+use steel_gen::opcode::OPCODE_NAMES;
+
+use crate::{
+    compiler::constants::SerializableConstantMap,
+    core::{
+        instructions::{DenseInstruction, Instruction},
+        labels::Expr,
+    },
+    parser::{parser::Sources, span::Span, span_visitor::get_span},
+    rerrs::{ErrorKind, SteelErr},
+    rvals::Result,
+    stop,
+};
+
+pub const MAGIC: &[u8; 8] = b"\x7fSTEELIM";
+
+pub const FORMAT_VERSION: u16 = 2;
+
+/// The steel-core features recorded in the image header. The position in this list is the bit used
+/// for that feature. New features must only be appended.
+pub const FEATURES: &[&str] = &[
+    "jit",
+    "dynamic",
+    "profiling",
+    "web",
+    "sqlite",
+    "unsafe-internals",
+    "anyhow",
+    "dylibs",
+    "markdown",
+    "smallvec",
+    "without-drop-protection",
+    "stacker",
+];
+
+fn enabled_feature_bits() -> u32 {
+    let enabled = [
+        cfg!(feature = "jit"),
+        cfg!(feature = "dynamic"),
+        cfg!(feature = "profiling"),
+        cfg!(feature = "web"),
+        cfg!(feature = "sqlite"),
+        cfg!(feature = "unsafe-internals"),
+        cfg!(feature = "anyhow"),
+        cfg!(feature = "dylibs"),
+        cfg!(feature = "markdown"),
+        cfg!(feature = "smallvec"),
+        cfg!(feature = "without-drop-protection"),
+        cfg!(feature = "stacker"),
+    ];
+
+    enabled
+        .iter()
+        .enumerate()
+        .filter(|(_, enabled)| **enabled)
+        .fold(0, |bits, (index, _)| bits | (1 << index))
+}
+
+/// The names of the steel-core features this build was compiled with
+pub fn enabled_features() -> Vec<&'static str> {
+    feature_names(enabled_feature_bits())
+}
+
+fn feature_names(bits: u32) -> Vec<&'static str> {
+    FEATURES
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| bits & (1 << index) != 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+/// Identifies the op codes that the instructions in an image refer to. Instructions are serialized
+/// with the index of their op code, so an image is only readable by a build with the same table.
+pub fn opcode_table_hash() -> u64 {
+    // FNV-1a, which is stable across platforms and versions unlike the std hasher
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    for byte in OPCODE_NAMES
+        .iter()
+        .flat_map(|x| x.bytes().chain(std::iter::once(0)))
+    {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB88320 & mask);
+        }
+    }
+
+    !crc
+}
+
+/// What kind of program an image holds, which determines the sections it must contain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ImageKind {
+    /// A `SerializableProgram` - bytecode that has been fully lowered, without symbols
+    Program = 1,
+    /// A `SerializableRawProgramWithSymbols`
+    RawProgram = 2,
+    /// A `NonInteractiveProgramImage` - a raw program along with the sources and modules it was compiled from
+    NonInteractive = 3,
+    /// An `EngineSnapshot` - the heap and global state of a fully initialized engine
+    Snapshot = 4,
+}
+
+impl ImageKind {
+    fn from_u8(value: u8) -> Result<Self> {
+        match value {
+            1 => Ok(ImageKind::Program),
+            2 => Ok(ImageKind::RawProgram),
+            3 => Ok(ImageKind::NonInteractive),
+            4 => Ok(ImageKind::Snapshot),
+            _ => stop!(Generic => "unknown image kind: {}", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u16)]
+pub enum SectionKind {
+    /// The instructions, as one list of instructions per top level expression
+    Code = 1,
+    /// The constant pool, referenced by index from the instructions
+    Constants = 2,
+    /// The path and text of every source the instructions have spans into, used for stack traces
+    SourceMap = 3,
+    /// The paths of the modules that were compiled into the program
+    Modules = 4,
+    /// The values reachable from the globals of a snapshotted engine
+    Heap = 5,
+    /// The compiler and vm state of a snapshotted engine, referring into the heap section
+    Engine = 6,
+}
+
+impl SectionKind {
+    fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            1 => Some(SectionKind::Code),
+            2 => Some(SectionKind::Constants),
+            3 => Some(SectionKind::SourceMap),
+            4 => Some(SectionKind::Modules),
+            5 => Some(SectionKind::Heap),
+            6 => Some(SectionKind::Engine),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SectionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SectionKind::Code => "code",
+            SectionKind::Constants => "constants",
+            SectionKind::SourceMap => "source map",
+            SectionKind::Modules => "modules",
+            SectionKind::Heap => "heap",
+            SectionKind::Engine => "engine",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageHeader {
+    pub format_version: u16,
+    pub kind: ImageKind,
+    pub opcode_table: u64,
+    pub opcode_count: u16,
+    pub features: u32,
+    pub steel_version: String,
+}
+
+impl ImageHeader {
+    fn current(kind: ImageKind) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            kind,
+            opcode_table: opcode_table_hash(),
+            opcode_count: OPCODE_NAMES.len() as u16,
+            features: enabled_feature_bits(),
+            steel_version: crate::VERSION.to_string(),
+        }
+    }
+
+    /// The names of the steel-core features the producer of the image was built with
+    pub fn feature_names(&self) -> Vec<&'static str> {
+        feature_names(self.features)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub kind: SectionKind,
+    pub checksum: u32,
+    pub payload: Vec<u8>,
+}
+
+/// A program image, see the module documentation for the layout
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub header: ImageHeader,
+    pub sections: Vec<Section>,
+}
+
+impl Image {
+    pub fn new(kind: ImageKind) -> Self {
+        Self {
+            header: ImageHeader::current(kind),
+            sections: Vec::new(),
+        }
+    }
+
+    /// Encode `value` with bincode and add it as a section
+    pub fn add_section<T: serde::Serialize>(&mut self, kind: SectionKind, value: &T) -> Result<()> {
+        let payload = bincode::serialize(value)
+            .map_err(|e| SteelErr::new(ErrorKind::Generic, e.to_string()))?;
+
+        self.sections.push(Section {
+            kind,
+            checksum: crc32(&payload),
+            payload,
+        });
+
+        Ok(())
+    }
+
+    pub fn section(&self, kind: SectionKind) -> Option<&Section> {
+        self.sections.iter().find(|x| x.kind == kind)
+    }
+
+    /// Decode the section of the given kind, which must be present in the image
+    pub fn read_section<T: serde::de::DeserializeOwned>(&self, kind: SectionKind) -> Result<T> {
+        let Some(section) = self.section(kind) else {
+            stop!(Generic => "malformed program image: missing the {} section", kind);
+        };
+
+        bincode::deserialize(&section.payload).map_err(|e| {
+            SteelErr::new(
+                ErrorKind::Generic,
+                format!("malformed program image: unable to read the {kind} section: {e}"),
+            )
+        })
+    }
+
+    /// Decode the section of the given kind if it is present
+    pub fn read_optional_section<T: serde::de::DeserializeOwned>(
+        &self,
+        kind: SectionKind,
+    ) -> Result<Option<T>> {
+        if self.section(kind).is_some() {
+            self.read_section(kind).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let header = &self.header;
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&header.format_version.to_le_bytes());
+        bytes.push(header.kind as u8);
+        bytes.push(0);
+        bytes.extend_from_slice(&header.opcode_table.to_le_bytes());
+        bytes.extend_from_slice(&header.opcode_count.to_le_bytes());
+        bytes.extend_from_slice(&header.features.to_le_bytes());
+        bytes.extend_from_slice(&(header.steel_version.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.steel_version.as_bytes());
+        bytes.extend_from_slice(&(self.sections.len() as u16).to_le_bytes());
+
+        for section in &self.sections {
+            bytes.extend_from_slice(&(section.kind as u16).to_le_bytes());
+            bytes.extend_from_slice(&(section.payload.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&section.checksum.to_le_bytes());
+            bytes.extend_from_slice(&section.payload);
+        }
+
+        bytes
+    }
+
+    /// Reads the header and sections of an image, without checking that it is compatible with
+    /// this build. Section checksums are verified.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, offset: 0 };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            stop!(Generic => "not a steel program image");
+        }
+
+        let format_version = reader.u16()?;
+
+        if format_version != FORMAT_VERSION {
+            stop!(Generic => "unsupported program image format version: {}, expected {}", format_version, FORMAT_VERSION);
+        }
+
+        let kind = ImageKind::from_u8(reader.take(2)?[0])?;
+        let opcode_table = reader.u64()?;
+        let opcode_count = reader.u16()?;
+        let features = reader.u32()?;
+        let version_length = reader.u16()? as usize;
+        let steel_version = String::from_utf8(reader.take(version_length)?.to_vec())
+            .map_err(|e| SteelErr::new(ErrorKind::Generic, e.to_string()))?;
+
+        let section_count = reader.u16()?;
+        let mut sections = Vec::with_capacity(section_count as usize);
+
+        for _ in 0..section_count {
+            let kind = reader.u16()?;
+            let length = reader.u64()? as usize;
+            let checksum = reader.u32()?;
+            let payload = reader.take(length)?;
+
+            // Sections from newer versions that we don't know about are skipped
+            let Some(kind) = SectionKind::from_u16(kind) else {
+                continue;
+            };
+
+            if crc32(payload) != checksum {
+                stop!(Generic => "program image is corrupt: checksum mismatch in the {} section", kind);
+            }
+
+            sections.push(Section {
+                kind,
+                checksum,
+                payload: payload.to_vec(),
+            });
+        }
+
+        Ok(Image {
+            header: ImageHeader {
+                format_version,
+                kind,
+                opcode_table,
+                opcode_count,
+                features,
+                steel_version,
+            },
+            sections,
+        })
+    }
+
+    /// Reads an image of the given kind, checking that it was produced by the same version of steel-core,
+    /// built with the same features, as this build
+    pub fn from_bytes(bytes: &[u8], kind: ImageKind) -> Result<Self> {
+        let image = Self::parse(bytes)?;
+
+        if image.header.kind != kind {
+            stop!(Generic => "expected a program image of kind {:?}, found {:?}", kind, image.header.kind);
+        }
+
+        if image.header.opcode_table != opcode_table_hash()
+            || image.header.opcode_count as usize != OPCODE_NAMES.len()
+        {
+            stop!(Generic => "program image was compiled by steel {}, which has an incompatible instruction set - recompile it with steel {}",
+                image.header.steel_version, crate::VERSION);
+        }
+
+        // The constants and builtins an image refers to depend on the version and enabled features
+        if image.header.steel_version != crate::VERSION {
+            stop!(Generic => "program image was compiled by steel {} - recompile it with steel {}",
+                image.header.steel_version, crate::VERSION);
+        }
+
+        if image.header.features != enabled_feature_bits() {
+            stop!(Generic => "program image was compiled by a steel built with the features [{}], but this build has [{}] - recompile it with this build",
+                image.header.feature_names().join(", "), enabled_features().join(", "));
+        }
+
+        Ok(image)
+    }
+}
+
+/// Renders the header, sections and decoded contents of an image in a human readable form. Images from
+/// versions of steel with a different instruction set only have their header and sections listed.
+pub fn disassemble(bytes: &[u8]) -> Result<String> {
+    let image = Image::parse(bytes)?;
+    let header = &image.header;
+    let compatible = header.opcode_table == opcode_table_hash()
+        && header.opcode_count as usize == OPCODE_NAMES.len();
+
+    let mut output = String::new();
+
+    writeln!(output, "format version: {}", header.format_version).unwrap();
+    writeln!(output, "kind:           {:?}", header.kind).unwrap();
+    writeln!(output, "steel version:  {}", header.steel_version).unwrap();
+    writeln!(
+        output,
+        "opcode table:   {:#018x} ({} op codes{})",
+        header.opcode_table,
+        header.opcode_count,
+        if compatible { "" } else { ", incompatible" }
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "features:       {}",
+        header.feature_names().join(", ")
+    )
+    .unwrap();
+
+    writeln!(output, "\nsections:").unwrap();
+    for section in &image.sections {
+        writeln!(
+            output,
+            "  {:<12} {:>10} bytes  crc32 {:#010x}",
+            section.kind.to_string(),
+            section.payload.len(),
+            section.checksum
+        )
+        .unwrap();
+    }
+
+    // Snapshots hold a heap rather than a program, there's no code to list
+    if header.kind == ImageKind::Snapshot {
+        return Ok(output);
+    }
+
+    if !compatible {
+        writeln!(
+            output,
+            "\nthe instructions in this image can't be decoded by steel {}",
+            crate::VERSION
+        )
+        .unwrap();
+        return Ok(output);
+    }
+
+    if let Some(modules) = image.read_optional_section::<Vec<PathBuf>>(SectionKind::Modules)? {
+        writeln!(output, "\nmodules:").unwrap();
+        for module in modules {
+            writeln!(output, "  {}", module.display()).unwrap();
+        }
+    }
+
+    let sources = image.read_optional_section::<Sources>(SectionKind::SourceMap)?;
+    let sources = sources.as_ref().map(|x| x.sources.lock().unwrap());
+
+    if let Some(sources) = &sources {
+        writeln!(output, "\nsources:").unwrap();
+        let mut paths = sources.paths().iter().collect::<Vec<_>>();
+        paths.sort();
+        for (id, path) in paths {
+            writeln!(output, "  {:>4}  {}", id.0, path.display()).unwrap();
+        }
+    }
+
+    let constants: SerializableConstantMap = image.read_section(SectionKind::Constants)?;
+    let constants = constants.constants();
+
+    writeln!(output, "\nconstants:").unwrap();
+    for (index, constant) in constants.iter().enumerate() {
+        writeln!(output, "  {index:>4}  {constant}").unwrap();
+    }
+
+    writeln!(output, "\ncode:").unwrap();
+
+    if header.kind == ImageKind::Program {
+        let code: Vec<Vec<DenseInstruction>> = image.read_section(SectionKind::Code)?;
+
+        for (index, instructions) in code.iter().enumerate() {
+            writeln!(output, "\n;; expression {index}").unwrap();
+            for (ip, instruction) in instructions.iter().enumerate() {
+                writeln!(
+                    output,
+                    "  {ip:>5}  {:<24} {}",
+                    format!("{:?}", instruction.op_code),
+                    instruction.payload_size
+                )
+                .unwrap();
+            }
+        }
+
+        return Ok(output);
+    }
+
+    let (_, code): (String, Vec<Vec<Instruction>>) = image.read_section(SectionKind::Code)?;
+
+    // Maps a span to the line and column it starts at, when the source it points into is in the image
+    let locate = |span: Span| -> Option<String> {
+        let sources = sources.as_ref()?;
+        let id = span.source_id?;
+        let text = sources.get(id)?;
+        let path = sources.get_path(&id)?;
+        let before = text.get(..span.start)?;
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map(|x| x + 1).unwrap_or(0) + 1;
+        Some(format!("{}:{line}:{column}", path.display()))
+    };
+
+    for (index, instructions) in code.iter().enumerate() {
+        writeln!(output, "\n;; expression {index}").unwrap();
+        for (ip, instruction) in instructions.iter().enumerate() {
+            let (contents, span) = match &instruction.contents {
+                Some(Expr::Atom(syn)) => (syn.ty.to_string(), syn.span),
+                Some(Expr::List(l)) => (l.to_string(), get_span(l)),
+                None => (String::new(), Span::default()),
+            };
+
+            write!(
+                output,
+                "  {ip:>5}  {:<24} {:<8} {contents}",
+                format!("{:?}", instruction.op_code),
+                instruction.payload_size
+            )
+            .unwrap();
+
+            if let Some(location) = locate(span) {
+                write!(output, "  ; {location}").unwrap();
+            }
+
+            writeln!(output).unwrap();
+        }
+    }
+
+    Ok(output)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8]> {
+        let end = self.offset.saturating_add(length);
+
+        if end > self.bytes.len() {
+            stop!(Generic => "program image is truncated");
+        }
+
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image() -> Image {
+        let mut image = Image::new(ImageKind::RawProgram);
+        image
+            .add_section(SectionKind::Modules, &vec!["foo.scm".to_string()])
+            .unwrap();
+        image
+    }
+
+    #[test]
+    fn round_trip() {
+        let image = Image::from_bytes(&image().to_bytes(), ImageKind::RawProgram).unwrap();
+
+        assert_eq!(image.header, ImageHeader::current(ImageKind::RawProgram));
+        assert_eq!(
+            image
+                .read_section::<Vec<String>>(SectionKind::Modules)
+                .unwrap(),
+            vec!["foo.scm".to_string()]
+        );
+        assert!(image.read_section::<Vec<u8>>(SectionKind::Code).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_images() {
+        let mut bytes = image().to_bytes();
+
+        assert!(Image::from_bytes(&bytes, ImageKind::Program).is_err());
+        assert!(Image::from_bytes(&bytes[..bytes.len() - 1], ImageKind::RawProgram).is_err());
+        assert!(Image::from_bytes(b"not an image", ImageKind::RawProgram).is_err());
+
+        // Corrupt the payload of the only section
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        let error = Image::from_bytes(&bytes, ImageKind::RawProgram).unwrap_err();
+        assert!(error.to_string().contains("checksum mismatch"));
+    }
+
+    #[test]
+    fn rejects_images_from_other_builds() {
+        let mut other_version = image();
+        other_version.header.steel_version = "0.0.1".to_string();
+
+        let error =
+            Image::from_bytes(&other_version.to_bytes(), ImageKind::RawProgram).unwrap_err();
+        assert!(error.to_string().contains("compiled by steel 0.0.1"));
+
+        let mut other_features = image();
+        other_features.header.features ^= 1;
+
+        let error =
+            Image::from_bytes(&other_features.to_bytes(), ImageKind::RawProgram).unwrap_err();
+        assert!(error.to_string().contains("built with the features"));
+
+        // The header is still readable, for disassembling
+        assert!(Image::parse(&other_features.to_bytes()).is_ok());
+    }
+
+    #[test]
+    fn disassemble_program_image() {
+        use crate::steel_vm::engine::{Engine, NonInteractiveProgramImage};
+
+        let path = std::env::temp_dir().join(format!("steel-image-{}.scm", std::process::id()));
+        let script = "(define (add-one x) (+ x 1))";
+        std::fs::write(&path, script).unwrap();
+
+        let program = Engine::create_non_interactive_program_image(script, path.clone());
+        std::fs::remove_file(&path).unwrap();
+
+        let bytes = program.unwrap().to_bytes().unwrap();
+        assert!(NonInteractiveProgramImage::from_bytes(&bytes).is_ok());
+
+        let output = disassemble(&bytes).unwrap();
+        assert!(output.contains("kind:           NonInteractive"));
+        assert!(output.contains(&format!("{}:1:", path.display())));
+        assert!(output.contains("add-one"));
+    }
+
+    #[test]
+    fn crc32_matches_reference() {
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+    }
+}
@@ -2,6 +2,7 @@
 #[allow(clippy::module_inception)]
 pub mod compiler;
 pub mod constants;
+pub mod image;
 pub mod map;
 pub mod modules;
 pub mod passes;
@@ -27,7 +27,7 @@ use std::{
     borrow::Cow,
     collections::{HashMap, HashSet},
     io::Read,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use crate::parser::expander::SteelMacro;
@@ -102,7 +102,8 @@ declare_builtins!(
     "#%private/steel/control" => "../scheme/modules/parameters.scm",
     "#%private/steel/reader" => "../scheme/modules/reader.scm",
     "#%private/steel/stdlib" => "../scheme/stdlib.scm",
-    "#%private/steel/match" => "../scheme/modules/match.scm"
+    "#%private/steel/match" => "../scheme/modules/match.scm",
+    "steel/testing" => "../scheme/modules/testing.scm"
 );
 
 create_prelude!(
@@ -132,6 +133,13 @@ pub(crate) struct ModuleManager {
     file_metadata: FxHashMap<PathBuf, SystemTime>,
     visited: FxHashSet<PathBuf>,
     custom_builtins: HashMap<String, String>,
+    // Modules required directly from the top level, so that their bindings
+    // can be refreshed when the module is reloaded
+    top_level_requires: Vec<RequireObject>,
+    // The prelude macros of an engine restored from a snapshot. Other engines
+    // share the ones registered when the kernel image was built.
+    #[serde(skip)]
+    prelude_macros: Option<FxHashMap<InternedString, SteelMacro>>,
 }
 
 impl ModuleManager {
@@ -144,9 +152,19 @@ impl ModuleManager {
             file_metadata,
             visited: FxHashSet::default(),
             custom_builtins: HashMap::new(),
+            top_level_requires: Vec::new(),
+            prelude_macros: None,
         }
     }
 
+    pub(crate) fn prelude_macros(&self) -> FxHashMap<InternedString, SteelMacro> {
+        prelude_macro_map(self.prelude_macros.as_ref())
+    }
+
+    pub(crate) fn set_prelude_macros(&mut self, macros: FxHashMap<InternedString, SteelMacro>) {
+        self.prelude_macros = Some(macros);
+    }
+
     pub fn add_builtin_module(&mut self, module_name: String, mut text: String) {
         // Custom external builtins should be loaded with the prelude first, otherwise
         // they'll need to handle a bunch of imports
@@ -192,6 +210,7 @@ impl ModuleManager {
             builtin_modules,
             global_macro_map,
             &self.custom_builtins,
+            self.prelude_macros.as_ref(),
             &[],
         )?;
 
@@ -202,6 +221,171 @@ impl ModuleManager {
         Ok(())
     }
 
+    // Compile the module again regardless of whether the file has changed, returning the
+    // statements that redefine it. If compilation fails, the previous version is kept.
+    pub(crate) fn reload_module(
+        &mut self,
+        path: PathBuf,
+        global_macro_map: &mut FxHashMap<InternedString, SteelMacro>,
+        kernel: &mut Option<Kernel>,
+        sources: &mut Sources,
+        builtin_modules: ModuleContainer,
+        search_dirs: &[PathBuf],
+    ) -> Result<Vec<ExprKind>> {
+        self.visited.clear();
+
+        let previous_module = self.compiled_modules.remove(&path);
+        let previous_modified = self.file_metadata.remove(&path);
+
+        let result = self.compile_module_statements(
+            path.clone(),
+            global_macro_map,
+            kernel,
+            sources,
+            builtin_modules,
+            search_dirs,
+        );
+
+        if result.is_err() {
+            if let Some(module) = previous_module {
+                self.compiled_modules.insert(path.clone(), module);
+            }
+
+            if let Some(modified) = previous_modified {
+                self.file_metadata.insert(path, modified);
+            }
+        }
+
+        result
+    }
+
+    fn compile_module_statements(
+        &mut self,
+        path: PathBuf,
+        global_macro_map: &mut FxHashMap<InternedString, SteelMacro>,
+        kernel: &mut Option<Kernel>,
+        sources: &mut Sources,
+        builtin_modules: ModuleContainer,
+        search_dirs: &[PathBuf],
+    ) -> Result<Vec<ExprKind>> {
+        let mut module_builder = ModuleBuilder::new_from_path(
+            path,
+            &mut self.compiled_modules,
+            &mut self.visited,
+            &mut self.file_metadata,
+            sources,
+            kernel,
+            builtin_modules,
+            global_macro_map,
+            &self.custom_builtins,
+            self.prelude_macros.as_ref(),
+            search_dirs,
+        )?;
+
+        let mut module_statements = module_builder.compile()?;
+
+        // Leaf modules are put into the cache by `compile`, anything with requires is left
+        // for the caller to compile
+        if !module_builder
+            .compiled_modules
+            .contains_key(&module_builder.name)
+        {
+            module_statements.push(module_builder.compile_module()?);
+        }
+
+        for expr in module_statements.iter_mut() {
+            expand(expr, global_macro_map)?;
+        }
+
+        Ok(module_statements)
+    }
+
+    // Files that have been modified since the module was last compiled
+    pub(crate) fn modified_modules(&self) -> Vec<PathBuf> {
+        let mut modified: Vec<_> = self
+            .file_metadata
+            .iter()
+            .filter(|(path, last_modified)| {
+                self.compiled_modules.contains_key(*path)
+                    && std::fs::metadata(path)
+                        .and_then(|x| x.modified())
+                        .map(|x| x != **last_modified)
+                        .unwrap_or(false)
+            })
+            .map(|x| x.0.clone())
+            .collect();
+
+        modified.sort();
+        modified
+    }
+
+    // Every module that requires the module at `path`, directly or transitively
+    pub(crate) fn dependents(&self, path: &Path) -> Vec<PathBuf> {
+        let mut dependents = Vec::new();
+        let mut queue = vec![path.to_path_buf()];
+
+        while let Some(next) = queue.pop() {
+            for (name, module) in &self.compiled_modules {
+                if name != path
+                    && !dependents.contains(name)
+                    && module
+                        .require_objects
+                        .iter()
+                        .any(|x| x.path.get_path().as_ref() == &next)
+                {
+                    dependents.push(name.clone());
+                    queue.push(name.clone());
+                }
+            }
+        }
+
+        dependents.sort();
+        dependents
+    }
+
+    // The globals that hold values provided by the module at `path`, paired with the name
+    // they were provided under. This covers modules that require it directly, along with
+    // requires from the top level.
+    pub(crate) fn require_aliases(&self, path: &Path) -> Result<Vec<(String, InternedString)>> {
+        let Some(module) = self.compiled_modules.get(path) else {
+            return Ok(Vec::new());
+        };
+
+        let mut aliases = Vec::new();
+
+        for (name, dependent) in &self.compiled_modules {
+            let requires = dependent
+                .require_objects
+                .iter()
+                .filter(|x| !x.for_syntax && x.path.get_path().as_ref() == path)
+                .collect::<Vec<_>>();
+
+            if requires.is_empty() {
+                continue;
+            }
+
+            let Some(name) = name.to_str() else {
+                stop!(Generic => format!("module path is not valid unicode: {:?}", name));
+            };
+
+            let prefix = "mangler".to_string() + name + MANGLER_SEPARATOR;
+
+            for require_object in requires {
+                aliases.extend(module.require_aliases(require_object, &prefix)?);
+            }
+        }
+
+        for require_object in self
+            .top_level_requires
+            .iter()
+            .filter(|x| x.path.get_path().as_ref() == path)
+        {
+            aliases.extend(module.require_aliases(require_object, "")?);
+        }
+
+        Ok(aliases)
+    }
+
     // #[allow(unused)]
     pub(crate) fn compile_main(
         &mut self,
@@ -236,11 +420,21 @@ impl ModuleManager {
             builtin_modules,
             global_macro_map,
             &self.custom_builtins,
+            self.prelude_macros.as_ref(),
             search_dirs,
         )?;
 
         let mut module_statements = module_builder.compile()?;
 
+        for require_object in &module_builder.require_objects {
+            if matches!(require_object.path, PathOrBuiltIn::Path(_))
+                && !require_object.for_syntax
+                && !self.top_level_requires.contains(require_object)
+            {
+                self.top_level_requires.push(require_object.clone());
+            }
+        }
+
         // for expr in module_builder.source_ast.iter_mut() {
         //     expand(expr, global_macro_map)?;
         // }
@@ -1019,6 +1213,74 @@ impl CompiledModule {
         self.emitted = emitted;
     }
 
+    // The globals defined by `require_object` when requiring this module from a module
+    // with the given mangled prefix, paired with the name of the provided value
+    fn require_aliases(
+        &self,
+        require_object: &RequireObject,
+        prefix: &str,
+    ) -> Result<Vec<(String, InternedString)>> {
+        let mut aliases = Vec::new();
+
+        for provide_expr in &self.provides {
+            let Some(provide_expr) = provide_expr.list() else {
+                stop!(TypeMismatch => format!("provide expected a list, found: {}", provide_expr))
+            };
+
+            for provide in provide_expr.args.iter().skip(1) {
+                let name = match provide {
+                    ExprKind::List(l) if l.first_ident() == Some(&*REQUIRE_IDENT_SPEC) => {
+                        l.args.get(1)
+                    }
+                    ExprKind::Atom(_) => Some(provide),
+                    _ => None,
+                };
+
+                let Some(name) = name.and_then(|x| x.atom_identifier()) else {
+                    continue;
+                };
+
+                if self.macro_map.contains_key(name) {
+                    continue;
+                }
+
+                let alias = if require_object.idents_to_import.is_empty() {
+                    *name
+                } else {
+                    let alias =
+                        require_object
+                            .idents_to_import
+                            .iter()
+                            .find_map(|ident| match ident {
+                                MaybeRenamed::Normal(i) if i.atom_identifier() == Some(name) => {
+                                    Some(*name)
+                                }
+                                MaybeRenamed::Renamed(from, to)
+                                    if from.atom_identifier() == Some(name) =>
+                                {
+                                    to.atom_identifier().copied()
+                                }
+                                _ => None,
+                            });
+
+                    match alias {
+                        Some(alias) => alias,
+                        None => continue,
+                    }
+                };
+
+                aliases.push((
+                    prefix.to_string()
+                        + require_object.prefix.as_deref().unwrap_or_default()
+                        + alias.resolve(),
+                    *name,
+                ));
+            }
+        }
+
+        Ok(aliases)
+    }
+
     fn to_top_level_module(
         &self,
         modules: &FxHashMap<PathBuf, CompiledModule>,
@@ -1588,7 +1850,7 @@ enum MaybeRenamed {
     Renamed(ExprKind, ExprKind),
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct RequireObject {
     path: PathOrBuiltIn,
     for_syntax: bool,
@@ -1596,7 +1858,7 @@ pub struct RequireObject {
     prefix: Option<String>,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 enum PathOrBuiltIn {
     BuiltIn(Cow<'static, str>),
     Path(PathBuf),
@@ -1635,6 +1897,15 @@ impl RequireObjectBuilder {
     }
 }
 
+fn prelude_macro_map(
+    prelude_macros: Option<&FxHashMap<InternedString, SteelMacro>>,
+) -> FxHashMap<InternedString, SteelMacro> {
+    match prelude_macros {
+        Some(macros) => macros.clone(),
+        None => DEFAULT_PRELUDE_MACROS.with(|x| x.borrow().clone()),
+    }
+}
+
 struct ModuleBuilder<'a> {
     name: PathBuf,
     main: bool,
@@ -1653,6 +1924,7 @@ struct ModuleBuilder<'a> {
     builtin_modules: ModuleContainer,
     global_macro_map: &'a FxHashMap<InternedString, SteelMacro>,
     custom_builtins: &'a HashMap<String, String>,
+    prelude_macros: Option<&'a FxHashMap<InternedString, SteelMacro>>,
     search_dirs: &'a [PathBuf],
 }
 
@@ -1670,6 +1942,7 @@ impl<'a> ModuleBuilder<'a> {
         builtin_modules: ModuleContainer,
         global_macro_map: &'a FxHashMap<InternedString, SteelMacro>,
         custom_builtins: &'a HashMap<String, String>,
+        prelude_macros: Option<&'a FxHashMap<InternedString, SteelMacro>>,
         search_dirs: &'a [PathBuf],
     ) -> Result<Self> {
         // TODO don't immediately canonicalize the path unless we _know_ its coming from a path
@@ -1690,7 +1963,7 @@ impl<'a> ModuleBuilder<'a> {
             name,
             main: true,
             source_ast,
-            macro_map: DEFAULT_PRELUDE_MACROS.with(|x| x.borrow().clone()),
+            macro_map: prelude_macro_map(prelude_macros),
             require_objects: Vec::new(),
             provides: Vec::new(),
             provides_for_syntax: Vec::new(),
@@ -1702,6 +1975,7 @@ impl<'a> ModuleBuilder<'a> {
             builtin_modules,
             global_macro_map,
             custom_builtins,
+            prelude_macros,
             search_dirs,
         })
     }
@@ -1818,6 +2092,7 @@ impl<'a> ModuleBuilder<'a> {
                     self.builtin_modules.clone(),
                     self.global_macro_map,
                     self.custom_builtins,
+                    self.prelude_macros,
                 )?;
 
                 // Walk the tree and compile any dependencies
@@ -1881,6 +2156,7 @@ impl<'a> ModuleBuilder<'a> {
                     self.builtin_modules.clone(),
                     self.global_macro_map,
                     self.custom_builtins,
+                    self.prelude_macros,
                     self.search_dirs,
                 )?;
 
@@ -2782,6 +3058,7 @@ impl<'a> ModuleBuilder<'a> {
         builtin_modules: ModuleContainer,
         global_macro_map: &'a FxHashMap<InternedString, SteelMacro>,
         custom_builtins: &'a HashMap<String, String>,
+        prelude_macros: Option<&'a FxHashMap<InternedString, SteelMacro>>,
     ) -> Result<Self> {
         ModuleBuilder::raw(
             name,
@@ -2793,6 +3070,7 @@ impl<'a> ModuleBuilder<'a> {
             builtin_modules,
             global_macro_map,
             custom_builtins,
+            prelude_macros,
             &[],
         )
         .parse_builtin(input)
@@ -2808,6 +3086,7 @@ impl<'a> ModuleBuilder<'a> {
         builtin_modules: ModuleContainer,
         global_macro_map: &'a FxHashMap<InternedString, SteelMacro>,
         custom_builtins: &'a HashMap<String, String>,
+        prelude_macros: Option<&'a FxHashMap<InternedString, SteelMacro>>,
         search_dirs: &'a [PathBuf],
     ) -> Result<Self> {
         ModuleBuilder::raw(
@@ -2820,6 +3099,7 @@ impl<'a> ModuleBuilder<'a> {
             builtin_modules,
             global_macro_map,
             custom_builtins,
+            prelude_macros,
             search_dirs,
         )
         .parse_from_path()
@@ -2835,6 +3115,7 @@ impl<'a> ModuleBuilder<'a> {
         builtin_modules: ModuleContainer,
         global_macro_map: &'a FxHashMap<InternedString, SteelMacro>,
         custom_builtins: &'a HashMap<String, String>,
+        prelude_macros: Option<&'a FxHashMap<InternedString, SteelMacro>>,
         search_dirs: &'a [PathBuf],
     ) -> Self {
         ModuleBuilder {
@@ -2842,7 +3123,7 @@ impl<'a> ModuleBuilder<'a> {
             main: false,
             source_ast: Vec::new(),
             // TODO: This used to be empty
-            macro_map: DEFAULT_PRELUDE_MACROS.with(|x| x.borrow().clone()),
+            macro_map: prelude_macro_map(prelude_macros),
             // macro_map: global_macro_map.clone(),
             require_objects: Vec::new(),
             provides: Vec::new(),
@@ -2855,6 +3136,7 @@ impl<'a> ModuleBuilder<'a> {
             builtin_modules,
             global_macro_map,
             custom_builtins,
+            prelude_macros,
             search_dirs,
         }
     }
@@ -128,7 +128,7 @@ impl<'a> VisitorMutRefUnit for NameUnMangler<'a> {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct NameMangler {
     pub(crate) globals: FxHashSet<InternedString>,
     prefix: String,
@@ -2,7 +2,7 @@ use crate::core::labels::Expr;
 use crate::parser::span_visitor::get_span;
 use crate::rvals::Result;
 use crate::{
-    compiler::constants::ConstantMap,
+    compiler::constants::{ConstantMap, SerializableConstantMap},
     core::{instructions::Instruction, opcode::OpCode},
     stop, SteelVal,
 };
@@ -28,7 +28,11 @@ use std::time::Instant;
 #[cfg(feature = "profiling")]
 use log::{debug, log_enabled};
 
-use super::{compiler::DebruijnIndicesInterner, map::SymbolMap};
+use super::{
+    compiler::DebruijnIndicesInterner,
+    image::{Image, ImageKind, SectionKind},
+    map::SymbolMap,
+};
 
 const _TILE_SUPER_INSTRUCTIONS: bool = false;
 
@@ -381,6 +385,7 @@ define_symbols! {
     STRUCT_KEYWORD => "struct",
     BETTER_LAMBDA => "#%better-lambda",
     DEFINE_VALUES => "define-values",
+    CASE_LAMBDA => "#%case-lambda",
     AS_KEYWORD => "as",
     SYNTAX_CONST_IF => "syntax-const-if",
     UNQUOTE => "unquote",
@@ -590,37 +595,38 @@ impl ProgramBuilder {
 #[derive(Serialize, Deserialize)]
 pub struct SerializableProgram {
     pub instructions: Vec<Vec<DenseInstruction>>,
-    pub constant_map: Vec<u8>,
+    pub constant_map: SerializableConstantMap,
 }
 
 impl SerializableProgram {
     pub fn write_to_file(&self, filename: &str) -> Result<()> {
-        use std::io::prelude::*;
-
-        let mut file = File::create(format!("{filename}.txt")).unwrap();
-
-        let buffer = bincode::serialize(self).unwrap();
-
-        file.write_all(&buffer)?;
+        std::fs::write(format!("{filename}.txt"), self.to_bytes()?)?;
         Ok(())
     }
 
     pub fn read_from_file(filename: &str) -> Result<Self> {
-        use std::io::prelude::*;
-
-        let mut file = File::open(format!("{filename}.txt")).unwrap();
-
-        let mut buffer = Vec::new();
+        Self::from_bytes(&std::fs::read(format!("{filename}.txt"))?)
+    }
 
-        let _ = file.read_to_end(&mut buffer).unwrap();
+    /// Serialize the program as an image, see [`crate::compiler::image`]
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut image = Image::new(ImageKind::Program);
+        image.add_section(SectionKind::Code, &self.instructions)?;
+        image.add_section(SectionKind::Constants, &self.constant_map)?;
+        Ok(image.to_bytes())
+    }
 
-        let program: SerializableProgram = bincode::deserialize(&buffer).unwrap();
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let image = Image::from_bytes(bytes, ImageKind::Program)?;
 
-        Ok(program)
+        Ok(SerializableProgram {
+            instructions: image.read_section(SectionKind::Code)?,
+            constant_map: image.read_section(SectionKind::Constants)?,
+        })
     }
 
     pub fn into_program(self) -> Program {
-        let constant_map = ConstantMap::from_bytes(&self.constant_map).unwrap();
+        let constant_map = ConstantMap::from_serialized(self.constant_map).unwrap();
         Program {
             constant_map,
             instructions: self.instructions,
@@ -653,7 +659,7 @@ impl Program {
     pub fn into_serializable_program(self) -> Result<SerializableProgram> {
         Ok(SerializableProgram {
             instructions: self.instructions,
-            constant_map: self.constant_map.to_bytes()?,
+            constant_map: self.constant_map.to_serializable(),
         })
     }
 }
@@ -671,41 +677,69 @@ pub struct RawProgramWithSymbols {
 #[derive(Serialize, Deserialize)]
 pub struct SerializableRawProgramWithSymbols {
     instructions: Vec<Vec<Instruction>>,
-    constant_map: Vec<u8>,
+    constant_map: SerializableConstantMap,
     version: String,
 }
 
 impl SerializableRawProgramWithSymbols {
     pub fn write_to_file(&self, filename: &str) -> Result<()> {
-        use std::io::prelude::*;
+        std::fs::write(format!("{filename}.txt"), self.to_bytes()?)?;
+        Ok(())
+    }
+
+    pub fn read_from_file(filename: &str) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(format!("{filename}.txt"))?)
+    }
 
-        let mut file = File::create(format!("{filename}.txt")).unwrap();
+    /// Serialize the program as an image, see [`crate::compiler::image`]
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut image = Image::new(ImageKind::RawProgram);
+        self.add_sections(&mut image)?;
+        Ok(image.to_bytes())
+    }
 
-        let buffer = bincode::serialize(self).unwrap();
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::from_image(&Image::from_bytes(bytes, ImageKind::RawProgram)?)
+    }
 
-        file.write_all(&buffer)?;
-        Ok(())
+    pub(crate) fn add_sections(&self, image: &mut Image) -> Result<()> {
+        image.add_section(SectionKind::Code, &(&self.version, &self.instructions))?;
+        image.add_section(SectionKind::Constants, &self.constant_map)
     }
 
-    pub fn read_from_file(filename: &str) -> Result<Self> {
-        use std::io::prelude::*;
+    pub(crate) fn from_image(image: &Image) -> Result<Self> {
+        let (version, instructions) = image.read_section(SectionKind::Code)?;
 
-        let mut file = File::open(format!("{filename}.txt")).unwrap();
-        let mut buffer = Vec::new();
-        let _ = file.read_to_end(&mut buffer).unwrap();
-        let program: Self = bincode::deserialize(&buffer).unwrap();
+        Ok(SerializableRawProgramWithSymbols {
+            instructions,
+            constant_map: image.read_section(SectionKind::Constants)?,
+            version,
+        })
+    }
+
+    pub fn instructions(&self) -> &[Vec<Instruction>] {
+        &self.instructions
+    }
 
-        Ok(program)
+    /// Deserializes the constant pool, for inspecting the program
+    pub fn constants(&self) -> Result<ConstantMap> {
+        ConstantMap::from_serialized(self.constant_map.clone())
     }
 
     pub fn into_raw_program(self) -> RawProgramWithSymbols {
-        let constant_map = ConstantMap::from_bytes(&self.constant_map).unwrap();
-        RawProgramWithSymbols {
+        self.try_into_raw_program().unwrap()
+    }
+
+    /// Like [`SerializableRawProgramWithSymbols::into_raw_program`], but reports a constant map
+    /// that fails to deserialize as an error rather than panicking
+    pub fn try_into_raw_program(self) -> Result<RawProgramWithSymbols> {
+        let constant_map = ConstantMap::from_serialized(self.constant_map)?;
+        Ok(RawProgramWithSymbols {
             // struct_functions: self.struct_functions,
             instructions: self.instructions,
             constant_map,
             version: self.version,
-        }
+        })
     }
 }
 
@@ -881,7 +915,7 @@ impl RawProgramWithSymbols {
     pub fn into_serializable_program(self) -> Result<SerializableRawProgramWithSymbols> {
         Ok(SerializableRawProgramWithSymbols {
             instructions: self.instructions,
-            constant_map: self.constant_map.to_bytes()?,
+            constant_map: self.constant_map.to_serializable(),
             version: self.version,
         })
     }
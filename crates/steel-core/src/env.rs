@@ -1,9 +1,17 @@
 use crate::rvals::{Result, SteelVal};
+use fxhash::FxHashMap;
+use std::rc::Rc;
 
 #[allow(unused)]
 #[derive(Debug, Clone)]
 pub struct Env {
     pub(crate) bindings_vec: Vec<SteelVal>,
+    // Globals belonging to namespaces that aren't currently active. These are
+    // kept here so that they are still treated as roots. New namespaces share
+    // the globals they start out with until they are first activated.
+    pub(crate) parked: FxHashMap<usize, Rc<Vec<SteelVal>>>,
+    // The globals that new namespaces start out with
+    pub(crate) namespace_base: Option<Rc<Vec<SteelVal>>>,
 }
 
 impl Env {
@@ -19,6 +27,8 @@ impl Env {
     pub fn root() -> Self {
         Env {
             bindings_vec: Vec::with_capacity(1024),
+            parked: FxHashMap::default(),
+            namespace_base: None,
         }
     }
 
@@ -83,6 +93,17 @@ impl Env {
     }
 
     pub fn roots(&self) -> impl Iterator<Item = &SteelVal> {
-        self.bindings_vec.iter()
+        self.bindings_vec
+            .iter()
+            .chain(self.parked.values().flat_map(|x| x.iter()))
+            .chain(self.namespace_base.iter().flat_map(|x| x.iter()))
+    }
+
+    /// Swap the active globals with the parked globals for the namespace `id`,
+    /// copying them first if they are still shared
+    pub(crate) fn swap_parked(&mut self, id: usize) {
+        if let Some(parked) = self.parked.get_mut(&id) {
+            std::mem::swap(&mut self.bindings_vec, Rc::make_mut(parked));
+        }
     }
 }
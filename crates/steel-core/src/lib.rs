@@ -8,6 +8,7 @@ pub mod primitives;
 #[macro_use]
 pub mod rerrs;
 pub mod rvals;
+pub mod serde;
 pub mod stdlib;
 #[macro_use]
 pub mod gc;
@@ -29,3 +30,6 @@ pub use im_rc::HashMap;
 pub use primitives::UnRecoverableResult;
 pub use values::RootToken;
 pub use values::RootedSteelVal;
+
+/// The version of steel-core. Compiled program images can only be read by the same version.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -10,9 +10,9 @@ use crate::{
 };
 
 pub use steel_parser::ast::{
-    AstTools, Atom, Begin, Define, ExprKind, If, IteratorExtensions, LambdaFunction, Let, List,
-    Macro, PatternPair, Quote, Require, Return, Set, SyntaxRules, STANDARD_MODULE_GET,
-    UNREADABLE_MODULE_GET,
+    AstTools, Atom, Begin, Define, ExprKind, If, IteratorExtensions, LambdaFunction,
+    LambdaSignature, Let, List, Macro, PatternPair, Quote, Require, Return, Set, SyntaxRules,
+    STANDARD_MODULE_GET, UNREADABLE_MODULE_GET,
 };
 
 impl TryFrom<ExprKind> for SteelVal {
@@ -218,6 +218,9 @@ impl TryFrom<&SteelVal> for ExprKind {
                 SymbolV(x) => Ok(ExprKind::Atom(Atom::new(SyntaxObject::default(
                     Identifier(x.as_str().into()),
                 )))),
+                SteelVal::Keyword(x) => Ok(ExprKind::Atom(Atom::new(SyntaxObject::default(
+                    crate::parser::tokens::TokenType::Keyword(x.as_str().into()),
+                )))),
                 SyntaxObject(s) => s
                     .to_exprkind()
                     .map_err(|_| "Unable to convert syntax object back to exprkind"),
@@ -237,6 +240,7 @@ impl TryFrom<&SteelVal> for ExprKind {
                 )))),
 
                 Pair(_) => Err("Can't convert from pair to expression!"),
+                Complex(_) => Err("Can't convert from complex number to expression!"),
 
                 // StructClosureV(_) => Err("Can't convert from struct-function to expression!"),
                 PortV(_) => Err("Can't convert from port to expression!"),
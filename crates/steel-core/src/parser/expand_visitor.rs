@@ -7,16 +7,20 @@ use crate::compiler::passes::reader::MultipleArityFunctions;
 use crate::compiler::passes::VisitorMutRefUnit;
 use crate::parser::ast::ExprKind;
 use crate::parser::parser::SyntaxObject;
+use crate::parser::span::Span;
 use crate::parser::span_visitor::get_span;
 use crate::steel_vm::engine::ModuleContainer;
-use crate::{compiler::program::AS_KEYWORD, parser::tokens::TokenType};
 use crate::{compiler::program::REQUIRE_BUILTIN, rvals::Result};
+use crate::{
+    compiler::program::{AS_KEYWORD, CASE_LAMBDA},
+    parser::tokens::TokenType,
+};
 
 use steel_parser::expr_list;
 
 use super::visitors::VisitorMutRef;
 use super::{
-    ast::{Atom, Define, LambdaFunction, List, Quote},
+    ast::{Atom, Define, LambdaFunction, LambdaSignature, List, Quote},
     interner::InternedString,
     kernel::Kernel,
 };
@@ -464,241 +468,211 @@ impl<'a> KernelExpander<'a> {
     }
 }
 
-fn _expand_default_arguments(
-    lambda_function: Box<super::ast::LambdaFunction>,
-) -> Result<Box<super::ast::LambdaFunction>> {
-    // todo!()
-
-    let _args_len = lambda_function.args.len();
+fn keyword(expr: &ExprKind) -> Option<InternedString> {
+    if let ExprKind::Atom(Atom {
+        syn: SyntaxObject {
+            ty: TokenType::Keyword(keyword),
+            ..
+        },
+    }) = expr
+    {
+        Some(*keyword)
+    } else {
+        None
+    }
+}
 
-    let mut found_pair = false;
-    for argument in &lambda_function.args {
-        if let ExprKind::List(_) = argument {
-            found_pair = true;
-        } else if found_pair {
-            stop!(BadSyntax => "Non default argument occurs after a default argument"; lambda_function.location.span)
+// Splits an argument into the identifier it binds and its default, if it has one
+fn argument_with_default(argument: ExprKind, span: Span) -> Result<(ExprKind, Option<ExprKind>)> {
+    match argument {
+        ExprKind::List(l) if l.len() == 2 && l.args[0].atom_identifier().is_some() => {
+            let mut args = l.args.into_iter();
+            Ok((args.next().unwrap(), args.next()))
+        }
+        argument if argument.atom_identifier().is_some() => Ok((argument, None)),
+        argument => {
+            stop!(BadSyntax => format!("lambda expects an identifier or [identifier default] for each argument, found: {argument}"); span)
         }
     }
-
-    let _non_default_bindings = lambda_function
-        .args
-        .iter()
-        .filter(|x| !matches!(x, ExprKind::List(_)))
-        .collect::<Vec<_>>();
-
-    // let bindings = lambda_function
-    //     .args
-    //     .iter()
-    //     .enumerate()
-    //     .filter(|x| matches!(x.1, ExprKind::List(_)))
-    //     .map(|x| {
-    //         if let ExprKind::List(l) = x.1 {
-    //             let var_name = l
-    //                 .get(0)
-    //                 .ok_or_else(throw!(BadSyntax => "empty default argument"))?;
-    //             let expr = l.get(1).ok_or_else(
-    //                 throw!(BadSyntax => "default argument missing default expression"),
-    //             )?;
-
-    //             let index = ExprKind::integer_literal(x.0 as isize, lambda_function.location.span);
-
-    //             let body = vec![
-
-    //             ]
-
-    //             todo!()
-    //         } else {
-    //             unreachable!()
-    //         }
-    //     })
-    //     .collect::<Result<Vec<_>>>();
-
-    todo!()
 }
 
-// Adjust the generated code to raise a specific error saying certain keys were missing.
-fn expand_keyword_arguments(lambda_function: &mut super::ast::LambdaFunction) -> Result<()> {
-    // TODO: Check if this already has a rest argument - if so, the generated code will need to be changed.
-    // The naive generated code will not handle rest arguments with keyword arguments, which can be a concern.
-    // In addition, this naively assumes that keyword arguments cannot be applied before positional arguments - which
-    // on its own is not the worst restriction, and perhaps we can leave that in place.
-    //
-    // If there are rest arguments though, we'll need to split the rest argument list into two - the first half will then get
-    // applied to the hashmap list, while the rest of the arguments will get applied to the correct place.
+// Expands optional and keyword arguments, `(a [b default] #:c c #:d [d default])`, into plain
+// arguments along with a signature that the vm matches the arguments of a call against. The vm
+// marks the arguments that weren't passed, and the defaults for those get filled in at the top of
+// the body.
+fn expand_lambda_signature(lambda_function: &mut LambdaFunction) -> Result<()> {
+    if lambda_function.signature.is_some() {
+        return Ok(());
+    }
 
     MultipleArityFunctions::new().visit_lambda_function(lambda_function);
 
-    // If this already has a rest arguments, we need to slice out the
-    // remaining function values from the keywords, and then bind those to whatever variable in the original
-    // list before we create the hash. Making the hash itself is also not exactly my favorite pattern - we need
-    // to allocate extra stuff - what we should probably do is create a special keyword allocation that we
-    // can consistently reuse inside the VM. If we can reuse that allocation repeatedly, we should be able
-    // to avoid much of the overhead of the allocation.
-
-    // TODO: Can partition these directly into the two groups
-    let keyword_args: Vec<&ExprKind> = lambda_function
+    if lambda_function
         .args
         .iter()
-        .skip_while(|x| {
-            !matches!(
-                x,
-                ExprKind::Atom(Atom {
-                    syn: SyntaxObject {
-                        ty: TokenType::Keyword(_),
-                        ..
-                    }
-                })
-            )
-        })
-        .collect();
-
-    // If there is a rest argument, we'll want to grab it for later use in the expansion
-    // TODO: @Matt - Come back to this one
-    // let mut rest_arg_expr = None;
-
-    // Bail out if theres no keyword args
-    if keyword_args.is_empty() {
+        .all(|x| x.atom_identifier().is_some())
+    {
         return Ok(());
     }
 
-    if (keyword_args.len() % 2 != 0 && !lambda_function.rest)
-        || (lambda_function.rest && keyword_args.len() - 1 % 2 != 0)
-    {
-        // The last argument is going to be the rest argument
-        // if lambda_function.rest {
-        //     rest_arg_expr = keyword_args.pop();
-        // }
+    let span = lambda_function.location.span;
+
+    let rest_argument = if lambda_function.rest {
+        lambda_function.args.pop()
+    } else {
+        None
+    };
+
+    let mut required = Vec::new();
+    let mut optional = Vec::new();
+    let mut keywords: Vec<(InternedString, ExprKind, Option<ExprKind>)> = Vec::new();
+
+    let mut args = std::mem::take(&mut lambda_function.args).into_iter();
 
-        stop!(Generic => "keyword arguments malformed - each option requires a value"; lambda_function.location.span)
+    while let Some(argument) = args.next() {
+        if let Some(keyword) = keyword(&argument) {
+            let Some(binding) = args.next() else {
+                stop!(BadSyntax => format!("keyword argument {keyword} is missing an identifier to bind it to"); span)
+            };
+
+            if keywords.iter().any(|x| x.0 == keyword) {
+                stop!(BadSyntax => format!("keyword argument {keyword} is declared more than once"); span)
+            }
+
+            let (name, default) = argument_with_default(binding, span)?;
+            keywords.push((keyword, name, default));
+        } else if !keywords.is_empty() {
+            stop!(BadSyntax => "Non keyword arguments found after the first keyword argument"; span)
+        } else {
+            match argument_with_default(argument, span)? {
+                (name, Some(default)) => optional.push((name, default)),
+                (name, None) if optional.is_empty() => required.push(name),
+                _ => {
+                    stop!(BadSyntax => "Non default argument occurs after a default argument"; span)
+                }
+            }
+        }
     }
 
-    let mut non_keyword_args: Vec<ExprKind> = lambda_function
-        .args
+    let signature = LambdaSignature::Parameters {
+        required: required.len(),
+        optional: optional.len(),
+        keywords: keywords.iter().map(|x| (x.0, x.2.is_none())).collect(),
+    };
+
+    let defaults = optional
         .iter()
-        .take_while(|x| {
-            !matches!(
-                x,
-                ExprKind::Atom(Atom {
-                    syn: SyntaxObject {
-                        ty: TokenType::Keyword(_),
-                        ..
-                    }
-                })
-            )
-        })
         .cloned()
-        .collect();
-
-    // From the keyword args, group them into pairs
-    let keyword_map = keyword_args
-        .chunks(2)
-        .into_iter()
-        .map(|x| (x[0], x[1]))
+        .chain(
+            keywords
+                .iter()
+                .filter_map(|(_, name, default)| Some((name.clone(), default.clone()?))),
+        )
         .collect::<Vec<_>>();
 
-    if !keyword_map.iter().map(|x| x.0).all(|x| {
-        matches!(
-            x,
-            ExprKind::Atom(Atom {
-                syn: SyntaxObject {
-                    ty: TokenType::Keyword(_),
-                    ..
-                }
-            })
-        )
-    }) {
-        stop!(Generic => "Non keyword arguments found after the first keyword argument"; lambda_function.location.span)
+    // Each default is bound in its own let, so that it can refer to the arguments before it
+    let mut body = std::mem::take(&mut lambda_function.body);
+
+    for (name, default) in defaults.into_iter().rev() {
+        let value = ExprKind::default_if(
+            expr_list![ExprKind::ident("#%unsupplied?"), name.clone()],
+            default,
+            name.clone(),
+        );
+
+        body = expr_list![
+            ExprKind::LambdaFunction(Box::new(LambdaFunction::new(
+                vec![name],
+                body,
+                SyntaxObject::default(TokenType::Lambda),
+            ))),
+            value
+        ];
     }
 
-    let bindings = keyword_map
+    lambda_function.args = required
         .into_iter()
-        .map(|x| {
-            let keyword = x.0;
-            let original_var_name = x.1;
+        .chain(optional.into_iter().map(|x| x.0))
+        .chain(keywords.into_iter().map(|x| x.1))
+        .chain(rest_argument)
+        .collect();
+    lambda_function.body = body;
+    lambda_function.signature = Some(signature);
 
-            // This is a bit wasteful... come back to this
-            let (var_name, expr) = if let ExprKind::List(l) = original_var_name {
-                (l[0].clone(), l[1].clone())
-            } else {
-                (original_var_name.clone(), original_var_name.clone())
-            };
+    Ok(())
+}
 
-            // println!("{:?}", original_var_name);
+// Merges the clauses of a `case-lambda`, `(#%case-lambda (lambda formals body ...) ...)`, into one
+// lambda. The vm passes it the index of the clause that matched the call followed by the
+// arguments for that clause, and the body binds those to the clause's own arguments.
+fn expand_case_lambda(l: &mut List, span: Span) -> Result<ExprKind> {
+    let mut clauses = Vec::new();
+    let mut functions = Vec::new();
 
-            // TODO: Go here to implement default arguments
-            let expression = ExprKind::default_if(
-                expr_list![
-                    ExprKind::ident("hash-contains?"),
-                    ExprKind::ident("!!dummy-rest-arg!!"),
-                    ExprKind::Quote(Box::new(Quote::new(
-                        keyword.clone(),
-                        lambda_function.location.clone(),
-                    ))),
-                ],
-                var_name.clone(),
-                ExprKind::default_if(
-                    ExprKind::bool_lit(matches!(original_var_name, ExprKind::List(_))),
-                    expr,
-                    expr_list![
-                        ExprKind::ident("error!"),
-                        ExprKind::string_lit(format!(
-                            "Function application missing required keyword argument: {keyword}"
-                        ))
-                    ],
-                ),
-            );
-
-            let func = ExprKind::LambdaFunction(Box::new(LambdaFunction::new(
-                vec![var_name.clone()],
-                expression,
-                SyntaxObject::default(TokenType::Lambda),
-            )));
+    for clause in l.args.drain(1..) {
+        let ExprKind::LambdaFunction(mut function) = clause else {
+            stop!(BadSyntax => "case-lambda expects each clause to be a list of arguments followed by a body"; span)
+        };
 
-            let application = expr_list![
-                func,
-                expr_list![
-                    ExprKind::ident("hash-try-get"),
-                    ExprKind::ident("!!dummy-rest-arg!!"),
-                    ExprKind::Quote(Box::new(Quote::new(
-                        keyword.clone(),
-                        lambda_function.location.clone(),
-                    ))),
-                ],
-            ];
+        MultipleArityFunctions::new().visit_lambda_function(&mut function);
+
+        if function.signature.is_some() {
+            stop!(BadSyntax => "case-lambda clauses can't have optional or keyword arguments"; span)
+        }
+
+        clauses.push((
+            function.args.len() - usize::from(function.rest),
+            function.rest,
+        ));
+        functions.push(function);
+    }
+
+    if functions.is_empty() {
+        stop!(BadSyntax => "case-lambda expects at least one clause"; span)
+    }
 
-            (var_name, application)
-        })
+    let clause_index = ExprKind::ident("#%case-lambda-clause");
+
+    let slots = functions.iter().map(|x| x.args.len()).max().unwrap_or(0);
+
+    let arguments = (0..slots)
+        .map(|i| ExprKind::ident(&format!("#%case-lambda-argument{i}")))
         .collect::<Vec<_>>();
 
-    non_keyword_args.push(ExprKind::ident("!!dummy-rest-arg!!"));
+    let mut body = None;
 
-    let mut inner_application = vec![ExprKind::LambdaFunction(Box::new(LambdaFunction::new(
-        bindings.iter().map(|x| x.0.clone()).collect(),
-        lambda_function.body.clone(),
-        SyntaxObject::default(TokenType::Lambda),
-    )))];
+    for (index, mut function) in functions.into_iter().enumerate().rev() {
+        // The vm already collected the rest argument into a list
+        function.rest = false;
 
-    inner_application.extend(bindings.iter().map(|x| x.1.clone()));
+        let arity = function.args.len();
 
-    *lambda_function = LambdaFunction::new_with_rest_arg(
-        non_keyword_args,
-        expr_list![
-            ExprKind::LambdaFunction(Box::new(LambdaFunction::new(
-                vec![ExprKind::ident("!!dummy-rest-arg!!")],
-                ExprKind::List(List::new(inner_application)),
-                SyntaxObject::default(TokenType::Lambda),
-            ))),
-            expr_list![
-                ExprKind::ident("apply"),
-                ExprKind::ident("%keyword-hash"), // This shouldn't be `hash` directly - something with a specific error
-                // TODO: do like, `(take x !!dummy-rest-arg!!)`
-                ExprKind::ident("!!dummy-rest-arg!!"),
-            ],
-        ],
-        SyntaxObject::default(TokenType::Lambda),
+        let mut application = vec![ExprKind::LambdaFunction(function)];
+        application.extend(arguments[..arity].iter().cloned());
+        let application = ExprKind::List(List::new(application));
+
+        body = Some(match body {
+            None => application,
+            Some(otherwise) => ExprKind::default_if(
+                expr_list![
+                    ExprKind::ident("#%prim.="),
+                    clause_index.clone(),
+                    ExprKind::integer_literal(index as isize, span)
+                ],
+                application,
+                otherwise,
+            ),
+        });
+    }
+
+    let mut function = LambdaFunction::new(
+        std::iter::once(clause_index).chain(arguments).collect(),
+        body.unwrap(),
+        SyntaxObject::new(TokenType::Lambda, span),
     );
+    function.signature = Some(LambdaSignature::Cases(clauses));
 
-    Ok(())
+    Ok(ExprKind::LambdaFunction(Box::new(function)))
 }
 
 // VisitorMutRef
@@ -721,15 +695,9 @@ impl<'a> VisitorMutRef for KernelExpander<'a> {
         &mut self,
         lambda_function: &mut super::ast::LambdaFunction,
     ) -> Self::Output {
-        // TODO: Unfortunately this wipes out the span
-        // There needs to be
+        expand_lambda_signature(lambda_function)?;
 
-        self.visit(&mut lambda_function.body)?;
-
-        // Expand keyword arguments if we can
-        expand_keyword_arguments(lambda_function)?;
-
-        Ok(())
+        self.visit(&mut lambda_function.body)
     }
 
     fn visit_begin(&mut self, begin: &mut super::ast::Begin) -> Self::Output {
@@ -853,6 +821,17 @@ impl<'a> VisitorMutRef for KernelExpander<'a> {
                             }
                         }
 
+                        if s == *CASE_LAMBDA {
+                            for clause in l.args[1..].iter_mut() {
+                                self.visit(clause)?;
+                            }
+
+                            let span = l.location.unwrap_or_else(|| get_span(&l.args[0]));
+                            *expr = expand_case_lambda(l, span)?;
+
+                            return Ok(());
+                        }
+
                         if s == *REQUIRE_BUILTIN {
                             match &l.args[1..] {
                                 [ExprKind::Atom(Atom {
@@ -37,7 +37,7 @@ pub(crate) fn fresh_kernel_image() -> Engine {
     KERNEL_IMAGE.with(|x| x.clone())
 }
 
-type TransformerMap = FxHashMap<String, FxHashSet<InternedString>>;
+pub(crate) type TransformerMap = FxHashMap<String, FxHashSet<InternedString>>;
 
 // Internal set of transformers that we'll embed
 #[derive(Clone, Debug)]
@@ -72,6 +72,25 @@ impl Kernel {
             set: Arc::new(RwLock::new(HashMap::default())),
         };
 
+        Self::register_transformer_functions(&mut engine, &transformers);
+
+        // Run the script for building the core interface for structs
+        engine.compile_and_run_raw_program(KERNEL).unwrap();
+
+        // let mut macros = HashSet::new();
+        // macros.insert("%better-lambda%".to_string());
+        // macros.insert(*STRUCT_KEYWORD);
+        // macros.insert(*DEFINE_VALUES);
+
+        Kernel {
+            // macros,
+            transformers,
+            constants: HashSet::new(),
+            engine: Box::new(engine),
+        }
+    }
+
+    fn register_transformer_functions(engine: &mut Engine, transformers: &Transformers) {
         let embedded_transformer_object = transformers.clone();
         engine.register_fn(
             "register-macro-transformer!",
@@ -105,19 +124,32 @@ impl Kernel {
                     .unwrap_or_else(|| SteelVal::ListV(List::new()))
             },
         );
+    }
 
-        // Run the script for building the core interface for structs
-        engine.compile_and_run_raw_program(KERNEL).unwrap();
+    /// The registered transformers and constants, for writing the kernel into a snapshot
+    pub(crate) fn snapshot_state(&self) -> (TransformerMap, HashSet<InternedString>) {
+        (
+            self.transformers.set.read().unwrap().clone(),
+            self.constants.clone(),
+        )
+    }
 
-        // let mut macros = HashSet::new();
-        // macros.insert("%better-lambda%".to_string());
-        // macros.insert(*STRUCT_KEYWORD);
-        // macros.insert(*DEFINE_VALUES);
+    /// Rebuilds a kernel from a snapshot, on top of `engine`. The engine state itself is
+    /// restored separately, once the kernel functions have been registered.
+    pub(crate) fn from_snapshot(
+        transformers: TransformerMap,
+        constants: HashSet<InternedString>,
+        mut engine: Engine,
+    ) -> Self {
+        let transformers = Transformers {
+            set: Arc::new(RwLock::new(transformers)),
+        };
+
+        Self::register_transformer_functions(&mut engine, &transformers);
 
         Kernel {
-            // macros,
             transformers,
-            constants: HashSet::new(),
+            constants,
             engine: Box::new(engine),
         }
     }
@@ -99,6 +99,10 @@ impl InterierSources {
     pub fn get_id(&self, path: &PathBuf) -> Option<SourceId> {
         self.reverse.get(path).copied()
     }
+
+    pub fn paths(&self) -> &HashMap<SourceId, PathBuf> {
+        &self.paths
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -170,7 +174,7 @@ impl TryFrom<SyntaxObject> for SteelVal {
             }
             FractionLiteral(n, d) => BigRational::new(n.into(), d.into()).into_steelval(),
             StringLiteral(x) => Ok(StringV(x.into())),
-            Keyword(x) => Ok(SymbolV(x.into())),
+            crate::parser::tokens::TokenType::Keyword(x) => Ok(SymbolV(x.into())),
             QuoteTick => {
                 Err(SteelErr::new(ErrorKind::UnexpectedToken, "'".to_string()).with_span(span))
             }
@@ -1,5 +1,5 @@
 pub mod contracts;
-mod control;
+pub mod control;
 mod fs;
 pub mod hashmaps;
 pub mod hashsets;
@@ -10,7 +10,8 @@ pub mod nums;
 pub mod ports;
 pub mod process;
 pub mod random;
-mod streams;
+pub mod regex;
+pub mod streams;
 pub mod strings;
 mod symbols;
 pub mod time;
@@ -21,8 +22,8 @@ pub mod vectors;
 use crate::gc::Gc;
 use crate::rvals::{FromSteelVal, IntoSteelVal};
 use crate::rvals::{
-    FunctionSignature, PrimitiveAsRef, PrimitiveAsRefMut, SteelHashMap, SteelHashSet, SteelVal,
-    SteelVector,
+    FunctionSignature, PrimitiveAsRef, PrimitiveAsRefMut, SteelComplex, SteelHashMap, SteelHashSet,
+    SteelVal, SteelVector,
 };
 use crate::values::closed::HeapRef;
 use crate::values::lists::List;
@@ -276,6 +277,31 @@ impl IntoSteelVal for BigRational {
     }
 }
 
+impl IntoSteelVal for SteelComplex {
+    fn into_steelval(self) -> Result<SteelVal, SteelErr> {
+        let inexact = |part: SteelVal| match part {
+            SteelVal::IntV(i) => Ok(SteelVal::NumV(i as f64)),
+            SteelVal::BigNum(n) => Ok(SteelVal::NumV(n.to_f64().unwrap())),
+            SteelVal::Rational(r) => Ok(SteelVal::NumV(r.to_f64().unwrap())),
+            SteelVal::BigRational(r) => Ok(SteelVal::NumV(r.to_f64().unwrap())),
+            SteelVal::NumV(n) => Ok(SteelVal::NumV(n)),
+            _ => Err(SteelErr::new(
+                ErrorKind::TypeMismatch,
+                format!("complex numbers need real parts, found: {}", part),
+            )),
+        };
+
+        match (self.re, self.im) {
+            (re, SteelVal::IntV(0)) => Ok(re),
+            // Both parts of a complex number share the same exactness
+            (re @ SteelVal::NumV(_), im) | (re, im @ SteelVal::NumV(_)) => Ok(SteelVal::Complex(
+                Gc::new(SteelComplex::new(inexact(re)?, inexact(im)?)),
+            )),
+            (re, im) => Ok(SteelVal::Complex(Gc::new(SteelComplex::new(re, im)))),
+        }
+    }
+}
+
 from_f64!(f64, f32);
 from_for_isize!(i32, i16, i8, u8, u16, u32, u64, usize, isize);
 try_from_impl!(NumV => f64, f32);
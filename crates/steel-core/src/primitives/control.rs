@@ -1,4 +1,5 @@
-use crate::rvals::{Result, SteelVal};
+use crate::rerrs::{ErrorKind, SteelErr};
+use crate::rvals::{as_underlying_type, IntoSteelVal, Result, SteelVal};
 use crate::stop;
 
 pub struct ControlOperations {}
@@ -28,10 +29,149 @@ impl ControlOperations {
                     error_message.push_str(error_val.trim_matches('\"'));
                 }
 
-                stop!(Generic => error_message);
+                let message = match &args[0] {
+                    SteelVal::StringV(s) => s.to_string(),
+                    other => other.to_string(),
+                };
+
+                Err(SteelErr::new(ErrorKind::Generic, error_message)
+                    .with_irritants(message, args[1..].to_vec()))
             } else {
                 stop!(ArityMismatch => "error takes at least one argument");
             }
         })
     }
 }
+
+fn as_error(value: &SteelVal) -> Option<SteelErr> {
+    if let SteelVal::Custom(c) = value {
+        as_underlying_type::<SteelErr>(c.borrow().as_ref()).cloned()
+    } else {
+        None
+    }
+}
+
+/// Structs defined with `define-condition-type` carry this property, and store their
+/// message and irritants in their first two fields.
+fn condition_fields(value: &SteelVal) -> Option<(SteelVal, SteelVal)> {
+    if let SteelVal::CustomStruct(s) = value {
+        let key = SteelVal::SymbolV("#:prop:error-object".into());
+        if s.get(&key).map(|x| x.is_truthy()).unwrap_or(false) && s.fields.len() >= 2 {
+            return Some((s.fields[0].clone(), s.fields[1].clone()));
+        }
+    }
+
+    None
+}
+
+fn raised(value: &SteelVal) -> SteelErr {
+    as_error(value).unwrap_or_else(|| SteelErr::raised(value.clone()))
+}
+
+/// `(raise obj)` - raises `obj`, which does not have to be an error object.
+pub(crate) fn raise(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "raise expects 1 argument, found {}", args.len());
+    }
+
+    Err(raised(&args[0]))
+}
+
+/// `(#%raise-with-depth obj depth)` - raises `obj` on behalf of the exception handler at
+/// `depth`, so that handlers installed inside of it let the error through.
+pub(crate) fn raise_with_depth(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 2 {
+        stop!(ArityMismatch => "#%raise-with-depth expects 2 arguments, found {}", args.len());
+    }
+
+    let SteelVal::IntV(depth) = &args[1] else {
+        stop!(TypeMismatch => "#%raise-with-depth expects an integer depth, found {}", &args[1]);
+    };
+
+    let mut error = raised(&args[0]);
+    error.set_handler_depth(*depth as usize);
+    Err(error)
+}
+
+/// `(#%error-handler-depth err)` - the depth an error was raised at, or `#f` if it should
+/// be handled by the nearest handler.
+pub(crate) fn error_handler_depth(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "#%error-handler-depth expects 1 argument, found {}", args.len());
+    }
+
+    match as_error(&args[0]).and_then(|e| e.handler_depth()) {
+        Some(depth) => depth.into_steelval(),
+        None => Ok(SteelVal::BoolV(false)),
+    }
+}
+
+/// `(#%error->condition err)` - the object that was raised, given the error that
+/// carried it up the stack.
+pub(crate) fn error_to_condition(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "#%error->condition expects 1 argument, found {}", args.len());
+    }
+
+    Ok(as_error(&args[0])
+        .and_then(|e| e.raised_value().cloned())
+        .unwrap_or_else(|| args[0].clone()))
+}
+
+pub(crate) fn is_error_object(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "error-object? expects 1 argument, found {}", args.len());
+    }
+
+    Ok(SteelVal::BoolV(
+        as_error(&args[0]).is_some() || condition_fields(&args[0]).is_some(),
+    ))
+}
+
+pub(crate) fn error_object_message(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "error-object-message expects 1 argument, found {}", args.len());
+    }
+
+    if let Some(error) = as_error(&args[0]) {
+        Ok(SteelVal::StringV(error.message().trim_start().into()))
+    } else if let Some((message, _)) = condition_fields(&args[0]) {
+        Ok(message)
+    } else {
+        stop!(TypeMismatch => "error-object-message expects an error object, found {}", &args[0])
+    }
+}
+
+pub(crate) fn error_object_irritants(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "error-object-irritants expects 1 argument, found {}", args.len());
+    }
+
+    if let Some(error) = as_error(&args[0]) {
+        Ok(SteelVal::ListV(error.irritants().iter().cloned().collect()))
+    } else if let Some((_, irritants)) = condition_fields(&args[0]) {
+        Ok(irritants)
+    } else {
+        stop!(TypeMismatch => "error-object-irritants expects an error object, found {}", &args[0])
+    }
+}
+
+pub(crate) fn is_file_error(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "file-error? expects 1 argument, found {}", args.len());
+    }
+
+    Ok(SteelVal::BoolV(
+        as_error(&args[0]).map(|e| e.kind() == ErrorKind::Io) == Some(true),
+    ))
+}
+
+pub(crate) fn is_read_error(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "read-error? expects 1 argument, found {}", args.len());
+    }
+
+    Ok(SteelVal::BoolV(
+        as_error(&args[0]).map(|e| e.kind() == ErrorKind::Parse) == Some(true),
+    ))
+}
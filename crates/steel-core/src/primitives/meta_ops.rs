@@ -1,4 +1,5 @@
 use crate::rvals::{poll_future, Result, SteelVal};
+use crate::steel_vm::vm::VmCore;
 use crate::stop;
 use crate::{
     gc::{get_object_count, Gc},
@@ -112,42 +113,48 @@ impl MetaOperations {
     //     })
     // }
 
+    // Polling happens with the VM installed as the current context, so that futures from dylibs can
+    // call back into it while they're running
     pub fn poll_value() -> SteelVal {
-        SteelVal::FuncV(|args: &[SteelVal]| -> Result<SteelVal> {
-            if args.len() != 1 {
-                stop!(Generic => "poll! only takes one argument");
-            }
+        SteelVal::BuiltIn(|ctx: &mut VmCore<'_>, args: &[SteelVal]| {
+            Some(ctx.enter_context(|| {
+                if args.len() != 1 {
+                    stop!(Generic => "poll! only takes one argument");
+                }
 
-            if let SteelVal::FutureV(fut) = args[0].clone() {
-                let fut = fut.unwrap();
-                let ready = poll_future(fut.into_shared());
-                match ready {
-                    Some(v) => v,
-                    None => Ok(SteelVal::BoolV(false)),
+                if let SteelVal::FutureV(fut) = args[0].clone() {
+                    let fut = fut.unwrap();
+                    let ready = poll_future(fut.into_shared());
+                    match ready {
+                        Some(v) => v,
+                        None => Ok(SteelVal::BoolV(false)),
+                    }
+                } else {
+                    stop!(Generic => "poll! accepts futures only");
                 }
-            } else {
-                stop!(Generic => "poll! accepts futures only");
-            }
+            }))
         })
     }
 
     pub fn block_on() -> SteelVal {
-        SteelVal::FuncV(|args: &[SteelVal]| -> Result<SteelVal> {
-            if args.len() != 1 {
-                stop!(Generic => "block-on! only takes one argument");
-            }
+        SteelVal::BuiltIn(|ctx: &mut VmCore<'_>, args: &[SteelVal]| {
+            Some(ctx.enter_context(|| {
+                if args.len() != 1 {
+                    stop!(Generic => "block-on! only takes one argument");
+                }
 
-            if let SteelVal::FutureV(fut) = args[0].clone() {
-                loop {
-                    let fut = fut.unwrap();
-                    let ready = poll_future(fut.into_shared());
-                    if let Some(v) = ready {
-                        return v;
+                if let SteelVal::FutureV(fut) = args[0].clone() {
+                    loop {
+                        let fut = fut.unwrap();
+                        let ready = poll_future(fut.into_shared());
+                        if let Some(v) = ready {
+                            return v;
+                        }
                     }
+                } else {
+                    stop!(Generic => "block-on! accepts futures only");
                 }
-            } else {
-                stop!(Generic => "block-on! accepts futures only");
-            }
+            }))
         })
     }
 
@@ -1,7 +1,13 @@
-use crate::rvals::{IntoSteelVal, Result, SteelVal};
-use crate::steel_vm::primitives::numberp;
-use crate::stop;
-use num::{BigInt, BigRational, CheckedAdd, CheckedMul, Integer, Rational32, ToPrimitive};
+use crate::rvals::{IntoSteelVal, Result, SteelComplex, SteelVal};
+use crate::steel_vm::builtin::DocTemplate;
+use crate::steel_vm::primitives::{numberp, realp};
+use crate::steel_vm::vm::{values, VmCore};
+use crate::{builtin_stop, stop};
+use num::complex::Complex64;
+use num::{
+    BigInt, BigRational, CheckedAdd, CheckedMul, Integer, One, Rational32, Signed, ToPrimitive,
+    Zero,
+};
 use std::ops::Neg;
 
 fn ensure_args_are_numbers(op: &str, args: &[SteelVal]) -> Result<()> {
@@ -13,6 +19,60 @@ fn ensure_args_are_numbers(op: &str, args: &[SteelVal]) -> Result<()> {
     Ok(())
 }
 
+fn ensure_real(op: &str, value: &SteelVal) -> Result<()> {
+    if !realp(value) {
+        stop!(TypeMismatch => "{op} expects a real number, found: {}", value)
+    }
+    Ok(())
+}
+
+/// Converts a real number to the closest `f64`.
+pub(crate) fn real_to_f64(value: &SteelVal) -> Option<f64> {
+    match value {
+        SteelVal::IntV(i) => Some(*i as f64),
+        SteelVal::NumV(n) => Some(*n),
+        SteelVal::Rational(r) => r.to_f64(),
+        SteelVal::BigRational(r) => r.to_f64(),
+        SteelVal::BigNum(n) => n.to_f64(),
+        _ => None,
+    }
+}
+
+fn to_bigrational(value: &SteelVal) -> Option<BigRational> {
+    match value {
+        SteelVal::IntV(i) => Some(BigRational::from_integer(BigInt::from(*i))),
+        SteelVal::BigNum(n) => Some(BigRational::from_integer(n.as_ref().clone())),
+        SteelVal::Rational(r) => Some(BigRational::new(
+            BigInt::from(*r.numer()),
+            BigInt::from(*r.denom()),
+        )),
+        SteelVal::BigRational(r) => Some(r.as_ref().clone()),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_negative(value: &SteelVal) -> bool {
+    match value {
+        SteelVal::IntV(i) => *i < 0,
+        SteelVal::NumV(n) => *n < 0.0,
+        SteelVal::Rational(r) => r.is_negative(),
+        SteelVal::BigRational(r) => r.is_negative(),
+        SteelVal::BigNum(n) => n.is_negative(),
+        _ => false,
+    }
+}
+
+fn to_complex64(value: &SteelVal) -> Option<Complex64> {
+    match value {
+        SteelVal::Complex(z) => Some(Complex64::new(real_to_f64(&z.re)?, real_to_f64(&z.im)?)),
+        real => Some(Complex64::new(real_to_f64(real)?, 0.0)),
+    }
+}
+
+fn from_complex64(z: Complex64) -> Result<SteelVal> {
+    SteelComplex::new(SteelVal::NumV(z.re), SteelVal::NumV(z.im)).into_steelval()
+}
+
 /// Multiplies `x` and `y` without any type checking.
 ///
 /// # Precondition
@@ -86,11 +146,30 @@ fn multiply_unchecked(x: &SteelVal, y: &SteelVal) -> Result<SteelVal> {
         (SteelVal::BigRational(x), SteelVal::BigRational(y)) => {
             (x.as_ref() * y.as_ref()).into_steelval()
         }
+        (SteelVal::BigRational(x), SteelVal::Rational(y))
+        | (SteelVal::Rational(y), SteelVal::BigRational(x)) => (x.as_ref()
+            * BigRational::new(BigInt::from(*y.numer()), BigInt::from(*y.denom())))
+        .into_steelval(),
         (SteelVal::BigRational(x), SteelVal::BigNum(y))
         | (SteelVal::BigNum(y), SteelVal::BigRational(x)) => {
             (x.as_ref() * y.as_ref()).into_steelval()
         }
         (SteelVal::BigNum(x), SteelVal::BigNum(y)) => (x.as_ref() * y.as_ref()).into_steelval(),
+        (SteelVal::Complex(x), SteelVal::Complex(y)) => SteelComplex::new(
+            subtract_unchecked(
+                &multiply_unchecked(&x.re, &y.re)?,
+                &multiply_unchecked(&x.im, &y.im)?,
+            )?,
+            add_unchecked(
+                &multiply_unchecked(&x.re, &y.im)?,
+                &multiply_unchecked(&x.im, &y.re)?,
+            )?,
+        )
+        .into_steelval(),
+        (SteelVal::Complex(x), y) | (y, SteelVal::Complex(x)) => {
+            SteelComplex::new(multiply_unchecked(&x.re, y)?, multiply_unchecked(&x.im, y)?)
+                .into_steelval()
+        }
         _ => unreachable!(),
     }
 }
@@ -120,28 +199,38 @@ pub fn multiply_primitive(args: &[SteelVal]) -> Result<SteelVal> {
     multiply_primitive_impl(args)
 }
 
-pub fn quotient(l: isize, r: isize) -> isize {
-    l / r
+fn recip(x: &SteelVal) -> Result<SteelVal> {
+    match x {
+        SteelVal::IntV(0) => stop!(Generic => "/: division by zero"),
+        SteelVal::IntV(n) => match i32::try_from(*n) {
+            Ok(n) => Rational32::new(1, n).into_steelval(),
+            Err(_) => BigRational::new(BigInt::from(1), BigInt::from(*n)).into_steelval(),
+        },
+        SteelVal::NumV(n) => n.recip().into_steelval(),
+        SteelVal::Rational(f) => f.recip().into_steelval(),
+        SteelVal::BigRational(f) => f.recip().into_steelval(),
+        SteelVal::BigNum(n) => BigRational::new(1.into(), n.as_ref().clone()).into_steelval(),
+        // 1 / (a + bi) = (a - bi) / (a^2 + b^2)
+        SteelVal::Complex(z) => {
+            let scale = recip(&add_unchecked(
+                &multiply_unchecked(&z.re, &z.re)?,
+                &multiply_unchecked(&z.im, &z.im)?,
+            )?)?;
+            SteelComplex::new(
+                multiply_unchecked(&z.re, &scale)?,
+                multiply_unchecked(&negate_unchecked(&z.im)?, &scale)?,
+            )
+            .into_steelval()
+        }
+        unexpected => {
+            stop!(TypeMismatch => "/ expects a number, but found: {:?}", unexpected)
+        }
+    }
 }
 
 #[steel_derive::native(name = "/", constant = true, arity = "AtLeast(1)")]
 pub fn divide_primitive(args: &[SteelVal]) -> Result<SteelVal> {
     ensure_args_are_numbers("/", args)?;
-    let recip = |x: &SteelVal| -> Result<SteelVal> {
-        match x {
-            SteelVal::IntV(n) => match i32::try_from(*n) {
-                Ok(n) => Rational32::new(1, n).into_steelval(),
-                Err(_) => BigRational::new(BigInt::from(1), BigInt::from(*n)).into_steelval(),
-            },
-            SteelVal::NumV(n) => n.recip().into_steelval(),
-            SteelVal::Rational(f) => f.recip().into_steelval(),
-            SteelVal::BigRational(f) => f.recip().into_steelval(),
-            SteelVal::BigNum(n) => BigRational::new(1.into(), n.as_ref().clone()).into_steelval(),
-            unexpected => {
-                stop!(TypeMismatch => "/ expects a number, but found: {:?}", unexpected)
-            }
-        }
-    };
     match &args {
         [] => stop!(ArityMismatch => "/ requires at least one argument"),
         [x] => recip(x),
@@ -154,10 +243,12 @@ pub fn divide_primitive(args: &[SteelVal]) -> Result<SteelVal> {
     }
 }
 
-#[steel_derive::native(name = "-", constant = true, arity = "AtLeast(1)")]
-pub fn subtract_primitive(args: &[SteelVal]) -> Result<SteelVal> {
-    ensure_args_are_numbers("-", args)?;
-    let negate = |x: &SteelVal| match x {
+/// Negates `x` without any type checking.
+///
+/// # Precondition
+/// - `x` must be a valid numerical type.
+fn negate_unchecked(x: &SteelVal) -> Result<SteelVal> {
+    match x {
         SteelVal::NumV(x) => (-x).into_steelval(),
         SteelVal::IntV(x) => match x.checked_neg() {
             Some(res) => res.into_steelval(),
@@ -171,22 +262,40 @@ pub fn subtract_primitive(args: &[SteelVal]) -> Result<SteelVal> {
         },
         SteelVal::BigRational(x) => x.as_ref().neg().into_steelval(),
         SteelVal::BigNum(x) => x.as_ref().clone().neg().into_steelval(),
+        SteelVal::Complex(x) => {
+            SteelComplex::new(negate_unchecked(&x.re)?, negate_unchecked(&x.im)?).into_steelval()
+        }
         _ => unreachable!(),
-    };
+    }
+}
+
+/// Subtracts `y` from `x` without any type checking.
+///
+/// # Precondition
+/// - `x` and `y` must be valid numerical types.
+fn subtract_unchecked(x: &SteelVal, y: &SteelVal) -> Result<SteelVal> {
+    add_unchecked(x, &negate_unchecked(y)?)
+}
+
+#[steel_derive::native(name = "-", constant = true, arity = "AtLeast(1)")]
+pub fn subtract_primitive(args: &[SteelVal]) -> Result<SteelVal> {
+    ensure_args_are_numbers("-", args)?;
     match args {
         [] => stop!(TypeMismatch => "- requires at least one argument"),
-        [x] => negate(x),
+        [x] => negate_unchecked(x),
         [x, ys @ ..] => {
-            let y = negate(&add_primitive(ys)?)?;
+            let y = negate_unchecked(&add_primitive(ys)?)?;
             add_primitive(&[x.clone(), y])
         }
     }
 }
 
-#[steel_derive::native(name = "+", constant = true, arity = "AtLeast(0)")]
-pub fn add_primitive(args: &[SteelVal]) -> Result<SteelVal> {
-    ensure_args_are_numbers("+", args)?;
-    let add = |x: &SteelVal, y: &SteelVal| match (x, y) {
+/// Adds `x` and `y` without any type checking.
+///
+/// # Precondition
+/// - `x` and `y` must be valid numerical types.
+fn add_unchecked(x: &SteelVal, y: &SteelVal) -> Result<SteelVal> {
+    match (x, y) {
         // Simple integer case. Probably very common.
         (SteelVal::IntV(x), SteelVal::IntV(y)) => match x.checked_add(y) {
             Some(res) => res.into_steelval(),
@@ -212,7 +321,14 @@ pub fn add_primitive(args: &[SteelVal]) -> Result<SteelVal> {
             (x + y.to_f64().unwrap()).into_steelval()
         }
         // Cases that interact with `Rational`.
-        (SteelVal::Rational(x), SteelVal::Rational(y)) => (x + y).into_steelval(),
+        (SteelVal::Rational(x), SteelVal::Rational(y)) => match x.checked_add(y) {
+            Some(res) => res.into_steelval(),
+            None => {
+                let mut res = BigRational::new(BigInt::from(*x.numer()), BigInt::from(*x.denom()));
+                res += BigRational::new(BigInt::from(*y.numer()), BigInt::from(*y.denom()));
+                res.into_steelval()
+            }
+        },
         (SteelVal::Rational(x), SteelVal::IntV(y)) | (SteelVal::IntV(y), SteelVal::Rational(x)) => {
             match i32::try_from(*y) {
                 Ok(y) => match x.checked_add(&Rational32::new(y, 1)) {
@@ -220,13 +336,13 @@ pub fn add_primitive(args: &[SteelVal]) -> Result<SteelVal> {
                     None => {
                         let res =
                             BigRational::new(BigInt::from(*x.numer()), BigInt::from(*x.denom()))
-                                * BigInt::from(y);
+                                + BigInt::from(y);
                         res.into_steelval()
                     }
                 },
                 Err(_) => {
                     let res = BigRational::new(BigInt::from(*x.numer()), BigInt::from(*x.denom()))
-                        * BigInt::from(*y);
+                        + BigInt::from(*y);
                     res.into_steelval()
                 }
             }
@@ -234,7 +350,7 @@ pub fn add_primitive(args: &[SteelVal]) -> Result<SteelVal> {
         (SteelVal::Rational(x), SteelVal::BigNum(y))
         | (SteelVal::BigNum(y), SteelVal::Rational(x)) => {
             let res =
-                BigRational::new(BigInt::from(*x.numer()), BigInt::from(*x.denom())) * y.as_ref();
+                BigRational::new(BigInt::from(*x.numer()), BigInt::from(*x.denom())) + y.as_ref();
             res.into_steelval()
         }
         // Cases that interact with `BigRational`. For the sake of performance, hopefully not too
@@ -246,9 +362,13 @@ pub fn add_primitive(args: &[SteelVal]) -> Result<SteelVal> {
         | (SteelVal::IntV(y), SteelVal::BigRational(x)) => {
             (x.as_ref() + BigInt::from(*y)).into_steelval()
         }
+        (SteelVal::BigRational(x), SteelVal::Rational(y))
+        | (SteelVal::Rational(y), SteelVal::BigRational(x)) => (x.as_ref()
+            + BigRational::new(BigInt::from(*y.numer()), BigInt::from(*y.denom())))
+        .into_steelval(),
         (SteelVal::BigRational(x), SteelVal::BigNum(y))
         | (SteelVal::BigNum(y), SteelVal::BigRational(x)) => {
-            (x.as_ref() * y.as_ref()).into_steelval()
+            (x.as_ref() + y.as_ref()).into_steelval()
         }
         // Remaining cases that interact with `BigNum`. Probably not too common.
         (SteelVal::BigNum(x), SteelVal::BigNum(y)) => {
@@ -261,16 +381,29 @@ pub fn add_primitive(args: &[SteelVal]) -> Result<SteelVal> {
             res += *y;
             res.into_steelval()
         }
+        // Complex numbers add component-wise.
+        (SteelVal::Complex(x), SteelVal::Complex(y)) => {
+            SteelComplex::new(add_unchecked(&x.re, &y.re)?, add_unchecked(&x.im, &y.im)?)
+                .into_steelval()
+        }
+        (SteelVal::Complex(x), y) | (y, SteelVal::Complex(x)) => {
+            SteelComplex::new(add_unchecked(&x.re, y)?, x.im.clone()).into_steelval()
+        }
         _ => unreachable!(),
-    };
+    }
+}
+
+#[steel_derive::native(name = "+", constant = true, arity = "AtLeast(0)")]
+pub fn add_primitive(args: &[SteelVal]) -> Result<SteelVal> {
+    ensure_args_are_numbers("+", args)?;
     match args {
         [] => 0.into_steelval(),
         [x] => x.clone().into_steelval(),
-        [x, y] => add(x, y),
+        [x, y] => add_unchecked(x, y),
         [x, y, zs @ ..] => {
-            let mut res = add(x, y)?;
+            let mut res = add_unchecked(x, y)?;
             for z in zs {
-                res = add(&res, z)?;
+                res = add_unchecked(&res, z)?;
             }
             res.into_steelval()
         }
@@ -279,15 +412,951 @@ pub fn add_primitive(args: &[SteelVal]) -> Result<SteelVal> {
 
 #[steel_derive::function(name = "exact?", constant = true)]
 pub fn exactp(value: &SteelVal) -> bool {
-    matches!(
-        value,
-        SteelVal::IntV(_) | SteelVal::BigNum(_) | SteelVal::Rational(_) | SteelVal::BigRational(_)
-    )
+    match value {
+        SteelVal::IntV(_)
+        | SteelVal::BigNum(_)
+        | SteelVal::Rational(_)
+        | SteelVal::BigRational(_) => true,
+        SteelVal::Complex(z) => exactp(&z.re),
+        _ => false,
+    }
 }
 
 #[steel_derive::function(name = "inexact?", constant = true)]
 pub fn inexactp(value: &SteelVal) -> bool {
-    matches!(value, SteelVal::NumV(_))
+    match value {
+        SteelVal::NumV(_) => true,
+        SteelVal::Complex(z) => inexactp(&z.re),
+        _ => false,
+    }
+}
+
+/// Returns #t if the value is a complex number. Every number is a complex number, the real
+/// numbers being those with an imaginary part of zero.
+#[steel_derive::function(name = "complex?", constant = true)]
+pub fn complexp(value: &SteelVal) -> bool {
+    numberp(value)
+}
+
+/// Returns #t if the value is an exact integer.
+#[steel_derive::function(name = "exact-integer?", constant = true)]
+pub fn exact_integerp(value: &SteelVal) -> bool {
+    matches!(value, SteelVal::IntV(_) | SteelVal::BigNum(_))
+}
+
+/// Returns #t if the value is a NaN.
+#[steel_derive::function(name = "nan?", constant = true)]
+pub fn nanp(value: &SteelVal) -> Result<SteelVal> {
+    match value {
+        SteelVal::NumV(n) => Ok(SteelVal::BoolV(n.is_nan())),
+        SteelVal::Complex(z) => Ok(SteelVal::BoolV(
+            nanp(&z.re)?.is_truthy() || nanp(&z.im)?.is_truthy(),
+        )),
+        _ if numberp(value) => Ok(SteelVal::BoolV(false)),
+        _ => stop!(TypeMismatch => "nan? expects a number, found: {}", value),
+    }
+}
+
+/// Returns #t if the value is positive or negative infinity.
+#[steel_derive::function(name = "infinite?", constant = true)]
+pub fn infinitep(value: &SteelVal) -> Result<SteelVal> {
+    match value {
+        SteelVal::NumV(n) => Ok(SteelVal::BoolV(n.is_infinite())),
+        SteelVal::Complex(z) => Ok(SteelVal::BoolV(
+            infinitep(&z.re)?.is_truthy() || infinitep(&z.im)?.is_truthy(),
+        )),
+        _ if numberp(value) => Ok(SteelVal::BoolV(false)),
+        _ => stop!(TypeMismatch => "infinite? expects a number, found: {}", value),
+    }
+}
+
+/// Returns #t if the value is neither infinite nor a NaN.
+#[steel_derive::function(name = "finite?", constant = true)]
+pub fn finitep(value: &SteelVal) -> Result<SteelVal> {
+    Ok(SteelVal::BoolV(
+        !nanp(value)?.is_truthy() && !infinitep(value)?.is_truthy(),
+    ))
+}
+
+/// Makes the complex number `real + imag * i`.
+///
+/// (make-rectangular real imag) -> number?
+///
+/// ```scheme
+/// (make-rectangular 1 2) ;; => 1+2i
+/// (make-rectangular 1.5 0) ;; => 1.5
+/// ```
+#[steel_derive::function(name = "make-rectangular", constant = true)]
+pub fn make_rectangular(real: &SteelVal, imag: &SteelVal) -> Result<SteelVal> {
+    ensure_real("make-rectangular", real)?;
+    ensure_real("make-rectangular", imag)?;
+    SteelComplex::new(real.clone(), imag.clone()).into_steelval()
+}
+
+/// Makes the complex number with the given magnitude and angle, in radians.
+///
+/// (make-polar magnitude angle) -> number?
+#[steel_derive::function(name = "make-polar", constant = true)]
+pub fn make_polar(magnitude: &SteelVal, angle: &SteelVal) -> Result<SteelVal> {
+    ensure_real("make-polar", magnitude)?;
+    ensure_real("make-polar", angle)?;
+
+    if let SteelVal::IntV(0) = angle {
+        return Ok(magnitude.clone());
+    }
+
+    from_complex64(Complex64::from_polar(
+        real_to_f64(magnitude).unwrap(),
+        real_to_f64(angle).unwrap(),
+    ))
+}
+
+/// Returns the real part of a number.
+#[steel_derive::function(name = "real-part", constant = true)]
+pub fn real_part(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::Complex(z) => Ok(z.re.clone()),
+        _ if realp(number) => Ok(number.clone()),
+        _ => stop!(TypeMismatch => "real-part expects a number, found: {}", number),
+    }
+}
+
+/// Returns the imaginary part of a number, which is 0 for real numbers.
+#[steel_derive::function(name = "imag-part", constant = true)]
+pub fn imag_part(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::Complex(z) => Ok(z.im.clone()),
+        _ if realp(number) => Ok(SteelVal::IntV(0)),
+        _ => stop!(TypeMismatch => "imag-part expects a number, found: {}", number),
+    }
+}
+
+/// Returns the magnitude of a number, which is its absolute value for real numbers.
+#[steel_derive::function(name = "magnitude", constant = true)]
+pub fn magnitude(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::Complex(z) => sqrt(&add_unchecked(
+            &multiply_unchecked(&z.re, &z.re)?,
+            &multiply_unchecked(&z.im, &z.im)?,
+        )?),
+        _ if is_negative(number) => negate_unchecked(number),
+        _ if realp(number) => Ok(number.clone()),
+        _ => stop!(TypeMismatch => "magnitude expects a number, found: {}", number),
+    }
+}
+
+/// Returns the angle of a number in radians, which is 0 for positive real numbers and pi for
+/// negative ones.
+#[steel_derive::function(name = "angle", constant = true)]
+pub fn angle(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::Complex(_) => Ok(SteelVal::NumV(to_complex64(number).unwrap().arg())),
+        SteelVal::NumV(n) => Ok(SteelVal::NumV(0f64.atan2(*n))),
+        _ if is_negative(number) => Ok(SteelVal::NumV(std::f64::consts::PI)),
+        _ if realp(number) => Ok(SteelVal::IntV(0)),
+        _ => stop!(TypeMismatch => "angle expects a number, found: {}", number),
+    }
+}
+
+/// Converts a number to an exact number, raising an error for infinities and NaN.
+///
+/// (exact z) -> exact?
+///
+/// ```scheme
+/// (exact 2.5) ;; => 5/2
+/// (exact 1.0) ;; => 1
+/// ```
+#[steel_derive::function(name = "exact", constant = true)]
+pub fn exact(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::IntV(_)
+        | SteelVal::BigNum(_)
+        | SteelVal::Rational(_)
+        | SteelVal::BigRational(_) => Ok(number.clone()),
+        SteelVal::NumV(n) => match BigRational::from_float(*n) {
+            Some(r) => r.into_steelval(),
+            None => stop!(ContractViolation => "exact: {} has no exact representation", number),
+        },
+        SteelVal::Complex(z) => SteelComplex::new(exact(&z.re)?, exact(&z.im)?).into_steelval(),
+        _ => stop!(TypeMismatch => "exact expects a number, found: {}", number),
+    }
+}
+
+/// Converts a number to an exact number. Alias of `exact`.
+#[steel_derive::function(name = "inexact->exact", constant = true)]
+pub fn inexact_to_exact(number: &SteelVal) -> Result<SteelVal> {
+    exact(number)
+}
+
+/// Converts a number to the closest inexact number.
+///
+/// (inexact z) -> inexact?
+///
+/// ```scheme
+/// (inexact 1/4) ;; => 0.25
+/// ```
+#[steel_derive::function(name = "inexact", constant = true)]
+pub fn inexact(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::Complex(z) => SteelComplex::new(inexact(&z.re)?, inexact(&z.im)?).into_steelval(),
+        _ => match real_to_f64(number) {
+            Some(n) => Ok(SteelVal::NumV(n)),
+            None => stop!(TypeMismatch => "inexact expects a number, found: {}", number),
+        },
+    }
+}
+
+/// Hands a pair of results to the continuation as two values, the way `values` would.
+fn two_values(ctx: &mut VmCore, result: Result<(SteelVal, SteelVal)>) -> Option<Result<SteelVal>> {
+    match result {
+        Ok((first, second)) => values(ctx, &[first, second]),
+        Err(e) => Some(Err(e)),
+    }
+}
+
+pub(crate) const EXACT_INTEGER_SQRT_DOC: DocTemplate<'static> = DocTemplate {
+    signature: "(exact-integer-sqrt k) -> (values exact-integer? exact-integer?)",
+    params: &["k : exact-nonnegative-integer?"],
+    description:
+        "Returns the largest integer `s` such that `s * s <= k`, and `k - s * s`, as two values.",
+    examples: &[("> (exact-integer-sqrt 17)", "(values 4 1)")],
+};
+
+pub(crate) fn exact_integer_sqrt(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>> {
+    if args.len() != 1 {
+        builtin_stop!(ArityMismatch => "exact-integer-sqrt expected 1 argument, found {}", args.len());
+    }
+
+    two_values(ctx, integer_sqrt(&args[0]))
+}
+
+fn integer_sqrt(number: &SteelVal) -> Result<(SteelVal, SteelVal)> {
+    let n = match number {
+        SteelVal::IntV(n) if *n >= 0 => BigInt::from(*n),
+        SteelVal::BigNum(n) if !n.is_negative() => n.as_ref().clone(),
+        _ => {
+            stop!(TypeMismatch => "exact-integer-sqrt expects a non-negative exact integer, found: {}", number)
+        }
+    };
+
+    let root = n.sqrt();
+    let rest = &n - &root * &root;
+
+    Ok((root.into_steelval()?, rest.into_steelval()?))
+}
+
+/// Returns the principal square root of a number. Exact numbers with exact square roots have
+/// them returned as such, and the roots of negative numbers are imaginary.
+///
+/// (sqrt z) -> number?
+///
+/// ```scheme
+/// (sqrt 16) ;; => 4
+/// (sqrt 1/4) ;; => 1/2
+/// (sqrt -4) ;; => +2i
+/// (sqrt 2) ;; => 1.4142135623730951
+/// ```
+#[steel_derive::function(name = "sqrt", constant = true)]
+pub fn sqrt(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::NumV(n) if *n < 0.0 => {
+            SteelComplex::new(SteelVal::NumV(0.0), SteelVal::NumV((-n).sqrt())).into_steelval()
+        }
+        SteelVal::NumV(n) => Ok(SteelVal::NumV(n.sqrt())),
+        SteelVal::Complex(_) => from_complex64(to_complex64(number).unwrap().sqrt()),
+        _ => {
+            let Some(r) = to_bigrational(number) else {
+                stop!(TypeMismatch => "sqrt expects a number, found: {}", number)
+            };
+
+            let numer = r.numer().abs();
+            let denom = r.denom();
+            let (numer_root, denom_root) = (numer.sqrt(), denom.sqrt());
+
+            let root = if &numer_root * &numer_root == numer && &denom_root * &denom_root == *denom
+            {
+                BigRational::new(numer_root, denom_root).into_steelval()?
+            } else {
+                SteelVal::NumV(r.abs().to_f64().unwrap().sqrt())
+            };
+
+            if r.is_negative() {
+                SteelComplex::new(SteelVal::IntV(0), root).into_steelval()
+            } else {
+                Ok(root)
+            }
+        }
+    }
+}
+
+/// Applies a real function to a real number, or its complex counterpart to a complex number.
+fn transcendental(
+    op: &str,
+    number: &SteelVal,
+    real: fn(f64) -> f64,
+    complex: fn(Complex64) -> Complex64,
+) -> Result<SteelVal> {
+    match number {
+        SteelVal::Complex(_) => from_complex64(complex(to_complex64(number).unwrap())),
+        _ => match real_to_f64(number) {
+            Some(n) => Ok(SteelVal::NumV(real(n))),
+            None => stop!(TypeMismatch => "{} expects a number, found: {}", op, number),
+        },
+    }
+}
+
+/// Returns Euler's number raised to the power of z.
+#[steel_derive::function(name = "exp", constant = true)]
+pub fn exp(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::IntV(0) => Ok(SteelVal::IntV(1)),
+        _ => transcendental("exp", number, f64::exp, |z| z.exp()),
+    }
+}
+
+fn natural_log(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::IntV(1) => Ok(SteelVal::IntV(0)),
+        _ if is_negative(number) => {
+            from_complex64(Complex64::new(real_to_f64(number).unwrap(), 0.0).ln())
+        }
+        _ => transcendental("log", number, f64::ln, |z| z.ln()),
+    }
+}
+
+/// Returns the natural logarithm of z, or its logarithm in the given base.
+///
+/// (log z [base]) -> number?
+///
+/// ```scheme
+/// (log 1) ;; => 0
+/// (log 100 10) ;; => 2.0
+/// (log -1) ;; => 0.0+3.141592653589793i
+/// ```
+#[steel_derive::native(name = "log", arity = "AtLeast(1)")]
+pub fn log(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() > 2 {
+        stop!(ArityMismatch => "log expects one or two arguments, found: {}", args.len());
+    }
+
+    let first = &args[0];
+    let Some(base) = args.get(1) else {
+        return natural_log(first);
+    };
+
+    if let SteelVal::IntV(1) = base {
+        stop!(Generic => "log: divide by zero with args: {} and {}", first, base);
+    }
+
+    // Logarithms in another base are always inexact, even for exact powers of the base
+    match (
+        inexact(&natural_log(first)?)?,
+        inexact(&natural_log(base)?)?,
+    ) {
+        (SteelVal::NumV(x), SteelVal::NumV(y)) => Ok(SteelVal::NumV(x / y)),
+        (x, y) => divide_primitive(&[x, y]),
+    }
+}
+
+/// Returns the sine of z, in radians.
+#[steel_derive::function(name = "sin", constant = true)]
+pub fn sin(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::IntV(0) => Ok(SteelVal::IntV(0)),
+        _ => transcendental("sin", number, f64::sin, |z| z.sin()),
+    }
+}
+
+/// Returns the cosine of z, in radians.
+#[steel_derive::function(name = "cos", constant = true)]
+pub fn cos(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::IntV(0) => Ok(SteelVal::IntV(1)),
+        _ => transcendental("cos", number, f64::cos, |z| z.cos()),
+    }
+}
+
+/// Returns the tangent of z, in radians.
+#[steel_derive::function(name = "tan", constant = true)]
+pub fn tan(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::IntV(0) => Ok(SteelVal::IntV(0)),
+        _ => transcendental("tan", number, f64::tan, |z| z.tan()),
+    }
+}
+
+/// Returns the arcsine of z, which is complex for real numbers outside of [-1, 1].
+#[steel_derive::function(name = "asin", constant = true)]
+pub fn asin(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::IntV(0) => Ok(SteelVal::IntV(0)),
+        _ if real_to_f64(number).is_some_and(|n| n.abs() > 1.0) => {
+            from_complex64(to_complex64(number).unwrap().asin())
+        }
+        _ => transcendental("asin", number, f64::asin, |z| z.asin()),
+    }
+}
+
+/// Returns the arccosine of z, which is complex for real numbers outside of [-1, 1].
+#[steel_derive::function(name = "acos", constant = true)]
+pub fn acos(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::IntV(1) => Ok(SteelVal::IntV(0)),
+        _ if real_to_f64(number).is_some_and(|n| n.abs() > 1.0) => {
+            from_complex64(to_complex64(number).unwrap().acos())
+        }
+        _ => transcendental("acos", number, f64::acos, |z| z.acos()),
+    }
+}
+
+/// Returns the arctangent of z, or with two arguments, the angle of the point `(x, y)`.
+///
+/// (atan z) -> number?
+/// (atan y x) -> real?
+#[steel_derive::native(name = "atan", arity = "AtLeast(1)")]
+pub fn atan(args: &[SteelVal]) -> Result<SteelVal> {
+    match args {
+        [SteelVal::IntV(0)] => Ok(SteelVal::IntV(0)),
+        [z] => transcendental("atan", z, f64::atan, |z| z.atan()),
+        [y, x] => {
+            ensure_real("atan", y)?;
+            ensure_real("atan", x)?;
+
+            if let SteelVal::IntV(0) = y {
+                if !is_negative(x) && exactp(x) {
+                    return Ok(SteelVal::IntV(0));
+                }
+            }
+
+            Ok(SteelVal::NumV(
+                real_to_f64(y).unwrap().atan2(real_to_f64(x).unwrap()),
+            ))
+        }
+        _ => stop!(ArityMismatch => "atan expects one or two arguments, found: {}", args.len()),
+    }
+}
+
+/// Raises a number to a power for the cases where the result can be complex.
+pub(crate) fn complex_expt(base: &SteelVal, exponent: &SteelVal) -> Result<SteelVal> {
+    match (to_complex64(base), to_complex64(exponent)) {
+        (_, _) if matches!(exponent, SteelVal::IntV(0)) => Ok(SteelVal::IntV(1)),
+        (Some(base), Some(exponent)) => from_complex64(base.powc(exponent)),
+        _ => stop!(TypeMismatch => "expt expected two numbers, found: {} and {}", base, exponent),
+    }
+}
+
+/// Returns the largest integer not greater than the given number.
+#[steel_derive::function(name = "floor", constant = true)]
+pub fn floor(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::IntV(_) | SteelVal::BigNum(_) => Ok(number.clone()),
+        SteelVal::NumV(n) => Ok(SteelVal::NumV(n.floor())),
+        SteelVal::Rational(r) => r.floor().into_steelval(),
+        SteelVal::BigRational(r) => r.floor().into_steelval(),
+        _ => stop!(TypeMismatch => "floor expects a real number, found: {}", number),
+    }
+}
+
+/// Returns the smallest integer not less than the given number.
+#[steel_derive::function(name = "ceiling", constant = true)]
+pub fn ceiling(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::IntV(_) | SteelVal::BigNum(_) => Ok(number.clone()),
+        SteelVal::NumV(n) => Ok(SteelVal::NumV(n.ceil())),
+        SteelVal::Rational(r) => r.ceil().into_steelval(),
+        SteelVal::BigRational(r) => r.ceil().into_steelval(),
+        _ => stop!(TypeMismatch => "ceiling expects a real number, found: {}", number),
+    }
+}
+
+/// Returns the integer closest to the given number whose absolute value is not larger.
+#[steel_derive::function(name = "truncate", constant = true)]
+pub fn truncate(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::IntV(_) | SteelVal::BigNum(_) => Ok(number.clone()),
+        SteelVal::NumV(n) => Ok(SteelVal::NumV(n.trunc())),
+        SteelVal::Rational(r) => r.trunc().into_steelval(),
+        SteelVal::BigRational(r) => r.trunc().into_steelval(),
+        _ => stop!(TypeMismatch => "truncate expects a real number, found: {}", number),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Rounding {
+    Floor,
+    Truncate,
+}
+
+/// Divides the integers `n` and `d`, rounding the quotient as given, and returns the quotient
+/// and the remainder. The results are inexact if either argument is.
+fn integer_division(
+    op: &str,
+    n: &SteelVal,
+    d: &SteelVal,
+    rounding: Rounding,
+) -> Result<(SteelVal, SteelVal)> {
+    let to_bigint = |x: &SteelVal| match x {
+        SteelVal::IntV(x) => BigInt::from(*x),
+        SteelVal::BigNum(x) => x.as_ref().clone(),
+        _ => unreachable!(),
+    };
+
+    match (n, d) {
+        (_, SteelVal::IntV(0)) => stop!(Generic => "{}: division by zero", op),
+        (SteelVal::IntV(n), SteelVal::IntV(d)) if n.checked_div(*d).is_some() => {
+            let (q, r) = match rounding {
+                Rounding::Floor => n.div_mod_floor(d),
+                Rounding::Truncate => n.div_rem(d),
+            };
+            Ok((SteelVal::IntV(q), SteelVal::IntV(r)))
+        }
+        (SteelVal::IntV(_) | SteelVal::BigNum(_), SteelVal::IntV(_) | SteelVal::BigNum(_)) => {
+            let (n, d) = (to_bigint(n), to_bigint(d));
+            let (q, r) = match rounding {
+                Rounding::Floor => n.div_mod_floor(&d),
+                Rounding::Truncate => n.div_rem(&d),
+            };
+            Ok((q.into_steelval()?, r.into_steelval()?))
+        }
+        _ => {
+            let integral = |x: &SteelVal| match x {
+                SteelVal::NumV(x) if x.fract() == 0.0 => Some(*x),
+                SteelVal::IntV(_) | SteelVal::BigNum(_) => real_to_f64(x),
+                _ => None,
+            };
+
+            let (Some(n), Some(d)) = (integral(n), integral(d)) else {
+                stop!(TypeMismatch => "{} expects two integers, found: {} and {}", op, n, d)
+            };
+
+            if d == 0.0 {
+                stop!(Generic => "{}: division by zero", op)
+            }
+
+            let mut r = n % d;
+            if let Rounding::Floor = rounding {
+                if r != 0.0 && (r < 0.0) != (d < 0.0) {
+                    r += d;
+                }
+            }
+
+            Ok((SteelVal::NumV(((n - r) / d).round()), SteelVal::NumV(r)))
+        }
+    }
+}
+
+pub(crate) const FLOOR_DIVISION_DOC: DocTemplate<'static> = DocTemplate {
+    signature: "(floor/ n1 n2) -> (values integer? integer?)",
+    params: &["n1 : integer?", "n2 : integer?"],
+    description: "Returns the quotient and remainder of dividing `n1` by `n2`, rounding the quotient towards negative infinity, as two values.",
+    examples: &[("> (floor/ -5 2)", "(values -3 1)")],
+};
+
+pub(crate) fn floor_division(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>> {
+    if args.len() != 2 {
+        builtin_stop!(ArityMismatch => "floor/ expected 2 arguments, found {}", args.len());
+    }
+
+    two_values(
+        ctx,
+        integer_division("floor/", &args[0], &args[1], Rounding::Floor),
+    )
+}
+
+/// Returns the quotient of dividing `n1` by `n2`, rounded towards negative infinity.
+#[steel_derive::function(name = "floor-quotient", constant = true)]
+pub fn floor_quotient(n1: &SteelVal, n2: &SteelVal) -> Result<SteelVal> {
+    Ok(integer_division("floor-quotient", n1, n2, Rounding::Floor)?.0)
+}
+
+/// Returns the remainder of dividing `n1` by `n2`, which has the sign of `n2`.
+#[steel_derive::function(name = "floor-remainder", constant = true)]
+pub fn floor_remainder(n1: &SteelVal, n2: &SteelVal) -> Result<SteelVal> {
+    Ok(integer_division("floor-remainder", n1, n2, Rounding::Floor)?.1)
+}
+
+pub(crate) const TRUNCATE_DIVISION_DOC: DocTemplate<'static> = DocTemplate {
+    signature: "(truncate/ n1 n2) -> (values integer? integer?)",
+    params: &["n1 : integer?", "n2 : integer?"],
+    description: "Returns the quotient and remainder of dividing `n1` by `n2`, rounding the quotient towards zero, as two values.",
+    examples: &[("> (truncate/ -5 2)", "(values -2 -1)")],
+};
+
+pub(crate) fn truncate_division(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>> {
+    if args.len() != 2 {
+        builtin_stop!(ArityMismatch => "truncate/ expected 2 arguments, found {}", args.len());
+    }
+
+    two_values(
+        ctx,
+        integer_division("truncate/", &args[0], &args[1], Rounding::Truncate),
+    )
+}
+
+/// Returns the quotient of dividing `n1` by `n2`, rounded towards zero.
+#[steel_derive::function(name = "truncate-quotient", constant = true)]
+pub fn truncate_quotient(n1: &SteelVal, n2: &SteelVal) -> Result<SteelVal> {
+    Ok(integer_division("truncate-quotient", n1, n2, Rounding::Truncate)?.0)
+}
+
+/// Returns the remainder of dividing `n1` by `n2`, which has the sign of `n1`.
+#[steel_derive::function(name = "truncate-remainder", constant = true)]
+pub fn truncate_remainder(n1: &SteelVal, n2: &SteelVal) -> Result<SteelVal> {
+    Ok(integer_division("truncate-remainder", n1, n2, Rounding::Truncate)?.1)
+}
+
+/// Returns the quotient of dividing `n1` by `n2`, rounded towards zero. Same as
+/// `truncate-quotient`.
+#[steel_derive::function(name = "quotient", constant = true)]
+pub fn quotient(n1: &SteelVal, n2: &SteelVal) -> Result<SteelVal> {
+    Ok(integer_division("quotient", n1, n2, Rounding::Truncate)?.0)
+}
+
+/// Returns the remainder of dividing `n1` by `n2`. Same as `truncate-remainder`.
+#[steel_derive::function(name = "remainder", constant = true)]
+pub fn remainder(n1: &SteelVal, n2: &SteelVal) -> Result<SteelVal> {
+    Ok(integer_division("remainder", n1, n2, Rounding::Truncate)?.1)
+}
+
+/// Returns `n1` modulo `n2`. Same as `floor-remainder`.
+#[steel_derive::function(name = "modulo", constant = true)]
+pub fn modulo(n1: &SteelVal, n2: &SteelVal) -> Result<SteelVal> {
+    Ok(integer_division("modulo", n1, n2, Rounding::Floor)?.1)
+}
+
+/// Returns the numerator of a rational number, in lowest terms.
+///
+/// ```scheme
+/// (numerator 6/4) ;; => 3
+/// (numerator 0.5) ;; => 1.0
+/// ```
+#[steel_derive::function(name = "numerator", constant = true)]
+pub fn numerator(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::IntV(_) | SteelVal::BigNum(_) => Ok(number.clone()),
+        SteelVal::Rational(r) => Ok(SteelVal::IntV(*r.numer() as isize)),
+        SteelVal::BigRational(r) => r.numer().clone().into_steelval(),
+        SteelVal::NumV(_) => inexact(&numerator(&exact(number)?)?),
+        _ => stop!(TypeMismatch => "numerator expects a rational number, found: {}", number),
+    }
+}
+
+/// Returns the denominator of a rational number, in lowest terms.
+///
+/// ```scheme
+/// (denominator 6/4) ;; => 2
+/// (denominator 0.5) ;; => 2.0
+/// ```
+#[steel_derive::function(name = "denominator", constant = true)]
+pub fn denominator(number: &SteelVal) -> Result<SteelVal> {
+    match number {
+        SteelVal::IntV(_) | SteelVal::BigNum(_) => Ok(SteelVal::IntV(1)),
+        SteelVal::Rational(r) => Ok(SteelVal::IntV(*r.denom() as isize)),
+        SteelVal::BigRational(r) => r.denom().clone().into_steelval(),
+        SteelVal::NumV(_) => inexact(&denominator(&exact(number)?)?),
+        _ => stop!(TypeMismatch => "denominator expects a rational number, found: {}", number),
+    }
+}
+
+/// The simplest rational in `[low, high]`, given `0 < low <= high`.
+fn simplest_positive_rational(low: &BigRational, high: &BigRational) -> BigRational {
+    let floor = low.floor();
+
+    if floor == *low {
+        floor
+    } else if floor < high.floor() {
+        floor + BigRational::one()
+    } else {
+        let rest = simplest_positive_rational(&(high - &floor).recip(), &(low - &floor).recip());
+        floor + rest.recip()
+    }
+}
+
+fn simplest_rational(low: &BigRational, high: &BigRational) -> BigRational {
+    if low.is_positive() {
+        simplest_positive_rational(low, high)
+    } else if high.is_negative() {
+        -simplest_positive_rational(&-high, &-low)
+    } else {
+        BigRational::zero()
+    }
+}
+
+/// Returns the simplest rational number differing from `x` by no more than `y`.
+///
+/// (rationalize x y) -> rational?
+///
+/// ```scheme
+/// (rationalize 3/10 1/10) ;; => 1/3
+/// (rationalize 0.3 1/10) ;; => 0.3333333333333333
+/// ```
+#[steel_derive::function(name = "rationalize", constant = true)]
+pub fn rationalize(x: &SteelVal, y: &SteelVal) -> Result<SteelVal> {
+    ensure_real("rationalize", x)?;
+    ensure_real("rationalize", y)?;
+
+    let inexact_result = matches!(x, SteelVal::NumV(_)) || matches!(y, SteelVal::NumV(_));
+
+    if inexact_result {
+        let (xf, yf) = (real_to_f64(x).unwrap(), real_to_f64(y).unwrap());
+
+        if xf.is_nan() || yf.is_nan() || (xf.is_infinite() && yf.is_infinite()) {
+            return Ok(SteelVal::NumV(f64::NAN));
+        } else if yf.is_infinite() {
+            return Ok(SteelVal::NumV(0.0));
+        } else if xf.is_infinite() {
+            return Ok(SteelVal::NumV(xf));
+        }
+    }
+
+    let x = to_bigrational(&exact(x)?).unwrap();
+    let y = to_bigrational(&exact(y)?).unwrap().abs();
+    let result = simplest_rational(&(&x - &y), &(&x + &y)).into_steelval()?;
+
+    if inexact_result {
+        inexact(&result)
+    } else {
+        Ok(result)
+    }
+}
+
+/// Writes a finite flonum in a radix other than 10, with as many digits after the point as it
+/// takes for `string->number` to read the same flonum back.
+fn format_flonum_radix(n: f64, radix: u32) -> String {
+    let sign = if n.is_sign_negative() { "-" } else { "" };
+    let magnitude = n.abs();
+    let exact = BigRational::from_float(magnitude).unwrap();
+    let radix_big = BigInt::from(radix);
+
+    let mut scale = BigInt::one();
+    let mut places = 0;
+    loop {
+        scale *= &radix_big;
+        places += 1;
+
+        let digits = (&exact * &scale).round().to_integer();
+        if BigRational::new(digits.clone(), scale.clone()).to_f64() == Some(magnitude) {
+            let (whole, fraction) = digits.div_rem(&scale);
+            return format!(
+                "{sign}{}.{:0>places$}",
+                whole.to_str_radix(radix),
+                fraction.to_str_radix(radix)
+            );
+        }
+    }
+}
+
+fn format_real(number: &SteelVal, radix: u32) -> Result<String> {
+    match number {
+        SteelVal::NumV(n) if n.is_nan() => Ok("+nan.0".to_string()),
+        SteelVal::NumV(n) if n.is_infinite() => {
+            Ok(if *n > 0.0 { "+inf.0" } else { "-inf.0" }.to_string())
+        }
+        SteelVal::NumV(n) if radix != 10 => Ok(format_flonum_radix(*n, radix)),
+        SteelVal::NumV(n) => Ok(format!("{n:?}")),
+        SteelVal::IntV(n) => Ok(BigInt::from(*n).to_str_radix(radix)),
+        SteelVal::BigNum(n) => Ok(n.to_str_radix(radix)),
+        _ => match to_bigrational(number) {
+            Some(r) => Ok(format!(
+                "{}/{}",
+                r.numer().to_str_radix(radix),
+                r.denom().to_str_radix(radix)
+            )),
+            None => stop!(TypeMismatch => "number->string expects a number, found: {}", number),
+        },
+    }
+}
+
+/// Writes a number in the given radix, in a form that `string->number` reads back.
+pub(crate) fn format_number(number: &SteelVal, radix: u32) -> Result<String> {
+    match number {
+        SteelVal::Complex(z) => {
+            let re = match &z.re {
+                SteelVal::IntV(0) => String::new(),
+                re => format_real(re, radix)?,
+            };
+            let im = match &z.im {
+                SteelVal::IntV(1) => "+".to_string(),
+                SteelVal::IntV(-1) => "-".to_string(),
+                im => format_real(im, radix)?,
+            };
+
+            if im.starts_with('-') || im.starts_with('+') {
+                Ok(format!("{re}{im}i"))
+            } else {
+                Ok(format!("{re}+{im}i"))
+            }
+        }
+        _ => format_real(number, radix),
+    }
+}
+
+/// Parses an unsigned integer, fraction or decimal. Decimals are only allowed in radix 10,
+/// and are read exactly if `exact` is set.
+fn parse_unsigned_real(text: &str, radix: u32, exact: bool) -> Option<SteelVal> {
+    let digits = |text: &str| {
+        if !text.is_empty() && text.chars().all(|c| c.is_digit(radix)) {
+            BigInt::parse_bytes(text.as_bytes(), radix)
+        } else {
+            None
+        }
+    };
+
+    if let Some((numer, denom)) = text.split_once('/') {
+        let (numer, denom) = (digits(numer)?, digits(denom)?);
+        if denom.is_zero() {
+            return None;
+        }
+        return BigRational::new(numer, denom).into_steelval().ok();
+    }
+
+    if let Some(n) = digits(text) {
+        return n.into_steelval().ok();
+    }
+
+    if radix != 10 {
+        // There's no exponent marker outside of radix 10, since `e` is a hexadecimal digit
+        let (whole, fraction) = text.split_once('.')?;
+        let significand = digits(&format!("{whole}{fraction}"))?;
+        let scale = BigInt::from(radix).pow(u32::try_from(fraction.len()).ok()?);
+        let value = BigRational::new(significand, scale);
+
+        return if exact {
+            value.into_steelval().ok()
+        } else {
+            value.to_f64().map(SteelVal::NumV)
+        };
+    }
+
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(i) => (&text[..i], Some(&text[i + 1..])),
+        None => (text, None),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let is_digits = |text: &str| text.chars().all(|c| c.is_ascii_digit());
+    if !is_digits(whole) || !is_digits(fraction) || whole.len() + fraction.len() == 0 {
+        return None;
+    }
+
+    let exponent = match exponent {
+        Some(exponent) => {
+            let unsigned = exponent.trim_start_matches(['+', '-']);
+            if unsigned.is_empty() || !is_digits(unsigned) || exponent.len() - unsigned.len() > 1 {
+                return None;
+            }
+            exponent.parse::<i64>().ok()?
+        }
+        None => 0,
+    };
+
+    if !exact {
+        return text.parse::<f64>().ok().map(SteelVal::NumV);
+    }
+
+    let significand = BigInt::parse_bytes(format!("{whole}{fraction}").as_bytes(), 10)?;
+    let exponent = exponent - fraction.len() as i64;
+    let scale = BigInt::from(10).pow(u32::try_from(exponent.unsigned_abs()).ok()?);
+
+    let value = if exponent < 0 {
+        BigRational::new(significand, scale)
+    } else {
+        BigRational::from_integer(significand * scale)
+    };
+
+    value.into_steelval().ok()
+}
+
+fn parse_real(text: &str, radix: u32, exact: bool) -> Option<SteelVal> {
+    let (negative, unsigned) = match text.as_bytes().first()? {
+        b'+' => (false, &text[1..]),
+        b'-' => (true, &text[1..]),
+        _ => (false, text),
+    };
+
+    let value = if unsigned.len() != text.len() && unsigned.eq_ignore_ascii_case("inf.0") {
+        SteelVal::NumV(f64::INFINITY)
+    } else if unsigned.len() != text.len() && unsigned.eq_ignore_ascii_case("nan.0") {
+        SteelVal::NumV(f64::NAN)
+    } else {
+        parse_unsigned_real(unsigned, radix, exact)?
+    };
+
+    if negative {
+        negate_unchecked(&value).ok()
+    } else {
+        Some(value)
+    }
+}
+
+fn parse_complex(text: &str, radix: u32, exact: bool) -> Option<SteelVal> {
+    if let Some(body) = text.strip_suffix(['i', 'I']) {
+        // The imaginary part starts at the last sign that doesn't belong to an exponent
+        let bytes = body.as_bytes();
+        let split = (0..bytes.len()).rev().find(|&i| {
+            matches!(bytes[i], b'+' | b'-')
+                && !(radix == 10 && i > 0 && matches!(bytes[i - 1], b'e' | b'E'))
+        })?;
+
+        let (re, im) = body.split_at(split);
+        let re = if re.is_empty() {
+            SteelVal::IntV(0)
+        } else {
+            parse_real(re, radix, exact)?
+        };
+        let im = match im {
+            "+" => SteelVal::IntV(1),
+            "-" => SteelVal::IntV(-1),
+            _ => parse_real(im, radix, exact)?,
+        };
+
+        return SteelComplex::new(re, im).into_steelval().ok();
+    }
+
+    if let Some((magnitude, angle)) = text.split_once('@') {
+        return make_polar(
+            &parse_real(magnitude, radix, exact)?,
+            &parse_real(angle, radix, exact)?,
+        )
+        .ok();
+    }
+
+    parse_real(text, radix, exact)
+}
+
+/// Parses the written form of a number: an optional radix (`#x`, `#o`, `#b`, `#d`) and
+/// exactness (`#e`, `#i`) prefix, followed by a real number or a complex number in rectangular
+/// (`1+2i`) or polar (`1@2`) form. Returns `None` if the text isn't a number.
+pub(crate) fn parse_number(text: &str, mut radix: u32) -> Option<SteelVal> {
+    let mut text = text;
+    let mut radix_prefix = false;
+    let mut exactness = None;
+
+    while let Some(rest) = text.strip_prefix('#') {
+        let prefix = rest.chars().next()?.to_ascii_lowercase();
+        match prefix {
+            'x' | 'o' | 'b' | 'd' if !radix_prefix => {
+                radix = match prefix {
+                    'x' => 16,
+                    'o' => 8,
+                    'b' => 2,
+                    _ => 10,
+                };
+                radix_prefix = true;
+            }
+            'e' | 'i' if exactness.is_none() => exactness = Some(prefix == 'e'),
+            _ => return None,
+        }
+        text = &rest[1..];
+    }
+
+    let number = parse_complex(text, radix, exactness == Some(true))?;
+
+    match exactness {
+        Some(true) => exact(&number).ok(),
+        Some(false) => inexact(&number).ok(),
+        None => Some(number),
+    }
 }
 
 pub struct NumOperations {}
@@ -520,4 +1589,23 @@ mod num_op_tests {
         let expected = IntV(8);
         assert_eq!(got, expected);
     }
+
+    #[test]
+    fn complex_numbers_with_exact_zero_imaginary_part_are_real() {
+        let z = make_rectangular(&IntV(1), &IntV(2)).unwrap();
+        let conjugate = make_rectangular(&IntV(1), &IntV(-2)).unwrap();
+        assert_eq!(multiply_primitive(&[z, conjugate]).unwrap(), IntV(5));
+    }
+
+    #[test]
+    fn parse_number_reads_prefixes_and_complex_numbers() {
+        assert_eq!(parse_number("#x-ff", 10), Some(IntV(-255)));
+        assert_eq!(
+            parse_number("#e1.5", 10).unwrap().to_string(),
+            Rational(Rational32::new(3, 2)).to_string()
+        );
+        assert_eq!(parse_number("1+2i", 10).unwrap().to_string(), "1+2i");
+        assert_eq!(parse_number("1e", 10), None);
+        assert_eq!(parse_number("#x#x1", 10), None);
+    }
 }
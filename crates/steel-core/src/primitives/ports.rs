@@ -1,10 +1,14 @@
-use crate::rvals::{Result, SteelString, SteelVal};
-use crate::steel_vm::builtin::BuiltInModule;
+use crate::primitives::strings::format_directives;
+use crate::rvals::printer::{print_value, Labels, PrintMode};
+use crate::rvals::reader;
+use crate::rvals::{PrimitiveAsRef, Result, SteelString, SteelVal};
+use crate::steel_vm::builtin::{BuiltInModule, MarkdownDoc};
+use crate::steel_vm::vm::VmCore;
 use crate::stop;
 use crate::values::port::new_rc_ref_cell;
 use crate::values::port::{SteelPort, SteelPortRepr};
 
-use steel_derive::function;
+use steel_derive::{function, native};
 
 thread_local! {
     pub static EOF_OBJECT: SteelString = "eof".into();
@@ -17,10 +21,16 @@ pub fn port_module() -> BuiltInModule {
         .register_native_fn_definition(OPEN_STDOUT_DEFINITION)
         .register_native_fn_definition(OPEN_INPUT_FILE_DEFINITION)
         .register_native_fn_definition(OPEN_OUTPUT_FILE_DEFINITION)
+        .register_native_fn_definition(OPEN_INPUT_STRING_DEFINITION)
         .register_native_fn_definition(OPEN_OUTPUT_STRING_DEFINITION)
         .register_native_fn_definition(WRITE_LINE_DEFINITION)
         .register_native_fn_definition(WRITE_STRING_DEFINITION)
         .register_native_fn_definition(WRITE_DEFINITION)
+        .register_native_fn_definition(WRITE_SHARED_DEFINITION)
+        .register_native_fn_definition(WRITE_SIMPLE_DEFINITION)
+        .register_value("raw-read", SteelVal::BuiltIn(read))
+        .register_doc("raw-read", READ_DOC)
+        .register_native_fn_definition(READ_SYNTAX_DEFINITION)
         .register_native_fn_definition(WRITE_CHAR_DEFINITION)
         .register_native_fn_definition(FLUSH_OUTPUT_PORT_DEFINITION)
         .register_native_fn_definition(READ_PORT_TO_STRING_DEFINITION)
@@ -29,7 +39,9 @@ pub fn port_module() -> BuiltInModule {
         .register_native_fn_definition(IS_INPUT_DEFINITION)
         .register_native_fn_definition(IS_OUTPUT_DEFINITION)
         .register_native_fn_definition(DEFAULT_INPUT_PORT_DEFINITION)
-        .register_native_fn_definition(DEFAULT_OUTPUT_PORT_DEFINITION);
+        .register_native_fn_definition(DEFAULT_OUTPUT_PORT_DEFINITION)
+        .register_native_fn_definition(DEFAULT_ERROR_PORT_DEFINITION)
+        .register_native_fn_definition(FPRINTF_DEFINITION);
     module
 }
 
@@ -89,6 +101,21 @@ pub fn open_output_file(path: &SteelString) -> Result<SteelVal> {
     SteelPort::new_textual_file_output(path).map(SteelVal::PortV)
 }
 
+/// Returns an input port that reads the characters of the string.
+///
+/// (open-input-string string?) -> input-port?
+///
+/// # Examples
+/// ```scheme
+/// > (define port (open-input-string "(1 2) foo"))
+/// > (raw-read port) ;; => '(1 2)
+/// > (raw-read port) ;; => 'foo
+/// ```
+#[function(name = "open-input-string")]
+pub fn open_input_string(string: &SteelString) -> SteelVal {
+    SteelVal::PortV(SteelPort::new_input_port_string(string.to_string()))
+}
+
 #[function(name = "open-output-string")]
 pub fn open_output_string() -> SteelVal {
     SteelVal::PortV(SteelPort::new_output_port())
@@ -171,18 +198,127 @@ pub fn write_line(port: &SteelPort, line: &SteelVal) -> Result<SteelVal> {
     }
 }
 
-#[function(name = "raw-write")]
-pub fn write(port: &SteelPort, line: &SteelVal) -> Result<SteelVal> {
-    let line = line.to_string();
-    let res = port.write_string(line.as_str());
+fn write_value(port: &SteelPort, value: &SteelVal, labels: Labels) -> Result<SteelVal> {
+    let mut output = String::new();
+    print_value(&mut output, value, PrintMode::Write, labels);
 
-    if res.is_ok() {
+    if port.write_string(&output).is_ok() {
         Ok(SteelVal::Void)
     } else {
         stop!(Generic => "unable to write string to port");
     }
 }
 
+/// Writes the value to the port in a form that `read` reads back as an equal value. Strings,
+/// characters and symbols are written as literals, and values that contain themselves are
+/// marked with datum labels so that the output is finite.
+///
+/// (raw-write port value) -> void?
+///
+/// * port : output-port?
+/// * value : any/c
+///
+/// # Examples
+///
+/// ```scheme
+/// > (define port (open-output-string))
+/// > (raw-write port (list "a\n" #\space '|two words|))
+/// > (get-output-string port) ;; => "(\"a\\n\" #\\space |two words|)"
+/// ```
+#[function(name = "raw-write")]
+pub fn write(port: &SteelPort, value: &SteelVal) -> Result<SteelVal> {
+    write_value(port, value, Labels::Cycles)
+}
+
+/// Like `raw-write`, but marks every value that appears more than once with a datum label,
+/// so that `read` gives back the same sharing.
+///
+/// (raw-write-shared port value) -> void?
+///
+/// * port : output-port?
+/// * value : any/c
+///
+/// # Examples
+///
+/// ```scheme
+/// > (define port (open-output-string))
+/// > (define shared (list 1 2))
+/// > (raw-write-shared port (list shared shared))
+/// > (get-output-string port) ;; => "(#0=(1 2) #0#)"
+/// ```
+#[function(name = "raw-write-shared")]
+pub fn write_shared(port: &SteelPort, value: &SteelVal) -> Result<SteelVal> {
+    write_value(port, value, Labels::Shared)
+}
+
+/// Like `raw-write`, but never uses datum labels. Values that contain themselves are cut off
+/// past a fixed depth.
+///
+/// (raw-write-simple port value) -> void?
+///
+/// * port : output-port?
+/// * value : any/c
+#[function(name = "raw-write-simple")]
+pub fn write_simple(port: &SteelPort, value: &SteelVal) -> Result<SteelVal> {
+    write_value(port, value, Labels::Never)
+}
+
+fn read(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>> {
+    Some(read_impl(ctx, args))
+}
+
+fn read_impl(ctx: &mut VmCore, args: &[SteelVal]) -> Result<SteelVal> {
+    let [port] = args else {
+        stop!(ArityMismatch => "raw-read expects 1 argument, found {}", args.len())
+    };
+
+    let SteelVal::PortV(port) = port else {
+        stop!(TypeMismatch => "raw-read expects an input port, found: {}", port)
+    };
+
+    reader::read_datum(port, &mut ctx.thread.heap)
+}
+
+const READ_DOC: MarkdownDoc<'static> = MarkdownDoc(
+    r##"Reads the next datum from the port, in the syntax that `write` prints, and returns the eof
+object when there's nothing left but whitespace and comments. Whatever follows the datum is
+left in the port. Datum labels can share structure, and a vector can contain itself, in which
+case it reads as a mutable vector. Bytevectors read as vectors of their bytes.
+
+(raw-read port) -> any/c
+
+* port : input-port?
+
+# Examples
+
+```scheme
+> (raw-read (open-input-string "(a . b) #(1 2)")) ;; => '(a . b)
+> (raw-read (open-input-string "(#0=(x) #0#)")) ;; => '((x) (x))
+> (define v (raw-read (open-input-string "#0=#(1 #0#)")))
+> (eq? v (mut-vector-ref v 1)) ;; => #true
+```
+"##,
+);
+
+/// Like `raw-read`, but returns a syntax object, whose span is the byte offsets of the datum
+/// in the port and whose elements are syntax objects with spans of their own.
+///
+/// (raw-read-syntax port) -> syntax?
+///
+/// * port : input-port?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (define stx (raw-read-syntax (open-input-string "  (a b)")))
+/// > (syntax-span stx) ;; => '(2 7 #false)
+/// > (syntax->datum stx) ;; => '(a b)
+/// ```
+#[function(name = "raw-read-syntax")]
+pub fn read_syntax(port: &SteelPort) -> Result<SteelVal> {
+    reader::read_syntax(port)
+}
+
 #[function(name = "raw-write-char")]
 pub fn write_char(port: &SteelPort, character: char) -> Result<SteelVal> {
     let res = port.write_char(character);
@@ -209,6 +345,39 @@ pub fn write_string(port: &SteelPort, line: &SteelVal) -> Result<SteelVal> {
     }
 }
 
+/// Formats the arguments according to the directives in the format string, like `format`, and
+/// writes the result to the port.
+///
+/// (fprintf port format-string args ...) -> void?
+///
+/// * port : output-port?
+/// * format-string : string?
+/// * args : any/c
+///
+/// # Examples
+///
+/// ```scheme
+/// > (define port (open-output-string))
+/// > (fprintf port "~a: ~5r" "total" 42)
+/// > (get-output-string port) ;; => "total:    42"
+/// ```
+#[native(name = "fprintf", arity = "AtLeast(2)")]
+pub fn fprintf(args: &[SteelVal]) -> Result<SteelVal> {
+    let port = <&SteelPort>::primitive_as_ref(&args[0])?;
+
+    let SteelVal::StringV(template) = &args[1] else {
+        stop!(TypeMismatch => "fprintf expects a format string, found: {}", &args[1]);
+    };
+
+    let output = format_directives("fprintf", template, &args[2..])?;
+
+    if port.write_string(&output).is_ok() {
+        Ok(SteelVal::Void)
+    } else {
+        stop!(Generic => "unable to write string to port");
+    }
+}
+
 #[function(name = "get-output-string")]
 pub fn get_output_string(port: &SteelPort) -> Result<SteelVal> {
     port.get_output_string().map(SteelVal::from)
@@ -229,6 +398,11 @@ pub fn default_output_port() -> SteelVal {
     SteelVal::PortV(SteelPort::default_current_output_port())
 }
 
+#[function(name = "#%default-error-port")]
+pub fn default_error_port() -> SteelVal {
+    SteelVal::PortV(SteelPort::default_current_error_port())
+}
+
 // TODO: In order for this to work, ports have to get refactored - the mutability needs to be
 // on the outside, rather than the inside.
 #[function(name = "close-output-port")]
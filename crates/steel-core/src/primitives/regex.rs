@@ -0,0 +1,483 @@
+use regex::{Captures, Match, Regex};
+
+use crate::gc::Gc;
+use crate::rvals::{Custom, FromSteelVal, Result, SteelString, SteelVal};
+use crate::steel_vm::builtin::{BuiltInModule, MarkdownDoc};
+use crate::steel_vm::vm::{VmContext, VmCore};
+use crate::stop;
+use crate::values::lists::List;
+
+use steel_derive::function;
+
+/// A compiled regular expression, see the `steel/regex` module.
+#[derive(Clone)]
+pub struct SteelRegex(Regex);
+
+impl Custom for SteelRegex {
+    fn fmt(&self) -> Option<std::result::Result<String, std::fmt::Error>> {
+        Some(Ok(format!("#<regex {:?}>", self.0.as_str())))
+    }
+}
+
+fn string_or_false(value: Option<&str>) -> SteelVal {
+    value
+        .map(|x| SteelVal::StringV(x.into()))
+        .unwrap_or(SteelVal::BoolV(false))
+}
+
+// Spans are byte offsets into the string, as a list of the start and end
+fn span(value: Match<'_>) -> SteelVal {
+    SteelVal::ListV(List::from(vec![
+        SteelVal::IntV(value.start() as isize),
+        SteelVal::IntV(value.end() as isize),
+    ]))
+}
+
+fn captures_to_list(captures: &Captures<'_>) -> SteelVal {
+    captures
+        .iter()
+        .map(|x| string_or_false(x.map(|x| x.as_str())))
+        .collect::<List<_>>()
+        .into()
+}
+
+fn captures_to_hashmap(regex: &Regex, captures: &Captures<'_>) -> SteelVal {
+    let map = regex
+        .capture_names()
+        .flatten()
+        .filter_map(|name| {
+            captures.name(name).map(|x| {
+                (
+                    SteelVal::StringV(name.into()),
+                    SteelVal::StringV(x.as_str().into()),
+                )
+            })
+        })
+        .collect::<im_rc::HashMap<_, _>>();
+
+    SteelVal::HashMapV(Gc::new(map).into())
+}
+
+/// # steel/regex
+///
+/// Regular expressions, backed by the Rust `regex` crate. See the documentation of that crate for
+/// the supported syntax.
+///
+/// Spans are returned as a list of the start and end offsets of the match, in bytes.
+///
+/// ```scheme
+/// (require-builtin steel/regex)
+///
+/// (define date (regex "(?<year>\\d{4})-(?<month>\\d{2})"))
+/// (regex/named-captures date "2024-03") ;; => (hash "year" "2024" "month" "03")
+/// ```
+#[steel_derive::define_module(name = "steel/regex")]
+pub fn regex_module() -> BuiltInModule {
+    let mut module = BuiltInModule::new("steel/regex");
+
+    module
+        .register_native_fn_definition(REGEX_DEFINITION)
+        .register_native_fn_definition(IS_REGEX_DEFINITION)
+        .register_native_fn_definition(REGEX_TO_STRING_DEFINITION)
+        .register_native_fn_definition(REGEX_ESCAPE_DEFINITION)
+        .register_native_fn_definition(REGEX_IS_MATCH_DEFINITION)
+        .register_native_fn_definition(REGEX_FIND_DEFINITION)
+        .register_native_fn_definition(REGEX_FIND_SPAN_DEFINITION)
+        .register_native_fn_definition(REGEX_FIND_ALL_DEFINITION)
+        .register_native_fn_definition(REGEX_FIND_ALL_SPANS_DEFINITION)
+        .register_native_fn_definition(REGEX_CAPTURES_DEFINITION)
+        .register_native_fn_definition(REGEX_CAPTURE_SPANS_DEFINITION)
+        .register_native_fn_definition(REGEX_CAPTURES_ALL_DEFINITION)
+        .register_native_fn_definition(REGEX_NAMED_CAPTURES_DEFINITION)
+        .register_native_fn_definition(REGEX_CAPTURE_NAMES_DEFINITION)
+        .register_native_fn_definition(REGEX_SPLIT_DEFINITION)
+        .register_native_fn_definition(REGEX_SPLITN_DEFINITION)
+        .register_value("regex/replace", SteelVal::BuiltIn(regex_replace))
+        .register_doc("regex/replace", REGEX_REPLACE_DOC)
+        .register_value("regex/replace-all", SteelVal::BuiltIn(regex_replace_all))
+        .register_doc("regex/replace-all", REGEX_REPLACE_ALL_DOC);
+
+    module
+}
+
+/// Compiles a regular expression. Raises an error if the pattern is invalid.
+///
+/// (regex pattern) -> regex?
+///
+/// * pattern : string?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex "a+b") ;; => #<regex "a+b">
+/// > (regex "(") ;; error
+/// ```
+#[function(name = "regex")]
+pub fn regex(pattern: &SteelString) -> Result<SteelVal> {
+    match Regex::new(pattern.as_str()) {
+        Ok(regex) => crate::rvals::IntoSteelVal::into_steelval(SteelRegex(regex)),
+        Err(e) => stop!(Generic => "regex: {}", e),
+    }
+}
+
+/// Checks if the given value is a compiled regular expression.
+///
+/// (regex? value) -> bool?
+#[function(name = "regex?")]
+pub fn is_regex(value: &SteelVal) -> bool {
+    match value {
+        SteelVal::Custom(c) => c.borrow().as_any_ref().is::<SteelRegex>(),
+        _ => false,
+    }
+}
+
+/// Returns the pattern that the regular expression was compiled from.
+///
+/// (regex->string regex) -> string?
+#[function(name = "regex->string")]
+pub fn regex_to_string(regex: SteelRegex) -> SteelVal {
+    SteelVal::StringV(regex.0.as_str().into())
+}
+
+/// Escapes all of the special characters in the string, so that it matches itself
+/// when compiled as a regular expression.
+///
+/// (regex/escape string) -> string?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex/escape "1+1") ;; => "1\\+1"
+/// ```
+#[function(name = "regex/escape")]
+pub fn regex_escape(value: &SteelString) -> SteelVal {
+    SteelVal::StringV(regex::escape(value.as_str()).into())
+}
+
+/// Checks if the regular expression matches anywhere in the string.
+///
+/// (regex/match? regex string) -> bool?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex/match? (regex "\\d+") "abc123") ;; => #t
+/// ```
+#[function(name = "regex/match?")]
+pub fn regex_is_match(regex: SteelRegex, haystack: &SteelString) -> bool {
+    regex.0.is_match(haystack.as_str())
+}
+
+/// Returns the leftmost match in the string, or `#f` if there isn't one.
+///
+/// (regex/find regex string) -> (or/c string? #f)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex/find (regex "\\d+") "abc123def456") ;; => "123"
+/// ```
+#[function(name = "regex/find")]
+pub fn regex_find(regex: SteelRegex, haystack: &SteelString) -> SteelVal {
+    string_or_false(regex.0.find(haystack.as_str()).map(|x| x.as_str()))
+}
+
+/// Returns the span of the leftmost match in the string, or `#f` if there isn't one.
+///
+/// (regex/find-span regex string) -> (or/c (list int? int?) #f)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex/find-span (regex "\\d+") "abc123def456") ;; => '(3 6)
+/// ```
+#[function(name = "regex/find-span")]
+pub fn regex_find_span(regex: SteelRegex, haystack: &SteelString) -> SteelVal {
+    regex
+        .0
+        .find(haystack.as_str())
+        .map(span)
+        .unwrap_or(SteelVal::BoolV(false))
+}
+
+/// Returns every non-overlapping match in the string.
+///
+/// (regex/find-all regex string) -> (listof string?)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex/find-all (regex "\\d+") "abc123def456") ;; => '("123" "456")
+/// ```
+#[function(name = "regex/find-all")]
+pub fn regex_find_all(regex: SteelRegex, haystack: &SteelString) -> SteelVal {
+    regex
+        .0
+        .find_iter(haystack.as_str())
+        .map(|x| SteelVal::StringV(x.as_str().into()))
+        .collect::<List<_>>()
+        .into()
+}
+
+/// Returns the spans of every non-overlapping match in the string.
+///
+/// (regex/find-all-spans regex string) -> (listof (list int? int?))
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex/find-all-spans (regex "\\d+") "abc123def456") ;; => '((3 6) (9 12))
+/// ```
+#[function(name = "regex/find-all-spans")]
+pub fn regex_find_all_spans(regex: SteelRegex, haystack: &SteelString) -> SteelVal {
+    regex
+        .0
+        .find_iter(haystack.as_str())
+        .map(span)
+        .collect::<List<_>>()
+        .into()
+}
+
+/// Returns the groups of the leftmost match in the string, or `#f` if there isn't one.
+/// The first group is the entire match. Groups that didn't participate in the match are `#f`.
+///
+/// (regex/captures regex string) -> (or/c (listof (or/c string? #f)) #f)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex/captures (regex "(\\w+)@(\\w+)?") "me@") ;; => '("me@" "me" #f)
+/// ```
+#[function(name = "regex/captures")]
+pub fn regex_captures(regex: SteelRegex, haystack: &SteelString) -> SteelVal {
+    regex
+        .0
+        .captures(haystack.as_str())
+        .map(|x| captures_to_list(&x))
+        .unwrap_or(SteelVal::BoolV(false))
+}
+
+/// Returns the spans of the groups of the leftmost match in the string, or `#f` if there isn't one.
+/// Groups that didn't participate in the match are `#f`.
+///
+/// (regex/capture-spans regex string) -> (or/c (listof (or/c (list int? int?) #f)) #f)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex/capture-spans (regex "(\\w+)@(\\w+)") "to: me@home") ;; => '((4 11) (4 6) (7 11))
+/// ```
+#[function(name = "regex/capture-spans")]
+pub fn regex_capture_spans(regex: SteelRegex, haystack: &SteelString) -> SteelVal {
+    regex
+        .0
+        .captures(haystack.as_str())
+        .map(|captures| {
+            captures
+                .iter()
+                .map(|x| x.map(span).unwrap_or(SteelVal::BoolV(false)))
+                .collect::<List<_>>()
+                .into()
+        })
+        .unwrap_or(SteelVal::BoolV(false))
+}
+
+/// Returns the groups of every non-overlapping match in the string, in the same
+/// format as `regex/captures`.
+///
+/// (regex/captures-all regex string) -> (listof (listof (or/c string? #f)))
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex/captures-all (regex "(\\w)=(\\d)") "a=1 b=2") ;; => '(("a=1" "a" "1") ("b=2" "b" "2"))
+/// ```
+#[function(name = "regex/captures-all")]
+pub fn regex_captures_all(regex: SteelRegex, haystack: &SteelString) -> SteelVal {
+    regex
+        .0
+        .captures_iter(haystack.as_str())
+        .map(|x| captures_to_list(&x))
+        .collect::<List<_>>()
+        .into()
+}
+
+/// Returns the named groups of the leftmost match in the string as a hashmap from the
+/// name of the group to the matched string, or `#f` if there isn't a match. Groups that
+/// didn't participate in the match are left out.
+///
+/// (regex/named-captures regex string) -> (or/c hash? #f)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex/named-captures (regex "(?<key>\\w+)=(?<value>\\w+)") "name=steel")
+/// ;; => (hash "key" "name" "value" "steel")
+/// ```
+#[function(name = "regex/named-captures")]
+pub fn regex_named_captures(regex: SteelRegex, haystack: &SteelString) -> SteelVal {
+    regex
+        .0
+        .captures(haystack.as_str())
+        .map(|x| captures_to_hashmap(&regex.0, &x))
+        .unwrap_or(SteelVal::BoolV(false))
+}
+
+/// Returns the names of the named groups in the regular expression.
+///
+/// (regex/capture-names regex) -> (listof string?)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex/capture-names (regex "(?<key>\\w+)=(\\w+)")) ;; => '("key")
+/// ```
+#[function(name = "regex/capture-names")]
+pub fn regex_capture_names(regex: SteelRegex) -> SteelVal {
+    regex
+        .0
+        .capture_names()
+        .flatten()
+        .map(|x| SteelVal::StringV(x.into()))
+        .collect::<List<_>>()
+        .into()
+}
+
+/// Splits the string on every match of the regular expression.
+///
+/// (regex/split regex string) -> (listof string?)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex/split (regex ",\\s*") "a, b,c") ;; => '("a" "b" "c")
+/// ```
+#[function(name = "regex/split")]
+pub fn regex_split(regex: SteelRegex, haystack: &SteelString) -> SteelVal {
+    regex
+        .0
+        .split(haystack.as_str())
+        .map(|x| SteelVal::StringV(x.into()))
+        .collect::<List<_>>()
+        .into()
+}
+
+/// Splits the string on the matches of the regular expression, into at most `limit` pieces.
+///
+/// (regex/splitn regex string limit) -> (listof string?)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (regex/splitn (regex ",") "a,b,c" 2) ;; => '("a" "b,c")
+/// ```
+#[function(name = "regex/splitn")]
+pub fn regex_splitn(regex: SteelRegex, haystack: &SteelString, limit: usize) -> SteelVal {
+    regex
+        .0
+        .splitn(haystack.as_str(), limit)
+        .map(|x| SteelVal::StringV(x.into()))
+        .collect::<List<_>>()
+        .into()
+}
+
+const REGEX_REPLACE_DOC: MarkdownDoc<'static> = MarkdownDoc(
+    r##"Replaces the leftmost match in the string.
+
+(regex/replace regex string replacement) -> string?
+
+* replacement : (or/c string? procedure?)
+
+The replacement is either a template, where `$1` or `${name}` refer to the groups of the
+match, or a procedure that is called with the groups of the match in the same format as
+`regex/captures`, and returns the string to replace it with.
+
+# Examples
+
+```scheme
+> (regex/replace (regex "(\\w+) (\\w+)") "hello world" "$2 $1") ;; => "world hello"
+> (regex/replace (regex "\\d+") "a1b2" (lambda (groups) "#")) ;; => "a#b2"
+```
+"##,
+);
+
+const REGEX_REPLACE_ALL_DOC: MarkdownDoc<'static> = MarkdownDoc(
+    r##"Replaces every non-overlapping match in the string.
+
+(regex/replace-all regex string replacement) -> string?
+
+* replacement : (or/c string? procedure?)
+
+The replacement is interpreted in the same way as for `regex/replace`.
+
+# Examples
+
+```scheme
+> (regex/replace-all (regex "\\d") "a1b2" "#") ;; => "a#b#"
+> (regex/replace-all (regex "\\d") "a1b2"
+                     (lambda (groups) (number->string (* 2 (string->number (car groups))))))
+;; => "a2b4"
+```
+"##,
+);
+
+fn replace_impl(ctx: &mut VmCore, args: &[SteelVal], name: &str, limit: usize) -> Result<SteelVal> {
+    if args.len() != 3 {
+        stop!(ArityMismatch => "{} expects 3 arguments, found: {}", name, args.len());
+    }
+
+    let regex = SteelRegex::from_steelval(&args[0])?;
+
+    let haystack = match &args[1] {
+        SteelVal::StringV(s) => s.clone(),
+        other => stop!(TypeMismatch => "{} expects a string, found: {}", name, other),
+    };
+
+    let replacer = match &args[2] {
+        SteelVal::StringV(template) => {
+            return Ok(SteelVal::StringV(
+                regex
+                    .0
+                    .replacen(haystack.as_str(), limit, template.as_str())
+                    .into_owned()
+                    .into(),
+            ));
+        }
+        other if other.is_function() => other.clone(),
+        other => {
+            stop!(TypeMismatch => "{} expects a string or a procedure for the replacement, found: {}", name, other)
+        }
+    };
+
+    let limit = if limit == 0 { usize::MAX } else { limit };
+
+    let mut output = String::with_capacity(haystack.len());
+    let mut last = 0;
+
+    for captures in regex.0.captures_iter(haystack.as_str()).take(limit) {
+        let matched = captures.get(0).unwrap();
+
+        output.push_str(&haystack[last..matched.start()]);
+
+        match ctx.call_function_one_arg(&replacer, captures_to_list(&captures))? {
+            SteelVal::StringV(s) => output.push_str(&s),
+            other => {
+                stop!(TypeMismatch => "{}: the replacement procedure must return a string, found: {}", name, other)
+            }
+        }
+
+        last = matched.end();
+    }
+
+    output.push_str(&haystack[last..]);
+
+    Ok(SteelVal::StringV(output.into()))
+}
+
+fn regex_replace(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>> {
+    Some(replace_impl(ctx, args, "regex/replace", 1))
+}
+
+fn regex_replace_all(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>> {
+    Some(replace_impl(ctx, args, "regex/replace-all", 0))
+}
@@ -1,15 +1,15 @@
 use crate::gc::Gc;
-use crate::rvals::{Result, SteelVal};
+use crate::rvals::{IntoSteelVal, Result, SteelVal};
 use crate::stop;
 
-use crate::values::lazy_stream::LazyStream;
+use crate::values::lazy_stream::{LazyStream, Promise};
 
 pub struct StreamOperations {}
 impl StreamOperations {
     pub fn stream_cons() -> SteelVal {
         SteelVal::FuncV(|args: &[SteelVal]| -> Result<SteelVal> {
             if args.len() != 2 {
-                stop!(ArityMismatch => "stream-cons requires 2 argments")
+                stop!(ArityMismatch => "#%stream-cons requires 2 argments")
             }
 
             if let SteelVal::Closure(_) = &args[1] {
@@ -20,7 +20,7 @@ impl StreamOperations {
                     stream_thunk,
                 ))))
             } else {
-                stop!(TypeMismatch => "stream-cons takes a function in the second position")
+                stop!(TypeMismatch => "#%stream-cons takes a function in the second position")
             }
         })
     }
@@ -69,3 +69,81 @@ impl StreamOperations {
         })
     }
 }
+
+fn as_promise<T>(value: &SteelVal, f: impl FnOnce(&mut Promise) -> T) -> Option<T> {
+    if let SteelVal::Custom(c) = value {
+        c.borrow_mut()
+            .as_any_ref_mut()
+            .downcast_mut::<Promise>()
+            .map(f)
+    } else {
+        None
+    }
+}
+
+/// `(#%make-promise done? value)` - a forced promise holding `value`, or a pending one
+/// that will call the thunk `value` to get another promise.
+pub(crate) fn make_promise(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 2 {
+        stop!(ArityMismatch => "#%make-promise expects 2 arguments, found {}", args.len());
+    }
+
+    Promise::new(args[0].is_truthy(), args[1].clone()).into_steelval()
+}
+
+pub(crate) fn is_promise(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "promise? expects 1 argument, found {}", args.len());
+    }
+
+    Ok(SteelVal::BoolV(as_promise(&args[0], |_| ()).is_some()))
+}
+
+pub(crate) fn promise_is_done(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "#%promise-done? expects 1 argument, found {}", args.len());
+    }
+
+    match as_promise(&args[0], |p| p.is_done()) {
+        Some(done) => Ok(SteelVal::BoolV(done)),
+        None => stop!(TypeMismatch => "#%promise-done? expects a promise, found {}", &args[0]),
+    }
+}
+
+pub(crate) fn promise_value(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "#%promise-value expects 1 argument, found {}", args.len());
+    }
+
+    match as_promise(&args[0], |p| p.value()) {
+        Some(value) => Ok(value),
+        None => stop!(TypeMismatch => "#%promise-value expects a promise, found {}", &args[0]),
+    }
+}
+
+/// `(#%promise-update! new old)`
+pub(crate) fn promise_update(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 2 {
+        stop!(ArityMismatch => "#%promise-update! expects 2 arguments, found {}", args.len());
+    }
+
+    if let (SteelVal::Custom(new), SteelVal::Custom(old)) = (&args[0], &args[1]) {
+        // Updating a promise with itself has nothing to do
+        if Gc::ptr_eq(new, old) {
+            return Ok(SteelVal::Void);
+        }
+
+        let old = old.borrow();
+        let mut new = new.borrow_mut();
+
+        if let (Some(new), Some(old)) = (
+            new.as_any_ref_mut().downcast_mut::<Promise>(),
+            old.as_any_ref().downcast_ref::<Promise>(),
+        ) {
+            Promise::update(new, old);
+            return Ok(SteelVal::Void);
+        }
+    }
+
+    stop!(TypeMismatch => "#%promise-update! expects two promises, found {} and {}", &args[0], &args[1])
+}
@@ -1,21 +1,37 @@
+use crate::primitives::nums::{format_number, parse_number, real_to_f64};
+use crate::rvals::printer::{print_to_string, PrintMode};
 use crate::values::lists::List;
 
-use crate::rvals::{RestArgsIter, Result, SteelString, SteelVal};
-use crate::steel_vm::builtin::BuiltInModule;
+use crate::rvals::{
+    AsRefMutSteelVal, Custom, CustomType, FromSteelVal, IntoSteelVal, RestArgsIter, Result,
+    SteelString, SteelVal,
+};
+use crate::steel_vm::builtin::{BuiltInModule, MarkdownDoc};
+use crate::steel_vm::primitives::numberp;
 use crate::steel_vm::register_fn::RegisterFn;
+use crate::steel_vm::vm::{VmContext, VmCore};
 use crate::stop;
 
+use std::cell::RefMut;
 use steel_derive::{function, native};
-
-fn char_upcase(c: char) -> char {
-    c.to_ascii_uppercase()
-}
+use unicode_general_category::{get_general_category, GeneralCategory};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
 
 /// # steel/strings
 ///
 /// Strings in Steel are immutable, fixed length arrays of characters. They are heap allocated, and
 /// are implemented under the hood as referenced counted Rust `Strings`. Rust `Strings` are stored
 /// as UTF-8 encoded bytes.
+///
+/// Every string, including the ones returned by `make-string`, `string` and `string-copy`, is
+/// immutable. Strings that can be changed in place with `string-set!`, `string-fill!`,
+/// `string-copy!` and `string-append!` are a separate type, created by `make-mutable-string` or
+/// `string->mutable-string` and turned back into a string with `mutable-string->string`.
+///
+/// Indices into strings count characters (Unicode scalar values), so functions like `string-ref`
+/// and `substring` have to walk the string up to the index. For linear passes over large strings,
+/// use the `string-cursor-*` functions, which move between characters in constant time.
 #[steel_derive::define_module(name = "steel/strings")]
 pub fn string_module() -> BuiltInModule {
     let mut module = BuiltInModule::new("steel/strings");
@@ -56,11 +72,68 @@ pub fn string_module() -> BuiltInModule {
         .register_native_fn_definition(STRING_TO_NUMBER_DEFINITION)
         .register_native_fn_definition(NUMBER_TO_STRING_DEFINITION)
         .register_native_fn_definition(REPLACE_DEFINITION)
-        .register_fn("char-upcase", char_upcase)
         .register_fn("char-whitespace?", char::is_whitespace)
         .register_fn("char-digit?", |c: char| char::is_digit(c, 10))
         .register_fn("char->number", |c: char| char::to_digit(c, 10))
-        .register_native_fn_definition(CHAR_EQUALS_DEFINITION);
+        .register_native_fn_definition(CHAR_EQUALS_DEFINITION)
+        .register_native_fn_definition(CHAR_ALPHABETIC_DEFINITION)
+        .register_native_fn_definition(CHAR_NUMERIC_DEFINITION)
+        .register_native_fn_definition(CHAR_UPPER_CASE_DEFINITION)
+        .register_native_fn_definition(CHAR_LOWER_CASE_DEFINITION)
+        .register_native_fn_definition(DIGIT_VALUE_DEFINITION)
+        .register_native_fn_definition(CHAR_UPCASE_DEFINITION)
+        .register_native_fn_definition(CHAR_DOWNCASE_DEFINITION)
+        .register_native_fn_definition(CHAR_FOLDCASE_PRIMITIVE_DEFINITION)
+        .register_native_fn_definition(CHAR_TO_INTEGER_DEFINITION)
+        .register_native_fn_definition(INTEGER_TO_CHAR_DEFINITION)
+        .register_native_fn_definition(CHAR_LESS_THAN_DEFINITION)
+        .register_native_fn_definition(CHAR_LESS_THAN_EQUAL_TO_DEFINITION)
+        .register_native_fn_definition(CHAR_GREATER_THAN_DEFINITION)
+        .register_native_fn_definition(CHAR_GREATER_THAN_EQUAL_TO_DEFINITION)
+        .register_native_fn_definition(CHAR_CI_EQUALS_DEFINITION)
+        .register_native_fn_definition(CHAR_CI_LESS_THAN_DEFINITION)
+        .register_native_fn_definition(CHAR_CI_LESS_THAN_EQUAL_TO_DEFINITION)
+        .register_native_fn_definition(CHAR_CI_GREATER_THAN_DEFINITION)
+        .register_native_fn_definition(CHAR_CI_GREATER_THAN_EQUAL_TO_DEFINITION)
+        .register_native_fn_definition(STRING_UPCASE_DEFINITION)
+        .register_native_fn_definition(STRING_DOWNCASE_DEFINITION)
+        .register_native_fn_definition(STRING_FOLDCASE_DEFINITION)
+        .register_native_fn_definition(STRING_COPY_DEFINITION)
+        .register_native_fn_definition(STRING_CONTAINS_DEFINITION)
+        .register_native_fn_definition(STRING_SEARCH_FORWARD_DEFINITION)
+        .register_native_fn_definition(STRING_SEARCH_BACKWARD_DEFINITION)
+        .register_value("string-index", SteelVal::BuiltIn(string_index))
+        .register_doc("string-index", STRING_INDEX_DOC)
+        .register_value("string-index-right", SteelVal::BuiltIn(string_index_right))
+        .register_doc("string-index-right", STRING_INDEX_RIGHT_DOC)
+        .register_native_fn_definition(STRING_PAD_DEFINITION)
+        .register_native_fn_definition(STRING_PAD_RIGHT_DEFINITION)
+        .register_native_fn_definition(STRING_JOIN_DEFINITION)
+        .register_native_fn_definition(STRING_TO_GRAPHEMES_DEFINITION)
+        .register_native_fn_definition(STRING_GRAPHEME_COUNT_DEFINITION)
+        .register_native_fn_definition(STRING_NORMALIZE_NFC_DEFINITION)
+        .register_native_fn_definition(STRING_NORMALIZE_NFD_DEFINITION)
+        .register_native_fn_definition(STRING_NORMALIZE_NFKC_DEFINITION)
+        .register_native_fn_definition(STRING_NORMALIZE_NFKD_DEFINITION)
+        .register_native_fn_definition(STRING_CURSOR_START_DEFINITION)
+        .register_native_fn_definition(STRING_CURSOR_END_DEFINITION)
+        .register_native_fn_definition(STRING_CURSOR_NEXT_DEFINITION)
+        .register_native_fn_definition(STRING_CURSOR_PREV_DEFINITION)
+        .register_native_fn_definition(STRING_CURSOR_REF_DEFINITION)
+        .register_native_fn_definition(STRING_CURSOR_TO_INDEX_DEFINITION)
+        .register_native_fn_definition(STRING_INDEX_TO_CURSOR_DEFINITION)
+        .register_native_fn_definition(SUBSTRING_CURSORS_DEFINITION)
+        .register_native_fn_definition(MAKE_MUTABLE_STRING_DEFINITION)
+        .register_native_fn_definition(STRING_TO_MUTABLE_STRING_DEFINITION)
+        .register_native_fn_definition(MUTABLE_STRING_TO_STRING_DEFINITION)
+        .register_native_fn_definition(IS_MUTABLE_STRING_DEFINITION)
+        .register_native_fn_definition(MUTABLE_STRING_LENGTH_DEFINITION)
+        .register_native_fn_definition(MUTABLE_STRING_REF_DEFINITION)
+        .register_native_fn_definition(STRING_SET_DEFINITION)
+        .register_native_fn_definition(STRING_FILL_DEFINITION)
+        .register_native_fn_definition(STRING_COPY_MUT_DEFINITION)
+        .register_native_fn_definition(STRING_APPEND_MUT_DEFINITION)
+        .register_native_fn_definition(FORMAT_DEFINITION);
     module
 }
 
@@ -74,20 +147,7 @@ pub fn char_equals(left: char, right: char) -> bool {
 }
 
 fn number_to_string_impl(value: &SteelVal, radix: Option<u32>) -> Result<SteelVal> {
-    match value {
-        SteelVal::IntV(v) => {
-            if let Some(radix) = radix {
-                Ok(SteelVal::StringV(
-                    radix_fmt::radix(*v, radix as u8).to_string().into(),
-                ))
-            } else {
-                Ok(SteelVal::StringV(v.to_string().into()))
-            }
-        }
-        SteelVal::NumV(n) => Ok(SteelVal::StringV(n.to_string().into())),
-        SteelVal::BigNum(n) => Ok(SteelVal::StringV(n.to_string().into())),
-        _ => stop!(TypeMismatch => "number->string expects a number type, found: {}", value),
-    }
+    format_number(value, radix.unwrap_or(10)).map(|x| SteelVal::StringV(x.into()))
 }
 
 /// Converts the given number to a string
@@ -111,31 +171,7 @@ pub fn number_to_string(value: &SteelVal, mut rest: RestArgsIter<'_, isize>) ->
 }
 
 fn string_to_number_impl(value: &str, radix: Option<u32>) -> Result<SteelVal> {
-    let expr = crate::parser::parser::Parser::parse(value)?;
-
-    if expr.len() != 1 {
-        // stop!(Generic => "")
-        return Ok(SteelVal::BoolV(false));
-    }
-
-    let number = expr.into_iter().next().unwrap();
-
-    let svalue = SteelVal::try_from(number)?;
-
-    match &svalue {
-        SteelVal::IntV(v) => {
-            if let Some(radix) = radix {
-                match isize::from_str_radix(value, radix) {
-                    Ok(parsed) => Ok(SteelVal::IntV(parsed)),
-                    Err(_) => Ok(SteelVal::BoolV(false)),
-                }
-            } else {
-                Ok(SteelVal::IntV(*v))
-            }
-        }
-        SteelVal::NumV(_) | SteelVal::BigNum(_) => Ok(svalue),
-        _ => Ok(SteelVal::BoolV(false)),
-    }
+    Ok(parse_number(value, radix.unwrap_or(10)).unwrap_or(SteelVal::BoolV(false)))
 }
 
 /// Converts the given string to a number
@@ -176,7 +212,7 @@ pub fn string_less_than_equal_to(left: &SteelString, right: &SteelString) -> boo
 
 #[function(name = "string-ci<=?", constant = true)]
 pub fn string_ci_less_than_equal_to(left: &SteelString, right: &SteelString) -> bool {
-    left.to_lowercase() <= right.to_lowercase()
+    caseless::default_case_fold_str(left) <= caseless::default_case_fold_str(right)
 }
 
 #[function(name = "string<?", constant = true)]
@@ -186,7 +222,7 @@ pub fn string_less_than(left: &SteelString, right: &SteelString) -> bool {
 
 #[function(name = "string-ci<?", constant = true)]
 pub fn string_ci_less_than(left: &SteelString, right: &SteelString) -> bool {
-    left.to_lowercase() < right.to_lowercase()
+    caseless::default_case_fold_str(left) < caseless::default_case_fold_str(right)
 }
 
 #[function(name = "string>=?", constant = true)]
@@ -196,7 +232,7 @@ pub fn string_greater_than_equal_to(left: &SteelString, right: &SteelString) ->
 
 #[function(name = "string-ci>=?", constant = true)]
 pub fn string_ci_greater_than_equal_to(left: &SteelString, right: &SteelString) -> bool {
-    left.to_lowercase() >= right.to_lowercase()
+    caseless::default_case_fold_str(left) >= caseless::default_case_fold_str(right)
 }
 
 #[function(name = "string>?", constant = true)]
@@ -206,7 +242,7 @@ pub fn string_greater_than(left: &SteelString, right: &SteelString) -> bool {
 
 #[function(name = "string-ci>?", constant = true)]
 pub fn string_ci_greater_than(left: &SteelString, right: &SteelString) -> bool {
-    left.to_lowercase() > right.to_lowercase()
+    caseless::default_case_fold_str(left) > caseless::default_case_fold_str(right)
 }
 
 #[function(name = "string=?", constant = true)]
@@ -216,29 +252,57 @@ pub fn string_equals(left: &SteelString, right: &SteelString) -> bool {
 
 #[function(name = "string-ci=?", constant = true)]
 pub fn string_ci_equals(left: &SteelString, right: &SteelString) -> bool {
-    left.to_lowercase() == right.to_lowercase()
+    caseless::default_case_fold_str(left) == caseless::default_case_fold_str(right)
 }
 
+/// Extracts the nth character out of a string. Finding the character walks the string up to
+/// the index, so this takes time proportional to the index - see `string-cursor-ref` for
+/// constant time access.
+///
+/// (string-ref str n) -> char?
+///
+/// * str : string?
+/// * n : int?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (string-ref "λx" 1) ;; => #\x
+/// ```
 #[function(name = "string-ref", constant = true)]
 pub fn string_ref(value: &SteelString, index: usize) -> Result<SteelVal> {
-    if index >= value.len() {
-        stop!(Generic => "string-ref: index out of bounds: index: {}, string length: {}", index, value);
+    match value.chars().nth(index) {
+        Some(c) => Ok(SteelVal::CharV(c)),
+        None => {
+            stop!(Generic => "string-ref: index out of bounds: index: {}, string length: {}", index, value.chars().count())
+        }
     }
-
-    Ok(SteelVal::CharV(value.as_str().chars().nth(index).unwrap()))
 }
 
+/// Creates a substring slicing the characters between the character indices `start`
+/// (inclusive) and `end` (exclusive, defaults to the length of the string).
+///
+/// (substring str start [end]) -> string?
+///
+/// * str : string?
+/// * start : int?
+/// * end : int?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (substring "héllo" 1 3) ;; => "él"
+/// > (substring "héllo" 2) ;; => "llo"
+/// ```
 #[function(name = "substring", constant = true)]
-pub fn substring(value: &SteelString, i: usize, j: usize) -> Result<SteelVal> {
-    if i >= value.len() {
-        stop!(Generic => "substring: index out of bounds: left bound: {}, string length: {}", i, value.len());
-    }
-
-    if i > j {
-        stop!(Generic => "substring: left bound must be less than or equal to the right bound: left: {}, right: {}", i, j);
-    }
-
-    Ok(SteelVal::StringV(value[i..j].into()))
+pub fn substring(
+    value: &SteelString,
+    start: usize,
+    rest: RestArgsIter<'_, isize>,
+) -> Result<SteelVal> {
+    let bounds = std::iter::once(Ok(start as isize)).chain(rest.0);
+    let (start, end) = string_range("substring", value, bounds)?;
+    Ok(SteelVal::StringV(value[start..end].into()))
 }
 
 #[function(name = "make-string")]
@@ -531,7 +595,7 @@ pub fn ends_with(value: &SteelString, suffix: &SteelString) -> bool {
     value.ends_with(suffix.as_str())
 }
 
-/// Get the length of the given string in UTF-8 bytes.
+/// Get the length of the given string in characters (Unicode scalar values).
 ///
 /// (string-length string?) -> int?
 ///
@@ -539,12 +603,12 @@ pub fn ends_with(value: &SteelString, suffix: &SteelString) -> bool {
 ///
 /// ```scheme
 /// > (string-length "apples") ;; => 6
-/// > (string-length "✅") ;; => 3
-/// > (string-length "🤖") ;; => 4
+/// > (string-length "✅") ;; => 1
+/// > (string-length "🤖") ;; => 1
 /// ```
 #[function(name = "string-length")]
 pub fn string_length(value: &SteelString) -> usize {
-    value.len()
+    value.chars().count()
 }
 
 /// Concatenates all of the given strings into one
@@ -565,202 +629,1537 @@ pub fn string_append(mut rest: RestArgsIter<'_, &SteelString>) -> Result<SteelVa
         .map(|x| SteelVal::StringV(x.into()))
 }
 
-#[cfg(test)]
-mod string_operation_tests {
-    use super::*;
-    use crate::rerrs::ErrorKind;
+// Strings are indexed by character, but stored as UTF-8, so finding the byte offset of an
+// index walks the string.
+fn byte_offset(value: &str, index: usize) -> Option<usize> {
+    value
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .chain(std::iter::once(value.len()))
+        .nth(index)
+}
 
-    // TODO combine these 3 macros into one
-    macro_rules! apply_tests_arity_too_many {
-        ($(($name:expr, $symbol:ident, $func:expr)),* $(,)?) => {
-            $(
-                #[test]
-                pub fn $symbol() {
-                    let args = vec![
-                        SteelVal::StringV("FOO".into()),
-                        SteelVal::StringV("BAR".into()),
-                    ];
-                    let res = $func(&args);
-                    let expected = ErrorKind::ArityMismatch;
-                    assert_eq!(res.unwrap_err().kind(), expected);
-                }
-            )*
-        };
-    }
+fn char_index(value: &str, offset: usize) -> usize {
+    value[..offset].chars().count()
+}
 
-    macro_rules! apply_tests_arity_too_few {
-        ($(($name:expr, $symbol:ident, $func:expr)),* $(,)?) => {
-            $(
-                #[test]
-                pub fn $symbol() {
-                    let args = vec![];
-                    let res = $func(&args);
-                    let expected = ErrorKind::ArityMismatch;
-                    assert_eq!(res.unwrap_err().kind(), expected);
-                }
-            )*
-        };
+fn non_negative_index(name: &str, index: isize) -> Result<usize> {
+    match usize::try_from(index) {
+        Ok(index) => Ok(index),
+        Err(_) => stop!(Generic => "{}: index must be non-negative, found: {}", name, index),
     }
+}
 
-    macro_rules! apply_tests_bad_arg {
-        ($(($name:expr, $symbol:ident, $func:expr)),* $(,)?) => {
-            $(
-                #[test]
-                pub fn $symbol() {
-                    let args = vec![SteelVal::NumV(10.0)];
-                    let res = $func(&args);
-                    let expected = ErrorKind::TypeMismatch;
-                    assert_eq!(res.unwrap_err().kind(), expected);
-                }
-            )*
-        };
-    }
+// Optional `start` and `end` character indices, defaulting to the whole string, as byte offsets
+fn string_range(
+    name: &str,
+    value: &str,
+    rest: impl Iterator<Item = Result<isize>>,
+) -> Result<(usize, usize)> {
+    let mut rest = rest.map(|index| non_negative_index(name, index?));
 
-    apply_tests_arity_too_many! {
-        ("string-upcase", string_upper_arity_too_many, steel_string_to_upper),
-        ("string-lowercase", string_lower_arity_too_many, steel_string_to_lower),
-        ("trim", trim_arity_too_many, steel_trim),
-        ("trim-start", trim_start_arity_too_many, steel_trim_start),
-        ("trim-end", trim_end_arity_too_many, steel_trim_end),
-        ("string->list", string_to_list_arity_too_many, steel_string_to_list),
-        ("split-whitespace", split_whitespace_arity_too_many, steel_split_whitespace),
-    }
+    let start = rest.next().transpose()?.unwrap_or(0);
 
-    apply_tests_arity_too_few! {
-        ("string-upcase", string_upper_arity_too_few, steel_string_to_upper),
-        ("string-lowercase", string_lower_arity_too_few, steel_string_to_lower),
-        ("trim", trim_arity_too_few, steel_trim),
-        ("trim-start", trim_start_arity_too_few, steel_trim_start),
-        ("trim-end", trim_end_arity_too_few, steel_trim_end),
-        ("string->list", string_to_list_arity_too_few, steel_string_to_list),
-        ("split-whitespace", split_whitespace_arity_too_few, steel_split_whitespace)
-    }
+    let Some(start_offset) = byte_offset(value, start) else {
+        stop!(Generic => "{}: index out of bounds: start: {}, string length: {}", name, start, value.chars().count());
+    };
 
-    apply_tests_bad_arg! {
-        ("string-upcase", string_upper_arity_takes_string, steel_string_to_upper),
-        ("string-lowercase", string_lower_arity_takes_string, steel_string_to_lower),
-        ("trim", trim_arity_takes_string, steel_trim),
-        ("trim-start", trim_start_arity_takes_string, steel_trim_start),
-        ("trim-end", trim_end_arity_takes_string, steel_trim_end),
-        ("string->list", string_to_list_takes_string, steel_string_to_list),
-        ("split-whitespace", split_whitespace_arity_takes_string, steel_split_whitespace)
-    }
+    let end_offset = match rest.next().transpose()? {
+        Some(end) if end < start => {
+            stop!(Generic => "{}: start must be less than or equal to end: start: {}, end: {}", name, start, end)
+        }
+        Some(end) => match value[start_offset..]
+            .char_indices()
+            .map(|(offset, _)| start_offset + offset)
+            .chain(std::iter::once(value.len()))
+            .nth(end - start)
+        {
+            Some(offset) => offset,
+            None => {
+                stop!(Generic => "{}: index out of bounds: end: {}, string length: {}", name, end, value.chars().count())
+            }
+        },
+        None => value.len(),
+    };
 
-    #[test]
-    fn string_append_test_normal() {
-        let args = vec![
-            SteelVal::StringV("foo".into()),
-            SteelVal::StringV("bar".into()),
-        ];
-        let res = steel_string_append(&args);
-        let expected = SteelVal::StringV("foobar".into());
-        assert_eq!(res.unwrap(), expected);
+    if let Some(extra) = rest.next() {
+        stop!(ArityMismatch => "{} expects at most 3 arguments, found an additional argument: {}", name, extra?);
     }
 
-    #[test]
-    fn string_append_test_takes_string() {
-        let args = vec![SteelVal::CharV('a'), SteelVal::CharV('b')];
-        let res = steel_string_append(&args);
-        let expected = ErrorKind::TypeMismatch;
-        assert_eq!(res.unwrap_err().kind(), expected);
-    }
+    Ok((start_offset, end_offset))
+}
 
-    #[test]
-    fn string_to_upper_normal() {
-        let args = vec![SteelVal::StringV("foobarbaz".into())];
-        let res = steel_string_to_upper(&args);
-        let expected = SteelVal::StringV("FOOBARBAZ".into());
-        assert_eq!(res.unwrap(), expected);
+fn char_foldcase(c: char) -> char {
+    let mut buffer = [0; 4];
+    let folded = caseless::default_case_fold_str(c.encode_utf8(&mut buffer));
+    let mut folded = folded.chars();
+    match (folded.next(), folded.next()) {
+        (Some(folded), None) => folded,
+        _ => c,
     }
+}
 
-    #[test]
-    fn string_to_upper_spaces() {
-        let args = vec![SteelVal::StringV("foo bar baz qux".into())];
-        let res = steel_string_to_upper(&args);
-        let expected = SteelVal::StringV("FOO BAR BAZ QUX".into());
-        assert_eq!(res.unwrap(), expected);
-    }
+/// Returns `#t` if the character is alphabetic.
+///
+/// (char-alphabetic? char?) -> bool?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (char-alphabetic? #\a) ;; => #true
+/// > (char-alphabetic? #\λ) ;; => #true
+/// > (char-alphabetic? #\1) ;; => #false
+/// ```
+#[function(name = "char-alphabetic?", constant = true)]
+pub fn char_alphabetic(c: char) -> bool {
+    c.is_alphabetic()
+}
 
-    #[test]
-    fn string_to_lower_normal() {
-        let args = vec![SteelVal::StringV("FOOBARBAZ".into())];
-        let res = steel_string_to_lower(&args);
-        let expected = SteelVal::StringV("foobarbaz".into());
-        assert_eq!(res.unwrap(), expected);
-    }
+fn is_decimal_digit(c: char) -> bool {
+    get_general_category(c) == GeneralCategory::DecimalNumber
+}
 
-    #[test]
-    fn string_to_lower_spaces() {
-        let args = vec![SteelVal::StringV("FOO BAR BAZ QUX".into())];
-        let res = steel_string_to_lower(&args);
-        let expected = SteelVal::StringV("foo bar baz qux".into());
-        assert_eq!(res.unwrap(), expected);
-    }
+/// Returns `#t` if the character is a decimal digit, in any script.
+///
+/// (char-numeric? char?) -> bool?
+#[function(name = "char-numeric?", constant = true)]
+pub fn char_numeric(c: char) -> bool {
+    is_decimal_digit(c)
+}
 
-    // TODO investigate this, assert_eq! fails without converting to string
-    #[test]
-    fn string_to_list_normal() {
-        let args = vec![SteelVal::StringV("foo".into())];
-        let res = steel_string_to_list(&args);
+/// Returns `#t` if the character is an uppercase letter.
+///
+/// (char-upper-case? char?) -> bool?
+#[function(name = "char-upper-case?", constant = true)]
+pub fn char_upper_case(c: char) -> bool {
+    c.is_uppercase()
+}
 
-        let expected = SteelVal::ListV(
-            vec![
-                SteelVal::CharV('f'),
-                SteelVal::CharV('o'),
-                SteelVal::CharV('o'),
-            ]
-            .into(),
-        );
+/// Returns `#t` if the character is a lowercase letter.
+///
+/// (char-lower-case? char?) -> bool?
+#[function(name = "char-lower-case?", constant = true)]
+pub fn char_lower_case(c: char) -> bool {
+    c.is_lowercase()
+}
 
-        assert_eq!(res.unwrap(), expected);
+/// Returns the value of the character as a decimal digit, or `#f` if it isn't one.
+/// Digits from any script are recognized.
+///
+/// (digit-value char?) -> (or/c int? #f)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (digit-value #\3) ;; => 3
+/// > (digit-value #\٤) ;; => 4
+/// > (digit-value #\a) ;; => #false
+/// ```
+#[function(name = "digit-value", constant = true)]
+pub fn digit_value(c: char) -> SteelVal {
+    if !is_decimal_digit(c) {
+        return SteelVal::BoolV(false);
     }
 
-    #[test]
-    fn string_to_list_empty() {
-        let args = vec![SteelVal::StringV("".into())];
-        let res = steel_string_to_list(&args);
-        let expected = SteelVal::ListV(List::new());
-        assert_eq!(res.unwrap(), expected);
-    }
+    // Decimal digits in Unicode come in runs of ten code points, from zero to nine, so the
+    // value of a digit is its position within the run of digits that it belongs to.
+    let position = (0..c as u32)
+        .rev()
+        .map_while(|previous| char::from_u32(previous).filter(|x| is_decimal_digit(*x)))
+        .count();
 
-    #[test]
-    fn trim_normal_no_changes() {
-        let args = vec![SteelVal::StringV("foo".into())];
-        let res = steel_trim(&args);
-        let expected = SteelVal::StringV("foo".into());
-        assert_eq!(res.unwrap(), expected);
-    }
+    SteelVal::IntV((position % 10) as isize)
+}
 
-    #[test]
-    fn trim_normal_trims_both_sides() {
-        let args = vec![SteelVal::StringV("      foo  ".into())];
-        let res = steel_trim(&args);
-        let expected = SteelVal::StringV("foo".into());
-        assert_eq!(res.unwrap(), expected);
+/// Returns the uppercase version of the character, or the character itself if its uppercase
+/// version isn't a single character.
+///
+/// (char-upcase char?) -> char?
+#[function(name = "char-upcase", constant = true)]
+pub fn char_upcase(c: char) -> char {
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(upper), None) => upper,
+        _ => c,
     }
+}
 
-    #[test]
-    fn trim_start_no_changes() {
-        let args = vec![SteelVal::StringV("foo".into())];
-        let res = steel_trim_start(&args);
-        let expected = SteelVal::StringV("foo".into());
-        assert_eq!(res.unwrap(), expected);
+/// Returns the lowercase version of the character, or the character itself if its lowercase
+/// version isn't a single character.
+///
+/// (char-downcase char?) -> char?
+#[function(name = "char-downcase", constant = true)]
+pub fn char_downcase(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => lower,
+        _ => c,
     }
+}
 
-    #[test]
-    fn trim_end_no_changes() {
-        let args = vec![SteelVal::StringV("foo".into())];
-        let res = steel_trim_end(&args);
-        let expected = SteelVal::StringV("foo".into());
-        assert_eq!(res.unwrap(), expected);
-    }
+/// Returns the case folded version of the character, which is what the case insensitive
+/// comparisons compare.
+///
+/// (char-foldcase char?) -> char?
+#[function(name = "char-foldcase", constant = true)]
+pub fn char_foldcase_primitive(c: char) -> char {
+    char_foldcase(c)
+}
 
-    #[test]
-    fn trim_start_normal_trims_left_side() {
-        let args = vec![SteelVal::StringV("      foo  ".into())];
-        let res = steel_trim_start(&args);
-        let expected = SteelVal::StringV("foo  ".into());
-        assert_eq!(res.unwrap(), expected);
+/// Returns the Unicode code point of the character.
+///
+/// (char->integer char?) -> int?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (char->integer #\a) ;; => 97
+/// > (char->integer #\λ) ;; => 955
+/// ```
+#[function(name = "char->integer", constant = true)]
+pub fn char_to_integer(c: char) -> isize {
+    c as isize
+}
+
+/// Returns the character with the given Unicode code point. Raises an error if the code point
+/// isn't a Unicode scalar value.
+///
+/// (integer->char int?) -> char?
+#[function(name = "integer->char", constant = true)]
+pub fn integer_to_char(n: isize) -> Result<SteelVal> {
+    match u32::try_from(n).ok().and_then(char::from_u32) {
+        Some(c) => Ok(SteelVal::CharV(c)),
+        None => stop!(ContractViolation => "integer->char: {} is not a valid code point", n),
+    }
+}
+
+#[function(name = "char<?", constant = true)]
+pub fn char_less_than(left: char, right: char) -> bool {
+    left < right
+}
+
+#[function(name = "char<=?", constant = true)]
+pub fn char_less_than_equal_to(left: char, right: char) -> bool {
+    left <= right
+}
+
+#[function(name = "char>?", constant = true)]
+pub fn char_greater_than(left: char, right: char) -> bool {
+    left > right
+}
+
+#[function(name = "char>=?", constant = true)]
+pub fn char_greater_than_equal_to(left: char, right: char) -> bool {
+    left >= right
+}
+
+#[function(name = "char-ci=?", constant = true)]
+pub fn char_ci_equals(left: char, right: char) -> bool {
+    char_foldcase(left) == char_foldcase(right)
+}
+
+#[function(name = "char-ci<?", constant = true)]
+pub fn char_ci_less_than(left: char, right: char) -> bool {
+    char_foldcase(left) < char_foldcase(right)
+}
+
+#[function(name = "char-ci<=?", constant = true)]
+pub fn char_ci_less_than_equal_to(left: char, right: char) -> bool {
+    char_foldcase(left) <= char_foldcase(right)
+}
+
+#[function(name = "char-ci>?", constant = true)]
+pub fn char_ci_greater_than(left: char, right: char) -> bool {
+    char_foldcase(left) > char_foldcase(right)
+}
+
+#[function(name = "char-ci>=?", constant = true)]
+pub fn char_ci_greater_than_equal_to(left: char, right: char) -> bool {
+    char_foldcase(left) >= char_foldcase(right)
+}
+
+/// Creates a new uppercased version of the input string, using the full Unicode case
+/// mappings - so the result can be longer than the input.
+///
+/// (string-upcase string?) -> string?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (string-upcase "straße") ;; => "STRASSE"
+/// ```
+#[function(name = "string-upcase")]
+pub fn string_upcase(value: &SteelString) -> String {
+    value.to_uppercase()
+}
+
+/// Creates a new lowercased version of the input string.
+///
+/// (string-downcase string?) -> string?
+#[function(name = "string-downcase")]
+pub fn string_downcase(value: &SteelString) -> String {
+    value.to_lowercase()
+}
+
+/// Creates a new case folded version of the input string. Strings that only differ in case
+/// fold to the same string.
+///
+/// (string-foldcase string?) -> string?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (string-foldcase "Straße") ;; => "strasse"
+/// ```
+#[function(name = "string-foldcase")]
+pub fn string_foldcase(value: &SteelString) -> String {
+    caseless::default_case_fold_str(value.as_str())
+}
+
+/// Returns a copy of the string between the character indices `start` (inclusive, defaults
+/// to 0) and `end` (exclusive, defaults to the length of the string).
+///
+/// (string-copy string? [start] [end]) -> string?
+#[function(name = "string-copy")]
+pub fn string_copy(value: &SteelString, rest: RestArgsIter<'_, isize>) -> Result<SteelVal> {
+    let (start, end) = string_range("string-copy", value, rest.0)?;
+    Ok(SteelVal::StringV(value[start..end].into()))
+}
+
+/// Returns the character index of the first occurrence of `pattern` in `string`, or `#f` if
+/// there is none.
+///
+/// (string-contains string? pattern) -> (or/c int? #f)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (string-contains "héllo world" "world") ;; => 6
+/// > (string-contains "hello" "xyz") ;; => #false
+/// ```
+#[function(name = "string-contains", constant = true)]
+pub fn string_contains(value: &SteelString, pattern: &SteelString) -> SteelVal {
+    match value.find(pattern.as_str()) {
+        Some(offset) => SteelVal::IntV(char_index(value, offset) as isize),
+        None => SteelVal::BoolV(false),
+    }
+}
+
+/// Searches for `pattern` in `string`, starting at the character index `start`. Returns the
+/// character index of the match, or `#f` if there is none.
+///
+/// (string-search-forward pattern string? start) -> (or/c int? #f)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (string-search-forward "a" "banana" 2) ;; => 3
+/// ```
+#[function(name = "string-search-forward", constant = true)]
+pub fn string_search_forward(
+    pattern: &SteelString,
+    value: &SteelString,
+    start: usize,
+) -> Result<SteelVal> {
+    let Some(offset) = byte_offset(value, start) else {
+        stop!(Generic => "string-search-forward: index out of bounds: start: {}, string length: {}", start, value.chars().count());
+    };
+
+    Ok(match value[offset..].find(pattern.as_str()) {
+        Some(found) => SteelVal::IntV(char_index(value, offset + found) as isize),
+        None => SteelVal::BoolV(false),
+    })
+}
+
+/// Searches backwards for `pattern` in `string`, ending at the character index `end`.
+/// Returns the character index of the end of the match, or `#f` if there is none.
+///
+/// (string-search-backward pattern string? end) -> (or/c int? #f)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (string-search-backward "a" "banana" 6) ;; => 6
+/// ```
+#[function(name = "string-search-backward", constant = true)]
+pub fn string_search_backward(
+    pattern: &SteelString,
+    value: &SteelString,
+    end: usize,
+) -> Result<SteelVal> {
+    let Some(offset) = byte_offset(value, end) else {
+        stop!(Generic => "string-search-backward: index out of bounds: end: {}, string length: {}", end, value.chars().count());
+    };
+
+    Ok(match value[..offset].rfind(pattern.as_str()) {
+        Some(found) => SteelVal::IntV(char_index(value, found + pattern.len()) as isize),
+        None => SteelVal::BoolV(false),
+    })
+}
+
+fn string_index_impl(
+    ctx: &mut VmCore,
+    args: &[SteelVal],
+    name: &str,
+    from_end: bool,
+) -> Result<SteelVal> {
+    if args.len() < 2 || args.len() > 4 {
+        stop!(ArityMismatch => "{} expects between 2 and 4 arguments, found: {}", name, args.len());
+    }
+
+    let value = SteelString::from_steelval(&args[0])?;
+    let bounds = args[2..].iter().map(isize::from_steelval);
+    let (start, end) = string_range(name, &value, bounds)?;
+
+    let mut matches = |c: char| -> Result<bool> {
+        match &args[1] {
+            SteelVal::CharV(target) => Ok(c == *target),
+            pred if pred.is_function() => Ok(ctx
+                .call_function_one_arg(pred, SteelVal::CharV(c))?
+                .is_truthy()),
+            other => {
+                stop!(TypeMismatch => "{} expects a character or a predicate, found: {}", name, other)
+            }
+        }
+    };
+
+    let mut found = None;
+    if from_end {
+        for (offset, c) in value[start..end].char_indices().rev() {
+            if matches(c)? {
+                found = Some(start + offset);
+                break;
+            }
+        }
+    } else {
+        for (offset, c) in value[start..end].char_indices() {
+            if matches(c)? {
+                found = Some(start + offset);
+                break;
+            }
+        }
+    }
+
+    Ok(match found {
+        Some(offset) => SteelVal::IntV(char_index(&value, offset) as isize),
+        None => SteelVal::BoolV(false),
+    })
+}
+
+fn string_index(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>> {
+    Some(string_index_impl(ctx, args, "string-index", false))
+}
+
+const STRING_INDEX_DOC: MarkdownDoc<'static> = MarkdownDoc(
+    r#"Returns the character index of the first character in the string that is equal to `char`,
+or satisfies `pred`, or `#f` if there is none. Only the characters between the indices `start`
+and `end` are searched.
+
+(string-index string? (or/c char? (-> char? bool?)) [start] [end]) -> (or/c int? #f)
+
+# Examples
+
+```scheme
+> (string-index "hello" #\l) ;; => 2
+> (string-index "hello" char-upper-case?) ;; => #false
+```
+"#,
+);
+
+fn string_index_right(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>> {
+    Some(string_index_impl(ctx, args, "string-index-right", true))
+}
+
+const STRING_INDEX_RIGHT_DOC: MarkdownDoc<'static> = MarkdownDoc(
+    r#"Returns the character index of the last character in the string that is equal to `char`,
+or satisfies `pred`, or `#f` if there is none. Only the characters between the indices `start`
+and `end` are searched.
+
+(string-index-right string? (or/c char? (-> char? bool?)) [start] [end]) -> (or/c int? #f)
+
+# Examples
+
+```scheme
+> (string-index-right "hello" #\l) ;; => 3
+```
+"#,
+);
+
+fn pad_char(name: &str, mut rest: RestArgsIter<'_, char>) -> Result<char> {
+    let c = rest.next().transpose()?.unwrap_or(' ');
+
+    if let Some(extra) = rest.next() {
+        stop!(ArityMismatch => "{} expects at most 3 arguments, found an additional argument: {}", name, extra?);
+    }
+
+    Ok(c)
+}
+
+/// Pads the string on the left to `len` characters. Strings longer than `len` are truncated,
+/// keeping their last `len` characters.
+///
+/// (string-pad string? len [char]) -> string?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (string-pad "42" 5) ;; => "   42"
+/// > (string-pad "42" 5 #\0) ;; => "00042"
+/// > (string-pad "12345" 3) ;; => "345"
+/// ```
+#[function(name = "string-pad")]
+pub fn string_pad(
+    value: &SteelString,
+    len: usize,
+    rest: RestArgsIter<'_, char>,
+) -> Result<SteelVal> {
+    let c = pad_char("string-pad", rest)?;
+    let count = value.chars().count();
+
+    let padded: String = if count >= len {
+        value.chars().skip(count - len).collect()
+    } else {
+        std::iter::repeat_n(c, len - count)
+            .chain(value.chars())
+            .collect()
+    };
+
+    Ok(SteelVal::StringV(padded.into()))
+}
+
+/// Pads the string on the right to `len` characters. Strings longer than `len` are truncated,
+/// keeping their first `len` characters.
+///
+/// (string-pad-right string? len [char]) -> string?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (string-pad-right "ab" 4 #\.) ;; => "ab.."
+/// > (string-pad-right "12345" 3) ;; => "123"
+/// ```
+#[function(name = "string-pad-right")]
+pub fn string_pad_right(
+    value: &SteelString,
+    len: usize,
+    rest: RestArgsIter<'_, char>,
+) -> Result<SteelVal> {
+    let c = pad_char("string-pad-right", rest)?;
+    let count = value.chars().count();
+
+    let padded: String = value
+        .chars()
+        .take(len)
+        .chain(std::iter::repeat_n(c, len.saturating_sub(count)))
+        .collect();
+
+    Ok(SteelVal::StringV(padded.into()))
+}
+
+/// Joins a list of strings, with the separator (defaults to `""`) between each of them.
+///
+/// (string-join (listof string?) [separator]) -> string?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (string-join '("a" "b" "c") ", ") ;; => "a, b, c"
+/// > (string-join '("a" "b")) ;; => "ab"
+/// ```
+#[function(name = "string-join")]
+pub fn string_join(
+    strings: &List<SteelVal>,
+    mut rest: RestArgsIter<'_, &SteelString>,
+) -> Result<SteelVal> {
+    let separator = rest.next().transpose()?;
+
+    if let Some(extra) = rest.next() {
+        stop!(ArityMismatch => "string-join expects at most 2 arguments, found an additional argument: {}", extra?);
+    }
+
+    let mut output = String::new();
+
+    for (i, value) in strings.iter().enumerate() {
+        let SteelVal::StringV(s) = value else {
+            stop!(TypeMismatch => "string-join expects a list of strings, found: {}", value);
+        };
+
+        if i > 0 {
+            if let Some(separator) = separator {
+                output.push_str(separator);
+            }
+        }
+
+        output.push_str(s);
+    }
+
+    Ok(SteelVal::StringV(output.into()))
+}
+
+/// Splits the string into its extended grapheme clusters - what a reader would see as
+/// a single character, such as a letter with its combining accents, or a flag.
+///
+/// (string->graphemes string?) -> (listof string?)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (string->graphemes "éa") ;; => '("é" "a")
+/// ```
+#[function(name = "string->graphemes")]
+pub fn string_to_graphemes(value: &SteelString) -> SteelVal {
+    value
+        .graphemes(true)
+        .map(|x| SteelVal::StringV(x.into()))
+        .collect::<List<_>>()
+        .into()
+}
+
+/// Returns the number of extended grapheme clusters in the string.
+///
+/// (string-grapheme-count string?) -> int?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (string-grapheme-count "🇺🇸") ;; => 1
+/// > (string-length "🇺🇸") ;; => 2
+/// ```
+#[function(name = "string-grapheme-count")]
+pub fn string_grapheme_count(value: &SteelString) -> usize {
+    value.graphemes(true).count()
+}
+
+/// Returns the string in Unicode Normalization Form C (canonical composition).
+///
+/// (string-normalize-nfc string?) -> string?
+#[function(name = "string-normalize-nfc")]
+pub fn string_normalize_nfc(value: &SteelString) -> String {
+    value.nfc().collect()
+}
+
+/// Returns the string in Unicode Normalization Form D (canonical decomposition).
+///
+/// (string-normalize-nfd string?) -> string?
+#[function(name = "string-normalize-nfd")]
+pub fn string_normalize_nfd(value: &SteelString) -> String {
+    value.nfd().collect()
+}
+
+/// Returns the string in Unicode Normalization Form KC (compatibility composition).
+///
+/// (string-normalize-nfkc string?) -> string?
+#[function(name = "string-normalize-nfkc")]
+pub fn string_normalize_nfkc(value: &SteelString) -> String {
+    value.nfkc().collect()
+}
+
+/// Returns the string in Unicode Normalization Form KD (compatibility decomposition).
+///
+/// (string-normalize-nfkd string?) -> string?
+#[function(name = "string-normalize-nfkd")]
+pub fn string_normalize_nfkd(value: &SteelString) -> String {
+    value.nfkd().collect()
+}
+
+fn check_cursor(name: &str, value: &str, cursor: usize) -> Result<()> {
+    if !value.is_char_boundary(cursor) {
+        stop!(Generic => "{}: invalid cursor: {}", name, cursor);
+    }
+    Ok(())
+}
+
+/// Returns the cursor at the start of the string.
+///
+/// Cursors are positions in a string that can be moved and dereferenced in constant time,
+/// unlike character indices. They are the byte offsets of the characters in the UTF-8
+/// encoding of the string - the same offsets as the spans returned by `steel/regex`.
+///
+/// (string-cursor-start string?) -> int?
+#[function(name = "string-cursor-start", constant = true)]
+pub fn string_cursor_start(_value: &SteelString) -> usize {
+    0
+}
+
+/// Returns the cursor past the end of the string.
+///
+/// (string-cursor-end string?) -> int?
+#[function(name = "string-cursor-end", constant = true)]
+pub fn string_cursor_end(value: &SteelString) -> usize {
+    value.len()
+}
+
+/// Returns the cursor of the character after the one at `cursor`.
+///
+/// (string-cursor-next string? cursor) -> int?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (string-cursor-next "λx" 0) ;; => 2
+/// ```
+#[function(name = "string-cursor-next", constant = true)]
+pub fn string_cursor_next(value: &SteelString, cursor: usize) -> Result<SteelVal> {
+    check_cursor("string-cursor-next", value, cursor)?;
+
+    match value[cursor..].chars().next() {
+        Some(c) => Ok(SteelVal::IntV((cursor + c.len_utf8()) as isize)),
+        None => stop!(Generic => "string-cursor-next: cursor is at the end of the string"),
+    }
+}
+
+/// Returns the cursor of the character before the one at `cursor`.
+///
+/// (string-cursor-prev string? cursor) -> int?
+#[function(name = "string-cursor-prev", constant = true)]
+pub fn string_cursor_prev(value: &SteelString, cursor: usize) -> Result<SteelVal> {
+    check_cursor("string-cursor-prev", value, cursor)?;
+
+    match value[..cursor].chars().next_back() {
+        Some(c) => Ok(SteelVal::IntV((cursor - c.len_utf8()) as isize)),
+        None => stop!(Generic => "string-cursor-prev: cursor is at the start of the string"),
+    }
+}
+
+/// Returns the character at `cursor`.
+///
+/// (string-cursor-ref string? cursor) -> char?
+#[function(name = "string-cursor-ref", constant = true)]
+pub fn string_cursor_ref(value: &SteelString, cursor: usize) -> Result<SteelVal> {
+    check_cursor("string-cursor-ref", value, cursor)?;
+
+    match value[cursor..].chars().next() {
+        Some(c) => Ok(SteelVal::CharV(c)),
+        None => stop!(Generic => "string-cursor-ref: cursor is at the end of the string"),
+    }
+}
+
+/// Converts a cursor into a character index.
+///
+/// (string-cursor->index string? cursor) -> int?
+#[function(name = "string-cursor->index", constant = true)]
+pub fn string_cursor_to_index(value: &SteelString, cursor: usize) -> Result<SteelVal> {
+    check_cursor("string-cursor->index", value, cursor)?;
+    Ok(SteelVal::IntV(char_index(value, cursor) as isize))
+}
+
+/// Converts a character index into a cursor.
+///
+/// (string-index->cursor string? index) -> int?
+#[function(name = "string-index->cursor", constant = true)]
+pub fn string_index_to_cursor(value: &SteelString, index: usize) -> Result<SteelVal> {
+    match byte_offset(value, index) {
+        Some(cursor) => Ok(SteelVal::IntV(cursor as isize)),
+        None => {
+            stop!(Generic => "string-index->cursor: index out of bounds: index: {}, string length: {}", index, value.chars().count())
+        }
+    }
+}
+
+/// Returns the part of the string between the cursors `start` and `end`.
+///
+/// (substring/cursors string? start end) -> string?
+#[function(name = "substring/cursors", constant = true)]
+pub fn substring_cursors(value: &SteelString, start: usize, end: usize) -> Result<SteelVal> {
+    check_cursor("substring/cursors", value, start)?;
+    check_cursor("substring/cursors", value, end)?;
+
+    if start > end {
+        stop!(Generic => "substring/cursors: start must be less than or equal to end: start: {}, end: {}", start, end);
+    }
+
+    Ok(SteelVal::StringV(value[start..end].into()))
+}
+
+/// A mutable, growable string, created by `make-mutable-string` or `string->mutable-string`.
+///
+/// Characters are stored unencoded, so `string-set!` and `mutable-string-ref` run in constant
+/// time, and `string-append!` runs in time proportional to what is appended - which makes
+/// mutable strings useful as string builders.
+#[derive(Clone, Default, PartialEq)]
+pub struct MutableString(Vec<char>);
+
+impl Custom for MutableString {
+    fn fmt(&self) -> Option<std::result::Result<String, std::fmt::Error>> {
+        Some(Ok(self.0.iter().collect()))
+    }
+
+    fn equality_hint(&self, other: &dyn CustomType) -> bool {
+        other
+            .as_any_ref()
+            .downcast_ref::<MutableString>()
+            .is_some_and(|other| self == other)
+    }
+}
+
+fn mutable_string<'a>(name: &str, value: &'a SteelVal) -> Result<RefMut<'a, MutableString>> {
+    match MutableString::as_mut_ref(value) {
+        Ok(value) => Ok(value),
+        Err(_) => stop!(TypeMismatch => "{} expects a mutable string, found: {}", name, value),
+    }
+}
+
+// The characters of either kind of string. Mutable strings are copied out, so that the source
+// can be the same mutable string as the destination.
+fn string_chars(name: &str, value: &SteelVal) -> Result<Vec<char>> {
+    match value {
+        SteelVal::StringV(s) => Ok(s.chars().collect()),
+        SteelVal::CharV(c) => Ok(vec![*c]),
+        _ => match MutableString::as_mut_ref(value) {
+            Ok(value) => Ok(value.0.clone()),
+            Err(_) => {
+                stop!(TypeMismatch => "{} expects a string or a mutable string, found: {}", name, value)
+            }
+        },
+    }
+}
+
+// Optional `start` and `end` indices into a sequence of `len` characters
+fn char_range(
+    name: &str,
+    len: usize,
+    rest: impl Iterator<Item = Result<isize>>,
+) -> Result<(usize, usize)> {
+    let mut rest = rest.map(|index| non_negative_index(name, index?));
+
+    let start = rest.next().transpose()?.unwrap_or(0);
+    let end = rest.next().transpose()?.unwrap_or(len);
+
+    if let Some(extra) = rest.next() {
+        stop!(ArityMismatch => "{}: found an unexpected additional argument: {}", name, extra?);
+    }
+
+    if end > len {
+        stop!(Generic => "{}: index out of bounds: end: {}, string length: {}", name, end, len);
+    }
+
+    if start > end {
+        stop!(Generic => "{}: start must be less than or equal to end: start: {}, end: {}", name, start, end);
+    }
+
+    Ok((start, end))
+}
+
+/// Creates a mutable string of length `k`, filled with `char` (defaults to `#\space`).
+///
+/// (make-mutable-string k [char]) -> mutable-string?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (mutable-string->string (make-mutable-string 3 #\x)) ;; => "xxx"
+/// ```
+#[function(name = "make-mutable-string")]
+pub fn make_mutable_string(k: usize, rest: RestArgsIter<'_, char>) -> Result<SteelVal> {
+    let c = pad_char("make-mutable-string", rest)?;
+    MutableString(vec![c; k]).into_steelval()
+}
+
+/// Creates a mutable string with a copy of the characters of `string` between the character
+/// indices `start` (defaults to 0) and `end` (defaults to the length of the string).
+///
+/// (string->mutable-string string? [start] [end]) -> mutable-string?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (define s (string->mutable-string "hello"))
+/// > (string-set! s 0 #\j)
+/// > (mutable-string->string s) ;; => "jello"
+/// ```
+#[function(name = "string->mutable-string")]
+pub fn string_to_mutable_string(
+    value: &SteelString,
+    rest: RestArgsIter<'_, isize>,
+) -> Result<SteelVal> {
+    let (start, end) = string_range("string->mutable-string", value, rest.0)?;
+    MutableString(value[start..end].chars().collect()).into_steelval()
+}
+
+/// Creates an immutable string with a copy of the characters of the mutable string between the
+/// indices `start` (defaults to 0) and `end` (defaults to the length of the string).
+///
+/// (mutable-string->string mutable-string? [start] [end]) -> string?
+#[function(name = "mutable-string->string")]
+pub fn mutable_string_to_string(
+    value: &SteelVal,
+    rest: RestArgsIter<'_, isize>,
+) -> Result<SteelVal> {
+    let value = mutable_string("mutable-string->string", value)?;
+    let (start, end) = char_range("mutable-string->string", value.0.len(), rest.0)?;
+    Ok(SteelVal::StringV(
+        value.0[start..end].iter().collect::<String>().into(),
+    ))
+}
+
+/// Returns `#t` if the value is a mutable string.
+///
+/// (mutable-string? any/c) -> bool?
+#[function(name = "mutable-string?")]
+pub fn is_mutable_string(value: &SteelVal) -> bool {
+    MutableString::as_mut_ref(value).is_ok()
+}
+
+/// Returns the number of characters in the mutable string.
+///
+/// (mutable-string-length mutable-string?) -> int?
+#[function(name = "mutable-string-length")]
+pub fn mutable_string_length(value: &SteelVal) -> Result<SteelVal> {
+    let value = mutable_string("mutable-string-length", value)?;
+    Ok(SteelVal::IntV(value.0.len() as isize))
+}
+
+/// Returns the character at index `k` of the mutable string.
+///
+/// (mutable-string-ref mutable-string? k) -> char?
+#[function(name = "mutable-string-ref")]
+pub fn mutable_string_ref(value: &SteelVal, k: usize) -> Result<SteelVal> {
+    let value = mutable_string("mutable-string-ref", value)?;
+
+    match value.0.get(k) {
+        Some(c) => Ok(SteelVal::CharV(*c)),
+        None => {
+            stop!(Generic => "mutable-string-ref: index out of bounds: index: {}, string length: {}", k, value.0.len())
+        }
+    }
+}
+
+/// Replaces the character at index `k` of the mutable string with `char`.
+///
+/// (string-set! mutable-string? k char?) -> void?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (define s (make-mutable-string 2 #\a))
+/// > (string-set! s 1 #\b)
+/// > (mutable-string->string s) ;; => "ab"
+/// ```
+#[function(name = "string-set!")]
+pub fn string_set(value: &SteelVal, k: usize, c: char) -> Result<SteelVal> {
+    let mut value = mutable_string("string-set!", value)?;
+    let len = value.0.len();
+
+    match value.0.get_mut(k) {
+        Some(slot) => *slot = c,
+        None => {
+            stop!(Generic => "string-set!: index out of bounds: index: {}, string length: {}", k, len)
+        }
+    }
+
+    Ok(SteelVal::Void)
+}
+
+/// Replaces the characters of the mutable string between the indices `start` (defaults to 0)
+/// and `end` (defaults to the length of the string) with `char`.
+///
+/// (string-fill! mutable-string? char? [start] [end]) -> void?
+#[function(name = "string-fill!")]
+pub fn string_fill(value: &SteelVal, c: char, rest: RestArgsIter<'_, isize>) -> Result<SteelVal> {
+    let mut value = mutable_string("string-fill!", value)?;
+    let (start, end) = char_range("string-fill!", value.0.len(), rest.0)?;
+    value.0[start..end].fill(c);
+    Ok(SteelVal::Void)
+}
+
+/// Copies the characters of `from` between the indices `start` (defaults to 0) and `end`
+/// (defaults to the length of `from`) into the mutable string `to`, starting at index `at`.
+/// The source can be either kind of string, including `to` itself.
+///
+/// (string-copy! to at from [start] [end]) -> void?
+///
+/// * to : mutable-string?
+/// * at : int?
+/// * from : (or/c string? mutable-string?)
+///
+/// # Examples
+///
+/// ```scheme
+/// > (define s (string->mutable-string "hello"))
+/// > (string-copy! s 1 "ipp")
+/// > (mutable-string->string s) ;; => "hippo"
+/// ```
+#[function(name = "string-copy!")]
+pub fn string_copy_mut(
+    to: &SteelVal,
+    at: usize,
+    from: &SteelVal,
+    rest: RestArgsIter<'_, isize>,
+) -> Result<SteelVal> {
+    let source = string_chars("string-copy!", from)?;
+    let (start, end) = char_range("string-copy!", source.len(), rest.0)?;
+
+    let mut to = mutable_string("string-copy!", to)?;
+
+    if at + (end - start) > to.0.len() {
+        stop!(Generic => "string-copy!: not enough room in the destination: at: {}, characters: {}, string length: {}", at, end - start, to.0.len());
+    }
+
+    to.0[at..at + (end - start)].copy_from_slice(&source[start..end]);
+    Ok(SteelVal::Void)
+}
+
+/// Appends each of the values, which can be strings, characters or mutable strings, to the end
+/// of the mutable string. The mutable string grows as needed, so building up a string with
+/// repeated calls takes time proportional to the length of the result.
+///
+/// (string-append! mutable-string? value ...) -> void?
+///
+/// # Examples
+///
+/// ```scheme
+/// > (define s (make-mutable-string 0))
+/// > (string-append! s "a" #\b "c")
+/// > (mutable-string->string s) ;; => "abc"
+/// ```
+#[native(name = "string-append!", arity = "AtLeast(1)")]
+pub fn string_append_mut(args: &[SteelVal]) -> Result<SteelVal> {
+    let (value, rest) = args.split_first().unwrap();
+
+    for arg in rest {
+        match arg {
+            SteelVal::StringV(s) => mutable_string("string-append!", value)?.0.extend(s.chars()),
+            SteelVal::CharV(c) => mutable_string("string-append!", value)?.0.push(*c),
+            _ => {
+                let chars = string_chars("string-append!", arg)?;
+                mutable_string("string-append!", value)?.0.extend(chars);
+            }
+        }
+    }
+
+    // Check the type when there was nothing to append
+    mutable_string("string-append!", value)?;
+
+    Ok(SteelVal::Void)
+}
+
+// Values printed with `~e` are cut off after this many characters, like in error messages
+const ERROR_PRINT_WIDTH: usize = 256;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Alignment {
+    Left,
+    Right,
+    Center,
+}
+
+struct Directive {
+    alignment: Option<Alignment>,
+    pad: Option<char>,
+    width: Option<usize>,
+    precision: Option<usize>,
+    radix: Option<u32>,
+}
+
+// Widths and precisions are allocated up front, so anything past this is refused rather than
+// letting a typo in a format string exhaust memory
+const MAX_DIRECTIVE_WIDTH: usize = 1 << 16;
+
+fn parse_digits(
+    name: &str,
+    option: &str,
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+) -> Result<Option<usize>> {
+    let mut digits = None;
+
+    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+        chars.next();
+
+        let value = digits
+            .unwrap_or(0usize)
+            .checked_mul(10)
+            .and_then(|x| x.checked_add(digit as usize));
+
+        match value {
+            Some(value) if value <= MAX_DIRECTIVE_WIDTH => digits = Some(value),
+            _ => {
+                stop!(Generic => "{}: the {} of a directive can be at most {}", name, option, MAX_DIRECTIVE_WIDTH)
+            }
+        }
+    }
+
+    Ok(digits)
+}
+
+// Parses the options between the `~` and the letter of a directive
+fn parse_directive(
+    name: &str,
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+) -> Result<Directive> {
+    let alignment = match chars.peek() {
+        Some('<') => Some(Alignment::Left),
+        Some('>') => Some(Alignment::Right),
+        Some('^') => Some(Alignment::Center),
+        _ => None,
+    };
+
+    if alignment.is_some() {
+        chars.next();
+    }
+
+    let pad = match chars.peek() {
+        Some('\'') => {
+            chars.next();
+            chars.next()
+        }
+        Some('0') => {
+            chars.next();
+            Some('0')
+        }
+        _ => None,
+    };
+
+    let width = parse_digits(name, "width", chars)?;
+
+    let precision = if chars.peek() == Some(&'.') {
+        chars.next();
+        parse_digits(name, "precision", chars)?
+    } else {
+        None
+    };
+
+    let radix = if chars.peek() == Some(&'#') {
+        chars.next();
+        parse_digits(name, "radix", chars)?.map(|x| x as u32)
+    } else {
+        None
+    };
+
+    Ok(Directive {
+        alignment,
+        pad,
+        width,
+        precision,
+        radix,
+    })
+}
+
+fn pad_to_width(output: &mut String, text: &str, directive: &Directive, default: Alignment) {
+    let len = text.chars().count();
+    let missing = directive.width.unwrap_or(0).saturating_sub(len);
+    let pad = directive.pad.unwrap_or(' ');
+
+    let (before, after) = match directive.alignment.unwrap_or(default) {
+        Alignment::Left => (0, missing),
+        Alignment::Right => (missing, 0),
+        Alignment::Center => (missing / 2, missing - missing / 2),
+    };
+
+    // Zeros go between the sign and the digits of a number
+    let text = if pad == '0' && before > 0 && (text.starts_with('-') || text.starts_with('+')) {
+        output.push_str(&text[..1]);
+        &text[1..]
+    } else {
+        text
+    };
+
+    output.extend(std::iter::repeat_n(pad, before));
+    output.push_str(text);
+    output.extend(std::iter::repeat_n(pad, after));
+}
+
+fn format_real(
+    name: &str,
+    value: &SteelVal,
+    precision: Option<usize>,
+    radix: Option<u32>,
+) -> Result<String> {
+    if !numberp(value) {
+        stop!(TypeMismatch => "{}: ~r expects a number, found: {}", name, value);
+    }
+
+    match (precision, radix) {
+        (Some(_), Some(radix)) if radix != 10 => {
+            stop!(Generic => "{}: ~r can't use a precision with a radix other than 10", name)
+        }
+        (Some(precision), _) => match real_to_f64(value) {
+            Some(number) if number.is_finite() => Ok(format!("{number:.precision$}")),
+            Some(_) => format_number(value, 10),
+            None => {
+                stop!(TypeMismatch => "{}: ~r with a precision expects a real number, found: {}", name, value)
+            }
+        },
+        (None, radix) => {
+            let radix = radix.unwrap_or(10);
+            if !(2..=36).contains(&radix) {
+                stop!(Generic => "{}: radix must be between 2 and 36, found: {}", name, radix);
+            }
+            format_number(value, radix)
+        }
+    }
+}
+
+/// Formats the arguments according to the directives in `template`, see `format`.
+pub(crate) fn format_directives(name: &str, template: &str, args: &[SteelVal]) -> Result<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut chars = template.chars().peekable();
+
+    // Characters since the last newline, for `~t`
+    let mut line_start = 0;
+
+    let mut next_arg = |directive: char| match args.next() {
+        Some(arg) => Ok(arg),
+        None => {
+            stop!(ArityMismatch => "{}: no argument left for ~{} in the format string: {:?}", name, directive, template)
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        if c != '~' {
+            if c == '\n' {
+                line_start = output.len() + 1;
+            }
+            output.push(c);
+            continue;
+        }
+
+        let directive = parse_directive(name, &mut chars)?;
+
+        let Some(conversion) = chars.next() else {
+            stop!(Generic => "{}: incomplete directive at the end of the format string: {:?}", name, template);
+        };
+
+        if directive.radix.is_some() && !matches!(conversion, 'r' | 'R') {
+            stop!(Generic => "{}: only ~r takes a radix, found: ~{}", name, conversion);
+        }
+
+        match conversion {
+            'a' | 'A' | 's' | 'S' | 'v' | 'V' | 'e' | 'E' => {
+                let arg = next_arg(conversion)?;
+
+                let mut text = match conversion.to_ascii_lowercase() {
+                    'a' => print_to_string(arg, PrintMode::Display),
+                    's' => print_to_string(arg, PrintMode::Write),
+                    _ => arg.to_string(),
+                };
+
+                let limit = match conversion.to_ascii_lowercase() {
+                    'e' => directive.precision.or(Some(ERROR_PRINT_WIDTH)),
+                    _ => directive.precision,
+                };
+
+                if let Some(limit) = limit {
+                    if let Some((offset, _)) = text.char_indices().nth(limit) {
+                        text.truncate(offset);
+                        if conversion.eq_ignore_ascii_case(&'e') && directive.precision.is_none() {
+                            text.push_str("...");
+                        }
+                    }
+                }
+
+                pad_to_width(&mut output, &text, &directive, Alignment::Left);
+            }
+            'r' | 'R' | 'b' | 'B' | 'o' | 'O' | 'x' | 'X' => {
+                let arg = next_arg(conversion)?;
+
+                let radix = match conversion.to_ascii_lowercase() {
+                    'b' => Some(2),
+                    'o' => Some(8),
+                    'x' => Some(16),
+                    _ => directive.radix,
+                };
+
+                if directive.precision.is_some() && !conversion.eq_ignore_ascii_case(&'r') {
+                    stop!(Generic => "{}: ~{} doesn't take a precision", name, conversion);
+                }
+
+                let text = format_real(name, arg, directive.precision, radix)?;
+
+                pad_to_width(&mut output, &text, &directive, Alignment::Right);
+            }
+            'c' | 'C' => match next_arg(conversion)? {
+                SteelVal::CharV(c) => pad_to_width(
+                    &mut output,
+                    c.encode_utf8(&mut [0; 4]),
+                    &directive,
+                    Alignment::Left,
+                ),
+                other => {
+                    stop!(TypeMismatch => "{}: ~c expects a character, found: {}", name, other)
+                }
+            },
+            't' | 'T' => {
+                let column = output[line_start..].chars().count();
+                let target = directive.width.unwrap_or(0);
+                let pad = directive.pad.unwrap_or(' ');
+
+                if column < target {
+                    output.extend(std::iter::repeat_n(pad, target - column));
+                } else {
+                    output.push(pad);
+                }
+            }
+            '%' | 'n' => {
+                output.push('\n');
+                line_start = output.len();
+            }
+            '~' => output.push('~'),
+            other => {
+                stop!(Generic => "{}: unknown directive ~{} in the format string: {:?}", name, other, template)
+            }
+        }
+    }
+
+    let remaining = args.count();
+    if remaining > 0 {
+        stop!(ArityMismatch => "{}: the format string {:?} has {} more argument(s) than it uses", name, template, remaining);
+    }
+
+    Ok(output)
+}
+
+/// Formats the arguments into a string according to the directives in the format string.
+///
+/// (format format-string args ...) -> string?
+///
+/// * format-string : string?
+/// * args : any/c
+///
+/// Each directive starts with a `~`, and consumes one argument unless noted otherwise:
+///
+/// * `~a` - the argument as `display` would print it
+/// * `~s` - the argument as `write` would print it
+/// * `~v` - the argument as the repl would print it
+/// * `~e` - like `~v`, but cut off after 256 characters, like in error messages
+/// * `~r` - a number, in base 10 unless a radix is given
+/// * `~b`, `~o`, `~x` - a number, in binary, octal or hexadecimal
+/// * `~c` - a character
+/// * `~t` - pads the current line up to the column given as the width, without an argument
+/// * `~n` or `~%` - a newline, without an argument
+/// * `~~` - a tilde, without an argument
+///
+/// Between the `~` and the letter, directives take options in the form
+/// `[align][pad][width][.precision][#radix]`:
+///
+/// * `align` - `<` to pad on the right, `>` to pad on the left or `^` to center. Numbers are
+///   aligned to the right by default, and everything else to the left.
+/// * `pad` - `'` followed by the padding character, or `0` to pad a number with zeros after its
+///   sign. Defaults to a space.
+/// * `width` - the minimum number of characters to print, at most 65536
+/// * `precision` - for `~r`, the number of digits after the decimal point. For everything
+///   else, the maximum number of characters to print. At most 65536.
+/// * `radix` - for `~r`, the base to print the number in, between 2 and 36
+///
+/// It is an error for the format string to use more or fewer arguments than are given.
+///
+/// # Examples
+///
+/// ```scheme
+/// > (format "~a and ~s" "apples" "oranges") ;; => "apples and \"oranges\""
+/// > (format "~5a|~>5a|~^5a|" 'ab 'ab 'ab) ;; => "ab   |   ab| ab  |"
+/// > (format "~.2r" 3.14159) ;; => "3.14"
+/// > (format "~08.3r" -2.5) ;; => "-002.500"
+/// > (format "~x ~#36r" 255 35) ;; => "ff z"
+/// > (format "~a~8t~a" "key" "value") ;; => "key     value"
+/// ```
+#[native(name = "format", arity = "AtLeast(1)")]
+pub fn format(args: &[SteelVal]) -> Result<SteelVal> {
+    let (template, args) = args.split_first().unwrap();
+
+    let SteelVal::StringV(template) = template else {
+        stop!(TypeMismatch => "format expects a format string, found: {}", template);
+    };
+
+    format_directives("format", template, args).map(|x| SteelVal::StringV(x.into()))
+}
+
+#[cfg(test)]
+mod string_operation_tests {
+    use super::*;
+    use crate::rerrs::ErrorKind;
+
+    // TODO combine these 3 macros into one
+    macro_rules! apply_tests_arity_too_many {
+        ($(($name:expr, $symbol:ident, $func:expr)),* $(,)?) => {
+            $(
+                #[test]
+                pub fn $symbol() {
+                    let args = vec![
+                        SteelVal::StringV("FOO".into()),
+                        SteelVal::StringV("BAR".into()),
+                    ];
+                    let res = $func(&args);
+                    let expected = ErrorKind::ArityMismatch;
+                    assert_eq!(res.unwrap_err().kind(), expected);
+                }
+            )*
+        };
+    }
+
+    macro_rules! apply_tests_arity_too_few {
+        ($(($name:expr, $symbol:ident, $func:expr)),* $(,)?) => {
+            $(
+                #[test]
+                pub fn $symbol() {
+                    let args = vec![];
+                    let res = $func(&args);
+                    let expected = ErrorKind::ArityMismatch;
+                    assert_eq!(res.unwrap_err().kind(), expected);
+                }
+            )*
+        };
+    }
+
+    macro_rules! apply_tests_bad_arg {
+        ($(($name:expr, $symbol:ident, $func:expr)),* $(,)?) => {
+            $(
+                #[test]
+                pub fn $symbol() {
+                    let args = vec![SteelVal::NumV(10.0)];
+                    let res = $func(&args);
+                    let expected = ErrorKind::TypeMismatch;
+                    assert_eq!(res.unwrap_err().kind(), expected);
+                }
+            )*
+        };
+    }
+
+    apply_tests_arity_too_many! {
+        ("string-upcase", string_upper_arity_too_many, steel_string_to_upper),
+        ("string-lowercase", string_lower_arity_too_many, steel_string_to_lower),
+        ("trim", trim_arity_too_many, steel_trim),
+        ("trim-start", trim_start_arity_too_many, steel_trim_start),
+        ("trim-end", trim_end_arity_too_many, steel_trim_end),
+        ("string->list", string_to_list_arity_too_many, steel_string_to_list),
+        ("split-whitespace", split_whitespace_arity_too_many, steel_split_whitespace),
+    }
+
+    apply_tests_arity_too_few! {
+        ("string-upcase", string_upper_arity_too_few, steel_string_to_upper),
+        ("string-lowercase", string_lower_arity_too_few, steel_string_to_lower),
+        ("trim", trim_arity_too_few, steel_trim),
+        ("trim-start", trim_start_arity_too_few, steel_trim_start),
+        ("trim-end", trim_end_arity_too_few, steel_trim_end),
+        ("string->list", string_to_list_arity_too_few, steel_string_to_list),
+        ("split-whitespace", split_whitespace_arity_too_few, steel_split_whitespace)
+    }
+
+    apply_tests_bad_arg! {
+        ("string-upcase", string_upper_arity_takes_string, steel_string_to_upper),
+        ("string-lowercase", string_lower_arity_takes_string, steel_string_to_lower),
+        ("trim", trim_arity_takes_string, steel_trim),
+        ("trim-start", trim_start_arity_takes_string, steel_trim_start),
+        ("trim-end", trim_end_arity_takes_string, steel_trim_end),
+        ("string->list", string_to_list_takes_string, steel_string_to_list),
+        ("split-whitespace", split_whitespace_arity_takes_string, steel_split_whitespace)
+    }
+
+    #[test]
+    fn string_append_test_normal() {
+        let args = vec![
+            SteelVal::StringV("foo".into()),
+            SteelVal::StringV("bar".into()),
+        ];
+        let res = steel_string_append(&args);
+        let expected = SteelVal::StringV("foobar".into());
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    #[test]
+    fn string_append_test_takes_string() {
+        let args = vec![SteelVal::CharV('a'), SteelVal::CharV('b')];
+        let res = steel_string_append(&args);
+        let expected = ErrorKind::TypeMismatch;
+        assert_eq!(res.unwrap_err().kind(), expected);
+    }
+
+    #[test]
+    fn string_to_upper_normal() {
+        let args = vec![SteelVal::StringV("foobarbaz".into())];
+        let res = steel_string_to_upper(&args);
+        let expected = SteelVal::StringV("FOOBARBAZ".into());
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    #[test]
+    fn string_to_upper_spaces() {
+        let args = vec![SteelVal::StringV("foo bar baz qux".into())];
+        let res = steel_string_to_upper(&args);
+        let expected = SteelVal::StringV("FOO BAR BAZ QUX".into());
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    #[test]
+    fn string_to_lower_normal() {
+        let args = vec![SteelVal::StringV("FOOBARBAZ".into())];
+        let res = steel_string_to_lower(&args);
+        let expected = SteelVal::StringV("foobarbaz".into());
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    #[test]
+    fn string_to_lower_spaces() {
+        let args = vec![SteelVal::StringV("FOO BAR BAZ QUX".into())];
+        let res = steel_string_to_lower(&args);
+        let expected = SteelVal::StringV("foo bar baz qux".into());
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    // TODO investigate this, assert_eq! fails without converting to string
+    #[test]
+    fn string_to_list_normal() {
+        let args = vec![SteelVal::StringV("foo".into())];
+        let res = steel_string_to_list(&args);
+
+        let expected = SteelVal::ListV(
+            vec![
+                SteelVal::CharV('f'),
+                SteelVal::CharV('o'),
+                SteelVal::CharV('o'),
+            ]
+            .into(),
+        );
+
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    #[test]
+    fn string_to_list_empty() {
+        let args = vec![SteelVal::StringV("".into())];
+        let res = steel_string_to_list(&args);
+        let expected = SteelVal::ListV(List::new());
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    #[test]
+    fn trim_normal_no_changes() {
+        let args = vec![SteelVal::StringV("foo".into())];
+        let res = steel_trim(&args);
+        let expected = SteelVal::StringV("foo".into());
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    #[test]
+    fn trim_normal_trims_both_sides() {
+        let args = vec![SteelVal::StringV("      foo  ".into())];
+        let res = steel_trim(&args);
+        let expected = SteelVal::StringV("foo".into());
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    #[test]
+    fn trim_start_no_changes() {
+        let args = vec![SteelVal::StringV("foo".into())];
+        let res = steel_trim_start(&args);
+        let expected = SteelVal::StringV("foo".into());
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    #[test]
+    fn trim_end_no_changes() {
+        let args = vec![SteelVal::StringV("foo".into())];
+        let res = steel_trim_end(&args);
+        let expected = SteelVal::StringV("foo".into());
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    #[test]
+    fn trim_start_normal_trims_left_side() {
+        let args = vec![SteelVal::StringV("      foo  ".into())];
+        let res = steel_trim_start(&args);
+        let expected = SteelVal::StringV("foo  ".into());
+        assert_eq!(res.unwrap(), expected);
     }
 
     #[test]
@@ -797,4 +2196,118 @@ mod string_operation_tests {
         );
         assert_eq!(res.unwrap(), expected);
     }
+
+    #[test]
+    fn string_length_counts_characters() {
+        let args = vec![SteelVal::StringV("héllo🤖".into())];
+        let res = steel_string_length(&args);
+        let expected = SteelVal::IntV(6);
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    #[test]
+    fn substring_uses_character_indices() {
+        let args = vec![
+            SteelVal::StringV("λλx".into()),
+            SteelVal::IntV(1),
+            SteelVal::IntV(3),
+        ];
+        let res = steel_substring(&args);
+        let expected = SteelVal::StringV("λx".into());
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    #[test]
+    fn substring_out_of_bounds() {
+        let args = vec![
+            SteelVal::StringV("λλx".into()),
+            SteelVal::IntV(1),
+            SteelVal::IntV(4),
+        ];
+        let res = steel_substring(&args);
+        let expected = ErrorKind::Generic;
+        assert_eq!(res.unwrap_err().kind(), expected);
+    }
+
+    #[test]
+    fn string_cursor_next_rejects_invalid_cursor() {
+        let args = vec![SteelVal::StringV("λx".into()), SteelVal::IntV(1)];
+        let res = steel_string_cursor_next(&args);
+        let expected = ErrorKind::Generic;
+        assert_eq!(res.unwrap_err().kind(), expected);
+    }
+
+    #[test]
+    fn digit_value_other_scripts() {
+        assert_eq!(digit_value('٣'), SteelVal::IntV(3));
+        assert_eq!(digit_value('９'), SteelVal::IntV(9));
+        assert_eq!(digit_value('𝟗'), SteelVal::IntV(9));
+        assert_eq!(digit_value('½'), SteelVal::BoolV(false));
+        assert_eq!(digit_value('Ⅲ'), SteelVal::BoolV(false));
+    }
+
+    #[test]
+    fn string_append_mut_grows_the_string() {
+        let value = MutableString::default().into_steelval().unwrap();
+        let args = vec![
+            value.clone(),
+            SteelVal::StringV("λa".into()),
+            SteelVal::CharV('b'),
+        ];
+        string_append_mut(&args).unwrap();
+
+        let res = steel_mutable_string_to_string(&[value]);
+        let expected = SteelVal::StringV("λab".into());
+        assert_eq!(res.unwrap(), expected);
+    }
+
+    #[test]
+    fn string_set_requires_mutable_string() {
+        let args = vec![
+            SteelVal::StringV("foo".into()),
+            SteelVal::IntV(0),
+            SteelVal::CharV('b'),
+        ];
+        let res = steel_string_set(&args);
+        let expected = ErrorKind::TypeMismatch;
+        assert_eq!(res.unwrap_err().kind(), expected);
+    }
+
+    #[test]
+    fn format_pads_numbers_after_the_sign() {
+        let res = format_directives(
+            "format",
+            "~06r|~^7r",
+            &[SteelVal::IntV(-42), SteelVal::IntV(1)],
+        );
+        assert_eq!(res.unwrap(), "-00042|   1   ");
+    }
+
+    #[test]
+    fn format_rejects_overflowing_widths() {
+        let res = format_directives("format", "~99999999999999999999999a", &[SteelVal::IntV(1)]);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::Generic);
+
+        let res = format_directives("format", "~.99999999999999999999999r", &[SteelVal::IntV(1)]);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::Generic);
+    }
+
+    #[test]
+    fn format_caps_widths_and_precisions() {
+        let res = format_directives("format", "~1000000000a", &[SteelVal::IntV(1)]);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::Generic);
+
+        let res = format_directives("format", "~.1000000000r", &[SteelVal::NumV(1.5)]);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::Generic);
+
+        let res = format_directives("format", "~65536t", &[]);
+        assert_eq!(res.unwrap().len(), 65536);
+    }
+
+    #[test]
+    fn format_requires_every_argument() {
+        let res = format_directives("format", "~a", &[SteelVal::IntV(1), SteelVal::IntV(2)]);
+        let expected = ErrorKind::ArityMismatch;
+        assert_eq!(res.unwrap_err().kind(), expected);
+    }
 }
@@ -9,6 +9,7 @@ use codespan_reporting::term;
 use codespan_reporting::term::termcolor::{ColorChoice, NoColor, StandardStream};
 
 use crate::parser::span::Span;
+use crate::SteelVal;
 
 use std::fmt;
 
@@ -20,6 +21,17 @@ struct Repr {
     pub span: Option<Span>,
     // pub source: Option<Rc<PathBuf>>,
     pub stack_trace: Option<DehydratedStackTrace>,
+    pub payload: Option<Payload>,
+    pub handler_depth: Option<usize>,
+}
+
+/// Scheme values attached to an error raised from Scheme code
+#[derive(Clone, Debug, PartialEq)]
+enum Payload {
+    /// An object passed to `raise` that is not an error object itself
+    Raised(SteelVal),
+    /// The message and irritants passed to `error`
+    Irritants(String, Vec<SteelVal>),
 }
 
 impl Repr {
@@ -49,6 +61,8 @@ pub enum ErrorKind {
     Parse,
     Infallible,
     Generic,
+    /// Evaluation was stopped through the engine's interrupt handle
+    Interrupted,
 }
 
 impl ErrorKind {
@@ -66,6 +80,7 @@ impl ErrorKind {
             Parse => "E09",
             Infallible => "E10",
             Generic => "E11",
+            Interrupted => "E12",
         }
     }
 }
@@ -84,6 +99,8 @@ impl From<std::io::Error> for Repr {
             span: None,
             // source: None,
             stack_trace: None,
+            payload: None,
+            handler_depth: None,
         }
     }
 }
@@ -108,6 +125,8 @@ impl From<Infallible> for Repr {
             span: None,
             // source: None,
             stack_trace: None,
+            payload: None,
+            handler_depth: None,
         }
     }
 }
@@ -135,6 +154,8 @@ impl From<ParseError> for Repr {
             span,
             // source: source.clone(),
             stack_trace: None,
+            payload: None,
+            handler_depth: None,
         }
     }
 }
@@ -186,10 +207,57 @@ impl SteelErr {
                 span: None,
                 // source: None,
                 stack_trace: None,
+                payload: None,
+                handler_depth: None,
             }),
         }
     }
 
+    /// An error raised by `(raise value)`, for a `value` that is not already an error.
+    pub fn raised(value: SteelVal) -> Self {
+        let mut error = SteelErr::new(ErrorKind::Generic, format!("uncaught exception: {value}"));
+        error.repr.payload = Some(Payload::Raised(value));
+        error
+    }
+
+    /// The object this error was raised with, if it was raised with something other than an error.
+    pub fn raised_value(&self) -> Option<&SteelVal> {
+        match &self.repr.payload {
+            Some(Payload::Raised(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Records the message and irritants given to `error`, separately from the rendered message.
+    pub fn with_irritants(mut self, message: String, irritants: Vec<SteelVal>) -> Self {
+        self.repr.payload = Some(Payload::Irritants(message, irritants));
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        match &self.repr.payload {
+            Some(Payload::Irritants(message, _)) => message,
+            _ => &self.repr.message,
+        }
+    }
+
+    pub fn irritants(&self) -> &[SteelVal] {
+        match &self.repr.payload {
+            Some(Payload::Irritants(_, irritants)) => irritants,
+            _ => &[],
+        }
+    }
+
+    /// How many exception handlers were installed where this error was raised, when that
+    /// differs from where it is being unwound to. See `raise-continuable`.
+    pub(crate) fn handler_depth(&self) -> Option<usize> {
+        self.repr.handler_depth
+    }
+
+    pub(crate) fn set_handler_depth(&mut self, depth: usize) {
+        self.repr.handler_depth = Some(depth);
+    }
+
     pub fn span(&self) -> Option<Span> {
         self.repr.span
     }
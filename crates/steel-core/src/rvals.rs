@@ -1,4 +1,6 @@
 pub mod cycles;
+pub mod printer;
+pub mod reader;
 
 use crate::{
     gc::{unsafe_erased_pointers::OpaqueReference, Gc},
@@ -583,6 +585,10 @@ impl ast::TryFromSteelValVisitorForExprKind {
                 TokenType::Identifier(x.as_str().into()),
                 span,
             )))),
+            Keyword(x) => Ok(ExprKind::Atom(Atom::new(SyntaxObject::new(
+                TokenType::Keyword(x.as_str().into()),
+                span,
+            )))),
 
             ListV(l) => {
                 // Rooted - things operate as normal
@@ -711,6 +717,9 @@ impl Syntax {
             SymbolV(x) => Ok(ExprKind::Atom(Atom::new(SyntaxObject::default(
                 TokenType::Identifier(x.as_str().into()),
             )))),
+            Keyword(x) => Ok(ExprKind::Atom(Atom::new(SyntaxObject::default(
+                TokenType::Keyword(x.as_str().into()),
+            )))),
             ListV(l) => {
                 let items: Result<Vec<ExprKind>> =
                     l.iter().map(Self::steelval_to_exprkind).collect();
@@ -759,6 +768,10 @@ impl Syntax {
                 TokenType::Identifier(x.as_str().into()),
                 span,
             )))),
+            Keyword(x) => Ok(ExprKind::Atom(Atom::new(SyntaxObject::new(
+                TokenType::Keyword(x.as_str().into()),
+                span,
+            )))),
             ListV(l) => {
                 let items: Result<Vec<ExprKind>> =
                     l.iter().map(Self::steelval_to_exprkind).collect();
@@ -826,6 +839,7 @@ pub enum SerializableSteelVal {
     BoxedDynFunction(BoxedDynFunction),
     BuiltIn(BuiltInSignature),
     SymbolV(String),
+    Keyword(String),
     Custom(Box<dyn CustomType + Send>),
     CustomStruct(SerializableUserDefinedStruct),
     // Attempt to reuse the storage if possible
@@ -905,6 +919,7 @@ pub fn from_serializable_value(ctx: &mut HeapSerializer, val: SerializableSteelV
         SerializableSteelVal::BoxedDynFunction(f) => SteelVal::BoxedFunction(Rc::new(f)),
         SerializableSteelVal::BuiltIn(f) => SteelVal::BuiltIn(f),
         SerializableSteelVal::SymbolV(s) => SteelVal::SymbolV(s.into()),
+        SerializableSteelVal::Keyword(s) => SteelVal::Keyword(s.into()),
         SerializableSteelVal::Custom(b) => SteelVal::Custom(Gc::new(RefCell::new(b))),
         SerializableSteelVal::CustomStruct(s) => {
             SteelVal::CustomStruct(Gc::new(UserDefinedStruct {
@@ -990,6 +1005,7 @@ pub fn into_serializable_value(
         )),
         SteelVal::BoxedFunction(f) => Ok(SerializableSteelVal::BoxedDynFunction((*f).clone())),
         SteelVal::BuiltIn(f) => Ok(SerializableSteelVal::BuiltIn(f)),
+        SteelVal::Keyword(s) => Ok(SerializableSteelVal::Keyword(s.to_string())),
         SteelVal::SymbolV(s) => Ok(SerializableSteelVal::SymbolV(s.to_string())),
         SteelVal::MutFunc(f) => Ok(SerializableSteelVal::MutFunc(f)),
         SteelVal::HashMapV(v) => Ok(SerializableSteelVal::HashMapV(
@@ -1216,6 +1232,44 @@ pub enum SteelVal {
     BigNum(Gc<BigInt>),
     // Like Rational but supports larger numerators and denominators.
     BigRational(Gc<BigRational>),
+    // A complex number, with real parts that are either both exact or both inexact.
+    Complex(Gc<SteelComplex>),
+    // A keyword written out in code, like `#:tag` in `(f #:tag 10)`. Unlike the symbol `'#:tag`,
+    // it names a keyword argument when passed to a function.
+    Keyword(SteelString),
+}
+
+/// The parts of a complex number.
+///
+/// The imaginary part is never an exact zero - those numbers are real, and are
+/// represented as such. See the [`IntoSteelVal`] implementation.
+#[derive(Clone, PartialEq, Hash)]
+pub struct SteelComplex {
+    pub re: SteelVal,
+    pub im: SteelVal,
+}
+
+impl SteelComplex {
+    pub fn new(re: SteelVal, im: SteelVal) -> SteelComplex {
+        SteelComplex { re, im }
+    }
+}
+
+impl fmt::Display for SteelComplex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !matches!(self.re, IntV(0)) {
+            write!(f, "{}", self.re)?;
+        }
+
+        match &self.im {
+            IntV(1) => write!(f, "+i"),
+            IntV(-1) => write!(f, "-i"),
+            NumV(n) if n.is_nan() => write!(f, "+nan.0i"),
+            NumV(n) if n.is_infinite() => write!(f, "{}inf.0i", if *n > 0.0 { '+' } else { '-' }),
+            im if crate::primitives::nums::is_negative(im) => write!(f, "{im}i"),
+            im => write!(f, "+{im}i"),
+        }
+    }
 }
 
 impl SteelVal {
@@ -1236,6 +1290,7 @@ impl SteelVal {
             function,
             name: None,
             arity: None,
+            struct_function: None,
         }))
     }
 
@@ -1560,6 +1615,7 @@ impl SteelVal {
             (StringV(l), StringV(r)) => Rc::ptr_eq(l, r),
             (FuncV(l), FuncV(r)) => *l as usize == *r as usize,
             (SymbolV(l), SymbolV(r)) => Rc::ptr_eq(l, r),
+            (Keyword(l), Keyword(r)) => l == r,
             (SteelVal::Custom(l), SteelVal::Custom(r)) => Gc::ptr_eq(l, r),
             (HashMapV(l), HashMapV(r)) => Gc::ptr_eq(&l.0, &r.0),
             (HashSetV(l), HashSetV(r)) => Gc::ptr_eq(&l.0, &r.0),
@@ -1597,6 +1653,7 @@ impl Hash for SteelVal {
             CustomStruct(s) => s.hash(state),
             BigNum(n) => n.hash(state),
             BigRational(f) => f.hash(state),
+            Complex(x) => x.hash(state),
             // Pair(cell) => {
             //     cell.hash(state);
             // }
@@ -1611,6 +1668,10 @@ impl Hash for SteelVal {
                 sym.hash(state);
                 // format!("symbol: {}")
             }
+            Keyword(keyword) => {
+                "keyword".hash(state);
+                keyword.hash(state);
+            }
             Custom(_) => unimplemented!(),
             // StructClosureV(_) => unimplemented!(),
             PortV(_) => unimplemented!(),
@@ -1662,6 +1723,7 @@ impl SteelVal {
                 | VectorV(_)
                 | StringV(_)
                 | SymbolV(_)
+                | Keyword(_)
                 | HashMapV(_)
                 | Closure(_)
                 | ListV(_)
@@ -1959,6 +2021,13 @@ pub fn number_equality(left: &SteelVal, right: &SteelVal) -> Result<SteelVal> {
         | (BigRational(_), BigNum(_))
         | (BigNum(_), BigRational(_)) => false,
         (IntV(_), BigNum(_)) | (BigNum(_), IntV(_)) => false,
+        (Complex(l), Complex(r)) => {
+            number_equality(&l.re, &r.re)?.is_truthy() && number_equality(&l.im, &r.im)?.is_truthy()
+        }
+        // An inexact complex number can still have a zero imaginary part, e.g. `1+0.0i`
+        (Complex(c), x) | (x, Complex(c)) => {
+            number_equality(&c.re, x)?.is_truthy() && number_equality(&c.im, &IntV(0))?.is_truthy()
+        }
         _ => stop!(TypeMismatch => "= expects two numbers, found: {:?} and {:?}", left, right),
     };
 
@@ -160,6 +160,7 @@ impl CycleDetector {
             IntV(x) => write!(f, "{x}"),
             Rational(x) => write!(f, "{n}/{d}", n = x.numer(), d = x.denom()),
             BigRational(x) => write!(f, "{n}/{d}", n = x.numer(), d = x.denom()),
+            Complex(x) => write!(f, "{}", x.as_ref()),
             StringV(s) => write!(f, "{s:?}"),
             BigNum(b) => write!(f, "{}", b.as_ref()),
             CharV(c) => {
@@ -180,7 +181,7 @@ impl CycleDetector {
                 }
             }
             Void => write!(f, "#<void>"),
-            SymbolV(s) => write!(f, "{s}"),
+            SymbolV(s) | Keyword(s) => write!(f, "{s}"),
             VectorV(lst) => {
                 let mut iter = lst.iter();
                 write!(f, "'#(")?;
@@ -284,6 +285,7 @@ impl CycleDetector {
             IntV(x) => write!(f, "{x}"),
             Rational(x) => write!(f, "{n}/{d}", n = x.numer(), d = x.denom()),
             BigRational(x) => write!(f, "{n}/{d}", n = x.numer(), d = x.denom()),
+            Complex(x) => write!(f, "{}", x.as_ref()),
             StringV(s) => write!(f, "{s:?}"),
             CharV(c) => {
                 if c.is_ascii_control() {
@@ -303,7 +305,7 @@ impl CycleDetector {
                 write!(f, "({} . {})", p.car(), p.cdr())
             }
             Void => write!(f, "#<void>"),
-            SymbolV(s) => write!(f, "{s}"),
+            SymbolV(s) | Keyword(s) => write!(f, "{s}"),
             VectorV(lst) => {
                 let mut iter = lst.iter();
                 write!(f, "(")?;
@@ -579,6 +581,7 @@ impl<'a> BreadthFirstSearchSteelValVisitor for CycleCollector<'a> {
     fn visit_int(&mut self, _int: isize) -> Self::Output {}
     fn visit_rational(&mut self, _: Rational32) -> Self::Output {}
     fn visit_bigrational(&mut self, _: Gc<BigRational>) -> Self::Output {}
+    fn visit_complex(&mut self, _: Gc<SteelComplex>) -> Self::Output {}
     fn visit_bignum(&mut self, _bignum: Gc<BigInt>) -> Self::Output {}
     fn visit_char(&mut self, _c: char) -> Self::Output {}
 
@@ -876,6 +879,7 @@ impl<'a> BreadthFirstSearchSteelValVisitor for IterativeDropHandler<'a> {
             | SteelVal::StringV(_)
             | SteelVal::FuncV(_)
             | SteelVal::SymbolV(_)
+            | SteelVal::Keyword(_)
             | SteelVal::FutureFunc(_)
             | SteelVal::FutureV(_)
             | SteelVal::BoxedFunction(_)
@@ -893,6 +897,7 @@ impl<'a> BreadthFirstSearchSteelValVisitor for IterativeDropHandler<'a> {
     fn visit_int(&mut self, _int: isize) {}
     fn visit_rational(&mut self, _: Rational32) {}
     fn visit_bigrational(&mut self, _: Gc<BigRational>) {}
+    fn visit_complex(&mut self, _: Gc<SteelComplex>) {}
     fn visit_char(&mut self, _c: char) {}
     fn visit_void(&mut self) {}
     fn visit_string(&mut self, _string: SteelString) {}
@@ -1108,13 +1113,14 @@ impl<'a> BreadthFirstSearchSteelValVisitor for IterativeDropHandler<'a> {
                 IntV(i) => self.visit_int(i),
                 Rational(x) => self.visit_rational(x),
                 BigRational(x) => self.visit_bigrational(x),
+                Complex(x) => self.visit_complex(x),
                 BigNum(b) => self.visit_bignum(b),
                 CharV(c) => self.visit_char(c),
                 VectorV(v) => self.visit_immutable_vector(v),
                 Void => self.visit_void(),
                 StringV(s) => self.visit_string(s),
                 FuncV(f) => self.visit_function_pointer(f),
-                SymbolV(s) => self.visit_symbol(s),
+                SymbolV(s) | Keyword(s) => self.visit_symbol(s),
                 SteelVal::Custom(c) => self.visit_custom_type(c),
                 HashMapV(h) => self.visit_hash_map(h),
                 HashSetV(s) => self.visit_hash_set(s),
@@ -1173,13 +1179,14 @@ pub trait BreadthFirstSearchSteelValVisitor {
                 IntV(i) => self.visit_int(i),
                 Rational(x) => self.visit_rational(x),
                 BigRational(x) => self.visit_bigrational(x),
+                Complex(x) => self.visit_complex(x),
                 BigNum(b) => self.visit_bignum(b),
                 CharV(c) => self.visit_char(c),
                 VectorV(v) => self.visit_immutable_vector(v),
                 Void => self.visit_void(),
                 StringV(s) => self.visit_string(s),
                 FuncV(f) => self.visit_function_pointer(f),
-                SymbolV(s) => self.visit_symbol(s),
+                SymbolV(s) | Keyword(s) => self.visit_symbol(s),
                 SteelVal::Custom(c) => self.visit_custom_type(c),
                 HashMapV(h) => self.visit_hash_map(h),
                 HashSetV(s) => self.visit_hash_set(s),
@@ -1214,6 +1221,7 @@ pub trait BreadthFirstSearchSteelValVisitor {
     fn visit_int(&mut self, _: isize) -> Self::Output;
     fn visit_rational(&mut self, _: Rational32) -> Self::Output;
     fn visit_bigrational(&mut self, _: Gc<BigRational>) -> Self::Output;
+    fn visit_complex(&mut self, _: Gc<SteelComplex>) -> Self::Output;
     fn visit_bignum(&mut self, _: Gc<BigInt>) -> Self::Output;
     fn visit_char(&mut self, _: char) -> Self::Output;
     fn visit_immutable_vector(&mut self, vector: SteelVector) -> Self::Output;
@@ -1265,12 +1273,13 @@ pub trait BreadthFirstSearchSteelValReferenceVisitor<'a> {
                 IntV(i) => self.visit_int(*i),
                 Rational(x) => self.visit_rational(*x),
                 BigRational(x) => self.visit_bigrational(x),
+                Complex(x) => self.visit_complex(x),
                 CharV(c) => self.visit_char(*c),
                 VectorV(v) => self.visit_immutable_vector(v),
                 Void => self.visit_void(),
                 StringV(s) => self.visit_string(s),
                 FuncV(f) => self.visit_function_pointer(*f),
-                SymbolV(s) => self.visit_symbol(s),
+                SymbolV(s) | Keyword(s) => self.visit_symbol(s),
                 SteelVal::Custom(c) => self.visit_custom_type(c),
                 HashMapV(h) => self.visit_hash_map(h),
                 HashSetV(s) => self.visit_hash_set(s),
@@ -1306,6 +1315,7 @@ pub trait BreadthFirstSearchSteelValReferenceVisitor<'a> {
     fn visit_int(&mut self, int: isize) -> Self::Output;
     fn visit_rational(&mut self, fract: Rational32) -> Self::Output;
     fn visit_bigrational(&mut self, _: &'a Gc<BigRational>) -> Self::Output;
+    fn visit_complex(&mut self, _: &'a Gc<SteelComplex>) -> Self::Output;
     fn visit_bignum(&mut self, _: &'a Gc<BigInt>) -> Self::Output;
     fn visit_char(&mut self, c: char) -> Self::Output;
     fn visit_immutable_vector(&mut self, vector: &'a SteelVector) -> Self::Output;
@@ -1534,7 +1544,7 @@ impl<'a> RecursiveEqualityHandler<'a> {
                     }
                     continue;
                 }
-                (SymbolV(l), SymbolV(r)) => {
+                (SymbolV(l), SymbolV(r)) | (Keyword(l), Keyword(r)) => {
                     if l != r {
                         return false;
                     }
@@ -1775,6 +1785,7 @@ impl<'a> BreadthFirstSearchSteelValVisitor for EqualityVisitor<'a> {
     fn visit_int(&mut self, _int: isize) -> Self::Output {}
     fn visit_rational(&mut self, _: Rational32) -> Self::Output {}
     fn visit_bigrational(&mut self, _: Gc<BigRational>) -> Self::Output {}
+    fn visit_complex(&mut self, _: Gc<SteelComplex>) -> Self::Output {}
     fn visit_bignum(&mut self, _bignum: Gc<BigInt>) -> Self::Output {}
     fn visit_char(&mut self, _c: char) -> Self::Output {}
     fn visit_void(&mut self) -> Self::Output {}
@@ -1908,9 +1919,11 @@ impl PartialEq for SteelVal {
             (NumV(l), NumV(r)) => l == r,
             (Rational(l), Rational(r)) => l == r,
             (BigRational(l), BigRational(r)) => l == r,
+            (Complex(l), Complex(r)) => l == r,
             (BigNum(l), BigNum(r)) => l == r,
             (StringV(l), StringV(r)) => l == r,
             (SymbolV(l), SymbolV(r)) => l == r,
+            (Keyword(l), Keyword(r)) => l == r,
             (CharV(l), CharV(r)) => l == r,
             (FuncV(l), FuncV(r)) => *l as usize == *r as usize,
             // (VectorV(l), VectorV(r)) => l == r,
@@ -0,0 +1,397 @@
+//! Printing values in the forms used by `display` and `write`.
+//!
+//! Unlike the `Display` implementation for `SteelVal`, which prints values the way the repl does
+//! (quoting lists and symbols), these print values the way the R7RS `display` and `write`
+//! procedures do - `write` output can be read back in, `display` output is meant for people.
+//!
+//! Values that are reached more than once can be marked with datum labels, `#0=(a b)` where
+//! they are first printed and `#0#` after that, so that cyclic values print finitely and
+//! `read` gives back the same sharing.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::primitives::nums::{format_number, parse_number};
+use crate::rvals::SteelVal;
+
+// Past this depth the rest of the value is elided, so that printing a cyclic value terminates
+// even without labels
+const MAX_DEPTH: usize = 128;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrintMode {
+    /// Strings and characters are printed as their contents
+    Display,
+    /// Strings and characters are printed as literals that read back as the same value
+    Write,
+}
+
+/// Which values are marked with datum labels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Labels {
+    /// Values that contain themselves, which is what `write` and `display` label
+    Cycles,
+    /// Every value that is reached more than once, which is what `write-shared` labels
+    Shared,
+    /// Nothing, which is what `write-simple` labels
+    Never,
+}
+
+/// Prints the value in the given mode, appending the output to `output`.
+pub fn print_value(output: &mut String, value: &SteelVal, mode: PrintMode, labels: Labels) {
+    let labelled = match labels {
+        Labels::Never => HashSet::new(),
+        labels => {
+            let mut finder = LabelFinder {
+                shared: labels == Labels::Shared,
+                visiting: HashSet::new(),
+                visited: HashSet::new(),
+                labelled: HashSet::new(),
+            };
+            finder.visit(value, 0);
+            finder.labelled
+        }
+    };
+
+    Printer {
+        output,
+        mode,
+        labelled,
+        numbers: HashMap::new(),
+    }
+    .print(value, 0)
+}
+
+/// Prints the value in the given mode into a new string, labelling cycles.
+pub fn print_to_string(value: &SteelVal, mode: PrintMode) -> String {
+    let mut output = String::new();
+    print_value(&mut output, value, mode, Labels::Cycles);
+    output
+}
+
+/// Appends the string as a string literal, escaping the characters the reader knows escapes for.
+pub fn write_string_literal(output: &mut String, value: &str) {
+    output.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            c => write_escaped(output, c),
+        }
+    }
+
+    output.push('"');
+}
+
+/// Appends the symbol, between bars if it wouldn't read back as the same symbol otherwise.
+pub fn write_symbol_literal(output: &mut String, symbol: &str) {
+    let needs_bars = symbol.is_empty()
+        || symbol == "."
+        || parse_number(symbol, 10).is_some()
+        || (symbol.starts_with('#') && !symbol.starts_with("#:") && !symbol.starts_with("#%"))
+        || symbol.chars().any(|c| {
+            c.is_whitespace()
+                || c.is_control()
+                || matches!(
+                    c,
+                    '(' | ')' | '[' | ']' | '"' | ';' | '|' | '\\' | '\'' | '`' | ','
+                )
+        });
+
+    if !needs_bars {
+        output.push_str(symbol);
+        return;
+    }
+
+    output.push('|');
+
+    for c in symbol.chars() {
+        match c {
+            '|' => output.push_str("\\|"),
+            c => write_escaped(output, c),
+        }
+    }
+
+    output.push('|');
+}
+
+// Appends a character inside of a string or symbol literal
+fn write_escaped(output: &mut String, c: char) {
+    match c {
+        '\\' => output.push_str("\\\\"),
+        '\t' => output.push_str("\\t"),
+        '\n' => output.push_str("\\n"),
+        '\r' => output.push_str("\\r"),
+        '\0' => output.push_str("\\0"),
+        c if c.is_control() => {
+            let _ = write!(output, "\\x{:x};", c as u32);
+        }
+        c => output.push(c),
+    }
+}
+
+/// Appends the character as a character literal.
+pub fn write_char_literal(output: &mut String, c: char) {
+    let name = match c {
+        '\u{7}' => "alarm",
+        '\u{8}' => "backspace",
+        '\u{7f}' => "delete",
+        '\u{1b}' => "escape",
+        '\n' => "newline",
+        '\0' => "null",
+        '\r' => "return",
+        ' ' => "space",
+        '\t' => "tab",
+        c if c.is_control() || c.is_whitespace() => {
+            let _ = write!(output, "#\\x{:x}", c as u32);
+            return;
+        }
+        c => {
+            output.push_str("#\\");
+            output.push(c);
+            return;
+        }
+    };
+
+    output.push_str("#\\");
+    output.push_str(name);
+}
+
+// Identifies the values that can be labelled: the address of their contents, and the length of
+// lists since the rest of a list shares its contents
+fn identity(value: &SteelVal) -> Option<(usize, usize)> {
+    match value {
+        SteelVal::ListV(l) if !l.is_empty() => Some((l.as_ptr_usize(), l.len())),
+        SteelVal::Pair(p) => Some((p.as_ptr() as usize, 0)),
+        SteelVal::VectorV(v) if !v.is_empty() => Some((v.0.as_ptr() as usize, 0)),
+        SteelVal::MutableVector(v) => Some((v.as_ptr_usize(), 0)),
+        _ => None,
+    }
+}
+
+fn for_each_child(value: &SteelVal, mut func: impl FnMut(&SteelVal)) {
+    match value {
+        SteelVal::ListV(l) => l.iter().for_each(func),
+        SteelVal::Pair(p) => {
+            func(&p.car);
+            func(&p.cdr);
+        }
+        SteelVal::VectorV(v) => v.iter().for_each(func),
+        SteelVal::MutableVector(v) => v.get().iter().for_each(func),
+        _ => {}
+    }
+}
+
+// Finds the values to label with a depth first search
+struct LabelFinder {
+    shared: bool,
+    visiting: HashSet<(usize, usize)>,
+    visited: HashSet<(usize, usize)>,
+    labelled: HashSet<(usize, usize)>,
+}
+
+impl LabelFinder {
+    fn visit(&mut self, value: &SteelVal, depth: usize) {
+        let Some(id) = identity(value) else {
+            return;
+        };
+
+        if depth > MAX_DEPTH {
+            return;
+        }
+
+        if self.visiting.contains(&id) {
+            self.labelled.insert(id);
+            return;
+        }
+
+        if !self.visited.insert(id) {
+            if self.shared {
+                self.labelled.insert(id);
+            }
+            return;
+        }
+
+        self.visiting.insert(id);
+        for_each_child(value, |child| self.visit(child, depth + 1));
+        self.visiting.remove(&id);
+    }
+}
+
+struct Printer<'a> {
+    output: &'a mut String,
+    mode: PrintMode,
+    labelled: HashSet<(usize, usize)>,
+    // The numbers of the labels printed so far
+    numbers: HashMap<(usize, usize), usize>,
+}
+
+impl Printer<'_> {
+    fn label(&self, value: &SteelVal) -> Option<(usize, usize)> {
+        identity(value).filter(|id| self.labelled.contains(id))
+    }
+
+    fn print(&mut self, value: &SteelVal, depth: usize) {
+        if depth > MAX_DEPTH {
+            self.output.push_str("...");
+            return;
+        }
+
+        if let Some(id) = self.label(value) {
+            if let Some(n) = self.numbers.get(&id) {
+                let _ = write!(self.output, "#{n}#");
+                return;
+            }
+
+            let n = self.numbers.len();
+            self.numbers.insert(id, n);
+            let _ = write!(self.output, "#{n}=");
+        }
+
+        match value {
+            SteelVal::BoolV(true) => self.output.push_str("#true"),
+            SteelVal::BoolV(false) => self.output.push_str("#false"),
+            SteelVal::IntV(_)
+            | SteelVal::NumV(_)
+            | SteelVal::Rational(_)
+            | SteelVal::BigNum(_)
+            | SteelVal::BigRational(_)
+            | SteelVal::Complex(_) => match format_number(value, 10) {
+                Ok(number) => self.output.push_str(&number),
+                Err(_) => {
+                    let _ = write!(self.output, "{value}");
+                }
+            },
+            SteelVal::StringV(s) => match self.mode {
+                PrintMode::Display => self.output.push_str(s),
+                PrintMode::Write => write_string_literal(self.output, s),
+            },
+            SteelVal::CharV(c) => match self.mode {
+                PrintMode::Display => self.output.push(*c),
+                PrintMode::Write => write_char_literal(self.output, *c),
+            },
+            SteelVal::SymbolV(s) => match self.mode {
+                PrintMode::Display => self.output.push_str(s),
+                PrintMode::Write => write_symbol_literal(self.output, s),
+            },
+            SteelVal::ListV(l) => self.print_sequence("(", l.iter(), depth),
+            SteelVal::Pair(p) => {
+                self.output.push('(');
+                self.print(&p.car, depth + 1);
+
+                let mut rest = &p.cdr;
+                loop {
+                    match rest {
+                        // A labelled tail has to be printed as a value of its own
+                        rest if self.label(rest).is_some() => {
+                            self.output.push_str(" . ");
+                            self.print(rest, depth + 1);
+                            break;
+                        }
+                        SteelVal::Pair(p) => {
+                            self.output.push(' ');
+                            self.print(&p.car, depth + 1);
+                            rest = &p.cdr;
+                        }
+                        SteelVal::ListV(l) => {
+                            for item in l.iter() {
+                                self.output.push(' ');
+                                self.print(item, depth + 1);
+                            }
+                            break;
+                        }
+                        other => {
+                            self.output.push_str(" . ");
+                            self.print(other, depth + 1);
+                            break;
+                        }
+                    }
+                }
+
+                self.output.push(')');
+            }
+            SteelVal::VectorV(v) => self.print_sequence("#(", v.iter(), depth),
+            SteelVal::MutableVector(v) => {
+                let items = v.get();
+                self.print_sequence("#(", items.iter(), depth)
+            }
+            SteelVal::Void => self.output.push_str("#<void>"),
+            other => {
+                let _ = write!(self.output, "{other}");
+            }
+        }
+    }
+
+    fn print_sequence<'b>(
+        &mut self,
+        open: &str,
+        items: impl Iterator<Item = &'b SteelVal>,
+        depth: usize,
+    ) {
+        self.output.push_str(open);
+
+        for (i, item) in items.enumerate() {
+            if i > 0 {
+                self.output.push(' ');
+            }
+            self.print(item, depth + 1);
+        }
+
+        self.output.push(')');
+    }
+}
+
+#[cfg(test)]
+mod printer_tests {
+    use super::*;
+    use crate::values::lists::List;
+
+    #[test]
+    fn display_and_write_strings_in_lists() {
+        let value = SteelVal::ListV(List::from(vec![
+            SteelVal::StringV("a \"b\"\n".into()),
+            SteelVal::CharV(' '),
+            SteelVal::SymbolV("c".into()),
+        ]));
+
+        assert_eq!(
+            print_to_string(&value, PrintMode::Display),
+            "(a \"b\"\n   c)"
+        );
+        assert_eq!(
+            print_to_string(&value, PrintMode::Write),
+            "(\"a \\\"b\\\"\\n\" #\\space c)"
+        );
+    }
+
+    #[test]
+    fn write_escapes_symbols() {
+        let value = SteelVal::ListV(List::from(vec![
+            SteelVal::SymbolV("a b".into()),
+            SteelVal::SymbolV("1".into()),
+            SteelVal::SymbolV("#:key".into()),
+        ]));
+
+        assert_eq!(
+            print_to_string(&value, PrintMode::Write),
+            "(|a b| |1| #:key)"
+        );
+        assert_eq!(print_to_string(&value, PrintMode::Display), "(a b 1 #:key)");
+    }
+
+    #[test]
+    fn labels_cycles_and_shared_values() {
+        let shared = SteelVal::ListV(List::from(vec![SteelVal::IntV(1)]));
+        let value = SteelVal::ListV(List::from(vec![shared.clone(), shared]));
+
+        let print = |labels| {
+            let mut output = String::new();
+            print_value(&mut output, &value, PrintMode::Write, labels);
+            output
+        };
+
+        assert_eq!(print(Labels::Cycles), "((1) (1))");
+        assert_eq!(print(Labels::Shared), "(#0=(1) #0#)");
+        assert_eq!(print(Labels::Never), "((1) (1))");
+    }
+}
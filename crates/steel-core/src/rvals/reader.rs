@@ -0,0 +1,649 @@
+//! Reading values from the external representations that `write` prints.
+//!
+//! Unlike the parser for programs, this reads a single datum at a time straight off of an input
+//! port, leaving whatever follows it in the port for the next read. On top of the syntax the
+//! parser accepts it reads vectors, bytevectors, `|symbols|`, the R7RS string and character
+//! escapes, and datum labels for shared and cyclic structure.
+//!
+//! Only vectors can contain themselves, since they are the only data with a mutable
+//! representation. A reference to a label from inside the datum it labels reads as an empty
+//! mutable vector, which is filled in with the elements of the labelled vector once it has been
+//! read.
+
+use std::collections::HashMap;
+
+use crate::gc::Gc;
+use crate::parser::span::Span;
+use crate::primitives::nums::parse_number;
+use crate::primitives::ports::EOF_OBJECT;
+use crate::rvals::{Result, SteelVal, Syntax};
+use crate::values::closed::{Heap, HeapRef};
+use crate::values::lists::{List, Pair};
+use crate::values::port::{SteelPort, SteelPortRepr};
+
+/// Reads the next datum from the port, or returns the eof object if there isn't one. Cyclic
+/// vectors are allocated on the heap.
+pub fn read_datum(port: &SteelPort, heap: &mut Heap) -> Result<SteelVal> {
+    read_from(port, Some(heap))
+}
+
+/// Like [`read_datum`], but returns a syntax object carrying the span of the datum in the port,
+/// whose `syntax-e` holds syntax objects for each of its elements. Syntax can't be cyclic.
+pub fn read_syntax(port: &SteelPort) -> Result<SteelVal> {
+    read_from(port, None)
+}
+
+fn read_from(port: &SteelPort, heap: Option<&mut Heap>) -> Result<SteelVal> {
+    let mut port = port.port.borrow_mut();
+    let offset = port.position().unwrap_or(0);
+
+    let mut reader = DatumReader {
+        port: &mut port,
+        offset,
+        syntax: heap.is_none(),
+        heap,
+        labels: HashMap::new(),
+    };
+
+    match reader.read_item()? {
+        Item::Datum(datum) => Ok(datum.syntax.unwrap_or(datum.value)),
+        Item::Eof => Ok(SteelVal::SymbolV(EOF_OBJECT.with(|x| x.clone()))),
+        Item::Close(c) => stop!(Parse => "read: unexpected `{}`", c),
+        Item::Dot => stop!(Parse => "read: unexpected `.`"),
+    }
+}
+
+// The characters that end a symbol or number, other than whitespace
+fn is_delimiter(byte: u8) -> bool {
+    byte.is_ascii_whitespace() || matches!(byte, b'(' | b')' | b'[' | b']' | b'"' | b';' | b'|')
+}
+
+#[derive(Clone)]
+struct Datum {
+    value: SteelVal,
+    // The syntax object for the value, when reading syntax
+    syntax: Option<SteelVal>,
+}
+
+enum Label {
+    // The datum is still being read, and this is the vector that references to it stand in for
+    Reading(Option<HeapRef<Vec<SteelVal>>>),
+    Read(Datum),
+}
+
+enum Item {
+    Datum(Datum),
+    Close(char),
+    Dot,
+    Eof,
+}
+
+struct DatumReader<'a> {
+    port: &'a mut SteelPortRepr,
+    // Offset in bytes of the next character in the port
+    offset: usize,
+    syntax: bool,
+    // Where cyclic vectors are allocated, which is missing when reading syntax
+    heap: Option<&'a mut Heap>,
+    labels: HashMap<u64, Label>,
+}
+
+impl DatumReader<'_> {
+    fn peek(&mut self) -> Result<Option<u8>> {
+        self.port.peek_byte()
+    }
+
+    fn next_char(&mut self) -> Result<Option<char>> {
+        let c = self.port.read_char()?;
+        if let Some(c) = c {
+            self.offset += c.len_utf8();
+        }
+        Ok(c)
+    }
+
+    fn expect_char(&mut self, context: &str) -> Result<char> {
+        match self.next_char()? {
+            Some(c) => Ok(c),
+            None => stop!(Parse => "read: unexpected end of input in {}", context),
+        }
+    }
+
+    // Consumes the next byte if it is `expected`
+    fn eat(&mut self, expected: u8) -> Result<bool> {
+        if self.peek()? == Some(expected) {
+            self.next_char()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    // Reads the rest of a symbol, number or character name
+    fn read_token(&mut self, token: &mut String) -> Result<()> {
+        while let Some(byte) = self.peek()? {
+            if is_delimiter(byte) {
+                break;
+            }
+            token.extend(self.next_char()?);
+        }
+        Ok(())
+    }
+
+    fn datum(&self, value: SteelVal, syntax_e: impl FnOnce() -> SteelVal, start: usize) -> Datum {
+        let syntax = self.syntax.then(|| {
+            Syntax::proto(
+                value.clone(),
+                syntax_e(),
+                Span::new(start, self.offset, None),
+            )
+            .into()
+        });
+
+        Datum { value, syntax }
+    }
+
+    fn atom(&self, value: SteelVal, start: usize) -> Datum {
+        self.datum(value.clone(), || value, start)
+    }
+
+    fn read_datum(&mut self, context: &str) -> Result<Datum> {
+        match self.read_item()? {
+            Item::Datum(datum) => Ok(datum),
+            Item::Close(c) => stop!(Parse => "read: unexpected `{}` in {}", c, context),
+            Item::Dot => stop!(Parse => "read: unexpected `.` in {}", context),
+            Item::Eof => stop!(Parse => "read: unexpected end of input in {}", context),
+        }
+    }
+
+    fn read_item(&mut self) -> Result<Item> {
+        loop {
+            while let Some(byte) = self.peek()? {
+                match byte {
+                    b';' => while !matches!(self.next_char()?, Some('\n') | None) {},
+                    byte if byte.is_ascii_whitespace() => {
+                        self.next_char()?;
+                    }
+                    _ => break,
+                }
+            }
+
+            let start = self.offset;
+
+            let c = match self.next_char()? {
+                Some(c) => c,
+                None => return Ok(Item::Eof),
+            };
+
+            let datum = match c {
+                '(' => self.read_list(')', start)?,
+                '[' => self.read_list(']', start)?,
+                ')' | ']' => return Ok(Item::Close(c)),
+                '\'' => self.read_abbreviation("quote", start)?,
+                '`' => self.read_abbreviation("quasiquote", start)?,
+                ',' if self.eat(b'@')? => self.read_abbreviation("unquote-splicing", start)?,
+                ',' => self.read_abbreviation("unquote", start)?,
+                '"' => {
+                    let string = self.read_string()?;
+                    self.atom(SteelVal::StringV(string.into()), start)
+                }
+                '|' => {
+                    let symbol = self.read_bar_symbol()?;
+                    self.atom(SteelVal::SymbolV(symbol.into()), start)
+                }
+                '#' => match self.peek()? {
+                    Some(b'|') => {
+                        self.skip_block_comment()?;
+                        continue;
+                    }
+                    Some(b';') => {
+                        self.next_char()?;
+                        self.read_datum("datum comment")?;
+                        continue;
+                    }
+                    _ => self.read_hash(start)?,
+                },
+                c => {
+                    let mut token = c.to_string();
+                    self.read_token(&mut token)?;
+
+                    if token == "." {
+                        return Ok(Item::Dot);
+                    }
+
+                    let value =
+                        parse_number(&token, 10).unwrap_or_else(|| SteelVal::SymbolV(token.into()));
+                    self.atom(value, start)
+                }
+            };
+
+            return Ok(Item::Datum(datum));
+        }
+    }
+
+    // Reads the elements of a list up to `close`, and the tail after a `.` if there is one
+    fn read_elements(&mut self, close: char) -> Result<(Vec<Datum>, Option<Datum>)> {
+        let mut items = Vec::new();
+        let mut tail = None;
+
+        loop {
+            match self.read_item()? {
+                Item::Datum(datum) if tail.is_none() => items.push(datum),
+                Item::Close(c) if c == close => return Ok((items, tail)),
+                Item::Close(c) => {
+                    stop!(Parse => "read: expected `{}` to close the list, found `{}`", close, c)
+                }
+                Item::Dot if !items.is_empty() && tail.is_none() => {
+                    tail = Some(self.read_datum("list")?);
+                }
+                Item::Datum(_) | Item::Dot => stop!(Parse => "read: bad use of `.` in list"),
+                Item::Eof => stop!(Parse => "read: unexpected end of input in list"),
+            }
+        }
+    }
+
+    fn read_list(&mut self, close: char, start: usize) -> Result<Datum> {
+        let (items, tail) = self.read_elements(close)?;
+
+        let value = improper_list(
+            items.iter().map(|item| item.value.clone()),
+            tail.as_ref().map(|tail| tail.value.clone()),
+        );
+
+        Ok(self.datum(
+            value,
+            || {
+                improper_list(
+                    items.iter().filter_map(|item| item.syntax.clone()),
+                    tail.and_then(|tail| tail.syntax),
+                )
+            },
+            start,
+        ))
+    }
+
+    fn read_abbreviation(&mut self, name: &str, start: usize) -> Result<Datum> {
+        let symbol = self.atom(SteelVal::SymbolV(name.into()), start);
+        let datum = self.read_datum(name)?;
+
+        let value = SteelVal::ListV(List::from(vec![symbol.value, datum.value]));
+
+        Ok(self.datum(
+            value,
+            || SteelVal::ListV(symbol.syntax.into_iter().chain(datum.syntax).collect()),
+            start,
+        ))
+    }
+
+    // Reads the datum after a `#` that isn't a comment
+    fn read_hash(&mut self, start: usize) -> Result<Datum> {
+        let c = self.expect_char("`#` syntax")?;
+
+        match c {
+            '(' => {
+                let (items, tail) = self.read_elements(')')?;
+                if tail.is_some() {
+                    stop!(Parse => "read: unexpected `.` in vector");
+                }
+
+                let vector = |values: Vec<SteelVal>| {
+                    SteelVal::VectorV(
+                        Gc::new(values.into_iter().collect::<im_rc::Vector<_>>()).into(),
+                    )
+                };
+
+                let value = vector(items.iter().map(|item| item.value.clone()).collect());
+
+                Ok(self.datum(
+                    value,
+                    || {
+                        vector(
+                            items
+                                .iter()
+                                .filter_map(|item| item.syntax.clone())
+                                .collect(),
+                        )
+                    },
+                    start,
+                ))
+            }
+            'u' => {
+                let mut token = "#u".to_string();
+                self.read_token(&mut token)?;
+
+                if token != "#u8" || !self.eat(b'(')? {
+                    stop!(Parse => "read: unknown syntax `{}`", token);
+                }
+
+                self.read_bytevector(start)
+            }
+            '\\' => {
+                let c = self.read_char_literal()?;
+                Ok(self.atom(SteelVal::CharV(c), start))
+            }
+            '0'..='9' => self.read_label(c),
+            c => {
+                let mut token = format!("#{c}");
+                self.read_token(&mut token)?;
+
+                let value = match token.as_str() {
+                    "#t" | "#true" => SteelVal::BoolV(true),
+                    "#f" | "#false" => SteelVal::BoolV(false),
+                    // Keywords and reserved names are symbols
+                    _ if token.starts_with("#:") || token.starts_with("#%") => {
+                        SteelVal::SymbolV(token.into())
+                    }
+                    _ => match parse_number(&token, 10) {
+                        Some(number) => number,
+                        None => stop!(Parse => "read: unknown syntax `{}`", token),
+                    },
+                };
+
+                Ok(self.atom(value, start))
+            }
+        }
+    }
+
+    // Reads `#n=datum` or `#n#`, after the first digit
+    fn read_label(&mut self, first: char) -> Result<Datum> {
+        let mut digits = first.to_string();
+
+        let label = loop {
+            match self.expect_char("datum label")? {
+                c if c.is_ascii_digit() => digits.push(c),
+                c @ ('=' | '#') => break c,
+                c => stop!(Parse => "read: unexpected `{}` in datum label #{}", c, digits),
+            }
+        };
+
+        let n: u64 = match digits.parse() {
+            Ok(n) => n,
+            Err(_) => stop!(Parse => "read: datum label #{} is too large", digits),
+        };
+
+        if label == '=' {
+            self.labels.insert(n, Label::Reading(None));
+            let mut datum = self.read_datum("labelled datum")?;
+
+            if let Some(Label::Reading(Some(mut placeholder))) = self.labels.remove(&n) {
+                let SteelVal::VectorV(items) = &datum.value else {
+                    stop!(Parse => "read: datum label #{}# refers to the datum it labels, which only vectors can do", n)
+                };
+
+                placeholder.set(items.iter().cloned().collect());
+                datum.value = SteelVal::MutableVector(placeholder);
+            }
+
+            self.labels.insert(n, Label::Read(datum.clone()));
+            return Ok(datum);
+        }
+
+        match self.labels.get_mut(&n) {
+            Some(Label::Read(datum)) => Ok(datum.clone()),
+            Some(Label::Reading(placeholder)) => {
+                let Some(heap) = self.heap.as_deref_mut() else {
+                    stop!(Parse => "read: datum label #{}# refers to the datum it labels, and syntax can't be cyclic", n)
+                };
+
+                // Collecting could free the vectors of other labels, since nothing but the data
+                // being read refers to them yet
+                let vector = placeholder
+                    .get_or_insert_with(|| heap.allocate_vector_without_collection(Vec::new()));
+
+                Ok(Datum {
+                    value: SteelVal::MutableVector(vector.clone()),
+                    syntax: None,
+                })
+            }
+            None => stop!(Parse => "read: reference to undefined datum label #{}#", n),
+        }
+    }
+
+    // Reads the bytes of a bytevector after `#u8(`. There is no bytevector type, so they read as
+    // a vector of integers.
+    fn read_bytevector(&mut self, start: usize) -> Result<Datum> {
+        let (items, tail) = self.read_elements(')')?;
+        if tail.is_some() {
+            stop!(Parse => "read: unexpected `.` in bytevector");
+        }
+
+        let bytes = items
+            .into_iter()
+            .map(|item| match item.value {
+                SteelVal::IntV(byte @ 0..=255) => Ok(SteelVal::IntV(byte)),
+                other => stop!(Parse => "read: expected a byte in bytevector, found: {}", other),
+            })
+            .collect::<Result<im_rc::Vector<_>>>()?;
+
+        let value = SteelVal::VectorV(Gc::new(bytes).into());
+        Ok(self.atom(value, start))
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        let mut string = String::new();
+
+        loop {
+            match self.expect_char("string")? {
+                '"' => return Ok(string),
+                '\\' => {
+                    let c = self.expect_char("string")?;
+                    match c {
+                        // A line continuation skips the whitespace around the newline
+                        ' ' | '\t' | '\n' | '\r' => {
+                            let mut newline = c == '\n';
+                            while let Some(byte @ (b' ' | b'\t' | b'\n' | b'\r')) = self.peek()? {
+                                if byte == b'\n' {
+                                    if newline {
+                                        break;
+                                    }
+                                    newline = true;
+                                }
+                                self.next_char()?;
+                            }
+                        }
+                        c => string.push(self.read_escape(c, '"')?),
+                    }
+                }
+                c => string.push(c),
+            }
+        }
+    }
+
+    // Reads the symbol after an opening `|`
+    fn read_bar_symbol(&mut self) -> Result<String> {
+        let mut symbol = String::new();
+
+        loop {
+            match self.expect_char("symbol")? {
+                '|' => return Ok(symbol),
+                '\\' => {
+                    let c = self.expect_char("symbol")?;
+                    symbol.push(self.read_escape(c, '|')?);
+                }
+                c => symbol.push(c),
+            }
+        }
+    }
+
+    // The character for the escape `\c` in a string or symbol
+    fn read_escape(&mut self, c: char, quote: char) -> Result<char> {
+        Ok(match c {
+            'a' => '\u{7}',
+            'b' => '\u{8}',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' | '"' | '|' => c,
+            'x' => {
+                let mut hex = String::new();
+                loop {
+                    match self.expect_char("escape")? {
+                        ';' => break,
+                        c if c.is_ascii_hexdigit() => hex.push(c),
+                        c => {
+                            stop!(Parse => "read: expected `;` to end the escape \\x{}, found `{}`", hex, c)
+                        }
+                    }
+                }
+                code_point(&hex)?
+            }
+            c => {
+                stop!(Parse => "read: unknown escape `\\{}` in {}", c, if quote == '"' { "string" } else { "symbol" })
+            }
+        })
+    }
+
+    // Reads the character after `#\`
+    fn read_char_literal(&mut self) -> Result<char> {
+        let first = self.expect_char("character")?;
+
+        let mut name = first.to_string();
+        self.read_token(&mut name)?;
+
+        if name.chars().nth(1).is_none() {
+            return Ok(first);
+        }
+
+        Ok(match name.as_str() {
+            "alarm" => '\u{7}',
+            "backspace" => '\u{8}',
+            "delete" => '\u{7f}',
+            "escape" => '\u{1b}',
+            "newline" => '\n',
+            "null" => '\0',
+            "return" => '\r',
+            "space" => ' ',
+            "tab" => '\t',
+            _ => {
+                let hex = name
+                    .strip_prefix('x')
+                    .or_else(|| {
+                        name.strip_prefix("u{")
+                            .and_then(|hex| hex.strip_suffix('}'))
+                    })
+                    .or_else(|| name.strip_prefix('u'))
+                    .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()));
+
+                match hex {
+                    Some(hex) => code_point(hex)?,
+                    None => stop!(Parse => "read: unknown character name `#\\{}`", name),
+                }
+            }
+        })
+    }
+
+    fn skip_block_comment(&mut self) -> Result<()> {
+        // The `|` after the opening `#`
+        self.next_char()?;
+
+        let mut depth = 1;
+        while depth > 0 {
+            match self.expect_char("block comment")? {
+                '|' if self.eat(b'#')? => depth -= 1,
+                '#' if self.eat(b'|')? => depth += 1,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn code_point(hex: &str) -> Result<char> {
+    match u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+        Some(c) => Ok(c),
+        None => stop!(Parse => "read: `{}` is not a valid unicode scalar value", hex),
+    }
+}
+
+// Conses the items onto the tail, making a list when the tail is one
+fn improper_list(
+    items: impl DoubleEndedIterator<Item = SteelVal>,
+    tail: Option<SteelVal>,
+) -> SteelVal {
+    match tail {
+        None => SteelVal::ListV(items.collect()),
+        Some(SteelVal::ListV(tail)) => SteelVal::ListV(items.chain(tail.iter().cloned()).collect()),
+        Some(tail) => items.rev().fold(tail, |cdr, car| {
+            SteelVal::Pair(Gc::new(Pair::cons(car, cdr)))
+        }),
+    }
+}
+
+#[cfg(test)]
+mod reader_tests {
+    use super::*;
+    use crate::rvals::printer::{print_value, Labels, PrintMode};
+
+    fn read_all(input: &str, heap: &mut Heap) -> Vec<SteelVal> {
+        let port = SteelPort::new_input_port_string(input.to_string());
+        let mut values = Vec::new();
+
+        loop {
+            match read_datum(&port, heap).unwrap() {
+                SteelVal::SymbolV(s) if s.as_str() == "eof" => return values,
+                value => values.push(value),
+            }
+        }
+    }
+
+    #[test]
+    fn reads_datums_one_at_a_time() {
+        let values = read_all(
+            "(a . (b)) #;skipped |x y| #\\space \"\\x3bb;\" #u8(0 255) ; done",
+            &mut Heap::new(),
+        );
+
+        assert_eq!(values.len(), 5);
+        assert_eq!(values[0].to_string(), "'(a b)");
+        assert_eq!(values[1], SteelVal::SymbolV("x y".into()));
+        assert_eq!(values[2], SteelVal::CharV(' '));
+        assert_eq!(values[3], SteelVal::StringV("λ".into()));
+        assert_eq!(
+            values[4],
+            SteelVal::VectorV(
+                Gc::new(im_rc::vector![SteelVal::IntV(0), SteelVal::IntV(255)]).into()
+            )
+        );
+    }
+
+    #[test]
+    fn written_labels_read_back_as_shared_values() {
+        let shared = SteelVal::ListV(vec![SteelVal::IntV(1)].into());
+        let value = SteelVal::ListV(vec![shared.clone(), shared].into());
+
+        let mut output = String::new();
+        print_value(&mut output, &value, PrintMode::Write, Labels::Shared);
+        assert_eq!(output, "(#0=(1) #0#)");
+
+        let read = read_all(&output, &mut Heap::new()).pop().unwrap();
+        assert_eq!(read, value);
+    }
+
+    #[test]
+    fn cyclic_labels_read_back_as_cyclic_vectors() {
+        let mut heap = Heap::new();
+        let read = read_all("#0=#((#0#) 2)", &mut heap).pop().unwrap();
+
+        let SteelVal::MutableVector(vector) = &read else {
+            panic!("expected a mutable vector, found: {read}");
+        };
+
+        let items = vector.get();
+        assert_eq!(items[0], SteelVal::ListV(vec![read.clone()].into()));
+        assert_eq!(items[1], SteelVal::IntV(2));
+
+        let mut output = String::new();
+        print_value(&mut output, &read, PrintMode::Write, Labels::Cycles);
+        assert_eq!(output, "#0=#((#0#) 2)");
+    }
+
+    #[test]
+    fn cycles_outside_of_vectors_are_errors() {
+        let read = |input: &str| SteelPort::new_input_port_string(input.to_string());
+
+        assert!(read_datum(&read("#0=(a #0#)"), &mut Heap::new()).is_err());
+        assert!(read_syntax(&read("#0=#(a #0#)")).is_err());
+        assert!(read_datum(&read("#u8(1 256)"), &mut Heap::new()).is_err());
+    }
+}
@@ -305,19 +305,54 @@
 ;                  ,(list-ref binding-index-pair 1)))
 ;             (enumerate 0 '() bindings))))
 
+;; The identifiers bound by a lambda list: `(a b)`, `(a b . rest)` or `args`
+(define (formals->identifiers formals)
+  (cond
+    [(symbol? formals) (list formals)]
+    [(null? formals) '()]
+    [(equal? (car formals) (string->symbol ".")) (formals->identifiers (cdr formals))]
+    [else (cons (car formals) (formals->identifiers (cdr formals)))]))
+
 (#%define-syntax (define-values expr)
-                 ; (displayln expr)
                  (define underlying (syntax-e expr))
-                 (define bindings (syntax->datum (second underlying)))
+                 (define formals (syntax->datum (second underlying)))
                  (define expression (third underlying))
-                 (define unreadable-list-name
-                   (make-unreadable '#%proto-define-values-binding-gensym__))
+                 (define identifiers (formals->identifiers formals))
+                 (define unreadable-list-name (gensym))
                  `(begin
-                    (define ,unreadable-list-name ,expression)
+                    (define ,unreadable-list-name
+                      (apply (lambda ,formals (list ,@identifiers))
+                             (#%values->list (#%call-producer (lambda () ,expression)))))
                     ,@(map (lambda (binding-index-pair)
                              `(define ,(car binding-index-pair)
                                 (list-ref ,unreadable-list-name ,(list-ref binding-index-pair 1))))
-                           (enumerate 0 '() bindings))))
+                           (enumerate 0 '() identifiers))))
+
+;; Nests one `apply` per binding, so that every temporary is evaluated before any
+;; of the formals come into scope
+(define (let-values-body formals temporaries body)
+  (if (null? (cdr formals))
+      `(apply (lambda ,(car formals) ,@body) ,(car temporaries))
+      `(apply (lambda ,(car formals)
+                ,(let-values-body (cdr formals) (cdr temporaries) body))
+              ,(car temporaries))))
+
+(#%define-syntax (let-values expr)
+                 (define underlying (syntax-e expr))
+                 (define bindings (map syntax-e (syntax-e (second underlying))))
+                 (define body (cdr (cdr underlying)))
+                 (define temporaries (map (lambda (_) (gensym)) bindings))
+                 (if (null? bindings)
+                     `((lambda () ,@body))
+                     `(let ,(map (lambda (pair)
+                                   `[,(car pair)
+                                      (#%values->list
+                                       (#%call-producer (lambda ()
+                                                          ,(second (list-ref bindings (second pair))))))])
+                                 (enumerate 0 '() temporaries))
+                        ,(let-values-body (map (lambda (binding) (syntax->datum (car binding))) bindings)
+                                          temporaries
+                                          body))))
 
 (#%define-syntax (#%better-lambda expr)
                  ; (displayln "Expanding: " expr)
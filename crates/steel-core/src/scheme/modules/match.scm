@@ -381,7 +381,7 @@
     `(define-values (,@list-identifiers)
        (let ([,temp ,(go-match pattern variable `(list ,@list-identifiers) (mutable-vector))])
          (if (not (equal? #f ,temp))
-             ,temp
+             (apply values ,temp)
              (error-with-span (quote ,(syntax-span (third unwrapped)))
                               "Unable to match the given expression: "
                               ,variable
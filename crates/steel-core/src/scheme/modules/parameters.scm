@@ -7,20 +7,42 @@
 
 ;;;;;; Parameters ;;;;;
 
-(struct Parameter (getter value)
-  #:mutable
+;; The values of parameters live in the dynamic environment of the vm, keyed by
+;; `key`. `initial` is the value it has when it hasn't been set or parameterized,
+;; which is also what a parameter starts out as in threads spawned with `spawn-thread!`
+;; when its value can't be moved across.
+(struct Parameter (getter key converter)
   #:printer (lambda (obj printer-function) (simple-display "<procedure:parameter-procedure>"))
   #:prop:procedure 0)
 
-(define (make-parameter value)
-  (define param (Parameter 'uninitialized value))
-
-  (set-Parameter-getter! param
-                         (case-lambda
-                           [() (Parameter-value param)]
-                           [(new-value) (set-Parameter-value! param new-value)]))
-
-  param)
+(define make-parameter
+  (case-lambda
+    [(value) (make-parameter value (lambda (x) x))]
+    [(value converter)
+     (define key (#%make-parameter-key))
+     (define initial (converter value))
+     (Parameter (case-lambda
+                  [() (#%parameter-ref key initial)]
+                  [(new-value) (#%parameter-set! key (converter new-value))])
+                key
+                converter)]))
+
+;; Installs the new bindings for the extent of `thunk`. Leaving it, whether by returning,
+;; raising an error or through a continuation, puts back the bindings from outside.
+(define (call-with-parameterization params vals thunk)
+  (define outer (#%current-parameterization))
+  (define inner
+    (let loop ([frame outer] [params params] [vals vals])
+      (if (null? params)
+          frame
+          (loop (#%extend-parameterization frame
+                                           (Parameter-key (car params))
+                                           ((Parameter-converter (car params)) (car vals)))
+                (cdr params)
+                (cdr vals)))))
+  (dynamic-wind (lambda () (#%set-parameterization! inner))
+                thunk
+                (lambda () (#%set-parameterization! outer))))
 
 (define-syntax parameterize
   (syntax-rules ()
@@ -29,29 +51,26 @@
      (begin
        body ...)]
 
-    [(parameterize ([var val] rest ...)
+    [(parameterize ([var val] ...)
        body ...)
-
-     (let ([old-value (var)])
-
-       (dynamic-wind (lambda () (set-Parameter-value! var val))
-                     (lambda ()
-                       (parameterize (rest ...)
-                         body ...))
-                     (lambda () (set-Parameter-value! var old-value))))]))
+     (call-with-parameterization (list var ...) (list val ...) (lambda () body ...))]))
 
 ;;;;;;; Bootstrapping printing functions for various primitive structs
 
 (provide current-input-port
          current-output-port
+         current-error-port
          simple-display
          simple-displayln
          newline
          write-char
-         write)
+         write
+         write-shared
+         write-simple)
 
 (define current-input-port (make-parameter (#%default-input-port)))
 (define current-output-port (make-parameter (#%default-output-port)))
+(define current-error-port (make-parameter (#%default-error-port)))
 
 (define (simple-display x)
   (raw-write-string (current-output-port) x))
@@ -69,13 +88,27 @@
 (define (write-char char port)
   (raw-write-char port char))
 
-(define (write obj port)
-  (raw-write port obj))
+(define write
+  (case-lambda
+    [(obj) (raw-write (current-output-port) obj)]
+    [(obj port) (raw-write port obj)]))
+
+(define write-shared
+  (case-lambda
+    [(obj) (raw-write-shared (current-output-port) obj)]
+    [(obj port) (raw-write-shared port obj)]))
+
+(define write-simple
+  (case-lambda
+    [(obj) (raw-write-simple (current-output-port) obj)]
+    [(obj port) (raw-write-simple port obj)]))
 
 ;;;;;;;;;;;;;;;;;;;;; Port functions ;;;;;;;;;;;;;;;;;;;;;
 
 (provide call-with-output-string
-         with-output-to-string)
+         with-output-to-string
+         printf
+         eprintf)
 
 (define (call-with-output-string proc)
   (define output-string (open-output-string))
@@ -87,6 +120,14 @@
                              (parameterize ([current-output-port p])
                                (proc)))))
 
+;; Like `format`, but writes the result to the current output port
+(define (printf format-string . args)
+  (apply fprintf (cons (current-output-port) (cons format-string args))))
+
+;; Like `format`, but writes the result to the current error port
+(define (eprintf format-string . args)
+  (apply fprintf (cons (current-error-port) (cons format-string args))))
+
 ;;;;;;;;;;;;;;;;;;;;; Dynamic Wind ;;;;;;;;;;;;;;;;;;;;;;;
 
 (define winders '())
@@ -132,10 +173,10 @@
   (lambda (f)
     (#%prim.call/cc (lambda (k)
                       (f (let ([save winders])
-                           (Continuation (lambda (x)
+                           (Continuation (lambda args
                                            (unless (eq? save winders)
                                              (do-wind save))
-                                           (k x)))))))))
+                                           (apply k args)))))))))
 
 (define call-with-current-continuation call/cc)
 
@@ -146,22 +187,141 @@
   (lambda (in body out)
     (in)
     (set! winders (cons (cons in out) winders))
-    (let ([ans* (call-with-exception-handler (lambda (err)
-                                               ;; Catch the exception on the way out
+    (let ([ans* (#%values->list
+                 (#%call-producer (lambda ()
+                                    (call-with-exception-handler (lambda (err)
+                                                                   ;; Catch the exception on the way out
 
-                                               ; (displayln winders)
+                                                                   ; (displayln winders)
 
-                                               ; (displayln "catching exception here")
+                                                                   ; (displayln "catching exception here")
 
-                                               (set! winders (cdr winders))
-                                               (out)
-                                               (raise-error err)
+                                                                   (set! winders (cdr winders))
+                                                                   (out)
+                                                                   (raise-error err)
 
-                                               void)
-                                             (lambda () (body)))])
+                                                                   void)
+                                                                 (lambda () (body))))))])
 
       ; (displayln winders)
 
       (set! winders (cdr winders))
       (out)
-      ans*)))
+      (apply values ans*))))
+
+;;;;;;;;;;;;;;;;;;;;; Exceptions ;;;;;;;;;;;;;;;;;;;;;;;
+
+(provide with-exception-handler
+         raise
+         raise-continuable
+         (for-syntax guard)
+         (for-syntax define-condition-type))
+
+;; The handlers installed by `with-exception-handler` and `guard`, innermost first.
+;;
+;; `raise` and `raise-continuable` call the innermost handler directly, in their own dynamic
+;; environment, whereas errors from primitives reach a handler by unwinding the stack to it.
+(define *exception-handlers* '())
+
+;; Errors raised while a handler is running are marked with the depth of the handlers
+;; outside of it, so that the handlers in between let them through.
+(define (handles-error? depth err)
+  (let ([raised-at (#%error-handler-depth err)]) (or (not raised-at) (<= depth raised-at))))
+
+(define (with-exception-handler handler thunk)
+  (define outer *exception-handlers*)
+  (define depth (+ 1 (length outer)))
+  (call-with-exception-handler
+   (lambda (err)
+     (set! *exception-handlers* outer)
+     (when (not (handles-error? depth err))
+       (raise-error err))
+     (handler (#%error->condition err))
+     (error "exception handler returned from a non-continuable exception:"
+            (#%error->condition err)))
+   (lambda ()
+     (dynamic-wind (lambda () (set! *exception-handlers* (cons handler outer)))
+                   thunk
+                   (lambda () (set! *exception-handlers* outer))))))
+
+;; Calls the innermost of `handlers` on `obj` with the outer ones installed. Returning
+;; from the handler is an error unless the exception is `continuable?`.
+(define (call-exception-handler handlers obj continuable?)
+  (call-with-exception-handler
+   (lambda (err)
+     (set! *exception-handlers* handlers)
+     (if (#%error-handler-depth err)
+         (raise-error err)
+         (#%raise-with-depth err (- (length handlers) 1))))
+   (lambda ()
+     (define result
+       (dynamic-wind (lambda () (set! *exception-handlers* (cdr handlers)))
+                     (lambda () ((car handlers) obj))
+                     (lambda () (set! *exception-handlers* handlers))))
+     (if continuable?
+         result
+         (error "exception handler returned from a non-continuable exception:" obj)))))
+
+(define (raise obj)
+  (define handlers *exception-handlers*)
+  (if (null? handlers)
+      (#%prim.raise obj)
+      (call-exception-handler handlers obj #f)))
+
+(define (raise-continuable obj)
+  (define handlers *exception-handlers*)
+  (if (null? handlers)
+      (raise obj)
+      (call-exception-handler handlers obj #t)))
+
+(define (call-with-guard clauses thunk)
+  (define outer *exception-handlers*)
+  (define depth (+ 1 (length outer)))
+  (call-with-exception-handler
+   (lambda (err)
+     (set! *exception-handlers* outer)
+     (if (handles-error? depth err) (clauses (#%error->condition err)) (raise-error err)))
+   (lambda ()
+     (dynamic-wind (lambda ()
+                     (set! *exception-handlers*
+                           (cons (lambda (condition) (#%raise-with-depth condition depth)) outer)))
+                   thunk
+                   (lambda () (set! *exception-handlers* outer))))))
+
+(define-syntax guard-clauses
+  (syntax-rules (else =>)
+    [(guard-clauses var) (raise var)]
+    [(guard-clauses var [else body ...]) (begin body ...)]
+    ;; The remaining clauses are wrapped in thunks outside of the scope of the temporary,
+    ;; so that it can't shadow anything they refer to
+    [(guard-clauses var [test => receiver] rest ...)
+     ((lambda (value receiver-thunk otherwise) (if value ((receiver-thunk) value) (otherwise)))
+      test
+      (lambda () receiver)
+      (lambda () (guard-clauses var rest ...)))]
+    [(guard-clauses var [test] rest ...)
+     ((lambda (value otherwise) (if value value (otherwise)))
+      test
+      (lambda () (guard-clauses var rest ...)))]
+    [(guard-clauses var [test body ...] rest ...)
+     (if test (begin body ...) (guard-clauses var rest ...))]))
+
+;; (guard (var clause ...) body ...)
+;;
+;; Evaluates the body, and if anything is raised, binds it to `var` and evaluates the
+;; clauses like `cond`. When none of the clauses apply the object is raised again,
+;; to the handlers outside of the `guard`.
+(define-syntax guard
+  (syntax-rules ()
+    [(guard (var clause ...) body ...)
+     (call-with-guard (lambda (var) (guard-clauses var clause ...)) (lambda () body ...))]))
+
+;; (define-condition-type name (field ...))
+;;
+;; Defines a struct that can be raised as an error object: the constructor takes the
+;; message and a list of irritants before the given fields, which `error-object-message`
+;; and `error-object-irritants` read back.
+(define-syntax define-condition-type
+  (syntax-rules ()
+    [(define-condition-type name (field ...))
+     (struct name (message irritants field ...) #:transparent #:prop:error-object #t)]))
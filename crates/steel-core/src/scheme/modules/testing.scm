@@ -0,0 +1,131 @@
+(provide (for-syntax test-case)
+         (for-syntax check-equal?)
+         (for-syntax check-not-equal?)
+         (for-syntax check-true)
+         (for-syntax check-false)
+         (for-syntax check-error)
+         registered-test-names
+         run-registered-test
+         start-test-harness!
+         test-harness-running?
+         register-check-result!)
+
+;;;;;;;;;;;;;;;;;;;;; Test registry ;;;;;;;;;;;;;;;;;;;;;
+
+;; Tests are registered in reverse order as the module they are defined in is loaded,
+;; and are only run when the test harness asks for them by index. Once the harness has
+;; asked for their names, they're indexed from a vector in the order they were defined.
+(define *registered-tests* '())
+(define *indexed-tests* (vector))
+
+;; Whether a test is currently running, and the failure messages of any checks
+;; that have failed during that test.
+(define *running-test* #f)
+(define *current-failures* '())
+(define *captured-output* #f)
+
+;; Set by `steel test` before it loads a test file
+(define *harness-running* #f)
+
+(define (start-test-harness!)
+  (set! *harness-running* #t))
+
+(define (test-harness-running?)
+  *harness-running*)
+
+(define (register-test! name thunk)
+  (set! *registered-tests* (cons (cons name thunk) *registered-tests*)))
+
+;; Registers a check that has already run as a test of its own, for test libraries that run their
+;; checks as the file is loaded. The message is #f if the check passed.
+(define (register-check-result! name message)
+  (register-test! name
+                  (lambda ()
+                    (when message
+                      (fail-check! name message)))))
+
+;; Checks that appear outside of a `test-case` become a test on their own
+(define (check-or-register name thunk)
+  (if *running-test*
+      (thunk)
+      (register-test! name thunk)))
+
+(define (fail-check! name message)
+  (set! *current-failures* (cons (string-append name ": " message) *current-failures*)))
+
+(define (registered-test-names)
+  (define tests (reverse *registered-tests*))
+  (set! *indexed-tests* (apply vector tests))
+  (map car tests))
+
+;; Runs the test at the given index, with anything written to the current output port captured.
+;; Returns a list of the failed check messages, the error raised by the test (or #f), and the
+;; captured output.
+(define (run-registered-test index)
+  (define raised #f)
+
+  (set! *running-test* #t)
+  (set! *current-failures* '())
+  (set! *captured-output* (open-output-string))
+
+  ;; The port is kept out of the locals, so that the handler's continuation doesn't capture it
+  (with-handler (lambda (err) (set! raised err))
+                (parameterize ([current-output-port *captured-output*])
+                  ((cdr (vector-ref *indexed-tests* index)))))
+
+  (set! *running-test* #f)
+  (list (reverse *current-failures*) raised (get-output-string *captured-output*)))
+
+;;;;;;;;;;;;;;;;;;;;; Test forms ;;;;;;;;;;;;;;;;;;;;;
+
+(define-syntax test-case
+  (syntax-rules ()
+    [(test-case name body ...)
+     (register-test! name
+                     (lambda ()
+                       body ...
+                       void))]))
+
+(define-syntax check-equal?
+  (syntax-rules ()
+    [(check-equal? name actual expected)
+     (check-or-register name (lambda () (check-equal-impl name actual expected)))]))
+
+(define-syntax check-not-equal?
+  (syntax-rules ()
+    [(check-not-equal? name actual expected)
+     (check-or-register name (lambda () (check-not-equal-impl name actual expected)))]))
+
+(define-syntax check-true
+  (syntax-rules ()
+    [(check-true name expr) (check-or-register name (lambda () (check-true-impl name expr)))]))
+
+(define-syntax check-false
+  (syntax-rules ()
+    [(check-false name expr) (check-or-register name (lambda () (check-false-impl name expr)))]))
+
+(define-syntax check-error
+  (syntax-rules ()
+    [(check-error name expr) (check-or-register name (lambda () (check-error-impl name (lambda () expr))))]))
+
+(define (check-equal-impl name actual expected)
+  (unless (equal? actual expected)
+    (fail-check! name (to-string "expected:" expected "actual:" actual))))
+
+(define (check-not-equal-impl name actual expected)
+  (when (equal? actual expected)
+    (fail-check! name (to-string "expected a value other than:" expected))))
+
+(define (check-true-impl name value)
+  (unless value
+    (fail-check! name "expected a true value, found: #false")))
+
+(define (check-false-impl name value)
+  (when value
+    (fail-check! name (to-string "expected #false, found:" value))))
+
+(define (check-error-impl name thunk)
+  (define raised #f)
+  (with-handler (lambda (err) (set! raised #t)) (thunk))
+  (unless raised
+    (fail-check! name "expected an error to be raised")))
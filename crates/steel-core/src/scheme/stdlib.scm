@@ -56,7 +56,8 @@
          *reset
          *shift
          force
-         values
+         make-promise
+         stream-cdr
          call-with-values)
 
 ; (define-syntax steel/base
@@ -662,116 +663,10 @@
   (syntax-rules ()
     [(case-lambda) (lambda args (error "CASE-LAMBDA without any clauses."))]
     [(case-lambda
-       [?a1
-        ?e1 ...]
-       ?clause1 ...)
-     (lambda args
-       (let ([l (length args)])
-         (case-lambda
-           "CLAUSE"
-           args
-           l
-           [?a1
-            ?e1 ...]
-           ?clause1 ...)))]
-    [(case-lambda
-       "CLAUSE"
-       ?args
-       ?l
-       [(?a1 ...)
-        ?e1 ...])
-     (if (= ?l (length '(?a1 ...)))
-         (apply (lambda (?a1 ...)
-                  ?e1 ...)
-                ?args)
-         (error! "Arity mismatch in case lambda: attempted to call function with arg length: "
-                 ?l
-                 "expecting length: "
-                 (length '(?a1 ...))))]
-    [(case-lambda
-       "CLAUSE"
-       ?args
-       ?l
-       [(?a1 ...)
-        ?e1 ...]
-       ?clause1 ...)
-     (if (= ?l (length '(?a1 ...)))
-         (apply (lambda (?a1 ...)
-                  ?e1 ...)
-                ?args)
-         (case-lambda
-           "CLAUSE"
-           ?args
-           ?l
-           ?clause1 ...))]
-    [(case-lambda
-       "CLAUSE"
-       ?args
-       ?l
-       [(?a1 . ?ar)
-        ?e1 ...]
-       ?clause1 ...)
-     (case-lambda
-       "IMPROPER"
-       ?args
-       ?l
-       1
-       [?a1
-        . ?ar]
-       [?ar
-        ?e1 ...]
-       ?clause1 ...)]
-    [(case-lambda
-       "CLAUSE"
-       ?args
-       ?l
-       [?a1
-        ?e1 ...])
-     (let ([?a1 ?args])
-       ?e1 ...)]
-    [(case-lambda
-       "CLAUSE"
-       ?args
-       ?l)
-     (error "Wrong number of arguments to CASE-LAMBDA.")]
-    [(case-lambda
-       "IMPROPER"
-       ?args
-       ?l
-       ?k
-       ?al
-       [(?a1 . ?ar)
-        ?e1 ...]
-       ?clause1 ...)
-     (case-lambda
-       "IMPROPER"
-       ?args
-       ?l
-       [+
-        ?k
-        1]
-       ?al
-       [?ar
-        ?e1 ...]
-       ?clause1 ...)]
-    [(case-lambda
-       "IMPROPER"
-       ?args
-       ?l
-       ?k
-       ?al
-       [?ar
-        ?e1 ...]
-       ?clause1 ...)
-     (if (>= ?l ?k)
-         (apply (lambda ?al
-                  ?e1 ...)
-                ?args)
-         (case-lambda
-           "CLAUSE"
-           ?args
-           ?l
-           ?clause1 ...))]))
+       [formals
+        body ...] ...)
+     (#%case-lambda (lambda formals
+                      body ...) ...)]))
 
 (define-syntax help
   (syntax-rules ()
@@ -798,21 +693,55 @@
   (syntax-rules ()
     [(contract/out name contract) (%require-ident-spec name (bind/c contract name 'name))]))
 
+;; Promises follow the R7RS reference implementation, see `Promise` for how
+;; chains of `delay-force` are kept from growing
 (define (force promise)
-  (promise))
+  (if (promise? promise)
+      (if (#%promise-done? promise)
+          (#%promise-value promise)
+          (let ([promise* ((#%promise-value promise))])
+            (when (not (#%promise-done? promise))
+              (#%promise-update! promise* promise))
+            (force promise)))
+      promise))
+
+(define (make-promise obj)
+  (if (promise? obj) obj (#%make-promise #t obj)))
 
 ;; syntax
+(define-syntax delay-force
+  (syntax-rules ()
+    [(delay-force expr) (#%make-promise #f (lambda () expr))]))
+
 (define-syntax delay
   (syntax-rules ()
-    [(delay expr) (lambda () expr)]))
+    [(delay expr) (delay-force (#%make-promise #t expr))]))
+
+;; The tail of the stream is only evaluated once, when it is first needed. A tail
+;; written as a thunk is still accepted, and is called at that point instead.
+(define-syntax stream-cons
+  (syntax-rules ()
+    [(stream-cons head tail)
+     (#%stream-cons head
+                    ((lambda (promise) (lambda () (force promise)))
+                     (delay ((lambda (rest) (if (procedure? rest) (rest) rest)) tail))))]))
+
+(define (stream-cdr stream)
+  ((#%stream-cdr stream)))
 
-(define values list)
 (define (call-with-values producer consumer)
-  (define result (apply consumer (producer)))
-  (cond
-    [(not (list? result)) result]
-    [(= (length result) 1) (car result)]
-    [else result]))
+  (apply consumer (#%values->list (#%call-producer producer))))
+
+(define-syntax receive
+  (syntax-rules ()
+    [(receive formals expr body ...)
+     (call-with-values (lambda () expr) (lambda formals body ...))]))
+
+(define-syntax let*-values
+  (syntax-rules ()
+    [(let*-values () body ...) ((lambda () body ...))]
+    [(let*-values ([formals expr] rest ...) body ...)
+     (call-with-values (lambda () expr) (lambda formals (let*-values (rest ...) body ...)))]))
 
 (define-syntax @doc
   (syntax-rules (struct define/contract)
@@ -0,0 +1,1064 @@
+//! Conversions between Rust types and `SteelVal`s via serde.
+//!
+//! Any type that implements `Serialize` can be turned into a `SteelVal` with [`to_steelval`], and any
+//! type that implements `Deserialize` can be read back out of one with [`from_steelval`], without
+//! needing hand written `IntoSteelVal` / `FromSteelVal` implementations.
+//!
+//! Values are mapped as follows:
+//!
+//! | Rust                          | Steel                                                      |
+//! |-------------------------------|------------------------------------------------------------|
+//! | `bool`                        | boolean                                                    |
+//! | integers                      | integer, or a big integer if it doesn't fit in an `isize`  |
+//! | `f32`, `f64`                  | number                                                     |
+//! | `char`                        | character                                                  |
+//! | `String`, `&str`              | string                                                     |
+//! | `Option<T>`                   | `#false` for `None`, otherwise the value itself            |
+//! | `()`, unit structs            | void                                                       |
+//! | sequences, tuples, bytes      | list                                                       |
+//! | maps                          | hashmap                                                    |
+//! | structs                       | hashmap keyed by symbols, or a struct, see [`StructRepresentation`] |
+//! | unit variants                 | symbol of the variant name                                 |
+//! | other variants                | hashmap with a single entry from the variant name to its contents |
+//!
+//! Since `None` is represented as `#false`, an `Option<bool>` holding `Some(false)` reads back as `None`.
+//!
+//! When reading values, vectors are accepted wherever a list is expected, strings and symbols are
+//! interchangeable, and structs declared in Steel are read as a map from their field names to their values.
+//! Errors include the path to the value that couldn't be converted, for example `servers[1].port`.
+
+use std::fmt::{self, Display};
+
+use ::serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use ::serde::ser::{self, Serialize};
+use num::{BigInt, ToPrimitive};
+
+use crate::{
+    gc::Gc,
+    rerrs::{ErrorKind, SteelErr},
+    rvals::{IntoSteelVal, Result, SteelVal},
+    values::{lists::List, structs::UserDefinedStruct},
+};
+
+/// Converts `value` into a `SteelVal`, representing structs as hashmaps
+pub fn to_steelval<T: ?Sized + Serialize>(value: &T) -> Result<SteelVal> {
+    value
+        .serialize(Serializer::new(StructRepresentation::HashMap))
+        .map_err(SteelErr::from)
+}
+
+/// Converts `value` into a `SteelVal`, representing structs as Steel structs
+pub fn to_steelval_with_structs<T: ?Sized + Serialize>(value: &T) -> Result<SteelVal> {
+    value
+        .serialize(Serializer::new(StructRepresentation::Struct))
+        .map_err(SteelErr::from)
+}
+
+/// Reads a `T` out of `value`
+pub fn from_steelval<T: DeserializeOwned>(value: &SteelVal) -> Result<T> {
+    T::deserialize(Deserializer::new(value)).map_err(SteelErr::from)
+}
+
+/// How Rust structs are represented when serialized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StructRepresentation {
+    /// A hashmap from the field names, as symbols, to their values
+    #[default]
+    HashMap,
+    /// An instance of the most recently declared Steel struct with the same name and fields. If no
+    /// such struct has been declared, a transparent struct type is declared for it.
+    Struct,
+}
+
+/// An error converting to or from a `SteelVal`, along with where in the value it happened
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    // Segments of the path to the value, innermost first
+    path: Vec<PathSegment>,
+    message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    Field(String),
+    Index(usize),
+}
+
+impl Error {
+    fn at(mut self, segment: PathSegment) -> Self {
+        self.path.push(segment);
+        self
+    }
+
+    /// The path to the value that failed to convert, e.g. `servers[1].port`
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+
+        for segment in self.path.iter().rev() {
+            match segment {
+                PathSegment::Field(name) if path.is_empty() => path.push_str(name),
+                PathSegment::Field(name) => {
+                    path.push('.');
+                    path.push_str(name);
+                }
+                PathSegment::Index(index) => path.push_str(&format!("[{index}]")),
+            }
+        }
+
+        path
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path(), self.message)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error {
+            path: Vec::new(),
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error {
+            path: Vec::new(),
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl From<Error> for SteelErr {
+    fn from(e: Error) -> Self {
+        SteelErr::new(ErrorKind::ConversionError, e.to_string())
+    }
+}
+
+impl From<SteelErr> for Error {
+    fn from(e: SteelErr) -> Self {
+        <Error as ser::Error>::custom(e)
+    }
+}
+
+type SerdeResult<T> = std::result::Result<T, Error>;
+
+fn hashmap(map: im_rc::HashMap<SteelVal, SteelVal>) -> SteelVal {
+    SteelVal::HashMapV(Gc::new(map).into())
+}
+
+fn tagged(variant: &str, value: SteelVal) -> SteelVal {
+    hashmap(im_rc::hashmap! { SteelVal::SymbolV(variant.into()) => value })
+}
+
+/// Serializes Rust values into `SteelVal`s
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Serializer {
+    structs: StructRepresentation,
+}
+
+impl Serializer {
+    pub fn new(structs: StructRepresentation) -> Self {
+        Self { structs }
+    }
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = SteelVal;
+    type Error = Error;
+
+    type SerializeSeq = SerializeVec;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = SerializeVec;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeStruct;
+    type SerializeStructVariant = SerializeStruct;
+
+    fn serialize_bool(self, v: bool) -> SerdeResult<SteelVal> {
+        Ok(SteelVal::BoolV(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> SerdeResult<SteelVal> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> SerdeResult<SteelVal> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> SerdeResult<SteelVal> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> SerdeResult<SteelVal> {
+        Ok(BigInt::from(v).into_steelval()?)
+    }
+
+    fn serialize_i128(self, v: i128) -> SerdeResult<SteelVal> {
+        Ok(BigInt::from(v).into_steelval()?)
+    }
+
+    fn serialize_u8(self, v: u8) -> SerdeResult<SteelVal> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u16(self, v: u16) -> SerdeResult<SteelVal> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u32(self, v: u32) -> SerdeResult<SteelVal> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u64(self, v: u64) -> SerdeResult<SteelVal> {
+        Ok(BigInt::from(v).into_steelval()?)
+    }
+
+    fn serialize_u128(self, v: u128) -> SerdeResult<SteelVal> {
+        Ok(BigInt::from(v).into_steelval()?)
+    }
+
+    fn serialize_f32(self, v: f32) -> SerdeResult<SteelVal> {
+        Ok(SteelVal::NumV(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> SerdeResult<SteelVal> {
+        Ok(SteelVal::NumV(v))
+    }
+
+    fn serialize_char(self, v: char) -> SerdeResult<SteelVal> {
+        Ok(SteelVal::CharV(v))
+    }
+
+    fn serialize_str(self, v: &str) -> SerdeResult<SteelVal> {
+        Ok(SteelVal::StringV(v.into()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> SerdeResult<SteelVal> {
+        Ok(SteelVal::ListV(
+            v.iter().map(|x| SteelVal::IntV(*x as isize)).collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> SerdeResult<SteelVal> {
+        Ok(SteelVal::BoolV(false))
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> SerdeResult<SteelVal> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> SerdeResult<SteelVal> {
+        Ok(SteelVal::Void)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> SerdeResult<SteelVal> {
+        Ok(SteelVal::Void)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> SerdeResult<SteelVal> {
+        Ok(SteelVal::SymbolV(variant.into()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> SerdeResult<SteelVal> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> SerdeResult<SteelVal> {
+        let value = value
+            .serialize(self)
+            .map_err(|e| e.at(PathSegment::Field(variant.to_string())))?;
+
+        Ok(tagged(variant, value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> SerdeResult<SerializeVec> {
+        Ok(SerializeVec {
+            serializer: self,
+            values: Vec::with_capacity(len.unwrap_or_default()),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> SerdeResult<SerializeVec> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> SerdeResult<SerializeVec> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> SerdeResult<SerializeVec> {
+        Ok(SerializeVec {
+            serializer: self,
+            values: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> SerdeResult<SerializeMap> {
+        Ok(SerializeMap {
+            serializer: self,
+            map: im_rc::HashMap::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> SerdeResult<SerializeStruct> {
+        Ok(SerializeStruct {
+            serializer: self,
+            name,
+            names: Vec::with_capacity(len),
+            values: Vec::with_capacity(len),
+            variant: None,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> SerdeResult<SerializeStruct> {
+        Ok(SerializeStruct {
+            serializer: self,
+            name: variant,
+            names: Vec::with_capacity(len),
+            values: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+}
+
+pub struct SerializeVec {
+    serializer: Serializer,
+    values: Vec<SteelVal>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeVec {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> SerdeResult<()> {
+        let index = self.values.len();
+        let value = value
+            .serialize(self.serializer)
+            .map_err(|e| e.at(PathSegment::Index(index)))?;
+        self.values.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> SerdeResult<SteelVal> {
+        let list = SteelVal::ListV(self.values.into_iter().collect::<List<_>>());
+
+        Ok(match self.variant {
+            Some(variant) => tagged(variant, list),
+            None => list,
+        })
+    }
+}
+
+impl ser::SerializeSeq for SerializeVec {
+    type Ok = SteelVal;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> SerdeResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> SerdeResult<SteelVal> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeVec {
+    type Ok = SteelVal;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> SerdeResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> SerdeResult<SteelVal> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeVec {
+    type Ok = SteelVal;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> SerdeResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> SerdeResult<SteelVal> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVec {
+    type Ok = SteelVal;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> SerdeResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> SerdeResult<SteelVal> {
+        self.finish()
+    }
+}
+
+pub struct SerializeMap {
+    serializer: Serializer,
+    map: im_rc::HashMap<SteelVal, SteelVal>,
+    key: Option<SteelVal>,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = SteelVal;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> SerdeResult<()> {
+        self.key = Some(key.serialize(self.serializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> SerdeResult<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| <Error as ser::Error>::custom("map value serialized before its key"))?;
+
+        let value = value
+            .serialize(self.serializer)
+            .map_err(|e| e.at(PathSegment::Field(key.to_string())))?;
+
+        self.map.insert(key, value);
+        Ok(())
+    }
+
+    fn end(self) -> SerdeResult<SteelVal> {
+        Ok(hashmap(self.map))
+    }
+}
+
+pub struct SerializeStruct {
+    serializer: Serializer,
+    name: &'static str,
+    names: Vec<&'static str>,
+    values: Vec<SteelVal>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeStruct {
+    fn push<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> SerdeResult<()> {
+        let value = value
+            .serialize(self.serializer)
+            .map_err(|e| e.at(PathSegment::Field(key.to_string())))?;
+        self.names.push(key);
+        self.values.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> SerdeResult<SteelVal> {
+        let value = match self.serializer.structs {
+            StructRepresentation::HashMap => hashmap(
+                self.names
+                    .iter()
+                    .map(|x| SteelVal::SymbolV((*x).into()))
+                    .zip(self.values)
+                    .collect(),
+            ),
+            StructRepresentation::Struct => SteelVal::CustomStruct(Gc::new(
+                UserDefinedStruct::with_named_fields(self.name, &self.names, self.values),
+            )),
+        };
+
+        // A struct variant is already tagged with its name when it's represented as a struct
+        Ok(match self.variant {
+            Some(variant) if self.serializer.structs == StructRepresentation::HashMap => {
+                tagged(variant, value)
+            }
+            _ => value,
+        })
+    }
+}
+
+impl ser::SerializeStruct for SerializeStruct {
+    type Ok = SteelVal;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> SerdeResult<()> {
+        self.push(key, value)
+    }
+
+    fn end(self) -> SerdeResult<SteelVal> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeStruct {
+    type Ok = SteelVal;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> SerdeResult<()> {
+        self.push(key, value)
+    }
+
+    fn end(self) -> SerdeResult<SteelVal> {
+        self.finish()
+    }
+}
+
+/// Deserializes Rust values out of a `SteelVal`
+pub struct Deserializer<'a> {
+    value: &'a SteelVal,
+}
+
+impl<'a> Deserializer<'a> {
+    pub fn new(value: &'a SteelVal) -> Self {
+        Self { value }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self.value {
+            SteelVal::BoolV(_) => "boolean",
+            SteelVal::IntV(_) | SteelVal::BigNum(_) => "integer",
+            SteelVal::NumV(_) | SteelVal::Rational(_) | SteelVal::BigRational(_) => "number",
+            SteelVal::CharV(_) => "character",
+            SteelVal::StringV(_) => "string",
+            SteelVal::SymbolV(_) => "symbol",
+            SteelVal::Keyword(_) => "keyword",
+            SteelVal::ListV(_) => "list",
+            SteelVal::VectorV(_) | SteelVal::MutableVector(_) => "vector",
+            SteelVal::HashMapV(_) => "hashmap",
+            SteelVal::HashSetV(_) => "hashset",
+            SteelVal::CustomStruct(_) => "struct",
+            SteelVal::Void => "void",
+            _ => "value",
+        }
+    }
+
+    fn invalid_type<T>(&self, expected: &str) -> SerdeResult<T> {
+        Err(<Error as de::Error>::custom(format!(
+            "expected {expected}, found {}: {}",
+            self.type_name(),
+            self.value
+        )))
+    }
+
+    fn elements(&self) -> Option<Vec<SteelVal>> {
+        match self.value {
+            SteelVal::ListV(l) => Some(l.iter().cloned().collect()),
+            SteelVal::VectorV(v) => Some(v.iter().cloned().collect()),
+            SteelVal::MutableVector(v) => Some(v.get()),
+            SteelVal::HashSetV(s) => Some(s.iter().cloned().collect()),
+            _ => None,
+        }
+    }
+
+    fn entries(&self) -> Option<Vec<(SteelVal, SteelVal)>> {
+        match self.value {
+            SteelVal::HashMapV(m) => Some(m.iter().map(|(k, v)| (k.clone(), v.clone())).collect()),
+            SteelVal::CustomStruct(s) => Some(
+                s.field_names()?
+                    .into_iter()
+                    .map(SteelVal::SymbolV)
+                    .zip(s.fields().iter().cloned())
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Deserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.value {
+            SteelVal::BoolV(b) => visitor.visit_bool(*b),
+            SteelVal::IntV(i) => visitor.visit_i64(*i as i64),
+            SteelVal::BigNum(b) => {
+                // Prefer the narrowest type, since not every visitor handles 128 bit integers
+                if let Some(u) = b.to_u64() {
+                    visitor.visit_u64(u)
+                } else if let Some(i) = b.to_i128() {
+                    visitor.visit_i128(i)
+                } else if let Some(u) = b.to_u128() {
+                    visitor.visit_u128(u)
+                } else {
+                    Err(<Error as de::Error>::custom(format!(
+                        "integer out of range: {}",
+                        self.value
+                    )))
+                }
+            }
+            SteelVal::NumV(n) => visitor.visit_f64(*n),
+            SteelVal::Rational(r) => visitor.visit_f64(*r.numer() as f64 / *r.denom() as f64),
+            SteelVal::BigRational(r) => match r.to_f64() {
+                Some(n) => visitor.visit_f64(n),
+                None => self.invalid_type("a number that fits in an f64"),
+            },
+            SteelVal::CharV(c) => visitor.visit_char(*c),
+            SteelVal::StringV(s) | SteelVal::SymbolV(s) => visitor.visit_str(s.as_str()),
+            SteelVal::Void => visitor.visit_unit(),
+            SteelVal::HashMapV(_) | SteelVal::CustomStruct(_) => self.deserialize_map(visitor),
+            _ if self.elements().is_some() => self.deserialize_seq(visitor),
+            _ => self.invalid_type("a value that can be deserialized"),
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.value {
+            SteelVal::IntV(i) => visitor.visit_f64(*i as f64),
+            SteelVal::BigNum(b) => match b.to_f64() {
+                Some(n) => visitor.visit_f64(n),
+                None => self.invalid_type("a number that fits in an f64"),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.value {
+            SteelVal::BoolV(false) | SteelVal::Void => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.value {
+            SteelVal::Void => visitor.visit_unit(),
+            _ => self.invalid_type("void"),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> SerdeResult<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> SerdeResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        let Some(elements) = self.elements() else {
+            return self.invalid_type("a list of bytes");
+        };
+
+        let bytes = elements
+            .iter()
+            .enumerate()
+            .map(|(index, x)| match x {
+                SteelVal::IntV(i) if (0..=255).contains(i) => Ok(*i as u8),
+                _ => Err(
+                    <Error as de::Error>::custom(format!("expected a byte, found: {x}"))
+                        .at(PathSegment::Index(index)),
+                ),
+            })
+            .collect::<SerdeResult<Vec<_>>>()?;
+
+        visitor.visit_byte_buf(bytes)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.elements() {
+            Some(elements) => visitor.visit_seq(SeqDeserializer {
+                elements: elements.into_iter(),
+                index: 0,
+            }),
+            None => self.invalid_type("a list"),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> SerdeResult<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> SerdeResult<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.entries() {
+            Some(entries) => visitor.visit_map(MapDeserializer {
+                entries: entries.into_iter(),
+                value: None,
+            }),
+            None => self.invalid_type("a hashmap"),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> SerdeResult<V::Value> {
+        match self.value {
+            SteelVal::HashMapV(_) | SteelVal::CustomStruct(_) => self.deserialize_map(visitor),
+            _ if self.elements().is_some() => self.deserialize_seq(visitor),
+            _ => self.invalid_type("a hashmap or struct"),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> SerdeResult<V::Value> {
+        match self.value {
+            SteelVal::StringV(s) | SteelVal::SymbolV(s) => {
+                visitor.visit_enum(s.as_str().into_deserializer())
+            }
+            SteelVal::HashMapV(m) if m.len() == 1 => {
+                let (variant, value) = m.iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer {
+                    variant: variant.clone(),
+                    value: Some(value.clone()),
+                })
+            }
+            // A struct declared in Steel is read as the variant with the same name
+            SteelVal::CustomStruct(s) => visitor.visit_enum(EnumDeserializer {
+                variant: SteelVal::SymbolV(s.name().resolve().into()),
+                value: Some(self.value.clone()),
+            }),
+            _ => self.invalid_type("a symbol, a hashmap with a single entry, or a struct"),
+        }
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string identifier ignored_any
+    }
+}
+
+struct SeqDeserializer {
+    elements: std::vec::IntoIter<SteelVal>,
+    index: usize,
+}
+
+impl<'de> SeqAccess<'de> for SeqDeserializer {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> SerdeResult<Option<T::Value>> {
+        let Some(value) = self.elements.next() else {
+            return Ok(None);
+        };
+
+        let index = self.index;
+        self.index += 1;
+
+        seed.deserialize(Deserializer::new(&value))
+            .map(Some)
+            .map_err(|e| e.at(PathSegment::Index(index)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+struct MapDeserializer {
+    entries: std::vec::IntoIter<(SteelVal, SteelVal)>,
+    value: Option<(SteelVal, SteelVal)>,
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> SerdeResult<Option<K::Value>> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+
+        let result = seed.deserialize(Deserializer::new(&key)).map(Some);
+        self.value = Some((key, value));
+        result
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> SerdeResult<V::Value> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| <Error as de::Error>::custom("map value requested before its key"))?;
+
+        seed.deserialize(Deserializer::new(&value))
+            .map_err(|e| e.at(PathSegment::Field(field_name(&key))))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+fn field_name(key: &SteelVal) -> String {
+    match key {
+        SteelVal::StringV(s) | SteelVal::SymbolV(s) => s.to_string(),
+        other => other.to_string(),
+    }
+}
+
+struct EnumDeserializer {
+    variant: SteelVal,
+    value: Option<SteelVal>,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = Error;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> SerdeResult<(V::Value, VariantDeserializer)> {
+        let variant = seed.deserialize(Deserializer::new(&self.variant))?;
+
+        Ok((
+            variant,
+            VariantDeserializer {
+                name: field_name(&self.variant),
+                value: self.value,
+            },
+        ))
+    }
+}
+
+struct VariantDeserializer {
+    name: String,
+    value: Option<SteelVal>,
+}
+
+impl VariantDeserializer {
+    fn value(&self) -> SerdeResult<&SteelVal> {
+        self.value.as_ref().ok_or_else(|| {
+            <Error as de::Error>::custom(format!("expected a value for the variant {}", self.name))
+        })
+    }
+
+    fn at_variant<T>(&self, result: SerdeResult<T>) -> SerdeResult<T> {
+        result.map_err(|e| e.at(PathSegment::Field(self.name.clone())))
+    }
+}
+
+impl<'de> VariantAccess<'de> for VariantDeserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> SerdeResult<()> {
+        match &self.value {
+            None | Some(SteelVal::Void) => Ok(()),
+            Some(other) => Err(<Error as de::Error>::custom(format!(
+                "expected no value for the unit variant {}, found: {other}",
+                self.name
+            ))),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> SerdeResult<T::Value> {
+        let result = seed.deserialize(Deserializer::new(self.value()?));
+        self.at_variant(result)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> SerdeResult<V::Value> {
+        let result = de::Deserializer::deserialize_seq(Deserializer::new(self.value()?), visitor);
+        self.at_variant(result)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> SerdeResult<V::Value> {
+        let result = de::Deserializer::deserialize_struct(
+            Deserializer::new(self.value()?),
+            "",
+            fields,
+            visitor,
+        );
+        self.at_variant(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::steel_vm::engine::Engine;
+    use ::serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Rectangle { width: f64, height: f64 },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        port: u16,
+        verbose: Option<bool>,
+        tags: Vec<String>,
+        shapes: Vec<Shape>,
+        limits: HashMap<String, i64>,
+        big: u64,
+    }
+
+    fn config() -> Config {
+        Config {
+            name: "server".to_string(),
+            port: 8080,
+            verbose: None,
+            tags: vec!["a".to_string(), "b".to_string()],
+            shapes: vec![
+                Shape::Point,
+                Shape::Circle(1.5),
+                Shape::Rectangle {
+                    width: 2.0,
+                    height: 3.0,
+                },
+            ],
+            limits: HashMap::from([("connections".to_string(), 100)]),
+            big: u64::MAX,
+        }
+    }
+
+    #[test]
+    fn round_trip_through_hashmaps() {
+        let value = to_steelval(&config()).unwrap();
+        assert_eq!(from_steelval::<Config>(&value).unwrap(), config());
+    }
+
+    #[test]
+    fn round_trip_through_structs() {
+        let value = to_steelval_with_structs(&config()).unwrap();
+        assert!(matches!(value, SteelVal::CustomStruct(_)));
+        assert_eq!(from_steelval::<Config>(&value).unwrap(), config());
+    }
+
+    #[test]
+    fn values_from_steel() {
+        let mut engine = Engine::new();
+        let value = engine
+            .compile_and_run_raw_program(
+                r#"
+                (struct Rectangle (width height))
+                (hash 'name "server"
+                      'port 8080
+                      'verbose #t
+                      'tags (vector "a")
+                      'shapes (list 'Point (hash 'Circle 1) (Rectangle 2 3))
+                      'limits (hash "connections" 100)
+                      'big 10)
+                "#,
+            )
+            .unwrap()
+            .pop()
+            .unwrap();
+
+        let config = from_steelval::<Config>(&value).unwrap();
+        assert_eq!(config.verbose, Some(true));
+        assert_eq!(config.tags, vec!["a".to_string()]);
+        assert_eq!(
+            config.shapes,
+            vec![
+                Shape::Point,
+                Shape::Circle(1.0),
+                Shape::Rectangle {
+                    width: 2.0,
+                    height: 3.0
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_include_the_path() {
+        let mut engine = Engine::new();
+        let value = engine
+            .compile_and_run_raw_program(
+                r#"(hash 'name "server" 'port 8080 'verbose #f 'tags (list "a" 10) 'shapes '() 'limits (hash) 'big 1)"#,
+            )
+            .unwrap()
+            .pop()
+            .unwrap();
+
+        let error = from_steelval::<Config>(&value).unwrap_err().to_string();
+        assert!(error.contains("tags[1]: "), "{error}");
+
+        let value = to_steelval(&HashMap::from([("port", 100000)])).unwrap();
+        let error = from_steelval::<HashMap<String, u16>>(&value)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("port: invalid value"), "{error}");
+    }
+}
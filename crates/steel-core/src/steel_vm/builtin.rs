@@ -575,6 +575,16 @@ impl BuiltInModule {
         "%-builtin-module-".to_string() + &self.module.borrow().name
     }
 
+    /// The values registered in the module, along with their names
+    pub(crate) fn values(&self) -> Vec<(Arc<str>, SteelVal)> {
+        self.module
+            .borrow()
+            .values
+            .iter()
+            .map(|(name, value)| (Arc::clone(name), value.clone()))
+            .collect()
+    }
+
     /// Add a value to the module namespace. This value can be any legal SteelVal, or if you're explicitly attempting
     /// to compile an program for later use and don't currently have access to the functions in memory, use `SteelVal::Void`
     pub fn register_value(&mut self, name: &str, value: SteelVal) -> &mut Self {
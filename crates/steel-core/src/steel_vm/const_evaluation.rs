@@ -608,7 +608,12 @@ impl<'a> ConsumingVisitor for ConstantEvaluator<'a> {
 
                 return self.eval_kernel_function(ident.clone(), func, Vec::new(), &[]);
             } else {
+                // Functions with optional or keyword arguments are bound by the vm, so they're left alone
                 if let ExprKind::LambdaFunction(f) = &func {
+                    if f.signature.is_some() {
+                        return Ok(ExprKind::List(List::new(vec![func])));
+                    }
+
                     if !f.args.is_empty() {
                         stop!(ArityMismatch => format!("function expected {} arguments, found 0", f.args.len()))
                     }
@@ -663,7 +668,7 @@ impl<'a> ConsumingVisitor for ConstantEvaluator<'a> {
         }
 
         match &func_expr {
-            ExprKind::LambdaFunction(_) => {}
+            ExprKind::LambdaFunction(l) if l.signature.is_none() => {}
             _ => {
                 let visited_func_expr = self.visit(func_expr)?;
                 args.insert(0, visited_func_expr);
@@ -0,0 +1,193 @@
+//! Line coverage for Steel programs.
+//!
+//! When coverage is enabled on an engine, the VM records the span of every instruction it executes,
+//! along with the spans of every instruction in each program it is handed. The latter tells us which
+//! lines contain executable code at all, so that code which never ran shows up as a miss rather than
+//! simply being absent from the report.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use fxhash::{FxHashMap, FxHashSet};
+
+use crate::parser::{parser::Sources, span::Span};
+use steel_parser::parser::SourceId;
+
+#[derive(Default, Clone, Debug)]
+pub(crate) struct CoverageRecorder {
+    // Spans that correspond to executable instructions, keyed by where they point to
+    known: FxHashSet<(SourceId, usize)>,
+    // Execution counts for the spans that have been hit
+    hits: FxHashMap<(SourceId, usize), usize>,
+}
+
+impl CoverageRecorder {
+    pub(crate) fn register_spans(&mut self, spans: &[Span]) {
+        self.known.extend(
+            spans
+                .iter()
+                .filter_map(|span| span.source_id.map(|id| (id, span.start))),
+        );
+    }
+
+    #[inline(always)]
+    pub(crate) fn hit(&mut self, span: Span) {
+        if let Some(id) = span.source_id {
+            *self.hits.entry((id, span.start)).or_default() += 1;
+        }
+    }
+
+    pub(crate) fn report(&self, sources: &Sources) -> CoverageReport {
+        let sources = sources.sources.lock().unwrap();
+
+        let mut files: BTreeMap<PathBuf, BTreeMap<usize, usize>> = BTreeMap::new();
+
+        // Byte offset of the start of each line, per source
+        let mut line_starts: FxHashMap<SourceId, Vec<usize>> = FxHashMap::default();
+
+        for (id, offset) in &self.known {
+            // Code that didn't come from a file (the repl, `eval`, etc) isn't reported on
+            let (Some(path), Some(text)) = (sources.get_path(id), sources.get(*id)) else {
+                continue;
+            };
+
+            let starts = line_starts.entry(*id).or_insert_with(|| {
+                std::iter::once(0)
+                    .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+                    .collect()
+            });
+
+            // Lines are 1 indexed
+            let line = starts.partition_point(|start| *start <= *offset);
+            let count = self.hits.get(&(*id, *offset)).copied().unwrap_or_default();
+
+            *files.entry(path).or_default().entry(line).or_default() += count;
+        }
+
+        CoverageReport {
+            files: files
+                .into_iter()
+                .map(|(path, lines)| FileCoverage { path, lines })
+                .collect(),
+        }
+    }
+}
+
+/// Line coverage for a single source file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileCoverage {
+    pub path: PathBuf,
+    /// Maps each line containing executable code to the number of times an instruction on that line was executed
+    pub lines: BTreeMap<usize, usize>,
+}
+
+impl FileCoverage {
+    pub fn lines_found(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|x| **x > 0).count()
+    }
+
+    pub fn percentage(&self) -> f64 {
+        if self.lines.is_empty() {
+            100.0
+        } else {
+            100.0 * self.lines_hit() as f64 / self.lines_found() as f64
+        }
+    }
+}
+
+/// Line coverage collected by an engine, see [`Engine::with_coverage`](crate::steel_vm::engine::Engine::with_coverage)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CoverageReport {
+    pub files: Vec<FileCoverage>,
+}
+
+impl CoverageReport {
+    /// Combine the coverage from another report into this one, i.e. from another engine that ran some of the same files
+    pub fn merge(&mut self, other: CoverageReport) {
+        for file in other.files {
+            if let Some(existing) = self.files.iter_mut().find(|x| x.path == file.path) {
+                for (line, count) in file.lines {
+                    *existing.lines.entry(line).or_default() += count;
+                }
+            } else {
+                self.files.push(file);
+            }
+        }
+
+        self.files.sort_by(|l, r| l.path.cmp(&r.path));
+    }
+
+    /// Render the report in the lcov tracefile format
+    pub fn to_lcov(&self) -> String {
+        let mut output = String::new();
+
+        for file in &self.files {
+            writeln!(output, "TN:").unwrap();
+            writeln!(output, "SF:{}", file.path.display()).unwrap();
+
+            for (line, count) in &file.lines {
+                writeln!(output, "DA:{line},{count}").unwrap();
+            }
+
+            writeln!(output, "LF:{}", file.lines_found()).unwrap();
+            writeln!(output, "LH:{}", file.lines_hit()).unwrap();
+            writeln!(output, "end_of_record").unwrap();
+        }
+
+        output
+    }
+
+    /// Render a table of the line coverage per file, for printing to the terminal
+    pub fn summary(&self) -> String {
+        let mut output = String::new();
+
+        let width = self
+            .files
+            .iter()
+            .map(|x| x.path.display().to_string().len())
+            .max()
+            .unwrap_or_default()
+            .max("Total".len());
+
+        writeln!(
+            output,
+            "{:<width$}  {:>7}  {:>7}  {:>7}",
+            "File", "Lines", "Hit", "Cover"
+        )
+        .unwrap();
+
+        for file in &self.files {
+            writeln!(
+                output,
+                "{:<width$}  {:>7}  {:>7}  {:>6.1}%",
+                file.path.display(),
+                file.lines_found(),
+                file.lines_hit(),
+                file.percentage()
+            )
+            .unwrap();
+        }
+
+        let found = self.files.iter().map(|x| x.lines_found()).sum::<usize>();
+        let hit = self.files.iter().map(|x| x.lines_hit()).sum::<usize>();
+        let percentage = if found == 0 {
+            100.0
+        } else {
+            100.0 * hit as f64 / found as f64
+        };
+
+        writeln!(
+            output,
+            "{:<width$}  {:>7}  {:>7}  {:>6.1}%",
+            "Total", found, hit, percentage
+        )
+        .unwrap();
+
+        output
+    }
+}
@@ -2,7 +2,9 @@
 
 use super::{
     builtin::{BuiltInModule, FunctionSignatureMetadata},
+    coverage::CoverageReport,
     primitives::{register_builtin_modules, register_builtin_modules_without_io, CONSTANTS},
+    snapshot::{EngineSlot, HeapDecoder, HeapEncoder, HeapSnapshot, NativeRef, ThreadSnapshot},
     vm::SteelThread,
 };
 
@@ -14,9 +16,11 @@ use super::dylib::DylibContainers;
 
 use crate::{
     compiler::{
-        compiler::{Compiler, SerializableCompiler},
+        compiler::{Compiler, CompilerSnapshot, NamespaceState, SerializableCompiler},
+        image::{Image, ImageKind, SectionKind},
         map::SymbolMap,
-        modules::{CompiledModule, PRELUDE_WITHOUT_BASE},
+        modules::{CompiledModule, MANGLER_SEPARATOR, PRELUDE_WITHOUT_BASE},
+        passes::mangle::{collect_globals, NameMangler},
         program::{Executable, RawProgramWithSymbols, SerializableRawProgramWithSymbols},
     },
     containers::RegisterValue,
@@ -32,10 +36,10 @@ use crate::{
         parser::SYNTAX_OBJECT_ID,
     },
     parser::{
-        kernel::{fresh_kernel_image, Kernel},
+        kernel::{fresh_kernel_image, Kernel, TransformerMap},
         parser::{ParseError, Parser, Sources},
     },
-    rerrs::{back_trace, back_trace_to_string},
+    rerrs::{back_trace, back_trace_to_string, ErrorKind},
     rvals::{
         cycles::{install_printer, print_in_engine, PRINT_IN_ENGINE_DEFINITION},
         FromSteelVal, IntoSteelVal, Result, SteelVal,
@@ -49,9 +53,12 @@ use std::{
     borrow::Cow,
     cell::{Cell, RefCell},
     collections::{HashMap, HashSet},
-    path::PathBuf,
+    path::{Path, PathBuf},
     rc::Rc,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
 use fxhash::{FxBuildHasher, FxHashMap};
@@ -145,6 +152,121 @@ pub struct EngineStatistics {
     pub sources_size: usize,
 }
 
+/// The result of reloading a module with [`Engine::reload_module`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleReload {
+    /// The module that was reloaded
+    pub module: PathBuf,
+    /// Modules that depend on the reloaded module, directly or transitively. Their imports
+    /// of the reloaded module are rebound, but anything they computed from the old definitions
+    /// when they were loaded is left as is.
+    pub invalidated: Vec<PathBuf>,
+}
+
+/// The error returned by [`Engine::reload_modified_modules`] when one of the modules fails to
+/// reload
+#[derive(Debug)]
+pub struct ModuleReloadError {
+    /// The modules that were reloaded before the failure. These stay reloaded.
+    pub reloaded: Vec<ModuleReload>,
+    /// The module that failed to reload
+    pub module: PathBuf,
+    /// Why the module failed to reload
+    pub error: SteelErr,
+}
+
+impl std::fmt::Display for ModuleReloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "failed to reload {:?}: {}", self.module, self.error)
+    }
+}
+
+impl std::error::Error for ModuleReloadError {}
+
+/// A separate top level environment within an [`Engine`], see [`Engine::create_namespace`]
+#[derive(Debug)]
+pub struct Namespace {
+    // The engine that created the namespace, since ids are only unique within an engine
+    engine: usize,
+    id: usize,
+}
+
+static NEXT_ENGINE_ID: AtomicUsize = AtomicUsize::new(0);
+
+struct Namespaces {
+    engine: usize,
+    // The compiler state that new namespaces start out with: the prelude along with
+    // the builtin modules. The matching globals are kept in the global environment of the vm.
+    base: Option<Rc<NamespaceState>>,
+    // The compiler state of each namespace, while it isn't active. The globals
+    // are parked in the global environment of the vm. A new namespace shares the
+    // base state until it is activated for the first time.
+    states: FxHashMap<usize, Rc<NamespaceState>>,
+    active: Option<usize>,
+    next_id: usize,
+}
+
+impl Default for Namespaces {
+    fn default() -> Self {
+        Namespaces {
+            engine: NEXT_ENGINE_ID.fetch_add(1, Ordering::Relaxed),
+            base: None,
+            states: FxHashMap::default(),
+            active: None,
+            next_id: 0,
+        }
+    }
+}
+
+// A cloned engine is a different engine, so the handles of the original don't apply to it
+impl Clone for Namespaces {
+    fn clone(&self) -> Self {
+        Namespaces {
+            engine: NEXT_ENGINE_ID.fetch_add(1, Ordering::Relaxed),
+            base: self.base.clone(),
+            states: self.states.clone(),
+            active: self.active,
+            next_id: self.next_id,
+        }
+    }
+}
+
+// Keeps a namespace active while it is borrowed, and switches back to the previously
+// active one when dropped, even if the code running in the namespace panics
+struct ActiveNamespace<'a> {
+    engine: &'a mut Engine,
+    id: usize,
+    previous: Option<usize>,
+}
+
+impl Drop for ActiveNamespace<'_> {
+    fn drop(&mut self) {
+        self.engine.swap_namespace(self.id);
+
+        if let Some(previous) = self.previous {
+            self.engine.swap_namespace(previous);
+        }
+
+        self.engine.namespaces.active = self.previous;
+    }
+}
+
+// Whether `value` holds on to a closure. Closures look up globals by their index in whichever
+// namespace is active when they are called, so they can't leave the namespace they were made in.
+fn contains_closure(value: &SteelVal) -> bool {
+    match value {
+        SteelVal::Closure(_) => true,
+        SteelVal::ListV(l) => l.iter().any(contains_closure),
+        SteelVal::VectorV(v) => v.iter().any(contains_closure),
+        SteelVal::MutableVector(v) => v.get().iter().any(contains_closure),
+        SteelVal::HashMapV(h) => h
+            .iter()
+            .any(|(key, value)| contains_closure(key) || contains_closure(value)),
+        SteelVal::CustomStruct(s) => s.fields.iter().any(contains_closure),
+        _ => false,
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct GlobalCheckpoint {
     symbol_map_offset: usize,
@@ -158,6 +280,7 @@ pub struct Engine {
     constants: Option<ImmutableHashMap<InternedString, SteelVal, FxBuildHasher>>,
     modules: ModuleContainer,
     sources: Sources,
+    namespaces: Namespaces,
     #[cfg(feature = "dylibs")]
     dylibs: DylibContainers,
 }
@@ -197,20 +320,71 @@ struct KernelImage {
     kernel_source: SerializableRawProgramWithSymbols,
 }
 
+// The state of an initialized engine, see `Engine::snapshot`. The values themselves
+// live in the heap section of the snapshot.
 #[derive(Serialize, Deserialize)]
+struct EngineSnapshot {
+    syntax_object_id: usize,
+    function_id: usize,
+    sources: Sources,
+    prelude_macros: FxHashMap<InternedString, SteelMacro>,
+    compiler: CompilerSnapshot,
+    thread: ThreadSnapshot,
+    kernel: Option<KernelSnapshot>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KernelSnapshot {
+    transformers: TransformerMap,
+    constants: HashSet<InternedString>,
+    compiler: CompilerSnapshot,
+    thread: ThreadSnapshot,
+}
+
 pub struct NonInteractiveProgramImage {
     sources: Sources,
     program: SerializableRawProgramWithSymbols,
+    modules: Vec<PathBuf>,
 }
 
 impl NonInteractiveProgramImage {
-    pub fn write_bytes_to_file(&self, out: &PathBuf) {
-        let mut f = std::fs::File::create(out).unwrap();
-        bincode::serialize_into(&mut f, self).unwrap();
+    pub fn write_bytes_to_file(&self, out: &PathBuf) -> Result<()> {
+        std::fs::write(out, self.to_bytes()?)?;
+        Ok(())
     }
 
-    pub fn from_bytes(bytes: &[u8]) -> Self {
-        bincode::deserialize(&bytes).unwrap()
+    /// Serialize the program as an image, see [`crate::compiler::image`]
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut image = Image::new(ImageKind::NonInteractive);
+        self.program.add_sections(&mut image)?;
+        image.add_section(SectionKind::SourceMap, &self.sources)?;
+        image.add_section(SectionKind::Modules, &self.modules)?;
+        Ok(image.to_bytes())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let image = Image::from_bytes(bytes, ImageKind::NonInteractive)?;
+
+        Ok(NonInteractiveProgramImage {
+            sources: image.read_section(SectionKind::SourceMap)?,
+            program: SerializableRawProgramWithSymbols::from_image(&image)?,
+            modules: image
+                .read_optional_section(SectionKind::Modules)?
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn program(&self) -> &SerializableRawProgramWithSymbols {
+        &self.program
+    }
+
+    /// The paths of the modules that were compiled into the program
+    pub fn modules(&self) -> &[PathBuf] {
+        &self.modules
+    }
+
+    pub fn sources(&self) -> &Sources {
+        &self.sources
     }
 }
 
@@ -336,6 +510,7 @@ impl Engine {
             constants: None,
             modules: ModuleContainer::default(),
             sources: Sources::new(),
+            namespaces: Namespaces::default(),
             #[cfg(feature = "dylibs")]
             dylibs: DylibContainers::new(),
         };
@@ -491,6 +666,7 @@ impl Engine {
             constants: None,
             modules: ModuleContainer::default(),
             sources: Sources::new(),
+            namespaces: Namespaces::default(),
             #[cfg(feature = "dylibs")]
             dylibs: DylibContainers::new(),
         };
@@ -565,15 +741,29 @@ impl Engine {
         expr: E,
         path: PathBuf,
     ) -> Result<NonInteractiveProgramImage> {
-        let mut engine = Engine::new();
+        Engine::new().emit_non_interactive_program_image(expr, path)
+    }
 
-        engine
+    /// Compiles the program at `path`, along with every module it requires, into an image that can be
+    /// run later with [`Engine::run_non_interactive_program_image`]. Any values registered on this engine
+    /// that the program refers to need to be registered on the engine that runs the image as well.
+    pub fn emit_non_interactive_program_image<E: AsRef<str> + Into<Cow<'static, str>>>(
+        &mut self,
+        expr: E,
+        path: PathBuf,
+    ) -> Result<NonInteractiveProgramImage> {
+        let program = self
             .emit_raw_program(expr, path)?
-            .into_serializable_program()
-            .map(|program| NonInteractiveProgramImage {
-                sources: engine.sources.clone(),
-                program,
-            })
+            .into_serializable_program()?;
+
+        let mut modules = self.modules().keys().cloned().collect::<Vec<_>>();
+        modules.sort();
+
+        Ok(NonInteractiveProgramImage {
+            sources: self.sources.clone(),
+            program,
+            modules,
+        })
     }
 
     // Execute from a statically linked non interactive program
@@ -582,9 +772,7 @@ impl Engine {
     ) -> Result<()> {
         // This _has_ to match the as the creation of the program above
         let mut engine = Engine::new();
-        engine.sources = program.sources;
-        let raw_program = SerializableRawProgramWithSymbols::into_raw_program(program.program);
-        let results = engine.run_raw_program(raw_program);
+        let results = engine.run_non_interactive_program_image(program);
 
         if let Err(e) = results {
             raise_error(&engine.sources, e);
@@ -593,6 +781,17 @@ impl Engine {
         Ok(())
     }
 
+    /// Runs a program image on this engine, replacing its sources with the ones bundled in the image
+    /// so that errors are reported against the original files.
+    pub fn run_non_interactive_program_image(
+        &mut self,
+        program: NonInteractiveProgramImage,
+    ) -> Result<Vec<SteelVal>> {
+        self.sources = program.sources;
+        let raw_program = program.program.try_into_raw_program()?;
+        self.run_raw_program(raw_program)
+    }
+
     // Create kernel bootstrap
     pub fn create_kernel_bootstrap_from_programs(output_path: PathBuf) {
         let mut vm = Engine {
@@ -601,6 +800,7 @@ impl Engine {
             constants: None,
             modules: ModuleContainer::default(),
             sources: Sources::new(),
+            namespaces: Namespaces::default(),
             #[cfg(feature = "dylibs")]
             dylibs: DylibContainers::new(),
         };
@@ -652,6 +852,7 @@ impl Engine {
             constants: None,
             modules: ModuleContainer::default(),
             sources: Sources::new(),
+            namespaces: Namespaces::default(),
             #[cfg(feature = "dylibs")]
             dylibs: DylibContainers::new(),
         };
@@ -759,6 +960,7 @@ impl Engine {
             constants: None,
             modules: ModuleContainer::default(),
             sources: Sources::new(),
+            namespaces: Namespaces::default(),
             #[cfg(feature = "dylibs")]
             dylibs: DylibContainers::new(),
         };
@@ -833,6 +1035,7 @@ impl Engine {
             constants: None,
             modules: ModuleContainer::default(),
             sources: Sources::new(),
+            namespaces: Namespaces::default(),
             #[cfg(feature = "dylibs")]
             dylibs: DylibContainers::new(),
         };
@@ -891,6 +1094,7 @@ impl Engine {
             constants: None,
             modules: ModuleContainer::default(),
             sources: Sources::new(),
+            namespaces: Namespaces::default(),
             #[cfg(feature = "dylibs")]
             dylibs: DylibContainers::new(),
         }
@@ -942,6 +1146,39 @@ impl Engine {
         self
     }
 
+    /// Turn line coverage collection on or off. Only programs run after coverage has been turned on
+    /// are counted - turning coverage off discards anything that has been collected so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate steel;
+    /// # use steel::steel_vm::engine::Engine;
+    /// let mut vm = Engine::new();
+    /// vm.with_coverage(true);
+    /// vm.run("(define (foo x) (+ x 1)) (foo 10)").unwrap();
+    ///
+    /// // Code that wasn't loaded from a file is not included in the report
+    /// assert!(vm.coverage_report().unwrap().files.is_empty());
+    /// ```
+    pub fn with_coverage(&mut self, coverage: bool) -> &mut Self {
+        self.virtual_machine.coverage = if coverage {
+            Some(self.virtual_machine.coverage.take().unwrap_or_default())
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Returns the line coverage collected so far for every file that has been run, or `None` if coverage
+    /// has not been turned on with [`Engine::with_coverage`].
+    pub fn coverage_report(&self) -> Option<CoverageReport> {
+        self.virtual_machine
+            .coverage
+            .as_ref()
+            .map(|coverage| coverage.report(&self.sources))
+    }
+
     #[inline]
     pub fn new_sandboxed() -> Self {
         let mut vm = Engine::new_raw();
@@ -957,6 +1194,8 @@ impl Engine {
             vm.compile_and_run_raw_program(core).unwrap();
         }
 
+        vm.save_namespace_base();
+
         vm
     }
 
@@ -1185,12 +1424,223 @@ impl Engine {
             panic!("This shouldn't happen!");
         }
 
+        engine.save_namespace_base();
+
         #[cfg(feature = "profiling")]
         log::info!(target: "engine-creation", "Engine Creation: {:?}", now.elapsed());
 
         engine
     }
 
+    /// Writes the state of this engine - its globals, constants, struct types and
+    /// compiled modules - into a snapshot, which [`Engine::from_snapshot`] can restore
+    /// without running the prelude or any module again.
+    ///
+    /// Native functions are not written out. They're found again in the restored engine
+    /// by the builtin module or global they came from, so any modules or functions registered
+    /// from Rust need to be registered again using [`Engine::from_snapshot_with`]. Values that
+    /// can't be written out, like open ports or native values, result in an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate steel;
+    /// # use steel::steel_vm::engine::Engine;
+    /// # use steel::SteelVal;
+    /// let mut vm = Engine::new();
+    /// vm.run("(define (square x) (* x x))").unwrap();
+    ///
+    /// let snapshot = vm.snapshot().unwrap();
+    ///
+    /// let mut restored = Engine::from_snapshot(&snapshot).unwrap();
+    /// assert_eq!(restored.run("(square 4)").unwrap(), vec![SteelVal::IntV(16)]);
+    /// ```
+    pub fn snapshot(&self) -> Result<Vec<u8>> {
+        if !self.namespaces.states.is_empty() || self.namespaces.active.is_some() {
+            stop!(Generic => "unable to snapshot the engine: an engine with namespaces can't be written into a snapshot");
+        }
+
+        let kernel = self.compiler.kernel.as_ref();
+
+        let mut encoder = HeapEncoder::new();
+
+        encoder.index_natives(
+            &self.modules,
+            &self.compiler.symbol_map,
+            &self.virtual_machine.global_env.bindings_vec,
+            EngineSlot::Main,
+        );
+
+        if let Some(kernel) = kernel {
+            encoder.index_natives(
+                &kernel.engine.modules,
+                &kernel.engine.compiler.symbol_map,
+                &kernel.engine.virtual_machine.global_env.bindings_vec,
+                EngineSlot::Kernel,
+            );
+        }
+
+        let thread = encoder.encode_thread(&self.virtual_machine, &self.compiler)?;
+
+        let kernel = kernel
+            .map(|kernel| -> Result<KernelSnapshot> {
+                let (transformers, constants) = kernel.snapshot_state();
+
+                Ok(KernelSnapshot {
+                    transformers,
+                    constants,
+                    compiler: kernel.engine.compiler.snapshot(),
+                    thread: encoder
+                        .encode_thread(&kernel.engine.virtual_machine, &kernel.engine.compiler)?,
+                })
+            })
+            .transpose()?;
+
+        let snapshot = EngineSnapshot {
+            syntax_object_id: SYNTAX_OBJECT_ID.load(std::sync::atomic::Ordering::Relaxed),
+            function_id: crate::compiler::code_gen::FUNCTION_ID
+                .load(std::sync::atomic::Ordering::Relaxed),
+            sources: self.sources.clone(),
+            prelude_macros: self.compiler.prelude_macros(),
+            compiler: self.compiler.snapshot(),
+            thread,
+            kernel,
+        };
+
+        let mut image = Image::new(ImageKind::Snapshot);
+        image.add_section(SectionKind::Heap, &encoder.finish())?;
+        image.add_section(SectionKind::Engine, &snapshot)?;
+
+        Ok(image.to_bytes())
+    }
+
+    /// Writes a snapshot of this engine to a file, see [`Engine::snapshot`]
+    pub fn write_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.snapshot()?)?;
+        Ok(())
+    }
+
+    /// Restores an engine from a snapshot created by [`Engine::snapshot`]
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self> {
+        Self::from_snapshot_with(bytes, |_| {})
+    }
+
+    /// Restores an engine from a snapshot created by [`Engine::snapshot`]. `setup` is
+    /// called on the engine before the snapshot is restored, and should register the
+    /// same modules and functions from Rust that the snapshotted engine had registered.
+    pub fn from_snapshot_with(bytes: &[u8], setup: impl FnOnce(&mut Engine)) -> Result<Self> {
+        let image = Image::from_bytes(bytes, ImageKind::Snapshot)?;
+        let heap: HeapSnapshot = image.read_section(SectionKind::Heap)?;
+        let snapshot: EngineSnapshot = image.read_section(SectionKind::Engine)?;
+
+        let base = |sources: &Sources| {
+            let mut engine = Engine {
+                virtual_machine: SteelThread::new(),
+                compiler: Compiler::default(),
+                constants: None,
+                modules: ModuleContainer::default(),
+                sources: sources.clone(),
+                namespaces: Namespaces::default(),
+                #[cfg(feature = "dylibs")]
+                dylibs: DylibContainers::new(),
+            };
+
+            register_builtin_modules(&mut engine);
+
+            let sources = sources.clone();
+
+            engine.register_fn("report-error!", move |error: SteelErr| {
+                raise_error(&sources, error);
+            });
+
+            engine
+        };
+
+        let mut engine = base(&snapshot.sources);
+        setup(&mut engine);
+
+        let kernel = snapshot.kernel.map(|kernel| {
+            (
+                Kernel::from_snapshot(
+                    kernel.transformers,
+                    kernel.constants,
+                    base(&snapshot.sources),
+                ),
+                kernel.compiler,
+                kernel.thread,
+            )
+        });
+
+        let mut decoder = HeapDecoder::new(&heap, |native| match native {
+            NativeRef::Module { module, name } => match engine.modules.inner().get(module.as_str())
+            {
+                Some(module) if module.contains(name) => Ok(module.get(name.to_string())),
+                _ => {
+                    stop!(Generic => "unable to restore the snapshot: {} isn't provided by the module {}", name, module)
+                }
+            },
+            NativeRef::Global {
+                engine: EngineSlot::Main,
+                name,
+            } => engine.extract_value(name),
+            NativeRef::Global {
+                engine: EngineSlot::Kernel,
+                name,
+            } => match &kernel {
+                Some((kernel, _, _)) => kernel.engine.extract_value(name),
+                None => stop!(Generic => "malformed snapshot: missing the kernel"),
+            },
+            NativeRef::BuiltInModule(name) => match engine.modules.inner().get(name.as_str()) {
+                Some(module) => module.clone().into_steelval(),
+                None => {
+                    stop!(Generic => "unable to restore the snapshot: the module {} isn't registered with the engine", name)
+                }
+            },
+        })?;
+
+        let constant_map = decoder.decode_thread(&snapshot.thread, &mut engine.virtual_machine)?;
+
+        let mut kernel = match kernel {
+            Some((mut kernel, compiler, thread)) => {
+                let constant_map =
+                    decoder.decode_thread(&thread, &mut kernel.engine.virtual_machine)?;
+                kernel.engine.compiler = Compiler::from_snapshot(compiler, constant_map, None);
+                kernel
+                    .engine
+                    .compiler
+                    .set_prelude_macros(snapshot.prelude_macros.clone());
+                Some(kernel)
+            }
+            None => None,
+        };
+
+        let heap = decoder.finish()?;
+
+        engine.virtual_machine.heap.share_allocations(&heap);
+
+        if let Some(kernel) = kernel.as_mut() {
+            kernel.engine.virtual_machine.heap.share_allocations(&heap);
+        }
+
+        engine.compiler = Compiler::from_snapshot(snapshot.compiler, constant_map, kernel);
+
+        // Syntax object and function ids are handed out process wide, and the restored
+        // code refers to them by value. The counters only ever move forward, so this
+        // keeps new ids from colliding with the restored ones without touching the ids
+        // that any other engine is using.
+        SYNTAX_OBJECT_ID.fetch_max(
+            snapshot.syntax_object_id,
+            std::sync::atomic::Ordering::Relaxed,
+        );
+
+        crate::compiler::code_gen::FUNCTION_ID
+            .fetch_max(snapshot.function_id, std::sync::atomic::Ordering::Relaxed);
+
+        engine.compiler.set_prelude_macros(snapshot.prelude_macros);
+
+        Ok(engine)
+    }
+
     /// Adds a directory for the engine to resolve paths from.
     ///
     /// By default, the engine will search $STEEL_HOME/cogs for modules,
@@ -1220,11 +1670,7 @@ impl Engine {
     /// vm.run("(+ 1 2 3)").unwrap();
     /// ```
     pub fn with_prelude(mut self) -> Result<Self> {
-        let core_libraries = &[crate::stdlib::PRELUDE];
-
-        for core in core_libraries {
-            self.compile_and_run_raw_program(*core)?;
-        }
+        self.register_prelude()?;
 
         Ok(self)
     }
@@ -1248,6 +1694,8 @@ impl Engine {
             self.compile_and_run_raw_program(*core)?;
         }
 
+        self.save_namespace_base();
+
         Ok(self)
     }
 
@@ -1412,7 +1860,7 @@ impl Engine {
                 }
                 TokenType::IntegerLiteral(MaybeBigInt::Big(b)) => b.clone().into_steelval(),
                 // TODO: Keywords shouldn't be misused as an expression - only in function calls are keywords allowed
-                TokenType::Keyword(k) => Ok(SteelVal::SymbolV(k.clone().into())),
+                TokenType::Keyword(k) => Ok(SteelVal::Keyword(k.resolve().into())),
                 what => {
                     // println!("getting here in the eval_atom - code_gen");
                     stop!(UnexpectedToken => what; t.span)
@@ -1690,6 +2138,50 @@ impl Engine {
         self
     }
 
+    /// Registers a function under the name `name` which receives its arguments as they are, without
+    /// any conversions. This is useful when the function is only known at runtime, for example when
+    /// it is provided over a foreign function interface. If `arity` is given, calls with any other
+    /// number of arguments are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate steel;
+    /// # use steel::steel_vm::engine::Engine;
+    /// use steel::rvals::SteelVal;
+    ///
+    /// let mut vm = Engine::new();
+    /// vm.register_dyn_fn("count-args", None, |args| Ok(SteelVal::IntV(args.len() as isize)));
+    /// assert_eq!(vm.run("(count-args 1 2 3)").unwrap(), vec![SteelVal::IntV(3)]);
+    /// ```
+    pub fn register_dyn_fn(
+        &mut self,
+        name: &str,
+        arity: Option<usize>,
+        func: impl Fn(&[SteelVal]) -> Result<SteelVal> + Send + Sync + 'static,
+    ) -> &mut Self {
+        let function_name = name.to_string();
+
+        let f = move |args: &[SteelVal]| -> Result<SteelVal> {
+            if let Some(arity) = arity {
+                if args.len() != arity {
+                    stop!(ArityMismatch => format!("{} expected {} arguments, got {}", function_name, arity, args.len()));
+                }
+            }
+
+            func(args)
+        };
+
+        self.register_value(
+            name,
+            SteelVal::BoxedFunction(Rc::new(BoxedDynFunction::new(
+                Arc::new(f),
+                Some(name),
+                arity,
+            ))),
+        )
+    }
+
     /// Registers a predicate for a given type. When embedding external values, it is convenient
     /// to be able to have a predicate to test if the given value is the specified type.
     /// In order to be registered, a type must implement [`FromSteelVal`](crate::rvals::FromSteelVal)
@@ -1825,6 +2317,27 @@ impl Engine {
         raise_error_to_string(&self.sources, error)
     }
 
+    /// Returns a flag that can be used to interrupt the currently running program from another thread.
+    /// Storing `true` causes evaluation to stop with an error at the next function call or loop iteration,
+    /// bypassing any exception handlers installed by the program. The flag is reset once observed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate steel;
+    /// # use steel::steel_vm::engine::Engine;
+    /// use std::sync::atomic::Ordering;
+    /// let mut vm = Engine::new();
+    /// let handle = vm.interrupt_handle();
+    /// handle.store(true, Ordering::Relaxed);
+    /// assert!(vm.run("(define (loop) (loop)) (loop)").is_err());
+    /// // The interrupt has been consumed
+    /// assert!(vm.run("(+ 1 2)").is_ok());
+    /// ```
+    pub fn interrupt_handle(&self) -> Arc<AtomicBool> {
+        self.virtual_machine.interrupt_handle()
+    }
+
     /// Execute a program given as the `expr`, and computes a `Vec<SteelVal>` corresponding to the output of each expression given.
     /// This method contains no path information used for error reporting, and simply runs the expression as is. Modules will be
     /// imported with the root directory as wherever the executable was started.
@@ -2010,6 +2523,328 @@ impl Engine {
         self.extract_value(&module_path)
     }
 
+    /// Runs `expr` as if it were written at the end of the module at `path`, which must already
+    /// be loaded. The module's definitions, imports and macros are in scope, including the ones it
+    /// doesn't provide, and definitions made by `expr` are added to the module.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate steel;
+    /// # use steel::steel_vm::engine::Engine;
+    /// let mut vm = Engine::new();
+    /// vm.run(r#"(require "lib.scm")"#).unwrap();
+    /// vm.run_in_module("lib.scm", "(private-helper 10)").unwrap();
+    /// ```
+    pub fn run_in_module<E: AsRef<str> + Into<Cow<'static, str>>>(
+        &mut self,
+        path: impl AsRef<Path>,
+        expr: E,
+    ) -> Result<Vec<SteelVal>> {
+        let path = self.resolve_module_path(path.as_ref())?;
+
+        let Some(name) = path.to_str() else {
+            stop!(Generic => format!("module path is not valid unicode: {:?}", path));
+        };
+
+        let prefix = "mangler".to_string() + name + MANGLER_SEPARATOR;
+        let macros = self.modules()[&path].macro_map.clone();
+
+        let expr: Cow<'static, str> = expr.into();
+        let id = self.sources.add_source(expr.clone(), None);
+
+        let mut exprs = Parser::new(&expr, Some(id)).collect::<std::result::Result<Vec<_>, _>>()?;
+
+        for expr in exprs.iter_mut() {
+            crate::parser::expand_visitor::expand(expr, &macros)?;
+        }
+
+        // Everything bound at the top level of the module is mangled with its prefix
+        let mut globals = collect_globals(&exprs);
+        globals.extend(self.symbol_map().values().iter().filter_map(|x| {
+            x.resolve()
+                .strip_prefix(prefix.as_str())
+                .map(InternedString::from)
+        }));
+
+        NameMangler::new(globals, prefix).mangle_vars(&mut exprs);
+
+        self.run_raw_program_from_exprs(exprs)
+    }
+
+    /// Recompiles a module that has already been loaded and rebinds its definitions in place.
+    ///
+    /// The module's top level runs again, and everything that imported from it - other modules
+    /// as well as the top level - sees the new definitions. State held by other modules is left
+    /// alone. Functions that were removed from the module stay bound to the old definitions.
+    ///
+    /// If the module fails to compile or run, an error is returned and any definitions that
+    /// were not yet rebound keep their previous values.
+    pub fn reload_module(&mut self, path: impl AsRef<Path>) -> Result<ModuleReload> {
+        let path = self.resolve_module_path(path.as_ref())?;
+
+        let constants = self.constants();
+        let program = self.compiler.reload_module(
+            path.clone(),
+            constants,
+            self.modules.clone(),
+            &mut self.sources,
+        )?;
+
+        self.run_raw_program(program)?;
+
+        if let SteelVal::HashMapV(provides) = self.get_module(path.clone())? {
+            for (alias, name) in self.compiler.module_require_aliases(&path)? {
+                if let Some(value) = provides.get(&SteelVal::SymbolV(name.resolve().into())) {
+                    self.update_value(&alias, value.clone());
+                }
+            }
+        }
+
+        Ok(ModuleReload {
+            invalidated: self.compiler.module_dependents(&path),
+            module: path,
+        })
+    }
+
+    /// Reloads every loaded module whose file has changed since it was compiled. Calling this
+    /// periodically is enough to watch modules for changes.
+    ///
+    /// Modules are reloaded before the modules that depend on them. Reloading stops at the first
+    /// module that fails, and the error lists the modules that were reloaded before it.
+    pub fn reload_modified_modules(
+        &mut self,
+    ) -> std::result::Result<Vec<ModuleReload>, ModuleReloadError> {
+        let mut modified = self.compiler.modified_modules();
+        let mut reloaded = Vec::with_capacity(modified.len());
+
+        while !modified.is_empty() {
+            // Pick a module that doesn't depend on anything else waiting to be reloaded
+            let next = modified
+                .iter()
+                .position(|path| {
+                    !modified.iter().any(|other| {
+                        other != path && self.compiler.module_dependents(other).contains(path)
+                    })
+                })
+                .unwrap_or(0);
+
+            let path = modified.remove(next);
+
+            match self.reload_module(&path) {
+                Ok(reload) => reloaded.push(reload),
+                Err(error) => {
+                    return Err(ModuleReloadError {
+                        reloaded,
+                        module: path,
+                        error,
+                    })
+                }
+            }
+        }
+
+        Ok(reloaded)
+    }
+
+    fn resolve_module_path(&self, path: &Path) -> Result<PathBuf> {
+        if self.modules().contains_key(path) {
+            return Ok(path.to_path_buf());
+        }
+
+        let canonical = std::fs::canonicalize(path)?;
+
+        self.modules()
+            .keys()
+            .find(|x| std::fs::canonicalize(x).is_ok_and(|x| x == canonical))
+            .cloned()
+            .ok_or_else(throw!(Generic => format!("module has not been loaded: {:?}", path)))
+    }
+
+    /// Creates a namespace: a separate top level environment within this engine, which
+    /// requires each of `modules` - either the name of a builtin module, or a path to a file.
+    ///
+    /// Every namespace starts out with just the prelude, as it was loaded when the engine was
+    /// created, and can require any of the builtin modules registered on the engine. Definitions
+    /// made at the top level of the engine or in other namespaces are not visible inside of it,
+    /// and vice versa. The starting state is shared between namespaces until they are first
+    /// used, so creating a namespace is cheap.
+    ///
+    /// Closures look up globals in whichever namespace is active when they are called, so they
+    /// can't be returned from [`Engine::run_in_namespace`]. Closures that escape a namespace in
+    /// other ways, e.g. through [`Engine::with_namespace`] or a value shared between namespaces,
+    /// should only be called while the namespace they were created in is active.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate steel;
+    /// # use steel::steel_vm::engine::Engine;
+    /// use steel::rvals::SteelVal;
+    /// let mut vm = Engine::new();
+    /// let first = vm.create_namespace(&[]).unwrap();
+    /// let second = vm.create_namespace(&[]).unwrap();
+    ///
+    /// vm.run_in_namespace(&first, "(define x 10)").unwrap();
+    /// vm.run_in_namespace(&second, "(define x 20)").unwrap();
+    ///
+    /// let x = vm.run_in_namespace(&first, "x").unwrap();
+    /// assert_eq!(x, vec![SteelVal::IntV(10)]);
+    /// assert!(vm.run("x").is_err());
+    ///
+    /// vm.drop_namespace(first).unwrap();
+    /// ```
+    pub fn create_namespace(&mut self, modules: &[&str]) -> Result<Namespace> {
+        let base = match &self.namespaces.base {
+            Some(base) => Rc::clone(base),
+            None => self.save_namespace_base(),
+        };
+
+        let id = self.namespaces.next_id;
+        self.namespaces.next_id += 1;
+
+        self.namespaces.states.insert(id, base);
+
+        let globals = &mut self.virtual_machine.global_env;
+        let base_globals = globals.namespace_base.clone().unwrap_or_default();
+        globals.parked.insert(id, base_globals);
+
+        let namespace = Namespace {
+            engine: self.namespaces.engine,
+            id,
+        };
+
+        let requires = modules
+            .iter()
+            .map(|module| {
+                if self.modules.inner().contains_key(*module) {
+                    format!("(require-builtin {module})")
+                } else {
+                    format!("(require {module:?})")
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let result = self.with_namespace(&namespace, |engine| {
+            // Builtin modules registered after the prelude was loaded
+            let modules = engine
+                .modules
+                .inner()
+                .values()
+                .filter(|module| engine.compiler.get_idx(&module.unreadable_name()).is_none())
+                .cloned()
+                .collect::<Vec<_>>();
+
+            for module in modules {
+                engine.register_value(&module.unreadable_name(), module.into_steelval()?);
+            }
+
+            if !requires.is_empty() {
+                engine.compile_and_run_raw_program(requires.join(" "))?;
+            }
+
+            Ok(())
+        });
+
+        if let Err(e) = result.and_then(|x| x) {
+            self.drop_namespace(namespace)?;
+            return Err(e);
+        }
+
+        Ok(namespace)
+    }
+
+    /// Runs `thunk` with `namespace` as the active top level environment. The previously
+    /// active environment is restored afterwards, even if `thunk` panics.
+    ///
+    /// Returns an error if the namespace was created by a different engine.
+    pub fn with_namespace<T>(
+        &mut self,
+        namespace: &Namespace,
+        thunk: impl FnOnce(&mut Engine) -> T,
+    ) -> Result<T> {
+        self.check_namespace(namespace)?;
+
+        let previous = self.namespaces.active;
+
+        if previous == Some(namespace.id) {
+            return Ok(thunk(self));
+        }
+
+        // Only one namespace is swapped in at a time, so that the state parked
+        // for the active namespace is always the engine's own top level
+        if let Some(previous) = previous {
+            self.swap_namespace(previous);
+        }
+
+        self.swap_namespace(namespace.id);
+        self.namespaces.active = Some(namespace.id);
+
+        let mut active = ActiveNamespace {
+            engine: self,
+            id: namespace.id,
+            previous,
+        };
+
+        Ok(thunk(&mut *active.engine))
+    }
+
+    /// Compiles and runs `program` in `namespace`. Returns an error if any of the resulting
+    /// values holds on to a closure, see [`Engine::create_namespace`].
+    pub fn run_in_namespace<E: AsRef<str> + Into<Cow<'static, str>>>(
+        &mut self,
+        namespace: &Namespace,
+        program: E,
+    ) -> Result<Vec<SteelVal>> {
+        let values = self.with_namespace(namespace, |engine| {
+            engine.compile_and_run_raw_program(program)
+        })??;
+
+        if values.iter().any(contains_closure) {
+            stop!(Generic => "closures can't be returned from a namespace, since they refer to its globals");
+        }
+
+        Ok(values)
+    }
+
+    /// Drops a namespace along with all of its definitions. Returns an error if the namespace
+    /// was created by a different engine.
+    pub fn drop_namespace(&mut self, namespace: Namespace) -> Result<()> {
+        self.check_namespace(&namespace)?;
+
+        self.namespaces.states.remove(&namespace.id);
+        self.virtual_machine.global_env.parked.remove(&namespace.id);
+
+        Ok(())
+    }
+
+    fn check_namespace(&self, namespace: &Namespace) -> Result<()> {
+        if namespace.engine != self.namespaces.engine
+            || !self.namespaces.states.contains_key(&namespace.id)
+        {
+            stop!(Generic => "namespace was created by a different engine");
+        }
+
+        Ok(())
+    }
+
+    // Records the current state as the one new namespaces start out with
+    fn save_namespace_base(&mut self) -> Rc<NamespaceState> {
+        let base = Rc::new(self.compiler.namespace_state());
+        self.namespaces.base = Some(Rc::clone(&base));
+
+        let globals = &mut self.virtual_machine.global_env;
+        globals.namespace_base = Some(Rc::new(globals.bindings_vec.clone()));
+
+        base
+    }
+
+    fn swap_namespace(&mut self, id: usize) {
+        if let Some(state) = self.namespaces.states.get_mut(&id) {
+            self.compiler.swap_namespace_state(Rc::make_mut(state));
+            self.virtual_machine.global_env.swap_parked(id);
+        }
+    }
+
     pub fn get_source_id(&self, path: &PathBuf) -> Option<SourceId> {
         self.sources.get_source_id(path)
     }
@@ -2269,4 +3104,106 @@ mod engine_api_tests {
             .compile_and_run_raw_program("(external-get-value-imm *external*)")
             .is_err());
     }
+
+    #[cfg(feature = "dylibs")]
+    #[test]
+    fn ffi_functions_can_call_back_into_the_vm() {
+        use crate::steel_vm::ffi::{FFIBoxedDynFunction, FFIModule, FFIValue, RegisterFFIFn};
+        use abi_stable::std_types::{RBox, RResult};
+
+        let mut module = FFIModule::new("ffi-callbacks");
+        module.register_fn("call-twice", |f: FFIBoxedDynFunction, x: isize| {
+            let RResult::ROk(once) = f.call([FFIValue::IntV(x)]) else {
+                return Err("first call failed".to_string());
+            };
+
+            match f.call([once]) {
+                RResult::ROk(FFIValue::IntV(value)) => Ok(value),
+                other => Err(format!("{:?}", other)),
+            }
+        });
+        module.register_fn("fold-range", |f: FFIBoxedDynFunction, n: isize| {
+            let mut total = FFIValue::IntV(0);
+
+            for i in 0..n {
+                match f.call([total, FFIValue::IntV(i)]) {
+                    RResult::ROk(value) => total = value,
+                    RResult::RErr(e) => return Err(e.to_string()),
+                }
+            }
+
+            Ok(total)
+        });
+
+        let mut engine = Engine::new();
+        engine.register_external_module(RBox::new(module)).unwrap();
+
+        let res = engine
+            .compile_and_run_raw_program(
+                "(require-builtin ffi-callbacks) (call-twice (lambda (x) (* x 3)) 2)",
+            )
+            .unwrap();
+
+        assert_eq!(res.last(), Some(&SteelVal::IntV(18)));
+
+        // Callbacks that grow the stack, made from deep within the program, while other native
+        // calls are still waiting on their arguments
+        let res = engine
+            .compile_and_run_raw_program(
+                r#"
+                (define (count-up n) (if (= n 0) 0 (+ 1 (count-up (- n 1)))))
+                (define (deep n)
+                  (if (= n 0)
+                      (fold-range (lambda (total x) (+ total (count-up x))) 1000)
+                      (+ 0 (deep (- n 1)))))
+                (call-twice (lambda (x) (+ x (deep 100))) 0)
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(res.last(), Some(&SteelVal::IntV(999000)));
+    }
+
+    #[cfg(feature = "dylibs")]
+    #[test]
+    fn procedures_keep_their_name_and_arity_across_ffi() {
+        use crate::steel_vm::ffi::{FFIBoxedDynFunction, FFIModule, RegisterFFIFn};
+        use abi_stable::std_types::RBox;
+
+        let mut module = FFIModule::new("ffi-procedure-info");
+        module.register_fn("procedure-info", |f: FFIBoxedDynFunction| {
+            format!("{} {}", f.name, f.arity)
+        });
+
+        let mut engine = Engine::new();
+        engine.register_external_module(RBox::new(module)).unwrap();
+
+        let res = engine
+            .compile_and_run_raw_program(
+                r#"
+                (require-builtin ffi-procedure-info)
+                (list (procedure-info (lambda (x y) x))
+                      (procedure-info (lambda (x . rest) x))
+                      (procedure-info car))
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            res.last().unwrap().to_string(),
+            r##"'("#<bytecode-closure> 2" "#<bytecode-closure> 1" "car 1")"##
+        );
+    }
+
+    #[test]
+    fn builtin_errors_point_at_the_call() {
+        let mut engine = Engine::new();
+
+        let program = "(define x 1)\n(values 1 2)\n";
+
+        let err = engine.compile_and_run_raw_program(program).unwrap_err();
+
+        let span = err.span().unwrap();
+        assert_eq!(&program[span.start..span.end], "values");
+    }
 }
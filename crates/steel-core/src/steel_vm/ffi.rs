@@ -8,15 +8,24 @@ use std::{
 
 use crate::{
     gc::{unsafe_erased_pointers::OpaqueReference, Gc},
+    parser::ast::LambdaSignature,
     rerrs::ErrorKind,
     rvals::{
-        as_underlying_type, Custom, CustomType, FutureResult, IntoSteelVal, Result, SRef,
-        SteelHashMap, SteelVal,
+        as_underlying_type, Custom, CustomType, FutureResult, IntoSteelVal, MutFunctionSignature,
+        Result, SRef, SteelHashMap, SteelVal,
     },
     values::functions::{BoxedDynFunction, StaticOrRcStr},
     SteelErr,
 };
 
+use super::{
+    builtin::{
+        get_function_metadata, get_function_name, Arity, BuiltInFunctionTypePointer,
+        FunctionSignatureMetadata,
+    },
+    vm::{with_current_context, VmContext},
+};
+
 use abi_stable::{
     std_types::{RBoxError, RCowStr, RHashMap, RResult, RSlice, RStr, RString, RVec, Tuple2},
     StableAbi,
@@ -318,6 +327,16 @@ impl<T: FromFFIVal> FromFFIVal for Vec<T> {
     }
 }
 
+impl FromFFIVal for FFIBoxedDynFunction {
+    fn from_ffi_val(val: FFIValue) -> RResult<Self, RBoxError> {
+        if let FFIValue::BoxedFunction(f) = val {
+            RResult::ROk(f)
+        } else {
+            conversion_error!(function, val)
+        }
+    }
+}
+
 impl FromFFIVal for FFIValue {
     fn from_ffi_val(val: FFIValue) -> RResult<Self, RBoxError> {
         RResult::ROk(val)
@@ -716,8 +735,24 @@ pub enum FFIArg<'a> {
 }
 
 impl<'a> std::hash::Hash for FFIArg<'a> {
-    fn hash<H: std::hash::Hasher>(&self, _state: &mut H) {
-        todo!()
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        // Only the variants compared in `PartialEq` contribute anything beyond
+        // their discriminant.
+        match self {
+            // `0.0 == -0.0`, so both have to land in the same bucket
+            FFIArg::NumV(n) if *n == 0.0 => 0u64.hash(state),
+            FFIArg::NumV(n) => n.to_bits().hash(state),
+            FFIArg::BoolV(b) => b.hash(state),
+            FFIArg::IntV(i) => i.hash(state),
+            FFIArg::StringV(s) => s.hash(state),
+            FFIArg::CharV { c } => c.hash(state),
+            FFIArg::Vector(v) => v.hash(state),
+            // Iteration order of a map is unspecified, so only hash the length
+            FFIArg::HashMap(h) => h.len().hash(state),
+            _ => {}
+        }
     }
 }
 
@@ -780,8 +815,24 @@ pub enum FFIValue {
 }
 
 impl std::hash::Hash for FFIValue {
-    fn hash<H: std::hash::Hasher>(&self, _state: &mut H) {
-        todo!()
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        // Only the variants compared in `PartialEq` contribute anything beyond
+        // their discriminant.
+        match self {
+            // `0.0 == -0.0`, so both have to land in the same bucket
+            FFIValue::NumV(n) if *n == 0.0 => 0u64.hash(state),
+            FFIValue::NumV(n) => n.to_bits().hash(state),
+            FFIValue::BoolV(b) => b.hash(state),
+            FFIValue::IntV(i) => i.hash(state),
+            FFIValue::StringV(s) => s.hash(state),
+            FFIValue::CharV { c } => c.hash(state),
+            FFIValue::Vector(v) => v.hash(state),
+            // Iteration order of a map is unspecified, so only hash the length
+            FFIValue::HashMap(h) => h.len().hash(state),
+            _ => {}
+        }
     }
 }
 
@@ -1006,6 +1057,15 @@ pub fn as_ffi_argument(value: &SteelVal) -> Result<FFIArg<'_>> {
         // SteelVal::StringV(s) => Ok(FFIValue::StringV(s.as_str().into())),
         SteelVal::StringV(s) => Ok(FFIArg::StringRef(RStr::from_str(s.as_str()))),
 
+        SteelVal::Closure(_)
+        | SteelVal::FuncV(_)
+        | SteelVal::BoxedFunction(_)
+        | SteelVal::MutFunc(_)
+        | SteelVal::BuiltIn(_)
+        | SteelVal::ContinuationFunction(_) => {
+            Ok(FFIArg::BoxedFunction(procedure_as_ffi_function(value)))
+        }
+
         _ => {
             stop!(TypeMismatch => "Cannot proceed with the conversion from steelval to FFI Value. This will only succeed for a subset of values deemed as FFI-safe-enough: {:?}", value)
         }
@@ -1057,13 +1117,152 @@ pub fn as_ffi_value(value: &SteelVal) -> Result<FFIValue> {
         // Don't copy the string unless we have to!
         SteelVal::StringV(s) => Ok(FFIValue::StringV(s.as_str().into())),
 
+        SteelVal::Closure(_)
+        | SteelVal::FuncV(_)
+        | SteelVal::BoxedFunction(_)
+        | SteelVal::MutFunc(_)
+        | SteelVal::BuiltIn(_)
+        | SteelVal::ContinuationFunction(_) => {
+            Ok(FFIValue::BoxedFunction(procedure_as_ffi_function(value)))
+        }
+
         _ => {
             stop!(TypeMismatch => "Cannot proceed with the conversion from steelval to FFI Value. This will only succeed for a subset of values deemed as FFI-safe-enough: {:?}", value)
         }
     }
 }
 
+/// A Steel value that is only ever touched on the thread that created it. The FFI function
+/// type has to be `Send + Sync`, so procedures handed to a dylib are wrapped in this, and
+/// accessing them from any other thread results in an error instead.
+struct ThreadBound {
+    value: std::mem::ManuallyDrop<SteelVal>,
+    thread: std::thread::ThreadId,
+}
+
+// SAFETY: The inner value is only accessed (and dropped) on the thread it was created on
+unsafe impl Send for ThreadBound {}
+unsafe impl Sync for ThreadBound {}
+
+impl ThreadBound {
+    fn new(value: SteelVal) -> Self {
+        Self {
+            value: std::mem::ManuallyDrop::new(value),
+            thread: std::thread::current().id(),
+        }
+    }
+
+    fn get(&self) -> Option<&SteelVal> {
+        (std::thread::current().id() == self.thread).then_some(&*self.value)
+    }
+}
+
+impl Drop for ThreadBound {
+    fn drop(&mut self) {
+        // If this ends up dropped on another thread, the value is leaked, since the reference
+        // counts can't be touched from here
+        if std::thread::current().id() == self.thread {
+            unsafe { std::mem::ManuallyDrop::drop(&mut self.value) }
+        }
+    }
+}
+
+// The name of a procedure and the number of arguments it requires, as far as they are known.
+// Closures don't carry their name at runtime, so they are named the way they are printed.
+fn procedure_name_and_arity(value: &SteelVal) -> (String, usize) {
+    let from_metadata = |metadata: Option<FunctionSignatureMetadata>| match metadata {
+        Some(FunctionSignatureMetadata {
+            name,
+            arity: Arity::Exact(arity) | Arity::AtLeast(arity),
+            ..
+        }) => Some((name.to_string(), arity)),
+        Some(FunctionSignatureMetadata { name, .. }) => Some((name.to_string(), 0)),
+        None => None,
+    };
+
+    let known = match value {
+        SteelVal::Closure(c) => {
+            let arity = match c.signature() {
+                Some(LambdaSignature::Parameters { required, .. }) => *required,
+                Some(LambdaSignature::Cases(clauses)) => {
+                    clauses.iter().map(|(arity, _)| *arity).min().unwrap_or(0)
+                }
+                None if c.is_multi_arity => c.arity().saturating_sub(1),
+                None => c.arity(),
+            };
+
+            Some((value.to_string(), arity))
+        }
+        SteelVal::BoxedFunction(f) => Some((
+            match &f.name {
+                Some(StaticOrRcStr::Static(name)) => name.to_string(),
+                Some(StaticOrRcStr::Owned(name)) => name.to_string(),
+                None => value.to_string(),
+            },
+            f.get_arity().unwrap_or(0),
+        )),
+        SteelVal::FuncV(f) => from_metadata(get_function_name(*f)),
+        SteelVal::MutFunc(f) => from_metadata(get_function_metadata(
+            BuiltInFunctionTypePointer::Mutable(*f as *const MutFunctionSignature),
+        )),
+        _ => None,
+    };
+
+    known.unwrap_or_else(|| (value.to_string(), 0))
+}
+
+// Wraps a Steel procedure so that it can be passed across the FFI boundary. Calling it re-enters
+// the VM that is currently running native code on this thread.
+fn procedure_as_ffi_function(value: &SteelVal) -> FFIBoxedDynFunction {
+    let (name, arity) = procedure_name_and_arity(value);
+    let procedure = ThreadBound::new(value.clone());
+
+    let function = move |args: RVec<FFIValue>| -> RResult<FFIValue, RBoxError> {
+        let Some(procedure) = procedure.get() else {
+            return RResult::RErr(ffi_error(
+                "Steel procedures can only be called from the thread that created them".into(),
+            ));
+        };
+
+        let args = match args
+            .into_iter()
+            .map(|x| x.into_steelval())
+            .collect::<Result<crate::values::lists::List<_>>>()
+        {
+            Ok(args) => args,
+            Err(e) => return RResult::RErr(ffi_error(e.to_string().into())),
+        };
+
+        let result = with_current_context(|ctx| ctx.call_function_many_args(procedure, args));
+
+        match result {
+            Some(Ok(value)) => match as_ffi_value(&value) {
+                Ok(value) => RResult::ROk(value),
+                Err(e) => RResult::RErr(ffi_error(e.to_string().into())),
+            },
+            Some(Err(e)) => RResult::RErr(ffi_error(e.to_string().into())),
+            None => RResult::RErr(ffi_error(
+                "Steel procedures can only be called while the VM is running native code on this thread"
+                    .into(),
+            )),
+        }
+    };
+
+    FFIBoxedDynFunction {
+        name: RString::from(name),
+        arity,
+        function: Arc::new(function),
+    }
+}
+
 impl FFIBoxedDynFunction {
+    /// Call this function with the given arguments. If the function was created from a Steel
+    /// procedure, this has to happen while the VM is running native code on the current thread,
+    /// i.e. from within a function registered by the module.
+    pub fn call(&self, args: impl IntoIterator<Item = FFIValue>) -> RResult<FFIValue, RBoxError> {
+        (self.function)(args.into_iter().collect())
+    }
+
     fn as_boxed_dyn_function(&self) -> BoxedDynFunction {
         let name = self.name.to_string();
 
@@ -1085,11 +1284,7 @@ impl FFIBoxedDynFunction {
             }
         };
 
-        BoxedDynFunction {
-            name: Some(StaticOrRcStr::Owned(Arc::new(name))),
-            arity: Some(self.arity),
-            function: Arc::new(function),
-        }
+        BoxedDynFunction::new_owned(Arc::new(function), Some(Arc::new(name)), Some(self.arity))
     }
 }
 
@@ -1113,11 +1308,7 @@ impl From<FFIBoxedDynFunction> for BoxedDynFunction {
             }
         };
 
-        BoxedDynFunction {
-            name: Some(StaticOrRcStr::Owned(Arc::new(name))),
-            arity: Some(value.arity),
-            function: Arc::new(function),
-        }
+        BoxedDynFunction::new_owned(Arc::new(function), Some(Arc::new(name)), Some(value.arity))
     }
 }
 
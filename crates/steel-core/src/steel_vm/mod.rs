@@ -2,6 +2,7 @@ pub mod builtin;
 pub mod cache;
 pub(crate) mod const_evaluation;
 pub mod contract_checker;
+pub mod coverage;
 #[cfg(feature = "dylibs")]
 pub mod dylib;
 pub mod engine;
@@ -11,6 +12,7 @@ mod lazy_stream;
 mod meta;
 pub mod primitives;
 pub mod register_fn;
+mod snapshot;
 #[cfg(test)]
 mod test_util;
 #[cfg(test)]
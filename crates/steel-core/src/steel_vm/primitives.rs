@@ -3,28 +3,40 @@ use super::{
     cache::WeakMemoizationTable,
     engine::Engine,
     register_fn::RegisterFn,
-    vm::{get_test_mode, list_modules, set_test_mode, VmCore},
+    vm::{get_test_mode, list_modules, parameters, set_test_mode, VmCore},
 };
 use crate::{
     gc::Gc,
     parser::{
-        ast::TryFromSteelValVisitorForExprKind, interner::InternedString, span::Span,
-        tryfrom_visitor::TryFromExprKindForSteelVal,
+        ast::{LambdaSignature, TryFromSteelValVisitorForExprKind},
+        interner::InternedString,
+        span::Span,
     },
     primitives::{
-        fs_module,
+        control, fs_module,
         hashmaps::hashmap_module,
         hashmaps::{HM_CONSTRUCT, HM_GET, HM_INSERT},
         hashsets::hashset_module,
         lists::{list_module, UnRecoverableResult},
         nums::{
-            quotient, ADD_PRIMITIVE_DEFINITION, DIVIDE_PRIMITIVE_DEFINITION, INEXACTP_DEFINITION,
-            MULTIPLY_PRIMITIVE_DEFINITION, SUBTRACT_PRIMITIVE_DEFINITION,
+            complex_expt, inexact, ACOS_DEFINITION, ADD_PRIMITIVE_DEFINITION, ANGLE_DEFINITION,
+            ASIN_DEFINITION, ATAN_DEFINITION, CEILING_DEFINITION, COMPLEXP_DEFINITION,
+            COS_DEFINITION, DENOMINATOR_DEFINITION, DIVIDE_PRIMITIVE_DEFINITION, EXACT_DEFINITION,
+            EXACT_INTEGERP_DEFINITION, EXP_DEFINITION, FINITEP_DEFINITION, FLOOR_DEFINITION,
+            FLOOR_QUOTIENT_DEFINITION, FLOOR_REMAINDER_DEFINITION, IMAG_PART_DEFINITION,
+            INEXACTP_DEFINITION, INEXACT_DEFINITION, INEXACT_TO_EXACT_DEFINITION,
+            INFINITEP_DEFINITION, LOG_DEFINITION, MAGNITUDE_DEFINITION, MAKE_POLAR_DEFINITION,
+            MAKE_RECTANGULAR_DEFINITION, MODULO_DEFINITION, MULTIPLY_PRIMITIVE_DEFINITION,
+            NANP_DEFINITION, NUMERATOR_DEFINITION, QUOTIENT_DEFINITION, RATIONALIZE_DEFINITION,
+            REAL_PART_DEFINITION, REMAINDER_DEFINITION, SIN_DEFINITION, SQRT_DEFINITION,
+            SUBTRACT_PRIMITIVE_DEFINITION, TAN_DEFINITION, TRUNCATE_DEFINITION,
+            TRUNCATE_QUOTIENT_DEFINITION, TRUNCATE_REMAINDER_DEFINITION,
         },
         port_module,
         process::process_module,
         random::random_module,
-        string_module,
+        regex::regex_module,
+        streams, string_module,
         time::time_module,
         vectors::immutable_vectors_module,
         ControlOperations, IoFunctions, MetaOperations, NumOperations, StreamOperations,
@@ -43,7 +55,10 @@ use crate::{
     },
     values::{
         closed::HeapRef,
-        functions::{attach_contract_struct, get_contract, LambdaMetadataTable},
+        functions::{
+            attach_contract_struct, get_contract, is_unsupplied, ByteCodeLambda,
+            LambdaMetadataTable,
+        },
         structs::{
             build_type_id_module, make_struct_type, struct_update_primitive, SteelResult,
             UserDefinedStruct,
@@ -314,6 +329,7 @@ thread_local! {
     pub static SANDBOXED_IO_MODULE: BuiltInModule = sandboxed_io_module();
     pub static PROCESS_MODULE: BuiltInModule = process_module();
     pub static RANDOM_MODULE: BuiltInModule = random_module();
+    pub static REGEX_MODULE: BuiltInModule = regex_module();
     pub static RESULT_MODULE: BuiltInModule = build_result_structs();
     pub static TYPE_ID_MODULE: BuiltInModule = build_type_id_module();
     pub static OPTION_MODULE: BuiltInModule = build_option_structs();
@@ -327,7 +343,6 @@ thread_local! {
 
     pub static MUTABLE_HASH_MODULE: BuiltInModule = mutable_hashmap_module();
     pub static MUTABLE_VECTOR_MODULE: BuiltInModule = mutable_vector_module();
-    pub static PRIVATE_READER_MODULE: BuiltInModule = reader_module();
 
 }
 
@@ -395,6 +410,7 @@ pub fn register_builtin_modules_without_io(engine: &mut Engine) {
         .register_module(JSON_MODULE.with(|x| x.clone()))
         .register_module(CONSTANTS_MODULE.with(|x| x.clone()))
         .register_module(SYNTAX_MODULE.with(|x| x.clone()))
+        .register_module(REGEX_MODULE.with(|x| x.clone()))
         .register_module(PRELUDE_MODULE.with(|x| x.clone()));
 }
 
@@ -474,12 +490,12 @@ pub fn register_builtin_modules(engine: &mut Engine) {
         .register_module(PRELUDE_MODULE.with(|x| x.clone()))
         .register_module(TIME_MODULE.with(|x| x.clone()))
         .register_module(RANDOM_MODULE.with(|x| x.clone()))
+        .register_module(REGEX_MODULE.with(|x| x.clone()))
         .register_module(THREADING_MODULE.with(|x| x.clone()));
 
     // Private module
     engine.register_module(MUTABLE_HASH_MODULE.with(|x| x.clone()));
     engine.register_module(MUTABLE_VECTOR_MODULE.with(|x| x.clone()));
-    engine.register_module(PRIVATE_READER_MODULE.with(|x| x.clone()));
 
     engine.register_module(IMMUTABLE_VECTOR_MODULE.with(|x| x.clone()));
 }
@@ -683,6 +699,7 @@ pub fn numberp(value: &SteelVal) -> bool {
             | SteelVal::Rational(_)
             | SteelVal::BigRational(_)
             | SteelVal::NumV(_)
+            | SteelVal::Complex(_)
     )
 }
 
@@ -702,7 +719,7 @@ fn floatp(value: &SteelVal) -> bool {
 }
 
 #[steel_derive::function(name = "real?", constant = true)]
-fn realp(value: &SteelVal) -> bool {
+pub fn realp(value: &SteelVal) -> bool {
     matches!(
         value,
         SteelVal::IntV(_)
@@ -870,11 +887,19 @@ fn identity_module() -> BuiltInModule {
 fn stream_module() -> BuiltInModule {
     let mut module = BuiltInModule::new("steel/streams");
     module
-        .register_value("stream-cons", StreamOperations::stream_cons())
+        .register_value("#%stream-cons", StreamOperations::stream_cons())
         .register_value("empty-stream", StreamOperations::empty_stream())
         .register_value("stream-empty?", StreamOperations::stream_empty_huh())
         .register_value("stream-car", StreamOperations::stream_car())
-        .register_value("#%stream-cdr", StreamOperations::stream_cdr());
+        .register_value("#%stream-cdr", StreamOperations::stream_cdr())
+        .register_value("#%make-promise", SteelVal::FuncV(streams::make_promise))
+        .register_value("promise?", SteelVal::FuncV(streams::is_promise))
+        .register_value("#%promise-done?", SteelVal::FuncV(streams::promise_is_done))
+        .register_value("#%promise-value", SteelVal::FuncV(streams::promise_value))
+        .register_value(
+            "#%promise-update!",
+            SteelVal::FuncV(streams::promise_update),
+        );
     module
 }
 
@@ -893,16 +918,10 @@ fn stream_module() -> BuiltInModule {
 //     module
 // }
 
+/// Converts a number to the closest inexact number. Alias of `inexact`.
 #[steel_derive::function(name = "exact->inexact", constant = true)]
 fn exact_to_inexact(number: &SteelVal) -> Result<SteelVal> {
-    match number {
-        SteelVal::IntV(i) => (*i as f64).into_steelval(),
-        SteelVal::Rational(f) => f.to_f64().unwrap().into_steelval(),
-        SteelVal::BigRational(f) => f.to_f64().unwrap().into_steelval(),
-        SteelVal::NumV(n) => n.into_steelval(),
-        SteelVal::BigNum(n) => Ok(SteelVal::NumV(n.to_f64().unwrap())),
-        _ => stop!(TypeMismatch => "exact->inexact expects a number type, found: {}", number),
-    }
+    inexact(number)
 }
 
 // Docs from racket:
@@ -934,12 +953,31 @@ fn abs(number: &SteelVal) -> Result<SteelVal> {
     }
 }
 
-// TODO: Add support for BigNum.
 #[steel_derive::function(name = "expt", constant = true)]
 fn expt(left: &SteelVal, right: &SteelVal) -> Result<SteelVal> {
     match (left, right) {
-        (SteelVal::IntV(l), SteelVal::IntV(r)) if *r < (u32::MAX as isize) => {
-            l.pow(*r as u32).into_steelval()
+        (SteelVal::Complex(_), _) | (_, SteelVal::Complex(_)) => complex_expt(left, right),
+        // Negative numbers raised to fractional powers have complex results
+        (_, SteelVal::NumV(_) | SteelVal::Rational(_) | SteelVal::BigRational(_))
+            if left < &SteelVal::IntV(0)
+                && !matches!(right, SteelVal::NumV(r) if r.fract() == 0.0) =>
+        {
+            complex_expt(left, right)
+        }
+        (SteelVal::IntV(_) | SteelVal::BigNum(_), SteelVal::IntV(r)) if *r < 0 => {
+            crate::primitives::divide_primitive(&[
+                SteelVal::IntV(1),
+                expt(left, &SteelVal::IntV(-r))?,
+            ])
+        }
+        (SteelVal::IntV(l), SteelVal::IntV(r)) if *r <= (u32::MAX as isize) => {
+            match l.checked_pow(*r as u32) {
+                Some(result) => result.into_steelval(),
+                None => BigInt::from(*l).pow(*r as u32).into_steelval(),
+            }
+        }
+        (SteelVal::BigNum(l), SteelVal::IntV(r)) if *r <= (u32::MAX as isize) => {
+            l.as_ref().pow(*r as u32).into_steelval()
         }
         (SteelVal::IntV(l), SteelVal::NumV(r)) => (*l as f64).powf(*r).into_steelval(),
         (SteelVal::IntV(l), SteelVal::Rational(r)) => {
@@ -998,55 +1036,6 @@ fn expt(left: &SteelVal, right: &SteelVal) -> Result<SteelVal> {
     }
 }
 
-/// Returns Euler's number raised to the power of z.
-#[steel_derive::function(name = "exp", constant = true)]
-fn exp(left: &SteelVal) -> Result<SteelVal> {
-    match left {
-        SteelVal::IntV(0) => Ok(SteelVal::IntV(1)),
-        SteelVal::IntV(l) if *l < i32::MAX as isize => {
-            Ok(SteelVal::NumV(std::f64::consts::E.powi(*l as i32)))
-        }
-        SteelVal::NumV(n) => Ok(SteelVal::NumV(std::f64::consts::E.powf(*n))),
-        SteelVal::Rational(f) => std::f64::consts::E
-            .powf(f.to_f64().unwrap())
-            .into_steelval(),
-        SteelVal::BigRational(f) => std::f64::consts::E
-            .powf(f.to_f64().unwrap())
-            .into_steelval(),
-        _ => {
-            stop!(Generic => "integer power too large to be used for exponent")
-        }
-    }
-}
-
-#[steel_derive::native(name = "log", arity = "AtLeast(1)")]
-fn log(args: &[SteelVal]) -> Result<SteelVal> {
-    if args.len() > 2 {
-        stop!(ArityMismatch => "log expects one or two arguments, found: {}", args.len());
-    }
-
-    let first = &args[0];
-    let base = args
-        .get(1)
-        .cloned()
-        .unwrap_or(SteelVal::NumV(std::f64::consts::E));
-
-    match (first, &base) {
-        (SteelVal::IntV(1), _) => Ok(SteelVal::IntV(0)),
-        (SteelVal::IntV(_) | SteelVal::NumV(_), SteelVal::IntV(1)) => {
-            stop!(Generic => "log: divide by zero with args: {} and {}", first, base);
-        }
-        (SteelVal::IntV(arg), SteelVal::NumV(n)) => Ok(SteelVal::NumV((*arg as f64).log(*n))),
-        (SteelVal::IntV(arg), SteelVal::IntV(base)) => Ok(SteelVal::IntV(arg.ilog(*base) as isize)),
-        (SteelVal::NumV(arg), SteelVal::NumV(n)) => Ok(SteelVal::NumV(arg.log(*n))),
-        (SteelVal::NumV(arg), SteelVal::IntV(base)) => Ok(SteelVal::NumV(arg.log(*base as f64))),
-        // TODO: Support BigNum, Rational, and BigRational.
-        _ => {
-            stop!(TypeMismatch => "log expects one or two numbers, found: {} and {}", first, base);
-        }
-    }
-}
-
 fn number_module() -> BuiltInModule {
     let mut module = BuiltInModule::new("steel/numbers");
     module
@@ -1057,7 +1046,28 @@ fn number_module() -> BuiltInModule {
         .register_native_fn_definition(SUBTRACT_PRIMITIVE_DEFINITION)
         .register_value("even?", NumOperations::even())
         .register_value("odd?", NumOperations::odd())
-        .register_fn("quotient", quotient)
+        .register_native_fn_definition(QUOTIENT_DEFINITION)
+        .register_native_fn_definition(REMAINDER_DEFINITION)
+        .register_native_fn_definition(MODULO_DEFINITION)
+        .register_value_with_doc(
+            "floor/",
+            SteelVal::BuiltIn(crate::primitives::nums::floor_division),
+            crate::primitives::nums::FLOOR_DIVISION_DOC,
+        )
+        .register_native_fn_definition(FLOOR_QUOTIENT_DEFINITION)
+        .register_native_fn_definition(FLOOR_REMAINDER_DEFINITION)
+        .register_value_with_doc(
+            "truncate/",
+            SteelVal::BuiltIn(crate::primitives::nums::truncate_division),
+            crate::primitives::nums::TRUNCATE_DIVISION_DOC,
+        )
+        .register_native_fn_definition(TRUNCATE_QUOTIENT_DEFINITION)
+        .register_native_fn_definition(TRUNCATE_REMAINDER_DEFINITION)
+        .register_value_with_doc(
+            "exact-integer-sqrt",
+            SteelVal::BuiltIn(crate::primitives::nums::exact_integer_sqrt),
+            crate::primitives::nums::EXACT_INTEGER_SQRT_DOC,
+        )
         .register_value("arithmetic-shift", NumOperations::arithmetic_shift())
         .register_native_fn_definition(ABS_DEFINITION)
         .register_native_fn_definition(EXPT_DEFINITION)
@@ -1065,8 +1075,35 @@ fn number_module() -> BuiltInModule {
         .register_native_fn_definition(EXACT_TO_INEXACT_DEFINITION)
         .register_native_fn_definition(EXACTP_DEFINITION)
         .register_native_fn_definition(INEXACTP_DEFINITION)
+        .register_native_fn_definition(EXACT_DEFINITION)
+        .register_native_fn_definition(INEXACT_DEFINITION)
+        .register_native_fn_definition(INEXACT_TO_EXACT_DEFINITION)
+        .register_native_fn_definition(EXACT_INTEGERP_DEFINITION)
+        .register_native_fn_definition(NANP_DEFINITION)
+        .register_native_fn_definition(INFINITEP_DEFINITION)
+        .register_native_fn_definition(FINITEP_DEFINITION)
+        .register_native_fn_definition(COMPLEXP_DEFINITION)
+        .register_native_fn_definition(MAKE_RECTANGULAR_DEFINITION)
+        .register_native_fn_definition(MAKE_POLAR_DEFINITION)
+        .register_native_fn_definition(REAL_PART_DEFINITION)
+        .register_native_fn_definition(IMAG_PART_DEFINITION)
+        .register_native_fn_definition(MAGNITUDE_DEFINITION)
+        .register_native_fn_definition(ANGLE_DEFINITION)
+        .register_native_fn_definition(NUMERATOR_DEFINITION)
+        .register_native_fn_definition(DENOMINATOR_DEFINITION)
+        .register_native_fn_definition(RATIONALIZE_DEFINITION)
+        .register_native_fn_definition(FLOOR_DEFINITION)
+        .register_native_fn_definition(CEILING_DEFINITION)
+        .register_native_fn_definition(TRUNCATE_DEFINITION)
+        .register_native_fn_definition(SQRT_DEFINITION)
         .register_native_fn_definition(EXP_DEFINITION)
-        .register_native_fn_definition(LOG_DEFINITION);
+        .register_native_fn_definition(LOG_DEFINITION)
+        .register_native_fn_definition(SIN_DEFINITION)
+        .register_native_fn_definition(COS_DEFINITION)
+        .register_native_fn_definition(TAN_DEFINITION)
+        .register_native_fn_definition(ASIN_DEFINITION)
+        .register_native_fn_definition(ACOS_DEFINITION)
+        .register_native_fn_definition(ATAN_DEFINITION);
 
     module
 }
@@ -1076,28 +1113,56 @@ pub fn equality_primitive(args: &[SteelVal]) -> Result<SteelVal> {
     Ok(SteelVal::BoolV(args.windows(2).all(|x| x[0] == x[1])))
 }
 
-pub fn gte_primitive(args: &[SteelVal]) -> Result<SteelVal> {
+// Compares each neighbouring pair of arguments. Complex numbers have no ordering, so
+// comparing one is an error rather than #f.
+#[inline(always)]
+fn ordering_primitive(
+    name: &str,
+    args: &[SteelVal],
+    holds: impl Fn(Ordering) -> bool,
+) -> Result<SteelVal> {
     if args.is_empty() {
         stop!(ArityMismatch => "expected at least one argument");
     }
 
+    if let Some(complex) = args.iter().find(|x| matches!(x, SteelVal::Complex(_))) {
+        stop!(TypeMismatch => "{} expected real numbers, found: {}", name, complex);
+    }
+
     Ok(SteelVal::BoolV(args.windows(2).all(|x| {
-        x[0].partial_cmp(&x[1])
-            .map(|x| x != Ordering::Less)
-            .unwrap_or(false)
+        x[0].partial_cmp(&x[1]).map(&holds).unwrap_or(false)
     })))
 }
 
+pub fn gt_primitive(args: &[SteelVal]) -> Result<SteelVal> {
+    ordering_primitive(">", args, |x| x == Ordering::Greater)
+}
+
+pub fn gte_primitive(args: &[SteelVal]) -> Result<SteelVal> {
+    ordering_primitive(">=", args, |x| x != Ordering::Less)
+}
+
+pub fn lt_primitive(args: &[SteelVal]) -> Result<SteelVal> {
+    ordering_primitive("<", args, |x| x == Ordering::Less)
+}
+
 #[inline(always)]
 pub fn lte_primitive(args: &[SteelVal]) -> Result<SteelVal> {
+    ordering_primitive("<=", args, |x| x != Ordering::Greater)
+}
+
+// Strings are compared by identity, since every string built at runtime is a new object
+// that only happens to share its contents with others
+fn eqv_primitive(args: &[SteelVal]) -> Result<SteelVal> {
     if args.is_empty() {
         stop!(ArityMismatch => "expected at least one argument");
     }
 
     Ok(SteelVal::BoolV(args.windows(2).all(|x| {
-        x[0].partial_cmp(&x[1])
-            .map(|x| x != Ordering::Greater)
-            .unwrap_or(false)
+        match (&x[0], &x[1]) {
+            (SteelVal::StringV(_), SteelVal::StringV(_)) => x[0].ptr_eq(&x[1]),
+            (left, right) => left == right,
+        }
     })))
 }
 
@@ -1108,7 +1173,7 @@ fn equality_module() -> BuiltInModule {
             "equal?",
             SteelVal::FuncV(ensure_tonicity_two!(|a, b| a == b)),
         )
-        .register_value("eqv?", SteelVal::FuncV(ensure_tonicity_two!(|a, b| a == b)))
+        .register_value("eqv?", SteelVal::FuncV(eqv_primitive))
         .register_value(
             "eq?",
             SteelVal::FuncV(ensure_tonicity_two!(
@@ -1126,10 +1191,10 @@ fn equality_module() -> BuiltInModule {
 fn ord_module() -> BuiltInModule {
     let mut module = BuiltInModule::new("steel/ord");
     module
-        .register_value(">", SteelVal::FuncV(ensure_tonicity_two!(|a, b| a > b)))
+        .register_value(">", SteelVal::FuncV(gt_primitive))
         .register_value(">=", SteelVal::FuncV(gte_primitive))
-        .register_value("<", SteelVal::FuncV(ensure_tonicity_two!(|a, b| a < b)))
-        .register_value("<=", SteelVal::FuncV(ensure_tonicity_two!(|a, b| a <= b)));
+        .register_value("<", SteelVal::FuncV(lt_primitive))
+        .register_value("<=", SteelVal::FuncV(lte_primitive));
     module
 }
 
@@ -1313,7 +1378,7 @@ fn arity(value: SteelVal) -> UnRecoverableResult {
                     Ok(SteelVal::IntV(c.arity() as isize)).into()
                 }
             } else {
-                Ok(SteelVal::IntV(c.arity() as isize)).into()
+                Ok(closure_arity(&c)).into()
             }
         }
         SteelVal::BoxedFunction(f) => f
@@ -1345,10 +1410,63 @@ fn arity(value: SteelVal) -> UnRecoverableResult {
     }
 }
 
-// Only works with fixed size arity functions
+fn unsupplied(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "#%unsupplied? expects 1 argument, found: {}", args.len());
+    }
+
+    Ok(SteelVal::BoolV(is_unsupplied(&args[0])))
+}
+
+// The number of positional arguments a closure takes, or for a `case-lambda` the number each of
+// its clauses takes. Closures with optional or keyword arguments describe them in a hash, so
+// `(lambda (a [b 10] #:c [c 3] #:d d) ...)` gives
+// `(hash 'required 1 'optional 1 'rest? #f 'keywords (list #:c #:d) 'required-keywords (list #:d))`
+fn closure_arity(c: &ByteCodeLambda) -> SteelVal {
+    match c.signature() {
+        Some(LambdaSignature::Parameters {
+            required,
+            optional,
+            keywords,
+        }) => {
+            let keyword_list = |only_required: bool| {
+                SteelVal::ListV(
+                    keywords
+                        .iter()
+                        .filter(|(_, required)| *required || !only_required)
+                        .map(|(keyword, _)| SteelVal::Keyword(keyword.resolve().into()))
+                        .collect(),
+                )
+            };
+
+            SteelVal::HashMapV(
+                Gc::new(im_rc::hashmap! {
+                    SteelVal::SymbolV("required".into()) => SteelVal::IntV(*required as isize),
+                    SteelVal::SymbolV("optional".into()) => SteelVal::IntV(*optional as isize),
+                    SteelVal::SymbolV("rest?".into()) => SteelVal::BoolV(c.is_multi_arity),
+                    SteelVal::SymbolV("keywords".into()) => keyword_list(false),
+                    SteelVal::SymbolV("required-keywords".into()) => keyword_list(true),
+                })
+                .into(),
+            )
+        }
+        Some(LambdaSignature::Cases(clauses)) => SteelVal::ListV(
+            clauses
+                .iter()
+                .map(|(arity, _)| SteelVal::IntV(*arity as isize))
+                .collect(),
+        ),
+        None => SteelVal::IntV(c.arity() as isize),
+    }
+}
+
+// Whether a closure accepts anything other than a fixed number of arguments - rest, optional or
+// keyword arguments, or more than one `case-lambda` clause
 fn is_multi_arity(value: SteelVal) -> UnRecoverableResult {
     match value {
-        SteelVal::Closure(c) => Ok(SteelVal::BoolV(c.is_multi_arity)).into(),
+        SteelVal::Closure(c) => {
+            Ok(SteelVal::BoolV(c.is_multi_arity || c.signature().is_some())).into()
+        }
         _ => steelerr!(TypeMismatch => "Unable to find the arity for the given function").into(),
     }
 }
@@ -1449,84 +1567,6 @@ impl MutableHashTable {
     }
 }
 
-struct Reader {
-    buffer: String,
-    offset: usize,
-}
-
-impl crate::rvals::Custom for Reader {}
-
-impl Reader {
-    fn create_reader() -> Reader {
-        Self {
-            buffer: String::new(),
-            offset: 0,
-        }
-    }
-
-    fn push_string(&mut self, input: crate::rvals::SteelString) {
-        self.buffer.push_str(input.as_str());
-    }
-
-    fn is_empty(&self) -> bool {
-        self.buffer.is_empty()
-    }
-
-    fn read_one(&mut self) -> Result<SteelVal> {
-        if let Some(buffer) = self.buffer.get(self.offset..) {
-            // println!("Reading range: {}", buffer);
-
-            let mut parser = crate::parser::parser::Parser::new_flat(buffer, None);
-
-            if let Some(raw) = parser.next() {
-                // self.offset += parser.offset();
-
-                let next = if let Ok(next) = raw {
-                    next
-                } else {
-                    return Ok(SteelVal::Void);
-                };
-
-                self.offset += parser.offset();
-
-                let result = TryFromExprKindForSteelVal::try_from_expr_kind_quoted(next);
-
-                if let Some(remaining) = self.buffer.get(self.offset..) {
-                    for _ in remaining.chars().take_while(|x| x.is_whitespace()) {
-                        self.offset += 1;
-                    }
-                }
-
-                if self.offset == self.buffer.len() {
-                    self.buffer.clear();
-                    self.offset = 0;
-                }
-
-                result
-            } else {
-                // No value, keep reading
-                Ok(SteelVal::Void)
-            }
-        } else {
-            Ok(SteelVal::SymbolV(
-                crate::primitives::ports::EOF_OBJECT.with(|x| x.clone()),
-            ))
-        }
-    }
-}
-
-fn reader_module() -> BuiltInModule {
-    let mut module = BuiltInModule::new("#%private/steel/reader");
-
-    module
-        .register_fn("new-reader", Reader::create_reader)
-        .register_fn("reader-push-string", Reader::push_string)
-        .register_fn("reader-read-one", Reader::read_one)
-        .register_fn("reader-empty?", Reader::is_empty);
-
-    module
-}
-
 fn mutable_vector_module() -> BuiltInModule {
     let mut module = BuiltInModule::new("#%private/steel/mvector");
 
@@ -1571,7 +1611,10 @@ fn make_mutable_box(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelV
         args[0].clone(), // TODO: Could actually move off of the stack entirely
         ctx.thread.stack.iter(),
         ctx.thread.stack_frames.iter().map(|x| x.function.as_ref()),
-        ctx.thread.global_env.roots(),
+        ctx.thread
+            .global_env
+            .roots()
+            .chain(ctx.thread.parameters.roots()),
     );
 
     Some(Ok(SteelVal::HeapAllocated(allocated_var)))
@@ -1638,7 +1681,64 @@ fn meta_module() -> BuiltInModule {
         .register_value("error-with-span", error_with_src_loc())
         .register_value("raise-error-with-span", error_from_error_with_span())
         .register_value("raise-error", raise_error_from_error())
+        .register_value("raise", SteelVal::FuncV(control::raise))
+        .register_value(
+            "#%raise-with-depth",
+            SteelVal::FuncV(control::raise_with_depth),
+        )
+        .register_value(
+            "#%error-handler-depth",
+            SteelVal::FuncV(control::error_handler_depth),
+        )
+        .register_value(
+            "#%error->condition",
+            SteelVal::FuncV(control::error_to_condition),
+        )
+        .register_value("error-object?", SteelVal::FuncV(control::is_error_object))
+        .register_value(
+            "error-object-message",
+            SteelVal::FuncV(control::error_object_message),
+        )
+        .register_value(
+            "error-object-irritants",
+            SteelVal::FuncV(control::error_object_irritants),
+        )
+        .register_value("file-error?", SteelVal::FuncV(control::is_file_error))
+        .register_value("read-error?", SteelVal::FuncV(control::is_read_error))
         .register_value("call/cc", SteelVal::BuiltIn(super::vm::call_cc))
+        .register_value(
+            "#%make-parameter-key",
+            SteelVal::FuncV(parameters::make_parameter_key),
+        )
+        .register_value(
+            "#%parameter-ref",
+            SteelVal::BuiltIn(parameters::parameter_ref),
+        )
+        .register_value(
+            "#%parameter-set!",
+            SteelVal::BuiltIn(parameters::parameter_set),
+        )
+        .register_value(
+            "#%current-parameterization",
+            SteelVal::BuiltIn(parameters::current_parameterization),
+        )
+        .register_value(
+            "#%extend-parameterization",
+            SteelVal::FuncV(parameters::extend_parameterization),
+        )
+        .register_value(
+            "#%set-parameterization!",
+            SteelVal::BuiltIn(parameters::set_parameterization),
+        )
+        .register_value("values", SteelVal::BuiltIn(super::vm::values))
+        .register_value(
+            "#%call-producer",
+            SteelVal::BuiltIn(super::vm::call_producer),
+        )
+        .register_value(
+            "#%values->list",
+            SteelVal::FuncV(crate::values::multiple_values::values_to_list),
+        )
         .register_value(
             "call-with-exception-handler",
             SteelVal::BuiltIn(super::vm::call_with_exception_handler),
@@ -1671,10 +1771,12 @@ fn meta_module() -> BuiltInModule {
         .register_value("iter-next!", SteelVal::FuncV(crate::rvals::iterator_next))
         // Check whether the iterator is done
         .register_value("#%iterator-finished", ITERATOR_FINISHED.with(|x| x.clone()))
+        // Check whether an optional or keyword argument was left out of a call
+        .register_value("#%unsupplied?", SteelVal::FuncV(unsupplied))
         .register_value("%iterator?", gen_pred!(BoxedIterator))
         .register_fn("env-var", get_environment_variable)
         .register_fn("maybe-get-env-var", maybe_get_environment_variable)
-        .register_fn("set-env-var!", std::env::set_var::<String, String>)
+        .register_fn("set-env-var!", |k: String, v: String| std::env::set_var(k, v))
         .register_fn("arity?", arity)
         .register_fn("function-name", lookup_function_name)
         .register_fn("#%native-fn-ptr-doc", lookup_doc)
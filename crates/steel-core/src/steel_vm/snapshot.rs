@@ -0,0 +1,1117 @@
+//! Snapshots of a fully initialized engine, see [`Engine::snapshot`](super::engine::Engine::snapshot).
+//!
+//! The heap of a snapshot holds everything reachable from the globals, constants and function interner
+//! of the engine - closures, structs, heap cells and the struct types they refer to. Values that are
+//! shared, or that could be part of a cycle, are written out once as a node and referred to by index.
+//!
+//! Native values can't be written out. They're recorded by where they came from instead - the builtin
+//! module that provides them, or the global they were registered as - and are relinked against the
+//! engine that the snapshot is restored into. Functions generated for a struct type are rebuilt from
+//! the struct type.
+
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+use fxhash::{FxHashMap, FxHashSet};
+use num::{BigInt, BigRational, Rational32};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    compiler::{compiler::Compiler, constants::ConstantMap, map::SymbolMap},
+    core::instructions::DenseInstruction,
+    gc::Gc,
+    parser::{ast::LambdaSignature, span::Span},
+    rvals::{IntoSteelVal, Result, SteelComplex, SteelVal},
+    values::{
+        closed::{Heap, HeapRef},
+        functions::{ByteCodeLambda, LambdaMetadataTable},
+        lazy_stream::LazyStream,
+        lists::{List, Pair},
+        port::{SteelPort, SteelPortRepr},
+        structs::{StructFunction, StructTypeDescriptor, UserDefinedStruct, VTable},
+    },
+};
+
+use super::{
+    builtin::BuiltInModule,
+    engine::ModuleContainer,
+    vm::{parameters::ParameterKey, SteelThread},
+};
+
+/// Which of the engines in a snapshot a global belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) enum EngineSlot {
+    Main,
+    Kernel,
+}
+
+/// Where a native value came from, so that it can be found again when restoring
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) enum NativeRef {
+    Module { module: String, name: String },
+    Global { engine: EngineSlot, name: String },
+    BuiltInModule(String),
+}
+
+#[derive(Serialize, Deserialize)]
+enum Value {
+    Void,
+    Bool(bool),
+    Int(isize),
+    Num(f64),
+    Rational(i32, i32),
+    BigNum(String),
+    BigRational(String),
+    Complex(Box<Value>, Box<Value>),
+    Char(char),
+    String(String),
+    Symbol(String),
+    Keyword(String),
+    List(Vec<Value>),
+    Pair(Box<Value>, Box<Value>),
+    Vector(Vec<Value>),
+    HashMap(Vec<(Value, Value)>),
+    HashSet(Vec<Value>),
+    Stdin,
+    Stdout,
+    Stderr,
+    ParameterKey(usize),
+    Node(usize),
+    Native(usize),
+    StructType(usize),
+    StructFunction {
+        function: StructFunction,
+        struct_type: usize,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+enum Node {
+    Closure {
+        id: usize,
+        arity: usize,
+        is_multi_arity: bool,
+        signature: Option<LambdaSignature>,
+        body: usize,
+        captures: Vec<Value>,
+        heap_allocated: Vec<usize>,
+        contract: Option<Value>,
+    },
+    Struct {
+        struct_type: usize,
+        fields: Vec<Value>,
+    },
+    Stream {
+        initial_value: Value,
+        thunk: Value,
+        empty: bool,
+    },
+    // Mutable cells. These are the only way for values to refer back to themselves.
+    HeapAllocated(Value),
+    MutableVector(Vec<Value>),
+    Boxed(Value),
+}
+
+#[derive(Serialize, Deserialize)]
+enum StructType {
+    /// Declared by native code, found through one of the functions for the struct type
+    Native(usize),
+    Declared {
+        name: String,
+        proc: Option<usize>,
+        properties: Vec<(Value, Value)>,
+    },
+}
+
+/// The values shared between the engines of a snapshot
+#[derive(Serialize, Deserialize)]
+pub(crate) struct HeapSnapshot {
+    natives: Vec<NativeRef>,
+    nodes: Vec<Node>,
+    struct_types: Vec<StructType>,
+    bodies: Vec<Vec<DenseInstruction>>,
+    // Docs attached to closures, for each of the doc tables
+    function_docs: Vec<(usize, Vec<(usize, String)>)>,
+}
+
+/// The globals, constants and functions of a single vm
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ThreadSnapshot {
+    globals: Vec<Value>,
+    constants: Vec<Value>,
+    closure_interner: Vec<(usize, usize)>,
+    pure_function_interner: Vec<(usize, usize)>,
+    spans: Vec<(usize, Vec<Span>)>,
+    instructions: Vec<(usize, usize)>,
+    contracts_on: bool,
+    test: bool,
+}
+
+#[derive(Hash, PartialEq, Eq)]
+enum NativeKey {
+    Func(usize),
+    MutFunc(usize),
+    BuiltIn(usize),
+    Boxed(usize),
+    Future(usize),
+    Custom(usize),
+}
+
+impl NativeKey {
+    fn of(value: &SteelVal) -> Option<NativeKey> {
+        match value {
+            SteelVal::FuncV(f) => Some(NativeKey::Func(*f as usize)),
+            SteelVal::MutFunc(f) => Some(NativeKey::MutFunc(*f as usize)),
+            SteelVal::BuiltIn(f) => Some(NativeKey::BuiltIn(*f as usize)),
+            SteelVal::BoxedFunction(f) => Some(NativeKey::Boxed(Arc::as_ptr(&f.function)
+                as *const ()
+                as usize)),
+            SteelVal::FutureFunc(f) => Some(NativeKey::Future(Rc::as_ptr(f) as *const () as usize)),
+            SteelVal::Custom(c) => Some(NativeKey::Custom(Gc::as_ptr(c) as usize)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(not(feature = "dynamic"))]
+fn closure_body(closure: &ByteCodeLambda) -> Rc<[DenseInstruction]> {
+    Rc::clone(&closure.body_exp)
+}
+
+#[cfg(feature = "dynamic")]
+fn closure_body(closure: &ByteCodeLambda) -> Rc<[DenseInstruction]> {
+    Rc::clone(&closure.body_exp.borrow())
+}
+
+fn describe(value: &SteelVal) -> String {
+    match value {
+        SteelVal::Custom(c) => format!("a value of type {}", c.borrow().name()),
+        SteelVal::PortV(_) => "a port".to_string(),
+        SteelVal::IterV(_) | SteelVal::ReducerV(_) => "a transducer".to_string(),
+        SteelVal::FutureV(_) => "a future".to_string(),
+        SteelVal::ContinuationFunction(_) => "a continuation".to_string(),
+        SteelVal::BoxedIterator(_) => "an iterator".to_string(),
+        SteelVal::SyntaxObject(_) => "a syntax object".to_string(),
+        SteelVal::Reference(_) => "a reference to a rust value".to_string(),
+        _ => "a native function".to_string(),
+    }
+}
+
+pub(crate) struct HeapEncoder {
+    natives: Vec<NativeRef>,
+    native_index: FxHashMap<NativeKey, usize>,
+    native_struct_types: FxHashMap<StructTypeDescriptor, usize>,
+    nodes: Vec<Option<Node>>,
+    node_index: FxHashMap<usize, usize>,
+    struct_types: Vec<Option<StructType>>,
+    struct_type_index: FxHashMap<StructTypeDescriptor, usize>,
+    bodies: Vec<Vec<DenseInstruction>>,
+    body_index: FxHashMap<usize, usize>,
+    closure_ids: FxHashSet<usize>,
+    doc_tables: Vec<(usize, SteelVal)>,
+    // What is being encoded, for error messages
+    context: String,
+}
+
+impl HeapEncoder {
+    pub(crate) fn new() -> Self {
+        Self {
+            natives: Vec::new(),
+            native_index: FxHashMap::default(),
+            native_struct_types: FxHashMap::default(),
+            nodes: Vec::new(),
+            node_index: FxHashMap::default(),
+            struct_types: Vec::new(),
+            struct_type_index: FxHashMap::default(),
+            bodies: Vec::new(),
+            body_index: FxHashMap::default(),
+            closure_ids: FxHashSet::default(),
+            doc_tables: Vec::new(),
+            context: String::new(),
+        }
+    }
+
+    fn add_native(&mut self, key: NativeKey, native: NativeRef, value: &SteelVal) {
+        if self.native_index.contains_key(&key) {
+            return;
+        }
+
+        let index = self.natives.len();
+        self.natives.push(native);
+        self.native_index.insert(key, index);
+
+        if let SteelVal::BoxedFunction(f) = value {
+            if let Some((_, descriptor)) = StructFunction::lookup(f) {
+                self.native_struct_types.entry(descriptor).or_insert(index);
+            }
+        }
+    }
+
+    /// Records where the native values of an engine come from. Values provided by a builtin module
+    /// are preferred, since those can be found in any engine with the module registered.
+    pub(crate) fn index_natives(
+        &mut self,
+        modules: &ModuleContainer,
+        symbol_map: &SymbolMap,
+        globals: &[SteelVal],
+        engine: EngineSlot,
+    ) {
+        let mut modules = modules.inner().values().collect::<Vec<_>>();
+        modules.sort_by_key(|module| module.name());
+
+        for module in modules {
+            let mut values = module.values();
+            values.sort_by(|left, right| left.0.cmp(&right.0));
+
+            for (name, value) in values {
+                if let Some(key) = NativeKey::of(&value) {
+                    let native = NativeRef::Module {
+                        module: module.name().to_string(),
+                        name: name.to_string(),
+                    };
+
+                    self.add_native(key, native, &value);
+                }
+            }
+        }
+
+        for (index, (name, value)) in symbol_map.values().iter().zip(globals).enumerate() {
+            // Only the latest definition of a name can be found again
+            if symbol_map.get(name).ok() != Some(index) {
+                continue;
+            }
+
+            // Struct types and their functions are rebuilt instead
+            let rebuilt = match value {
+                SteelVal::BoxedFunction(f) => StructFunction::lookup(f).is_some(),
+                SteelVal::Custom(c) => {
+                    let custom = c.borrow();
+                    custom.as_any_ref().is::<StructTypeDescriptor>()
+                }
+                _ => false,
+            };
+
+            if rebuilt {
+                continue;
+            }
+
+            if let Some(key) = NativeKey::of(value) {
+                let native = NativeRef::Global {
+                    engine,
+                    name: name.resolve().to_string(),
+                };
+
+                self.add_native(key, native, value);
+            }
+        }
+    }
+
+    pub(crate) fn encode_thread(
+        &mut self,
+        thread: &SteelThread,
+        compiler: &Compiler,
+    ) -> Result<ThreadSnapshot> {
+        let symbol_map = &compiler.symbol_map;
+
+        let mut globals = Vec::with_capacity(thread.global_env.bindings_vec.len());
+
+        for (index, value) in thread.global_env.bindings_vec.iter().enumerate() {
+            self.context = symbol_map
+                .values()
+                .get(index)
+                .map(|name| format!("the global `{}`", name.resolve()))
+                .unwrap_or_else(|| format!("global #{index}"));
+
+            globals.push(self.encode(value)?);
+        }
+
+        self.context = "the constant map".to_string();
+
+        // The vm shares the constant map of the compiler once a program has been run
+        let constants = (0..compiler.constant_map.len())
+            .map(|index| self.encode(&compiler.constant_map.get(index)))
+            .collect::<Result<_>>()?;
+
+        self.context = "the function interner".to_string();
+
+        let interner = &thread.function_interner;
+
+        let mut closure_interner = Vec::with_capacity(interner.closure_interner.len());
+        for (id, closure) in &interner.closure_interner {
+            closure_interner.push((*id, self.encode_closure(closure)?));
+        }
+
+        let mut pure_function_interner = Vec::with_capacity(interner.pure_function_interner.len());
+        for (id, closure) in &interner.pure_function_interner {
+            let Value::Node(node) = self.encode(&SteelVal::Closure(closure.clone()))? else {
+                unreachable!()
+            };
+            pure_function_interner.push((*id, node));
+        }
+
+        let spans = interner
+            .spans
+            .iter()
+            .map(|(id, spans)| (*id, spans.to_vec()))
+            .collect();
+
+        let instructions = interner
+            .instructions
+            .iter()
+            .map(|(id, body)| (*id, self.encode_body(Rc::clone(body))))
+            .collect();
+
+        Ok(ThreadSnapshot {
+            globals,
+            constants,
+            closure_interner,
+            pure_function_interner,
+            spans,
+            instructions,
+            contracts_on: thread.runtime_options.contracts_on,
+            test: thread.runtime_options.test,
+        })
+    }
+
+    pub(crate) fn finish(self) -> HeapSnapshot {
+        let function_docs = self
+            .doc_tables
+            .iter()
+            .map(|(native, table)| {
+                let entries = match table {
+                    SteelVal::Custom(c) => c
+                        .borrow()
+                        .as_any_ref()
+                        .downcast_ref::<LambdaMetadataTable>()
+                        .map(|table| table.closure_entries(&self.closure_ids))
+                        .unwrap_or_default(),
+                    _ => Vec::new(),
+                };
+
+                (
+                    *native,
+                    entries
+                        .into_iter()
+                        .map(|(id, doc)| (id, doc.to_string()))
+                        .collect(),
+                )
+            })
+            .collect();
+
+        HeapSnapshot {
+            natives: self.natives,
+            nodes: self.nodes.into_iter().map(Option::unwrap).collect(),
+            struct_types: self.struct_types.into_iter().map(Option::unwrap).collect(),
+            bodies: self.bodies,
+            function_docs,
+        }
+    }
+
+    fn encode_body(&mut self, body: Rc<[DenseInstruction]>) -> usize {
+        let key = Rc::as_ptr(&body) as *const () as usize;
+
+        if let Some(index) = self.body_index.get(&key) {
+            return *index;
+        }
+
+        let index = self.bodies.len();
+        self.bodies.push(body.to_vec());
+        self.body_index.insert(key, index);
+
+        index
+    }
+
+    // Reserves a node for the value at the given address, or returns the existing
+    // node if it has already been encoded.
+    fn reserve(&mut self, key: usize) -> std::result::Result<usize, usize> {
+        if let Some(index) = self.node_index.get(&key) {
+            return Err(*index);
+        }
+
+        let index = self.nodes.len();
+        self.nodes.push(None);
+        self.node_index.insert(key, index);
+
+        Ok(index)
+    }
+
+    fn encode_closure(&mut self, closure: &ByteCodeLambda) -> Result<usize> {
+        let index = self.nodes.len();
+        self.nodes.push(None);
+
+        let node = self.closure_node(closure)?;
+        self.nodes[index] = Some(node);
+
+        Ok(index)
+    }
+
+    fn closure_node(&mut self, closure: &ByteCodeLambda) -> Result<Node> {
+        self.closure_ids.insert(closure.id);
+
+        let captures = closure
+            .captures
+            .iter()
+            .map(|x| self.encode(x))
+            .collect::<Result<_>>()?;
+
+        let heap_allocated = closure
+            .heap_allocated
+            .borrow()
+            .iter()
+            .map(
+                |cell| match self.encode(&SteelVal::HeapAllocated(cell.clone()))? {
+                    Value::Node(index) => Ok(index),
+                    _ => unreachable!(),
+                },
+            )
+            .collect::<Result<_>>()?;
+
+        let contract = closure
+            .get_contract_information()
+            .map(|x| self.encode(&x))
+            .transpose()?;
+
+        Ok(Node::Closure {
+            id: closure.id,
+            arity: closure.arity,
+            is_multi_arity: closure.is_multi_arity,
+            signature: closure.signature().cloned(),
+            body: self.encode_body(closure_body(closure)),
+            captures,
+            heap_allocated,
+            contract,
+        })
+    }
+
+    fn encode_struct_type(&mut self, descriptor: StructTypeDescriptor) -> Result<usize> {
+        if let Some(index) = self.struct_type_index.get(&descriptor) {
+            return Ok(*index);
+        }
+
+        let index = self.struct_types.len();
+        self.struct_types.push(None);
+        self.struct_type_index.insert(descriptor, index);
+
+        let struct_type = if let Some(native) = self.native_struct_types.get(&descriptor) {
+            StructType::Native(*native)
+        } else {
+            let (name, proc, properties) = VTable::entry(&descriptor);
+
+            StructType::Declared {
+                name: name.resolve().to_string(),
+                proc,
+                properties: properties
+                    .iter()
+                    .map(|(key, value)| Ok((self.encode(key)?, self.encode(value)?)))
+                    .collect::<Result<_>>()?,
+            }
+        };
+
+        self.struct_types[index] = Some(struct_type);
+
+        Ok(index)
+    }
+
+    fn encode_all<'a>(&mut self, values: impl Iterator<Item = &'a SteelVal>) -> Result<Vec<Value>> {
+        values.map(|x| self.encode(x)).collect()
+    }
+
+    fn unsupported<T>(&self, value: &SteelVal) -> Result<T> {
+        stop!(Generic => "unable to snapshot the engine: {} refers to {}, which can't be written into a snapshot", self.context, describe(value))
+    }
+
+    fn encode(&mut self, value: &SteelVal) -> Result<Value> {
+        if let Some(key) = NativeKey::of(value) {
+            if let Some(index) = self.native_index.get(&key) {
+                let index = *index;
+
+                if let SteelVal::Custom(c) = value {
+                    if c.borrow().as_any_ref().is::<LambdaMetadataTable>()
+                        && !self.doc_tables.iter().any(|(native, _)| *native == index)
+                    {
+                        self.doc_tables.push((index, value.clone()));
+                    }
+                }
+
+                return Ok(Value::Native(index));
+            }
+        }
+
+        let encoded = match value {
+            SteelVal::Void => Value::Void,
+            SteelVal::BoolV(b) => Value::Bool(*b),
+            SteelVal::IntV(i) => Value::Int(*i),
+            SteelVal::NumV(n) => Value::Num(*n),
+            SteelVal::Rational(r) => Value::Rational(*r.numer(), *r.denom()),
+            SteelVal::BigNum(n) => Value::BigNum(n.to_string()),
+            SteelVal::BigRational(r) => Value::BigRational(r.to_string()),
+            SteelVal::Complex(c) => {
+                Value::Complex(Box::new(self.encode(&c.re)?), Box::new(self.encode(&c.im)?))
+            }
+            SteelVal::CharV(c) => Value::Char(*c),
+            SteelVal::StringV(s) => Value::String(s.to_string()),
+            SteelVal::SymbolV(s) => Value::Symbol(s.to_string()),
+            SteelVal::Keyword(s) => Value::Keyword(s.to_string()),
+            SteelVal::ListV(l) => Value::List(self.encode_all(l.iter())?),
+            SteelVal::Pair(p) => Value::Pair(
+                Box::new(self.encode(&p.car)?),
+                Box::new(self.encode(&p.cdr)?),
+            ),
+            SteelVal::VectorV(v) => Value::Vector(self.encode_all(v.iter())?),
+            SteelVal::HashMapV(h) => Value::HashMap(
+                h.iter()
+                    .map(|(key, value)| Ok((self.encode(key)?, self.encode(value)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            SteelVal::HashSetV(h) => Value::HashSet(self.encode_all(h.iter())?),
+            SteelVal::PortV(port) => match &*port.port.borrow() {
+                SteelPortRepr::StdInput(_) => Value::Stdin,
+                SteelPortRepr::StdOutput(_) => Value::Stdout,
+                SteelPortRepr::StdError(_) => Value::Stderr,
+                SteelPortRepr::DynWriter(writer) if SteelPort::is_default_output_writer(writer) => {
+                    Value::Stdout
+                }
+                _ => return self.unsupported(value),
+            },
+            SteelVal::Custom(c) => {
+                if let Some(module) = c.borrow().as_any_ref().downcast_ref::<BuiltInModule>() {
+                    // Modules that aren't bound to a global are still found by name
+                    let key = NativeKey::of(value).unwrap();
+                    let native = NativeRef::BuiltInModule(module.name().to_string());
+                    self.add_native(key, native, value);
+                    Value::Native(self.natives.len() - 1)
+                } else if let Some(key) = c.borrow().as_any_ref().downcast_ref::<ParameterKey>() {
+                    Value::ParameterKey(key.id())
+                } else if let Some(descriptor) = c
+                    .borrow()
+                    .as_any_ref()
+                    .downcast_ref::<StructTypeDescriptor>()
+                {
+                    Value::StructType(self.encode_struct_type(*descriptor)?)
+                } else {
+                    return self.unsupported(value);
+                }
+            }
+            SteelVal::BoxedFunction(f) => match StructFunction::lookup(f) {
+                Some((function, descriptor)) => Value::StructFunction {
+                    function,
+                    struct_type: self.encode_struct_type(descriptor)?,
+                },
+                None => return self.unsupported(value),
+            },
+            SteelVal::Closure(c) => match self.reserve(Gc::as_ptr(c) as usize) {
+                Ok(index) => {
+                    let node = self.closure_node(c)?;
+                    self.nodes[index] = Some(node);
+                    Value::Node(index)
+                }
+                Err(index) => Value::Node(index),
+            },
+            SteelVal::CustomStruct(s) => match self.reserve(Gc::as_ptr(s) as usize) {
+                Ok(index) => {
+                    let struct_type = self.encode_struct_type(s.type_descriptor)?;
+                    let fields = self.encode_all(s.fields.iter())?;
+                    self.nodes[index] = Some(Node::Struct {
+                        struct_type,
+                        fields,
+                    });
+                    Value::Node(index)
+                }
+                Err(index) => Value::Node(index),
+            },
+            SteelVal::StreamV(s) => match self.reserve(Gc::as_ptr(s) as usize) {
+                Ok(index) => {
+                    let initial_value = self.encode(&s.initial_value)?;
+                    let thunk = self.encode(&s.stream_thunk)?;
+                    self.nodes[index] = Some(Node::Stream {
+                        initial_value,
+                        thunk,
+                        empty: s.empty_stream,
+                    });
+                    Value::Node(index)
+                }
+                Err(index) => Value::Node(index),
+            },
+            SteelVal::HeapAllocated(cell) => match self.reserve(cell.as_ptr_usize()) {
+                Ok(index) => {
+                    let inner = self.encode(&cell.get())?;
+                    self.nodes[index] = Some(Node::HeapAllocated(inner));
+                    Value::Node(index)
+                }
+                Err(index) => Value::Node(index),
+            },
+            SteelVal::MutableVector(v) => match self.reserve(v.as_ptr_usize()) {
+                Ok(index) => {
+                    let values = self.encode_all(v.get().iter())?;
+                    self.nodes[index] = Some(Node::MutableVector(values));
+                    Value::Node(index)
+                }
+                Err(index) => Value::Node(index),
+            },
+            SteelVal::Boxed(b) => match self.reserve(Gc::as_ptr(b) as usize) {
+                Ok(index) => {
+                    let inner = self.encode(&b.borrow())?;
+                    self.nodes[index] = Some(Node::Boxed(inner));
+                    Value::Node(index)
+                }
+                Err(index) => Value::Node(index),
+            },
+            _ => return self.unsupported(value),
+        };
+
+        Ok(encoded)
+    }
+}
+
+// A value that could only be filled in once everything it refers to exists
+enum Pending<'a> {
+    HeapAllocated(HeapRef<SteelVal>, &'a Value),
+    MutableVector(HeapRef<Vec<SteelVal>>, &'a [Value]),
+    Boxed(Gc<RefCell<SteelVal>>, &'a Value),
+    Contract(Gc<ByteCodeLambda>, &'a Value),
+    StructType(StructTypeDescriptor, Option<usize>, &'a [(Value, Value)]),
+}
+
+pub(crate) struct HeapDecoder<'a> {
+    snapshot: &'a HeapSnapshot,
+    natives: Vec<SteelVal>,
+    nodes: Vec<Option<SteelVal>>,
+    struct_types: Vec<Option<StructTypeDescriptor>>,
+    bodies: Vec<Option<Rc<[DenseInstruction]>>>,
+    pending: Vec<Pending<'a>>,
+    heap: Heap,
+    // Parameter keys are given fresh ids, so that they can't collide with the keys of
+    // parameters made after restoring
+    parameter_keys: FxHashMap<usize, SteelVal>,
+}
+
+impl<'a> HeapDecoder<'a> {
+    /// `resolve` finds the native values that the snapshot refers to
+    pub(crate) fn new(
+        snapshot: &'a HeapSnapshot,
+        mut resolve: impl FnMut(&NativeRef) -> Result<SteelVal>,
+    ) -> Result<Self> {
+        let natives = snapshot
+            .natives
+            .iter()
+            .map(&mut resolve)
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            snapshot,
+            natives,
+            nodes: vec![None; snapshot.nodes.len()],
+            struct_types: vec![None; snapshot.struct_types.len()],
+            bodies: vec![None; snapshot.bodies.len()],
+            pending: Vec::new(),
+            heap: Heap::new(),
+            parameter_keys: FxHashMap::default(),
+        })
+    }
+
+    pub(crate) fn decode_thread(
+        &mut self,
+        snapshot: &ThreadSnapshot,
+        thread: &mut SteelThread,
+    ) -> Result<ConstantMap> {
+        thread.global_env.bindings_vec = snapshot
+            .globals
+            .iter()
+            .map(|x| self.decode(x))
+            .collect::<Result<_>>()?;
+
+        let constant_map = ConstantMap::from_vec(
+            snapshot
+                .constants
+                .iter()
+                .map(|x| self.decode(x))
+                .collect::<Result<_>>()?,
+        );
+
+        thread.constant_map = constant_map.clone();
+
+        let interner = &mut thread.function_interner;
+
+        interner.closure_interner = snapshot
+            .closure_interner
+            .iter()
+            .map(|(id, node)| Ok((*id, (*self.decode_closure(*node)?).clone())))
+            .collect::<Result<_>>()?;
+
+        interner.pure_function_interner = snapshot
+            .pure_function_interner
+            .iter()
+            .map(|(id, node)| Ok((*id, self.decode_closure(*node)?)))
+            .collect::<Result<_>>()?;
+
+        interner.spans = snapshot
+            .spans
+            .iter()
+            .map(|(id, spans)| (*id, spans.as_slice().into()))
+            .collect();
+
+        interner.instructions = snapshot
+            .instructions
+            .iter()
+            .map(|(id, body)| Ok((*id, self.body(*body)?)))
+            .collect::<Result<_>>()?;
+
+        thread.runtime_options.contracts_on = snapshot.contracts_on;
+        thread.runtime_options.test = snapshot.test;
+
+        Ok(constant_map)
+    }
+
+    /// Fills in the mutable cells and struct types, and returns the heap that
+    /// holds the cells
+    pub(crate) fn finish(mut self) -> Result<Heap> {
+        while let Some(pending) = self.pending.pop() {
+            match pending {
+                Pending::HeapAllocated(cell, value) => {
+                    cell.set_interior_mut(self.decode(value)?);
+                }
+                Pending::MutableVector(vector, values) => {
+                    vector.set_interior_mut(self.decode_all(values)?);
+                }
+                Pending::Boxed(boxed, value) => {
+                    *boxed.borrow_mut() = self.decode(value)?;
+                }
+                Pending::Contract(closure, contract) => {
+                    if let SteelVal::CustomStruct(contract) = self.decode(contract)? {
+                        closure.attach_contract_information(contract);
+                    }
+                }
+                Pending::StructType(descriptor, proc, properties) => {
+                    let properties = properties
+                        .iter()
+                        .map(|(key, value)| Ok((self.decode(key)?, self.decode(value)?)))
+                        .collect::<Result<_>>()?;
+
+                    VTable::set_entry(&descriptor, proc, Gc::new(properties));
+                }
+            }
+        }
+
+        for (native, entries) in &self.snapshot.function_docs {
+            if let SteelVal::Custom(c) = self.native(*native)? {
+                if let Some(table) = c
+                    .borrow_mut()
+                    .as_any_ref_mut()
+                    .downcast_mut::<LambdaMetadataTable>()
+                {
+                    for (id, doc) in entries {
+                        table.insert_closure_entry(*id, doc.as_str().into());
+                    }
+                }
+            }
+        }
+
+        Ok(self.heap)
+    }
+
+    fn native(&self, index: usize) -> Result<&SteelVal> {
+        self.natives
+            .get(index)
+            .ok_or_else(throw!(Generic => "malformed snapshot: missing native value {}", index))
+    }
+
+    fn body(&mut self, index: usize) -> Result<Rc<[DenseInstruction]>> {
+        let Some(instructions) = self.snapshot.bodies.get(index) else {
+            stop!(Generic => "malformed snapshot: missing function body {}", index)
+        };
+
+        if let Some(body) = &self.bodies[index] {
+            return Ok(Rc::clone(body));
+        }
+
+        let body: Rc<[DenseInstruction]> = instructions.as_slice().into();
+        self.bodies[index] = Some(Rc::clone(&body));
+
+        Ok(body)
+    }
+
+    fn decode_closure(&mut self, node: usize) -> Result<Gc<ByteCodeLambda>> {
+        match self.decode_node(node)? {
+            SteelVal::Closure(c) => Ok(c),
+            _ => stop!(Generic => "malformed snapshot: expected a function"),
+        }
+    }
+
+    fn struct_type(&mut self, index: usize) -> Result<StructTypeDescriptor> {
+        let snapshot = self.snapshot;
+
+        let Some(struct_type) = snapshot.struct_types.get(index) else {
+            stop!(Generic => "malformed snapshot: missing struct type {}", index)
+        };
+
+        if let Some(descriptor) = self.struct_types[index] {
+            return Ok(descriptor);
+        }
+
+        let descriptor = match struct_type {
+            StructType::Native(native) => match self.native(*native)? {
+                SteelVal::BoxedFunction(f) => match StructFunction::lookup(f) {
+                    Some((_, descriptor)) => descriptor,
+                    None => {
+                        stop!(Generic => "unable to restore the snapshot: {:?} is no longer a struct function", snapshot.natives[*native])
+                    }
+                },
+                _ => {
+                    stop!(Generic => "unable to restore the snapshot: {:?} is no longer a struct function", snapshot.natives[*native])
+                }
+            },
+            StructType::Declared {
+                name,
+                proc,
+                properties,
+            } => {
+                let descriptor = VTable::new_entry(name.as_str().into(), *proc);
+                self.pending
+                    .push(Pending::StructType(descriptor, *proc, properties));
+                descriptor
+            }
+        };
+
+        self.struct_types[index] = Some(descriptor);
+
+        Ok(descriptor)
+    }
+
+    fn decode_node(&mut self, index: usize) -> Result<SteelVal> {
+        let snapshot = self.snapshot;
+
+        let Some(node) = snapshot.nodes.get(index) else {
+            stop!(Generic => "malformed snapshot: missing node {}", index)
+        };
+
+        if let Some(value) = &self.nodes[index] {
+            return Ok(value.clone());
+        }
+
+        let value = match node {
+            Node::Closure {
+                id,
+                arity,
+                is_multi_arity,
+                signature,
+                body,
+                captures,
+                heap_allocated,
+                contract,
+            } => {
+                let captures = captures
+                    .iter()
+                    .map(|x| self.decode(x))
+                    .collect::<Result<_>>()?;
+
+                let heap_allocated = heap_allocated
+                    .iter()
+                    .map(|node| match self.decode_node(*node)? {
+                        SteelVal::HeapAllocated(cell) => Ok(cell),
+                        _ => stop!(Generic => "malformed snapshot: expected a heap cell"),
+                    })
+                    .collect::<Result<_>>()?;
+
+                let mut closure = ByteCodeLambda::new(
+                    *id,
+                    self.body(*body)?,
+                    *arity,
+                    *is_multi_arity,
+                    captures,
+                    heap_allocated,
+                );
+                closure.set_signature(signature.clone().map(Rc::new));
+
+                let closure = Gc::new(closure);
+
+                if let Some(contract) = contract {
+                    self.pending
+                        .push(Pending::Contract(closure.clone(), contract));
+                }
+
+                SteelVal::Closure(closure)
+            }
+            Node::Struct {
+                struct_type,
+                fields,
+            } => {
+                let descriptor = self.struct_type(*struct_type)?;
+                let fields = fields
+                    .iter()
+                    .map(|x| self.decode(x))
+                    .collect::<Result<Vec<_>>>()?;
+
+                SteelVal::CustomStruct(Gc::new(UserDefinedStruct::new(descriptor, &fields)))
+            }
+            Node::Stream {
+                initial_value,
+                thunk,
+                empty,
+            } => SteelVal::StreamV(Gc::new(LazyStream {
+                initial_value: self.decode(initial_value)?,
+                stream_thunk: self.decode(thunk)?,
+                empty_stream: *empty,
+            })),
+            Node::HeapAllocated(value) => {
+                let cell = self.heap.allocate_without_collection(SteelVal::Void);
+                self.pending
+                    .push(Pending::HeapAllocated(cell.clone(), value));
+                SteelVal::HeapAllocated(cell)
+            }
+            Node::MutableVector(values) => {
+                let vector = self.heap.allocate_vector_without_collection(Vec::new());
+                self.pending
+                    .push(Pending::MutableVector(vector.clone(), values));
+                SteelVal::MutableVector(vector)
+            }
+            Node::Boxed(value) => {
+                let boxed = Gc::new(RefCell::new(SteelVal::Void));
+                self.pending.push(Pending::Boxed(boxed.clone(), value));
+                SteelVal::Boxed(boxed)
+            }
+        };
+
+        self.nodes[index] = Some(value.clone());
+
+        Ok(value)
+    }
+
+    fn decode_all(&mut self, values: &[Value]) -> Result<Vec<SteelVal>> {
+        values.iter().map(|x| self.decode(x)).collect()
+    }
+
+    fn decode(&mut self, value: &Value) -> Result<SteelVal> {
+        let decoded = match value {
+            Value::Void => SteelVal::Void,
+            Value::Bool(b) => SteelVal::BoolV(*b),
+            Value::Int(i) => SteelVal::IntV(*i),
+            Value::Num(n) => SteelVal::NumV(*n),
+            Value::Rational(_, 0) => {
+                stop!(Generic => "malformed snapshot: rational with a zero denominator")
+            }
+            Value::Rational(numer, denom) => {
+                SteelVal::Rational(Rational32::new_raw(*numer, *denom))
+            }
+            Value::BigNum(n) => match n.parse::<BigInt>() {
+                Ok(n) => SteelVal::BigNum(Gc::new(n)),
+                Err(_) => stop!(Generic => "malformed snapshot: invalid integer: {}", n),
+            },
+            Value::BigRational(r) => match r.parse::<BigRational>() {
+                Ok(r) => SteelVal::BigRational(Gc::new(r)),
+                Err(_) => stop!(Generic => "malformed snapshot: invalid rational: {}", r),
+            },
+            Value::Complex(re, im) => SteelVal::Complex(Gc::new(SteelComplex::new(
+                self.decode(re)?,
+                self.decode(im)?,
+            ))),
+            Value::Char(c) => SteelVal::CharV(*c),
+            Value::String(s) => SteelVal::StringV(s.as_str().into()),
+            Value::Symbol(s) => SteelVal::SymbolV(s.as_str().into()),
+            Value::Keyword(s) => SteelVal::Keyword(s.as_str().into()),
+            Value::List(values) => SteelVal::ListV(List::from(self.decode_all(values)?)),
+            Value::Pair(car, cdr) => {
+                SteelVal::Pair(Gc::new(Pair::cons(self.decode(car)?, self.decode(cdr)?)))
+            }
+            Value::Vector(values) => SteelVal::VectorV(
+                Gc::new(
+                    self.decode_all(values)?
+                        .into_iter()
+                        .collect::<im_rc::Vector<_>>(),
+                )
+                .into(),
+            ),
+            Value::HashMap(pairs) => SteelVal::HashMapV(
+                Gc::new(
+                    pairs
+                        .iter()
+                        .map(|(key, value)| Ok((self.decode(key)?, self.decode(value)?)))
+                        .collect::<Result<im_rc::HashMap<_, _>>>()?,
+                )
+                .into(),
+            ),
+            Value::HashSet(values) => SteelVal::HashSetV(
+                Gc::new(
+                    self.decode_all(values)?
+                        .into_iter()
+                        .collect::<im_rc::HashSet<_>>(),
+                )
+                .into(),
+            ),
+            Value::Stdin => SteelVal::PortV(SteelPort::default_current_input_port()),
+            Value::Stdout => SteelVal::PortV(SteelPort::default_current_output_port()),
+            Value::Stderr => SteelVal::PortV(SteelPort::default_current_error_port()),
+            Value::ParameterKey(id) => match self.parameter_keys.get(id) {
+                Some(key) => key.clone(),
+                None => {
+                    let key = ParameterKey::fresh().into_steelval()?;
+                    self.parameter_keys.insert(*id, key.clone());
+                    key
+                }
+            },
+            Value::Node(index) => self.decode_node(*index)?,
+            Value::Native(index) => self.native(*index)?.clone(),
+            Value::StructType(index) => self.struct_type(*index)?.into_steelval()?,
+            Value::StructFunction {
+                function,
+                struct_type,
+            } => function.build(self.struct_type(*struct_type)?),
+        };
+
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heap(nodes: Vec<Node>) -> HeapSnapshot {
+        HeapSnapshot {
+            natives: Vec::new(),
+            nodes,
+            struct_types: Vec::new(),
+            bodies: Vec::new(),
+            function_docs: Vec::new(),
+        }
+    }
+
+    fn decode(snapshot: &HeapSnapshot, value: &Value) -> Result<SteelVal> {
+        let mut decoder = HeapDecoder::new(snapshot, |_| unreachable!())?;
+        let value = decoder.decode(value)?;
+        decoder.finish()?;
+        Ok(value)
+    }
+
+    #[test]
+    fn malformed_heaps_are_errors() {
+        let empty = heap(Vec::new());
+
+        for value in [
+            Value::Node(0),
+            Value::Native(0),
+            Value::StructType(0),
+            Value::Rational(1, 0),
+        ] {
+            let error = decode(&empty, &value).unwrap_err();
+            assert!(error.to_string().contains("malformed snapshot"), "{error}");
+        }
+
+        let dangling = heap(vec![
+            Node::HeapAllocated(Value::Node(5)),
+            Node::Closure {
+                id: 0,
+                arity: 0,
+                is_multi_arity: false,
+                signature: None,
+                body: 3,
+                captures: Vec::new(),
+                heap_allocated: Vec::new(),
+                contract: None,
+            },
+        ]);
+
+        for value in [Value::Node(0), Value::Node(1)] {
+            let error = decode(&dangling, &value).unwrap_err();
+            assert!(error.to_string().contains("malformed snapshot"), "{error}");
+        }
+    }
+}
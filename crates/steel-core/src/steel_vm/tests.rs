@@ -608,3 +608,464 @@ mod find_closest_match_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod coverage_tests {
+    use crate::steel_vm::engine::Engine;
+
+    #[test]
+    fn unexecuted_lines_are_reported_as_misses() {
+        let script = r#"(define (used x)
+  (+ x 1))
+
+(define (unused x)
+  (* x 2))
+
+(define (loop n)
+  (when (> n 0)
+    (used n)
+    (loop (- n 1))))
+
+(loop 10)
+"#;
+
+        // Paths are resolved against the file system, so the script needs to exist on disk
+        let path = std::env::temp_dir().join(format!("steel-coverage-{}.scm", std::process::id()));
+        std::fs::write(&path, script).unwrap();
+
+        let mut vm = Engine::new();
+        vm.with_coverage(true);
+        let result = vm.compile_and_run_raw_program_with_path(script, path.clone());
+        std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        let report = vm.coverage_report().unwrap();
+        assert_eq!(report.files.len(), 1);
+
+        let file = &report.files[0];
+        assert!(file.lines.get(&10).copied().unwrap_or_default() >= 10);
+        assert_eq!(file.lines.get(&5), Some(&0));
+        assert!(file.lines_hit() < file.lines_found());
+
+        let lcov = report.to_lcov();
+        assert!(lcov.starts_with(&format!("TN:\nSF:{}\n", file.path.display())));
+        assert!(lcov.contains("DA:5,0\n"));
+        assert!(lcov.ends_with("end_of_record\n"));
+    }
+
+    #[test]
+    fn coverage_is_off_by_default() {
+        let vm = Engine::new();
+        assert!(vm.coverage_report().is_none());
+    }
+}
+
+#[cfg(test)]
+mod reload_tests {
+    use crate::rvals::SteelVal;
+    use crate::steel_vm::engine::Engine;
+    use std::time::{Duration, SystemTime};
+
+    fn write_module(path: &std::path::Path, contents: &str, modified: SystemTime) {
+        std::fs::write(path, contents).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn reloading_a_module_rebinds_its_definitions() {
+        let dir = std::env::temp_dir().join(format!("steel-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let start = SystemTime::now() - Duration::from_secs(60);
+
+        write_module(
+            &dir.join("a.scm"),
+            r#"(provide greet) (define (greet) "hello")"#,
+            start,
+        );
+        write_module(
+            &dir.join("b.scm"),
+            r#"
+            (require "a.scm")
+            (provide greet-twice)
+            (define (greet-twice) (string-append (greet) (greet)))
+            "#,
+            start,
+        );
+
+        let main =
+            r#"(require "b.scm") (require (only-in "a.scm" (greet hello))) (define state 42)"#;
+        write_module(&dir.join("main.scm"), main, start);
+
+        let mut vm = Engine::new();
+        vm.compile_and_run_raw_program_with_path(main, dir.join("main.scm"))
+            .unwrap();
+
+        assert!(vm.reload_modified_modules().unwrap().is_empty());
+
+        write_module(
+            &dir.join("a.scm"),
+            r#"(provide greet) (define (greet) "bye")"#,
+            start + Duration::from_secs(10),
+        );
+
+        let reloaded = vm.reload_modified_modules();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let reloaded = reloaded.unwrap();
+
+        assert_eq!(reloaded.len(), 1);
+        assert!(reloaded[0].module.ends_with("a.scm"));
+        assert_eq!(reloaded[0].invalidated.len(), 1);
+        assert!(reloaded[0].invalidated[0].ends_with("b.scm"));
+
+        let result = vm
+            .compile_and_run_raw_program("(list (greet-twice) (hello) state)")
+            .unwrap();
+
+        assert_eq!(
+            result[0],
+            SteelVal::ListV(
+                vec![
+                    SteelVal::StringV("byebye".into()),
+                    SteelVal::StringV("bye".into()),
+                    SteelVal::IntV(42)
+                ]
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn failed_reloads_report_the_modules_already_reloaded() {
+        let dir = std::env::temp_dir().join(format!("steel-reload-error-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let start = SystemTime::now() - Duration::from_secs(60);
+
+        write_module(&dir.join("a.scm"), "(provide x) (define x 1)", start);
+        write_module(
+            &dir.join("b.scm"),
+            r#"(require "a.scm") (provide y) (define y (+ x 1))"#,
+            start,
+        );
+
+        let main = r#"(require "b.scm")"#;
+        write_module(&dir.join("main.scm"), main, start);
+
+        let mut vm = Engine::new();
+        vm.compile_and_run_raw_program_with_path(main, dir.join("main.scm"))
+            .unwrap();
+
+        let later = start + Duration::from_secs(10);
+        write_module(&dir.join("a.scm"), "(provide x) (define x 10)", later);
+        write_module(&dir.join("b.scm"), "(provide y) (define y", later);
+
+        let reloaded = vm.reload_modified_modules();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let error = reloaded.unwrap_err();
+
+        assert!(error.module.ends_with("b.scm"));
+        assert_eq!(error.reloaded.len(), 1);
+        assert!(error.reloaded[0].module.ends_with("a.scm"));
+    }
+
+    #[test]
+    fn reloading_an_unknown_module_is_an_error() {
+        let mut vm = Engine::new();
+        assert!(vm.reload_module("does-not-exist.scm").is_err());
+    }
+}
+
+#[cfg(test)]
+mod namespace_tests {
+    use crate::rvals::SteelVal;
+    use crate::steel_vm::builtin::BuiltInModule;
+    use crate::steel_vm::engine::Engine;
+
+    #[test]
+    fn namespaces_have_separate_globals() {
+        let mut vm = Engine::new();
+        vm.run("(define top-level-only 1)").unwrap();
+
+        let first = vm.create_namespace(&[]).unwrap();
+        let second = vm.create_namespace(&[]).unwrap();
+
+        vm.run_in_namespace(&first, "(define x 10)").unwrap();
+        vm.run_in_namespace(&second, "(define x 20) (define (get-x) x)")
+            .unwrap();
+
+        let result = vm
+            .run_in_namespace(&second, "(map (lambda (y) (+ y (get-x))) (list 1 2))")
+            .unwrap();
+        assert_eq!(
+            result,
+            vec![SteelVal::ListV(
+                vec![SteelVal::IntV(21), SteelVal::IntV(22)].into()
+            )]
+        );
+
+        assert_eq!(
+            vm.run_in_namespace(&first, "x").unwrap(),
+            vec![SteelVal::IntV(10)]
+        );
+
+        // Namespaces only start out with the prelude, not the engine's own definitions
+        assert!(vm.run_in_namespace(&first, "top-level-only").is_err());
+        assert!(vm.run("x").is_err());
+        assert_eq!(vm.extract::<isize>("top-level-only").unwrap(), 1);
+
+        vm.drop_namespace(first).unwrap();
+
+        assert_eq!(
+            vm.run_in_namespace(&second, "x").unwrap(),
+            vec![SteelVal::IntV(20)]
+        );
+    }
+
+    #[test]
+    fn namespaces_share_builtin_modules() {
+        let mut module = BuiltInModule::new("tenant/api");
+        module.register_value("answer", SteelVal::IntV(42));
+
+        let mut vm = Engine::new();
+        vm.register_module(module);
+
+        let namespace = vm.create_namespace(&["tenant/api"]).unwrap();
+
+        assert_eq!(
+            vm.run_in_namespace(&namespace, "answer").unwrap(),
+            vec![SteelVal::IntV(42)]
+        );
+        assert!(vm.run("answer").is_err());
+
+        assert!(vm.create_namespace(&["does/not/exist.scm"]).is_err());
+    }
+
+    #[test]
+    fn with_namespace_can_be_nested() {
+        let mut vm = Engine::new();
+        let first = vm.create_namespace(&[]).unwrap();
+        let second = vm.create_namespace(&[]).unwrap();
+
+        vm.run_in_namespace(&first, "(define name 'first)").unwrap();
+        vm.run_in_namespace(&second, "(define name 'second)")
+            .unwrap();
+
+        let names = vm
+            .with_namespace(&first, |vm| {
+                let outer = vm.extract_value("name").unwrap();
+                let inner = vm
+                    .with_namespace(&second, |vm| vm.extract_value("name").unwrap())
+                    .unwrap();
+                let again = vm
+                    .with_namespace(&first, |vm| vm.extract_value("name").unwrap())
+                    .unwrap();
+                (outer, inner, again, vm.extract_value("name").unwrap())
+            })
+            .unwrap();
+
+        assert_eq!(names.0, SteelVal::SymbolV("first".into()));
+        assert_eq!(names.1, SteelVal::SymbolV("second".into()));
+        assert_eq!(names.2, SteelVal::SymbolV("first".into()));
+        assert_eq!(names.3, SteelVal::SymbolV("first".into()));
+        assert!(vm.extract_value("name").is_err());
+    }
+
+    #[test]
+    fn namespaces_from_another_engine_are_rejected() {
+        let mut vm = Engine::new();
+        let mut other = Engine::new();
+
+        let namespace = vm.create_namespace(&[]).unwrap();
+        other.create_namespace(&[]).unwrap();
+
+        assert!(other.run_in_namespace(&namespace, "1").is_err());
+        assert!(other.with_namespace(&namespace, |_| ()).is_err());
+        assert!(other.drop_namespace(namespace).is_err());
+    }
+
+    #[test]
+    fn the_top_level_is_restored_after_a_panic() {
+        let mut vm = Engine::new();
+        let namespace = vm.create_namespace(&[]).unwrap();
+
+        vm.run("(define outside 1)").unwrap();
+        vm.run_in_namespace(&namespace, "(define inside 2)")
+            .unwrap();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vm.with_namespace(&namespace, |_| panic!("oops")).unwrap();
+        }));
+        assert!(result.is_err());
+
+        assert_eq!(vm.extract::<isize>("outside").unwrap(), 1);
+        assert!(vm.extract_value("inside").is_err());
+        assert_eq!(
+            vm.run_in_namespace(&namespace, "inside").unwrap(),
+            vec![SteelVal::IntV(2)]
+        );
+    }
+
+    #[test]
+    fn closures_cannot_be_returned_from_a_namespace() {
+        let mut vm = Engine::new();
+        let namespace = vm.create_namespace(&[]).unwrap();
+
+        vm.run_in_namespace(&namespace, "(define secret 42)")
+            .unwrap();
+
+        assert!(vm
+            .run_in_namespace(&namespace, "(lambda () secret)")
+            .is_err());
+        assert!(vm
+            .run_in_namespace(&namespace, "(list 1 (lambda () secret))")
+            .is_err());
+
+        // The definitions made before the closure was rejected are kept
+        assert_eq!(
+            vm.run_in_namespace(&namespace, "((lambda () secret))")
+                .unwrap(),
+            vec![SteelVal::IntV(42)]
+        );
+    }
+}
+
+#[cfg(test)]
+mod snapshot_tests {
+    use crate::rvals::SteelVal;
+    use crate::steel_vm::engine::Engine;
+    use crate::steel_vm::register_fn::RegisterFn;
+
+    #[test]
+    fn snapshot_restores_globals() {
+        let mut vm = Engine::new();
+
+        vm.run(
+            r#"
+            (struct point (x y) #:transparent)
+            (define origin (point 10 20))
+            (define (make-counter)
+              (define count 0)
+              (lambda () (set! count (+ count 1)) count))
+            (define counter (make-counter))
+            (counter)
+            (define table (hash 'a (list 1 2 3) 'b "two"))
+            "#,
+        )
+        .unwrap();
+
+        let snapshot = vm.snapshot().unwrap();
+        let mut restored = Engine::from_snapshot(&snapshot).unwrap();
+
+        assert_eq!(
+            restored
+                .run("(+ (point-x origin) (point-y origin))")
+                .unwrap(),
+            vec![SteelVal::IntV(30)]
+        );
+        assert_eq!(
+            restored.run("(point? origin)").unwrap(),
+            vec![SteelVal::BoolV(true)]
+        );
+        assert_eq!(restored.run("(counter)").unwrap(), vec![SteelVal::IntV(2)]);
+        assert_eq!(
+            restored.run("(hash-ref table 'b)").unwrap(),
+            vec![SteelVal::StringV("two".into())]
+        );
+        assert_eq!(
+            restored
+                .run("(map (lambda (x) (* x 2)) (range 0 3))")
+                .unwrap(),
+            vec![SteelVal::ListV(
+                vec![SteelVal::IntV(0), SteelVal::IntV(2), SteelVal::IntV(4)].into()
+            )]
+        );
+
+        // The kernel is restored as well, so new structs can still be declared
+        assert_eq!(
+            restored
+                .run("(struct segment (left right)) (segment-right (segment 1 2))")
+                .unwrap()
+                .last()
+                .cloned(),
+            Some(SteelVal::IntV(2))
+        );
+    }
+
+    #[test]
+    fn snapshot_keeps_parameters_apart() {
+        let mut vm = Engine::new();
+
+        vm.run(r#"(define location (make-parameter "here"))"#)
+            .unwrap();
+
+        let snapshot = vm.snapshot().unwrap();
+        let mut restored = Engine::from_snapshot(&snapshot).unwrap();
+
+        // Parameters made after restoring don't share bindings with the restored ones
+        assert_eq!(
+            restored
+                .run(
+                    r#"
+                    (define other (make-parameter 0))
+                    (parameterize ([other 1]) (location))
+                    "#
+                )
+                .unwrap()
+                .last()
+                .cloned(),
+            Some(SteelVal::StringV("here".into()))
+        );
+        assert_eq!(
+            restored
+                .run(r#"(parameterize ([location "there"]) (list (location) (other)))"#)
+                .unwrap(),
+            vec![SteelVal::ListV(
+                vec![SteelVal::StringV("there".into()), SteelVal::IntV(0)].into()
+            )]
+        );
+    }
+
+    #[test]
+    fn snapshot_relinks_registered_functions() {
+        let mut vm = Engine::new();
+        vm.register_fn("add-one", |x: isize| x + 1);
+        vm.run("(define (add-two x) (add-one (add-one x)))")
+            .unwrap();
+
+        let snapshot = vm.snapshot().unwrap();
+
+        assert!(Engine::from_snapshot(&snapshot).is_err());
+
+        let mut restored = Engine::from_snapshot_with(&snapshot, |vm| {
+            vm.register_fn("add-one", |x: isize| x + 1);
+        })
+        .unwrap();
+
+        assert_eq!(
+            restored.run("(add-two 1)").unwrap(),
+            vec![SteelVal::IntV(3)]
+        );
+    }
+
+    #[test]
+    fn snapshot_rejects_unsupported_values() {
+        let mut vm = Engine::new();
+        vm.run(r#"(define port (open-output-string))"#).unwrap();
+
+        let error = vm.snapshot().unwrap_err();
+        assert!(error.to_string().contains("`port`"));
+
+        // Only the default output port stands in for stdout
+        let mut vm = Engine::new();
+        vm.register_value("writer", SteelVal::new_dyn_writer_port(Vec::new()));
+
+        let error = vm.snapshot().unwrap_err();
+        assert!(error.to_string().contains("`writer`"), "{error}");
+    }
+}
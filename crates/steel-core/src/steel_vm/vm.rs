@@ -10,6 +10,8 @@ use crate::steel_vm::primitives::steel_unbox;
 use crate::steel_vm::primitives::steel_unbox_mutable;
 use crate::values::closed::Heap;
 use crate::values::functions::SerializedLambda;
+use crate::values::functions::{is_unsupplied, signature_from_constant, UNSUPPLIED_ARGUMENT};
+use crate::values::multiple_values::MultipleValues;
 use crate::values::structs::UserDefinedStruct;
 use crate::values::transducers::Reducer;
 use crate::{
@@ -27,16 +29,19 @@ use crate::{
 use crate::{
     env::Env,
     gc::Gc,
-    parser::span::Span,
+    parser::{ast::LambdaSignature, span::Span},
     rerrs::{ErrorKind, SteelErr},
     rvals::{Result, SteelVal},
     stop,
     values::functions::ByteCodeLambda,
 };
 use std::rc::Weak;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::{cell::RefCell, collections::HashMap, iter::Iterator, rc::Rc};
 
 use super::builtin::DocTemplate;
+use super::coverage::CoverageRecorder;
 
 use crate::values::lists::List;
 
@@ -52,7 +57,9 @@ use crate::rvals::{
     as_underlying_type, from_serializable_value, into_serializable_value, IntoSteelVal,
 };
 
+pub(crate) mod parameters;
 pub(crate) mod threads;
+use parameters::Parameterization;
 pub(crate) use threads::{spawn_thread, thread_join};
 
 #[inline]
@@ -154,6 +161,12 @@ pub struct StackFrame {
     // TODO: Delete this one!
     // continuation_mark: Option<MaybeContinuation>,
     weak_continuation_mark: Option<WeakContinuation>,
+
+    /// Whether returning from this frame returns to the producer of `call-with-values`,
+    /// which is the only place multiple values can be returned to. Set on the producer's
+    /// frame by `#%call-producer`, kept by tail calls since they reuse the frame, and
+    /// inherited by frames pushed by a tail call out of this one.
+    pub(crate) accepts_values: bool,
 }
 
 impl Eq for StackFrame {}
@@ -206,6 +219,7 @@ impl StackFrame {
             handler: None,
 
             weak_continuation_mark: None,
+            accepts_values: false,
             // spans,
             // span_id,
         }
@@ -240,6 +254,7 @@ impl StackFrame {
             handler: None,
 
             weak_continuation_mark: None,
+            accepts_values: false,
             // spans,
             // span_id,
         }
@@ -292,6 +307,40 @@ thread_local! {
     pub(crate) static DEFAULT_CONSTANT_MAP: ConstantMap = ConstantMap::new();
 }
 
+thread_local! {
+    // The VM that is currently running a native function on this thread, if any. Native functions
+    // that aren't handed a context (i.e. functions from dylibs) use this to call back into the VM.
+    static CURRENT_CONTEXT: std::cell::Cell<*mut ()> = const { std::cell::Cell::new(std::ptr::null_mut()) };
+}
+
+// Restores the previous context once the native function returns, even if it panics
+struct ContextGuard(*mut ());
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        CURRENT_CONTEXT.with(|x| x.set(self.0));
+    }
+}
+
+/// Runs `f` with the VM that is currently running a native function on this thread. Returns `None`
+/// if there isn't one, i.e. when called outside of a native function, or from another thread.
+pub(crate) fn with_current_context<R>(f: impl FnOnce(&mut VmCore<'_>) -> R) -> Option<R> {
+    // Take the context while it is in use, so that it can't be aliased. Native functions called
+    // from within `f` install it again.
+    let context = CURRENT_CONTEXT.with(|x| x.replace(std::ptr::null_mut()));
+
+    if context.is_null() {
+        return None;
+    }
+
+    let _guard = ContextGuard(context);
+
+    // SAFETY: The pointer was installed by `VmCore::enter_context`, which hands the VM over to the
+    // native function by giving up its own reference until the function returns. Native functions
+    // own their arguments, so nothing else points into the VM while it runs.
+    Some(f(unsafe { &mut *(context as *mut VmCore<'_>) }))
+}
+
 // Drain and move across the thread boundary, OR, enforce the restriction that only pure functions
 // can move into a new thread... that might be the easiest way?
 #[derive(Clone)]
@@ -299,7 +348,7 @@ pub struct SteelThread {
     pub(crate) global_env: Env,
     pub(crate) stack: Vec<SteelVal>,
     profiler: OpCodeOccurenceProfiler,
-    function_interner: FunctionInterner,
+    pub(crate) function_interner: FunctionInterner,
     super_instructions: Vec<Rc<DynamicBlock>>,
 
     pub(crate) heap: Heap,
@@ -309,6 +358,13 @@ pub struct SteelThread {
     pub(crate) current_frame: StackFrame,
     pub(crate) stack_frames: Vec<StackFrame>,
     pub(crate) constant_map: ConstantMap,
+    // Set from another thread to request that the currently running program stops
+    // at the next call or loop iteration.
+    pub(crate) interrupted: Arc<AtomicBool>,
+    // Records which instructions have been executed, when coverage is turned on
+    pub(crate) coverage: Option<CoverageRecorder>,
+    // The values of parameter objects, see `parameterize`
+    pub(crate) parameters: Parameterization,
 }
 
 #[derive(Clone)]
@@ -337,8 +393,8 @@ struct SpanId(usize);
 
 #[derive(Default, Clone)]
 pub struct FunctionInterner {
-    closure_interner: fxhash::FxHashMap<usize, ByteCodeLambda>,
-    pure_function_interner: fxhash::FxHashMap<usize, Gc<ByteCodeLambda>>,
+    pub(crate) closure_interner: fxhash::FxHashMap<usize, ByteCodeLambda>,
+    pub(crate) pure_function_interner: fxhash::FxHashMap<usize, Gc<ByteCodeLambda>>,
     // Functions will store a reference to a slot here, rather than any other way
     // getting the span can be super late bound then, and we don't need to worry about
     // cache misses nearly as much
@@ -349,10 +405,10 @@ pub struct FunctionInterner {
     // actually any references to this still in existence. Functions should probably hold a direct
     // reference to the existing thread in which it was created, and if passed in externally by
     // another run time, we can nuke it?
-    spans: fxhash::FxHashMap<usize, Rc<[Span]>>,
+    pub(crate) spans: fxhash::FxHashMap<usize, Rc<[Span]>>,
     // Keep these around - each thread keeps track of the instructions on the bytecode object, but we shouldn't
     // need to dereference that until later? When we actually move to that
-    instructions: fxhash::FxHashMap<usize, Rc<[DenseInstruction]>>,
+    pub(crate) instructions: fxhash::FxHashMap<usize, Rc<[DenseInstruction]>>,
 }
 
 impl SteelThread {
@@ -374,9 +430,19 @@ impl SteelThread {
             // we'll have each thread default to an empty constant map, and replace it with the map bundled
             // with the executables
             constant_map: DEFAULT_CONSTANT_MAP.with(|x| x.clone()),
+            interrupted: Arc::new(AtomicBool::new(false)),
+            coverage: None,
+            parameters: Parameterization::default(),
         }
     }
 
+    /// Returns the flag used to interrupt this thread. Storing `true` into it will cause the
+    /// currently executing program to stop with an error at the next function call or loop
+    /// iteration. The flag is cleared once the interrupt has been observed.
+    pub fn interrupt_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.interrupted)
+    }
+
     // If you want to explicitly turn off contracts, you can do so
     pub fn with_contracts(&mut self, contracts: bool) -> &mut Self {
         self.runtime_options.contracts_on = contracts;
@@ -545,6 +611,10 @@ impl SteelThread {
     ) -> Result<SteelVal> {
         self.profiler.reset();
 
+        if let Some(coverage) = self.coverage.as_mut() {
+            coverage.register_spans(&spans);
+        }
+
         #[cfg(feature = "profiling")]
         let execution_time = Instant::now();
 
@@ -564,6 +634,9 @@ impl SteelThread {
             // (let () (call-with-exception-handler (lambda (x) (displayln x)) (lambda () (+ 10 20 (error "oops!")))) (displayln "hi"))
 
             if let Err(e) = result {
+                // Interrupts are not catchable - unwind straight past any installed handlers
+                let interrupted = e.kind() == ErrorKind::Interrupted;
+
                 while let Some(mut last) = vm_instance.thread.stack_frames.pop() {
                     // Unwind the stack, close continuation marks here!
                     // vm_instance.close_continuation_marks(&last);
@@ -585,7 +658,7 @@ impl SteelThread {
                         vm_instance.close_continuation_marks(&last);
                     }
 
-                    if let Some(handler) = last.handler {
+                    if let Some(handler) = last.handler.clone().filter(|_| !interrupted) {
                         // Drop the stack BACK to where it was on this level
                         vm_instance.thread.stack.truncate(last.sp);
 
@@ -1166,7 +1239,10 @@ impl<'a> VmCore<'a> {
             value,
             self.thread.stack.iter(),
             self.thread.stack_frames.iter().map(|x| x.function.as_ref()),
-            self.thread.global_env.roots(),
+            self.thread
+                .global_env
+                .roots()
+                .chain(self.thread.parameters.roots()),
         );
 
         SteelVal::HeapAllocated(allocated_var)
@@ -1177,7 +1253,10 @@ impl<'a> VmCore<'a> {
             values,
             self.thread.stack.iter(),
             self.thread.stack_frames.iter().map(|x| x.function.as_ref()),
-            self.thread.global_env.roots(),
+            self.thread
+                .global_env
+                .roots()
+                .chain(self.thread.parameters.roots()),
         );
 
         SteelVal::MutableVector(allocated_var)
@@ -1189,7 +1268,10 @@ impl<'a> VmCore<'a> {
             None,
             self.thread.stack.iter(),
             self.thread.stack_frames.iter().map(|x| x.function.as_ref()),
-            self.thread.global_env.roots(),
+            self.thread
+                .global_env
+                .roots()
+                .chain(self.thread.parameters.roots()),
         );
     }
 
@@ -1407,7 +1489,8 @@ impl<'a> VmCore<'a> {
             }
             SteelVal::BoxedFunction(func) => {
                 let arg_vec = [arg];
-                func.func()(&arg_vec).map_err(|x| x.set_span_if_none(*cur_inst_span))
+                self.call_boxed_with_context(func.func(), &arg_vec)
+                    .map_err(|x| x.set_span_if_none(*cur_inst_span))
             }
             // SteelVal::ContractedFunction(cf) => {
             //     let arg_vec = vec![arg];
@@ -1442,7 +1525,8 @@ impl<'a> VmCore<'a> {
             }
             SteelVal::BoxedFunction(func) => {
                 let arg_vec = [arg1, arg2];
-                func.func()(&arg_vec).map_err(|x| x.set_span_if_none(*cur_inst_span))
+                self.call_boxed_with_context(func.func(), &arg_vec)
+                    .map_err(|x| x.set_span_if_none(*cur_inst_span))
             }
             // SteelVal::ContractedFunction(cf) => {
             //     let arg_vec = vec![arg1, arg2];
@@ -1476,7 +1560,8 @@ impl<'a> VmCore<'a> {
             }
             SteelVal::BoxedFunction(func) => {
                 let arg_vec: Vec<_> = args.into_iter().collect();
-                func.func()(&arg_vec).map_err(|x| x.set_span_if_none(*cur_inst_span))
+                self.call_boxed_with_context(func.func(), &arg_vec)
+                    .map_err(|x| x.set_span_if_none(*cur_inst_span))
             }
             // SteelVal::ContractedFunction(cf) => {
             //     let arg_vec: Vec<_> = args.into_iter().collect();
@@ -1692,6 +1777,13 @@ impl<'a> VmCore<'a> {
 
             // assert_eq!(self.spans.len(), self.instructions.len());
 
+            if unlikely(self.thread.coverage.is_some()) {
+                let span = self.current_span();
+                if let Some(coverage) = self.thread.coverage.as_mut() {
+                    coverage.hit(span);
+                }
+            }
+
             #[cfg(feature = "dynamic")]
             if let Some(pat) = self.thread.profiler.process_opcode(
                 &self.instructions[self.ip].op_code,
@@ -2264,6 +2356,7 @@ impl<'a> VmCore<'a> {
                     payload_size,
                     ..
                 } => {
+                    self.check_interrupt()?;
                     self.ip += 1;
                     let next_inst = self.instructions[self.ip];
                     self.handle_call_global(
@@ -2277,6 +2370,7 @@ impl<'a> VmCore<'a> {
                     payload_size,
                     ..
                 } => {
+                    self.check_interrupt()?;
                     // println!("calling global tail");
                     // crate::core::instructions::pretty_print_dense_instructions(&self.instructions);
                     let next_inst = self.instructions[self.ip + 1];
@@ -2295,6 +2389,7 @@ impl<'a> VmCore<'a> {
                     payload_size,
                     ..
                 } => {
+                    self.check_interrupt()?;
                     // TODO: @Matt -> don't pop the function off of the stack, just read it from there directly.
                     let func = self.thread.stack.pop().unwrap();
                     // println!("Calling: {}", func);
@@ -2309,6 +2404,7 @@ impl<'a> VmCore<'a> {
                     payload_size,
                     ..
                 } => {
+                    self.check_interrupt()?;
                     let func = self.thread.stack.pop().unwrap();
                     self.handle_tail_call(func, payload_size as usize)?
                 }
@@ -2329,7 +2425,8 @@ impl<'a> VmCore<'a> {
                     payload_size,
                     ..
                 } => {
-                    let current_arity = payload_size as usize;
+                    self.check_interrupt()?;
+                    let mut current_arity = payload_size as usize;
                     // This is the number of (local) functions we need to pop to get back to the place we want to be at
                     // let depth = self.instructions[self.ip + 1].payload_size as usize;
 
@@ -2338,6 +2435,20 @@ impl<'a> VmCore<'a> {
                     //     self.pop_count -= 1;
                     // }
 
+                    // Rest, optional and keyword arguments get matched up the same way as
+                    // for any other call
+                    let function = &self.thread.stack_frames.last().unwrap().function;
+
+                    if unlikely(function.is_multi_arity || function.signature.is_some()) {
+                        let function = Gc::clone(function);
+
+                        self.adjust_stack_for_multi_arity(
+                            &function,
+                            current_arity,
+                            &mut current_arity,
+                        )?;
+                    }
+
                     let last_stack_frame = self.thread.stack_frames.last().unwrap();
 
                     #[cfg(feature = "dynamic")]
@@ -2509,10 +2620,26 @@ impl<'a> VmCore<'a> {
         std::mem::replace(&mut self.thread.stack[offset], SteelVal::Void)
     }
 
+    // Checked on calls and loop back edges, so that any non terminating program
+    // will eventually observe the interrupt. The flag is consumed by the error it raises.
+    #[inline(always)]
+    fn check_interrupt(&self) -> Result<()> {
+        if unlikely(self.thread.interrupted.load(Ordering::Relaxed)) {
+            self.thread.interrupted.store(false, Ordering::Relaxed);
+            stop!(Interrupted => "evaluation interrupted"; self.current_span());
+        }
+
+        Ok(())
+    }
+
     // #[inline(always)]
     // TODO: This is definitely an issue - if the instruction stack is empty,
     // We will probably end up grabbing a garbage span
     fn current_span(&self) -> Span {
+        self.span_at(self.ip)
+    }
+
+    fn span_at(&self, ip: usize) -> Span {
         //// New way
         // self.thread
         //     .stack_frames
@@ -2535,9 +2662,9 @@ impl<'a> VmCore<'a> {
                     .function_interner
                     .spans
                     .get(&x)
-                    .and_then(|x| x.get(self.ip))
+                    .and_then(|x| x.get(ip))
             })
-            .or_else(|| self.root_spans.get(self.ip))
+            .or_else(|| self.root_spans.get(ip))
             .copied()
             .unwrap_or_default()
 
@@ -2821,7 +2948,8 @@ impl<'a> VmCore<'a> {
 
         self.ip += 1;
 
-        let is_multi_arity = self.instructions[self.ip].payload_size == 1;
+        let signature = self.instructions[self.ip].payload_size as usize;
+        let is_multi_arity = signature & 1 == 1;
 
         self.ip += 1;
 
@@ -2884,7 +3012,7 @@ impl<'a> VmCore<'a> {
             // snag the arity from the eclosure instruction
             let arity = self.instructions[forward_index - 1].payload_size;
 
-            let constructed_lambda = Gc::new(ByteCodeLambda::new(
+            let mut constructed_lambda = ByteCodeLambda::new(
                 closure_id,
                 closure_body,
                 arity as usize,
@@ -2892,7 +3020,11 @@ impl<'a> VmCore<'a> {
                 Vec::new(),
                 Vec::new(),
                 // Rc::clone(&spans),
-            ));
+            );
+
+            constructed_lambda.set_signature(self.closure_signature(signature));
+
+            let constructed_lambda = Gc::new(constructed_lambda);
 
             self.thread
                 .function_interner
@@ -2915,6 +3047,14 @@ impl<'a> VmCore<'a> {
         self.ip = forward_index;
     }
 
+    // Looks up the signature that the compiler pointed the closure at, if it has one. This only
+    // runs when the prototype of a closure gets built, copies of it share the signature
+    fn closure_signature(&self, payload: usize) -> Option<Rc<LambdaSignature>> {
+        let index = (payload >> 1).checked_sub(1)?;
+
+        signature_from_constant(&self.constants.get(index)).map(Rc::new)
+    }
+
     fn handle_new_start_closure(&mut self, offset: usize) -> Result<()> {
         // println!("Hitting start closure");
 
@@ -2928,7 +3068,8 @@ impl<'a> VmCore<'a> {
 
         self.ip += 1;
 
-        let is_multi_arity = self.instructions[self.ip].payload_size == 1;
+        let signature = self.instructions[self.ip].payload_size as usize;
+        let is_multi_arity = signature & 1 == 1;
 
         self.ip += 1;
 
@@ -3106,6 +3247,8 @@ impl<'a> VmCore<'a> {
                 Vec::new(),
             );
 
+            constructed_lambda.set_signature(self.closure_signature(signature));
+
             self.thread
                 .function_interner
                 .closure_interner
@@ -3216,10 +3359,14 @@ impl<'a> VmCore<'a> {
         payload_size: usize,
         new_arity: &mut usize,
     ) -> Result<()> {
-        if likely(!closure.is_multi_arity) {
+        if likely(!closure.is_multi_arity && closure.signature.is_none()) {
             if unlikely(closure.arity() != payload_size) {
                 stop!(ArityMismatch => format!("function expected {} arguments, found {}", closure.arity(), payload_size); self.current_span());
             }
+        } else if let Some(signature) = closure.signature() {
+            self.bind_signature_arguments(closure, signature, payload_size)?;
+
+            *new_arity = closure.arity();
         } else {
             // println!(
             //     "multi closure function, multi arity, arity: {:?}",
@@ -3259,6 +3406,119 @@ impl<'a> VmCore<'a> {
         Ok(())
     }
 
+    // Matches the arguments on top of the stack up with the arguments of a function that has
+    // optional or keyword arguments, or multiple clauses, leaving one value per argument
+    fn bind_signature_arguments(
+        &mut self,
+        closure: &ByteCodeLambda,
+        signature: &LambdaSignature,
+        payload_size: usize,
+    ) -> Result<()> {
+        let start = self.thread.stack.len() - payload_size;
+        let rest = closure.is_multi_arity;
+
+        match signature {
+            LambdaSignature::Parameters {
+                required,
+                optional,
+                keywords,
+            } => {
+                let positional_slots = required + optional;
+                let keyword_slots = start + positional_slots;
+
+                let arguments = self.thread.stack.split_off(start);
+
+                // Anything not passed is marked, for the body of the function to fill in
+                self.thread.stack.resize(
+                    keyword_slots + keywords.len(),
+                    UNSUPPLIED_ARGUMENT.with(|x| x.clone()),
+                );
+
+                let mut positional = 0;
+                let mut rest_arguments = Vec::new();
+                let mut arguments = arguments.into_iter();
+
+                while let Some(argument) = arguments.next() {
+                    let keyword = match &argument {
+                        SteelVal::Keyword(keyword) if !keywords.is_empty() => Some(keyword.clone()),
+                        _ => None,
+                    };
+
+                    let Some(keyword) = keyword else {
+                        if positional < positional_slots {
+                            self.thread.stack[start + positional] = argument;
+                        } else if rest {
+                            rest_arguments.push(argument);
+                        }
+
+                        positional += 1;
+                        continue;
+                    };
+
+                    let Some(index) = keywords
+                        .iter()
+                        .position(|(k, _)| k.resolve() == keyword.as_str())
+                    else {
+                        stop!(ArityMismatch => format!("function application has an unknown keyword argument: {keyword}"); self.current_span());
+                    };
+
+                    let Some(value) = arguments.next() else {
+                        stop!(ArityMismatch => format!("function application is missing a value for the keyword argument: {keyword}"); self.current_span());
+                    };
+
+                    if !is_unsupplied(&self.thread.stack[keyword_slots + index]) {
+                        stop!(ArityMismatch => format!("function application has the keyword argument {keyword} more than once"); self.current_span());
+                    }
+
+                    self.thread.stack[keyword_slots + index] = value;
+                }
+
+                if positional < *required || (positional > positional_slots && !rest) {
+                    stop!(ArityMismatch => format!("function expected {} arguments, found {}", expected_arguments(signature, rest), positional); self.current_span());
+                }
+
+                let missing = keywords.iter().enumerate().find(|(index, (_, required))| {
+                    *required && is_unsupplied(&self.thread.stack[keyword_slots + index])
+                });
+
+                if let Some((_, (keyword, _))) = missing {
+                    stop!(ArityMismatch => format!("function application missing required keyword argument: {keyword}"); self.current_span());
+                }
+
+                if rest {
+                    self.thread
+                        .stack
+                        .push(SteelVal::ListV(rest_arguments.into()));
+                }
+            }
+            LambdaSignature::Cases(clauses) => {
+                let Some(index) = clauses.iter().position(|(arity, rest)| {
+                    payload_size == *arity || (*rest && payload_size > *arity)
+                }) else {
+                    stop!(ArityMismatch => format!("function expected {} arguments, found {}", expected_arguments(signature, rest), payload_size); self.current_span());
+                };
+
+                let (arity, rest) = clauses[index];
+
+                if rest {
+                    let values = self.thread.stack.drain(start + arity..).collect();
+                    self.thread.stack.push(SteelVal::ListV(values));
+                }
+
+                // The body dispatches on the index of the clause, the arguments of the other
+                // clauses are padded out
+                self.thread
+                    .stack
+                    .insert(start, SteelVal::IntV(index as isize));
+                self.thread
+                    .stack
+                    .resize(start + closure.arity(), SteelVal::Void);
+            }
+        }
+
+        Ok(())
+    }
+
     #[inline(always)]
     fn cut_sequence(&mut self) {
         #[cfg(feature = "dynamic")]
@@ -3298,7 +3558,7 @@ impl<'a> VmCore<'a> {
             FuncV(f) => self.call_primitive_func(f, payload_size),
             MutFunc(f) => self.call_primitive_mut_func(f, payload_size),
             // ContractedFunction(cf) => self.call_contracted_function_tail_call(&cf, payload_size),
-            ContinuationFunction(cc) => self.call_continuation(cc),
+            ContinuationFunction(cc) => self.call_continuation(cc, payload_size),
             Closure(closure) => self.new_handle_tail_call_closure(closure, payload_size),
             BuiltIn(f) => self.call_builtin_func(f, payload_size),
             CustomStruct(s) => self.call_custom_struct(&s, payload_size),
@@ -3311,6 +3571,27 @@ impl<'a> VmCore<'a> {
         }
     }
 
+    /// Runs `f` with this VM installed as the current context, so that native code running within it
+    /// can call back into the VM, see [`with_current_context`]. `f` must not hold on to any
+    /// references into the VM.
+    #[inline(always)]
+    pub(crate) fn enter_context<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        // From here until `f` returns, the VM is only reached through this pointer
+        let context: *mut VmCore<'_> = self;
+        let previous = CURRENT_CONTEXT.with(|x| x.replace(context as *mut ()));
+        let _guard = ContextGuard(previous);
+        f()
+    }
+
+    #[inline(always)]
+    fn call_boxed_with_context(
+        &mut self,
+        func: &dyn Fn(&[SteelVal]) -> Result<SteelVal>,
+        args: &[SteelVal],
+    ) -> Result<SteelVal> {
+        self.enter_context(|| func(args))
+    }
+
     // #[inline(always)]
     fn call_boxed_func(
         &mut self,
@@ -3319,13 +3600,17 @@ impl<'a> VmCore<'a> {
     ) -> Result<()> {
         // println!("{:?}, {:?}", self.thread.stack, payload_size);
 
-        let last_index = self.thread.stack.len() - payload_size;
+        // The arguments are moved off of the stack, since the function may call back into the VM
+        let args = self
+            .thread
+            .stack
+            .drain(self.thread.stack.len() - payload_size..)
+            .collect::<SmallVec<[_; 4]>>();
 
-        let result = func(&self.thread.stack[last_index..])
+        let result = self
+            .call_boxed_with_context(func, &args)
             .map_err(|x| x.set_span_if_none(self.current_span()))?;
 
-        self.thread.stack.truncate(last_index);
-
         self.thread.stack.push(result);
         self.ip += 1;
         Ok(())
@@ -3403,6 +3688,7 @@ impl<'a> VmCore<'a> {
         // Note: We Advance the pointer here. In the event we're calling a builtin that fusses with
         // the instruction pointer, we allow the function to override this. For example, call/cc will
         // advance the pointer - or perhaps, even fuss with the control flow.
+        let call_site = self.ip;
         self.ip += 1;
 
         // TODO: Don't do this - just read directly from the stack
@@ -3415,10 +3701,11 @@ impl<'a> VmCore<'a> {
         let result = func(self, &args).map(|x| {
             x.map_err(|x| {
                 // TODO: @Matt 4/24/2022 -> combine this into one function probably
+                // The pointer has already moved past the call, so point at the call itself
                 if x.has_span() {
                     x
                 } else {
-                    x.set_span_if_none(self.current_span())
+                    x.set_span_if_none(self.span_at(call_site))
                 }
                 // x.set_span_if_none(self.current_span())
             })
@@ -3596,11 +3883,11 @@ impl<'a> VmCore<'a> {
     // #[inline(always)]
     // TODO: See if calling continuations can be implemented in terms of the core ABI
     // That way, we dont need a special "continuation" function
-    fn call_continuation(&mut self, continuation: Continuation) -> Result<()> {
-        let last =
-            self.thread.stack.pop().ok_or_else(
-                throw!(ArityMismatch => "continuation expected 1 argument, found none"),
-            )?;
+    fn call_continuation(&mut self, continuation: Continuation, payload_size: usize) -> Result<()> {
+        // Anything other than a single argument is delivered to the continuation
+        // as multiple values
+        let last_index = self.thread.stack.len() - payload_size;
+        let last = MultipleValues::pack(self.thread.stack.split_off(last_index));
 
         // println!("Calling continuation...");
 
@@ -3620,10 +3907,38 @@ impl<'a> VmCore<'a> {
 
         self.ip += 1;
 
+        if payload_size != 1 && !self.returns_to_producer() {
+            stop!(ArityMismatch => format!("continuation expected 1 argument, found {}", payload_size); self.current_span());
+        }
+
         self.thread.stack.push(last);
         Ok(())
     }
 
+    /// Whether the call that was just made returns its result to the producer of
+    /// `call-with-values`, the only continuation that accepts multiple values. That's
+    /// the case for tail calls out of a frame that returns to the producer, see
+    /// [`StackFrame::accepts_values`]. Anything else expects a single value.
+    fn returns_to_producer(&self) -> bool {
+        is_tail_call(&self.instructions, self.ip)
+            && self
+                .thread
+                .stack_frames
+                .last()
+                .is_some_and(|frame| frame.accepts_values)
+    }
+
+    /// A frame pushed by a tail call returns to wherever its caller returns, so if
+    /// the caller returns to the producer of `call-with-values`, the new frame does too.
+    #[inline(always)]
+    fn inherit_producer_return(&mut self) {
+        if let [.., caller, frame] = self.thread.stack_frames.as_mut_slice() {
+            if caller.accepts_values && is_tail_call(&frame.instructions, frame.ip) {
+                frame.accepts_values = true;
+            }
+        }
+    }
+
     // #[inline(always)]
     fn handle_lazy_closure(
         &mut self,
@@ -3721,10 +4036,10 @@ impl<'a> VmCore<'a> {
 
         match &stack_func {
             BoxedFunction(f) => {
-                self.thread.stack.push(
-                    f.func()(&[local, const_value])
-                        .map_err(|x| x.set_span_if_none(self.current_span()))?,
-                );
+                let result = self
+                    .call_boxed_with_context(f.func(), &[local, const_value])
+                    .map_err(|x| x.set_span_if_none(self.current_span()))?;
+                self.thread.stack.push(result);
                 self.ip += 4;
             }
             FuncV(f) => {
@@ -3849,6 +4164,8 @@ impl<'a> VmCore<'a> {
             ), // .with_span(self.current_span()),
         );
 
+        self.inherit_producer_return();
+
         // self.current_arity = Some(closure.arity());
 
         self.check_stack_overflow()?;
@@ -3921,6 +4238,8 @@ impl<'a> VmCore<'a> {
             StackFrame::new(self.sp, closure, self.ip + 1, instructions), // .with_span(self.current_span()),
         );
 
+        self.inherit_producer_return();
+
         // self.current_arity = Some(closure.arity());
 
         self.check_stack_overflow()?;
@@ -3973,6 +4292,8 @@ impl<'a> VmCore<'a> {
             ), // .with_span(self.current_span()),
         );
 
+        self.inherit_producer_return();
+
         // self.current_arity = Some(closure.arity());
 
         self.check_stack_overflow()?;
@@ -4048,7 +4369,7 @@ impl<'a> VmCore<'a> {
             MutFunc(f) => self.call_primitive_mut_func(*f, payload_size)?,
             FutureFunc(f) => self.call_future_func(f.clone(), payload_size)?,
             // ContractedFunction(cf) => self.call_contracted_function(&cf, payload_size)?,
-            ContinuationFunction(cc) => self.call_continuation(cc.clone())?,
+            ContinuationFunction(cc) => self.call_continuation(cc.clone(), payload_size)?,
             // #[cfg(feature = "jit")]
             // CompiledFunction(function) => self.call_compiled_function(function, payload_size)?,
             // Contract(c) => self.call_contract(&c, payload_size)?,
@@ -4082,7 +4403,7 @@ impl<'a> VmCore<'a> {
             MutFunc(f) => self.call_primitive_mut_func(f, payload_size),
             FutureFunc(f) => self.call_future_func(f, payload_size),
             // ContractedFunction(cf) => self.call_contracted_function(&cf, payload_size),
-            ContinuationFunction(cc) => self.call_continuation(cc),
+            ContinuationFunction(cc) => self.call_continuation(cc, payload_size),
             // Contract(c) => self.call_contract(&c, payload_size),
             BuiltIn(f) => {
                 // self.ip -= 1;
@@ -4110,7 +4431,7 @@ impl<'a> VmCore<'a> {
         self.ip += 1;
 
         match &stack_func {
-            BoxedFunction(f) => f.func()(args),
+            BoxedFunction(f) => self.call_boxed_with_context(f.func(), args),
             MutFunc(f) => f(args),
             FuncV(f) => f(args),
             FutureFunc(f) => Ok(SteelVal::FutureV(Gc::new(f(args)?))),
@@ -4137,7 +4458,8 @@ impl<'a> VmCore<'a> {
         match stack_func {
             BoxedFunction(f) => {
                 self.ip += 1;
-                self.thread.stack.push(f.func()(args)?)
+                let result = self.call_boxed_with_context(f.func(), args)?;
+                self.thread.stack.push(result)
             }
             MutFunc(f) => {
                 self.ip += 1;
@@ -4194,7 +4516,7 @@ impl<'a> VmCore<'a> {
             FutureFunc(f) => self.call_future_func(f, payload_size),
             MutFunc(f) => self.call_primitive_mut_func(f, payload_size),
             // ContractedFunction(cf) => self.call_contracted_function(&cf, payload_size),
-            ContinuationFunction(cc) => self.call_continuation(cc),
+            ContinuationFunction(cc) => self.call_continuation(cc, payload_size),
             Closure(closure) => self.handle_function_call_closure(closure, payload_size),
             // #[cfg(feature = "jit")]
             // CompiledFunction(function) => self.call_compiled_function(function, payload_size)?,
@@ -4286,6 +4608,8 @@ pub fn call_with_exception_handler(
                 .with_handler(handler),
             );
 
+            ctx.inherit_producer_return();
+
             // ctx.stack_index.push(ctx.stack.len());
 
             // Put the continuation as the argument
@@ -4387,6 +4711,8 @@ pub fn call_cc(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>>
                 .with_continuation_mark(continuation.clone()),
             );
 
+            ctx.inherit_producer_return();
+
             ctx.pop_count += 1;
 
             ctx.instructions = closure.body_exp();
@@ -4469,11 +4795,91 @@ pub(crate) fn environment_offset(ctx: &mut VmCore, args: &[SteelVal]) -> Option<
     Some(Ok(ctx.thread.global_env.len().into_steelval().unwrap()))
 }
 
+// Describes how many arguments a function with a signature takes, for arity errors
+fn expected_arguments(signature: &LambdaSignature, rest: bool) -> String {
+    match signature {
+        LambdaSignature::Parameters { required, .. } if rest => format!("at least {required}"),
+        LambdaSignature::Parameters {
+            required,
+            optional: 0,
+            ..
+        } => required.to_string(),
+        LambdaSignature::Parameters {
+            required, optional, ..
+        } => format!("between {} and {}", required, required + optional),
+        LambdaSignature::Cases(clauses) => {
+            let mut arities = clauses
+                .iter()
+                .map(|(arity, rest)| {
+                    if *rest {
+                        format!("at least {arity}")
+                    } else {
+                        arity.to_string()
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            match arities.pop() {
+                Some(last) if !arities.is_empty() => format!("{} or {}", arities.join(", "), last),
+                Some(last) => last,
+                None => "no".to_string(),
+            }
+        }
+    }
+}
+
 // TODO: This apply does not respect tail position
 // Something like this: (define (loop) (apply loop '()))
 // _should_ result in an infinite loop. In the current form, this is a Rust stack overflow.
 // Similarly, care should be taken to check out transduce, because nested calls to that will
 // result in a stack overflow with sufficient depth on the recursive calls
+/// `(values v ...)`. Anything other than a single value can only be returned to the
+/// producer of `call-with-values`, see [`call_producer`].
+pub(crate) fn values(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>> {
+    if args.len() != 1 && !ctx.returns_to_producer() {
+        builtin_stop!(ArityMismatch => "values: returned {} values to a continuation expecting a single value", args.len());
+    }
+
+    Some(Ok(MultipleValues::pack(args.to_vec())))
+}
+
+/// `(#%call-producer thunk)` - calls the producer of `call-with-values`, such that
+/// it can return multiple values with `values`. The result is unpacked with `#%values->list`.
+pub(crate) fn call_producer(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>> {
+    if args.len() != 1 {
+        builtin_stop!(ArityMismatch => "#%call-producer expects 1 argument, found {}", args.len());
+    }
+
+    match &args[0] {
+        SteelVal::Closure(closure) => {
+            // Roll back one level, the same as a regular call
+            ctx.ip -= 1;
+
+            if let Err(e) = ctx.handle_function_call_closure(closure.clone(), 0) {
+                return Some(Err(e));
+            }
+
+            if let Some(frame) = ctx.thread.stack_frames.last_mut() {
+                frame.accepts_values = true;
+            }
+
+            None
+        }
+        SteelVal::BuiltIn(f) if *f as usize == values as BuiltInSignature as usize => {
+            Some(Ok(MultipleValues::pack(Vec::new())))
+        }
+        producer => apply(ctx, &[producer.clone(), SteelVal::ListV(List::new())]),
+    }
+}
+
+/// Whether the instruction before `ip`, the call that returns to `ip`, is a tail call
+fn is_tail_call(instructions: &[DenseInstruction], ip: usize) -> bool {
+    matches!(
+        instructions.get(ip.wrapping_sub(1)).map(|x| x.op_code),
+        Some(OpCode::TAILCALL | OpCode::CALLGLOBALTAIL)
+    )
+}
+
 pub(crate) fn apply(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>> {
     // arity_check!(apply, args, 2);
 
@@ -4501,8 +4907,20 @@ pub(crate) fn apply(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelV
     let arg1 = arg_iter.next().unwrap();
     let arg2 = arg_iter.next().unwrap();
 
+    // Structs with a procedure property are applied through that procedure
+    if let SteelVal::CustomStruct(s) = arg1 {
+        if let Some(procedure) = s.maybe_proc() {
+            let procedure = match procedure {
+                SteelVal::HeapAllocated(h) => h.get(),
+                procedure => procedure.clone(),
+            };
+
+            return apply(ctx, &[procedure, arg2.clone()]);
+        }
+    }
+
     if let SteelVal::ListV(l) = arg2 {
-        if arg1.is_function() {
+        if arg1.is_function() || matches!(arg1, SteelVal::ContinuationFunction(_)) {
             // println!("Calling apply with args: {:?}, {:?}", arg1, arg2);
             // ctx.call_function_many_args(&arg1, l.clone())
 
@@ -4539,6 +4957,14 @@ pub(crate) fn apply(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelV
 
                     ctx.ip += 1;
 
+                    if l.len() != 1 && !ctx.returns_to_producer() {
+                        builtin_stop!(ArityMismatch => format!("continuation expected 1 argument, found {}", l.len()); ctx.current_span());
+                    }
+
+                    ctx.thread
+                        .stack
+                        .push(MultipleValues::pack(l.iter().cloned().collect()));
+
                     None
                     // ctx.stack.push(continuation);
                 }
@@ -4558,10 +4984,11 @@ pub(crate) fn apply(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelV
                     Some(result)
                 }
                 SteelVal::BoxedFunction(f) => {
-                    let mut args = l.into_iter().cloned().collect::<Vec<_>>();
+                    let args = l.into_iter().cloned().collect::<Vec<_>>();
 
-                    let result =
-                        f.func()(&args).map_err(|e| e.set_span_if_none(ctx.current_span()));
+                    let result = ctx
+                        .call_boxed_with_context(f.func(), &args)
+                        .map_err(|e| e.set_span_if_none(ctx.current_span()));
 
                     Some(result)
                 }
@@ -5837,7 +6264,10 @@ fn new_box_handler(ctx: &mut VmCore<'_>) -> Result<()> {
         last,
         ctx.thread.stack.iter(),
         ctx.thread.stack_frames.iter().map(|x| x.function.as_ref()),
-        ctx.thread.global_env.roots(),
+        ctx.thread
+            .global_env
+            .roots()
+            .chain(ctx.thread.parameters.roots()),
     );
 
     let result = SteelVal::HeapAllocated(allocated_var);
@@ -5963,7 +6393,10 @@ fn alloc_handler(ctx: &mut VmCore<'_>) -> Result<()> {
         ctx.thread.stack[offset].clone(), // TODO: Could actually move off of the stack entirely
         ctx.thread.stack.iter(),
         ctx.thread.stack_frames.iter().map(|x| x.function.as_ref()),
-        ctx.thread.global_env.roots(),
+        ctx.thread
+            .global_env
+            .roots()
+            .chain(ctx.thread.parameters.roots()),
     );
 
     ctx.thread
@@ -0,0 +1,216 @@
+//! The dynamic environment of a thread, which holds the values of parameter objects.
+//!
+//! A parameter object is made in `#%private/steel/control` as a procedure wrapping a
+//! [`ParameterKey`] and its initial value. Its current value is looked up here: first in the
+//! bindings installed by the innermost `parameterize`, then in the values set outside of any
+//! `parameterize`, and otherwise it is the initial value. `parameterize` installs its bindings
+//! with `dynamic-wind`, so escaping from or re-entering its body with a continuation, or
+//! unwinding out of it with an error, swaps them in and out as well.
+
+use std::sync::atomic::AtomicUsize;
+
+use fxhash::FxHashMap;
+
+use crate::rvals::{
+    as_underlying_type, cycles::BreadthFirstSearchSteelValVisitor, Custom, FromSteelVal,
+    SerializableSteelVal,
+};
+
+use super::*;
+
+static NEXT_PARAMETER_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Identifies a parameter object across threads - a thread spawned with
+/// `spawn-thread!` gets copies of the parent's parameter objects, and of the values
+/// they have at that point.
+pub(crate) struct ParameterKey(usize);
+
+impl ParameterKey {
+    pub(crate) fn fresh() -> Self {
+        ParameterKey(NEXT_PARAMETER_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub(crate) fn id(&self) -> usize {
+        self.0
+    }
+
+    fn id_of(value: &SteelVal) -> Option<usize> {
+        if let SteelVal::Custom(c) = value {
+            as_underlying_type::<ParameterKey>(c.borrow().as_ref()).map(|key| key.0)
+        } else {
+            None
+        }
+    }
+}
+
+impl Custom for ParameterKey {
+    fn fmt(&self) -> Option<std::result::Result<String, std::fmt::Error>> {
+        Some(Ok(format!("#<parameter-key {}>", self.0)))
+    }
+
+    fn into_serializable_steelval(&mut self) -> Option<SerializableSteelVal> {
+        Some(SerializableSteelVal::Custom(Box::new(ParameterKey(self.0))))
+    }
+
+    fn equality_hint(&self, other: &dyn crate::rvals::CustomType) -> bool {
+        as_underlying_type::<ParameterKey>(other)
+            .map(|other| self.0 == other.0)
+            .unwrap_or(false)
+    }
+}
+
+/// The bindings of the parameters for the extent of a `parameterize`. These are
+/// persistent, so that each `parameterize` can hold on to the bindings it installs and
+/// the ones it restores on the way out.
+#[derive(Clone, Default)]
+pub(crate) struct ParameterFrame(im_rc::HashMap<usize, SteelVal>);
+
+impl Custom for ParameterFrame {
+    fn fmt(&self) -> Option<std::result::Result<String, std::fmt::Error>> {
+        Some(Ok("#<parameterization>".to_string()))
+    }
+
+    fn gc_visit_children(&self, context: &mut crate::values::closed::MarkAndSweepContext) {
+        for value in self.0.values() {
+            context.push_back(value.clone());
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Parameterization {
+    frame: ParameterFrame,
+    // Values set outside of any `parameterize`
+    roots: FxHashMap<usize, SteelVal>,
+}
+
+impl Parameterization {
+    /// Starts out with the given values set for the parameters
+    pub(crate) fn with_values(values: impl IntoIterator<Item = (usize, SteelVal)>) -> Self {
+        Parameterization {
+            frame: ParameterFrame::default(),
+            roots: values.into_iter().collect(),
+        }
+    }
+
+    fn get(&self, id: usize) -> Option<&SteelVal> {
+        self.frame.0.get(&id).or_else(|| self.roots.get(&id))
+    }
+
+    fn set(&mut self, id: usize, value: SteelVal) {
+        if self.frame.0.contains_key(&id) {
+            self.frame.0.insert(id, value);
+        } else {
+            self.roots.insert(id, value);
+        }
+    }
+
+    /// The current value of every parameter that has been given one
+    pub(crate) fn values(&self) -> impl Iterator<Item = (usize, &SteelVal)> {
+        self.roots
+            .iter()
+            .filter(|(id, _)| !self.frame.0.contains_key(id))
+            .chain(self.frame.0.iter())
+            .map(|(id, value)| (*id, value))
+    }
+
+    pub(crate) fn roots(&self) -> impl Iterator<Item = &SteelVal> {
+        self.frame.0.values().chain(self.roots.values())
+    }
+}
+
+fn key_id(value: &SteelVal, name: &str) -> Result<usize> {
+    match ParameterKey::id_of(value) {
+        Some(id) => Ok(id),
+        None => stop!(TypeMismatch => "{} expects a parameter key, found: {}", name, value),
+    }
+}
+
+/// `(#%make-parameter-key)`
+pub(crate) fn make_parameter_key(args: &[SteelVal]) -> Result<SteelVal> {
+    if !args.is_empty() {
+        stop!(ArityMismatch => "#%make-parameter-key expects no arguments, found {}", args.len());
+    }
+
+    ParameterKey::fresh().into_steelval()
+}
+
+fn parameter_ref_result(ctx: &mut VmCore, args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 2 {
+        stop!(ArityMismatch => "#%parameter-ref expects 2 arguments, found {}", args.len());
+    }
+
+    let id = key_id(&args[0], "#%parameter-ref")?;
+
+    Ok(ctx
+        .thread
+        .parameters
+        .get(id)
+        .cloned()
+        .unwrap_or_else(|| args[1].clone()))
+}
+
+/// `(#%parameter-ref key initial-value)`
+pub(crate) fn parameter_ref(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>> {
+    Some(parameter_ref_result(ctx, args))
+}
+
+fn parameter_set_result(ctx: &mut VmCore, args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 2 {
+        stop!(ArityMismatch => "#%parameter-set! expects 2 arguments, found {}", args.len());
+    }
+
+    let id = key_id(&args[0], "#%parameter-set!")?;
+    ctx.thread.parameters.set(id, args[1].clone());
+
+    Ok(SteelVal::Void)
+}
+
+/// `(#%parameter-set! key value)`
+pub(crate) fn parameter_set(ctx: &mut VmCore, args: &[SteelVal]) -> Option<Result<SteelVal>> {
+    Some(parameter_set_result(ctx, args))
+}
+
+/// `(#%current-parameterization)`
+pub(crate) fn current_parameterization(
+    ctx: &mut VmCore,
+    args: &[SteelVal],
+) -> Option<Result<SteelVal>> {
+    if !args.is_empty() {
+        builtin_stop!(ArityMismatch => "#%current-parameterization expects no arguments, found {}", args.len());
+    }
+
+    Some(ctx.thread.parameters.frame.clone().into_steelval())
+}
+
+fn set_parameterization_result(ctx: &mut VmCore, args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "#%set-parameterization! expects 1 argument, found {}", args.len());
+    }
+
+    let frame = ParameterFrame::from_steelval(&args[0])?;
+    ctx.thread.parameters.frame = frame;
+
+    Ok(SteelVal::Void)
+}
+
+/// `(#%set-parameterization! parameterization)`
+pub(crate) fn set_parameterization(
+    ctx: &mut VmCore,
+    args: &[SteelVal],
+) -> Option<Result<SteelVal>> {
+    Some(set_parameterization_result(ctx, args))
+}
+
+/// `(#%extend-parameterization parameterization key value)` - a copy of the bindings with
+/// `key` bound to `value`
+pub(crate) fn extend_parameterization(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 3 {
+        stop!(ArityMismatch => "#%extend-parameterization expects 3 arguments, found {}", args.len());
+    }
+
+    let ParameterFrame(bindings) = ParameterFrame::from_steelval(&args[0])?;
+    let id = key_id(&args[1], "#%extend-parameterization")?;
+
+    ParameterFrame(bindings.update(id, args[2].clone())).into_steelval()
+}
@@ -67,6 +67,7 @@ pub fn closure_into_serializable(
             body_exp: prototype.body_exp,
             arity: prototype.arity,
             is_multi_arity: prototype.is_multi_arity,
+            signature: prototype.signature,
             captures: Vec::new(),
         };
 
@@ -90,6 +91,7 @@ pub fn closure_into_serializable(
 
             arity: c.arity,
             is_multi_arity: c.is_multi_arity,
+            signature: c.signature().cloned(),
         };
 
         CACHED_CLOSURES.with(|x| x.borrow_mut().insert(c.id, prototype.clone()));
@@ -99,6 +101,7 @@ pub fn closure_into_serializable(
             body_exp: prototype.body_exp,
             arity: prototype.arity,
             is_multi_arity: prototype.is_multi_arity,
+            signature: prototype.signature,
             captures: Vec::new(),
         };
 
@@ -118,6 +121,9 @@ struct MovableThread {
     global_env: Vec<SerializableSteelVal>,
     function_interner: MovableFunctionInterner,
     runtime_options: RunTimeOptions,
+    // The values the parameters have in the spawning thread. Values that can't be moved
+    // across threads are left out, so those parameters start at their initial values.
+    parameters: Vec<(usize, SerializableSteelVal)>,
 }
 
 struct MovableFunctionInterner {
@@ -259,6 +265,17 @@ fn spawn_thread_result(ctx: &mut VmCore, args: &[SteelVal]) -> Result<SteelVal>
         ),
 
         runtime_options: ctx.thread.runtime_options.clone(),
+
+        parameters: ctx
+            .thread
+            .parameters
+            .values()
+            .filter_map(|(id, value)| {
+                into_serializable_value(value.clone(), &mut initial_map, &mut visited)
+                    .ok()
+                    .map(|value| (id, value))
+            })
+            .collect(),
     };
 
     let sendable_vtable_entries = VTable::sendable_entries(&mut initial_map, &mut visited)?;
@@ -308,6 +325,8 @@ fn spawn_thread_result(ctx: &mut VmCore, args: &[SteelVal]) -> Result<SteelVal>
                     .into_iter()
                     .map(|x| from_serializable_value(&mut serializer, x))
                     .collect(),
+                parked: Default::default(),
+                namespace_base: None,
             }
         );
 
@@ -348,6 +367,13 @@ fn spawn_thread_result(ctx: &mut VmCore, args: &[SteelVal]) -> Result<SteelVal>
             }
         );
 
+        let parameters = Parameterization::with_values(
+            thread
+                .parameters
+                .into_iter()
+                .map(|(id, value)| (id, from_serializable_value(&mut serializer, value))),
+        );
+
         // Patch over the values in the final heap!
 
         time!("Patching over heap values", {
@@ -380,6 +406,9 @@ fn spawn_thread_result(ctx: &mut VmCore, args: &[SteelVal]) -> Result<SteelVal>
             current_frame: StackFrame::main(),
             stack_frames: Vec::with_capacity(32),
             constant_map,
+            interrupted: Arc::new(AtomicBool::new(false)),
+            coverage: None,
+            parameters,
         };
 
         #[cfg(feature = "profiling")]
@@ -0,0 +1,3 @@
+(require-builtin steel/regex)
+
+(regex "(")
@@ -58,6 +58,7 @@ test_harness_success! {
     calculator,
     capture_upvalue,
     capture_upvalues_arity_two,
+    case_lambda,
     close_upvalue,
     closure_value_capture,
     comma_quibbling,
@@ -71,7 +72,9 @@ test_harness_success! {
     docs,
     ellipses,
     empty,
+    exceptions,
     fib,
+    format,
     generator,
     generic_execution,
     generic_execution_dropping,
@@ -81,6 +84,7 @@ test_harness_success! {
     heap_sort,
     help,
     html_table,
+    keyword_arguments,
     letrec_mutual_recursion,
     letrec_simple_recursion,
     list_functions,
@@ -89,12 +93,19 @@ test_harness_success! {
     math,
     maxsubseq,
     merge_sort,
+    multiple_values,
+    mutable_strings,
     ncsubseq,
     numbers,
+    numeric_tower,
+    parameters,
     pascals,
     permutations,
+    promises,
     quicksort,
     read,
+    read_write,
+    regex,
     require_alias,
     require_only_in,
     require_prefix,
@@ -114,6 +125,7 @@ test_harness_success! {
     stack_struct,
     stack_test_with_contract,
     string_append,
+    strings_unicode,
     structs,
     // TODO: @Matt 11/11/2023
     threads,
@@ -128,6 +140,7 @@ test_harness_failure! {
     function_used_before_definition,
     global_env,
     identifier_used_before_definition,
+    invalid_regex,
     local_struct_inaccessible,
     require_only_in_missing_identifier,
 }
@@ -0,0 +1,49 @@
+(define (error-message thunk)
+  (guard (e [(error-object? e) (error-object-message e)]) (thunk)))
+
+;; The first clause that accepts the number of arguments is used
+(define describe
+  (case-lambda
+    [() 'none]
+    [(x) (list 'one x)]
+    [(x y) (list 'two x y)]
+    [(x y . rest) (list 'many x y rest)]))
+
+(assert! (equal? (describe) 'none))
+(assert! (equal? (describe 1) '(one 1)))
+(assert! (equal? (describe 1 2) '(two 1 2)))
+(assert! (equal? (describe 1 2 3 4) '(many 1 2 (3 4))))
+(assert! (equal? (apply describe '(1 2 3)) '(many 1 2 (3))))
+
+;; A clause can take every argument as a list
+(define all-args (case-lambda [args args]))
+(assert! (equal? (all-args) '()))
+(assert! (equal? (all-args 1 2) '(1 2)))
+
+;; Clauses can call each other in tail position without growing the stack
+(define sum-to
+  (case-lambda
+    [(n) (sum-to n 0)]
+    [(n acc) (if (= n 0) acc (sum-to (- n 1) (+ acc n)))]))
+
+(assert! (equal? (sum-to 100000) 5000050000))
+
+;; Clauses capture variables from the surrounding scope
+(define (make-counter start)
+  (define count start)
+  (case-lambda
+    [() count]
+    [(n) (set! count (+ count n)) count]))
+
+(define counter (make-counter 10))
+(counter 5)
+(assert! (equal? (counter) 15))
+
+;; Arity introspection lists the arity of each clause
+(assert! (equal? (arity? describe) '(0 1 2 2)))
+(assert! (multi-arity? describe))
+
+(define single (case-lambda [(x) x]))
+(assert! (equal? (error-message (lambda () (single 1 2))) "function expected 1 arguments, found 2"))
+(assert! (equal? (error-message (lambda () ((case-lambda [(x) x] [(x y z . rest) x]) 1 2)))
+                 "function expected 1 or at least 3 arguments, found 2"))
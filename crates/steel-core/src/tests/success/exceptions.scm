@@ -0,0 +1,90 @@
+;; Any object can be raised
+(assert! (equal? '(sym boom) (guard (e [(symbol? e) (list 'sym e)]) (raise 'boom))))
+(assert! (equal? '(else 42) (guard (e [(string? e) 'str] [else (list 'else e)]) (raise 42))))
+
+;; `=>` clauses and test-only clauses
+(assert! (equal? 42 (guard (e [(assoc 'a e) => cdr] [(assoc 'b e)]) (raise (list (cons 'a 42))))))
+(assert! (equal? (cons 'b 23) (guard (e [(assoc 'a e) => cdr] [(assoc 'b e)]) (raise (list (cons 'b 23))))))
+
+;; The clauses don't capture variables from the surrounding scope
+(define value 'outer-value)
+(assert! (equal? 'outer-value (guard (e [(assoc 'a e) => cdr] [#t value]) (raise '()))))
+
+;; Errors from `error` and from primitives are error objects
+(assert! (equal? '("bad thing" (1 2))
+                 (guard (e [(error-object? e) (list (error-object-message e) (error-object-irritants e))])
+                   (error "bad thing" 1 2))))
+
+(assert! (guard (e [(error-object? e) (string? (error-object-message e))]) (car '())))
+(assert! (not (error-object? 'boom)))
+
+(assert! (equal? 'file (guard (e [(file-error? e) 'file]) (open-input-file "/nonexistent/file"))))
+(assert! (equal? 'read (guard (e [(read-error? e) 'read]) (read! "(1 2"))))
+
+;; Unmatched conditions go to the enclosing handlers
+(assert! (equal? '(outer sym) (guard (e [#t (list 'outer e)]) (guard (e [(string? e) 'inner]) (raise 'sym)))))
+
+;; raise-continuable returns the value of the handler
+(assert! (equal? 43 (with-exception-handler (lambda (c) 42) (lambda () (+ (raise-continuable 'oops) 1)))))
+
+;; The innermost handler wins, even when it is a guard
+(assert! (equal? 'guarded
+                 (with-exception-handler (lambda (e) 10)
+                                         (lambda ()
+                                           (guard (e [(symbol? e) 'guarded])
+                                             (+ 1 (raise-continuable 'x)))))))
+
+;; Handlers run with the outer handlers installed
+(assert! (equal? '(outer (wrapped x))
+                 (guard (e [#t (list 'outer e)])
+                   (with-exception-handler (lambda (e) (raise (list 'wrapped e)))
+                                           (lambda () (raise-continuable 'x))))))
+
+(assert! (equal? 'outer
+                 (guard (e [(error-object? e) 'outer])
+                   (with-exception-handler (lambda (e) (car '())) (lambda () (raise-continuable 'x))))))
+
+;; Escaping from a handler
+(assert! (equal? '(caught boom)
+                 (call/cc (lambda (k)
+                            (with-exception-handler (lambda (e) (k (list 'caught e)))
+                                                    (lambda () (raise 'boom)))))))
+
+;; Returning from a handler for `raise` is an error
+(assert! (error-object? (guard (e [#t e])
+                          (with-exception-handler (lambda (e) 'ignored) (lambda () (raise 'x))))))
+
+;; Custom conditions
+(define-condition-type http-error (status))
+
+(assert! (equal? '(404 #true "not found" (x))
+                 (guard (e [(http-error? e)
+                            (list (http-error-status e)
+                                  (error-object? e)
+                                  (error-object-message e)
+                                  (error-object-irritants e))])
+                   (raise (http-error "not found" '(x) 404)))))
+
+;; dynamic-wind still unwinds
+(define unwound #f)
+(guard (e [#t e])
+  (dynamic-wind (lambda () void) (lambda () (raise 'x)) (lambda () (set! unwound #t))))
+(assert! unwound)
+
+;; Handlers for `raise` run in the dynamic environment of the `raise`
+(define current-tag (make-parameter 'outer))
+(define wound-out #f)
+
+(assert! (equal? '(inner #f)
+                 (call/cc (lambda (k)
+                            (with-exception-handler
+                             (lambda (e) (k (list (current-tag) wound-out)))
+                             (lambda ()
+                               (dynamic-wind (lambda () void)
+                                             (lambda ()
+                                               (parameterize ([current-tag 'inner])
+                                                 (raise 'x)))
+                                             (lambda () (set! wound-out #t)))))))))
+(assert! wound-out)
+
+(assert! (error-object? (guard (e [#t e]) (#%error-handler-depth))))
@@ -0,0 +1,39 @@
+(assert! (equal? (format "~a and ~s" "apples" "oranges") "apples and \"oranges\""))
+(assert! (equal? (format "~a ~s" (list "a" #\b 'c) (list "a" #\b 'c)) "(a b c) (\"a\" #\\b c)"))
+(assert! (equal? (format "~v" (list 1 2)) "'(1 2)"))
+(assert! (equal? (format "~~ ~%") "~ \n"))
+
+;; Padding and alignment
+(assert! (equal? (format "~5a|~>5a|~^5a|" 'ab 'ab 'ab) "ab   |   ab| ab  |"))
+(assert! (equal? (format "~'*6a|" "hi") "hi****|"))
+(assert! (equal? (format "~.3a" "abcdef") "abc"))
+(assert! (equal? (format "~3a" "longer") "longer"))
+
+;; Numbers
+(assert! (equal? (format "~r ~5r ~<5r|" 42 42 42) "42    42 42   |"))
+(assert! (equal? (format "~.2r" 3.14159) "3.14"))
+(assert! (equal? (format "~08.3r" -2.5) "-002.500"))
+(assert! (equal? (format "~x ~o ~b ~#36r" 255 8 5 35) "ff 10 101 z"))
+(assert! (equal? (format "~r" 1/3) "1/3"))
+
+;; Columns
+(define (row key value)
+  (format "~a~10t~a" key value))
+
+(assert! (equal? (row "name" "steel") "name      steel"))
+(assert! (equal? (row "a-long-key" 1) "a-long-key 1"))
+(assert! (equal? (format "ab~%cd~4tx") "ab\ncd  x"))
+
+;; ~e cuts off long values
+(assert! (equal? (string-length (format "~e" (make-string 300 #\a))) 259))
+
+;; Writing to ports
+(define port (open-output-string))
+(fprintf port "~a: ~5r" "total" 42)
+(assert! (equal? (get-output-string port) "total:    42"))
+
+(assert! (equal? (with-output-to-string (lambda () (printf "~a-~a" 1 2))) "1-2"))
+
+;; Widths and precisions are bounded
+(assert! (error-object? (guard (e [#t e]) (format "~99999999999999999999999a" 1))))
+(assert! (error-object? (guard (e [#t e]) (format "~1000000000a" 1))))
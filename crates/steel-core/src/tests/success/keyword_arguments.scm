@@ -0,0 +1,98 @@
+(define (error-message thunk)
+  (guard (e [(error-object? e) (error-object-message e)]) (thunk)))
+
+;; Optional arguments fill in from the left, and their defaults can refer to the arguments before them
+(define (range-list start [end (+ start 3)] [step 1])
+  (let loop ([i start] [acc '()])
+    (if (>= i end) (reverse acc) (loop (+ i step) (cons i acc)))))
+
+(assert! (equal? (range-list 2) '(2 3 4)))
+(assert! (equal? (range-list 2 6) '(2 3 4 5)))
+(assert! (equal? (range-list 0 10 3) '(0 3 6 9)))
+
+;; Keyword arguments can be passed in any order, after the positional arguments
+(define (make-point x #:y y #:z [z (* 2 y)])
+  (list x y z))
+
+(assert! (equal? (make-point 1 #:y 2) '(1 2 4)))
+(assert! (equal? (make-point 1 #:z 5 #:y 2) '(1 2 5)))
+
+;; Defaults are only evaluated when the argument is left out
+(define evaluated 0)
+(define (counted #:value [value (begin (set! evaluated (+ evaluated 1)) 'default)])
+  value)
+
+(assert! (equal? (counted #:value 'given) 'given))
+(assert! (equal? evaluated 0))
+(assert! (equal? (counted) 'default))
+(assert! (equal? evaluated 1))
+
+;; Keywords can be passed through apply
+(assert! (equal? (apply make-point (list 1 #:y 3)) '(1 3 6)))
+(assert! (equal? (apply range-list '(1 3)) '(1 2)))
+
+;; Only keywords written out in the code are keywords, quoted ones are passed along as data
+(define (tag-with value #:tag [tag #f])
+  (list value tag))
+
+(assert! (equal? (tag-with '#:tag #:tag 'v) '(#:tag v)))
+(assert! (equal? (tag-with (string->symbol "#:tag")) '(#:tag #f)))
+(assert! (equal? (apply tag-with '(#:tag)) '(#:tag #f)))
+
+;; Keywords are values of their own rather than symbols
+(define tag-keyword (car (list #:tag)))
+(assert! (not (symbol? tag-keyword)))
+(assert! (not (equal? tag-keyword '#:tag)))
+(assert! (equal? tag-keyword (cadr (list 1 #:tag))))
+
+;; Keywords mixed with a rest argument
+(define (tagged tag #:separator [separator ", "] . items)
+  (list tag separator items))
+
+(assert! (equal? (tagged 'a 1 2) '(a ", " (1 2))))
+(assert! (equal? (tagged 'a #:separator "-" 1 2) '(a "-" (1 2))))
+
+;; Self tail calls bind the arguments the same way
+(define (collect n #:acc [acc '()])
+  (if (= n 0) acc (collect (- n 1) #:acc (cons n acc))))
+
+(assert! (equal? (collect 100000 #:acc '()) (collect 100000)))
+(assert! (equal? (collect 3) '(1 2 3)))
+
+(define (countdown n . seen)
+  (if (= n 0) seen (countdown (- n 1) n)))
+
+(assert! (equal? (countdown 3) '(1)))
+
+;; Anonymous functions take optional arguments too
+(assert! (equal? ((lambda ([x 5]) x)) 5))
+(assert! (equal? ((lambda ([x 5]) x) 6) 6))
+
+;; Arity introspection gives the range of positional arguments and the keywords
+(define (mixed a [b 10] #:c [c 3] #:d d)
+  (list a b c d))
+
+(assert! (equal? (arity? mixed)
+                 (hash 'required 1
+                       'optional 1
+                       'rest? #f
+                       'keywords (list #:c #:d)
+                       'required-keywords (list #:d))))
+(assert! (equal? (hash-ref (arity? make-point) 'required-keywords) (list #:y)))
+(assert! (hash-ref (arity? tagged) 'rest?))
+(assert! (equal? (arity? error-message) 1))
+(assert! (multi-arity? mixed))
+(assert! (multi-arity? range-list))
+(assert! (not (multi-arity? error-message)))
+
+;; Errors name the keyword at fault
+(assert! (equal? (error-message (lambda () (make-point 1)))
+                 "function application missing required keyword argument: #:y"))
+(assert! (equal? (error-message (lambda () (make-point 1 #:y 2 #:w 3)))
+                 "function application has an unknown keyword argument: #:w"))
+(assert! (equal? (error-message (lambda () (make-point 1 #:y)))
+                 "function application is missing a value for the keyword argument: #:y"))
+(assert! (equal? (error-message (lambda () (make-point 1 #:y 2 #:y 3)))
+                 "function application has the keyword argument #:y more than once"))
+(assert! (equal? (error-message (lambda () (range-list)))
+                 "function expected between 1 and 3 arguments, found 0"))
@@ -0,0 +1,77 @@
+;; A single value is not wrapped
+(assert! (equal? 10 (values 10)))
+(assert! (equal? 10 (call-with-values (lambda () 5) (lambda (x) (* x 2)))))
+
+;; A list is one value, not many
+(assert! (equal? '((1 2)) (call-with-values (lambda () (values '(1 2))) list)))
+(assert! (equal? '(1 2) (call-with-values (lambda () (values 1 2)) list)))
+(assert! (equal? '() (call-with-values (lambda () (values)) list)))
+(assert! (equal? 3 (call-with-values (lambda () (values 1 2)) +)))
+
+(define-values (a b . c) (values 1 2 3 4))
+(assert! (equal? '(1 2 (3 4)) (list a b c)))
+
+(define-values (single) (values '(1 2)))
+(assert! (equal? '(1 2) single))
+
+(define-values all (values 1 2))
+(assert! (equal? '(1 2) all))
+
+(define (local-define-values)
+  (define-values (p q) (values 10 20))
+  (define-values (r s) (values 1 2))
+  (list p q r s))
+
+(assert! (equal? '(10 20 1 2) (local-define-values)))
+
+;; let-values evaluates every expression before binding anything
+(define x 'outer)
+(assert! (equal? '(1 2 outer (7 8))
+                 (let-values ([(x y) (values 1 2)] [(z) (values x)] [rest (values 7 8)])
+                   (list x y z rest))))
+
+(assert! (equal? '(1 2 1)
+                 (let*-values ([(x y) (values 1 2)] [(z) (values x)])
+                   (list x y z))))
+
+(assert! (equal? '(1 (2 3)) (receive (head . tail) (values 1 2 3) (list head tail))))
+
+;; Continuations accept multiple values
+(assert! (equal? '(1 2) (call-with-values (lambda () (call/cc (lambda (k) (k 1 2)))) list)))
+(assert! (equal? '(3 4) (call-with-values (lambda () (call/cc (lambda (k) (apply k '(3 4))))) list)))
+(assert! (equal? '() (call-with-values (lambda () (call/cc (lambda (k) (k)))) list)))
+
+;; The consumer is called in tail position
+(define (loop n)
+  (if (= n 0)
+      'done
+      (call-with-values (lambda () (values (- n 1) 0)) (lambda (m _) (loop m)))))
+
+(assert! (equal? 'done (loop 100000)))
+
+;; Multiple values are only returned to the producer of call-with-values, through any
+;; number of tail calls, and everything else expects a single value
+(define (values-rejected? thunk)
+  (call-with-exception-handler (lambda (err) #t)
+                               (lambda ()
+                                 (thunk)
+                                 #f)))
+
+(assert! (values-rejected? (lambda () (list (values 1 2)))))
+(assert! (values-rejected? (lambda () (equal? (values) (values)))))
+(assert! (values-rejected? (lambda () (let ([x (values 1 2)]) x))))
+(assert! (values-rejected? (lambda () (+ 1 (call/cc (lambda (k) (k 1 2)))))))
+(assert! (values-rejected? (lambda () (list (truncate/ 5 2)))))
+
+(define (two-values)
+  (values 5 6))
+
+(assert! (values-rejected?
+          (lambda () (call-with-values (lambda () (let ([r (two-values)]) r)) list))))
+(assert! (equal? '(5 6) (call-with-values (lambda () (let ([x 1]) (two-values))) list)))
+(assert! (equal? '(5 6) (call-with-values (lambda () (if #t (two-values) 0)) list)))
+(assert! (equal? '(7 8)
+                 (call-with-values (lambda ()
+                                     (dynamic-wind (lambda () #t) (lambda () (values 7 8)) (lambda () #t)))
+                                   list)))
+(assert! (equal? '() (call-with-values values list)))
@@ -0,0 +1,66 @@
+(define s (string->mutable-string "hello"))
+
+(assert! (mutable-string? s))
+(assert! (not (mutable-string? "hello")))
+(assert! (equal? (mutable-string-length s) 5))
+
+(string-set! s 0 #\j)
+(assert! (equal? (mutable-string-ref s 0) #\j))
+(assert! (equal? (mutable-string->string s) "jello"))
+(assert! (equal? (mutable-string->string s 1 3) "el"))
+
+;; Characters are indexed as characters, not bytes
+(define greek (string->mutable-string "λλλ"))
+(string-set! greek 1 #\x)
+(assert! (equal? (mutable-string->string greek) "λxλ"))
+
+(string-fill! s #\z 3)
+(assert! (equal? (mutable-string->string s) "jelzz"))
+
+(string-copy! s 1 "ipp")
+(assert! (equal? (mutable-string->string s) "jippz"))
+
+;; Copying a string onto itself
+(string-copy! s 0 s 1 3)
+(assert! (equal? (mutable-string->string s) "ipppz"))
+
+;; Building up a string
+(define builder (make-mutable-string 0))
+(let loop ([i 0])
+  (when (< i 1000)
+    (string-append! builder (number->string i) #\,)
+    (loop (+ i 1))))
+
+(assert! (equal? (mutable-string-length builder) 3890))
+(assert! (equal? (mutable-string->string builder 0 8) "0,1,2,3,"))
+
+(define self-append (string->mutable-string "ab"))
+(string-append! self-append self-append)
+(assert! (equal? (mutable-string->string self-append) "abab"))
+
+;; Equality compares the contents
+(assert! (equal? (string->mutable-string "abc") (string->mutable-string "abc")))
+(assert! (not (equal? (string->mutable-string "abc") (string->mutable-string "abd"))))
+
+;; Mutable strings display as their contents, so they can be written to ports
+(assert! (equal? (with-output-to-string (lambda () (display builder))) (mutable-string->string builder)))
+(assert! (equal? (mutable-string->string (make-mutable-string 3 #\x)) "xxx"))
+;; Strings built at runtime are ordinary immutable strings, so everything that takes a
+;; string accepts them
+(assert! (string? (string #\a)))
+(assert! (equal? (string->jsexpr (string #\" #\a #\")) "a"))
+(assert! (equal? (value->jsexpr-string (string #\a)) "\"a\""))
+(assert! (error-object? (guard (e [#t e]) (string-set! (make-string 2 #\a) 0 #\b))))
+(assert! (error-object? (guard (e [#t e]) (string-set! (string-copy "ab") 0 #\b))))
+
+;; Mutable strings are their own type, distinct from strings with the same contents
+(define key (string->mutable-string "a"))
+(assert! (not (string? key)))
+(assert! (not (equal? key "a")))
+(assert! (not (eqv? key "a")))
+(assert! (not (eqv? (string #\a) "a")))
+(assert! (eqv? key key))
+
+;; Their contents can change, so they can't be used as hash keys
+(assert! (error-object? (guard (e [#t e]) (hash key 1))))
+(assert! (error-object? (guard (e [#t e]) (hash-insert (hash) key 1))))
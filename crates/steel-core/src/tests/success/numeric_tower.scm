@@ -0,0 +1,121 @@
+;; Complex numbers
+(define z (make-rectangular 1 2))
+
+(assert! (complex? z))
+(assert! (not (real? z)))
+(assert! (exact? z))
+(assert! (equal? 1 (real-part z)))
+(assert! (equal? 2 (imag-part z)))
+(assert! (equal? 0 (imag-part 5)))
+(assert! (= 5 (* z (make-rectangular 1 -2))))
+(assert! (= (make-rectangular 2.5 2.0) (+ z 1.5)))
+(assert! (inexact? (+ z 1.5)))
+(assert! (= (make-rectangular 1/5 -2/5) (/ z)))
+(assert! (equal? 5 (magnitude (make-rectangular 3 4))))
+(assert! (equal? 7 (make-rectangular 7 0)))
+(assert! (equal? "1+2i" (number->string z)))
+(assert! (equal? "-i" (number->string (make-rectangular 0 -1))))
+
+;; Complex numbers aren't ordered
+(define (rejected? thunk)
+  (call-with-exception-handler (lambda (err) #t)
+                               (lambda ()
+                                 (thunk)
+                                 #f)))
+
+(assert! (rejected? (lambda () (< z 1))))
+(assert! (rejected? (lambda () (> 1 z))))
+(assert! (rejected? (lambda () (<= 1 2 z))))
+(assert! (rejected? (lambda () (>= z z))))
+(assert! (rejected? (lambda () (max 1 z))))
+
+;; Exactness
+(assert! (equal? 5/2 (exact 2.5)))
+(assert! (equal? 1 (exact 1.0)))
+(assert! (equal? 0.25 (inexact 1/4)))
+(assert! (equal? 0.25 (exact->inexact 1/4)))
+(assert! (equal? 5/2 (inexact->exact 2.5)))
+(assert! (exact-integer? 5))
+(assert! (not (exact-integer? 5.0)))
+(assert! (nan? (/ 0. 0.)))
+(assert! (infinite? (/ 1. 0.)))
+(assert! (finite? 1/3))
+
+;; Square roots
+(assert! (equal? 4 (sqrt 16)))
+(assert! (equal? 1/2 (sqrt 1/4)))
+(assert! (equal? (make-rectangular 0 2) (sqrt -4)))
+(assert! (inexact? (sqrt 2)))
+
+(let-values ([(s r) (exact-integer-sqrt 17)])
+  (assert! (equal? 4 s))
+  (assert! (equal? 1 r)))
+
+;; Integer division
+(let-values ([(q r) (floor/ -5 2)])
+  (assert! (equal? -3 q))
+  (assert! (equal? 1 r)))
+
+(let-values ([(q r) (truncate/ -5 2)])
+  (assert! (equal? -2 q))
+  (assert! (equal? -1 r)))
+
+(assert! (equal? -3 (floor-quotient -5 2)))
+(assert! (equal? 1 (floor-remainder -5 2)))
+(assert! (equal? -2 (truncate-quotient -5 2)))
+(assert! (equal? -1 (truncate-remainder -5 2)))
+(assert! (equal? 1 (modulo -7 2)))
+(assert! (equal? -1 (remainder -7 2)))
+(assert! (equal? 1.0 (modulo -7 2.0)))
+(assert! (equal? 33333333333333333333 (quotient 100000000000000000000 3)))
+
+;; Rationals
+(assert! (equal? 3 (numerator 6/4)))
+(assert! (equal? 2 (denominator 6/4)))
+(assert! (equal? 2.0 (denominator 0.5)))
+(assert! (equal? 1/3 (rationalize 3/10 1/10)))
+(assert! (equal? (/ 1. 3.) (rationalize 0.3 1/10)))
+(assert! (equal? 2 (floor 5/2)))
+(assert! (equal? -2.0 (ceiling -2.5)))
+(assert! (equal? -2 (truncate -5/2)))
+
+;; Transcendental functions
+(assert! (equal? 1 (exp 0)))
+(assert! (equal? 0 (log 1)))
+(assert! (equal? 2.0 (log 100 10)))
+(assert! (equal? 3.0 (log 8 2)))
+(assert! (equal? 0 (sin 0)))
+(assert! (equal? 1 (cos 0)))
+(assert! (equal? (atan 1) (atan 1 1)))
+(assert! (not (real? (log -1))))
+(assert! (not (real? (asin 2))))
+(assert! (equal? 1/4 (expt 2 -2)))
+(assert! (equal? 1267650600228229401496703205376 (expt 2 100)))
+
+;; Reading and writing numbers
+(assert! (equal? z (string->number "1+2i")))
+(assert! (equal? (make-rectangular 0 1) (string->number "+i")))
+(assert! (equal? -255 (string->number "#x-ff")))
+(assert! (equal? 5 (string->number "#b101")))
+(assert! (equal? 5 (string->number "101" 2)))
+(assert! (equal? 3/2 (string->number "#e1.5")))
+(assert! (equal? 0.25 (string->number "#i1/4")))
+(assert! (equal? 1000.0 (string->number "1e3")))
+(assert! (equal? 1 (string->number "1@0")))
+(assert! (infinite? (string->number "-inf.0")))
+(assert! (equal? (make-rectangular -0.0025 4.0) (string->number "-2.5e-3+4i")))
+(assert! (not (string->number "abc")))
+(assert! (not (string->number "1/0")))
+(assert! (equal? "ff" (number->string 255 16)))
+(assert! (equal? "1/11" (number->string 1/3 2)))
+(assert! (equal? "+nan.0" (number->string (/ 0. 0.))))
+
+;; Flonums in other radices are written with enough digits to be read back
+(assert! (equal? "101.1" (number->string 5.5 2)))
+(assert! (equal? "-ff.8" (number->string -255.5 16)))
+(assert! (equal? "0.1" (number->string (/ 1. 3) 3)))
+(assert! (equal? 5.5 (string->number "101.1" 2)))
+(assert! (equal? 11/2 (string->number "#e#b101.1")))
+(assert! (equal? 0.1 (string->number (number->string 0.1 7) 7)))
+(assert! (equal? 1e300 (string->number (number->string 1e300 16) 16)))
+(assert! (equal? "-inf.0" (number->string (/ -1. 0.) 2)))
@@ -0,0 +1,66 @@
+(define location (make-parameter "here"))
+
+(assert! (equal? "here" (location)))
+(assert! (equal? "there" (parameterize ([location "there"]) (location))))
+(assert! (equal? "here" (location)))
+
+;; Nested parameterize, and values computed before any of them are bound
+(assert! (equal? '("in a house" "with a mouse" "in a house")
+                 (parameterize ([location "in a house"])
+                   (list (location)
+                         (parameterize ([location "with a mouse"])
+                           (location))
+                         (location)))))
+
+;; Converters are applied to the initial value and to parameterized values
+(define doubled (make-parameter 10 (lambda (x) (* x 2))))
+(assert! (equal? 20 (doubled)))
+(assert! (equal? 6 (parameterize ([doubled 3]) (doubled))))
+
+;; Escaping with a continuation puts back the outer value
+(define escaped
+  (call/cc (lambda (return)
+             (parameterize ([location "inside"])
+               (return (location))))))
+
+(assert! (equal? "inside" escaped))
+(assert! (equal? "here" (location)))
+
+;; As does unwinding with an error
+(define caught
+  (guard (e [#t (location)])
+    (parameterize ([location "raised"])
+      (raise 'oops))))
+
+(assert! (equal? "here" caught))
+(assert! (equal? "here" (location)))
+
+;; Re-entering the body with a continuation puts back the inner value
+(define resume #f)
+(define seen '())
+
+(parameterize ([location "re-entered"])
+  (call/cc (lambda (k) (set! resume k)))
+  (set! seen (cons (location) seen)))
+
+(when (< (length seen) 2)
+  (resume #f))
+
+(assert! (equal? '("re-entered" "re-entered") seen))
+(assert! (equal? "here" (location)))
+
+;; Setting a parameter inside of parameterize only lasts until it returns
+(parameterize ([location "temporary"])
+  (location "changed")
+  (assert! (equal? "changed" (location))))
+
+(assert! (equal? "here" (location)))
+
+(location "set")
+(assert! (equal? "set" (location)))
+
+;; The port parameters
+(assert! (output-port? (current-output-port)))
+(assert! (output-port? (current-error-port)))
+(assert! (input-port? (current-input-port)))
+(assert! (equal? "hello" (with-output-to-string (lambda () (display "hello")))))
@@ -0,0 +1,62 @@
+;; Promises are memoized
+(define count 0)
+(define p
+  (delay (begin
+           (set! count (+ count 1))
+           (* 6 7))))
+
+(assert! (promise? p))
+(assert! (not (promise? 42)))
+(assert! (equal? 42 (force p)))
+(assert! (equal? 42 (force p)))
+(assert! (equal? 1 count))
+
+;; make-promise wraps values, but returns promises as is
+(assert! (equal? 5 (force (make-promise 5))))
+(assert! (eq? p (make-promise p)))
+
+;; Forcing something that isn't a promise returns it
+(assert! (equal? 10 (force 10)))
+
+;; Long chains of delay-force run in constant space
+(define (loop n)
+  (if (= n 0)
+      (delay 'done)
+      (delay-force (loop (- n 1)))))
+
+(assert! (equal? 'done (force (loop 100000))))
+
+;; A promise that forces itself while running keeps the first result
+(define x 0)
+(define reentrant
+  (delay (begin
+           (set! x (+ x 1))
+           (if (> x 5) x (force reentrant)))))
+
+(assert! (equal? 6 (force reentrant)))
+(assert! (equal? 6 (force reentrant)))
+
+;; Stream tails are only computed once
+(define calls 0)
+(define (naturals n)
+  (stream-cons n
+               (begin
+                 (set! calls (+ calls 1))
+                 (naturals (+ n 1)))))
+
+(define (stream-take n s)
+  (if (= n 0)
+      '()
+      (cons (stream-car s) (stream-take (- n 1) (stream-cdr s)))))
+
+(define s (naturals 0))
+(assert! (equal? '(0 1 2 3) (stream-take 4 s)))
+(assert! (equal? '(0 1 2 3) (stream-take 4 s)))
+(assert! (equal? 4 calls))
+
+;; The tail can still be written as a thunk
+(define (integers n)
+  (stream-cons n (lambda () (integers (+ 1 n)))))
+
+(assert! (equal? '(0 1 2) (stream-take 3 (integers 0))))
+(assert! (equal? '(0 1 2) (transduce (integers 0) (taking 3) (into-list))))
@@ -0,0 +1,96 @@
+(define (write->string value)
+  (let ([port (open-output-string)])
+    (write value port)
+    (get-output-string port)))
+
+(define (write-shared->string value)
+  (let ([port (open-output-string)])
+    (write-shared value port)
+    (get-output-string port)))
+
+(define (round-trips? value)
+  (equal? value (read (open-input-string (write->string value)))))
+
+;; Everything with an external representation reads back as an equal value
+(define examples
+  (list 42
+        -2.5
+        1/3
+        (expt 10 30)
+        (make-rectangular 1 2)
+        "tab\tnewline\n \"quoted\" back\\slash"
+        (list->string (list (integer->char 7) (integer->char 955)))
+        #\a
+        #\space
+        #\newline
+        (integer->char 0)
+        (integer->char 955)
+        'symbol
+        (string->symbol "two words")
+        (string->symbol "")
+        (string->symbol "42")
+        (string->symbol "with|bar")
+        '#:keyword
+        #true
+        #false
+        '()
+        (list 1 (list "two" #\3) 'four)
+        (cons 1 2)
+        (cons 1 (cons 2 3))
+        (vector 1 "two" (vector #\3))
+        (list 'quote 'x)))
+
+(let loop ([examples examples])
+  (when (not (null? examples))
+    (assert! (round-trips? (car examples)))
+    (loop (cdr examples))))
+
+;; Symbols and characters that wouldn't read back on their own are escaped
+(assert! (equal? (write->string (string->symbol "two words")) "|two words|"))
+(assert! (equal? (write->string (string->symbol "42")) "|42|"))
+(assert! (equal? (write->string (integer->char 7)) "#\\alarm"))
+(assert! (equal? (write->string (list "a" #\b 'c)) "(\"a\" #\\b c)"))
+
+;; Cycles are written with datum labels, shared structure only by write-shared
+(define cyclic (mutable-vector 1 2))
+(vector-set! cyclic 1 cyclic)
+(assert! (equal? (write->string cyclic) "#0=#(1 #0#)"))
+
+;; and read back as the same cycle
+(define read-cyclic (read (open-input-string (write->string cyclic))))
+(assert! (eq? (mut-vector-ref read-cyclic 1) read-cyclic))
+(assert! (equal? (mut-vector-ref read-cyclic 0) 1))
+(assert! (equal? (write->string read-cyclic) "#0=#(1 #0#)"))
+
+(define read-nested (read (open-input-string "#0=#((#0#) 2)")))
+(assert! (eq? (car (mut-vector-ref read-nested 0)) read-nested))
+
+(define shared (list 1 2))
+(assert! (equal? (write->string (list shared shared)) "((1 2) (1 2))"))
+(assert! (equal? (write-shared->string (list shared shared)) "(#0=(1 2) #0#)"))
+
+(define read-shared (read (open-input-string (write-shared->string (list shared shared)))))
+(assert! (equal? read-shared (list shared shared)))
+(assert! (eq? (car read-shared) (cadr read-shared)))
+
+;; The reader leaves what follows a datum in the port, and skips comments
+(define port (open-input-string "(a . b) ; comment\n #| block |# #;(skipped) [1 2] 'x"))
+(assert! (equal? (read port) (cons 'a 'b)))
+(assert! (equal? (read port) '(1 2)))
+(assert! (equal? (read port) ''x))
+(assert! (equal? (read port) 'eof))
+
+(assert! (equal? (read (open-input-string "\"\\x41;b\\\n   c\"")) "Abc"))
+(assert! (equal? (read (open-input-string "#\\x41")) #\A))
+(assert! (equal? (read (open-input-string "#u8(0 10 255)")) (vector 0 10 255)))
+
+;; Malformed input raises read errors
+(assert! (equal? 'read (guard (e [(read-error? e) 'read]) (read (open-input-string "(1 2")))))
+(assert! (equal? 'read (guard (e [(read-error? e) 'read]) (read (open-input-string "#0=(a #0#)")))))
+(assert! (equal? 'read (guard (e [(read-error? e) 'read]) (read (open-input-string "#u8(256)")))))
+
+;; read-syntax gives the spans of the datum and its elements
+(define syntax (read-syntax (open-input-string "  (a (b c))")))
+(assert! (equal? (syntax->datum syntax) '(a (b c))))
+(assert! (equal? (syntax-span syntax) (list 2 11 #false)))
+(assert! (equal? (map syntax-span (syntax-e syntax)) (list (list 3 4 #false) (list 5 10 #false))))
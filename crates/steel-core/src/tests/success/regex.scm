@@ -0,0 +1,47 @@
+(require-builtin steel/regex)
+
+(define digits (regex "\\d+"))
+
+(assert! (regex? digits))
+(assert! (not (regex? "\\d+")))
+(assert! (equal? "\\d+" (regex->string digits)))
+
+(assert! (regex/match? digits "abc123"))
+(assert! (not (regex/match? digits "abc")))
+
+(assert! (equal? "123" (regex/find digits "abc123def456")))
+(assert! (equal? #f (regex/find digits "abc")))
+(assert! (equal? '(3 6) (regex/find-span digits "abc123def456")))
+(assert! (equal? '("123" "456") (regex/find-all digits "abc123def456")))
+(assert! (equal? '((3 6) (9 12)) (regex/find-all-spans digits "abc123def456")))
+
+;; Spans are byte offsets
+(assert! (equal? '(3 5) (regex/find-span (regex "\\d+") "λa12")))
+
+(define email (regex "(\\w+)@(\\w+)?"))
+(assert! (equal? '("me@" "me" #f) (regex/captures email "me@")))
+(assert! (equal? #f (regex/captures email "nothing here")))
+(assert! (equal? '((0 7) (0 2) (3 7)) (regex/capture-spans email "me@home")))
+
+(define pair (regex "(?<key>\\w+)=(?<value>\\w+)?"))
+(assert! (equal? '("key" "value") (regex/capture-names pair)))
+(assert! (equal? (hash "key" "name" "value" "steel") (regex/named-captures pair "name=steel")))
+(assert! (equal? (hash "key" "name") (regex/named-captures pair "name=")))
+(assert! (equal? '(("a=1" "a" "1") ("b=2" "b" "2")) (regex/captures-all pair "a=1 b=2")))
+
+(assert! (equal? '("a" "b" "c") (regex/split (regex ",\\s*") "a, b,c")))
+(assert! (equal? '("a" "b,c") (regex/splitn (regex ",") "a,b,c" 2)))
+
+(assert! (equal? "world hello" (regex/replace (regex "(\\w+) (\\w+)") "hello world" "$2 $1")))
+(assert! (equal? "a#b2" (regex/replace digits "a1b2" "#")))
+(assert! (equal? "a#b#" (regex/replace-all digits "a1b2" "#")))
+(assert! (equal? "name: steel" (regex/replace pair "name=steel" "${key}: ${value}")))
+
+(define (double groups)
+  (number->string (* 2 (string->number (car groups)))))
+
+(assert! (equal? "a2b4" (regex/replace-all digits "a1b2" double)))
+(assert! (equal? "a2b2" (regex/replace digits "a1b2" double)))
+
+(assert! (equal? "1\\+1" (regex/escape "1+1")))
+(assert! (regex/match? (regex (regex/escape "1+1")) "1+1"))
@@ -0,0 +1,75 @@
+;; Indexing counts characters, not bytes
+(assert! (equal? (string-length "héllo") 5))
+(assert! (equal? (string-length "🤖") 1))
+(assert! (equal? (string-ref "λx" 1) #\x))
+(assert! (equal? (substring "héllo" 1 3) "él"))
+(assert! (equal? (substring "héllo" 2) "llo"))
+(assert! (equal? (substring "abc" 3 3) ""))
+(assert! (equal? (string-copy "héllo" 1) "éllo"))
+
+;; Characters
+(assert! (char-alphabetic? #\λ))
+(assert! (not (char-alphabetic? #\1)))
+(assert! (char-numeric? #\7))
+(assert! (char-upper-case? #\Ä))
+(assert! (char-lower-case? #\ß))
+(assert! (equal? (digit-value #\7) 7))
+(assert! (equal? (digit-value #\٤) 4))
+(assert! (equal? (digit-value #\x) #f))
+(assert! (equal? (char-upcase #\λ) #\Λ))
+(assert! (equal? (char-downcase #\Ä) #\ä))
+(assert! (equal? (char-foldcase #\Σ) #\σ))
+(assert! (equal? (char->integer #\λ) 955))
+(assert! (equal? (integer->char 955) #\λ))
+(assert! (char<? #\a #\b))
+(assert! (char>=? #\b #\b))
+(assert! (char-ci=? #\Λ #\λ))
+
+;; Case mapping and folding
+(assert! (equal? (string-upcase "straße") "STRASSE"))
+(assert! (equal? (string-downcase "ÀB") "àb"))
+(assert! (equal? (string-foldcase "Straße") "strasse"))
+(assert! (string-ci=? "STRASSE" "straße"))
+
+;; Searching
+(assert! (equal? (string-index "hello" #\l) 2))
+(assert! (equal? (string-index-right "hello" #\l) 3))
+(assert! (equal? (string-index "héllo" char-upper-case?) #f))
+(assert! (equal? (string-index "héLlo" char-upper-case?) 2))
+(assert! (equal? (string-index "hello" #\l 3) 3))
+(assert! (equal? (string-contains "héllo world" "world") 6))
+(assert! (equal? (string-contains "hello" "xyz") #f))
+(assert! (equal? (string-search-forward "a" "bänana" 2) 3))
+(assert! (equal? (string-search-backward "a" "banana" 6) 6))
+
+;; Padding and joining
+(assert! (equal? (string-pad "42" 5 #\0) "00042"))
+(assert! (equal? (string-pad "12345" 3) "345"))
+(assert! (equal? (string-pad-right "ab" 4 #\.) "ab.."))
+(assert! (equal? (string-pad-right "λλλλ" 2) "λλ"))
+(assert! (equal? (string-join '("a" "b" "c") ", ") "a, b, c"))
+(assert! (equal? (string-join '()) ""))
+
+;; Graphemes and normalization
+(define decomposed (string #\e (integer->char #x301)))
+(assert! (equal? (string-length decomposed) 2))
+(assert! (equal? (string-grapheme-count decomposed) 1))
+(assert! (equal? (string->graphemes (string-append decomposed "a")) (list decomposed "a")))
+(assert! (equal? (string-normalize-nfc decomposed) "é"))
+(assert! (equal? (string-normalize-nfd "é") decomposed))
+(assert! (equal? (string-normalize-nfkc "ﬁ") "fi"))
+
+;; Cursors walk the string in constant time per step
+(define (count-lambdas s)
+  (let loop ([cursor (string-cursor-start s)] [count 0])
+    (if (= cursor (string-cursor-end s))
+        count
+        (loop (string-cursor-next s cursor)
+              (if (equal? (string-cursor-ref s cursor) #\λ) (+ count 1) count)))))
+
+(assert! (equal? (count-lambdas "λxλyλ") 3))
+(assert! (equal? (string-cursor-next "λx" 0) 2))
+(assert! (equal? (string-cursor-prev "λx" 2) 0))
+(assert! (equal? (string-cursor->index "λλx" 4) 2))
+(assert! (equal? (string-index->cursor "λλx" 2) 4))
+(assert! (equal? (substring/cursors "λλx" 2 5) "λx"))
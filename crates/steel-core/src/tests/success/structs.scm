@@ -30,3 +30,4 @@
 (define callable (Callable (lambda (x) (+ x 10))))
 
 (assert! (equal? (callable 100) 110))
+(assert! (equal? (apply callable (list 100)) 110))
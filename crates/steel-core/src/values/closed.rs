@@ -14,8 +14,8 @@ use crate::{
     gc::{unsafe_erased_pointers::OpaqueReference, Gc},
     rvals::{
         cycles::BreadthFirstSearchSteelValVisitor, BoxedAsyncFunctionSignature, CustomType,
-        FunctionSignature, FutureResult, MutFunctionSignature, SteelHashMap, SteelHashSet,
-        SteelString, Syntax,
+        FunctionSignature, FutureResult, MutFunctionSignature, SteelComplex, SteelHashMap,
+        SteelHashSet, SteelString, Syntax,
     },
     steel_vm::vm::BuiltInSignature,
     values::functions::ByteCodeLambda,
@@ -296,6 +296,25 @@ impl Heap {
         HeapRef { inner: weak_ptr }
     }
 
+    pub(crate) fn allocate_vector_without_collection(
+        &mut self,
+        values: Vec<SteelVal>,
+    ) -> HeapRef<Vec<SteelVal>> {
+        let pointer = Rc::new(RefCell::new(HeapAllocated::new(values)));
+        let weak_ptr = Rc::downgrade(&pointer);
+
+        self.vectors.push(pointer);
+
+        HeapRef { inner: weak_ptr }
+    }
+
+    /// Keeps the allocations of another heap alive in this one as well, the same
+    /// way that cloning a heap shares the allocations between the two
+    pub(crate) fn share_allocations(&mut self, other: &Heap) {
+        self.memory.extend(other.memory.iter().cloned());
+        self.vectors.extend(other.vectors.iter().cloned());
+    }
+
     fn vector_cells_allocated(&self) -> usize {
         // self.vectors.iter().map(|x| x.borrow().value.len()).sum()
         self.vectors.len()
@@ -729,6 +748,7 @@ impl<'a> BreadthFirstSearchSteelValVisitor for MarkAndSweepContext<'a> {
     fn visit_int(&mut self, _int: isize) -> Self::Output {}
     fn visit_rational(&mut self, _: Rational32) -> Self::Output {}
     fn visit_bigrational(&mut self, _: Gc<BigRational>) -> Self::Output {}
+    fn visit_complex(&mut self, _: Gc<SteelComplex>) -> Self::Output {}
 
     fn visit_list(&mut self, list: List<SteelVal>) -> Self::Output {
         for value in list {
@@ -14,7 +14,7 @@ use fxhash::FxHashSet;
 use crate::{
     core::{instructions::DenseInstruction, opcode::OpCode},
     gc::Gc,
-    parser::{parser::SyntaxObjectId, span::Span},
+    parser::{ast::LambdaSignature, interner::InternedString, parser::SyntaxObjectId, span::Span},
     rvals::{
         from_serializable_value, into_serializable_value, AsRefSteelVal, BoxedFunctionSignature,
         Custom, FunctionSignature, HeapSerializer, IntoSteelVal, MutFunctionSignature,
@@ -31,7 +31,8 @@ use crate::{
 
 use super::{
     closed::{Heap, HeapRef},
-    structs::UserDefinedStruct,
+    lists::List,
+    structs::{StructFunction, StructTypeDescriptor, UserDefinedStruct},
 };
 
 // pub(crate) enum Function {
@@ -75,6 +76,19 @@ impl LambdaMetadataTable {
         }
     }
 
+    /// The docs attached to closures, keyed by the id of the closure
+    pub(crate) fn closure_entries(&self, ids: &FxHashSet<usize>) -> Vec<(usize, SteelString)> {
+        self.fn_ptr_table
+            .iter()
+            .filter(|(id, _)| ids.contains(id))
+            .map(|(id, doc)| (*id, doc.clone()))
+            .collect()
+    }
+
+    pub(crate) fn insert_closure_entry(&mut self, id: usize, doc: SteelString) {
+        self.fn_ptr_table.insert(id, doc);
+    }
+
     pub fn get(&self, function: SteelVal) -> Option<SteelString> {
         match function {
             SteelVal::Closure(b) => self.fn_ptr_table.get(&b.id).cloned(),
@@ -111,6 +125,9 @@ pub struct ByteCodeLambda {
     call_count: Cell<usize>,
 
     pub(crate) is_multi_arity: bool,
+    // How the arguments of a call are matched up with the arguments of this function, if it has
+    // optional or keyword arguments, or multiple clauses
+    pub(crate) signature: Option<Rc<LambdaSignature>>,
     pub(crate) captures: Vec<SteelVal>,
     pub(crate) heap_allocated: RefCell<Vec<HeapRef<SteelVal>>>,
     // pub(crate) spans: Rc<[Span]>,
@@ -147,6 +164,7 @@ pub struct SerializedLambda {
     pub body_exp: Vec<DenseInstruction>,
     pub arity: usize,
     pub is_multi_arity: bool,
+    pub signature: Option<LambdaSignature>,
     // TODO: Go ahead and create a ThreadSafeSteelVal where we will just deep clone everything, move
     // it across the thread, and reconstruct on the other side.
     pub captures: Vec<SerializableSteelVal>,
@@ -158,6 +176,7 @@ pub struct SerializedLambdaPrototype {
     pub body_exp: Vec<DenseInstruction>,
     pub arity: usize,
     pub is_multi_arity: bool,
+    pub signature: Option<LambdaSignature>,
     // TODO: Go ahead and create a ThreadSafeSteelVal where we will just deep clone everything, move
     // it across the thread, and reconstruct on the other side.
     // pub captures: Vec<SerializableSteelVal>,
@@ -191,6 +210,7 @@ impl ByteCodeLambda {
             call_count: Cell::new(0),
 
             is_multi_arity,
+            signature: None,
             captures,
             // TODO: Allocated the necessary size right away <- we're going to index into it
             heap_allocated: RefCell::new(heap_allocated),
@@ -205,7 +225,7 @@ impl ByteCodeLambda {
     }
 
     pub(crate) fn from_serialized(heap: &mut HeapSerializer, value: SerializedLambda) -> Self {
-        ByteCodeLambda::new(
+        let mut lambda = ByteCodeLambda::new(
             value.id,
             value.body_exp.into(),
             value.arity,
@@ -216,7 +236,11 @@ impl ByteCodeLambda {
                 .map(|x| from_serializable_value(heap, x))
                 .collect(),
             Vec::new(),
-        )
+        );
+
+        lambda.set_signature(value.signature.map(Rc::new));
+
+        lambda
     }
 
     pub fn main(instructions: Vec<DenseInstruction>) -> ByteCodeLambda {
@@ -243,6 +267,14 @@ impl ByteCodeLambda {
         self.heap_allocated = RefCell::new(heap_allocated);
     }
 
+    pub(crate) fn set_signature(&mut self, signature: Option<Rc<LambdaSignature>>) {
+        self.signature = signature;
+    }
+
+    pub(crate) fn signature(&self) -> Option<&LambdaSignature> {
+        self.signature.as_deref()
+    }
+
     pub fn body_exp(&self) -> Rc<[DenseInstruction]> {
         #[cfg(feature = "dynamic")]
         return Rc::clone(&self.body_exp.borrow());
@@ -347,6 +379,108 @@ impl ByteCodeLambda {
     // pub(crate) fn block_tail(&self, block_pattern
 }
 
+thread_local! {
+    // What the vm binds the optional and keyword arguments that weren't passed to, so that the body
+    // of the function knows to use the default instead
+    pub(crate) static UNSUPPLIED_ARGUMENT: SteelVal = SteelVal::SymbolV("#%unsupplied".into());
+}
+
+pub(crate) fn is_unsupplied(value: &SteelVal) -> bool {
+    match value {
+        SteelVal::SymbolV(s) => UNSUPPLIED_ARGUMENT.with(|unsupplied| match unsupplied {
+            SteelVal::SymbolV(unsupplied) => Rc::ptr_eq(s, unsupplied),
+            _ => false,
+        }),
+        _ => false,
+    }
+}
+
+/// Encodes the signature as a constant, so that the compiler can point a closure at it.
+///
+/// The parameters `(a [b 1] #:c c)` encode as `(parameters 1 1 #:c #true)`, and a `case-lambda`
+/// with the clauses `(x)` and `(x . rest)` as `(cases 1 #false 1 #true)`.
+pub(crate) fn signature_to_constant(signature: &LambdaSignature) -> SteelVal {
+    let mut values = Vec::new();
+
+    match signature {
+        LambdaSignature::Parameters {
+            required,
+            optional,
+            keywords,
+        } => {
+            values.push(SteelVal::SymbolV("parameters".into()));
+            values.push(SteelVal::IntV(*required as isize));
+            values.push(SteelVal::IntV(*optional as isize));
+
+            for (keyword, required) in keywords {
+                values.push(SteelVal::SymbolV(keyword.resolve().into()));
+                values.push(SteelVal::BoolV(*required));
+            }
+        }
+        LambdaSignature::Cases(clauses) => {
+            values.push(SteelVal::SymbolV("cases".into()));
+
+            for (arity, rest) in clauses {
+                values.push(SteelVal::IntV(*arity as isize));
+                values.push(SteelVal::BoolV(*rest));
+            }
+        }
+    }
+
+    SteelVal::ListV(List::from(values))
+}
+
+/// The inverse of [`signature_to_constant`].
+pub(crate) fn signature_from_constant(value: &SteelVal) -> Option<LambdaSignature> {
+    let SteelVal::ListV(values) = value else {
+        return None;
+    };
+
+    let values = values.iter().cloned().collect::<Vec<_>>();
+    let (kind, values) = values.split_first()?;
+
+    let count = |value: &SteelVal| match value {
+        SteelVal::IntV(n) => usize::try_from(*n).ok(),
+        _ => None,
+    };
+
+    let flag = |value: &SteelVal| match value {
+        SteelVal::BoolV(b) => Some(*b),
+        _ => None,
+    };
+
+    match kind {
+        SteelVal::SymbolV(kind) if kind.as_str() == "parameters" => {
+            let (required, optional) = (count(values.first()?)?, count(values.get(1)?)?);
+
+            let keywords = values[2..]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [SteelVal::SymbolV(keyword), required] => {
+                        Some((InternedString::from(keyword.as_str()), flag(required)?))
+                    }
+                    _ => None,
+                })
+                .collect::<Option<_>>()?;
+
+            Some(LambdaSignature::Parameters {
+                required,
+                optional,
+                keywords,
+            })
+        }
+        SteelVal::SymbolV(kind) if kind.as_str() == "cases" => values
+            .chunks(2)
+            .map(|pair| match pair {
+                [arity, rest] => Some((count(arity)?, flag(rest)?)),
+                _ => None,
+            })
+            .collect::<Option<_>>()
+            .map(LambdaSignature::Cases),
+        _ => None,
+    }
+}
+
 pub fn attach_contract_struct(args: &[SteelVal]) -> crate::rvals::Result<SteelVal> {
     if let SteelVal::Closure(closure) = &args[0] {
         if let SteelVal::CustomStruct(s) = &args[1] {
@@ -416,6 +550,9 @@ pub struct BoxedDynFunction {
         Arc<dyn Fn(&[SteelVal]) -> crate::rvals::Result<SteelVal> + Send + Sync + 'static>,
     pub name: Option<StaticOrRcStr>,
     pub arity: Option<usize>,
+    // Set for the functions generated for a struct type, so they can be rebuilt
+    // when restoring a snapshot
+    pub(crate) struct_function: Option<(StructFunction, StructTypeDescriptor)>,
 }
 
 impl BoxedDynFunction {
@@ -436,6 +573,7 @@ impl BoxedDynFunction {
                 .map(|x| Arc::new(x.to_string()))
                 .map(StaticOrRcStr::Owned),
             arity,
+            struct_function: None,
         }
     }
 
@@ -450,6 +588,7 @@ impl BoxedDynFunction {
             function,
             name: name.map(StaticOrRcStr::Owned),
             arity,
+            struct_function: None,
         }
     }
 
@@ -1,4 +1,6 @@
-use crate::rvals::SteelVal;
+use std::{cell::RefCell, rc::Rc};
+
+use crate::rvals::{cycles::BreadthFirstSearchSteelValVisitor, SteelVal};
 
 #[derive(Clone)]
 pub struct LazyStream {
@@ -42,3 +44,71 @@ impl LazyStream {
         SteelVal::BoolV(self.empty_stream)
     }
 }
+
+#[derive(Clone)]
+enum PromiseState {
+    Done(SteelVal),
+    // A thunk that produces another promise, see `delay-force`
+    Delayed(SteelVal),
+}
+
+/// A promise, as created by `delay`, `delay-force` and `make-promise`.
+///
+/// Forcing follows the R7RS reference implementation: when a delayed promise
+/// produces another promise, the two are made to share their state, which is what
+/// lets chains of `delay-force` run in constant space.
+pub(crate) struct Promise {
+    state: Rc<RefCell<PromiseState>>,
+}
+
+impl Promise {
+    pub(crate) fn new(done: bool, value: SteelVal) -> Self {
+        let state = if done {
+            PromiseState::Done(value)
+        } else {
+            PromiseState::Delayed(value)
+        };
+
+        Promise {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        matches!(&*self.state.borrow(), PromiseState::Done(_))
+    }
+
+    /// The forced value, or the thunk if this hasn't been forced yet
+    pub(crate) fn value(&self) -> SteelVal {
+        match &*self.state.borrow() {
+            PromiseState::Done(value) | PromiseState::Delayed(value) => value.clone(),
+        }
+    }
+
+    /// Moves the state of `new` into `old`, and then has `new` share it
+    pub(crate) fn update(new: &mut Promise, old: &Promise) {
+        let state = new.state.borrow().clone();
+        *old.state.borrow_mut() = state;
+        new.state = Rc::clone(&old.state);
+    }
+}
+
+impl crate::rvals::Custom for Promise {
+    fn fmt(&self) -> Option<std::result::Result<String, std::fmt::Error>> {
+        match &*self.state.borrow() {
+            PromiseState::Done(value) => Some(Ok(format!("#<promise!{value}>"))),
+            PromiseState::Delayed(_) => Some(Ok("#<promise>".to_string())),
+        }
+    }
+
+    fn gc_visit_children(&self, context: &mut crate::values::closed::MarkAndSweepContext) {
+        context.push_back(self.value());
+    }
+
+    // Promises are only equal to themselves
+    fn equality_hint(&self, other: &dyn crate::rvals::CustomType) -> bool {
+        crate::rvals::as_underlying_type::<Promise>(other)
+            .map(|other| Rc::ptr_eq(&self.state, &other.state))
+            .unwrap_or(false)
+    }
+}
@@ -5,6 +5,7 @@ pub(crate) mod functions;
 pub(crate) mod json_vals;
 pub(crate) mod lazy_stream;
 pub(crate) mod lists;
+pub(crate) mod multiple_values;
 pub(crate) mod port;
 pub(crate) mod structs;
 pub(crate) mod transducers;
@@ -0,0 +1,66 @@
+use crate::{
+    rvals::{as_underlying_type, cycles::BreadthFirstSearchSteelValVisitor, IntoSteelVal, Result},
+    values::lists::List,
+    SteelVal,
+};
+
+/// The result of `(values ...)` with anything other than exactly one argument.
+///
+/// A single value is always returned as itself, so only zero or several values
+/// are ever wrapped. The wrapper is only ever returned to the producer of
+/// `call-with-values` - `values` refuses to return it anywhere else - where it is
+/// unpacked right away with `#%values->list`. This keeps `(values '(1 2))` and
+/// `(values 1 2)` distinct.
+pub(crate) struct MultipleValues {
+    values: Vec<SteelVal>,
+}
+
+impl MultipleValues {
+    /// Packs the given values following the return convention: exactly one value is
+    /// returned unwrapped, anything else is wrapped.
+    pub(crate) fn pack(mut values: Vec<SteelVal>) -> SteelVal {
+        if values.len() == 1 {
+            values.pop().unwrap()
+        } else {
+            MultipleValues { values }.into_steelval().unwrap()
+        }
+    }
+
+    /// Unpacks a value produced by [`MultipleValues::pack`] into a list of its values.
+    pub(crate) fn unpack(value: &SteelVal) -> List<SteelVal> {
+        if let SteelVal::Custom(c) = value {
+            if let Some(multiple) = as_underlying_type::<MultipleValues>(c.borrow().as_ref()) {
+                return multiple.values.iter().cloned().collect();
+            }
+        }
+
+        List::from(vec![value.clone()])
+    }
+}
+
+impl crate::rvals::Custom for MultipleValues {
+    fn fmt(&self) -> Option<std::result::Result<String, std::fmt::Error>> {
+        let mut output = "#<values".to_string();
+        for value in &self.values {
+            output.push(' ');
+            output.push_str(&value.to_string());
+        }
+        output.push('>');
+        Some(Ok(output))
+    }
+
+    fn gc_visit_children(&self, context: &mut crate::values::closed::MarkAndSweepContext) {
+        for value in &self.values {
+            context.push_back(value.clone());
+        }
+    }
+}
+
+/// `(#%values->list v)` - the values carried by `v` as a list.
+pub(crate) fn values_to_list(args: &[SteelVal]) -> Result<SteelVal> {
+    if args.len() != 1 {
+        stop!(ArityMismatch => "#%values->list expects 1 argument, found {}", args.len());
+    }
+
+    Ok(SteelVal::ListV(MultipleValues::unpack(&args[0])))
+}
@@ -3,7 +3,7 @@ use std::fs::OpenOptions;
 use std::io;
 use std::io::prelude::*;
 use std::io::Cursor;
-use std::io::{BufReader, BufWriter, Stdin, Stdout};
+use std::io::{BufReader, BufWriter, Stderr, Stdin, Stdout};
 use std::process::ChildStdin;
 use std::process::ChildStdout;
 use std::sync::Arc;
@@ -35,6 +35,9 @@ thread_local! {
     pub static DEFAULT_OUTPUT_PORT: RcRefCell<SteelPort> = new_rc_ref_cell(SteelPort { port: new_rc_ref_cell(SteelPortRepr::StdOutput(io::stdout())) } );
     pub static CAPTURED_OUTPUT_PORT: RcRefCell<BufWriter<Vec<u8>>> = new_rc_ref_cell(BufWriter::new(Vec::new()));
 
+    // What the default output port writes to in tests instead of stdout
+    static TEST_OUTPUT: Arc<Mutex<dyn Write + Send + Sync>> = Arc::new(Mutex::new(BufWriter::new(Vec::new())));
+
     // pub static STANDARD_OUT: SteelPort = SteelPort::StringOutput(Rc::new(RefCell::new(BufWriter::new(Vec::new()))));
 }
 
@@ -49,6 +52,7 @@ pub enum SteelPortRepr {
     FileOutput(String, BufWriter<File>),
     StdInput(Stdin),
     StdOutput(Stdout),
+    StdError(Stderr),
     ChildStdOutput(BufReader<ChildStdout>),
     ChildStdInput(BufWriter<ChildStdin>),
     StringInput(BufReader<Cursor<Vec<u8>>>),
@@ -69,6 +73,7 @@ impl std::fmt::Debug for SteelPortRepr {
             }
             SteelPortRepr::StdInput(s) => f.debug_tuple("StdInput").field(s).finish(),
             SteelPortRepr::StdOutput(s) => f.debug_tuple("StdOutput").field(s).finish(),
+            SteelPortRepr::StdError(s) => f.debug_tuple("StdError").field(s).finish(),
             SteelPortRepr::ChildStdOutput(s) => f.debug_tuple("ChildStdOutput").field(s).finish(),
             SteelPortRepr::ChildStdInput(s) => f.debug_tuple("ChildStdInput").field(s).finish(),
             SteelPortRepr::StringInput(s) => f.debug_tuple("StringInput").field(s).finish(),
@@ -82,6 +87,7 @@ impl std::fmt::Debug for SteelPortRepr {
 pub enum SendablePort {
     StdInput(Stdin),
     StdOutput(Stdout),
+    StdError(Stderr),
     BoxDynWriter(Arc<Mutex<dyn Write + Send + Sync>>),
     Closed,
 }
@@ -91,6 +97,8 @@ impl SendablePort {
         match value {
             SteelPortRepr::StdInput(_) => Ok(SendablePort::StdInput(io::stdin())),
             SteelPortRepr::StdOutput(_) => Ok(SendablePort::StdOutput(io::stdout())),
+            SteelPortRepr::StdError(_) => Ok(SendablePort::StdError(io::stderr())),
+            SteelPortRepr::DynWriter(w) => Ok(SendablePort::BoxDynWriter(Arc::clone(w))),
             SteelPortRepr::Closed => Ok(SendablePort::Closed),
             _ => {
                 stop!(Generic => "Unable to send port across threads: {:?}", value)
@@ -112,6 +120,9 @@ impl SteelPort {
             SendablePort::StdOutput(s) => SteelPort {
                 port: new_rc_ref_cell(SteelPortRepr::StdOutput(s)),
             },
+            SendablePort::StdError(s) => SteelPort {
+                port: new_rc_ref_cell(SteelPortRepr::StdError(s)),
+            },
             SendablePort::Closed => SteelPort {
                 port: new_rc_ref_cell(SteelPortRepr::Closed),
             },
@@ -147,6 +158,22 @@ macro_rules! port_read_str_fn(
     }};
 );
 
+// Runs `$body` with `$br` bound to the buffered reader of an input port
+macro_rules! with_input_port(
+    ($port: expr, $name: literal, |$br: ident| $body: expr) => {{
+        match $port {
+            SteelPortRepr::FileInput(_, $br) => $body,
+            SteelPortRepr::StringInput($br) => $body,
+            SteelPortRepr::ChildStdOutput($br) => $body,
+            SteelPortRepr::StdInput(stdin) => {
+                let $br = &mut stdin.lock();
+                $body
+            }
+            port => stop!(TypeMismatch => "{} expects an input port, found: {:?}", $name, port),
+        }
+    }};
+);
+
 impl SteelPortRepr {
     pub fn read_line(&mut self) -> Result<(usize, String)> {
         match self {
@@ -173,6 +200,7 @@ impl SteelPortRepr {
         match self {
             SteelPortRepr::FileOutput(_, s) => Ok(s.flush()?),
             SteelPortRepr::StdOutput(s) => Ok(s.flush()?),
+            SteelPortRepr::StdError(s) => Ok(s.flush()?),
             SteelPortRepr::ChildStdInput(s) => Ok(s.flush()?),
             SteelPortRepr::StringOutput(s) => Ok(s.flush()?),
             SteelPortRepr::DynWriter(s) => Ok(s.lock().unwrap().flush()?),
@@ -189,21 +217,47 @@ impl SteelPortRepr {
         }
     }
 
-    pub fn read_char(&mut self) -> Result<(usize, char)> {
-        // FIXME: this only reads 1 u8 and casts it to char
-        macro_rules! port_read_chr(
-            ($br: ident) => {{
-                let mut chr = [0; 1];
-                $br.read_exact(&mut chr)?;
-                Ok((1, chr[0] as char))
-            }};
-        );
+    /// Returns the next byte of input without consuming it, or `None` at the end of the input.
+    pub fn peek_byte(&mut self) -> Result<Option<u8>> {
+        with_input_port!(self, "peek-byte", |br| Ok(br.fill_buf()?.first().copied()))
+    }
 
-        match self {
-            SteelPortRepr::FileInput(_, br) => port_read_chr!(br),
-            SteelPortRepr::StdInput(br) => port_read_chr!(br),
-            _x => stop!(Generic => "read-char"),
-        }
+    /// Reads the next character, or returns `None` at the end of the input.
+    pub fn read_char(&mut self) -> Result<Option<char>> {
+        with_input_port!(self, "read-char", |br| {
+            let first = match br.fill_buf()?.first() {
+                Some(first) => *first,
+                None => return Ok(None),
+            };
+
+            let width = match first {
+                0x00..=0x7f => 1,
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => stop!(Generic => "read-char: input is not valid utf-8"),
+            };
+
+            let mut bytes = [0; 4];
+            br.read_exact(&mut bytes[..width])?;
+
+            match std::str::from_utf8(&bytes[..width]) {
+                Ok(c) => Ok(c.chars().next()),
+                Err(_) => stop!(Generic => "read-char: input is not valid utf-8"),
+            }
+        })
+    }
+
+    /// The offset in bytes of the next character from the start of the input, for ports that
+    /// can tell.
+    pub fn position(&mut self) -> Option<usize> {
+        let position = match self {
+            SteelPortRepr::FileInput(_, br) => br.stream_position(),
+            SteelPortRepr::StringInput(br) => br.stream_position(),
+            _ => return None,
+        };
+
+        position.ok().map(|position| position as usize)
     }
 
     pub fn write_char(&mut self, c: char) -> Result<()> {
@@ -222,6 +276,11 @@ impl SteelPortRepr {
                 write!(br, "{}", c)?;
                 br.flush()?;
             }
+            SteelPortRepr::StdError(out) => {
+                let mut br = out.lock();
+                write!(br, "{}", c)?;
+                br.flush()?;
+            }
             SteelPortRepr::DynWriter(o) => {
                 let mut br = o.lock().unwrap();
                 write!(br, "{}", c)?;
@@ -248,6 +307,11 @@ impl SteelPortRepr {
                 write!(br, "{}", string)?;
                 br.flush()?;
             }
+            SteelPortRepr::StdError(out) => {
+                let mut br = out.lock();
+                write!(br, "{}", string)?;
+                br.flush()?;
+            }
             SteelPortRepr::DynWriter(o) => {
                 let mut br = o.lock().unwrap();
                 write!(br, "{}", string)?;
@@ -271,6 +335,7 @@ impl SteelPortRepr {
         match self {
             SteelPortRepr::FileOutput(_, br) => write_string!(br),
             SteelPortRepr::StdOutput(br) => write_string!(br),
+            SteelPortRepr::StdError(br) => write_string!(br),
             SteelPortRepr::ChildStdInput(br) => write_string!(br),
             SteelPortRepr::StringOutput(br) => write_string!(br),
             SteelPortRepr::DynWriter(br) => {
@@ -286,7 +351,10 @@ impl SteelPortRepr {
     pub fn is_input(&self) -> bool {
         matches!(
             self,
-            SteelPortRepr::FileInput(_, _) | SteelPortRepr::StdInput(_)
+            SteelPortRepr::FileInput(_, _)
+                | SteelPortRepr::StdInput(_)
+                | SteelPortRepr::StringInput(_)
+                | SteelPortRepr::ChildStdOutput(_)
         )
     }
 
@@ -295,6 +363,7 @@ impl SteelPortRepr {
             self,
             SteelPortRepr::FileOutput(_, _)
                 | SteelPortRepr::StdOutput(_)
+                | SteelPortRepr::StdError(_)
                 | SteelPortRepr::DynWriter(_)
         )
     }
@@ -305,6 +374,7 @@ impl SteelPortRepr {
             SteelPortRepr::FileInput(_, _)
                 | SteelPortRepr::FileOutput(_, _)
                 | SteelPortRepr::StdOutput(_)
+                | SteelPortRepr::StdError(_)
                 | SteelPortRepr::StdInput(_)
         )
     }
@@ -323,7 +393,9 @@ impl SteelPortRepr {
 
     pub fn close_output_port(&mut self) -> Result<()> {
         match self {
-            SteelPortRepr::FileOutput(_, _) | SteelPortRepr::StdOutput(_) => {
+            SteelPortRepr::FileOutput(_, _)
+            | SteelPortRepr::StdOutput(_)
+            | SteelPortRepr::StdError(_) => {
                 *self = SteelPortRepr::Closed;
                 Ok(())
             }
@@ -391,10 +463,18 @@ impl SteelPort {
         self.port.borrow_mut().read_all_str()
     }
 
-    pub fn read_char(&self) -> Result<(usize, char)> {
+    pub fn peek_byte(&self) -> Result<Option<u8>> {
+        self.port.borrow_mut().peek_byte()
+    }
+
+    pub fn read_char(&self) -> Result<Option<char>> {
         self.port.borrow_mut().read_char()
     }
 
+    pub fn position(&self) -> Option<usize> {
+        self.port.borrow_mut().position()
+    }
+
     pub fn write_char(&self, c: char) -> Result<()> {
         self.port.borrow_mut().write_char(c)
     }
@@ -435,9 +515,7 @@ impl SteelPort {
         if cfg!(test) {
             // Write out to thread safe port
             SteelPort {
-                port: new_rc_ref_cell(SteelPortRepr::DynWriter(Arc::new(Mutex::new(
-                    BufWriter::new(Vec::new()),
-                )))),
+                port: new_rc_ref_cell(SteelPortRepr::DynWriter(TEST_OUTPUT.with(Arc::clone))),
             }
         } else {
             SteelPort {
@@ -446,6 +524,17 @@ impl SteelPort {
         }
     }
 
+    /// Whether the writer is the one the default output port writes to in place of stdout
+    pub(crate) fn is_default_output_writer(writer: &Arc<Mutex<dyn Write + Send + Sync>>) -> bool {
+        TEST_OUTPUT.with(|output| std::ptr::addr_eq(Arc::as_ptr(output), Arc::as_ptr(writer)))
+    }
+
+    pub fn default_current_error_port() -> Self {
+        SteelPort {
+            port: new_rc_ref_cell(SteelPortRepr::StdError(io::stderr())),
+        }
+    }
+
     pub fn get_output_string(&self) -> Result<String> {
         self.port.borrow_mut().get_output_string()
     }
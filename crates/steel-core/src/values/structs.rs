@@ -109,6 +109,58 @@ impl StructTypeDescriptor {
     }
 }
 
+type StructFunctionPointer = Arc<dyn Fn(&[SteelVal]) -> Result<SteelVal> + Send + Sync + 'static>;
+
+/// The functions generated for a struct type. The closures themselves are opaque, so the
+/// boxed function carries which one it is in order to rebuild it when restoring a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum StructFunction {
+    Constructor { len: usize },
+    ConstructorWithOptions { len: usize },
+    Predicate,
+    Getter,
+    GetterIndex { index: usize },
+}
+
+impl StructFunction {
+    fn build_with(
+        self,
+        descriptor: StructTypeDescriptor,
+        function: StructFunctionPointer,
+        name: Option<Arc<String>>,
+        arity: Option<usize>,
+    ) -> SteelVal {
+        let mut function = BoxedDynFunction::new_owned(function, name, arity);
+        function.struct_function = Some((self, descriptor));
+
+        SteelVal::BoxedFunction(Rc::new(function))
+    }
+
+    /// Finds out which struct function this is, if it is one
+    pub(crate) fn lookup(
+        function: &BoxedDynFunction,
+    ) -> Option<(StructFunction, StructTypeDescriptor)> {
+        function.struct_function
+    }
+
+    /// Builds a new copy of this function for the given struct type
+    pub(crate) fn build(self, descriptor: StructTypeDescriptor) -> SteelVal {
+        match self {
+            StructFunction::Constructor { len } => {
+                UserDefinedStruct::constructor(descriptor.name(), len, descriptor)
+            }
+            StructFunction::ConstructorWithOptions { len } => {
+                UserDefinedStruct::constructor_with_options(len, descriptor)
+            }
+            StructFunction::Predicate => UserDefinedStruct::predicate(descriptor),
+            StructFunction::Getter => UserDefinedStruct::getter_prototype(descriptor),
+            StructFunction::GetterIndex { index } => {
+                UserDefinedStruct::getter_prototype_index(descriptor, index)
+            }
+        }
+    }
+}
+
 pub struct SerializableUserDefinedStruct {
     pub(crate) fields: Vec<SerializableSteelVal>,
 
@@ -140,6 +192,39 @@ impl UserDefinedStruct {
             .and_then(|x| x.as_bool())
             .unwrap_or_default()
     }
+
+    pub(crate) fn fields(&self) -> &[SteelVal] {
+        &self.fields
+    }
+
+    /// The names of the fields, in order, if the struct was declared with them
+    pub(crate) fn field_names(&self) -> Option<Vec<SteelString>> {
+        let names = FIELDS_KEY.with(|key| self.get(key))?;
+
+        names
+            .list()?
+            .iter()
+            .map(|x| match x {
+                SteelVal::SymbolV(s) => Some(s.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Constructs an instance of the most recently declared struct type named `name` with exactly
+    /// these fields. If there isn't one, a transparent struct type is declared for it.
+    pub(crate) fn with_named_fields(
+        name: &str,
+        field_names: &[&'static str],
+        fields: Vec<SteelVal>,
+    ) -> Self {
+        let type_descriptor = VTable::find_or_declare(name, field_names);
+
+        Self {
+            fields: fields.into_iter().collect(),
+            type_descriptor,
+        }
+    }
 }
 
 // TODO: This could blow the stack for big trees...
@@ -172,7 +257,7 @@ impl std::fmt::Display for UserDefinedStruct {
 }
 
 impl UserDefinedStruct {
-    fn new(
+    pub(crate) fn new(
         // name: InternedString,
         type_descriptor: StructTypeDescriptor,
         fields: &[SteelVal],
@@ -340,11 +425,12 @@ impl UserDefinedStruct {
             Ok(SteelVal::CustomStruct(Gc::new(new_struct)))
         };
 
-        SteelVal::BoxedFunction(Rc::new(BoxedDynFunction::new_owned(
+        StructFunction::ConstructorWithOptions { len }.build_with(
+            descriptor,
             Arc::new(f),
             Some(descriptor.name().resolve().to_string().into()),
             Some(len),
-        )))
+        )
     }
 
     fn constructor(
@@ -372,11 +458,12 @@ impl UserDefinedStruct {
             Ok(SteelVal::CustomStruct(Gc::new(new_struct)))
         };
 
-        SteelVal::BoxedFunction(Rc::new(BoxedDynFunction::new_owned(
+        StructFunction::Constructor { len }.build_with(
+            type_descriptor,
             Arc::new(f),
             Some(name.resolve().to_string().into()),
             Some(len),
-        )))
+        )
     }
 
     fn predicate(descriptor: StructTypeDescriptor) -> SteelVal {
@@ -400,12 +487,12 @@ impl UserDefinedStruct {
             }))
         };
 
-        SteelVal::BoxedFunction(Rc::new(BoxedDynFunction::new_owned(
+        StructFunction::Predicate.build_with(
+            descriptor,
             Arc::new(f),
-            // Some(out_name),
             Some(descriptor.name().resolve().to_string().into()),
             Some(1),
-        )))
+        )
     }
 
     fn getter_prototype(descriptor: StructTypeDescriptor) -> SteelVal {
@@ -448,12 +535,12 @@ impl UserDefinedStruct {
             }
         };
 
-        SteelVal::BoxedFunction(Rc::new(BoxedDynFunction::new_owned(
+        StructFunction::Getter.build_with(
+            descriptor,
             Arc::new(f),
-            // Some(out_name),
             Some(descriptor.name().resolve().to_string().into()),
             Some(2),
-        )))
+        )
     }
 
     fn getter_prototype_index(descriptor: StructTypeDescriptor, index: usize) -> SteelVal {
@@ -491,11 +578,12 @@ impl UserDefinedStruct {
             }
         };
 
-        SteelVal::BoxedFunction(Rc::new(BoxedDynFunction::new_owned(
+        StructFunction::GetterIndex { index }.build_with(
+            descriptor,
             Arc::new(f),
             Some(descriptor.name().resolve().to_string().into()),
             Some(1),
-        )))
+        )
     }
 
     // pub fn properties(&self) -> SteelVal {
@@ -651,6 +739,41 @@ pub struct VTable {
 }
 
 impl VTable {
+    fn find_or_declare(name: &str, field_names: &[&'static str]) -> StructTypeDescriptor {
+        let field_names = field_names
+            .iter()
+            .map(|x| SteelVal::SymbolV((*x).into()))
+            .collect::<List<_>>();
+
+        let existing = VTABLE.with(|x| {
+            x.borrow()
+                .entries
+                .iter()
+                .rposition(|entry| {
+                    entry.name.resolve() == name
+                        && FIELDS_KEY.with(|key| entry.properties.get(key).cloned())
+                            == Some(SteelVal::ListV(field_names.clone()))
+                })
+                .map(StructTypeDescriptor)
+        });
+
+        existing.unwrap_or_else(|| {
+            let name: InternedString = name.into();
+            let descriptor = Self::new_entry(name, None);
+
+            let properties = im_rc::hashmap! {
+                TRANSPARENT_KEY.with(|x| x.clone()) => SteelVal::BoolV(true),
+                MUTABLE_KEY.with(|x| x.clone()) => SteelVal::BoolV(false),
+                FIELDS_KEY.with(|x| x.clone()) => SteelVal::ListV(field_names),
+                SteelVal::SymbolV("#:name".into()) => SteelVal::SymbolV(name.resolve().into()),
+            };
+
+            Self::set_entry(&descriptor, None, Gc::new(properties));
+
+            descriptor
+        })
+    }
+
     fn insert(name: InternedString, options: Gc<im_rc::HashMap<SteelVal, SteelVal>>) {
         VTABLE.with(|x| x.borrow_mut().map.insert(name, options));
     }
@@ -713,6 +836,22 @@ impl VTable {
         }
     }
 
+    /// The name, procedure index, and properties of an entry
+    pub(crate) fn entry(
+        descriptor: &StructTypeDescriptor,
+    ) -> (
+        InternedString,
+        Option<usize>,
+        Gc<im_rc::HashMap<SteelVal, SteelVal>>,
+    ) {
+        VTABLE.with(|x| {
+            let guard = x.borrow();
+            let entry = &guard.entries[descriptor.0];
+
+            (entry.name, entry.proc, entry.properties.clone())
+        })
+    }
+
     // Returns a type descriptor, in this case it is just a usize
     pub fn new_entry(name: InternedString, proc: Option<usize>) -> StructTypeDescriptor {
         VTABLE.with(|x| {
@@ -8,30 +8,374 @@ use std::collections::HashMap;
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
 use syn::{
-    punctuated::Punctuated, Data, DeriveInput, Expr, ExprLit, FnArg, Ident, ItemFn, Lit, Meta,
-    ReturnType, Signature, Type, TypeReference,
+    punctuated::Punctuated, Attribute, Data, DataEnum, DeriveInput, Expr, ExprLit, Fields, FnArg,
+    Ident, Index, ItemFn, Lit, LitStr, Member, Meta, ReturnType, Signature, Type, TypeReference,
 };
 
-#[proc_macro_derive(Steel)]
+/// Implements `Custom` for the type, so that it can be passed to and from Steel.
+///
+/// With `#[steel(register)]` on the type, this also generates a `register_into` function that
+/// registers a Steel API for it into a `BuiltInModule`, using the Rust doc comments as the docs:
+///
+/// * For structs: the constructor `Name`, the predicate `Name?`, and for each field a getter
+///   `Name-field` and a setter `set-Name-field!`. Fields of tuple structs are named by their index.
+/// * For enums: the predicate `Name?`, and for each variant a constructor `Name-Variant`, a predicate
+///   `Name-Variant?` and a getter `Name-Variant-field` for each of its fields.
+///
+/// Underscores in field names are replaced with dashes. The following attributes are supported:
+///
+/// * `#[steel(name = "...")]` on the type, a variant or a field sets the name used on the Steel side
+/// * `#[steel(skip)]` on a field leaves it out of the API, and uses `Default::default()` for it in
+///   constructors. On a variant, no functions are generated for it at all.
+/// * `#[steel(read_only)]` on a field doesn't generate a setter for it
+#[proc_macro_derive(Steel, attributes(steel))]
 pub fn derive_steel(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
 
-    match &input.data {
-        Data::Struct(_) | Data::Enum(_) => {
-            let gen = quote! {
-                impl steel::rvals::Custom for #name {}
+    let custom = match &input.data {
+        Data::Struct(_) | Data::Enum(_) => quote! {
+            impl steel::rvals::Custom for #name {}
+        },
+        _ => {
+            let output = quote! { #input };
+            return output.into();
+        }
+    };
+
+    let options = match SteelOptions::parse(&input.attrs) {
+        Ok(options) => options,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    if !options.register {
+        return custom.into();
+    }
+
+    if !input.generics.params.is_empty() {
+        return syn::Error::new_spanned(
+            &input.generics,
+            "`#[steel(register)]` is not supported on generic types",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let type_name = options.name.unwrap_or_else(|| name.to_string());
+
+    let functions = match &input.data {
+        Data::Struct(data) => struct_functions(name, &type_name, &input.attrs, &data.fields),
+        Data::Enum(data) => enum_functions(name, &type_name, data),
+        _ => unreachable!(),
+    };
+
+    let functions = match functions {
+        Ok(functions) => functions,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let register_doc = format!(
+        "Registers the Steel functions for [`{name}`] into `module`, see `#[derive(Steel)]`"
+    );
+
+    let output = quote! {
+        #custom
+
+        impl #name {
+            #[doc = #register_doc]
+            pub fn register_into(module: &mut steel::steel_vm::builtin::BuiltInModule) {
+                #(#functions)*
+            }
+        }
+    };
+
+    output.into()
+}
+
+// Options from the `#[steel(...)]` attributes on a type, variant or field
+#[derive(Default)]
+struct SteelOptions {
+    name: Option<String>,
+    register: bool,
+    skip: bool,
+    read_only: bool,
+}
+
+impl SteelOptions {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = SteelOptions::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("steel")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    options.name = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("register") {
+                    options.register = true;
+                } else if meta.path.is_ident("skip") {
+                    options.skip = true;
+                } else if meta.path.is_ident("read_only") {
+                    options.read_only = true;
+                } else {
+                    return Err(meta.error("unknown steel attribute"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(options)
+    }
+}
+
+struct SteelField<'a> {
+    member: Member,
+    ty: &'a Type,
+    name: String,
+    doc: Option<String>,
+    options: SteelOptions,
+}
+
+fn steel_fields(fields: &Fields) -> syn::Result<Vec<SteelField<'_>>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let options = SteelOptions::parse(&field.attrs)?;
+
+            let (member, default_name) = match &field.ident {
+                Some(ident) => (
+                    Member::Named(ident.clone()),
+                    ident.to_string().replace('_', "-"),
+                ),
+                None => (Member::Unnamed(Index::from(index)), index.to_string()),
             };
 
-            gen.into()
+            Ok(SteelField {
+                member,
+                ty: &field.ty,
+                name: options.name.clone().unwrap_or(default_name),
+                doc: parse_doc_comment(&field.attrs),
+                options,
+            })
+        })
+        .collect()
+}
+
+// Defines a native function taking exactly `arity` arguments, with `body` evaluating to its result,
+// and registers it into `module` along with its docs
+fn native_function(
+    name: &str,
+    arity: usize,
+    doc: Option<&String>,
+    body: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let name_with_colon = format!("{name}: ");
+
+    let doc = doc.map(|doc| {
+        quote! {
+            module.register_doc(#name, steel::steel_vm::builtin::MarkdownDoc(#doc));
         }
-        _ => {
-            let output = quote! { #input };
-            output.into()
+    });
+
+    quote! {
+        {
+            #[allow(unused_imports)]
+            fn native(args: &[steel::SteelVal]) -> steel::rvals::Result<steel::SteelVal> {
+                use steel::rvals::{AsRefMutSteelVal, AsRefSteelVal, FromSteelVal, IntoSteelVal};
+
+                if args.len() != #arity {
+                    steel::stop!(ArityMismatch => format!("{} expected {} arguments, got {}", #name, #arity, args.len()))
+                }
+
+                #[allow(dead_code)]
+                fn err_thunk(mut err: steel::rerrs::SteelErr) -> steel::rerrs::SteelErr {
+                    err.prepend_message(#name_with_colon);
+                    err.set_kind(steel::rerrs::ErrorKind::TypeMismatch);
+                    err
+                }
+
+                #body
+            }
+
+            module.register_native_fn(#name, native, steel::steel_vm::builtin::Arity::Exact(#arity));
+            #doc
         }
     }
 }
 
+// Builds the value at `path` out of the arguments, using the default value for skipped fields
+fn constructor_body(
+    path: proc_macro2::TokenStream,
+    fields: &[SteelField],
+) -> proc_macro2::TokenStream {
+    let mut index = 0usize;
+
+    let values = fields.iter().map(|field| {
+        let member = &field.member;
+        let ty = field.ty;
+
+        if field.options.skip {
+            quote! { #member: Default::default() }
+        } else {
+            let value = quote! {
+                #member: <#ty as FromSteelVal>::from_steelval(&args[#index]).map_err(err_thunk)?
+            };
+            index += 1;
+            value
+        }
+    });
+
+    quote! {
+        #path { #(#values,)* }.into_steelval()
+    }
+}
+
+fn predicate(name: &str, ty: &Ident, doc: String) -> proc_macro2::TokenStream {
+    native_function(
+        name,
+        1,
+        Some(&doc),
+        quote! {
+            Ok(steel::SteelVal::BoolV(<#ty as AsRefSteelVal>::as_ref(&args[0], &mut ()).is_ok()))
+        },
+    )
+}
+
+fn struct_functions(
+    ty: &Ident,
+    type_name: &str,
+    attrs: &[Attribute],
+    fields: &Fields,
+) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+    let fields = steel_fields(fields)?;
+    let exposed = fields
+        .iter()
+        .filter(|x| !x.options.skip)
+        .collect::<Vec<_>>();
+
+    let mut functions = vec![
+        native_function(
+            type_name,
+            exposed.len(),
+            parse_doc_comment(attrs).as_ref(),
+            constructor_body(quote! { #ty }, &fields),
+        ),
+        predicate(
+            &format!("{type_name}?"),
+            ty,
+            format!("Returns `#t` if the value is a `{type_name}`"),
+        ),
+    ];
+
+    for field in exposed {
+        let member = &field.member;
+        let field_ty = field.ty;
+
+        functions.push(native_function(
+            &format!("{type_name}-{}", field.name),
+            1,
+            field.doc.as_ref(),
+            quote! {
+                let mut nursery = ();
+                let this = <#ty as AsRefSteelVal>::as_ref(&args[0], &mut nursery).map_err(err_thunk)?;
+                this.#member.clone().into_steelval()
+            },
+        ));
+
+        if !field.options.read_only {
+            functions.push(native_function(
+                &format!("set-{type_name}-{}!", field.name),
+                2,
+                None,
+                quote! {
+                    let value = <#field_ty as FromSteelVal>::from_steelval(&args[1]).map_err(err_thunk)?;
+                    let mut this = <#ty as AsRefMutSteelVal>::as_mut_ref(&args[0]).map_err(err_thunk)?;
+                    this.#member = value;
+                    Ok(steel::SteelVal::Void)
+                },
+            ));
+        }
+    }
+
+    Ok(functions)
+}
+
+fn enum_functions(
+    ty: &Ident,
+    type_name: &str,
+    data: &DataEnum,
+) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+    let mut functions = vec![predicate(
+        &format!("{type_name}?"),
+        ty,
+        format!("Returns `#t` if the value is a `{type_name}`"),
+    )];
+
+    for variant in &data.variants {
+        let options = SteelOptions::parse(&variant.attrs)?;
+
+        if options.skip {
+            continue;
+        }
+
+        let ident = &variant.ident;
+        let steel_variant = options.name.unwrap_or_else(|| ident.to_string());
+        let variant_name = format!("{type_name}-{steel_variant}");
+
+        let fields = steel_fields(&variant.fields)?;
+        let exposed = fields
+            .iter()
+            .filter(|x| !x.options.skip)
+            .collect::<Vec<_>>();
+
+        functions.push(native_function(
+            &variant_name,
+            exposed.len(),
+            parse_doc_comment(&variant.attrs).as_ref(),
+            constructor_body(quote! { #ty::#ident }, &fields),
+        ));
+
+        functions.push(native_function(
+            &format!("{variant_name}?"),
+            1,
+            Some(&format!(
+                "Returns `#t` if the value is a `{type_name}` of the `{steel_variant}` variant"
+            )),
+            quote! {
+                Ok(steel::SteelVal::BoolV(matches!(
+                    <#ty as AsRefSteelVal>::as_ref(&args[0], &mut ()).as_deref(),
+                    Ok(#ty::#ident { .. })
+                )))
+            },
+        ));
+
+        for field in exposed {
+            let member = &field.member;
+            let getter_name = format!("{variant_name}-{}", field.name);
+            let expected = format!(
+                "{getter_name}: expected a {type_name} of the {steel_variant} variant, found: {{}}"
+            );
+
+            functions.push(native_function(
+                &getter_name,
+                1,
+                field.doc.as_ref(),
+                quote! {
+                    let mut nursery = ();
+                    let this = <#ty as AsRefSteelVal>::as_ref(&args[0], &mut nursery).map_err(err_thunk)?;
+
+                    match &*this {
+                        #ty::#ident { #member: value, .. } => value.clone().into_steelval(),
+                        _ => steel::stop!(TypeMismatch => #expected, args[0]),
+                    }
+                },
+            ));
+        }
+    }
+
+    Ok(functions)
+}
+
 fn parse_key_value_pairs(args: &Punctuated<Meta, Token![,]>) -> HashMap<String, String> {
     let mut map = HashMap::new();
 
@@ -58,13 +402,12 @@ fn parse_key_value_pairs(args: &Punctuated<Meta, Token![,]>) -> HashMap<String,
     map
 }
 
-fn parse_doc_comment(input: ItemFn) -> Option<String> {
-    let maybe_str_literals = input
-        .attrs
-        .into_iter()
-        .filter_map(|attr| match attr.meta {
+fn parse_doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let maybe_str_literals = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
             Meta::NameValue(name_value) if name_value.path.is_ident("doc") => {
-                Some(name_value.value)
+                Some(name_value.value.clone())
             }
             _ => None,
         })
@@ -117,7 +460,7 @@ pub fn define_module(
 
     let sign: Signature = input.sig.clone();
 
-    let maybe_doc_comments = parse_doc_comment(input.clone());
+    let maybe_doc_comments = parse_doc_comment(&input.attrs);
 
     let function_name = sign.ident;
 
@@ -172,7 +515,7 @@ pub fn native(
     let modified_input = input.clone();
     let sign: Signature = input.clone().sig;
 
-    let maybe_doc_comments = parse_doc_comment(input);
+    let maybe_doc_comments = parse_doc_comment(&input.attrs);
     let function_name = sign.ident.clone();
 
     let doc_name = Ident::new(
@@ -250,7 +593,7 @@ pub fn function(
     // let ident = input.sig.ident.clone();
     let sign: Signature = input.clone().sig;
 
-    let maybe_doc_comments = parse_doc_comment(input);
+    let maybe_doc_comments = parse_doc_comment(&input.attrs);
 
     // modified_input.attrs = Vec::new();
 
@@ -32,6 +32,13 @@ macro_rules! declare_opcodes {
         pub static PATTERNS: &'static [&'static [(OpCode, usize)]] = &[
                 $( &[ $(($k, $v)),* ] ),* ,
         ];
+
+        /// The name of every op code, in declaration order. Serialized programs refer to op codes by their
+        /// position in this table, so any change to it makes previously serialized programs unreadable.
+        pub static OPCODE_NAMES: &'static [&'static str] = &[
+            $(stringify!($variant)),* ,
+            $(stringify!($super)),*
+        ];
     }
 
 }
@@ -714,6 +714,25 @@ impl From<Define> for ExprKind {
     }
 }
 
+/// How the arguments of a call get matched up with the arguments of a lambda, for lambdas that
+/// take more than a fixed list of positional arguments and maybe a rest argument.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LambdaSignature {
+    /// `(a [b default] #:c c #:d [d default])` - the arguments of the lambda are the required
+    /// positional arguments, then the optional ones, then one per keyword in the order they were
+    /// declared, then the rest argument if there is one.
+    Parameters {
+        required: usize,
+        optional: usize,
+        /// Each keyword, and whether it has to be passed
+        keywords: Vec<(InternedString, bool)>,
+    },
+    /// The clauses of a `case-lambda`, as the number of arguments each one takes and whether it
+    /// also takes a rest argument. The first argument of the lambda is the index of the clause
+    /// that matched, followed by the arguments of that clause.
+    Cases(Vec<(usize, bool)>),
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LambdaFunction {
     pub args: Vec<ExprKind>,
@@ -721,6 +740,8 @@ pub struct LambdaFunction {
     pub location: SyntaxObject,
     pub rest: bool,
     pub syntax_object_id: usize,
+    #[serde(default)]
+    pub signature: Option<LambdaSignature>,
 }
 
 impl Clone for LambdaFunction {
@@ -731,6 +752,7 @@ impl Clone for LambdaFunction {
             location: self.location.clone(),
             rest: self.rest,
             syntax_object_id: SyntaxObjectId::fresh().0,
+            signature: self.signature.clone(),
         }
     }
 }
@@ -741,6 +763,7 @@ impl PartialEq for LambdaFunction {
             && self.body == other.body
             && self.location == other.location
             && self.rest == other.rest
+            && self.signature == other.signature
     }
 }
 
@@ -791,6 +814,7 @@ impl LambdaFunction {
             location,
             rest: false,
             syntax_object_id: SyntaxObjectId::fresh().0,
+            signature: None,
         }
     }
 
@@ -801,6 +825,7 @@ impl LambdaFunction {
             location,
             rest: true,
             syntax_object_id: SyntaxObjectId::fresh().0,
+            signature: None,
         }
     }
 
@@ -816,6 +841,7 @@ impl LambdaFunction {
             location,
             rest,
             syntax_object_id: SyntaxObjectId::fresh().0,
+            signature: None,
         }
     }
 
@@ -1095,8 +1121,10 @@ impl List {
         }
     }
 
+    // Lambdas with a signature can't be bound like a let, since the arguments have to be matched
+    // up by the vm, so calls to them don't count as anonymous function calls
     pub fn is_anonymous_function_call(&self) -> bool {
-        matches!(self.args.get(0), Some(ExprKind::LambdaFunction(_)))
+        matches!(self.args.first(), Some(ExprKind::LambdaFunction(l)) if l.signature.is_none())
     }
 
     pub fn is_a_builtin_expr(&self) -> bool {
@@ -1104,18 +1132,16 @@ impl List {
     }
 
     pub fn first_func_mut(&mut self) -> Option<&mut LambdaFunction> {
-        if let Some(ExprKind::LambdaFunction(l)) = self.args.get_mut(0) {
-            Some(l)
-        } else {
-            None
+        match self.args.first_mut() {
+            Some(ExprKind::LambdaFunction(l)) if l.signature.is_none() => Some(l),
+            _ => None,
         }
     }
 
     pub fn first_func(&self) -> Option<&LambdaFunction> {
-        if let Some(ExprKind::LambdaFunction(l)) = self.args.get(0) {
-            Some(l)
-        } else {
-            None
+        match self.args.first() {
+            Some(ExprKind::LambdaFunction(l)) if l.signature.is_none() => Some(l),
+            _ => None,
         }
     }
 }
@@ -2036,17 +2062,20 @@ pub fn parse_lambda(a: Atom, value: Vec<ExprKind>) -> Result<ExprKind, ParseErro
             let args = l.args;
 
             for arg in &args {
-                if let ExprKind::Atom(_) = arg {
-                    continue;
-                } else {
-                    return Err(ParseError::SyntaxError(
-                        format!(
-                            "lambda function expects a list of identifiers, found: {}",
-                            List::new(args)
-                        ),
-                        syn.span,
-                        None,
-                    ));
+                match arg {
+                    ExprKind::Atom(_) => {}
+                    // An optional argument with its default, `[x default]`
+                    ExprKind::List(l) if l.len() == 2 && l.args[0].atom_identifier().is_some() => {}
+                    _ => {
+                        return Err(ParseError::SyntaxError(
+                            format!(
+                                "lambda function expects a list of identifiers, found: {}",
+                                List::new(args)
+                            ),
+                            syn.span,
+                            None,
+                        ));
+                    }
                 }
             }
 
@@ -1,8 +1,20 @@
 #[macro_use]
 mod repl;
 mod highlight;
+pub mod server;
+
+pub use server::ReplServerAddress;
 
 /// Run the Steel repl with the given `Engine`. Exits on IO error or when the user requests to exit.
 pub fn run_repl(vm: steel::steel_vm::engine::Engine) -> std::io::Result<()> {
     repl::repl_base(vm)
 }
+
+/// Run a network repl server with the given `Engine`, for use by editors and other tooling.
+/// See [`server`] for a description of the protocol. Blocks until the listener fails.
+pub fn run_repl_server(
+    vm: steel::steel_vm::engine::Engine,
+    address: ReplServerAddress,
+) -> std::io::Result<()> {
+    server::run_server(vm, address)
+}
@@ -0,0 +1,641 @@
+//! A network repl, for driving a long lived `Engine` from an editor or other tooling.
+//!
+//! The server only listens on loopback (or a unix domain socket), and speaks a simple framed protocol.
+//! Every message in either direction is a frame: the length of the payload in bytes, written in ascii
+//! decimal and terminated by a newline, followed by the payload itself. The payload is a json object.
+//!
+//! Requests carry an `op`, and optionally an `id` which is echoed back on the response:
+//!
+//! ```text
+//! {"id": 1, "op": "eval", "code": "(+ 1 2 3)"}
+//! {"id": 2, "op": "eval", "code": "(foo 10)", "module": "path/to/module.scm"}
+//! {"id": 3, "op": "load-file", "path": "path/to/file.scm"}
+//! {"id": 4, "op": "complete", "prefix": "hash-"}
+//! {"id": 5, "op": "doc", "symbol": "hash-insert"}
+//! {"id": 6, "op": "interrupt"}
+//! ```
+//!
+//! An `eval` with a `module` runs the code inside of that module, loading it first if needed, so
+//! that its private definitions are in scope.
+//!
+//! Responses always include a `status` of either `"ok"` or `"error"`. Errors report the kind of error,
+//! the message, and when span information is available, the rendered report that the repl would print.
+//!
+//! Evaluation happens one request at a time on the thread that started the server, against a single
+//! shared `Engine` - definitions made by one connection are visible to all of the others. `interrupt`
+//! is handled as soon as it is read, and stops whatever evaluation is currently in progress.
+
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Map, Value};
+use steel::rerrs::SteelErr;
+use steel::rvals::SteelVal;
+use steel::steel_vm::engine::Engine;
+
+/// Frames larger than this are rejected, rather than attempting to allocate space for them.
+const MAX_FRAME_SIZE: usize = 64 * 1024 * 1024;
+
+/// Where the repl server should listen for connections.
+#[derive(Debug, Clone)]
+pub enum ReplServerAddress {
+    /// Listen on the given port on `127.0.0.1`. A port of `0` picks any free port.
+    Tcp(u16),
+    /// Listen on a unix domain socket at the given path.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+type SharedWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+struct Job {
+    request: Map<String, Value>,
+    writer: SharedWriter,
+}
+
+/// Reads a single frame from the stream. Returns `Ok(None)` if the stream was closed
+/// cleanly before the start of a frame.
+pub fn read_frame<R: BufRead>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut header = String::new();
+
+    if reader.read_line(&mut header)? == 0 {
+        return Ok(None);
+    }
+
+    let length = header.trim().parse::<usize>().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid frame header: {:?}", header.trim()),
+        )
+    })?;
+
+    if length > MAX_FRAME_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {length} bytes exceeds the maximum frame size"),
+        ));
+    }
+
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload)?;
+
+    Ok(Some(payload))
+}
+
+/// Writes a single frame to the stream.
+pub fn write_frame<W: Write + ?Sized>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+    writeln!(writer, "{}", payload.len())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+fn send(writer: &SharedWriter, response: Value) {
+    let payload = response.to_string();
+
+    // If the client has gone away there is nobody left to tell
+    let _ = write_frame(&mut **writer.lock().unwrap(), payload.as_bytes());
+}
+
+fn response(id: Option<&Value>, status: &str) -> Map<String, Value> {
+    let mut map = Map::new();
+
+    if let Some(id) = id {
+        map.insert("id".to_string(), id.clone());
+    }
+
+    map.insert("status".to_string(), Value::from(status));
+    map
+}
+
+fn error_response(id: Option<&Value>, kind: &str, message: String) -> Value {
+    let mut map = response(id, "error");
+    map.insert(
+        "error".to_string(),
+        json!({ "kind": kind, "message": message }),
+    );
+    Value::Object(map)
+}
+
+fn string_field<'a>(request: &'a Map<String, Value>, field: &str) -> Option<&'a str> {
+    request.get(field).and_then(|x| x.as_str())
+}
+
+/// Run the repl server, evaluating requests against the given `Engine`. This blocks the current thread
+/// until the listener fails.
+pub fn run_server(vm: Engine, address: ReplServerAddress) -> io::Result<()> {
+    match address {
+        ReplServerAddress::Tcp(port) => {
+            let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))?;
+            serve_tcp(vm, listener)
+        }
+        #[cfg(unix)]
+        ReplServerAddress::Unix(path) => {
+            let listener = std::os::unix::net::UnixListener::bind(&path)?;
+            let result = serve_unix(vm, listener);
+            let _ = std::fs::remove_file(&path);
+            result
+        }
+    }
+}
+
+/// Serve repl requests from connections accepted on an already bound listener. Connections that do not
+/// originate from a loopback address are dropped.
+pub fn serve_tcp(vm: Engine, listener: TcpListener) -> io::Result<()> {
+    let local = listener.local_addr()?;
+
+    if !local.ip().is_loopback() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("refusing to serve the repl on non loopback address: {local}"),
+        ));
+    }
+
+    serve(vm, move |on_connection| {
+        for stream in listener.incoming() {
+            let stream = stream?;
+
+            if !stream.peer_addr()?.ip().is_loopback() {
+                continue;
+            }
+
+            let writer = stream.try_clone()?;
+            on_connection(Box::new(stream), Box::new(writer));
+        }
+
+        Ok(())
+    })
+}
+
+/// Serve repl requests from connections accepted on an already bound unix domain socket.
+#[cfg(unix)]
+pub fn serve_unix(vm: Engine, listener: std::os::unix::net::UnixListener) -> io::Result<()> {
+    serve(vm, move |on_connection| {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let writer = stream.try_clone()?;
+            on_connection(Box::new(stream), Box::new(writer));
+        }
+
+        Ok(())
+    })
+}
+
+type OnConnection<'a> = &'a mut dyn FnMut(Box<dyn Read + Send>, Box<dyn Write + Send>);
+
+fn serve<F>(vm: Engine, accept: F) -> io::Result<()>
+where
+    F: FnOnce(OnConnection) -> io::Result<()> + Send + 'static,
+{
+    let mut session = Session::new(vm);
+
+    let (sender, receiver) = channel::<Job>();
+    let interrupt = session.vm.interrupt_handle();
+    let evaluating = Arc::clone(&session.evaluating);
+
+    let acceptor = std::thread::spawn(move || {
+        accept(&mut |reader, writer| {
+            let sender = sender.clone();
+            let interrupt = Arc::clone(&interrupt);
+            let evaluating = Arc::clone(&evaluating);
+
+            std::thread::spawn(move || {
+                handle_connection(reader, writer, sender, interrupt, evaluating)
+            });
+        })
+    });
+
+    // The engine can't leave this thread, so all of the evaluation happens here
+    for Job { request, writer } in receiver {
+        let response = session.handle(&request);
+        send(&writer, response);
+    }
+
+    acceptor
+        .join()
+        .unwrap_or_else(|_| Err(io::Error::other("acceptor panicked")))
+}
+
+fn handle_connection(
+    reader: Box<dyn Read + Send>,
+    writer: Box<dyn Write + Send>,
+    sender: Sender<Job>,
+    interrupt: Arc<AtomicBool>,
+    evaluating: Arc<AtomicBool>,
+) {
+    let mut reader = BufReader::new(reader);
+    let writer: SharedWriter = Arc::new(Mutex::new(writer));
+
+    loop {
+        let frame = match read_frame(&mut reader) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => return,
+            Err(e) => {
+                // We can't recover the framing after a bad frame, so report and hang up
+                send(&writer, error_response(None, "Protocol", e.to_string()));
+                return;
+            }
+        };
+
+        let request = match serde_json::from_slice::<Value>(&frame) {
+            Ok(Value::Object(request)) => request,
+            Ok(_) => {
+                send(
+                    &writer,
+                    error_response(
+                        None,
+                        "Protocol",
+                        "request must be a json object".to_string(),
+                    ),
+                );
+                continue;
+            }
+            Err(e) => {
+                send(&writer, error_response(None, "Protocol", e.to_string()));
+                continue;
+            }
+        };
+
+        // Interrupts can't wait in line behind the evaluation they're trying to stop
+        if string_field(&request, "op") == Some("interrupt") {
+            let running = evaluating.load(Ordering::SeqCst);
+
+            if running {
+                interrupt.store(true, Ordering::SeqCst);
+            }
+
+            let mut response = response(request.get("id"), "ok");
+            response.insert("interrupted".to_string(), Value::from(running));
+            send(&writer, Value::Object(response));
+            continue;
+        }
+
+        let job = Job {
+            request,
+            writer: Arc::clone(&writer),
+        };
+
+        if sender.send(job).is_err() {
+            return;
+        }
+    }
+}
+
+struct Session {
+    vm: Engine,
+    evaluating: Arc<AtomicBool>,
+    required_modules: HashSet<PathBuf>,
+}
+
+impl Session {
+    fn new(vm: Engine) -> Self {
+        Self {
+            vm,
+            evaluating: Arc::new(AtomicBool::new(false)),
+            required_modules: HashSet::new(),
+        }
+    }
+
+    fn handle(&mut self, request: &Map<String, Value>) -> Value {
+        let id = request.get("id");
+
+        match string_field(request, "op") {
+            Some("eval") => {
+                let Some(code) = string_field(request, "code") else {
+                    return missing_field(id, "eval", "code");
+                };
+
+                let code = code.to_string();
+
+                // Code for a module runs inside of it, where its private definitions are visible
+                if let Some(module) = string_field(request, "module") {
+                    let module = PathBuf::from(module);
+
+                    // Requiring the module runs its top level, which can be interrupted like
+                    // anything else that gets evaluated
+                    let require = self.require_code(&module);
+                    let mut required = false;
+
+                    let response = self.evaluate(id, |vm| {
+                        if let Some(require) = require {
+                            vm.compile_and_run_raw_program(require)?;
+                            required = true;
+                        }
+
+                        vm.run_in_module(module.clone(), code)
+                    });
+
+                    if required {
+                        self.required_modules.insert(module);
+                    }
+
+                    return response;
+                }
+
+                self.evaluate(id, |vm| vm.compile_and_run_raw_program(code))
+            }
+            Some("load-file") => {
+                let Some(path) = string_field(request, "path") else {
+                    return missing_field(id, "load-file", "path");
+                };
+
+                let path = PathBuf::from(path);
+
+                let contents = match std::fs::read_to_string(&path) {
+                    Ok(contents) => contents,
+                    Err(e) => return error_response(id, "Io", format!("{}: {e}", path.display())),
+                };
+
+                self.evaluate(id, |vm| {
+                    vm.compile_and_run_raw_program_with_path(contents, path)
+                })
+            }
+            Some("complete") => {
+                let prefix = string_field(request, "prefix").unwrap_or_default();
+
+                let mut response = response(id, "ok");
+                response.insert(
+                    "completions".to_string(),
+                    Value::from(self.completions(prefix)),
+                );
+                Value::Object(response)
+            }
+            Some("doc") => {
+                let Some(symbol) = string_field(request, "symbol") else {
+                    return missing_field(id, "doc", "symbol");
+                };
+
+                let mut response = response(id, "ok");
+                response.insert(
+                    "doc".to_string(),
+                    self.vm
+                        .builtin_modules()
+                        .get_doc(symbol)
+                        .map(Value::from)
+                        .unwrap_or(Value::Null),
+                );
+                Value::Object(response)
+            }
+            Some(op) => error_response(id, "Protocol", format!("unknown op: {op}")),
+            None => error_response(id, "Protocol", "request is missing an op".to_string()),
+        }
+    }
+
+    fn evaluate(
+        &mut self,
+        id: Option<&Value>,
+        thunk: impl FnOnce(&mut Engine) -> steel::rvals::Result<Vec<SteelVal>>,
+    ) -> Value {
+        // Discard any interrupt that arrived after the last evaluation finished
+        self.vm.interrupt_handle().store(false, Ordering::SeqCst);
+        self.evaluating.store(true, Ordering::SeqCst);
+
+        let result = thunk(&mut self.vm);
+
+        self.evaluating.store(false, Ordering::SeqCst);
+
+        match result {
+            Ok(values) => {
+                let values = values
+                    .into_iter()
+                    .filter(|x| !matches!(x, SteelVal::Void))
+                    .map(|x| match x {
+                        SteelVal::StringV(s) => Value::from(format!("{:?}", s.as_ref())),
+                        other => Value::from(other.to_string()),
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut response = response(id, "ok");
+                response.insert("values".to_string(), Value::from(values));
+                Value::Object(response)
+            }
+            Err(e) => self.steel_error_response(id, e),
+        }
+    }
+
+    // The code that requires the module, unless it has been required already
+    fn require_code(&self, path: &Path) -> Option<String> {
+        if self.required_modules.contains(path) {
+            return None;
+        }
+
+        let path_string = Value::from(path.to_string_lossy()).to_string();
+
+        Some(format!("(require {path_string})"))
+    }
+
+    fn completions(&self, prefix: &str) -> Vec<String> {
+        let mut completions = self
+            .vm
+            .symbol_map()
+            .values()
+            .iter()
+            .map(|x| x.resolve())
+            // Skip over the mangled names of module internals
+            .filter(|x| {
+                !x.starts_with("__module-")
+                    && !x.starts_with("#%")
+                    && !(x.starts_with("mangler") && x.contains("__%#__"))
+            })
+            .filter(|x| x.starts_with(prefix))
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+
+        completions.sort();
+        completions.dedup();
+        completions
+    }
+
+    fn steel_error_response(&self, id: Option<&Value>, error: SteelErr) -> Value {
+        let mut map = response(id, "error");
+
+        let mut error_object = Map::new();
+        error_object.insert(
+            "kind".to_string(),
+            Value::from(format!("{:?}", error.kind())),
+        );
+        error_object.insert("message".to_string(), Value::from(error.to_string()));
+
+        if let Some(span) = error.span() {
+            error_object.insert(
+                "span".to_string(),
+                json!({ "start": span.start, "end": span.end }),
+            );
+        }
+
+        if let Some(report) = self.vm.raise_error_to_string(error) {
+            error_object.insert("report".to_string(), Value::from(report));
+        }
+
+        map.insert("error".to_string(), Value::Object(error_object));
+        Value::Object(map)
+    }
+}
+
+fn missing_field(id: Option<&Value>, op: &str, field: &str) -> Value {
+    error_response(
+        id,
+        "Protocol",
+        format!("{op} request is missing the `{field}` field"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpStream;
+
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Client {
+        fn connect(address: SocketAddr) -> Self {
+            let stream = TcpStream::connect(address).unwrap();
+            Self {
+                writer: stream.try_clone().unwrap(),
+                reader: BufReader::new(stream),
+            }
+        }
+
+        fn send(&mut self, request: Value) {
+            write_frame(&mut self.writer, request.to_string().as_bytes()).unwrap();
+        }
+
+        fn receive(&mut self) -> Value {
+            let frame = read_frame(&mut self.reader).unwrap().unwrap();
+            serde_json::from_slice(&frame).unwrap()
+        }
+
+        fn request(&mut self, request: Value) -> Value {
+            self.send(request);
+            self.receive()
+        }
+    }
+
+    fn start_server() -> SocketAddr {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || serve_tcp(Engine::new(), listener));
+
+        address
+    }
+
+    #[test]
+    fn eval_complete_and_errors() {
+        let mut client = Client::connect(start_server());
+
+        let response = client.request(
+            json!({"id": 1, "op": "eval", "code": "(define foo-bar 10) (+ foo-bar 1) \"hi\""}),
+        );
+        assert_eq!(response["id"], json!(1));
+        assert_eq!(response["status"], json!("ok"));
+        assert_eq!(response["values"], json!(["11", "\"hi\""]));
+
+        let response = client.request(json!({"id": 2, "op": "complete", "prefix": "foo-b"}));
+        assert_eq!(response["completions"], json!(["foo-bar"]));
+
+        let response = client.request(json!({"id": 3, "op": "eval", "code": "(car 10)"}));
+        assert_eq!(response["status"], json!("error"));
+        assert_eq!(response["error"]["kind"], json!("TypeMismatch"));
+        assert!(response["error"]["report"].is_string());
+
+        let response = client.request(json!({"id": 4, "op": "frobnicate"}));
+        assert_eq!(response["status"], json!("error"));
+        assert_eq!(response["error"]["kind"], json!("Protocol"));
+    }
+
+    #[test]
+    fn eval_in_module() {
+        let module = std::env::temp_dir().join(format!("repl-server-{}.scm", std::process::id()));
+        std::fs::write(
+            &module,
+            "(provide public) (define (helper x) (* x 2)) (define (public x) (helper x))",
+        )
+        .unwrap();
+
+        let mut client = Client::connect(start_server());
+        let module = module.to_str().unwrap();
+
+        let response =
+            client.request(json!({"id": 1, "op": "eval", "code": "(helper 21)", "module": module}));
+        assert_eq!(response["values"], json!(["42"]));
+
+        // Definitions go into the module rather than the top level
+        let response = client.request(
+            json!({"id": 2, "op": "eval", "code": "(define (triple x) (+ x (helper x)))", "module": module}),
+        );
+        assert_eq!(response["status"], json!("ok"));
+
+        let response =
+            client.request(json!({"id": 3, "op": "eval", "code": "(triple 2)", "module": module}));
+        assert_eq!(response["values"], json!(["6"]));
+
+        let response = client.request(json!({"id": 4, "op": "eval", "code": "(helper 1)"}));
+        assert_eq!(response["status"], json!("error"));
+
+        let response = client.request(json!({"id": 5, "op": "eval", "code": "(public 1)"}));
+        assert_eq!(response["values"], json!(["2"]));
+
+        // The mangled names of the module internals aren't offered as completions
+        let response = client.request(json!({"id": 6, "op": "complete", "prefix": "mangler"}));
+        assert_eq!(response["completions"], json!([]));
+    }
+
+    #[test]
+    fn interrupt_stops_running_evaluation() {
+        let address = start_server();
+        let mut client = Client::connect(address);
+
+        client.send(json!({"id": 1, "op": "eval", "code": "(define (spin) (spin)) (spin)"}));
+
+        // Keep interrupting until the loop has actually started and been stopped
+        let mut interrupter = Client::connect(address);
+        while !interrupter.request(json!({"op": "interrupt"}))["interrupted"]
+            .as_bool()
+            .unwrap()
+        {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let response = client.receive();
+        assert_eq!(response["id"], json!(1));
+        assert_eq!(response["status"], json!("error"));
+        assert_eq!(response["error"]["kind"], json!("Interrupted"));
+
+        // The engine is still usable afterwards
+        let response = client.request(json!({"id": 2, "op": "eval", "code": "(+ 1 2)"}));
+        assert_eq!(response["values"], json!(["3"]));
+    }
+
+    #[test]
+    fn interrupt_stops_module_top_level() {
+        let module =
+            std::env::temp_dir().join(format!("repl-server-spin-{}.scm", std::process::id()));
+        std::fs::write(&module, "(define (spin) (spin)) (spin)").unwrap();
+
+        let address = start_server();
+        let mut client = Client::connect(address);
+
+        client
+            .send(json!({"id": 1, "op": "eval", "code": "1", "module": module.to_str().unwrap()}));
+
+        let mut interrupter = Client::connect(address);
+        while !interrupter.request(json!({"op": "interrupt"}))["interrupted"]
+            .as_bool()
+            .unwrap()
+        {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let response = client.receive();
+        assert_eq!(response["id"], json!(1));
+        assert_eq!(response["error"]["kind"], json!("Interrupted"));
+    }
+}
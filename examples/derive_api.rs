@@ -0,0 +1,86 @@
+use steel::steel_vm::builtin::BuiltInModule;
+use steel::steel_vm::engine::Engine;
+
+use steel_derive::Steel;
+
+/// A point on the plane
+#[derive(Clone, Debug, Steel, PartialEq)]
+#[steel(register)]
+pub struct Point {
+    /// The horizontal coordinate
+    x: f64,
+    /// The vertical coordinate
+    #[steel(read_only)]
+    y: f64,
+    // Fields that can't be converted to a SteelVal can be left out, and are
+    // set to their default value by the constructor
+    #[steel(skip)]
+    cache: Option<Box<Point>>,
+}
+
+#[derive(Clone, Debug, Steel, PartialEq)]
+#[steel(register)]
+pub enum Shape {
+    Empty,
+    Circle(f64),
+    #[steel(name = "Rect")]
+    Rectangle {
+        width: f64,
+        height: f64,
+    },
+}
+
+pub fn main() {
+    // The generated functions can be registered into any module
+    let mut module = BuiltInModule::new("geometry");
+    Point::register_into(&mut module);
+    Shape::register_into(&mut module);
+
+    let mut vm = Engine::new();
+    vm.register_module(module);
+
+    vm.compile_and_run_raw_program(
+        r#"
+        (require-builtin geometry)
+
+        (define p (Point 1.0 2.0))
+        (set-Point-x! p 10.0)
+
+        (define shapes (list (Shape-Empty) (Shape-Circle 2.0) (Shape-Rect 3.0 4.0)))
+
+        (define (area shape)
+          (cond [(Shape-Circle? shape) (* 3.0 (Shape-Circle-0 shape) (Shape-Circle-0 shape))]
+                [(Shape-Rect? shape) (* (Shape-Rect-width shape) (Shape-Rect-height shape))]
+                [else 0.0]))
+
+        (define areas (map area shapes))
+        "#,
+    )
+    .unwrap();
+
+    let point: Point = vm.extract("p").unwrap();
+    println!("point: {point:?}");
+    assert_eq!(
+        Point {
+            x: 10.0,
+            y: 2.0,
+            cache: None
+        },
+        point
+    );
+
+    let areas: Vec<f64> = vm.extract("areas").unwrap();
+    println!("areas: {areas:?}");
+    assert_eq!(vec![0.0, 12.0, 12.0], areas);
+
+    // Read only fields don't get a setter
+    assert!(vm
+        .compile_and_run_raw_program("(set-Point-y! p 10.0)")
+        .is_err());
+
+    // Using a getter on the wrong variant is an error
+    let error = vm
+        .compile_and_run_raw_program("(Shape-Circle-0 (Shape-Empty))")
+        .unwrap_err();
+    println!("error: {error}");
+}
@@ -3,12 +3,26 @@
                                      prepare
                                      execute
                                      query
+                                     query/hash
+                                     query-row
+                                     query-iter
+                                     rows/next!
                                      begin/transaction
                                      transaction/finish
                                      transaction/commit
                                      transaction/try-commit
                                      transaction/rollback
                                      transaction/try-finish
+                                     savepoint
+                                     savepoint/release
+                                     savepoint/rollback
+                                     create-function
+                                     create-aggregate
+                                     blob?
+                                     list->blob
+                                     string->blob
+                                     blob->list
+                                     blob-length
                                      SqliteConnection?
                                      SqliteTransaction?
                                      SqlitePreparedStatement?
@@ -20,13 +34,33 @@
          open-in-memory
          open
          (contract/out prepare (->/c SqliteConnection? string? any/c))
+         blob?
          (contract/out execute (->/c SqlitePreparedStatement? list? any/c))
-         (contract/out query (->/c SqlitePreparedStatement? list? list?))
+         (contract/out query (->/c SqlitePreparedStatement? (or/c list? hash?) list?))
+         (contract/out query/hash (->/c SqlitePreparedStatement? (or/c list? hash?) list?))
+         (contract/out query/map
+                       (->/c SqlitePreparedStatement? (or/c list? hash?) procedure? list?))
+         (contract/out query-row (->/c SqlitePreparedStatement? (or/c list? hash?) list?))
+         (contract/out query-iter (->/c SqlitePreparedStatement? (or/c list? hash?) any/c))
+         rows/next!
+         rows/for-each
          (contract/out begin/transaction (->/c SqliteConnection? SqliteTransaction?))
          (contract/out transaction/finish (->/c SqliteTransaction? any/c))
          (contract/out transaction/commit (->/c SqliteTransaction? any/c))
          (contract/out transaction/rollback (->/c SqliteTransaction? any/c))
          (contract/out transaction/try-finish (->/c SqliteTransaction? any/c))
+         (contract/out savepoint (->/c SqliteConnection? string? any/c))
+         (contract/out savepoint/release (->/c SqliteConnection? string? any/c))
+         (contract/out savepoint/rollback (->/c SqliteConnection? string? any/c))
+         (contract/out run-savepoint (->/c SqliteConnection? string? (->/c any/c) any/c))
+         (contract/out create-function
+                       (->/c SqliteConnection? string? integer? procedure? any/c))
+         (contract/out create-aggregate
+                       (->/c SqliteConnection? string? integer? procedure? procedure? procedure? any/c))
+         (contract/out list->blob (->/c list? blob?))
+         (contract/out string->blob (->/c string? blob?))
+         (contract/out blob->list (->/c blob? list?))
+         (contract/out blob-length (->/c blob? integer?))
          (contract/out run-transaction
                        (->/c SqliteConnection? (->/c SqliteTransaction? any/c) any/c)))
 
@@ -39,13 +73,56 @@
 (define open-in-memory sqlite/open-in-memory)
 
 ;;@doc
-;; Execute a sqlite statement with a list of parameters, without returning any rows.
+;; Execute a sqlite statement once for each group of parameters, without returning any rows.
+;; Each group is either a list of positional parameters, or a hash map of named parameters.
+;; Returns the number of rows changed.
 (define execute sqlite/execute)
 
 ;;@doc
-;; Run a sqlite statement with a list of parameters, returning the rows found.
+;; Run a sqlite statement with a list of positional parameters, or a hash map of named
+;; parameters, returning the rows found as lists. Names without a prefix are bound to `:name`.
+;;
+;; ```scheme
+;; (query (prepare connection "SELECT name FROM person WHERE id = :id") (hash "id" 1))
+;; ```
 (define query sqlite/query)
 
+;;@doc
+;; Like `query`, but returns each row as a hash map from the column name to the value.
+(define query/hash sqlite/query/hash)
+
+;;@doc
+;; Like `query`, but calls the procedure with the columns of each row as the arguments,
+;; returning the results. Handy for building structs out of rows.
+;;
+;; ```scheme
+;; (struct person (id name))
+;; (query/map (prepare connection "SELECT id, name FROM person") '() person)
+;; ```
+(define (query/map statement params func)
+  (map (lambda (row) (apply func row)) (query statement params)))
+
+;;@doc
+;; Run the query, returning only the first row. Raises an error if there are no rows.
+(define query-row sqlite/query-row)
+
+;;@doc
+;; Run the query, returning a cursor over the rows instead of reading them all into memory.
+;; Rows are read from the cursor with `rows/next!`.
+(define query-iter sqlite/query-iter)
+
+;;@doc
+;; Read the next row from a cursor returned by `query-iter`, or `#f` when there are no more rows.
+(define rows/next! sqlite/rows/next!)
+
+;;@doc
+;; Call the procedure on each remaining row of a cursor returned by `query-iter`.
+(define (rows/for-each func rows)
+  (let ([row (rows/next! rows)])
+    (when row
+      (func row)
+      (rows/for-each func rows))))
+
 ;;@doc
 ;; Start a sqlite transaction
 (define begin/transaction sqlite/begin/transaction)
@@ -75,6 +152,59 @@
 ;; this will do nothing.
 (define transaction/try-finish sqlite/transaction/try-finish)
 
+;;@doc
+;; Create a savepoint with the given name, which can be nested inside of transactions
+;; and other savepoints.
+(define savepoint sqlite/savepoint)
+
+;;@doc
+;; Release the savepoint, keeping the changes made since it was created.
+(define savepoint/release sqlite/savepoint/release)
+
+;;@doc
+;; Roll back the changes made since the savepoint was created, and release it.
+(define savepoint/rollback sqlite/savepoint/rollback)
+
+;;@doc
+;; Register a scalar SQL function backed by a procedure. An arity of -1 accepts any number
+;; of arguments.
+;;
+;; ```scheme
+;; (create-function connection "double" 1 (lambda (x) (* x 2)))
+;; (query (prepare connection "SELECT double(21)") '()) ;; => '((42))
+;; ```
+(define create-function sqlite/create-function)
+
+;;@doc
+;; Register an aggregate SQL function. `initial` is a thunk returning the starting
+;; accumulator, `step` is called with the accumulator followed by the arguments for each
+;; row and returns the new accumulator, and `finalize` turns the accumulator into the result.
+;;
+;; ```scheme
+;; (create-aggregate connection "total" 1 (lambda () 0) + (lambda (x) x))
+;; ```
+(define create-aggregate sqlite/create-aggregate)
+
+;;@doc
+;; Test if the value is a blob, which is how sqlite `BLOB` values are represented.
+(define blob? sqlite/blob?)
+
+;;@doc
+;; Create a blob from a list of bytes.
+(define list->blob sqlite/list->blob)
+
+;;@doc
+;; Create a blob from the utf-8 encoding of a string.
+(define string->blob sqlite/string->blob)
+
+;;@doc
+;; Return the bytes of a blob as a list of integers.
+(define blob->list sqlite/blob->list)
+
+;;@doc
+;; Return the number of bytes in a blob.
+(define blob-length sqlite/blob-length)
+
 ;;@doc
 ;; Test if the value is a `SqliteConnection`.
 (define SqliteConnection? sqlite/SqliteConnection?)
@@ -106,3 +236,20 @@
                   (thunk transaction)
                   (transaction/try-commit transaction))
                 (lambda () (transaction/try-finish transaction))))
+;;@doc
+;; Run the thunk inside of a savepoint. The savepoint is released if the thunk
+;; returns successfully, and rolled back if there are any exceptions.
+;;
+;; (->/c SqliteConnection? string? (->/c any/c) any/c)
+(define (run-savepoint connection name thunk)
+  (define released #f)
+  (savepoint connection name)
+  (dynamic-wind (lambda () void)
+                (lambda ()
+                  (let ([result (thunk)])
+                    (savepoint/release connection name)
+                    (set! released #t)
+                    result))
+                (lambda ()
+                  (unless released
+                    (savepoint/rollback connection name)))))
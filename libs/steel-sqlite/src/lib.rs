@@ -1,14 +1,14 @@
-use std::rc::Rc;
+use std::{collections::HashSet, panic::AssertUnwindSafe, rc::Rc};
 
-use abi_stable::std_types::{RString, RVec};
+use abi_stable::std_types::{RResult, RString, RVec, Tuple2};
 use rusqlite::{
-    params_from_iter,
-    types::{FromSql, FromSqlError, ToSqlOutput, Value},
-    Connection, Statement, ToSql, Transaction,
+    functions::{Aggregate, Context, FunctionFlags},
+    types::{FromSql, FromSqlError, ToSqlOutput, Value, ValueRef},
+    Connection, Row, Rows, Statement, ToSql, Transaction,
 };
 use steel::{
     rvals::{as_underlying_type, Custom},
-    steel_vm::ffi::{FFIModule, FFIValue, RegisterFFIFn},
+    steel_vm::ffi::{FFIBoxedDynFunction, FFIModule, FFIValue, RegisterFFIFn},
 };
 
 struct SqliteConnection {
@@ -154,10 +154,30 @@ impl Drop for SqliteTransaction {
 
 enum SqliteError {
     TransactionAlreadyCompleted,
+    InvalidParameters(String),
+    Callback(String),
     Generic(rusqlite::Error),
 }
 
-impl Custom for SqliteError {}
+impl std::fmt::Display for SqliteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SqliteError::TransactionAlreadyCompleted => {
+                write!(f, "transaction has already been completed")
+            }
+            SqliteError::InvalidParameters(message) => write!(f, "{message}"),
+            SqliteError::Callback(message) => write!(f, "{message}"),
+            SqliteError::Generic(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+// Errors cross the FFI boundary as their message, so that they are readable on the other side
+impl From<SqliteError> for FFIValue {
+    fn from(value: SqliteError) -> Self {
+        FFIValue::StringV(value.to_string().into())
+    }
+}
 
 impl From<rusqlite::Error> for SqliteError {
     fn from(value: rusqlite::Error) -> Self {
@@ -165,74 +185,226 @@ impl From<rusqlite::Error> for SqliteError {
     }
 }
 
+/// Binds the parameters for the next execution of the statement. A list binds the parameters
+/// by position, while a hash map binds them by name. Names without a prefix default to `:name`.
+fn bind_parameters(statement: &mut Statement<'_>, params: FFIValue) -> Result<(), SqliteError> {
+    let expected = statement.parameter_count();
+
+    match params {
+        FFIValue::Vector(values) => {
+            if values.len() != expected {
+                return Err(rusqlite::Error::InvalidParameterCount(values.len(), expected).into());
+            }
+
+            for (index, value) in values.into_iter().enumerate() {
+                statement.raw_bind_parameter(index + 1, FFIWrapper(value))?;
+            }
+        }
+        FFIValue::HashMap(values) => {
+            let mut bound = HashSet::new();
+
+            for Tuple2(name, value) in values {
+                let FFIValue::StringV(name) = name else {
+                    return Err(SqliteError::InvalidParameters(format!(
+                        "named parameters must be strings, found: {name:?}"
+                    )));
+                };
+
+                let name = if name.starts_with([':', '@', '$']) {
+                    name.into_string()
+                } else {
+                    format!(":{name}")
+                };
+
+                let index = statement.parameter_index(&name)?.ok_or_else(|| {
+                    SqliteError::InvalidParameters(format!("unknown parameter: {name}"))
+                })?;
+
+                statement.raw_bind_parameter(index, FFIWrapper(value))?;
+                bound.insert(index);
+            }
+
+            // Parameters keep their value from the last execution otherwise
+            if bound.len() != expected {
+                return Err(rusqlite::Error::InvalidParameterCount(bound.len(), expected).into());
+            }
+        }
+        other => {
+            return Err(SqliteError::InvalidParameters(format!(
+                "parameters must be a list or a hash map, found: {other:?}"
+            )))
+        }
+    }
+
+    Ok(())
+}
+
+fn read_row(row: &Row<'_>, width: usize) -> Result<RVec<FFIValue>, SqliteError> {
+    let mut computed_row = RVec::with_capacity(width);
+
+    for i in 0..width {
+        computed_row.push(row.get::<_, FFIWrapper>(i)?.0);
+    }
+
+    Ok(computed_row)
+}
+
+fn call(function: &FFIBoxedDynFunction, args: RVec<FFIValue>) -> Result<FFIValue, SqliteError> {
+    match function.call(args) {
+        RResult::ROk(value) => Ok(value),
+        RResult::RErr(e) => Err(SqliteError::Callback(e.to_string())),
+    }
+}
+
 impl SqlitePreparedStatement {
-    fn execute(&mut self, params: Vec<Vec<FFIValue>>) -> Result<usize, SqliteError> {
-        let mut count = 0;
+    fn statement(&mut self) -> &mut Statement<'static> {
+        self.prepared_statement.as_mut().unwrap()
+    }
+
+    fn execute(&mut self, params: Vec<FFIValue>) -> Result<usize, SqliteError> {
+        let statement = self.statement();
 
         if params.is_empty() {
-            count += self.prepared_statement.as_mut().unwrap().execute([])?;
-        } else {
-            for group in params {
-                count += self
-                    .prepared_statement
-                    .as_mut()
-                    .unwrap()
-                    .execute(params_from_iter(group.into_iter().map(FFIWrapper)))?;
-            }
+            return Ok(statement.execute([])?);
+        }
+
+        let mut count = 0;
+
+        for group in params {
+            bind_parameters(statement, group)?;
+            count += statement.raw_execute()?;
         }
 
         Ok(count)
     }
 
+    // Runs the query, calling `f` on each row
+    fn for_each_row(
+        &mut self,
+        params: FFIValue,
+        mut f: impl FnMut(&Row<'_>, usize) -> Result<(), SqliteError>,
+    ) -> Result<(), SqliteError> {
+        let statement = self.statement();
+        let width = statement.column_count();
+
+        bind_parameters(statement, params)?;
+
+        let mut rows = statement.raw_query();
+
+        while let Some(row) = rows.next()? {
+            f(row, width)?;
+        }
+
+        Ok(())
+    }
+
     // This is doing... lots of copying. Probably need to profile and figure out
     // a better interaction at the FFI boundary that doesn't require copying
     // the vector repeatedly
-    fn query(&mut self, params: Vec<FFIValue>) -> Result<FFIValue, SqliteError> {
-        let mut rows = self
-            .prepared_statement
-            .as_mut()
-            .unwrap()
-            .query(params_from_iter(params.into_iter().map(FFIWrapper)))?;
-
+    fn query(&mut self, params: FFIValue) -> Result<FFIValue, SqliteError> {
         let mut results = RVec::new();
 
-        let mut width: Option<usize> = None;
+        self.for_each_row(params, |row, width| {
+            results.push(FFIValue::Vector(read_row(row, width)?));
+            Ok(())
+        })?;
 
-        while let Some(row) = rows.next()? {
-            if let Some(width) = width {
-                // TODO: Save the row length for the next iteration, so that we can pre allocate
-                // the row width
-                let mut computed_row: RVec<FFIValue> = RVec::with_capacity(width);
+        Ok(FFIValue::Vector(results))
+    }
 
-                for i in 0..width {
-                    computed_row.push(row.get(i).map(|x: FFIWrapper| x.0).unwrap())
-                }
+    fn query_hash(&mut self, params: FFIValue) -> Result<FFIValue, SqliteError> {
+        let names: Vec<String> = self
+            .statement()
+            .column_names()
+            .into_iter()
+            .map(String::from)
+            .collect();
 
-                results.push(FFIValue::Vector(computed_row));
-            } else {
-                // TODO: Save the row length for the next iteration, so that we can pre allocate
-                // the row width
-                // let mut computed_row: Vec<SteelVal> = Vec::new();
-                let mut i = 0;
-                let mut computed_row: RVec<FFIValue> = RVec::new();
-
-                while let Ok(value) = row.get::<_, FFIWrapper>(i) {
-                    computed_row.push(value.0);
-                    i += 1;
-                }
+        let mut results = RVec::new();
 
-                results.push(FFIValue::Vector(computed_row));
+        self.for_each_row(params, |row, width| {
+            let row = read_row(row, width)?;
+            results.push(FFIValue::HashMap(
+                names
+                    .iter()
+                    .map(|name| FFIValue::StringV(name.as_str().into()))
+                    .zip(row)
+                    .collect(),
+            ));
+            Ok(())
+        })?;
 
-                width = Some(i);
-            }
+        Ok(FFIValue::Vector(results))
+    }
+
+    fn query_row(&mut self, params: FFIValue) -> Result<FFIValue, SqliteError> {
+        let statement = self.statement();
+        let width = statement.column_count();
+
+        bind_parameters(statement, params)?;
+
+        match statement.raw_query().next()? {
+            Some(row) => Ok(FFIValue::Vector(read_row(row, width)?)),
+            None => Err(rusqlite::Error::QueryReturnedNoRows.into()),
         }
+    }
 
-        Ok(FFIValue::Vector(results))
+    // Rather than collecting all of the rows, hands back a cursor over them. The cursor
+    // gets its own copy of the statement, so that this one can still be used.
+    fn query_iter(&self, params: FFIValue) -> Result<SqliteRows, SqliteError> {
+        let mut statement = Box::new(unsafe {
+            std::mem::transmute::<Statement<'_>, Statement<'static>>(
+                self._connection.prepare(&self._sql)?,
+            )
+        });
+
+        bind_parameters(&mut statement, params)?;
+
+        let width = statement.column_count();
+
+        // The rows borrow from the boxed statement, which doesn't move for as long
+        // as the cursor is alive
+        let rows = unsafe { std::mem::transmute::<Rows<'_>, Rows<'static>>(statement.raw_query()) };
+
+        Ok(SqliteRows {
+            rows: Some(rows),
+            width,
+            _statement: statement,
+            _connection: Rc::clone(&self._connection),
+        })
     }
 }
 
 impl Custom for SqlitePreparedStatement {}
 
+struct SqliteRows {
+    // Fields are dropped in order, so the rows go before the statement they borrow from
+    rows: Option<Rows<'static>>,
+    width: usize,
+    _statement: Box<Statement<'static>>,
+    _connection: Rc<Connection>,
+}
+
+impl Custom for SqliteRows {}
+
+impl SqliteRows {
+    // Returns the next row, or `#f` once the rows have been exhausted
+    fn next(&mut self) -> Result<FFIValue, SqliteError> {
+        let Some(rows) = self.rows.as_mut() else {
+            return Ok(FFIValue::BoolV(false));
+        };
+
+        match rows.next()? {
+            Some(row) => Ok(FFIValue::Vector(read_row(row, self.width)?)),
+            None => {
+                // Reset the statement as soon as we're done
+                self.rows = None;
+                Ok(FFIValue::BoolV(false))
+            }
+        }
+    }
+}
+
 impl SqliteConnection {
     fn prepare(&self, sql: String) -> Result<SqlitePreparedStatement, SqliteError> {
         let sql = Rc::new(sql);
@@ -247,6 +419,178 @@ impl SqliteConnection {
             }),
         })
     }
+
+    fn savepoint(&self, name: String) -> Result<(), SqliteError> {
+        Ok(self
+            .connection
+            .execute_batch(&format!("SAVEPOINT {}", quote_identifier(&name)))?)
+    }
+
+    fn release_savepoint(&self, name: String) -> Result<(), SqliteError> {
+        Ok(self
+            .connection
+            .execute_batch(&format!("RELEASE SAVEPOINT {}", quote_identifier(&name)))?)
+    }
+
+    fn rollback_savepoint(&self, name: String) -> Result<(), SqliteError> {
+        // Rolling back leaves the savepoint on the stack, so release it as well
+        let name = quote_identifier(&name);
+
+        Ok(self.connection.execute_batch(&format!(
+            "ROLLBACK TO SAVEPOINT {name}; RELEASE SAVEPOINT {name}"
+        ))?)
+    }
+
+    // Registers a scalar SQL function. The function gets called with the arguments of the
+    // SQL function, and has to be called on the thread that is running the VM.
+    fn create_function(
+        &self,
+        name: String,
+        arity: isize,
+        function: FFIBoxedDynFunction,
+    ) -> Result<(), SqliteError> {
+        let function = AssertUnwindSafe(function);
+
+        Ok(self.connection.create_scalar_function(
+            &name,
+            sql_arity(arity)?,
+            FunctionFlags::SQLITE_UTF8,
+            move |context| {
+                let args = function_arguments(context)?;
+                call(&function, args)
+                    .map(FFIWrapper)
+                    .map_err(|e| rusqlite::Error::UserFunctionError(e.to_string().into()))
+            },
+        )?)
+    }
+
+    // Registers an aggregate SQL function. `initial` is a thunk producing the starting
+    // accumulator, `step` gets called with the accumulator followed by the arguments for
+    // each row and returns the new accumulator, and `finalize` turns the accumulator into
+    // the result.
+    fn create_aggregate(
+        &self,
+        name: String,
+        arity: isize,
+        initial: FFIBoxedDynFunction,
+        step: FFIBoxedDynFunction,
+        finalize: FFIBoxedDynFunction,
+    ) -> Result<(), SqliteError> {
+        Ok(self.connection.create_aggregate_function(
+            &name,
+            sql_arity(arity)?,
+            FunctionFlags::SQLITE_UTF8,
+            SteelAggregate {
+                initial: AssertUnwindSafe(initial),
+                step: AssertUnwindSafe(step),
+                finalize: AssertUnwindSafe(finalize),
+            },
+        )?)
+    }
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// -1 means the function takes any number of arguments
+fn sql_arity(arity: isize) -> Result<i32, SqliteError> {
+    i32::try_from(arity)
+        .ok()
+        .filter(|arity| *arity >= -1)
+        .ok_or_else(|| SqliteError::InvalidParameters(format!("invalid arity: {arity}")))
+}
+
+fn function_arguments(context: &Context<'_>) -> rusqlite::Result<RVec<FFIValue>> {
+    (0..context.len())
+        .map(|i| FFIWrapper::column_result(context.get_raw(i)).map(|x| x.0))
+        .collect::<Result<_, _>>()
+        .map_err(|e| rusqlite::Error::UserFunctionError(Box::new(e)))
+}
+
+struct SteelAggregate {
+    initial: AssertUnwindSafe<FFIBoxedDynFunction>,
+    step: AssertUnwindSafe<FFIBoxedDynFunction>,
+    finalize: AssertUnwindSafe<FFIBoxedDynFunction>,
+}
+
+impl SteelAggregate {
+    fn initial(&self) -> rusqlite::Result<FFIValue> {
+        call(&self.initial, RVec::new())
+            .map_err(|e| rusqlite::Error::UserFunctionError(e.to_string().into()))
+    }
+}
+
+impl Aggregate<AssertUnwindSafe<FFIValue>, FFIWrapper> for SteelAggregate {
+    fn init(&self, _: &mut Context<'_>) -> rusqlite::Result<AssertUnwindSafe<FFIValue>> {
+        self.initial().map(AssertUnwindSafe)
+    }
+
+    fn step(
+        &self,
+        context: &mut Context<'_>,
+        accumulator: &mut AssertUnwindSafe<FFIValue>,
+    ) -> rusqlite::Result<()> {
+        let mut args = RVec::with_capacity(context.len() + 1);
+        args.push(std::mem::replace(&mut accumulator.0, FFIValue::Void));
+        args.extend(function_arguments(context)?);
+
+        accumulator.0 = call(&self.step, args)
+            .map_err(|e| rusqlite::Error::UserFunctionError(e.to_string().into()))?;
+
+        Ok(())
+    }
+
+    fn finalize(
+        &self,
+        _: &mut Context<'_>,
+        accumulator: Option<AssertUnwindSafe<FFIValue>>,
+    ) -> rusqlite::Result<FFIWrapper> {
+        // No rows means `init` was never called
+        let accumulator = match accumulator {
+            Some(accumulator) => accumulator.0,
+            None => self.initial()?,
+        };
+
+        call(&self.finalize, RVec::from(vec![accumulator]))
+            .map(FFIWrapper)
+            .map_err(|e| rusqlite::Error::UserFunctionError(e.to_string().into()))
+    }
+}
+
+#[derive(Clone)]
+struct SqliteBlob(Vec<u8>);
+
+impl Custom for SqliteBlob {}
+
+impl SqliteBlob {
+    fn from_list(bytes: Vec<isize>) -> Result<Self, String> {
+        bytes
+            .into_iter()
+            .map(|byte| u8::try_from(byte).map_err(|_| format!("not a byte: {byte}")))
+            .collect::<Result<_, _>>()
+            .map(SqliteBlob)
+    }
+
+    fn from_string(string: String) -> Self {
+        SqliteBlob(string.into_bytes())
+    }
+
+    fn to_list(&self) -> Vec<isize> {
+        self.0.iter().map(|byte| *byte as isize).collect()
+    }
+
+    fn length(&self) -> usize {
+        self.0.len()
+    }
+}
+
+fn is_blob(value: FFIValue) -> bool {
+    if let FFIValue::Custom { custom } = value {
+        as_underlying_type::<SqliteBlob>(custom.inner.borrow().as_ref()).is_some()
+    } else {
+        false
+    }
 }
 
 struct FFIWrapper(FFIValue);
@@ -267,14 +611,24 @@ impl ToSql for FFIWrapper {
     fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
         match &self.0 {
             // FFIValue::BoxedFunction(_) => todo!(),
-            // FFIValue::BoolV(b) => Ok(rusqlite::types::ToSqlOutput::Owned(Value::Bo)),
+            FFIValue::BoolV(b) => Ok(ToSqlOutput::Owned(Value::Integer(*b as i64))),
             FFIValue::NumV(f) => Ok(ToSqlOutput::Owned(Value::Real(*f))),
             FFIValue::IntV(i) => Ok(ToSqlOutput::Owned(Value::Integer(*i as i64))),
             FFIValue::Void => Ok(ToSqlOutput::Owned(Value::Null)),
             FFIValue::StringV(s) => Ok(ToSqlOutput::Owned(Value::Text(s.to_string()))),
             // FFIValue::Vector(_) => todo!(),
             // FFIValue::CharV { c } => todo!(),
-            // FFIValue::Custom { custom } => todo!(),
+            FFIValue::Custom { custom } => {
+                match as_underlying_type::<SqliteBlob>(custom.inner.borrow().as_ref()) {
+                    Some(blob) => Ok(ToSqlOutput::Owned(Value::Blob(blob.0.clone()))),
+                    None => Err(rusqlite::Error::ToSqlConversionFailure(Box::new(
+                        SqliteConversionError(format!(
+                            "Unable to convert value to a sql value: {}",
+                            custom.name
+                        )),
+                    ))),
+                }
+            }
             // FFIValue::HashMap(_) => todo!(),
             _ => Err(rusqlite::Error::ToSqlConversionFailure(Box::new(
                 SqliteConversionError(format!(
@@ -287,20 +641,15 @@ impl ToSql for FFIWrapper {
 }
 
 impl FromSql for FFIWrapper {
-    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+    fn column_result(value: ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
         match value {
-            rusqlite::types::ValueRef::Null => Ok(FFIWrapper(FFIValue::Void)),
-            rusqlite::types::ValueRef::Integer(i) => Ok(FFIWrapper(FFIValue::IntV(i as isize))),
-            rusqlite::types::ValueRef::Real(f) => Ok(FFIWrapper(FFIValue::NumV(f))),
-            rusqlite::types::ValueRef::Text(t) => Ok(FFIWrapper(FFIValue::StringV(
+            ValueRef::Null => Ok(FFIWrapper(FFIValue::Void)),
+            ValueRef::Integer(i) => Ok(FFIWrapper(FFIValue::IntV(i as isize))),
+            ValueRef::Real(f) => Ok(FFIWrapper(FFIValue::NumV(f))),
+            ValueRef::Text(t) => Ok(FFIWrapper(FFIValue::StringV(
                 RString::from_utf8(t).map_err(|e| FromSqlError::Other(Box::new(e)))?,
             ))),
-            rusqlite::types::ValueRef::Blob(_) => Err(rusqlite::types::FromSqlError::Other(
-                Box::new(SqliteConversionError(format!(
-                    "Unable to convert value to a sql value: {:?}",
-                    &value
-                ))),
-            )),
+            ValueRef::Blob(b) => Ok(FFIWrapper(SqliteBlob(b.to_vec()).into())),
         }
     }
 }
@@ -319,12 +668,190 @@ pub fn build_module() -> FFIModule {
         .register_fn("open", SqliteConnection::open)
         .register_fn("execute", SqlitePreparedStatement::execute)
         .register_fn("query", SqlitePreparedStatement::query)
+        .register_fn("query/hash", SqlitePreparedStatement::query_hash)
+        .register_fn("query-row", SqlitePreparedStatement::query_row)
+        .register_fn("query-iter", SqlitePreparedStatement::query_iter)
+        .register_fn("rows/next!", SqliteRows::next)
         .register_fn("begin/transaction", SqliteConnection::begin_transaction)
         .register_fn("transaction/finish", SqliteTransaction::finish)
         .register_fn("transaction/commit", SqliteTransaction::commit)
         .register_fn("transaction/try-commit", SqliteTransaction::try_commit)
         .register_fn("transaction/rollback", SqliteTransaction::rollback)
-        .register_fn("transaction/try-finish", SqliteTransaction::try_finish);
+        .register_fn("transaction/try-finish", SqliteTransaction::try_finish)
+        .register_fn("savepoint", SqliteConnection::savepoint)
+        .register_fn("savepoint/release", SqliteConnection::release_savepoint)
+        .register_fn("savepoint/rollback", SqliteConnection::rollback_savepoint)
+        .register_fn("create-function", SqliteConnection::create_function)
+        .register_fn("create-aggregate", SqliteConnection::create_aggregate)
+        .register_fn("blob?", is_blob)
+        .register_fn("list->blob", SqliteBlob::from_list)
+        .register_fn("string->blob", SqliteBlob::from_string)
+        .register_fn("blob->list", SqliteBlob::to_list)
+        .register_fn("blob-length", SqliteBlob::length);
 
     module
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use abi_stable::std_types::RBox;
+    use steel::steel_vm::engine::Engine;
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        engine
+            .register_external_module(RBox::new(build_module()))
+            .unwrap();
+
+        engine
+            .compile_and_run_raw_program(
+                r#"
+                (require-builtin dylib/steel/sqlite)
+
+                (define connection (open-in-memory))
+
+                (execute (prepare connection
+                           "CREATE TABLE person (id INTEGER PRIMARY KEY, name TEXT, active INTEGER, avatar BLOB)")
+                         '())
+
+                (execute (prepare connection
+                           "INSERT INTO person (name, active, avatar) VALUES (:name, :active, :avatar)")
+                         (list (hash ":name" "alice" "active" #t "avatar" (list->blob '(1 2 255)))
+                               (hash "name" "bob" ":active" #f "avatar" void)))
+                "#,
+            )
+            .unwrap();
+
+        engine
+    }
+
+    fn run(engine: &mut Engine, program: &str) {
+        if let Err(e) = engine.compile_and_run_raw_program(program.to_string()) {
+            panic!("{}", e.emit_result_to_string("test.scm", program));
+        }
+    }
+
+    #[test]
+    fn named_parameters_and_row_mapping() {
+        let mut engine = engine();
+
+        run(
+            &mut engine,
+            r#"
+            (define by-name (prepare connection "SELECT id, name, active FROM person WHERE name = :name"))
+
+            (assert! (equal? (query by-name (hash "name" "alice")) '((1 "alice" 1))))
+            (assert! (equal? (query-row by-name (hash "name" "bob")) '(2 "bob" 0)))
+
+            (assert! (equal? (query/hash by-name (hash "name" "alice"))
+                             (list (hash "id" 1 "name" "alice" "active" 1))))
+
+            (define cursor (query-iter (prepare connection "SELECT name FROM person ORDER BY id") '()))
+            (assert! (equal? (rows/next! cursor) '("alice")))
+            (assert! (equal? (rows/next! cursor) '("bob")))
+            (assert! (not (rows/next! cursor)))
+            "#,
+        );
+
+        for program in [
+            // No rows
+            r#"(query-row by-name (hash "name" "carol"))"#,
+            // Missing a parameter
+            r#"(query by-name (hash))"#,
+            // Unknown parameter
+            r#"(query by-name (hash "name" "alice" "age" 10))"#,
+            // Wrong number of positional parameters
+            r#"(query by-name '())"#,
+        ] {
+            assert!(
+                engine.compile_and_run_raw_program(program).is_err(),
+                "{program}"
+            );
+        }
+    }
+
+    #[test]
+    fn blobs_round_trip() {
+        let mut engine = engine();
+
+        run(
+            &mut engine,
+            r#"
+            (define avatars (query (prepare connection "SELECT avatar FROM person ORDER BY id") '()))
+            (define avatar (car (car avatars)))
+
+            (assert! (blob? avatar))
+            (assert! (equal? (blob->list avatar) '(1 2 255)))
+            (assert! (equal? (blob-length avatar) 3))
+            (assert! (equal? (cadr avatars) (list void)))
+
+            (assert! (equal? (query (prepare connection "SELECT length(?1)") (list (string->blob "hello")))
+                             '((5))))
+            "#,
+        );
+
+        assert!(engine
+            .compile_and_run_raw_program("(list->blob '(256))")
+            .is_err());
+    }
+
+    #[test]
+    fn savepoints() {
+        let mut engine = engine();
+
+        run(
+            &mut engine,
+            r#"
+            (define count (prepare connection "SELECT count(*) FROM person"))
+            (define insert (prepare connection "INSERT INTO person (name) VALUES (?1)"))
+
+            (savepoint connection "outer")
+            (execute insert '(("carol")))
+            (savepoint connection "inner")
+            (execute insert '(("dave")))
+            (savepoint/rollback connection "inner")
+            (savepoint/release connection "outer")
+
+            (assert! (equal? (query-row count '()) '(3)))
+            "#,
+        );
+    }
+
+    #[test]
+    fn user_defined_functions() {
+        let mut engine = engine();
+
+        run(
+            &mut engine,
+            r#"
+            (create-function connection "shout" 1 (lambda (name) (string-append name "!")))
+            (create-aggregate connection "joined" 1
+              (lambda () "")
+              (lambda (acc name) (string-append acc name))
+              (lambda (acc) (string-length acc)))
+
+            (assert! (equal? (query (prepare connection "SELECT shout(name) FROM person ORDER BY id") '())
+                             '(("alice!") ("bob!"))))
+
+            (assert! (equal? (query-row (prepare connection "SELECT joined(name) FROM person") '())
+                             '(8)))
+
+            (assert! (equal? (query-row (prepare connection "SELECT joined(name) FROM person WHERE id > 10") '())
+                             '(0)))
+            "#,
+        );
+
+        engine
+            .compile_and_run_raw_program(
+                r#"(create-function connection "fails" 0 (lambda () (car '())))"#,
+            )
+            .unwrap();
+
+        let error = engine
+            .compile_and_run_raw_program(r#"(query (prepare connection "SELECT fails()") '())"#)
+            .unwrap_err();
+
+        assert!(error.to_string().contains("car"), "{error}");
+    }
+}
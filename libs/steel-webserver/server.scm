@@ -3,16 +3,18 @@
 (require "steel/result")
 
 (#%require-dylib "libsteel_webserver"
-                 (only-in start-server!
-                          setup-channels
-                          receiver/recv
-                          thread/join
-                          request-path
-                          request-type
-                          sender/send
-                          request-body
-                          Response-Ok
-                          Response-Err))
+                 (only-in make-router
+                          router/get!
+                          router/post!
+                          router/middleware!
+                          serve!
+                          request/method
+                          request/path
+                          request/param
+                          request/body
+                          response
+                          response/header
+                          response/status))
 
 (define connection (connection/open-in-memory))
 
@@ -41,73 +43,39 @@
                                    (list (list (hash-get person-hash 'name)
                                                (hash-get person-hash 'data)))))
 
-(define vm-receiver void)
-(define vm-sender void)
-(define command-channel void)
-
-;; We don't need the extra garbage laying around, so we'll just go ahead
-;; and unroot these channels here
-(let ([channels (setup-channels)])
-  (set! vm-sender (first channels))
-  (set! vm-receiver (second channels))
-  (set! command-channel (third channels)))
-
-(define (hello-world-handler)
-  (value->jsexpr-string (hash 'hello "world")))
-
-(define (people-handler)
-  (value->jsexpr-string (get-people)))
-
-(define (add-person-handler person-hash)
-  (displayln person-hash)
-  (add-person person-hash)
-  (value->jsexpr-string person-hash))
-
-(define *routes* (hash "hello/world" hello-world-handler "people" people-handler))
-
-(define *post-routes* (hash "add" add-person-handler))
-
-(define POST 0)
-(define GET 1)
-
-(define (handle-request req)
-  (define type (request-type req))
-  ; (displayln "Path: " (request-path req))
-  (cond
-    [(= GET type)
-     =>
-     ((hash-get *routes* (request-path req)))]
-
-    [(= POST type)
-     =>
-     ((hash-get *post-routes* (request-path req)) (request-body req))]
-    [else
-     =>
-     (displayln "Unknown request type!: " type)]))
-
-(struct WorkerThread (sender thread-handle))
-
-;;@doc
-;; Spawn a worker thread
-(define (spawn-worker-thread)
-  (spawn-thread! (lambda ()
-                   ;; Loop and handle requests until we're done!
-                   (event-loop)
-                   (stdout-simple-displayln "Shutting down thread: " (thread::current/id)))))
-
-;; Setup n threads to send on, round robin?
-(define (event-loop)
-  (with-handler
-   (lambda (err)
-     (report-error! err)
-     (sender/send vm-sender (Response-Err (to-string err))))
-   (->> (receiver/recv vm-receiver) (handle-request) (Response-Ok) (sender/send vm-sender)))
-
-  (event-loop))
-
-(displayln "Routes: " *routes*)
-
-(start-server! command-channel '("/*route") 3000)
-
-;; Try to run this on multiple threads...
-(map thread-join! (map (lambda (_) (spawn-worker-thread)) (range 0 10)))
+(define (json-response status value)
+  (response/header (response status (value->jsexpr-string value))
+                   "content-type"
+                   "application/json"))
+
+(define router (make-router))
+
+(router/get! router "/hello/world" (lambda (req) (json-response 200 (hash 'hello "world"))))
+
+(router/get! router "/people" (lambda (req) (json-response 200 (get-people))))
+
+(router/get! router
+             "/people/:name"
+             (lambda (req)
+               (define name (request/param req "name"))
+               (define matching (filter (lambda (person) (equal? (hash-get person "name") name))
+                                        (get-people)))
+               (if (null? matching)
+                   (response 404 (string-append "No person named " name))
+                   (json-response 200 (car matching)))))
+
+(router/post! router
+              "/people"
+              (lambda (req)
+                (define person (string->jsexpr (request/body req)))
+                (add-person person)
+                (json-response 201 person)))
+
+;; Log every request along with the status it was answered with
+(router/middleware! router
+                    (lambda (req next)
+                      (define res (next req))
+                      (displayln (request/method req) " " (request/path req) " " (response/status res))
+                      res))
+
+(serve! router "127.0.0.1:3000")
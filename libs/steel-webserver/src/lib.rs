@@ -1,247 +1,850 @@
-use axum::{extract::Query, http::StatusCode, routing::get, Router};
-use std::{collections::HashMap, net::SocketAddr};
+use abi_stable::std_types::{RBoxError, RResult, RString, RVec};
+use axum::{
+    body::{boxed, Body, Bytes, Full},
+    extract::{Path, Query},
+    http::{header, header::HeaderName, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response as HttpResponse},
+    routing::any,
+};
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+};
 use steel::{
-    rvals::{Custom, SerializableSteelVal},
-    steel_vm::ffi::{FFIModule, FFIValue, IntoFFIVal, RegisterFFIFn},
+    rvals::Custom,
+    steel_vm::ffi::{
+        FFIBoxedDynFunction, FFIModule, FFIValue, FromFFIVal, IntoFFIVal, RegisterFFIFn,
+    },
 };
+use tokio::{runtime::Handle, sync::oneshot};
 
 use crossbeam::channel::{unbounded, Receiver, Sender};
 
-use axum::extract::Path;
+steel::declare_module!(build_module);
+
+pub fn build_module() -> FFIModule {
+    let mut module = FFIModule::new("dylib/steel/webserver");
+
+    module
+        .register_fn("make-router", Router::default)
+        .register_fn("router/route!", Router::route)
+        .register_fn(
+            "router/get!",
+            |router: &mut Router, path: String, handler: FFIBoxedDynFunction| {
+                router.add(Some(Method::GET), path, handler)
+            },
+        )
+        .register_fn(
+            "router/post!",
+            |router: &mut Router, path: String, handler: FFIBoxedDynFunction| {
+                router.add(Some(Method::POST), path, handler)
+            },
+        )
+        .register_fn(
+            "router/put!",
+            |router: &mut Router, path: String, handler: FFIBoxedDynFunction| {
+                router.add(Some(Method::PUT), path, handler)
+            },
+        )
+        .register_fn(
+            "router/delete!",
+            |router: &mut Router, path: String, handler: FFIBoxedDynFunction| {
+                router.add(Some(Method::DELETE), path, handler)
+            },
+        )
+        .register_fn(
+            "router/patch!",
+            |router: &mut Router, path: String, handler: FFIBoxedDynFunction| {
+                router.add(Some(Method::PATCH), path, handler)
+            },
+        )
+        .register_fn(
+            "router/any!",
+            |router: &mut Router, path: String, handler: FFIBoxedDynFunction| {
+                router.add(None, path, handler)
+            },
+        )
+        .register_fn("router/middleware!", Router::middleware)
+        .register_fn("server/bind", Server::bind)
+        .register_fn("server/address", Server::address)
+        .register_fn("server/run!", Server::run)
+        .register_fn("server/stop!", Server::stop)
+        .register_fn("serve!", serve)
+        .register_fn("request/method", Request::method)
+        .register_fn("request/path", Request::path)
+        .register_fn("request/params", Request::params)
+        .register_fn("request/param", Request::param)
+        .register_fn("request/query", Request::query)
+        .register_fn("request/query-param", Request::query_param)
+        .register_fn("request/headers", Request::headers)
+        .register_fn("request/header", Request::header)
+        .register_fn("request/body", Request::body)
+        .register_fn("request/body-bytes", Request::body_bytes)
+        .register_fn("body-bytes->list", BodyBytes::to_list)
+        .register_fn("body-bytes-length", BodyBytes::length)
+        .register_fn("response", Response::new)
+        .register_fn("response/stream", Response::stream)
+        .register_fn("response/header", Response::with_header)
+        .register_fn("response/status", Response::status)
+        .register_fn("response/body", Response::body);
+
+    module
+}
+
+fn ffi_error(message: impl Into<String>) -> RBoxError {
+    let error: Box<dyn std::error::Error + Send + Sync> = message.into().into();
+    RBoxError::from_box(error)
+}
 
-#[derive(Copy, Clone, PartialEq, Eq)]
-enum RequestType {
-    // Post,
-    Get,
+fn string_map(map: &HashMap<String, String>) -> FFIValue {
+    FFIValue::HashMap(
+        map.iter()
+            .map(|(key, value)| {
+                (
+                    FFIValue::StringV(key.as_str().into()),
+                    FFIValue::StringV(value.as_str().into()),
+                )
+            })
+            .collect(),
+    )
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone)]
+struct Route {
+    // `None` matches any method
+    method: Option<Method>,
+    path: String,
+    handler: FFIBoxedDynFunction,
+}
+
+/// The routes and middleware of a server. Handlers are Steel procedures that get called
+/// on the thread running the server, with a request as their only argument.
+#[derive(Clone, Default)]
+struct Router {
+    routes: Vec<Route>,
+    middleware: Vec<FFIBoxedDynFunction>,
+}
+
+impl Custom for Router {}
+
+impl Router {
+    fn route(
+        &mut self,
+        method: String,
+        path: String,
+        handler: FFIBoxedDynFunction,
+    ) -> Result<(), String> {
+        let method = match method.to_ascii_uppercase().as_str() {
+            "ANY" | "*" => None,
+            method => Some(
+                Method::from_bytes(method.as_bytes())
+                    .map_err(|_| format!("router/route!: invalid method: {method}"))?,
+            ),
+        };
+
+        self.add(method, path, handler)
+    }
+
+    fn add(
+        &mut self,
+        method: Option<Method>,
+        path: String,
+        handler: FFIBoxedDynFunction,
+    ) -> Result<(), String> {
+        if !path.starts_with('/') {
+            return Err(format!("route paths must start with a `/`: {path}"));
+        }
+
+        if self
+            .routes
+            .iter()
+            .any(|route| route.path == path && route.method == method)
+        {
+            return Err(format!("duplicate route: {path}"));
+        }
+
+        self.routes.push(Route {
+            method,
+            path,
+            handler,
+        });
+
+        Ok(())
+    }
+
+    // Middleware runs in the order that it was registered, the first one being the outermost
+    fn middleware(&mut self, middleware: FFIBoxedDynFunction) {
+        self.middleware.push(middleware);
+    }
+
+    // The distinct paths, in the order they were first registered
+    fn paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = Vec::new();
+
+        for route in &self.routes {
+            if !paths.contains(&route.path) {
+                paths.push(route.path.clone());
+            }
+        }
+
+        paths
+    }
+
+    fn find(&self, path: &str, method: &Method) -> Result<&Route, Vec<Method>> {
+        let candidates = || self.routes.iter().filter(|route| route.path == path);
+
+        let exact = candidates().find(|route| route.method.as_ref() == Some(method));
+
+        // HEAD falls back to GET, hyper takes care of dropping the body
+        let head = || {
+            candidates().find(|route| *method == Method::HEAD && route.method == Some(Method::GET))
+        };
+
+        let wildcard = || candidates().find(|route| route.method.is_none());
+
+        exact.or_else(head).or_else(wildcard).ok_or_else(|| {
+            candidates()
+                .filter_map(|route| route.method.clone())
+                .collect()
+        })
+    }
+}
+
+#[derive(Clone)]
 struct Request {
-    typ: RequestType,
+    method: Method,
     path: String,
-    body: Option<String>,
-    query_parameters: HashMap<String, String>,
+    params: HashMap<String, String>,
+    query: HashMap<String, String>,
+    headers: HashMap<String, String>,
+    body: Bytes,
 }
 
 impl Custom for Request {}
 
+// The raw bytes of a request body, for bodies that aren't text
+#[derive(Clone)]
+struct BodyBytes(Bytes);
+
+impl Custom for BodyBytes {}
+
+impl BodyBytes {
+    fn to_list(&self) -> Vec<isize> {
+        self.0.iter().map(|byte| *byte as isize).collect()
+    }
+
+    fn length(&self) -> usize {
+        self.0.len()
+    }
+}
+
 impl Request {
-    fn get_type(&self) -> isize {
-        match self.typ {
-            // RequestType::Post => 0,
-            RequestType::Get => 1,
-        }
+    fn method(&self) -> String {
+        self.method.to_string()
     }
 
-    fn get_path(&self) -> String {
+    fn path(&self) -> String {
         self.path.clone()
     }
 
-    fn get_body(&self) -> Option<String> {
-        self.body.clone()
+    fn params(&self) -> FFIValue {
+        string_map(&self.params)
     }
 
-    fn get_query_parameters(&self) -> FFIValue {
-        if self.query_parameters.is_empty() {
-            FFIValue::BoolV(false)
-        } else {
-            FFIValue::HashMap(
-                self.query_parameters
-                    .clone()
-                    .into_iter()
-                    .map(|(key, value)| {
-                        (key.into_ffi_val().unwrap(), value.into_ffi_val().unwrap())
-                    })
-                    .collect(),
-            )
-        }
+    fn param(&self, name: String) -> Option<String> {
+        self.params.get(&name).cloned()
+    }
+
+    fn query(&self) -> FFIValue {
+        string_map(&self.query)
+    }
+
+    fn query_param(&self, name: String) -> Option<String> {
+        self.query.get(&name).cloned()
+    }
+
+    // Header names are always lower case
+    fn headers(&self) -> FFIValue {
+        string_map(&self.headers)
+    }
+
+    fn header(&self, name: String) -> Option<String> {
+        self.headers.get(&name.to_ascii_lowercase()).cloned()
+    }
+
+    fn body(&self) -> Result<String, String> {
+        std::str::from_utf8(&self.body)
+            .map(|body| body.to_string())
+            .map_err(|_| "the request body isn't valid UTF-8, use request/body-bytes".to_string())
+    }
+
+    fn body_bytes(&self) -> BodyBytes {
+        BodyBytes(self.body.clone())
     }
 }
 
 #[derive(Clone)]
-struct CommandMessenger {
-    sender: Sender<Request>,
-    receiver: Receiver<RequestResult>,
+enum ResponseBody {
+    Text(String),
+    // A procedure that gets called with a `send` function, which writes a chunk
+    // of the body to the client
+    Stream(FFIBoxedDynFunction),
 }
 
-impl Custom for CommandMessenger {
-    fn into_serializable_steelval(&mut self) -> Option<steel::rvals::SerializableSteelVal> {
-        Some(steel::rvals::SerializableSteelVal::Custom(Box::new(
-            self.clone(),
-        )))
-    }
+#[derive(Clone)]
+struct Response {
+    status: StatusCode,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    body: ResponseBody,
 }
 
-impl CommandMessenger {
-    pub fn new(sender: Sender<Request>, receiver: Receiver<RequestResult>) -> Self {
-        Self { sender, receiver }
+impl Custom for Response {}
+
+impl Response {
+    fn new(status: usize, body: String) -> Result<Self, String> {
+        Ok(Response {
+            status: status_code(status)?,
+            headers: Vec::new(),
+            body: ResponseBody::Text(body),
+        })
     }
 
-    pub fn send_request(&self, request: Request) {
-        // Send the message to the VM context to parse
-        match self.sender.send(request) {
-            Ok(_) => {}
-            Err(e) => {
-                println!("Error: {e:?}")
+    fn stream(status: usize, producer: FFIBoxedDynFunction) -> Result<Self, String> {
+        Ok(Response {
+            status: status_code(status)?,
+            headers: Vec::new(),
+            body: ResponseBody::Stream(producer),
+        })
+    }
+
+    fn with_header(mut self, name: String, value: String) -> Result<Self, String> {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("invalid header name: {name}"))?;
+        let value =
+            HeaderValue::from_str(&value).map_err(|_| format!("invalid header value: {value}"))?;
+
+        self.headers.push((name, value));
+
+        Ok(self)
+    }
+
+    fn status(&self) -> usize {
+        self.status.as_u16() as usize
+    }
+
+    fn body(&self) -> Option<String> {
+        match &self.body {
+            ResponseBody::Text(text) => Some(text.clone()),
+            ResponseBody::Stream(_) => None,
+        }
+    }
+
+    // Handlers can return either a string, which is sent as a 200 with a text body,
+    // or a response built with `response`
+    fn from_handler_result(value: FFIValue) -> RResult<Self, RBoxError> {
+        match value {
+            FFIValue::StringV(text) => RResult::ROk(Response {
+                status: StatusCode::OK,
+                headers: Vec::new(),
+                body: ResponseBody::Text(text.into_string()),
+            }),
+            value @ FFIValue::Custom { .. } => Response::from_ffi_val(value),
+            other => RResult::RErr(ffi_error(format!(
+                "handlers must return a string or a response, found: {other:?}"
+            ))),
+        }
+    }
+
+    fn into_http(
+        self,
+    ) -> (
+        HttpResponse,
+        Option<(FFIBoxedDynFunction, hyper::body::Sender)>,
+    ) {
+        let mut response = HttpResponse::new(boxed(Full::from(Bytes::new())));
+        *response.status_mut() = self.status;
+
+        let stream = match self.body {
+            ResponseBody::Text(text) => {
+                response.headers_mut().insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("text/plain; charset=utf-8"),
+                );
+                *response.body_mut() = boxed(Full::from(text));
+                None
+            }
+            ResponseBody::Stream(producer) => {
+                let (sender, body) = Body::channel();
+                *response.body_mut() = boxed(body);
+                Some((producer, sender))
             }
         };
+
+        // Explicit headers take precedence over the default content type
+        let mut explicit = HeaderMap::new();
+        for (name, value) in self.headers {
+            explicit.append(name, value);
+        }
+        for name in explicit.keys() {
+            response.headers_mut().remove(name);
+        }
+        response.headers_mut().extend(explicit);
+
+        (response, stream)
     }
 }
 
-#[derive(Clone)]
-struct WrappedReceiver {
-    receiver: Receiver<Request>,
+fn status_code(status: usize) -> Result<StatusCode, String> {
+    u16::try_from(status)
+        .ok()
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .ok_or_else(|| format!("invalid status code: {status}"))
 }
 
-impl WrappedReceiver {
-    fn recv(&self) -> Request {
-        self.receiver.recv().unwrap()
+// Runs the middleware chain, ending in the handler. Each middleware gets the request along
+// with a `next` function that continues down the chain.
+fn call_chain(
+    middleware: &[FFIBoxedDynFunction],
+    handler: &FFIBoxedDynFunction,
+    request: FFIValue,
+) -> RResult<FFIValue, RBoxError> {
+    let result = match middleware.split_first() {
+        None => handler.call([request]),
+        Some((first, rest)) => {
+            let rest = rest.to_vec();
+            let handler = handler.clone();
+
+            let next = FFIBoxedDynFunction {
+                name: RString::from("next"),
+                arity: 1,
+                function: Arc::new(move |mut args: RVec<FFIValue>| {
+                    if args.len() != 1 {
+                        return RResult::RErr(ffi_error("next: arity mismatch, expected 1"));
+                    }
+
+                    call_chain(&rest, &handler, args.pop().unwrap())
+                }),
+            };
+
+            first.call([request, FFIValue::BoxedFunction(next)])
+        }
+    };
+
+    // Normalize so that middleware always sees a response coming back from `next`
+    match result {
+        RResult::ROk(value) => match Response::from_handler_result(value) {
+            RResult::ROk(response) => response.into_ffi_val(),
+            RResult::RErr(e) => RResult::RErr(e),
+        },
+        RResult::RErr(e) => RResult::RErr(e),
     }
 }
 
-impl Custom for WrappedReceiver {
-    fn into_serializable_steelval(&mut self) -> Option<steel::rvals::SerializableSteelVal> {
-        Some(steel::rvals::SerializableSteelVal::Custom(Box::new(
-            self.clone(),
-        )))
+// Feeds the chunks produced by a streaming response into the body. Runs on the VM thread,
+// after the head of the response has already been sent.
+fn stream_body(producer: FFIBoxedDynFunction, sender: hyper::body::Sender, runtime: &Handle) {
+    let sender = Arc::new(Mutex::new(Some(sender)));
+
+    let send = {
+        let sender = Arc::clone(&sender);
+        let runtime = runtime.clone();
+
+        FFIBoxedDynFunction {
+            name: RString::from("send"),
+            arity: 1,
+            function: Arc::new(move |args: RVec<FFIValue>| {
+                let chunk = match args.as_slice() {
+                    [FFIValue::StringV(chunk)] => Bytes::from(chunk.to_string()),
+                    _ => return RResult::RErr(ffi_error("send: expected a single string")),
+                };
+
+                let mut guard = sender.lock().unwrap();
+
+                // Once the client goes away, there is nothing left to send to
+                let sent = match guard.as_mut() {
+                    Some(sender) => runtime.block_on(sender.send_data(chunk)).is_ok(),
+                    None => false,
+                };
+
+                RResult::ROk(FFIValue::BoolV(sent))
+            }),
+        }
+    };
+
+    let result = producer.call([FFIValue::BoxedFunction(send)]);
+
+    let sender = sender.lock().unwrap().take();
+
+    if let (Some(sender), RResult::RErr(e)) = (sender, result) {
+        eprintln!("Error while streaming response: {e}");
+        sender.abort();
     }
 }
 
+struct Job {
+    // Index into the distinct paths of the router
+    path: usize,
+    request: Request,
+    reply: oneshot::Sender<HttpResponse>,
+}
+
+struct ServerState {
+    address: SocketAddr,
+    router: Router,
+    paths: Vec<String>,
+    jobs: Receiver<Job>,
+    runtime: Handle,
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// A bound server. Connections are accepted on a background thread, while the handlers
+/// run on whichever thread calls `server/run!`.
 #[derive(Clone)]
-struct WrappedSender {
-    sender: Sender<RequestResult>,
+struct Server {
+    state: Arc<ServerState>,
 }
 
-impl WrappedSender {
-    fn send(&self, value: RequestResult) {
-        self.sender.send(value).unwrap()
+impl Custom for Server {}
+
+impl Server {
+    fn bind(router: Router, address: String) -> Result<Server, String> {
+        let listener = TcpListener::bind(&address).map_err(|e| e.to_string())?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+        let local_address = listener.local_addr().map_err(|e| e.to_string())?;
+
+        let paths = router.paths();
+        let (job_sender, jobs) = unbounded();
+
+        // axum panics on paths it can't route, i.e. conflicting parameters
+        let app = std::panic::catch_unwind(|| app(&paths, job_sender))
+            .map_err(|_| "server/bind: conflicting routes".to_string())?;
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let handle = runtime.handle().clone();
+
+        let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+
+        let thread = std::thread::spawn(move || {
+            runtime.block_on(async move {
+                let server = match axum::Server::from_tcp(listener) {
+                    Ok(server) => server,
+                    Err(e) => {
+                        eprintln!("Unable to start server: {e}");
+                        return;
+                    }
+                };
+
+                let result = server
+                    .serve(app.into_make_service())
+                    .with_graceful_shutdown(async {
+                        shutdown_signal.await.ok();
+                    })
+                    .await;
+
+                if let Err(e) = result {
+                    eprintln!("Server error: {e}");
+                }
+            })
+        });
+
+        Ok(Server {
+            state: Arc::new(ServerState {
+                address: local_address,
+                router,
+                paths,
+                jobs,
+                runtime: handle,
+                shutdown: Mutex::new(Some(shutdown)),
+                thread: Mutex::new(Some(thread)),
+            }),
+        })
     }
-}
 
-impl Custom for WrappedSender {
-    fn into_serializable_steelval(&mut self) -> Option<steel::rvals::SerializableSteelVal> {
-        Some(steel::rvals::SerializableSteelVal::Custom(Box::new(
-            self.clone(),
-        )))
+    fn address(&self) -> String {
+        self.state.address.to_string()
     }
-}
 
-fn setup_channels() -> Vec<FFIValue> {
-    let (command_sender, vm_receiver) = unbounded();
-    let (vm_sender, command_receiver) = unbounded();
+    // Handles requests on the current thread until the server is stopped
+    fn run(self) {
+        let state = &self.state;
+
+        // The senders live in the app, so this ends once the server has shut down
+        while let Ok(job) = state.jobs.recv() {
+            let (response, stream) = state.respond(job.path, job.request);
 
-    let command_messenger = CommandMessenger::new(command_sender, command_receiver);
+            // The client may have disconnected in the meantime
+            let _ = job.reply.send(response);
 
-    vec![
-        WrappedSender { sender: vm_sender }.into_ffi_val().unwrap(),
-        WrappedReceiver {
-            receiver: vm_receiver,
+            if let Some((producer, sender)) = stream {
+                stream_body(producer, sender, &state.runtime);
+            }
         }
-        .into_ffi_val()
-        .unwrap(),
-        command_messenger.into_ffi_val().unwrap(),
-    ]
-}
 
-steel::declare_module!(build_module);
+        if let Some(thread) = state.thread.lock().unwrap().take() {
+            thread.join().ok();
+        }
+    }
 
-#[derive(Clone)]
-enum RequestResult {
-    Ok(String),
-    Err(String),
+    // Stops accepting connections. Requests that are in flight still get handled.
+    fn stop(self) {
+        if let Some(shutdown) = self.state.shutdown.lock().unwrap().take() {
+            shutdown.send(()).ok();
+        }
+    }
 }
 
-impl Custom for RequestResult {
-    fn into_serializable_steelval(&mut self) -> Option<steel::rvals::SerializableSteelVal> {
-        Some(SerializableSteelVal::Custom(Box::new(self.clone())))
+impl ServerState {
+    fn respond(
+        &self,
+        path: usize,
+        request: Request,
+    ) -> (
+        HttpResponse,
+        Option<(FFIBoxedDynFunction, hyper::body::Sender)>,
+    ) {
+        let route = match self.router.find(&self.paths[path], &request.method) {
+            Ok(route) => route,
+            Err(allowed) => {
+                let allowed = allowed
+                    .iter()
+                    .map(Method::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                let response = (
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    [(header::ALLOW, allowed)],
+                    "Method Not Allowed",
+                )
+                    .into_response();
+
+                return (response, None);
+            }
+        };
+
+        let request = match request.into_ffi_val() {
+            RResult::ROk(request) => request,
+            RResult::RErr(e) => return (internal_error(e), None),
+        };
+
+        match call_chain(&self.router.middleware, &route.handler, request)
+            .and_then(Response::from_handler_result)
+        {
+            RResult::ROk(response) => response.into_http(),
+            RResult::RErr(e) => (internal_error(e), None),
+        }
     }
 }
 
-pub fn build_module() -> FFIModule {
-    let mut module = FFIModule::new("dylib/steel/webserver");
+fn internal_error(error: RBoxError) -> HttpResponse {
+    eprintln!("Error while handling request: {error}");
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
 
-    module
-        .register_fn("start-server!", spawn_server)
-        .register_fn("setup-channels", setup_channels)
-        .register_fn("receiver/recv", WrappedReceiver::recv)
-        .register_fn("sender/send", WrappedSender::send)
-        .register_fn("thread/join", WrappedJoinHandler::join)
-        .register_fn("request-path", Request::get_path)
-        .register_fn("request-body", Request::get_body)
-        .register_fn("request-type", Request::get_type)
-        .register_fn("request-query-params", Request::get_query_parameters)
-        .register_fn("Response-Ok", RequestResult::Ok)
-        .register_fn("Response-Err", RequestResult::Err);
+fn app(paths: &[String], jobs: Sender<Job>) -> axum::Router {
+    let mut app = axum::Router::new();
+
+    for (index, path) in paths.iter().enumerate() {
+        let jobs = jobs.clone();
+
+        app = app.route(
+            path,
+            any(
+                move |method: Method,
+                      headers: HeaderMap,
+                      uri: axum::http::Uri,
+                      params: Option<Path<HashMap<String, String>>>,
+                      Query(query): Query<HashMap<String, String>>,
+                      body: Bytes| async move {
+                    let mut header_values: HashMap<String, String> = HashMap::new();
+
+                    // Repeated headers are combined into a comma separated list
+                    for (name, value) in headers.iter() {
+                        let value = String::from_utf8_lossy(value.as_bytes());
+
+                        header_values
+                            .entry(name.as_str().to_string())
+                            .and_modify(|existing| {
+                                existing.push_str(", ");
+                                existing.push_str(&value);
+                            })
+                            .or_insert_with(|| value.into_owned());
+                    }
+
+                    let request = Request {
+                        method,
+                        path: uri.path().to_string(),
+                        params: params.map(|Path(params)| params).unwrap_or_default(),
+                        query,
+                        headers: header_values,
+                        body,
+                    };
+
+                    let (reply, response) = oneshot::channel();
+
+                    let job = Job {
+                        path: index,
+                        request,
+                        reply,
+                    };
+
+                    if jobs.send(job).is_err() {
+                        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+                    }
+
+                    response
+                        .await
+                        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+                },
+            ),
+        );
+    }
 
-    module
+    app
 }
 
-struct WrappedJoinHandler {
-    handle: Option<std::thread::JoinHandle<()>>,
+fn serve(router: Router, address: String) -> Result<(), String> {
+    Server::bind(router, address)?.run();
+    Ok(())
 }
 
-impl WrappedJoinHandler {
-    fn join(&mut self) {
-        self.handle.take().unwrap().join().unwrap()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use abi_stable::std_types::RBox;
+    use steel::{steel_vm::engine::Engine, SteelVal};
+
+    const PROGRAM: &str = r#"
+        (require-builtin dylib/steel/webserver)
+
+        (define router (make-router))
+
+        (router/get! router "/hello/:name"
+          (lambda (req) (string-append "hello " (request/param req "name"))))
+
+        (router/post! router "/echo"
+          (lambda (req)
+            (response/header (response 201 (request/body req)) "content-type" "application/json")))
+
+        (router/post! router "/upload"
+          (lambda (req)
+            (define bytes (request/body-bytes req))
+            (to-string (body-bytes-length bytes) (body-bytes->list bytes))))
+
+        (router/post! router "/text" (lambda (req) (request/body req)))
+
+        (router/route! router "put" "/items/:id"
+          (lambda (req)
+            (string-append (request/param req "id") ":" (request/header req "X-Token"))))
+
+        (router/get! router "/search"
+          (lambda (req) (hash-ref (request/query req) "q")))
+
+        (router/get! router "/stream"
+          (lambda (req)
+            (response/stream 200
+              (lambda (send) (send "one,") (send "two,") (send "three")))))
+
+        (router/get! router "/fail" (lambda (req) (car '())))
+
+        (router/middleware! router
+          (lambda (req next) (response/header (next req) "x-middleware" (request/method req))))
+
+        (router/delete! router "/stop" (lambda (req) (server/stop! server) "stopping"))
+
+        (define server (server/bind router "127.0.0.1:0"))
+
+        (router/any! router "/unused" (lambda (req) "not bound"))
+
+        (server/address server)
+    "#;
+
+    fn status(result: Result<ureq::Response, ureq::Error>) -> (u16, String) {
+        let response = match result {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => panic!("{e}"),
+        };
+
+        (response.status(), response.into_string().unwrap())
     }
-}
 
-impl Custom for WrappedJoinHandler {}
+    #[test]
+    fn serves_requests_over_loopback() {
+        let mut engine = Engine::new();
+        engine
+            .register_external_module(RBox::new(build_module()))
+            .unwrap();
 
-fn spawn_server(
-    command_messenger: CommandMessenger,
-    routes: Vec<String>,
-    port: usize,
-) -> WrappedJoinHandler {
-    let handle = std::thread::spawn(move || {
-        tokio::runtime::Builder::new_multi_thread()
-            .enable_all()
-            .build()
-            .unwrap()
-            .block_on(async {
-                let mut app = Router::new();
-
-                for route in routes {
-                    let messenger = command_messenger.clone();
-
-                    app = app.route(
-                        &route,
-                        get(
-                            |Path(path): Path<String>,
-                             Query(params): Query<std::collections::HashMap<String, String>>,
-                             body: Option<String>| async move {
-                                let request = Request {
-                                    typ: RequestType::Get,
-                                    path,
-                                    body,
-                                    query_parameters: params,
-                                };
-
-                                messenger.send_request(request);
-
-                                match messenger.receiver.recv() {
-                                    Ok(RequestResult::Ok(v)) => Ok(v),
-                                    Ok(RequestResult::Err(_)) => {
-                                        Err(StatusCode::INTERNAL_SERVER_ERROR)
-                                    }
-                                    Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-                                }
-                            },
-                        ),
-                    );
-                }
+        let address = match engine.compile_and_run_raw_program(PROGRAM).unwrap().pop() {
+            Some(SteelVal::StringV(address)) => address.to_string(),
+            other => panic!("{other:?}"),
+        };
 
-                // run it
-                let addr = SocketAddr::from(([127, 0, 0, 1], port as u16));
-                println!("listening on {addr}");
-                axum::Server::bind(&addr)
-                    .serve(app.into_make_service())
-                    .await
+        let base = format!("http://{address}");
+
+        let client = std::thread::spawn(move || {
+            // Stop the server even when a check fails, so the test fails instead of hanging
+            let checks = std::panic::catch_unwind(|| {
+                let hello = ureq::get(&format!("{base}/hello/steel")).call().unwrap();
+                assert_eq!(hello.header("x-middleware"), Some("GET"));
+                assert_eq!(hello.into_string().unwrap(), "hello steel");
+
+                let echo = ureq::post(&format!("{base}/echo"))
+                    .send_string("{\"a\": 1}")
                     .unwrap();
-            })
-    });
+                assert_eq!(echo.status(), 201);
+                assert_eq!(echo.content_type(), "application/json");
+                assert_eq!(echo.into_string().unwrap(), "{\"a\": 1}");
+
+                // Binary bodies arrive untouched, but can't be read as text
+                let binary = [0, 159, 146, 150, 255];
+                let upload = ureq::post(&format!("{base}/upload"))
+                    .send_bytes(&binary)
+                    .unwrap();
+                assert_eq!(upload.into_string().unwrap(), "5 '(0 159 146 150 255)");
+                assert_eq!(
+                    status(ureq::post(&format!("{base}/text")).send_bytes(&binary)).0,
+                    500
+                );
+
+                let item = ureq::put(&format!("{base}/items/42"))
+                    .set("x-token", "secret")
+                    .call()
+                    .unwrap();
+                assert_eq!(item.into_string().unwrap(), "42:secret");
+
+                let search = ureq::get(&format!("{base}/search?q=lisp")).call().unwrap();
+                assert_eq!(search.into_string().unwrap(), "lisp");
+
+                let stream = ureq::get(&format!("{base}/stream")).call().unwrap();
+                assert_eq!(stream.into_string().unwrap(), "one,two,three");
+
+                assert_eq!(status(ureq::get(&format!("{base}/fail")).call()).0, 500);
+                assert_eq!(status(ureq::post(&format!("{base}/hello/x")).call()).0, 405);
+                assert_eq!(status(ureq::get(&format!("{base}/missing")).call()).0, 404);
+
+                // Routes added after binding aren't served
+                assert_eq!(status(ureq::get(&format!("{base}/unused")).call()).0, 404);
+            });
+
+            let stopped = status(ureq::delete(&format!("{base}/stop")).call());
+            if let Err(panic) = checks {
+                std::panic::resume_unwind(panic);
+            }
+            stopped
+        });
+
+        engine
+            .compile_and_run_raw_program("(server/run! server)")
+            .unwrap();
 
-    WrappedJoinHandler {
-        handle: Some(handle),
+        assert_eq!(client.join().unwrap(), (200, "stopping".to_string()));
     }
 }
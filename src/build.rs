@@ -0,0 +1,371 @@
+//! Standalone executables for `steel build`.
+//!
+//! The entrypoint is compiled, along with every module and cog it requires, into a single program
+//! image. That image is then embedded into a small generated cargo project which links against the
+//! same steel-core, with the same features, as this binary, and which is built in release mode to
+//! produce the executable.
+//!
+//! Modules are resolved at build time, so the resulting executable doesn't need the sources or the
+//! installed cogs to be present when it runs. Native (dylib) cogs are still loaded at run time.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use steel::steel_vm::engine::{Engine, NonInteractiveProgramImage};
+use steel::SteelVal;
+
+/// Set to a local steel-core crate to build executables against it, rather than the one this binary
+/// was built from. Overridden by [`BuildOptions::steel_core_path`].
+pub const STEEL_CORE_PATH_VAR: &str = "STEEL_CORE_PATH";
+
+// The image format is tied to the exact steel-core that produced it, so by default executables are
+// built against the sources of the steel-core this binary was built with
+const BUILT_WITH_STEEL_CORE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/crates/steel-core");
+
+const PROGRAM_IMAGE: &str = "program.bin";
+
+const RUNNER_SOURCE: &str = r#"use steel::steel_vm::engine::{Engine, NonInteractiveProgramImage};
+use steel::SteelVal;
+
+fn main() {
+    let program = match NonInteractiveProgramImage::from_bytes(include_bytes!("program.bin")) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    let mut engine = Engine::new();
+    engine.register_value(
+        "std::env::args",
+        SteelVal::ListV(
+            std::env::args()
+                .skip(1)
+                .map(|x| SteelVal::StringV(x.into()))
+                .collect(),
+        ),
+    );
+
+    if let Err(e) = engine.run_non_interactive_program_image(program) {
+        engine.raise_error(e);
+        std::process::exit(1);
+    }
+}
+"#;
+
+#[derive(Debug, Clone)]
+pub struct BuildOptions {
+    /// The program to build
+    pub entrypoint: PathBuf,
+    /// Where to write the executable. Defaults to the name of the entrypoint, in the current directory
+    pub output: Option<PathBuf>,
+    /// Directory to generate the cargo project in. Defaults to `steel_target/<name>`
+    pub project_dir: Option<PathBuf>,
+    /// A local steel-core crate to build against. Defaults to the `STEEL_CORE_PATH` environment
+    /// variable, and then to the steel-core this binary was built with
+    pub steel_core_path: Option<PathBuf>,
+    /// Build the generated project. If false, only the project is generated
+    pub build: bool,
+}
+
+impl BuildOptions {
+    pub fn new(entrypoint: PathBuf) -> Self {
+        Self {
+            entrypoint,
+            output: None,
+            project_dir: None,
+            steel_core_path: None,
+            build: true,
+        }
+    }
+
+    fn output(&self) -> PathBuf {
+        self.output.clone().unwrap_or_else(|| {
+            PathBuf::from(self.entrypoint.file_stem().unwrap_or_default())
+                .with_extension(std::env::consts::EXE_EXTENSION)
+        })
+    }
+}
+
+/// Compiles the entrypoint and generates the cargo project for it, building it and copying the
+/// executable to the output path if requested. Returns the path to the generated project.
+pub fn build_executable(options: &BuildOptions) -> Result<PathBuf, Box<dyn Error>> {
+    let output = options.output();
+    let name = package_name(&output)?;
+
+    let image = compile_program_image(&options.entrypoint)?;
+
+    let project = options
+        .project_dir
+        .clone()
+        .unwrap_or_else(|| Path::new("steel_target").join(&name));
+
+    write_project(&project, &name, &image, options)?;
+
+    if !options.build {
+        return Ok(project);
+    }
+
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+
+    let status = Command::new(&cargo)
+        .arg("build")
+        .arg("--release")
+        .arg("--manifest-path")
+        .arg(project.join("Cargo.toml"))
+        // Ignore any target directory configured in the environment, so we know where the executable ends up
+        .arg("--target-dir")
+        .arg(project.join("target"))
+        .status()
+        .map_err(|e| format!("unable to run {}: {e}", cargo.to_string_lossy()))?;
+
+    if !status.success() {
+        return Err(format!("building the project in {} failed", project.display()).into());
+    }
+
+    let executable = project
+        .join("target")
+        .join("release")
+        .join(format!("{name}{}", std::env::consts::EXE_SUFFIX));
+
+    std::fs::copy(&executable, &output).map_err(|e| {
+        format!(
+            "unable to copy {} to {}: {e}",
+            executable.display(),
+            output.display()
+        )
+    })?;
+
+    Ok(project)
+}
+
+fn compile_program_image(entrypoint: &Path) -> Result<NonInteractiveProgramImage, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(entrypoint)
+        .map_err(|e| format!("unable to read {}: {e}", entrypoint.display()))?;
+
+    // The executable registers the command line arguments before running the image,
+    // so the program needs to be compiled with them in scope as well
+    let mut engine = Engine::new();
+    engine.register_value("std::env::args", SteelVal::ListV(vec![].into()));
+
+    engine
+        .emit_non_interactive_program_image(contents, entrypoint.to_path_buf())
+        .map_err(|e| {
+            engine
+                .raise_error_to_string(e.clone())
+                .unwrap_or_else(|| e.to_string())
+                .into()
+        })
+}
+
+fn write_project(
+    project: &Path,
+    name: &str,
+    image: &NonInteractiveProgramImage,
+    options: &BuildOptions,
+) -> Result<(), Box<dyn Error>> {
+    let src = project.join("src");
+
+    // The rest of the directory is left alone, so that the build cache is reused between builds
+    std::fs::create_dir_all(&src)
+        .map_err(|e| format!("unable to create {}: {e}", src.display()))?;
+
+    std::fs::write(src.join(PROGRAM_IMAGE), image.to_bytes()?)?;
+    std::fs::write(src.join("main.rs"), RUNNER_SOURCE)?;
+    std::fs::write(project.join("Cargo.toml"), cargo_manifest(name, options)?)?;
+
+    Ok(())
+}
+
+fn cargo_manifest(name: &str, options: &BuildOptions) -> Result<String, Box<dyn Error>> {
+    // Images can only be read by a steel-core built with the same features as the one that wrote them
+    let features = steel::compiler::image::enabled_features()
+        .iter()
+        .map(|x| format!("{x:?}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let path = steel_core_path(options)?;
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("unable to find steel-core at {}: {e}", path.display()))?;
+    let path = path.to_string_lossy();
+
+    Ok(format!(
+        r#"[package]
+name = "{name}"
+edition = "2021"
+version = "0.1.0"
+publish = false
+
+# Keep this project out of any workspace it happens to be generated in
+[workspace]
+
+[dependencies]
+steel-core = {{ path = {path:?}, features = [{features}] }}
+
+[profile.release]
+debug = false
+lto = true
+"#
+    ))
+}
+
+fn steel_core_path(options: &BuildOptions) -> Result<PathBuf, Box<dyn Error>> {
+    if let Some(path) = options
+        .steel_core_path
+        .clone()
+        .or_else(|| std::env::var_os(STEEL_CORE_PATH_VAR).map(PathBuf::from))
+    {
+        return Ok(path);
+    }
+
+    let path = PathBuf::from(BUILT_WITH_STEEL_CORE);
+
+    if !path.join("Cargo.toml").exists() {
+        return Err(format!(
+            "unable to find the steel-core sources this binary was built with at {}. \
+             Set {STEEL_CORE_PATH_VAR} to a checkout of steel-core {}",
+            path.display(),
+            steel::VERSION
+        )
+        .into());
+    }
+
+    Ok(path)
+}
+
+fn package_name(output: &Path) -> Result<String, Box<dyn Error>> {
+    let stem = output
+        .file_stem()
+        .and_then(|x| x.to_str())
+        .ok_or_else(|| format!("invalid output path: {}", output.display()))?;
+
+    let name = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err(format!("the output name must start with a letter: {stem}").into());
+    }
+
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_options(name: &str, contents: &str) -> (PathBuf, BuildOptions) {
+        let directory = std::env::temp_dir().join(format!("steel-build-{name}"));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let entrypoint = directory.join("app.scm");
+        std::fs::write(&entrypoint, contents).unwrap();
+
+        let options = BuildOptions {
+            output: Some(directory.join("app")),
+            project_dir: Some(directory.join("project")),
+            build: false,
+            ..BuildOptions::new(entrypoint)
+        };
+
+        (directory, options)
+    }
+
+    #[test]
+    fn generated_project_runs_the_program() {
+        let (directory, options) = build_options(
+            "project",
+            r#"
+(require "lib.scm")
+(define result (double (length std::env::args)))
+"#,
+        );
+
+        std::fs::write(
+            directory.join("lib.scm"),
+            "(provide double) (define (double x) (* x 2))",
+        )
+        .unwrap();
+
+        let project = build_executable(&options).unwrap();
+
+        let manifest = std::fs::read_to_string(project.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains(r#"name = "app""#));
+        assert!(manifest.contains(r#"features = ["dylibs", "markdown", "stacker"]"#));
+        assert!(manifest.contains(&format!(
+            "path = {:?}",
+            Path::new(BUILT_WITH_STEEL_CORE)
+                .canonicalize()
+                .unwrap()
+                .to_string_lossy()
+        )));
+        assert!(project.join("src/main.rs").exists());
+
+        // The required module is bundled, so the image runs without it on disk
+        std::fs::remove_file(directory.join("lib.scm")).unwrap();
+
+        let bytes = std::fs::read(project.join("src").join(PROGRAM_IMAGE)).unwrap();
+        let image = NonInteractiveProgramImage::from_bytes(&bytes).unwrap();
+
+        let mut engine = Engine::new();
+        engine.register_value(
+            "std::env::args",
+            SteelVal::ListV(vec![SteelVal::IntV(1), SteelVal::IntV(2)].into()),
+        );
+        engine.run_non_interactive_program_image(image).unwrap();
+
+        let result = engine.compile_and_run_raw_program("result").unwrap();
+        assert_eq!(result, vec![SteelVal::IntV(4)]);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    #[ignore = "builds steel-core in release mode, which takes several minutes"]
+    fn built_executable_runs_the_program() {
+        let (directory, options) = build_options(
+            "executable",
+            "(displayln (apply + (map string->number std::env::args)))",
+        );
+
+        let options = BuildOptions {
+            build: true,
+            ..options
+        };
+
+        build_executable(&options).unwrap();
+
+        let output = Command::new(directory.join("app"))
+            .args(["1", "2"])
+            .output()
+            .unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "3");
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn compile_errors_are_reported() {
+        let (directory, options) = build_options("error", "(define x 10)\n(if)");
+
+        let error = build_executable(&options).unwrap_err().to_string();
+        assert!(error.contains("app.scm"));
+        assert!(!directory.join("project").exists());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -2,13 +2,19 @@ extern crate steel;
 extern crate steel_derive;
 extern crate steel_repl;
 
+pub mod build;
+pub mod testing;
+
+use steel::compiler::image::disassemble as disassemble_image;
 use steel::steel_vm::engine::Engine;
 use steel_doc::walk_dir;
-use steel_repl::run_repl;
+use steel_repl::{run_repl, run_repl_server, ReplServerAddress};
 
+use build::{build_executable, BuildOptions};
 use std::path::PathBuf;
 use std::process;
 use std::{error::Error, fs};
+use testing::{run_tests, TestOptions, TestOutputFormat};
 
 use clap::Parser;
 
@@ -39,12 +45,60 @@ enum EmitAction {
         default_file: Option<PathBuf>,
         arguments: Vec<String>,
     },
-    /// Tests the module - only tests modules which provide values
-    Test { default_file: Option<String> },
+    /// Runs the tests in the given file, or in every file under the given directory
+    /// that requires `steel/testing` or the older `tests/unit-test.scm`
+    Test {
+        default_file: Option<PathBuf>,
+        /// Only run tests whose name contains this string. Can be given multiple times
+        #[arg(long)]
+        filter: Vec<String>,
+        /// How to report the test results
+        #[arg(long, value_enum, default_value_t = TestOutputFormat::Pretty)]
+        format: TestOutputFormat,
+        /// Number of test files to run at the same time. Defaults to the number of cpus
+        #[arg(long)]
+        jobs: Option<usize>,
+        /// Collect line coverage while running the tests, and print a summary per file
+        #[arg(long)]
+        coverage: bool,
+        /// Where to write the lcov coverage report
+        #[arg(long, default_value = "lcov.info", requires = "coverage")]
+        coverage_output: PathBuf,
+    },
     /// Generate the documentation for a file
     Doc { default_file: Option<PathBuf> },
-    /// Experimental
-    Compile { file: PathBuf },
+    /// Build a standalone executable from a program, bundling every module it requires
+    #[command(alias = "compile")]
+    Build {
+        file: PathBuf,
+        /// Where to write the executable. Defaults to the name of the file, in the current directory
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Directory to generate the cargo project in. Defaults to steel_target/<name>
+        #[arg(long)]
+        project_dir: Option<PathBuf>,
+        /// Build against this steel-core crate. Defaults to the STEEL_CORE_PATH environment
+        /// variable, and then to the steel-core this binary was built with
+        #[arg(long)]
+        steel_core_path: Option<PathBuf>,
+        /// Only generate the cargo project, without building it
+        #[arg(long)]
+        no_build: bool,
+    },
+    /// Print the header, sections and instructions of a compiled program image,
+    /// such as the program.bin generated by `steel build`
+    Disasm { file: PathBuf },
+    /// Start a repl server on loopback, for evaluating code from an editor
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 7888)]
+        port: u16,
+        /// Listen on a unix domain socket at this path instead of a tcp port
+        #[arg(long)]
+        socket: Option<PathBuf>,
+        /// File to load before accepting connections
+        default_file: Option<PathBuf>,
+    },
 }
 
 pub fn run(clap_args: Args) -> Result<(), Box<dyn Error>> {
@@ -91,23 +145,34 @@ pub fn run(clap_args: Args) -> Result<(), Box<dyn Error>> {
 
         Args {
             default_file: None,
-            action: Some(EmitAction::Test { default_file }),
+            action:
+                Some(EmitAction::Test {
+                    default_file,
+                    filter,
+                    format,
+                    jobs,
+                    coverage,
+                    coverage_output,
+                }),
             ..
         } => {
-            let file_or_current_dir: String = default_file.unwrap_or(".".to_string());
-            if let Some(path) = PathBuf::from(file_or_current_dir).to_str() {
-                let mut vm = Engine::new();
-                vm.register_value(
-                    "std::env::args",
-                    steel::SteelVal::ListV(vec![path.to_string().into()].into()),
-                );
-                let test_script = include_str!("../cogs/test-runner.scm");
-                if let Err(e) = vm.run(test_script) {
-                    e.emit_result(path, &test_script);
-                    return Err(Box::new(e));
-                }
+            let options = TestOptions {
+                path: default_file.unwrap_or_else(|| PathBuf::from(".")),
+                filters: filter,
+                format,
+                jobs: jobs.unwrap_or_else(|| {
+                    std::thread::available_parallelism()
+                        .map(|x| x.get())
+                        .unwrap_or(1)
+                }),
+                coverage: coverage.then_some(coverage_output),
+            };
+
+            if run_tests(&options, &mut std::io::stdout())? {
+                Ok(())
+            } else {
+                Err("there were test failures".into())
             }
-            Ok(())
         }
         Args {
             default_file: None,
@@ -195,80 +260,81 @@ pub fn run(clap_args: Args) -> Result<(), Box<dyn Error>> {
 
         Args {
             default_file: None,
-            action: Some(EmitAction::Compile { file }),
+            action: Some(EmitAction::Disasm { file }),
             ..
         } => {
-            println!("---- Warning: This is an experimental feature ----");
-
-            let entrypoint =
-                fs::read_to_string(&file).expect("Something went wrong reading the file");
+            let bytes =
+                fs::read(&file).map_err(|e| format!("unable to read {}: {e}", file.display()))?;
 
-            // Something went wrong - TODO: Raise the error correctly
-            let non_interactive_program =
-                Engine::create_non_interactive_program_image(entrypoint, file).unwrap();
+            print!("{}", disassemble_image(&bytes).map_err(|e| e.to_string())?);
 
-            let mut temporary_output = PathBuf::from("steel_target/src");
+            Ok(())
+        }
 
-            if !temporary_output.exists() {
-                std::fs::create_dir_all(&temporary_output).unwrap();
-            } else {
-                // Clean up I guess?
-                std::fs::remove_dir_all(&temporary_output).unwrap();
-                std::fs::create_dir_all(&temporary_output).unwrap();
+        Args {
+            default_file: None,
+            action:
+                Some(EmitAction::Build {
+                    file,
+                    output,
+                    project_dir,
+                    steel_core_path,
+                    no_build,
+                }),
+            ..
+        } => {
+            let options = BuildOptions {
+                output,
+                project_dir,
+                steel_core_path,
+                build: !no_build,
+                ..BuildOptions::new(file)
+            };
+
+            // Compile errors are rendered against the source, which the debug output of the error would mangle
+            let project = build_executable(&options).map_err(|e| {
+                eprintln!("{}", e.to_string().trim_end());
+                "unable to build the executable"
+            })?;
+
+            if no_build {
+                println!("generated project in {}", project.display());
             }
 
-            temporary_output.push("program.bin");
-
-            // This probably needs to get stashed in some temporary target directory?
-            non_interactive_program.write_bytes_to_file(&temporary_output);
-
-            temporary_output.pop();
-
-            let rust_entrypoint = r#"
-fn main() {
-    let program = steel::steel_vm::engine::NonInteractiveProgramImage::from_bytes(include_bytes!("program.bin"));
-
-    steel::steel_vm::engine::Engine::execute_non_interactive_program_image(program);
-}
-            "#;
-
-            temporary_output.push("main.rs");
-
-            std::fs::write(&temporary_output, rust_entrypoint).unwrap();
-
-            temporary_output.pop();
-            temporary_output.pop();
-
-            temporary_output.push("Cargo.toml");
-
-            let toml_file = r#"
-[package]
-name = "steel-executable"
-authors = [""]
-edition = "2021"
-license = "MIT OR Apache-2.0"
-version = "0.1.0"
-
-[workspace]
+            Ok(())
+        }
 
+        Args {
+            default_file: None,
+            action:
+                Some(EmitAction::Serve {
+                    port,
+                    socket,
+                    default_file,
+                }),
+            ..
+        } => {
+            if let Some(path) = default_file {
+                let contents = fs::read_to_string(&path)?;
+                let res = vm.compile_and_run_raw_program_with_path(contents.clone(), path.clone());
 
-[dependencies]
-steel-core = { git = "https://github.com/mattwparas/steel.git", features = ["web", "sqlite", "dylibs"] }
+                if let Err(e) = res {
+                    e.emit_result(path.to_str().unwrap(), &contents);
+                    return Err(Box::new(e));
+                }
+            }
 
-[profile.release]
-debug = false
-lto = true
-            "#;
-            std::fs::write(&temporary_output, toml_file).unwrap();
-            std::process::Command::new("cargo")
-                .current_dir("steel_target")
-                .arg("build")
-                .arg("--release")
-                .spawn()
-                .unwrap()
-                .wait()
-                .unwrap();
+            let address = match socket {
+                #[cfg(unix)]
+                Some(path) => ReplServerAddress::Unix(path),
+                #[cfg(not(unix))]
+                Some(_) => {
+                    return Err("unix domain sockets are not supported on this platform".into())
+                }
+                None => ReplServerAddress::Tcp(port),
+            };
 
+            run_repl_server(vm, address)?;
             Ok(())
         }
 
@@ -297,13 +363,18 @@ pub fn finish(result: Result<(), std::io::Error>) -> ! {
 
 #[test]
 fn test_runner() {
-    let args = Args {
-        action: None,
-        default_file: Some(PathBuf::from("cogs/test-runner.scm")),
-        arguments: vec!["cogs/".to_string()],
+    let options = TestOptions {
+        path: PathBuf::from("cogs/"),
+        filters: Vec::new(),
+        format: TestOutputFormat::Pretty,
+        jobs: 4,
+        coverage: None,
     };
 
-    run(args).unwrap()
+    let mut output = Vec::new();
+    let passed = run_tests(&options, &mut output).unwrap();
+
+    assert!(passed, "{}", String::from_utf8_lossy(&output));
 }
 
 #[test]
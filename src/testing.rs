@@ -0,0 +1,810 @@
+//! Test harness for `steel test`.
+//!
+//! Test files are discovered by parsing every `.scm` file under the given path and looking for a
+//! `(require "steel/testing")`, or a require of the older `tests/unit-test.scm` library. Each test
+//! file is then loaded into a fresh `Engine`, which registers the `test-case` and top level `check-*`
+//! forms it contains. The registered tests are then run one at a time, with anything they write to
+//! the current output port captured. Test files are spread across a pool of worker threads, each
+//! with their own engine.
+//!
+//! When filters are given, the names of the tests in a file are first collected from its expanded
+//! program, and files with no matching test are never loaded. Tests registered with names computed at
+//! run time can't be collected, so files with none that can are always loaded.
+//!
+//! The checks of `tests/unit-test.scm` run as the file is loaded, so under the harness each of them
+//! is registered as a test of its own that reports the result of the check.
+//!
+//! When coverage is requested, each engine records line coverage from the moment it starts loading
+//! its test file, and the results across all of the engines are merged into a single lcov file.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use steel::parser::ast::ExprKind;
+use steel::parser::parser::Parser;
+use steel::rerrs::SteelErr;
+use steel::rvals::{FromSteelVal, SteelVal};
+use steel::steel_vm::coverage::CoverageReport;
+use steel::steel_vm::engine::Engine;
+
+const TESTING_MODULE: &str = "steel/testing";
+// The test library used before `steel/testing`, required by path from the cogs
+const LEGACY_TESTING_MODULE: &str = "tests/unit-test.scm";
+
+// The functions the `steel/testing` macros expand to, after module name mangling
+const REGISTRATION_FUNCTIONS: [&str; 2] = [
+    "manglersteel/testing__%#__register-test!",
+    "manglersteel/testing__%#__check-or-register",
+];
+
+/// How the results of the test run should be reported
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TestOutputFormat {
+    /// Human readable output
+    #[default]
+    Pretty,
+    /// Test Anything Protocol, version 13
+    Tap,
+    /// JUnit XML
+    Junit,
+}
+
+#[derive(Debug, Clone)]
+pub struct TestOptions {
+    /// File or directory to search for tests
+    pub path: PathBuf,
+    /// Only tests whose name contains one of these strings are run. Runs everything if empty.
+    pub filters: Vec<String>,
+    pub format: TestOutputFormat,
+    /// Number of test files to run concurrently
+    pub jobs: usize,
+    /// Collect line coverage, writing it in the lcov format to this path
+    pub coverage: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestOutcome {
+    Passed,
+    /// The messages of the checks that failed
+    Failed(Vec<String>),
+    /// The test raised an error, rendered with its stack trace if available
+    Errored(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub name: String,
+    pub outcome: TestOutcome,
+    pub output: String,
+    pub duration: Duration,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.outcome == TestOutcome::Passed
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub results: Vec<TestResult>,
+    /// The file could not be loaded, so none of its tests ran
+    pub load_error: Option<String>,
+    pub filtered_out: usize,
+    pub coverage: Option<CoverageReport>,
+}
+
+impl FileReport {
+    fn failed(&self) -> bool {
+        self.load_error.is_some() || self.results.iter().any(|x| !x.passed())
+    }
+}
+
+/// Discovers and runs all of the tests under `options.path`, writing the report to `writer`.
+/// Returns whether every test passed.
+pub fn run_tests<W: Write>(options: &TestOptions, writer: &mut W) -> Result<bool, Box<dyn Error>> {
+    let now = Instant::now();
+
+    let mut files = Vec::new();
+    discover_test_files(&options.path, &mut files)?;
+    files.sort();
+
+    let mut reports = run_test_files(
+        files,
+        &options.filters,
+        options.jobs.max(1),
+        options.coverage.is_some(),
+    );
+
+    match options.format {
+        TestOutputFormat::Pretty => write_pretty(writer, &reports, now.elapsed())?,
+        TestOutputFormat::Tap => write_tap(writer, &reports)?,
+        TestOutputFormat::Junit => write_junit(writer, &reports)?,
+    }
+
+    if let Some(output) = &options.coverage {
+        let mut coverage = CoverageReport::default();
+
+        for report in reports.iter_mut() {
+            if let Some(report) = report.coverage.take() {
+                coverage.merge(report);
+            }
+        }
+
+        // Builtin modules are registered under paths that don't exist on disk
+        coverage.files.retain(|x| x.path.exists());
+
+        std::fs::write(output, coverage.to_lcov())?;
+
+        // The other formats are meant to be consumed by other tools, so leave them as is
+        if options.format == TestOutputFormat::Pretty {
+            writeln!(writer, "\ncoverage written to {}\n", output.display())?;
+            write!(writer, "{}", coverage.summary())?;
+        }
+    }
+
+    writer.flush()?;
+
+    Ok(!reports.iter().any(|x| x.failed()))
+}
+
+fn discover_test_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    if path.is_dir() {
+        for entry in path.read_dir()? {
+            discover_test_files(&entry?.path(), files)?;
+        }
+    } else if path.extension().and_then(|x| x.to_str()) == Some("scm") {
+        let contents = std::fs::read_to_string(path)?;
+
+        // Files that don't parse can't be test files, and will be reported
+        // by whatever does end up requiring them
+        if let Ok(exprs) = Parser::parse(&contents) {
+            if exprs.iter().any(requires_testing_module) {
+                files.push(path.to_path_buf());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn requires_testing_module(expr: &ExprKind) -> bool {
+    let is_testing_module = |x: &ExprKind| {
+        x.string_literal()
+            .is_some_and(|x| x == TESTING_MODULE || x.ends_with(LEGACY_TESTING_MODULE))
+    };
+
+    match expr {
+        ExprKind::Require(r) => r.modules.iter().any(is_testing_module),
+        ExprKind::Begin(b) => b.exprs.iter().any(requires_testing_module),
+        ExprKind::List(l) if l.first_ident().map(|x| x.resolve()) == Some("require") => {
+            l.args.iter().any(is_testing_module)
+        }
+        _ => false,
+    }
+}
+
+// Collects the names of the tests registered at the top level of the expanded program
+fn collect_test_names(expr: &ExprKind, names: &mut Vec<String>) {
+    match expr {
+        ExprKind::Begin(b) => {
+            for expr in &b.exprs {
+                collect_test_names(expr, names);
+            }
+        }
+        ExprKind::List(l)
+            if l.first_ident()
+                .is_some_and(|x| REGISTRATION_FUNCTIONS.contains(&x.resolve())) =>
+        {
+            if let Some(name) = l.args.get(1).and_then(|x| x.string_literal()) {
+                names.push(name.to_string());
+            }
+        }
+        _ => {}
+    }
+}
+
+fn run_test_files(
+    files: Vec<PathBuf>,
+    filters: &[String],
+    jobs: usize,
+    coverage: bool,
+) -> Vec<FileReport> {
+    let count = files.len();
+    let queue = Arc::new(Mutex::new(
+        files.into_iter().enumerate().collect::<VecDeque<_>>(),
+    ));
+    let reports = Arc::new(Mutex::new(Vec::with_capacity(count)));
+
+    let workers = (0..jobs.min(count))
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let reports = Arc::clone(&reports);
+            let filters = filters.to_vec();
+
+            // Match the stack the main thread gets, since deep recursion in tests is common
+            std::thread::Builder::new()
+                .stack_size(8 * 1024 * 1024)
+                .spawn(move || loop {
+                    let Some((index, path)) = queue.lock().unwrap().pop_front() else {
+                        return;
+                    };
+
+                    let report = run_test_file(path, &filters, coverage);
+                    reports.lock().unwrap().push((index, report));
+                })
+                .expect("Unable to spawn test worker thread")
+        })
+        .collect::<Vec<_>>();
+
+    for worker in workers {
+        // The engine shouldn't panic, but if it does we still want to report on the other files
+        let _ = worker.join();
+    }
+
+    let mut reports = std::mem::take(&mut *reports.lock().unwrap());
+    reports.sort_by_key(|(index, _)| *index);
+    reports.into_iter().map(|(_, report)| report).collect()
+}
+
+fn run_test_file(path: PathBuf, filters: &[String], coverage: bool) -> FileReport {
+    let mut report = FileReport {
+        path: path.clone(),
+        results: Vec::new(),
+        load_error: None,
+        filtered_out: 0,
+        coverage: None,
+    };
+
+    let mut vm = Engine::new();
+    vm.register_value("std::env::args", SteelVal::ListV(vec![].into()));
+
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            report.load_error = Some(e.to_string());
+            return report;
+        }
+    };
+
+    if !filters.is_empty() {
+        let mut collected = Vec::new();
+
+        // Expanding compiles the required modules without running them, so this can't be done with
+        // the engine that loads the file. Any errors here will be reported when the file is loaded.
+        if let Ok(exprs) = Engine::new().emit_expanded_ast(&contents, Some(path.clone())) {
+            for expr in &exprs {
+                collect_test_names(expr, &mut collected);
+            }
+        }
+
+        if !collected.is_empty() && !collected.iter().any(|name| matches_filters(name, filters)) {
+            report.filtered_out = collected.len();
+            return report;
+        }
+    }
+
+    let names = vm
+        .compile_and_run_raw_program("(set-test-mode!)")
+        .and_then(|_| vm.compile_and_run_raw_program("(require \"steel/testing\")"))
+        .and_then(|_| vm.compile_and_run_raw_program("(start-test-harness!)"))
+        .and_then(|_| {
+            vm.with_coverage(coverage);
+            vm.compile_and_run_raw_program_with_path(contents, path.clone())
+        })
+        .and_then(|_| vm.compile_and_run_raw_program("(registered-test-names)"))
+        .and_then(|mut x| Vec::<String>::from_steelval(&x.pop().unwrap_or(SteelVal::Void)))
+        .map_err(|e| render_error(&vm, e));
+
+    let names = match names {
+        Ok(names) => names,
+        Err(e) => {
+            report.load_error = Some(e);
+            report.coverage = vm.coverage_report();
+            return report;
+        }
+    };
+
+    for (index, name) in names.into_iter().enumerate() {
+        if !matches_filters(&name, filters) {
+            report.filtered_out += 1;
+            continue;
+        }
+
+        let now = Instant::now();
+        let result = vm.compile_and_run_raw_program(format!("(run-registered-test {index})"));
+        let duration = now.elapsed();
+
+        let (outcome, output) = match result.and_then(|mut x| decode_result(x.pop())) {
+            Ok((failures, None, output)) if failures.is_empty() => (TestOutcome::Passed, output),
+            Ok((failures, None, output)) => (TestOutcome::Failed(failures), output),
+            Ok((_, Some(error), output)) => {
+                (TestOutcome::Errored(render_error(&vm, error)), output)
+            }
+            Err(e) => (TestOutcome::Errored(render_error(&vm, e)), String::new()),
+        };
+
+        report.results.push(TestResult {
+            name,
+            outcome,
+            output,
+            duration,
+        });
+    }
+
+    report.coverage = vm.coverage_report();
+    report
+}
+
+fn matches_filters(name: &str, filters: &[String]) -> bool {
+    filters.is_empty() || filters.iter().any(|x| name.contains(x.as_str()))
+}
+
+// The result of `run-registered-test` is a list of the failed checks, the raised error or #f, and
+// the captured output
+fn decode_result(
+    value: Option<SteelVal>,
+) -> steel::rvals::Result<(Vec<String>, Option<SteelErr>, String)> {
+    let value = value.unwrap_or(SteelVal::Void);
+
+    if let SteelVal::ListV(list) = &value {
+        if let [failures, raised, output] = list.iter().cloned().collect::<Vec<_>>().as_slice() {
+            let raised = match raised {
+                SteelVal::BoolV(false) => None,
+                other => Some(SteelErr::from_steelval(other)?),
+            };
+
+            return Ok((
+                Vec::<String>::from_steelval(failures)?,
+                raised,
+                String::from_steelval(output)?,
+            ));
+        }
+    }
+
+    steel::stop!(TypeMismatch => "unexpected result from the test runner: {}", value)
+}
+
+fn render_error(vm: &Engine, error: SteelErr) -> String {
+    vm.raise_error_to_string(error.clone())
+        .unwrap_or_else(|| error.to_string())
+}
+
+fn write_pretty<W: Write>(
+    writer: &mut W,
+    reports: &[FileReport],
+    elapsed: Duration,
+) -> std::io::Result<()> {
+    let total = reports.iter().map(|x| x.results.len()).sum::<usize>();
+
+    writeln!(
+        writer,
+        "running {} tests across {} files",
+        total,
+        reports.len()
+    )?;
+
+    for report in reports {
+        let path = report.path.display();
+
+        if report.load_error.is_some() {
+            writeln!(writer, "file {path} ... FAILED TO LOAD")?;
+        }
+
+        for result in &report.results {
+            let status = if result.passed() { "ok" } else { "FAILED" };
+            writeln!(writer, "test {path} :: {} ... {status}", result.name)?;
+        }
+    }
+
+    let failures = reports
+        .iter()
+        .flat_map(|report| {
+            let load_failure = report
+                .load_error
+                .as_ref()
+                .map(|e| (report.path.display().to_string(), e.clone()));
+
+            let test_failures = report.results.iter().filter_map(move |result| {
+                let message = match &result.outcome {
+                    TestOutcome::Passed => return None,
+                    TestOutcome::Failed(checks) => checks.join("\n"),
+                    TestOutcome::Errored(e) => e.clone(),
+                };
+
+                Some((
+                    format!("{} :: {}", report.path.display(), result.name),
+                    if result.output.is_empty() {
+                        message
+                    } else {
+                        format!("{message}\n\n---- captured output ----\n{}", result.output)
+                    },
+                ))
+            });
+
+            load_failure.into_iter().chain(test_failures)
+        })
+        .collect::<Vec<_>>();
+
+    if !failures.is_empty() {
+        writeln!(writer, "\nfailures:\n")?;
+
+        for (name, message) in &failures {
+            writeln!(writer, "---- {name} ----")?;
+            writeln!(writer, "{}\n", message.trim_end())?;
+        }
+    }
+
+    let passed = reports
+        .iter()
+        .flat_map(|x| x.results.iter())
+        .filter(|x| x.passed())
+        .count();
+    let failed = total - passed;
+    let load_errors = reports.iter().filter(|x| x.load_error.is_some()).count();
+    let filtered_out = reports.iter().map(|x| x.filtered_out).sum::<usize>();
+
+    writeln!(
+        writer,
+        "\ntest result: {}. {} passed; {} failed; {} files failed to load; {} filtered out; finished in {:.2}s",
+        if failures.is_empty() { "ok" } else { "FAILED" },
+        passed,
+        failed,
+        load_errors,
+        filtered_out,
+        elapsed.as_secs_f64()
+    )
+}
+
+fn write_tap<W: Write>(writer: &mut W, reports: &[FileReport]) -> std::io::Result<()> {
+    let total = reports
+        .iter()
+        .map(|x| x.results.len() + usize::from(x.load_error.is_some()))
+        .sum::<usize>();
+
+    writeln!(writer, "TAP version 13")?;
+    writeln!(writer, "1..{total}")?;
+
+    let mut number = 0;
+
+    for report in reports {
+        let path = report.path.display();
+
+        if let Some(error) = &report.load_error {
+            number += 1;
+            writeln!(writer, "not ok {number} - {path}")?;
+            write_tap_diagnostic(writer, "error", error)?;
+        }
+
+        for result in &report.results {
+            number += 1;
+
+            match &result.outcome {
+                TestOutcome::Passed => {
+                    writeln!(writer, "ok {number} - {path} :: {}", result.name)?;
+                }
+                TestOutcome::Failed(checks) => {
+                    writeln!(writer, "not ok {number} - {path} :: {}", result.name)?;
+                    write_tap_diagnostic(writer, "failures", &checks.join("\n"))?;
+                }
+                TestOutcome::Errored(error) => {
+                    writeln!(writer, "not ok {number} - {path} :: {}", result.name)?;
+                    write_tap_diagnostic(writer, "error", error)?;
+                }
+            }
+
+            for line in result.output.lines() {
+                writeln!(writer, "# {line}")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_tap_diagnostic<W: Write>(writer: &mut W, key: &str, message: &str) -> std::io::Result<()> {
+    writeln!(writer, "  ---")?;
+    writeln!(writer, "  {key}: |")?;
+    for line in message.lines() {
+        writeln!(writer, "    {line}")?;
+    }
+    writeln!(writer, "  ...")
+}
+
+fn write_junit<W: Write>(writer: &mut W, reports: &[FileReport]) -> std::io::Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, "<testsuites>")?;
+
+    for report in reports {
+        let suite = xml_escape(&report.path.display().to_string());
+        let failures = report
+            .results
+            .iter()
+            .filter(|x| matches!(x.outcome, TestOutcome::Failed(_)))
+            .count();
+        let errors = report
+            .results
+            .iter()
+            .filter(|x| matches!(x.outcome, TestOutcome::Errored(_)))
+            .count()
+            + usize::from(report.load_error.is_some());
+        let time = report
+            .results
+            .iter()
+            .map(|x| x.duration.as_secs_f64())
+            .sum::<f64>();
+
+        writeln!(
+            writer,
+            r#"  <testsuite name="{suite}" tests="{}" failures="{failures}" errors="{errors}" time="{time:.3}">"#,
+            report.results.len(),
+        )?;
+
+        if let Some(error) = &report.load_error {
+            writeln!(
+                writer,
+                r#"    <error message="failed to load test file">{}</error>"#,
+                xml_escape(error)
+            )?;
+        }
+
+        for result in &report.results {
+            writeln!(
+                writer,
+                r#"    <testcase name="{}" classname="{suite}" time="{:.3}">"#,
+                xml_escape(&result.name),
+                result.duration.as_secs_f64()
+            )?;
+
+            match &result.outcome {
+                TestOutcome::Passed => {}
+                TestOutcome::Failed(checks) => writeln!(
+                    writer,
+                    r#"      <failure message="{} checks failed">{}</failure>"#,
+                    checks.len(),
+                    xml_escape(&checks.join("\n"))
+                )?,
+                TestOutcome::Errored(error) => writeln!(
+                    writer,
+                    r#"      <error message="error raised">{}</error>"#,
+                    xml_escape(error)
+                )?,
+            }
+
+            if !result.output.is_empty() {
+                writeln!(
+                    writer,
+                    "      <system-out>{}</system-out>",
+                    xml_escape(&result.output)
+                )?;
+            }
+
+            writeln!(writer, "    </testcase>")?;
+        }
+
+        writeln!(writer, "  </testsuite>")?;
+    }
+
+    writeln!(writer, "</testsuites>")
+}
+
+fn xml_escape(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+
+    for c in input.chars() {
+        match c {
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '&' => output.push_str("&amp;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&apos;"),
+            c => output.push(c),
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_test_file(name: &str, contents: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("steel-test-harness-{name}"));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("tests.scm");
+        std::fs::write(&path, contents).unwrap();
+        directory
+    }
+
+    #[test]
+    fn reports_passing_failing_and_filtered_tests() {
+        let directory = write_test_file(
+            "mixed",
+            r#"
+(require "steel/testing")
+
+(test-case "passes" (check-equal? "addition" (+ 1 2) 3))
+(test-case "fails"
+  (displayln "some output")
+  (check-equal? "subtraction" (- 1 2) 0))
+(test-case "raises" (car 10))
+(check-true "top level check" #t)
+"#,
+        );
+
+        let options = TestOptions {
+            path: directory.clone(),
+            filters: Vec::new(),
+            format: TestOutputFormat::Tap,
+            jobs: 2,
+            coverage: None,
+        };
+
+        let mut output = Vec::new();
+        assert!(!run_tests(&options, &mut output).unwrap());
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("1..4"));
+        assert!(output.contains("ok 1 - "));
+        assert!(output.contains("not ok 2 - "));
+        assert!(output.contains("subtraction: expected: 0 actual: -1"));
+        assert!(output.contains("# some output"));
+        assert!(output.contains("not ok 3 - "));
+        assert!(output.contains("ok 4 - "));
+
+        let options = TestOptions {
+            filters: vec!["passes".to_string()],
+            format: TestOutputFormat::Junit,
+            ..options
+        };
+
+        let mut output = Vec::new();
+        assert!(run_tests(&options, &mut output).unwrap());
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(r#"<testcase name="passes""#));
+        assert!(!output.contains(r#"<testcase name="fails""#));
+
+        // Raised errors are reported apart from failed checks
+        let options = TestOptions {
+            filters: vec!["fails".to_string(), "raises".to_string()],
+            ..options
+        };
+
+        let mut output = Vec::new();
+        assert!(!run_tests(&options, &mut output).unwrap());
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(r#"failures="1" errors="1""#));
+        assert!(output.contains(r#"<failure message="1 checks failed">"#));
+        assert!(output.contains(r#"<error message="error raised">"#));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn coverage_is_written_as_lcov() {
+        let directory = write_test_file(
+            "coverage",
+            r#"
+(require "steel/testing")
+
+(define (double x)
+  (* x 2))
+
+(test-case "doubles" (check-equal? "double" (double 2) 4))
+"#,
+        );
+
+        let lcov = directory.join("lcov.info");
+
+        let options = TestOptions {
+            path: directory.join("tests.scm"),
+            filters: Vec::new(),
+            format: TestOutputFormat::Pretty,
+            jobs: 1,
+            coverage: Some(lcov.clone()),
+        };
+
+        let mut output = Vec::new();
+        assert!(run_tests(&options, &mut output).unwrap());
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("tests.scm"));
+        assert!(output.contains("Total"));
+
+        let lcov = std::fs::read_to_string(lcov).unwrap();
+        assert!(lcov.contains("tests.scm\n"));
+        assert!(lcov.contains("end_of_record"));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn load_errors_are_not_counted_as_failed_tests() {
+        let directory = write_test_file(
+            "load-error",
+            r#"
+(require "steel/testing")
+
+(test-case "never runs" (check-true "true" #t))
+(car 10)
+"#,
+        );
+
+        let options = TestOptions {
+            path: directory.clone(),
+            filters: Vec::new(),
+            format: TestOutputFormat::Pretty,
+            jobs: 1,
+            coverage: None,
+        };
+
+        let mut output = Vec::new();
+        assert!(!run_tests(&options, &mut output).unwrap());
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("FAILED TO LOAD"));
+        assert!(output.contains("0 passed; 0 failed; 1 files failed to load;"));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn files_with_no_matching_tests_are_not_loaded() {
+        let directory = write_test_file(
+            "not-loaded",
+            r#"
+(require "steel/testing")
+
+(test-case "addition" (check-equal? "addition" (+ 1 2) 3))
+(check-true "top level check" #t)
+(car 10)
+"#,
+        );
+
+        let options = TestOptions {
+            path: directory.clone(),
+            filters: vec!["subtraction".to_string()],
+            format: TestOutputFormat::Pretty,
+            jobs: 1,
+            coverage: None,
+        };
+
+        let mut output = Vec::new();
+        assert!(run_tests(&options, &mut output).unwrap());
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("0 passed; 0 failed; 0 files failed to load; 2 filtered out;"));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn files_using_the_old_test_library_are_discovered() {
+        let directory = write_test_file("legacy", r#"(require "steel/tests/unit-test.scm")"#);
+
+        let mut files = Vec::new();
+        discover_test_files(&directory, &mut files).unwrap();
+        assert_eq!(files, vec![directory.join("tests.scm")]);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn files_without_the_testing_module_are_skipped() {
+        let directory = write_test_file("skipped", "(define x 10)");
+
+        let mut files = Vec::new();
+        discover_test_files(&directory, &mut files).unwrap();
+        assert!(files.is_empty());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
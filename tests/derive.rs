@@ -0,0 +1,138 @@
+use steel::rerrs::ErrorKind;
+use steel::steel_vm::builtin::BuiltInModule;
+use steel::steel_vm::engine::Engine;
+use steel::SteelVal;
+
+use steel_derive::Steel;
+
+/// A counter
+#[derive(Clone, Debug, Steel, PartialEq)]
+#[steel(register, name = "counter")]
+struct Counter {
+    /// The current count
+    count_value: isize,
+    #[steel(read_only)]
+    step: isize,
+    #[steel(skip)]
+    history: Vec<isize>,
+}
+
+#[derive(Clone, Debug, Steel, PartialEq)]
+#[steel(register)]
+enum Token {
+    Eof,
+    #[steel(name = "num")]
+    Number(isize),
+    Word {
+        text: String,
+    },
+}
+
+fn engine() -> Engine {
+    let mut module = BuiltInModule::new("derive-test");
+    Counter::register_into(&mut module);
+    Token::register_into(&mut module);
+
+    let mut vm = Engine::new();
+    vm.register_module(module);
+    vm.compile_and_run_raw_program("(require-builtin derive-test)")
+        .unwrap();
+    vm
+}
+
+fn run(vm: &mut Engine, program: &str) -> SteelVal {
+    vm.compile_and_run_raw_program(program.to_string())
+        .unwrap()
+        .pop()
+        .unwrap()
+}
+
+#[test]
+fn struct_getters_and_setters() {
+    let mut vm = engine();
+
+    run(&mut vm, "(define c (counter 1 2))");
+    assert_eq!(run(&mut vm, "(counter? c)"), SteelVal::BoolV(true));
+    assert_eq!(run(&mut vm, "(counter? 10)"), SteelVal::BoolV(false));
+    assert_eq!(run(&mut vm, "(counter-count-value c)"), SteelVal::IntV(1));
+
+    run(&mut vm, "(set-counter-count-value! c 5)");
+    assert_eq!(run(&mut vm, "(counter-step c)"), SteelVal::IntV(2));
+
+    let counter: Counter = vm.extract("c").unwrap();
+    assert_eq!(
+        Counter {
+            count_value: 5,
+            step: 2,
+            history: Vec::new()
+        },
+        counter
+    );
+}
+
+#[test]
+fn struct_errors() {
+    let mut vm = engine();
+
+    // Read only and skipped fields don't get functions
+    assert!(vm
+        .compile_and_run_raw_program("(set-counter-step! (counter 1 2) 3)")
+        .is_err());
+    assert!(vm
+        .compile_and_run_raw_program("(counter-history (counter 1 2))")
+        .is_err());
+
+    let error = vm.compile_and_run_raw_program("(counter 1)").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::ArityMismatch);
+
+    let error = vm
+        .compile_and_run_raw_program("(counter-count-value 10)")
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    assert!(error.to_string().contains("counter-count-value"));
+
+    let error = vm
+        .compile_and_run_raw_program("(counter \"one\" 2)")
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+}
+
+#[test]
+fn enum_getters() {
+    let mut vm = engine();
+
+    assert_eq!(
+        run(&mut vm, "(Token-num-0 (Token-num 10))"),
+        SteelVal::IntV(10)
+    );
+    assert_eq!(
+        run(&mut vm, "(Token-Word-text (Token-Word \"hello\"))"),
+        SteelVal::StringV("hello".into())
+    );
+    assert_eq!(
+        run(&mut vm, "(Token-num? (Token-num 1))"),
+        SteelVal::BoolV(true)
+    );
+    assert_eq!(
+        run(&mut vm, "(Token-num? (Token-Eof))"),
+        SteelVal::BoolV(false)
+    );
+    assert_eq!(run(&mut vm, "(Token? (Token-Eof))"), SteelVal::BoolV(true));
+}
+
+#[test]
+fn enum_getter_on_wrong_variant_uses_the_steel_name() {
+    let mut vm = engine();
+
+    let error = vm
+        .compile_and_run_raw_program("(Token-num-0 (Token-Eof))")
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+
+    let message = error.to_string();
+    assert!(
+        message.contains("expected a Token of the num variant"),
+        "{message}"
+    );
+    assert!(!message.contains("Number"), "{message}");
+}